@@ -0,0 +1,176 @@
+//! Criterion benchmarks for the solver hot paths.
+//!
+//! Run with `cargo bench --no-default-features --features bench`; add the
+//! `parallel` feature to also measure thread scaling (1 to 8 threads).
+//! The `simd` group pits the dispatching kernels against `simd::scalar`;
+//! natively both sides are scalar, so it only shows a gap on simd128 builds.
+//! Inputs come from the seeded scenarios shared with the golden tests.
+//! With `eval-table` the evaluator group also times the two-plus-two table.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use poker_solver_core::{compute_equity_matrix, evaluate_7_cards, init_lookup_tables};
+use poker_solver_core::solver::{build_river_tree, DCFRTrainer};
+
+#[path = "../tests/fixtures/scenarios.rs"]
+mod scenarios;
+
+fn bench_evaluate_7_cards(c: &mut Criterion) {
+    init_lookup_tables();
+    let hands = scenarios::random_seven_card_hands(1_000_000);
+
+    let mut group = c.benchmark_group("evaluator");
+    group.sample_size(10);
+    group.bench_function("evaluate_7_cards x1M", |b| {
+        b.iter(|| {
+            let mut acc = 0u32;
+            for hand in &hands {
+                acc += evaluate_7_cards(black_box(hand)) as u32;
+            }
+            acc
+        })
+    });
+
+    // Same hands through the two-plus-two table; every later bench uses it too
+    #[cfg(feature = "eval-table")]
+    {
+        use poker_solver_core::poker::eval_table::{generate_eval_table, load_eval_table, table_bytes};
+        load_eval_table(&table_bytes(&generate_eval_table())).expect("generated table loads");
+        group.bench_function("evaluate_7_cards x1M (eval table)", |b| {
+            b.iter(|| {
+                let mut acc = 0u32;
+                for hand in &hands {
+                    acc += evaluate_7_cards(black_box(hand)) as u32;
+                }
+                acc
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_equity_matrix(c: &mut Criterion) {
+    init_lookup_tables();
+    let board = scenarios::board();
+    let range0 = scenarios::random_range(300, 1);
+    let range1 = scenarios::random_range(300, 2);
+
+    let mut group = c.benchmark_group("equity");
+    group.sample_size(10);
+    group.bench_function("compute_equity_matrix 300x300", |b| {
+        b.iter(|| compute_equity_matrix(black_box(&board), &range0, &range1))
+    });
+    group.finish();
+}
+
+fn bench_tree_build(c: &mut Criterion) {
+    let config = scenarios::three_size_config();
+    c.bench_function("build_river_tree 3 sizes / 3 raises", |b| {
+        b.iter(|| build_river_tree(black_box(&config)).unwrap())
+    });
+}
+
+fn bench_cfr(c: &mut Criterion) {
+    init_lookup_tables();
+    let config = scenarios::mid_size_config();
+    let (range0, range1) = scenarios::mid_size_ranges();
+    let matrix = compute_equity_matrix(&scenarios::board(), &range0, &range1);
+    let tree = build_river_tree(&config).unwrap();
+    let max_actions = tree.nodes.iter().map(|n| n.num_actions as usize).max().unwrap_or(0);
+    let num_hands = [range0.len(), range1.len()];
+    let reach = [vec![1.0; num_hands[0]], vec![1.0; num_hands[1]]];
+
+    let mut group = c.benchmark_group("cfr");
+    group.sample_size(10);
+    group.bench_function("DCFRTrainer::train 100 iterations", |b| {
+        b.iter(|| {
+            let mut trainer = DCFRTrainer::new(tree.infoset_map.len(), max_actions, num_hands);
+            trainer.train(&tree, &matrix, 100, &reach);
+            trainer
+        })
+    });
+    group.finish();
+}
+
+fn bench_simd_kernels(c: &mut Criterion) {
+    use poker_solver_core::simd::{self, scalar};
+
+    init_lookup_tables();
+    let board = scenarios::board();
+    let range0 = scenarios::random_range(300, 1);
+    let range1 = scenarios::random_range(1000, 2);
+    let matrix = compute_equity_matrix(&board, &range0, &range1);
+    let row = &matrix[..range1.len()];
+    let reach: Vec<f32> = (0..range1.len()).map(|i| (i % 7) as f32 / 7.0).collect();
+    let scores: Vec<u16> = (0..range1.len()).map(|i| (i * 7 % 7462 + 1) as u16).collect();
+    let masks: Vec<u64> = range1.iter().map(|h| h.iter().fold(0, |m, c| m | c.bitmask())).collect();
+    let mut out = vec![0.0; range1.len()];
+    let (mut acc_eq, mut acc_total) = (vec![0.0; range1.len()], vec![0.0; range1.len()]);
+
+    let mut group = c.benchmark_group("simd");
+    group.bench_function("equity_row x1000 / dispatch", |b| {
+        b.iter(|| simd::equity_row(black_box(3000), 0b11, &scores, &masks, &mut out))
+    });
+    group.bench_function("equity_row x1000 / scalar", |b| {
+        b.iter(|| scalar::equity_row(black_box(3000), 0b11, &scores, &masks, &mut out))
+    });
+    group.bench_function("masked_sums x1000 / dispatch", |b| b.iter(|| simd::masked_sums(black_box(row), &reach)));
+    group.bench_function("masked_sums x1000 / scalar", |b| b.iter(|| scalar::masked_sums(black_box(row), &reach)));
+    group.bench_function("accumulate_opponent x1000 / dispatch", |b| {
+        b.iter(|| simd::accumulate_opponent(black_box(row), 0.5, &mut acc_eq, &mut acc_total))
+    });
+    group.bench_function("accumulate_opponent x1000 / scalar", |b| {
+        b.iter(|| scalar::accumulate_opponent(black_box(row), 0.5, &mut acc_eq, &mut acc_total))
+    });
+    group.bench_function("mul_add x1000 / dispatch", |b| b.iter(|| simd::mul_add(&mut acc_eq, black_box(&reach), &acc_total)));
+    group.bench_function("mul_add x1000 / scalar", |b| b.iter(|| scalar::mul_add(&mut acc_eq, black_box(&reach), &acc_total)));
+    group.bench_function("discount x1000 / dispatch", |b| b.iter(|| simd::discount(&mut out, black_box(0.9), 0.5)));
+    group.bench_function("discount x1000 / scalar", |b| b.iter(|| scalar::discount(&mut out, black_box(0.9), 0.5)));
+    group.finish();
+}
+
+#[cfg(feature = "parallel")]
+fn bench_thread_scaling(c: &mut Criterion) {
+    use poker_solver_core::solver::exploitability;
+
+    init_lookup_tables();
+    let board = scenarios::board();
+    let range0 = scenarios::random_range(300, 1);
+    let range1 = scenarios::random_range(300, 2);
+
+    let config = scenarios::mid_size_config();
+    let (cfr0, cfr1) = scenarios::mid_size_ranges();
+    let matrix = compute_equity_matrix(&board, &cfr0, &cfr1);
+    let tree = build_river_tree(&config).unwrap();
+    let max_actions = tree.nodes.iter().map(|n| n.num_actions as usize).max().unwrap_or(0);
+    let num_hands = [cfr0.len(), cfr1.len()];
+    let reach = [vec![1.0; num_hands[0]], vec![1.0; num_hands[1]]];
+    let mut trainer = DCFRTrainer::new(tree.infoset_map.len(), max_actions, num_hands);
+    trainer.train(&tree, &matrix, 20, &reach);
+
+    let mut group = c.benchmark_group("thread_scaling");
+    group.sample_size(10);
+    for threads in [1, 2, 4, 8] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        group.bench_function(format!("equity 300x300 / {} threads", threads), |b| {
+            b.iter(|| pool.install(|| compute_equity_matrix(&board, &range0, &range1)))
+        });
+        group.bench_function(format!("exploitability / {} threads", threads), |b| {
+            b.iter(|| pool.install(|| exploitability(&tree, &trainer, &matrix, &reach)))
+        });
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "parallel"))]
+fn bench_thread_scaling(_c: &mut Criterion) {}
+
+criterion_group!(
+    benches,
+    bench_evaluate_7_cards,
+    bench_equity_matrix,
+    bench_tree_build,
+    bench_cfr,
+    bench_simd_kernels,
+    bench_thread_scaling
+);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Build script: decides whether the rayon code paths are compiled in.
+//!
+//! Emits `cfg(rayon_pool)` for native builds with the `parallel` feature and
+//! for wasm32 builds with the `wasm-threads` feature. Everything else (the
+//! default WASM build in particular) stays sequential.
+//!
+//! Threaded WASM needs shared memory, which stable Rust's prebuilt std does
+//! not provide. Build with nightly, rebuilding std with atomics enabled:
+//!
+//! ```sh
+//! RUSTFLAGS='-C target-feature=+atomics,+bulk-memory,+mutable-globals' \
+//!   rustup run nightly wasm-pack build --target web --release -- \
+//!   --features wasm-threads -Z build-std=panic_abort,std
+//! ```
+//!
+//! (`npm run wasm:build:threads` does the above.) The page must be served
+//! cross-origin isolated (`Cross-Origin-Opener-Policy: same-origin`,
+//! `Cross-Origin-Embedder-Policy: require-corp`) and call
+//! `await init_thread_pool(navigator.hardwareConcurrency)` once after `init()`.
+
+use std::env;
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(rayon_pool)");
+    println!("cargo::rerun-if-changed=build.rs");
+
+    let wasm = env::var("CARGO_CFG_TARGET_ARCH").is_ok_and(|arch| arch == "wasm32");
+    let parallel = env::var_os("CARGO_FEATURE_PARALLEL").is_some();
+    let wasm_threads = env::var_os("CARGO_FEATURE_WASM_THREADS").is_some();
+
+    if wasm && wasm_threads {
+        let features = env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default();
+        if !features.split(',').any(|f| f == "atomics") {
+            panic!(
+                "the `wasm-threads` feature needs a nightly build with \
+                 RUSTFLAGS='-C target-feature=+atomics,+bulk-memory,+mutable-globals' \
+                 and -Z build-std=panic_abort,std (see build.rs)"
+            );
+        }
+    }
+
+    if (parallel && !wasm) || (wasm_threads && wasm) {
+        println!("cargo::rustc-cfg=rayon_pool");
+    }
+}
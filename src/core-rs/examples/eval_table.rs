@@ -0,0 +1,18 @@
+//! Writes a two-plus-two style evaluation table for `load_eval_table`.
+//!
+//! ```text
+//! cargo run --release --features eval-table --example eval_table -- HandRanks.dat
+//! ```
+
+use std::time::Instant;
+
+use poker_solver_core::poker::eval_table::{generate_eval_table, table_bytes};
+
+fn main() -> Result<(), String> {
+    let output = std::env::args().nth(1).ok_or("Usage: eval_table OUTPUT")?;
+    let start = Instant::now();
+    let table = generate_eval_table();
+    std::fs::write(&output, table_bytes(&table)).map_err(|e| format!("Failed to write '{}': {}", output, e))?;
+    eprintln!("Wrote {} ({} entries, {:.1}s)", output, table.len(), start.elapsed().as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,27 @@
+//! Regenerates `src/poker/preflop_equity.bin`, the shipped class-vs-class
+//! preflop equity table.
+//!
+//! ```text
+//! cargo run --release --features parallel --example preflop_table -- [SAMPLES] [OUTPUT]
+//! ```
+//! `SAMPLES` is the number of boards dealt per pair of classes (default
+//! 50000, what the shipped table uses); `OUTPUT` defaults to the shipped file.
+
+use std::time::Instant;
+
+use poker_solver_core::poker::preflop::{encode_table, generate_preflop_table};
+
+fn main() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let samples = match args.next() {
+        Some(s) => s.parse::<usize>().map_err(|_| format!("SAMPLES must be a positive integer, got '{}'", s))?,
+        None => 50_000,
+    };
+    let output = args.next().unwrap_or_else(|| concat!(env!("CARGO_MANIFEST_DIR"), "/src/poker/preflop_equity.bin").to_string());
+
+    let start = Instant::now();
+    let table = generate_preflop_table(samples, 0x5eed);
+    std::fs::write(&output, encode_table(&table)).map_err(|e| format!("Failed to write '{}': {}", output, e))?;
+    eprintln!("Wrote {} ({} samples per matchup, {:.1}s)", output, samples, start.elapsed().as_secs_f64());
+    Ok(())
+}
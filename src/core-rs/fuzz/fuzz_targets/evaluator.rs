@@ -0,0 +1,33 @@
+//! Two distinct 7-card hands (picked from the fuzz bytes) through
+//! `evaluate_7_cards`: scores stay in 1..=7462, categories match the naive
+//! reference evaluator, and both evaluators order the hands the same way.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use poker_solver_core::evaluate_7_cards;
+
+#[path = "../../tests/fixtures/reference_evaluator.rs"]
+mod reference_evaluator;
+
+use reference_evaluator::{category_of_score, distinct_cards, eval_best};
+
+fuzz_target!(|data: &[u8]| {
+    let Some(cards) = distinct_cards(data, 14) else {
+        return;
+    };
+    let (a, b) = cards.split_at(7);
+
+    let score_a = evaluate_7_cards(a);
+    let score_b = evaluate_7_cards(b);
+    assert!((1..=7462).contains(&score_a), "score {} out of range", score_a);
+    assert!((1..=7462).contains(&score_b), "score {} out of range", score_b);
+
+    let ref_a = eval_best(a);
+    let ref_b = eval_best(b);
+    assert_eq!(category_of_score(score_a), ref_a.0, "{:?}", a);
+    assert_eq!(category_of_score(score_b), ref_b.0, "{:?}", b);
+
+    // Lower score is better; higher reference value is better.
+    assert_eq!(score_a.cmp(&score_b), ref_b.cmp(&ref_a), "{:?} vs {:?}", a, b);
+});
@@ -0,0 +1,34 @@
+//! Arbitrary text through the card, range and action parsers: nothing may
+//! panic, and whatever is accepted must survive a format/parse round trip.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use poker_solver_core::solver::types::parse_action;
+use poker_solver_core::{format_range, parse_range, Card};
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+
+    if let Some(card) = Card::from_str(&text) {
+        assert_eq!(Card::from_str(&card.to_string()), Some(card));
+        assert!(card.index() < 52);
+    }
+
+    let range = parse_range(&text);
+    assert!(range.iter().all(|hand| hand.len() == 2));
+    assert_eq!(parse_range(&format_range(&range)), range);
+
+    let (action, amount) = parse_action(&text);
+    let formatted = match amount {
+        Some(a) => format!("{} {}", action.as_str(), a),
+        None => action.as_str().to_string(),
+    };
+    let (reparsed, reamount) = parse_action(&formatted);
+    assert_eq!(reparsed, action);
+    match (amount, reamount) {
+        (Some(a), Some(b)) => assert!(a == b || (a.is_nan() && b.is_nan())),
+        (None, None) => {}
+        other => panic!("amount did not round-trip: {:?} from {:?}", other, formatted),
+    }
+});
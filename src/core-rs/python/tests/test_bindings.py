@@ -0,0 +1,49 @@
+"""Smoke tests for the PyO3 bindings.
+
+Run after `maturin develop --release` (from src/core-rs):
+
+    pytest python/tests
+"""
+
+import math
+
+import poker_solver_core as psc
+
+CONFIG = {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [0.75], "raise_sizes": [1.0], "raise_limit": 1}
+BOARD = "Ks Th 7s 4d 2c"
+RANGE0 = ["As Ad", "Kc Kd", "Qh Jh", "6h 5h"]
+RANGE1 = "Ac Kh, Tc Td, 9s 8s, Qc Qd"
+
+
+def test_solve_and_read_strategy():
+    session = psc.SolverSession(CONFIG, BOARD, RANGE0, RANGE1)
+    session.step(200)
+    assert session.iterations == 200
+    assert session.exploitability() < 0.05 * CONFIG["initial_pot"]
+
+    root = session.get_range_strategy(0)
+    assert root["player"] == 0
+    assert root["hands"] == RANGE0
+    for row in root["strategy"]:
+        assert len(row) == len(root["actions"])
+        assert math.isclose(sum(row), 1.0, abs_tol=1e-4)
+
+
+def test_export_import_round_trip():
+    solved = psc.SolverSession(CONFIG, BOARD, RANGE0, RANGE1)
+    solved.step(50)
+
+    restored = psc.SolverSession(CONFIG, BOARD, RANGE0, RANGE1)
+    restored.import_solution(solved.export_solution())
+    assert restored.iterations == 50
+    assert restored.get_range_strategy(0) == solved.get_range_strategy(0)
+
+
+def test_equity_and_evaluation():
+    assert psc.evaluate("As Ks Qs Js Ts") == 1
+    assert psc.hand_name(psc.evaluate("2c 2d 2h 9s 9c")) == "Full House"
+    assert psc.equity(BOARD, "As Ad", "9s 8s") == 1.0
+
+    matrix = psc.equity_matrix(BOARD, RANGE0, RANGE1)
+    assert len(matrix) == len(RANGE0)
+    assert all(len(row) == 4 for row in matrix)
@@ -0,0 +1,318 @@
+//! Aggregate reports: the same ranges and config solved on many boards,
+//! with the root strategy and EVs tabulated per board and per texture group.
+//!
+//! Spec format (the CLI's `--aggregate` mode reads the same document):
+//! ```json
+//! {
+//!   "config": { "initial_pot": 100, "stacks": [200, 200], "bet_sizes": [0.5], "raise_sizes": [1.0] },
+//!   "range0": "As Ad, Qc Qd",
+//!   "range1": "Kc Kd, Jh Jc",
+//!   "boards": ["Ks Th 7s 4d 2c", "Ks Th 7s 4d 2h"],
+//!   "iterations": 1000,
+//!   "target_exploitability": 0.5,
+//!   "group_by": ["pairing", "suits"]
+//! }
+//! ```
+//! Instead of `boards`, `turn` (four cards) reports on every river of that
+//! turn, one board per class of suit-isomorphic rivers weighted by the
+//! class size, and `flops` (a subset name from
+//! [`crate::poker::flops::FLOP_SUBSETS`]) reports on canonical flops
+//! weighted by the flops each stands for. `iterations` and `target_exploitability` apply to each board
+//! as in [`crate::solve`]; `group_by` takes names from
+//! [`TEXTURE_FIELDS`] and defaults to pairing, suits and straights.
+//!
+//! The result is `{"ok": true, "actions": [...], "boards": [{ "board",
+//! "texture", "group", "weight", "iterations", "exploitability_pct", "ev",
+//! "frequencies" }], "groups": [{ "group", "boards", "weight", "ev",
+//! "frequencies" }]}`, or `{"ok": false, "errors": [...]}`. `frequencies`
+//! are player 0's root action frequencies (range weighted), in the order of
+//! `actions`; `ev` is each player's value in chips. Groups average their
+//! boards by weight.
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::poker::flops::{flop_string, flop_subset};
+use crate::poker::isomorphism::{board_symmetries, permute_card};
+use crate::poker::texture::{classify, TEXTURE_FIELDS};
+use crate::poker::Card;
+use crate::batch::BatchSolver;
+use crate::solve::SolveSpec;
+use crate::solver::GameConfig;
+use crate::SolverSession;
+
+/// Texture fields boards are grouped by when the spec names none.
+pub const DEFAULT_GROUP_BY: [&str; 3] = ["pairing", "suits", "straight_possible"];
+
+/// An aggregate report request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AggregateSpec {
+    pub config: GameConfig,
+    pub range0: String,
+    pub range1: String,
+    #[serde(default)]
+    pub boards: Option<Vec<String>>,
+    /// Report on the rivers of this turn instead of listing `boards`.
+    #[serde(default)]
+    pub turn: Option<String>,
+    /// Report on a named subset of canonical flops instead of `boards`.
+    #[serde(default)]
+    pub flops: Option<String>,
+    #[serde(default)]
+    pub iterations: Option<usize>,
+    #[serde(default)]
+    pub target_exploitability: Option<f32>,
+    #[serde(default)]
+    pub group_by: Option<Vec<String>>,
+}
+
+impl AggregateSpec {
+    /// The boards to solve with their weights, or every problem found.
+    pub fn boards(&self) -> Result<Vec<(String, f32)>, Vec<String>> {
+        match (&self.boards, &self.turn, &self.flops) {
+            (Some(boards), None, None) if !boards.is_empty() => Ok(boards.iter().map(|b| (b.clone(), 1.0)).collect()),
+            (None, None, Some(subset)) => Ok(flop_subset(subset).map_err(|e| vec![format!("flops: {}", e)])?
+                .into_iter()
+                .map(|(flop, weight)| (flop_string(&flop), weight as f32))
+                .collect()),
+            (None, Some(turn), None) => {
+                let cards: Vec<Card> = turn.split_whitespace().filter_map(Card::from_str).collect();
+                if cards.len() != 4 || turn.split_whitespace().count() != 4 {
+                    return Err(vec![format!("turn: expected 4 cards, got '{}'", turn)]);
+                }
+                Ok(canonical_rivers(&cards).into_iter()
+                    .map(|(river, weight)| (format!("{} {}", turn.trim(), river), weight as f32))
+                    .collect())
+            }
+            _ => Err(vec!["boards: give exactly one of a non-empty `boards` list, a `turn` or a `flops` subset".to_string()]),
+        }
+    }
+
+    /// The one-board solve spec for `board`.
+    fn solve_spec(&self, board: &str) -> SolveSpec {
+        SolveSpec {
+            config: self.config.clone(),
+            board: board.to_string(),
+            range0: self.range0.clone(),
+            range1: self.range1.clone(),
+            iterations: self.iterations,
+            target_exploitability: self.target_exploitability,
+            outputs: None,
+        }
+    }
+
+    fn group_by(&self) -> Vec<String> {
+        self.group_by.clone().unwrap_or_else(|| DEFAULT_GROUP_BY.iter().map(|f| f.to_string()).collect())
+    }
+}
+
+/// One river card per class of rivers that a suit symmetry of `turn` maps
+/// onto each other, with the class size.
+pub fn canonical_rivers(turn: &[Card]) -> Vec<(Card, usize)> {
+    let symmetries = board_symmetries(turn, &[]);
+    let mut classes: Vec<(Card, usize)> = Vec::new();
+    for index in 0..52u8 {
+        let card = Card::from_index(index);
+        if turn.contains(&card) {
+            continue;
+        }
+        let canonical = symmetries.iter().map(|perm| permute_card(card, perm)).min_by_key(|c| c.index()).expect("the identity is a symmetry");
+        match classes.iter_mut().find(|(c, _)| *c == canonical) {
+            Some((_, count)) => *count += 1,
+            None => classes.push((canonical, 1)),
+        }
+    }
+    classes
+}
+
+/// Run an aggregate report from a JSON spec and return the JSON result
+/// document. `progress(done, total, board)` is called after each board.
+/// Never fails: problems are reported in the document's `errors`.
+pub fn run_aggregate_report(spec_json: &str, progress: impl FnMut(usize, usize, &str)) -> String {
+    let result = serde_json::from_str::<AggregateSpec>(spec_json)
+        .map_err(|e| vec![format!("spec: {}", e)])
+        .and_then(|spec| aggregate_spec(&spec, progress));
+
+    match result {
+        Ok(document) => document,
+        Err(errors) => json!({ "ok": false, "errors": errors }),
+    }
+    .to_string()
+}
+
+/// [`run_aggregate_report`] for an already parsed spec.
+pub fn aggregate_spec(spec: &AggregateSpec, mut progress: impl FnMut(usize, usize, &str)) -> Result<serde_json::Value, Vec<String>> {
+    let boards = spec.boards()?;
+    let group_by = spec.group_by();
+    let mut errors = Vec::new();
+    for field in &group_by {
+        if !TEXTURE_FIELDS.contains(&field.as_str()) {
+            errors.push(format!("group_by: unknown texture field '{}' (expected one of {:?})", field, TEXTURE_FIELDS));
+        }
+    }
+    for (board, _) in &boards {
+        errors.extend(spec.solve_spec(board).validate().into_iter().map(|e| format!("{}: {}", board, e)));
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut actions: Vec<String> = Vec::new();
+    let mut rows = Vec::new();
+    let max_iterations = spec.solve_spec("").max_iterations();
+    let mut batch = BatchSolver::new(spec.config.clone(), &spec.range0, &spec.range1, max_iterations, spec.target_exploitability);
+    for (done, (board, weight)) in boards.iter().enumerate() {
+        let (session, exploitability_pct) = batch.solve_board(board).map_err(|e| vec![format!("{}: {}", board, e)])?;
+
+        let mut frequencies = vec![0.0; actions.len()];
+        for (label, frequency) in root_frequencies(session) {
+            match actions.iter().position(|a| *a == label) {
+                Some(i) => frequencies[i] = frequency,
+                None => {
+                    actions.push(label);
+                    frequencies.push(frequency);
+                }
+            }
+        }
+        let trainer = session.combo_trainer(&session.trainer);
+        let values = crate::solver::player_exploitability(&session.tree, &trainer, &session.equity_matrix, &session.initial_reach);
+        let texture = classify(&session.board);
+        let group = texture.group(&group_by).map_err(|e| vec![e])?;
+        rows.push(json!({
+            "board": board,
+            "texture": texture,
+            "group": group,
+            "weight": weight,
+            "iterations": session.iterations(),
+            "exploitability_pct": exploitability_pct,
+            "ev": values.average,
+            "frequencies": frequencies,
+        }));
+        progress(done + 1, boards.len(), board);
+    }
+
+    // Boards solved before a later board added an action never take it
+    for row in &mut rows {
+        let frequencies = row["frequencies"].as_array_mut().expect("rows list frequencies");
+        frequencies.resize(actions.len(), json!(0.0));
+    }
+
+    Ok(json!({ "ok": true, "actions": actions, "boards": rows, "groups": group_rows(&rows, actions.len()) }))
+}
+
+/// Player 0's range-weighted frequency of each root action.
+fn root_frequencies(session: &SolverSession) -> Vec<(String, f32)> {
+    let root = &session.tree.nodes[0];
+    let trainer = session.combo_trainer(&session.trainer);
+    let weights = &session.initial_reach[0];
+    let total: f32 = weights.iter().sum();
+    (0..root.num_actions as usize)
+        .map(|a| {
+            let taken: f32 = weights.iter().enumerate()
+                .map(|(h, w)| w * trainer.average_strategy(root.infoset_id as usize, h, root.num_actions as usize)[a])
+                .sum();
+            (session.action_label(root.children_start as usize + a), if total > 0.0 { taken / total } else { 0.0 })
+        })
+        .collect()
+}
+
+/// Running weighted sums of one texture group.
+struct GroupTotals {
+    group: String,
+    boards: usize,
+    weight: f64,
+    ev: [f64; 2],
+    frequencies: Vec<f64>,
+}
+
+/// Weighted averages of the board rows by group, in order of appearance.
+fn group_rows(rows: &[serde_json::Value], num_actions: usize) -> Vec<serde_json::Value> {
+    let mut groups: Vec<GroupTotals> = Vec::new();
+    for row in rows {
+        let group = row["group"].as_str().expect("rows have a group");
+        let i = match groups.iter().position(|g| g.group == group) {
+            Some(i) => i,
+            None => {
+                groups.push(GroupTotals { group: group.to_string(), boards: 0, weight: 0.0, ev: [0.0; 2], frequencies: vec![0.0; num_actions] });
+                groups.len() - 1
+            }
+        };
+        let totals = &mut groups[i];
+        let w = row["weight"].as_f64().expect("rows have a weight");
+        totals.boards += 1;
+        totals.weight += w;
+        for (p, e) in totals.ev.iter_mut().enumerate() {
+            *e += w * row["ev"][p].as_f64().expect("rows have EVs");
+        }
+        for (a, f) in totals.frequencies.iter_mut().enumerate() {
+            *f += w * row["frequencies"][a].as_f64().expect("rows have frequencies");
+        }
+    }
+
+    groups.into_iter()
+        .map(|g| json!({
+            "group": g.group,
+            "boards": g.boards,
+            "weight": g.weight,
+            "ev": g.ev.map(|e| e / g.weight),
+            "frequencies": g.frequencies.iter().map(|f| f / g.weight).collect::<Vec<_>>(),
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_report() {
+        let spec = r#"{
+            "config": {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": [], "raise_limit": 0},
+            "range0": "As Ad, 6h 5h",
+            "range1": "Kc Kd, 9s 8s",
+            "boards": ["Ks Th 7s 4d 2c", "Ks Th 7s 4d 2s", "Ks Th 7d 4d 2c", "Kh Tc 7s 7d 2c", "Kh Td 3s 3d 2c"],
+            "iterations": 30,
+            "group_by": ["pairing"]
+        }"#;
+        let mut calls = Vec::new();
+        let result: serde_json::Value = serde_json::from_str(&run_aggregate_report(spec, |done, total, board| {
+            calls.push((done, total, board.to_string()));
+        })).unwrap();
+        assert_eq!(result["ok"], true, "{}", result);
+        assert_eq!(calls.len(), 5);
+        assert_eq!(calls[4], (5, 5, "Kh Td 3s 3d 2c".to_string()));
+
+        assert_eq!(result["actions"], json!(["check", "bet 100"]));
+        let boards = result["boards"].as_array().unwrap();
+        assert_eq!(boards.len(), 5);
+        assert_eq!(boards[1]["texture"]["suits"], "flush_possible");
+        for board in boards {
+            let frequencies: f64 = board["frequencies"].as_array().unwrap().iter().map(|f| f.as_f64().unwrap()).sum();
+            assert!((frequencies - 1.0).abs() < 1e-5);
+            assert_eq!(board["iterations"], 30);
+        }
+
+        // Three unpaired boards, then two paired ones
+        let groups = result["groups"].as_array().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!((groups[0]["group"].as_str(), groups[0]["boards"].as_u64()), (Some("unpaired"), Some(3)));
+        assert_eq!((groups[1]["group"].as_str(), groups[1]["boards"].as_u64()), (Some("paired"), Some(2)));
+        let mean = (0..3).map(|i| boards[i]["ev"][0].as_f64().unwrap()).sum::<f64>() / 3.0;
+        assert!((groups[0]["ev"][0].as_f64().unwrap() - mean).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_turn_rivers_are_weighted_by_isomorphism() {
+        let turn: Vec<Card> = "Ks Qs Ts 7h".split_whitespace().filter_map(Card::from_str).collect();
+        let rivers = canonical_rivers(&turn);
+        assert_eq!(rivers.iter().map(|(_, n)| n).sum::<usize>(), 48);
+        // Clubs and diamonds are interchangeable, so each of their ranks is one class
+        assert_eq!(rivers.len(), 48 - 13);
+
+        let spec: AggregateSpec = serde_json::from_str(r#"{
+            "config": {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": []},
+            "range0": "As Ad", "range1": "Kc Kd", "turn": "Ks Qs Ts"
+        }"#).unwrap();
+        assert!(spec.boards().unwrap_err()[0].starts_with("turn:"));
+    }
+}
@@ -0,0 +1,1254 @@
+//! Range reports for coaching views, computed from hand-vs-range equities
+//! (see [`crate::poker::range_equities`]).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::poker::equity::hand_mask;
+use crate::poker::made_hands::HandDetail;
+use crate::poker::preflop::{class_index, class_name, NUM_CLASSES};
+use crate::poker::{compute_equity_matrix, evaluate_7_cards, get_hand_rank_name, hand_class, range_equities, Card, HandRank};
+use crate::solver::types::LineStep;
+
+/// Edges of the equity buckets: 0-25%, 25-50%, 50-75% and 75-100%. Each
+/// bucket includes its lower edge; the last one also includes 100%.
+pub const EQUITY_BUCKET_EDGES: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+/// Combos with more than this equity count as nut hands.
+pub const NUT_EQUITY: f32 = 0.9;
+
+/// How one player's range is spread over equity.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RangeProfile {
+    /// Total weight of the combos that have a possible matchup.
+    pub combos: f32,
+    /// Weighted mean equity against the other range.
+    pub mean_equity: f32,
+    /// Share of the weight in each bucket of [`EQUITY_BUCKET_EDGES`].
+    pub buckets: [f32; 4],
+    /// Share of the weight above [`NUT_EQUITY`].
+    pub nut_share: f32,
+}
+
+/// Both players' [`RangeProfile`]s and how they compare.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RangeAdvantage {
+    pub bucket_edges: [f32; 5],
+    pub nut_equity: f32,
+    pub players: [RangeProfile; 2],
+    /// Player 0's mean equity minus player 1's.
+    pub mean_equity_diff: f32,
+    /// Player 0's nut share minus player 1's.
+    pub nut_share_diff: f32,
+}
+
+/// Profile of a range from each combo's equity and weight. Combos with a
+/// NaN equity (no possible matchup) or no weight are left out.
+pub fn range_profile(equities: &[f32], weights: &[f32]) -> RangeProfile {
+    let mut combos = 0.0;
+    let mut equity_sum = 0.0;
+    let mut buckets = [0.0; 4];
+    let mut nuts = 0.0;
+
+    for (&equity, &weight) in equities.iter().zip(weights) {
+        if equity.is_nan() || weight <= 0.0 {
+            continue;
+        }
+        combos += weight;
+        equity_sum += weight * equity;
+        let bucket = EQUITY_BUCKET_EDGES[1..4].iter().take_while(|&&edge| equity >= edge).count();
+        buckets[bucket] += weight;
+        if equity > NUT_EQUITY {
+            nuts += weight;
+        }
+    }
+
+    let share = |w: f32| if combos > 0.0 { w / combos } else { 0.0 };
+    RangeProfile {
+        combos,
+        mean_equity: share(equity_sum),
+        buckets: buckets.map(share),
+        nut_share: share(nuts),
+    }
+}
+
+/// Range advantage of player 0 over player 1, from both players' equities
+/// against each other and their combo weights.
+pub fn range_advantage(equities: [&[f32]; 2], weights: [&[f32]; 2]) -> RangeAdvantage {
+    let players = [range_profile(equities[0], weights[0]), range_profile(equities[1], weights[1])];
+    RangeAdvantage {
+        bucket_edges: EQUITY_BUCKET_EDGES,
+        nut_equity: NUT_EQUITY,
+        mean_equity_diff: players[0].mean_equity - players[1].mean_equity,
+        nut_share_diff: players[0].nut_share - players[1].nut_share,
+        players,
+    }
+}
+
+/// One of [`equity_buckets`]' equal-width equity buckets.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EquityBucket {
+    /// Lowest equity in the bucket, included.
+    pub low: f32,
+    /// Highest equity, excluded except in the last bucket.
+    pub high: f32,
+    /// Share of the range's weight in the bucket.
+    pub share: f32,
+    /// Indices of the combos in the bucket.
+    pub combos: Vec<usize>,
+}
+
+/// Split a range into `count` (at least one) equal-width equity buckets,
+/// from each combo's equity and weight. Combos with a NaN equity or no
+/// weight are left out, as in [`range_profile`].
+pub fn equity_buckets(equities: &[f32], weights: &[f32], count: usize) -> Vec<EquityBucket> {
+    let mut buckets: Vec<EquityBucket> = (0..count)
+        .map(|b| EquityBucket { low: b as f32 / count as f32, high: (b + 1) as f32 / count as f32, share: 0.0, combos: Vec::new() })
+        .collect();
+    let mut total = 0.0;
+    for (h, (&equity, &weight)) in equities.iter().zip(weights).enumerate() {
+        if equity.is_nan() || weight <= 0.0 {
+            continue;
+        }
+        let bucket = ((equity * count as f32) as usize).min(count - 1);
+        buckets[bucket].share += weight;
+        buckets[bucket].combos.push(h);
+        total += weight;
+    }
+    if total > 0.0 {
+        buckets.iter_mut().for_each(|b| b.share /= total);
+    }
+    buckets
+}
+
+/// How a betting range is split into value and bluffs.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolarizationThresholds {
+    /// Combos with at least this equity against the continuing range are value.
+    pub value_equity: f32,
+    /// Combos below this equity are bluffs; those in between are neither.
+    pub bluff_equity: f32,
+    /// How many of the most frequent value and bluff combos to list.
+    pub top_combos: usize,
+}
+
+impl Default for PolarizationThresholds {
+    fn default() -> Self {
+        Self { value_equity: 0.5, bluff_equity: 0.5, top_combos: 10 }
+    }
+}
+
+/// One combo of a betting range.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComboEquity {
+    pub hand: String,
+    /// Reach weight of the combo taking the action.
+    pub weight: f32,
+    /// Equity against the opponent's continuing range.
+    pub equity: f32,
+}
+
+/// A betting range split by [`PolarizationThresholds`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RangeSplit {
+    pub value_combos: f32,
+    pub bluff_combos: f32,
+    /// Bluff weight per unit of value weight; `None` without value combos.
+    pub bluff_ratio: Option<f32>,
+    /// Most frequent value combos, heaviest first.
+    pub value: Vec<ComboEquity>,
+    /// Most frequent bluff combos, heaviest first.
+    pub bluffs: Vec<ComboEquity>,
+}
+
+/// Polarization of one bet or raise.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BetPolarization {
+    /// Action string, e.g. `"bet 100"`.
+    pub action: String,
+    /// Node the action leads to.
+    pub node: usize,
+    /// Chips the bettor puts in with the action.
+    pub size: f32,
+    /// Share of the bettor's range that takes the action.
+    pub frequency: f32,
+    /// Fold frequency a pure bluff needs to break even.
+    pub alpha: f32,
+    /// Bluff:value ratio that makes a bluff catcher indifferent.
+    pub optimal_bluff_ratio: f32,
+    #[serde(flatten)]
+    pub split: RangeSplit,
+}
+
+/// Polarization of every bet and raise at a node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PolarizationReport {
+    pub node: usize,
+    pub player: usize,
+    pub thresholds: PolarizationThresholds,
+    pub actions: Vec<BetPolarization>,
+}
+
+/// Fold frequency at which risking `risk` to win `pot` breaks even.
+pub fn alpha(risk: f32, pot: f32) -> f32 {
+    risk / (risk + pot)
+}
+
+/// Bluff:value ratio that gives a caller paying `call` into `pot` (the pot
+/// after the bet) zero expectation: calling wins `pot` against a bluff and
+/// loses `call` against value.
+pub fn optimal_bluff_ratio(call: f32, pot: f32) -> f32 {
+    call / pot
+}
+
+/// Split `combos` into value and bluffs. Combos without weight or without
+/// a possible matchup (NaN equity) are left out.
+pub fn split_range(combos: Vec<ComboEquity>, thresholds: &PolarizationThresholds) -> RangeSplit {
+    let (mut value, mut bluffs): (Vec<_>, Vec<_>) = (Vec::new(), Vec::new());
+    for combo in combos {
+        if combo.weight <= 0.0 || combo.equity.is_nan() {
+            continue;
+        }
+        if combo.equity >= thresholds.value_equity {
+            value.push(combo);
+        } else if combo.equity < thresholds.bluff_equity {
+            bluffs.push(combo);
+        }
+    }
+
+    let value_combos: f32 = value.iter().map(|c| c.weight).sum();
+    let bluff_combos: f32 = bluffs.iter().map(|c| c.weight).sum();
+    for list in [&mut value, &mut bluffs] {
+        list.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+        list.truncate(thresholds.top_combos);
+    }
+    RangeSplit {
+        value_combos,
+        bluff_combos,
+        bluff_ratio: (value_combos > 0.0).then(|| bluff_combos / value_combos),
+        value,
+        bluffs,
+    }
+}
+
+/// How one river changes the range-vs-range picture of a turn.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RiverImpact {
+    pub river: String,
+    /// Player 0's mean equity once this river is dealt.
+    pub equity: f32,
+    /// `equity` minus player 0's mean equity on the turn (all rivers
+    /// pooled); negative swings favour player 1.
+    pub equity_swing: f32,
+    /// The player whose equity goes up.
+    pub improves: usize,
+    /// Weight of each player's combos above [`NUT_EQUITY`] on this river.
+    pub nut_combos: [f32; 2],
+    /// Of those, the weight that was not above [`NUT_EQUITY`] on the turn.
+    pub new_nut_combos: [f32; 2],
+}
+
+/// Every river of the 4-card `board` with its [`RiverImpact`], largest
+/// equity swings first. Unweighted ranges; combos on the board are ignored.
+pub fn river_impact(board: &[Card], ranges: [&[Vec<Card>]; 2]) -> Result<Vec<RiverImpact>, String> {
+    if board.len() != 4 {
+        return Err(format!("River impact needs a 4-card turn board, got {} cards", board.len()));
+    }
+    let weights = [vec![1.0; ranges[0].len()], vec![1.0; ranges[1].len()]];
+    let weights = [&weights[0][..], &weights[1][..]];
+    let rivers: Vec<Card> = (0..52).map(Card::from_index).filter(|c| !board.contains(c)).collect();
+    let blocks: Vec<Vec<f32>> = rivers.iter()
+        .map(|&river| compute_equity_matrix(&[board, &[river]].concat(), ranges[0], ranges[1]))
+        .collect();
+
+    let turn = range_equities(&blocks.concat(), weights);
+    let turn_mean = range_profile(&turn[0], weights[0]).mean_equity;
+    let mut impacts: Vec<RiverImpact> = rivers.iter().zip(&blocks)
+        .map(|(river, block)| {
+            let equities = range_equities(block, weights);
+            let equity = range_profile(&equities[0], weights[0]).mean_equity;
+            let nuts = |p: usize, new: bool| -> f32 {
+                (0..equities[p].len())
+                    .filter(|&h| equities[p][h] > NUT_EQUITY && !(new && turn[p][h] > NUT_EQUITY))
+                    .map(|h| weights[p][h])
+                    .sum()
+            };
+            RiverImpact {
+                river: river.to_string(),
+                equity,
+                equity_swing: equity - turn_mean,
+                improves: if equity >= turn_mean { 0 } else { 1 },
+                nut_combos: [nuts(0, false), nuts(1, false)],
+                new_nut_combos: [nuts(0, true), nuts(1, true)],
+            }
+        })
+        .collect();
+    impacts.sort_by(|a, b| b.equity_swing.abs().total_cmp(&a.equity_swing.abs()));
+    Ok(impacts)
+}
+
+/// How one hand fares against another on a complete board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchupResult {
+    Win,
+    Lose,
+    Tie,
+    /// The hands share a card, or one holds a board card.
+    Blocked,
+}
+
+/// Two hands compared on a river board, from the first hand's side.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchupDetail {
+    /// 1 for a win, 0.5 for a tie, 0 for a loss; `None` when blocked.
+    pub equity: Option<f32>,
+    pub result: MatchupResult,
+    /// Evaluator score (lower is better) and hand name of each hand that
+    /// does not hold a board card.
+    pub hand1_score: Option<u16>,
+    pub hand1_name: Option<String>,
+    pub hand2_score: Option<u16>,
+    pub hand2_name: Option<String>,
+}
+
+/// `hand1` against `hand2` on a 5-card `board`.
+pub fn matchup_detail(board: &[Card], hand1: &[Card], hand2: &[Card]) -> Result<MatchupDetail, String> {
+    if board.len() != 5 {
+        return Err(format!("A matchup needs a 5-card board, got {} cards", board.len()));
+    }
+    if hand1.len() != 2 || hand2.len() != 2 {
+        return Err("Hands must have 2 cards".to_string());
+    }
+    let score = |hand: &[Card]| (hand_mask(hand) & hand_mask(board) == 0).then(|| evaluate_7_cards(&[hand, board].concat()));
+    let (score1, score2) = (score(hand1), score(hand2));
+    let result = match (score1, score2) {
+        _ if hand_mask(hand1) & hand_mask(hand2) != 0 => MatchupResult::Blocked,
+        (Some(a), Some(b)) => match a.cmp(&b) {
+            std::cmp::Ordering::Less => MatchupResult::Win,
+            std::cmp::Ordering::Equal => MatchupResult::Tie,
+            std::cmp::Ordering::Greater => MatchupResult::Lose,
+        },
+        _ => MatchupResult::Blocked,
+    };
+    Ok(MatchupDetail {
+        equity: match result {
+            MatchupResult::Win => Some(1.0),
+            MatchupResult::Tie => Some(0.5),
+            MatchupResult::Lose => Some(0.0),
+            MatchupResult::Blocked => None,
+        },
+        result,
+        hand1_score: score1,
+        hand1_name: score1.map(|s| get_hand_rank_name(s).to_string()),
+        hand2_score: score2,
+        hand2_name: score2.map(|s| get_hand_rank_name(s).to_string()),
+    })
+}
+
+/// Range against range on a turn or river board.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RangeEquity {
+    /// Each player's equity; the two sum to 1.
+    pub equity: [f32; 2],
+    /// Shares of the possible matchups player 0 wins, ties and loses. On
+    /// the turn every river dealt counts as a matchup of its own.
+    pub win: f32,
+    pub tie: f32,
+    pub lose: f32,
+    /// Possible matchups: pairs of combos sharing no card with each other
+    /// or the board, times the rivers on the turn.
+    pub matchups: usize,
+    /// Each player's equity by hand class as a 13x13 grid, aces first
+    /// (see [`crate::poker::preflop`]); `None` where the range has no live
+    /// combo.
+    pub grids: [Vec<Vec<Option<f32>>>; 2],
+}
+
+/// Equity of `ranges` against each other on a 4- or 5-card `board`,
+/// every combo weighted equally; the turn averages every river.
+pub fn range_equity(board: &[Card], ranges: [&[Vec<Card>]; 2]) -> Result<RangeEquity, String> {
+    let blocks: Vec<Vec<f32>> = match board.len() {
+        5 => vec![compute_equity_matrix(board, ranges[0], ranges[1])],
+        4 => (0..52).map(Card::from_index)
+            .filter(|c| !board.contains(c))
+            .map(|river| compute_equity_matrix(&[board, &[river]].concat(), ranges[0], ranges[1]))
+            .collect(),
+        n => return Err(format!("Range equity needs a 4- or 5-card board, got {} cards", n)),
+    };
+    let (mut win, mut tie, mut matchups) = (0usize, 0usize, 0usize);
+    for e in blocks.iter().flatten().filter(|e| !e.is_nan()) {
+        matchups += 1;
+        if *e == 1.0 {
+            win += 1;
+        } else if *e == 0.5 {
+            tie += 1;
+        }
+    }
+    let share = |n: usize| if matchups > 0 { n as f32 / matchups as f32 } else { 0.0 };
+    let (win, tie) = (share(win), share(tie));
+    let lose = if matchups > 0 { 1.0 - win - tie } else { 0.0 };
+
+    let weights = [vec![1.0; ranges[0].len()], vec![1.0; ranges[1].len()]];
+    let equities = range_equities(&blocks.concat(), [&weights[0], &weights[1]]);
+    let grids = [0, 1].map(|p| {
+        let (mut sums, mut counts) = (vec![0.0; NUM_CLASSES], vec![0usize; NUM_CLASSES]);
+        for (hand, &e) in ranges[p].iter().zip(&equities[p]) {
+            if let (false, Some(class)) = (e.is_nan(), class_index(&hand_class(hand))) {
+                sums[class] += e;
+                counts[class] += 1;
+            }
+        }
+        (0..13)
+            .map(|row| (0..13).map(|col| {
+                let class = row * 13 + col;
+                (counts[class] > 0).then(|| sums[class] / counts[class] as f32)
+            }).collect())
+            .collect()
+    });
+    Ok(RangeEquity { equity: [win + tie / 2.0, lose + tie / 2.0], win, tie, lose, matchups, grids })
+}
+
+/// A what-if change to one of the opponent's strategies: at `node`, every
+/// hand moves `fraction` of its probability for action `from` to action
+/// `to` (action strings as in histories, e.g. `"call"` or `"bet 50"`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrategyShift {
+    pub node: usize,
+    pub from: String,
+    pub to: String,
+    pub fraction: f32,
+}
+
+/// Move `fraction` of the probability of action `from` to action `to`.
+/// The probabilities still sum to the same total.
+pub fn shift_strategy(probs: &mut [f32], from: usize, to: usize, fraction: f32) {
+    let moved = probs[from] * fraction;
+    probs[from] -= moved;
+    probs[to] += moved;
+}
+
+/// A player's expected value (in chips, per matchup) at a node.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct NodeValues {
+    /// Playing their own average strategy.
+    pub equilibrium: f32,
+    /// Best-responding to the opponent.
+    pub best_response: f32,
+}
+
+/// How a player's value at a node moves when the opponent deviates.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExploitReport {
+    pub node: usize,
+    pub player: usize,
+    /// Pot at the node, for scale.
+    pub pot: f32,
+    pub shifts: Vec<StrategyShift>,
+    /// Against the opponent's average strategy.
+    pub baseline: NodeValues,
+    /// Against the shifted strategy.
+    pub perturbed: NodeValues,
+    /// Perturbed minus baseline equilibrium value.
+    pub equilibrium_delta: f32,
+    /// Perturbed minus baseline best-response value.
+    pub best_response_delta: f32,
+}
+
+impl ExploitReport {
+    pub fn new(node: usize, player: usize, pot: f32, shifts: Vec<StrategyShift>, baseline: NodeValues, perturbed: NodeValues) -> Self {
+        Self {
+            node,
+            player,
+            pot,
+            shifts,
+            baseline,
+            perturbed,
+            equilibrium_delta: perturbed.equilibrium - baseline.equilibrium,
+            best_response_delta: perturbed.best_response - baseline.best_response,
+        }
+    }
+}
+
+/// Combos realizing less than this share of their equity are flagged.
+pub const UNDER_REALIZATION: f32 = 0.7;
+
+/// How much of its raw equity one combo turns into EV.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComboRealization {
+    pub hand: String,
+    pub weight: f32,
+    /// Showdown equity against the opponent's starting range.
+    pub equity: f32,
+    /// EV at the root in chips, zero-sum (see [`realization`]).
+    pub ev: f32,
+    /// `None` when the combo has no equity to realize.
+    pub realization: Option<f32>,
+    /// Realization is below [`UNDER_REALIZATION`].
+    pub under_realizes: bool,
+}
+
+/// Equity realization of one player's range.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayerRealization {
+    pub player: usize,
+    /// The range's pot share over its equity share, both weighted by combo
+    /// weight and the number of opposing combos each one meets.
+    pub realization: f32,
+    pub combos: Vec<ComboRealization>,
+}
+
+/// Share of its immediate-showdown value a combo realizes: the pot share
+/// it ends up with over `equity * pot`. Payoffs are zero-sum around each
+/// player owning half of `pot`, so the pot share is `ev + pot / 2`.
+pub fn realization(equity: f32, ev: f32, pot: f32) -> Option<f32> {
+    let share = equity * pot;
+    (share > 1e-6 * pot).then(|| (ev + pot / 2.0) / share)
+}
+
+/// Decisions whose two best actions are within this share of the pot in
+/// EV count as close.
+pub const CLOSE_DECISION: f32 = 0.02;
+
+/// One legal action of a hint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActionHint {
+    /// History entry, e.g. `"bet 75"`.
+    pub action: String,
+    /// How often the solver takes it with this combo.
+    pub frequency: f32,
+    /// EV of the combo taking it, the solver playing on from there.
+    pub ev: f32,
+}
+
+/// What the solver does with one combo at one decision, and why.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Hint {
+    pub node: usize,
+    pub player: usize,
+    pub hand: String,
+    pub pot: f32,
+    /// EV of the solver's mix.
+    pub ev: f32,
+    pub actions: Vec<ActionHint>,
+    /// The highest-EV action.
+    pub best: String,
+    /// How much more the best action is worth than the next best; `None`
+    /// with a single action.
+    pub ev_gap: Option<f32>,
+    /// The gap is within [`CLOSE_DECISION`] of the pot.
+    pub close: bool,
+    /// One line for the overlay, e.g. `"Bet 75 (+12.30 EV, 4.10 more than
+    /// check)"`.
+    pub recommendation: String,
+}
+
+impl Hint {
+    /// Rank `actions` (at least one) and write the recommendation.
+    pub fn new(node: usize, player: usize, hand: String, pot: f32, actions: Vec<ActionHint>) -> Hint {
+        let mut order: Vec<&ActionHint> = actions.iter().collect();
+        order.sort_by(|a, b| b.ev.total_cmp(&a.ev));
+        let (best, next) = (order[0], order.get(1));
+        let ev_gap = next.map(|n| best.ev - n.ev);
+        let close = ev_gap.is_some_and(|gap| gap <= CLOSE_DECISION * pot);
+        let mut name = best.action.clone();
+        name[..1].make_ascii_uppercase();
+        let recommendation = match (next, ev_gap) {
+            (Some(n), Some(gap)) if close => format!("Close: {} ({:+.2} EV) and {} are within {:.2}", name, best.ev, n.action, gap),
+            (Some(n), Some(gap)) => format!("{} ({:+.2} EV, {:.2} more than {})", name, best.ev, gap, n.action),
+            _ => format!("{} ({:+.2} EV, the only action)", name, best.ev),
+        };
+        Hint {
+            node,
+            player,
+            hand,
+            pot,
+            ev: actions.iter().map(|a| a.frequency * a.ev).sum(),
+            best: best.action.clone(),
+            ev_gap,
+            close,
+            recommendation,
+            actions,
+        }
+    }
+}
+
+/// One decision of a user strategy to grade: the line reaching it and how
+/// often each hand takes each action there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserNode {
+    /// History entries from the root, as in `get_strategy_for_history`.
+    pub line: Vec<String>,
+    /// Action frequencies keyed by history entry (`"fold"`, `"bet 75"`),
+    /// for a combo (`"Kc Kd"`), a class (`"KK"`, `"AKs"`) or `"*"` for every
+    /// other hand. The most specific key wins and frequencies are
+    /// normalized; hands without a key play the solver's strategy.
+    pub hands: BTreeMap<String, BTreeMap<String, f32>>,
+}
+
+/// Settings of a leak report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeakOptions {
+    /// Most leaks listed.
+    pub top: usize,
+    /// Leaks costing less than this, in chips per hand, are left out.
+    pub min_loss: f32,
+}
+
+impl Default for LeakOptions {
+    fn default() -> Self {
+        Self { top: 5, min_loss: 0.0 }
+    }
+}
+
+/// What a user strategy changes for one hand class at one decision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassDelta {
+    pub class: String,
+    /// EV lost against the solver's strategy, in chips per hand dealt.
+    pub loss: f32,
+    /// Reach-weighted user minus solver frequency of every action.
+    pub excess: Vec<f32>,
+}
+
+/// The graded decisions of one node, input of [`leak_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeDeltas {
+    pub node: usize,
+    pub line: Vec<String>,
+    /// Where the decision is, e.g. `"facing bet 75 on the turn"`.
+    pub context: String,
+    /// History entry of every action.
+    pub actions: Vec<String>,
+    pub classes: Vec<ClassDelta>,
+}
+
+/// EV lost at one graded decision.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineLoss {
+    pub node: usize,
+    pub line: Vec<String>,
+    pub loss: f32,
+}
+
+/// EV lost by one hand class over every graded decision.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClassLoss {
+    pub class: String,
+    pub loss: f32,
+}
+
+/// Hand classes at one decision that take the same action too often.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Leak {
+    pub node: usize,
+    pub line: Vec<String>,
+    /// The action taken too often.
+    pub action: String,
+    pub classes: Vec<String>,
+    pub loss: f32,
+    /// E.g. `"You fold too often with JJ–88 facing bet 75 on the turn,
+    /// costing 1.30 per 100 hands"`.
+    pub description: String,
+}
+
+/// Where a user strategy loses EV against the solver's. Every loss is in
+/// chips per hand dealt, each graded decision compared on its own with
+/// the solver playing every other one; lines and classes come costliest
+/// first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeakReport {
+    pub player: usize,
+    pub total_loss: f32,
+    pub lines: Vec<LineLoss>,
+    pub classes: Vec<ClassLoss>,
+    pub leaks: Vec<Leak>,
+}
+
+/// Aggregate the per-class deltas of every graded node into a
+/// [`LeakReport`]. At each node the classes losing EV are grouped by the
+/// action they overuse the most, and each group is a leak.
+pub fn leak_report(player: usize, nodes: Vec<NodeDeltas>, options: &LeakOptions) -> LeakReport {
+    let mut classes: BTreeMap<&str, f32> = BTreeMap::new();
+    let mut leaks = Vec::new();
+    for node in &nodes {
+        let mut groups: BTreeMap<usize, (Vec<String>, f32)> = BTreeMap::new();
+        for delta in &node.classes {
+            *classes.entry(&delta.class).or_default() += delta.loss;
+            if delta.loss <= 0.0 {
+                continue;
+            }
+            let overused = (0..delta.excess.len()).max_by(|&a, &b| delta.excess[a].total_cmp(&delta.excess[b])).expect("a decision has actions");
+            let group = groups.entry(overused).or_default();
+            group.0.push(delta.class.clone());
+            group.1 += delta.loss;
+        }
+        for (action, (hands, loss)) in groups {
+            let action = node.actions[action].clone();
+            let description = format!("You {} too often with {} {}, costing {:.2} per 100 hands", action, class_runs(&hands), node.context, loss * 100.0);
+            leaks.push(Leak { node: node.node, line: node.line.clone(), action, classes: hands, loss, description });
+        }
+    }
+    leaks.retain(|l| l.loss >= options.min_loss);
+    leaks.sort_by(|a, b| b.loss.total_cmp(&a.loss));
+    leaks.truncate(options.top);
+
+    let mut lines: Vec<LineLoss> = nodes.iter()
+        .map(|n| LineLoss { node: n.node, line: n.line.clone(), loss: n.classes.iter().map(|c| c.loss).sum() })
+        .collect();
+    lines.sort_by(|a, b| b.loss.total_cmp(&a.loss));
+    let mut classes: Vec<ClassLoss> = classes.into_iter().map(|(class, loss)| ClassLoss { class: class.to_string(), loss }).collect();
+    classes.sort_by(|a, b| b.loss.total_cmp(&a.loss));
+    LeakReport { player, total_loss: lines.iter().map(|l| l.loss).sum(), lines, classes, leaks }
+}
+
+/// Hand classes written compactly in grid order: runs of three or more
+/// neighbouring classes (`JJ–88`, `KQs–KTs`) become ranges, at most four
+/// parts are spelled out.
+pub fn class_runs(classes: &[String]) -> String {
+    let mut indices: Vec<usize> = classes.iter().filter_map(|c| class_index(c)).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    // The next weaker class of the same kind on the grid
+    let next = |i: usize| match (i / 13).cmp(&(i % 13)) {
+        std::cmp::Ordering::Equal => i + 14,
+        std::cmp::Ordering::Less => i + 1,
+        std::cmp::Ordering::Greater => i + 13,
+    };
+
+    let mut runs: Vec<Vec<usize>> = Vec::new();
+    for i in indices {
+        match runs.last_mut() {
+            Some(run) if next(*run.last().expect("runs are never empty")) == i => run.push(i),
+            _ => runs.push(vec![i]),
+        }
+    }
+    let mut parts: Vec<String> = Vec::new();
+    for run in &runs {
+        if run.len() >= 3 {
+            parts.push(format!("{}–{}", class_name(run[0]), class_name(run[run.len() - 1])));
+        } else {
+            parts.extend(run.iter().map(|&i| class_name(i)));
+        }
+    }
+    if parts.len() > 4 {
+        let more = parts.len() - 3;
+        parts.truncate(3);
+        parts.push(format!("{} more", more));
+    }
+    parts.join(", ")
+}
+
+/// A condition on the combos of a hand query. Conditions nest with `all`,
+/// `any` and `not`, e.g. `{"all": [{"category": "flush"}, {"frequency":
+/// {"action": "bet", "min": 0.9}}]}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandFilter {
+    All(Vec<HandFilter>),
+    Any(Vec<HandFilter>),
+    Not(Box<HandFilter>),
+    /// How often the combo takes `action`, a history entry: `"bet 75"` is
+    /// the bet closest to 75, a bare `"bet"` or `"raise"` every size.
+    Frequency { action: String, min: Option<f32>, max: Option<f32> },
+    /// EV of the combo's mix in chips per opposing combo; combos no
+    /// opposing combo reaches against never match.
+    Ev { min: Option<f32>, max: Option<f32> },
+    /// Share of the combo that reaches the node.
+    Reach { min: Option<f32>, max: Option<f32> },
+    /// A hand category (`"flush"`, `"one_pair"`) or a [`HandDetail`]
+    /// (`"top_pair_top_kicker"`).
+    Category(String),
+    /// Blocker score as in `get_blocker_scores`; nodes without a bet to
+    /// respond to have none, and nothing matches.
+    Blocker { min: Option<f32>, max: Option<f32> },
+    /// The combo holds at least one of these cards, e.g. `"As Ks"`.
+    Holds(String),
+}
+
+/// Key a hand query sorts by.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandSort {
+    Hand,
+    Reach,
+    Ev,
+    Blocker,
+    /// Frequency of an action, matched as in [`HandFilter::Frequency`].
+    Frequency(String),
+}
+
+/// A range explorer query: the combos matching `filter` (every live combo
+/// without one), sorted by `sort`, highest first unless `ascending`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandQuery {
+    pub filter: Option<HandFilter>,
+    pub sort: HandSort,
+    pub ascending: bool,
+    pub limit: Option<usize>,
+}
+
+impl Default for HandQuery {
+    fn default() -> Self {
+        Self { filter: None, sort: HandSort::Hand, ascending: false, limit: None }
+    }
+}
+
+/// One combo at the queried node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HandRow {
+    pub hand: String,
+    pub class: String,
+    /// Snake_case [`HandRank`] name.
+    pub category: &'static str,
+    pub detail: HandDetail,
+    pub reach: f32,
+    /// Frequency of every action of the node.
+    pub frequencies: Vec<f32>,
+    pub ev: Option<f32>,
+    pub blocker: Option<f32>,
+    #[serde(skip)]
+    pub cards: u64,
+}
+
+/// Result of a [`HandQuery`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HandQueryResult {
+    pub node: usize,
+    pub player: usize,
+    pub actions: Vec<String>,
+    /// Live combos before filtering.
+    pub combos: usize,
+    pub hands: Vec<HandRow>,
+}
+
+/// Indices of the actions among `actions` that `action` refers to (see
+/// [`HandFilter::Frequency`]).
+fn action_indices(action: &str, actions: &[LineStep]) -> Result<Vec<usize>, String> {
+    let unknown = || format!("Action '{}' is not available at this node", action);
+    let Some(LineStep::Action(kind, amount)) = LineStep::parse(action) else { return Err(unknown()) };
+    let same_kind = (0..actions.len()).filter(|&a| matches!(actions[a], LineStep::Action(k, _) if k == kind));
+    let indices: Vec<usize> = match amount {
+        Some(amount) => same_kind
+            .min_by(|&a, &b| {
+                let distance = |a: usize| match actions[a] {
+                    LineStep::Action(_, Some(x)) => (x - amount).abs(),
+                    _ => f32::INFINITY,
+                };
+                distance(a).total_cmp(&distance(b))
+            })
+            .into_iter()
+            .collect(),
+        None => same_kind.collect(),
+    };
+    if indices.is_empty() { Err(unknown()) } else { Ok(indices) }
+}
+
+/// `value` is known and within the optional bounds.
+fn within(value: Option<f32>, min: Option<f32>, max: Option<f32>) -> bool {
+    value.is_some_and(|v| min.is_none_or(|m| v >= m) && max.is_none_or(|m| v <= m))
+}
+
+impl HandFilter {
+    /// Whether `row` matches, the node's actions being `actions`. Fails on
+    /// an action or category the filter cannot refer to.
+    pub fn matches(&self, row: &HandRow, actions: &[LineStep]) -> Result<bool, String> {
+        Ok(match self {
+            HandFilter::All(filters) => {
+                for f in filters {
+                    if !f.matches(row, actions)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            HandFilter::Any(filters) => {
+                for f in filters {
+                    if f.matches(row, actions)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            HandFilter::Not(filter) => !filter.matches(row, actions)?,
+            HandFilter::Frequency { action, min, max } => {
+                let frequency = action_indices(action, actions)?.iter().map(|&a| row.frequencies[a]).sum();
+                within(Some(frequency), *min, *max)
+            }
+            HandFilter::Ev { min, max } => within(row.ev, *min, *max),
+            HandFilter::Reach { min, max } => within(Some(row.reach), *min, *max),
+            HandFilter::Category(name) => {
+                let name = name.trim().to_ascii_lowercase().replace(' ', "_");
+                match (HandRank::from_name(&name), HandDetail::from_name(&name)) {
+                    (Some(rank), _) => rank.name() == row.category,
+                    (None, Some(detail)) => detail == row.detail,
+                    (None, None) => return Err(format!("Unknown hand category '{}'", name)),
+                }
+            }
+            HandFilter::Blocker { min, max } => within(row.blocker, *min, *max),
+            HandFilter::Holds(cards) => {
+                let cards: Vec<Card> = cards.split_whitespace().filter_map(Card::from_str).collect();
+                if cards.is_empty() {
+                    return Err("Holds needs at least one card".to_string());
+                }
+                row.cards & hand_mask(&cards) != 0
+            }
+        })
+    }
+}
+
+/// Apply `query` to `rows`, every live combo of the node whose actions are
+/// `actions`.
+pub fn query_hands(node: usize, player: usize, actions: &[LineStep], rows: Vec<HandRow>, query: &HandQuery) -> Result<HandQueryResult, String> {
+    let combos = rows.len();
+    let mut hands = Vec::new();
+    for row in rows {
+        if query.filter.as_ref().map_or(Ok(true), |f| f.matches(&row, actions))? {
+            hands.push(row);
+        }
+    }
+
+    let key: Box<dyn Fn(&HandRow) -> f32> = match &query.sort {
+        HandSort::Hand => Box::new(|_| 0.0),
+        HandSort::Reach => Box::new(|row| row.reach),
+        HandSort::Ev => Box::new(|row| row.ev.unwrap_or(f32::NEG_INFINITY)),
+        HandSort::Blocker => Box::new(|row| row.blocker.unwrap_or(f32::NEG_INFINITY)),
+        HandSort::Frequency(action) => {
+            let indices = action_indices(action, actions)?;
+            Box::new(move |row| indices.iter().map(|&a| row.frequencies[a]).sum())
+        }
+    };
+    // Stable, so ties keep range order
+    hands.sort_by(|a, b| {
+        let order = key(b).total_cmp(&key(a));
+        if query.ascending { order.reverse() } else { order }
+    });
+    if let Some(limit) = query.limit {
+        hands.truncate(limit);
+    }
+    Ok(HandQueryResult { node, player, actions: actions.iter().map(|a| a.to_string()).collect(), combos, hands })
+}
+
+/// Combos whose most frequent action has at least this frequency count as
+/// pure.
+pub const PURE_FREQUENCY: f32 = 0.99;
+
+/// Most-mixed combos listed per node of a mixing report.
+pub const MIXED_EXAMPLES: usize = 3;
+
+/// Shannon entropy of a strategy in bits: 0 for a pure one, 1 for an even
+/// two-way mix.
+pub fn entropy(probs: &[f32]) -> f32 {
+    -probs.iter().filter(|&&p| p > 0.0).map(|&p| p * p.log2()).sum::<f32>()
+}
+
+/// One combo's strategy at a node of a mixing report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MixedCombo {
+    pub hand: String,
+    /// Share of the combo that reaches the node.
+    pub reach: f32,
+    pub entropy: f32,
+    pub frequencies: Vec<f32>,
+}
+
+/// How mixed the acting player's strategy is at one node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeMixing {
+    pub node: usize,
+    pub line: Vec<String>,
+    pub player: usize,
+    pub actions: Vec<String>,
+    /// Chance that the node is reached, ignoring card removal between the
+    /// ranges.
+    pub reach: f32,
+    /// Average entropy of the combos, weighted by their reach.
+    pub entropy: f32,
+    /// Combos reaching the node that play a pure strategy (see
+    /// [`PURE_FREQUENCY`]) and that mix.
+    pub pure: usize,
+    pub mixed: usize,
+    /// The [`MIXED_EXAMPLES`] most mixed combos, most mixed first.
+    pub examples: Vec<MixedCombo>,
+}
+
+impl NodeMixing {
+    /// Summarize `combos`, the acting player's combos that reach the node.
+    pub fn new(node: usize, line: Vec<String>, player: usize, actions: Vec<String>, reach: f32, mut combos: Vec<MixedCombo>) -> Self {
+        let weight: f32 = combos.iter().map(|c| c.reach).sum();
+        let entropy = if weight > 0.0 { combos.iter().map(|c| c.reach * c.entropy).sum::<f32>() / weight } else { 0.0 };
+        let pure = combos.iter().filter(|c| c.frequencies.iter().any(|&p| p >= PURE_FREQUENCY)).count();
+        let mixed = combos.len() - pure;
+        combos.sort_by(|a, b| b.entropy.total_cmp(&a.entropy));
+        combos.truncate(MIXED_EXAMPLES.min(mixed));
+        NodeMixing { node, line, player, actions, reach, entropy, pure, mixed, examples: combos }
+    }
+}
+
+/// Where a solution mixes, for picking simplification candidates.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MixingReport {
+    /// Average node entropy, weighted by node reach.
+    pub entropy: f32,
+    /// Every decision node somebody reaches, most mixed first.
+    pub nodes: Vec<NodeMixing>,
+}
+
+impl MixingReport {
+    pub fn new(mut nodes: Vec<NodeMixing>) -> Self {
+        nodes.retain(|n| n.reach > 0.0);
+        nodes.sort_by(|a, b| b.entropy.total_cmp(&a.entropy).then(b.reach.total_cmp(&a.reach)));
+        let reach: f32 = nodes.iter().map(|n| n.reach).sum();
+        let entropy = if reach > 0.0 { nodes.iter().map(|n| n.reach * n.entropy).sum::<f32>() / reach } else { 0.0 };
+        MixingReport { entropy, nodes }
+    }
+}
+
+/// Settings of an automatic sizing solve: a coarse solve of a tree with
+/// every bet size in `sizes`, then a full solve keeping only the sizes that
+/// carry the betting at each node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoSizeOptions {
+    /// Bet sizes of the coarse tree, as pot fractions; the all-in is added
+    /// as usual.
+    pub sizes: Vec<f32>,
+    /// Iterations of the coarse solve.
+    pub coarse_iterations: usize,
+    /// Iterations of the final solve, counting the coarse ones it starts from.
+    pub iterations: usize,
+    /// Most bets or raises kept at one node.
+    pub max_sizes: usize,
+    /// Sizes are kept, most frequent first, until they hold this share of
+    /// the node's betting frequency.
+    pub coverage: f32,
+}
+
+impl Default for AutoSizeOptions {
+    fn default() -> Self {
+        Self { sizes: vec![0.25, 0.5, 0.75, 1.0, 1.5], coarse_iterations: 200, iterations: 1000, max_sizes: 2, coverage: 0.9 }
+    }
+}
+
+/// Indices of the sizes to keep given each size's frequency: the most
+/// frequent first, until they hold `coverage` of the total or `max_sizes`
+/// are kept. At least one size is kept. Returned in ascending order.
+pub fn pick_sizes(frequencies: &[f32], max_sizes: usize, coverage: f32) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..frequencies.len()).collect();
+    order.sort_by(|&a, &b| frequencies[b].total_cmp(&frequencies[a]));
+    let total: f32 = frequencies.iter().sum();
+
+    let (mut kept, mut held) = (Vec::new(), 0.0);
+    for i in order {
+        if !kept.is_empty() && (kept.len() >= max_sizes || held >= coverage * total) {
+            break;
+        }
+        held += frequencies[i];
+        kept.push(i);
+    }
+    kept.sort_unstable();
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::parse_range;
+
+    #[test]
+    fn test_buckets_and_edges() {
+        let equities = [0.0, 0.25, 0.6, 0.95, 1.0, f32::NAN];
+        let profile = range_profile(&equities, &[1.0, 1.0, 2.0, 1.0, 3.0, 5.0]);
+        assert_eq!(profile.combos, 8.0);
+        assert_eq!(profile.buckets, [0.125, 0.125, 0.25, 0.5]);
+        assert_eq!(profile.nut_share, 0.5);
+        assert!((profile.mean_equity - (0.25 + 1.2 + 0.95 + 3.0) / 8.0).abs() < 1e-6);
+
+        // Four equal buckets match the profile's
+        let buckets = equity_buckets(&equities, &[1.0, 1.0, 2.0, 1.0, 3.0, 5.0], 4);
+        assert_eq!(buckets.iter().map(|b| b.share).collect::<Vec<_>>(), profile.buckets);
+        assert_eq!(buckets.iter().map(|b| b.combos.clone()).collect::<Vec<_>>(), [vec![0], vec![1], vec![2], vec![3, 4]]);
+        assert_eq!((buckets[1].low, buckets[1].high), (0.25, 0.5));
+        let tenths = equity_buckets(&equities, &[1.0; 6], 10);
+        assert_eq!(tenths[6].combos, [2]);
+        assert_eq!(tenths[9].combos, [3, 4]);
+        assert!(equity_buckets(&[f32::NAN], &[1.0], 2).iter().all(|b| b.share == 0.0));
+    }
+
+    #[test]
+    fn test_empty_range() {
+        let profile = range_profile(&[f32::NAN], &[1.0]);
+        assert_eq!(profile.combos, 0.0);
+        assert_eq!(profile.buckets, [0.0; 4]);
+    }
+
+    #[test]
+    fn test_split_range() {
+        let combo = |hand: &str, weight, equity| ComboEquity { hand: hand.to_string(), weight, equity };
+        let thresholds = PolarizationThresholds { value_equity: 0.6, bluff_equity: 0.3, top_combos: 1 };
+        let split = split_range(vec![
+            combo("As Ad", 1.0, 0.9),
+            combo("Ks Kd", 2.0, 0.7),
+            combo("Qs Qd", 1.0, 0.5),
+            combo("6h 5h", 0.5, 0.1),
+            combo("9s 8s", 0.0, 0.0),
+            combo("Kh Qh", 1.0, f32::NAN),
+        ], &thresholds);
+        assert_eq!((split.value_combos, split.bluff_combos), (3.0, 0.5));
+        assert_eq!(split.bluff_ratio, Some(0.5 / 3.0));
+        assert_eq!(split.value[0].hand, "Ks Kd");
+        assert_eq!(split.value.len(), 1);
+        assert_eq!(split.bluffs[0].hand, "6h 5h");
+    }
+
+    #[test]
+    fn test_pot_sized_bet_theory() {
+        // A pot-sized bet needs 50% folds, and the caller gets 2:1.
+        assert_eq!(alpha(100.0, 100.0), 0.5);
+        assert_eq!(optimal_bluff_ratio(100.0, 200.0), 0.5);
+    }
+
+    #[test]
+    fn test_shift_strategy() {
+        let mut probs = [0.2, 0.5, 0.3];
+        shift_strategy(&mut probs, 1, 0, 0.2);
+        assert_eq!(probs, [0.3, 0.4, 0.3]);
+        shift_strategy(&mut probs, 2, 2, 1.0);
+        assert_eq!(probs, [0.3, 0.4, 0.3]);
+    }
+
+    #[test]
+    fn test_pick_sizes() {
+        // One size carries the betting
+        assert_eq!(pick_sizes(&[0.01, 0.02, 0.6], 2, 0.9), vec![2]);
+        // Two sizes are needed for 90%, but no more than two
+        assert_eq!(pick_sizes(&[0.3, 0.1, 0.35, 0.25], 2, 0.9), vec![0, 2]);
+        assert_eq!(pick_sizes(&[0.3, 0.1, 0.35, 0.25], 4, 0.9), vec![0, 2, 3]);
+        // A node that never bets keeps one size
+        assert_eq!(pick_sizes(&[0.0, 0.0], 2, 0.9).len(), 1);
+    }
+
+    #[test]
+    fn test_class_runs() {
+        let runs = |classes: &[&str]| class_runs(&classes.iter().map(|c| c.to_string()).collect::<Vec<_>>());
+        assert_eq!(runs(&["88", "TT", "JJ", "99"]), "JJ–88");
+        assert_eq!(runs(&["KTs", "KQs", "KJs", "AA", "AKo", "AQo"]), "AA, AKo, KQs–KTs, AQo");
+        assert_eq!(runs(&["AA", "QQ", "TT", "88", "66"]), "AA, QQ, TT, 2 more");
+    }
+
+    #[test]
+    fn test_leak_report_groups_by_overused_action() {
+        let delta = |class: &str, loss, excess: Vec<f32>| ClassDelta { class: class.to_string(), loss, excess };
+        let node = NodeDeltas {
+            node: 3,
+            line: vec!["bet 75".to_string()],
+            context: "facing bet 75 on the turn".to_string(),
+            actions: vec!["fold".to_string(), "call".to_string()],
+            classes: vec![
+                delta("JJ", 0.004, vec![0.2, -0.2]),
+                delta("TT", 0.006, vec![0.3, -0.3]),
+                delta("99", 0.003, vec![0.1, -0.1]),
+                delta("72o", 0.002, vec![-0.1, 0.1]),
+                delta("AA", -0.001, vec![0.0, 0.0]),
+            ],
+        };
+        let report = leak_report(1, vec![node.clone()], &LeakOptions::default());
+        assert!((report.total_loss - 0.014).abs() < 1e-6);
+        assert_eq!(report.lines[0].node, 3);
+        assert_eq!(report.classes[0].class, "TT");
+        assert_eq!(report.classes.last().unwrap().class, "AA");
+        assert_eq!(report.leaks.len(), 2);
+        assert_eq!(report.leaks[0].action, "fold");
+        assert_eq!(report.leaks[0].description, "You fold too often with JJ–99 facing bet 75 on the turn, costing 1.30 per 100 hands");
+        assert_eq!(report.leaks[1].classes, vec!["72o"]);
+
+        let report = leak_report(1, vec![node], &LeakOptions { top: 5, min_loss: 0.005 });
+        assert_eq!(report.leaks.len(), 1);
+        assert_eq!(report.lines.len(), 1);
+    }
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(entropy(&[1.0, 0.0]), 0.0);
+        assert_eq!(entropy(&[0.5, 0.5]), 1.0);
+        assert!((entropy(&[0.25, 0.25, 0.5]) - 1.5).abs() < 1e-6);
+
+        let combo = |hand: &str, reach, frequencies: Vec<f32>| MixedCombo { hand: hand.to_string(), reach, entropy: entropy(&frequencies), frequencies };
+        let node = NodeMixing::new(0, vec![], 0, vec![], 1.0, vec![
+            combo("As Ad", 1.0, vec![0.0, 1.0]),
+            combo("Ks Kd", 1.0, vec![0.995, 0.005]),
+            combo("6h 5h", 2.0, vec![0.5, 0.5]),
+        ]);
+        assert_eq!((node.pure, node.mixed), (2, 1));
+        assert_eq!(node.examples.len(), 1);
+        assert!((node.entropy - (entropy(&[0.995, 0.005]) + 2.0) / 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_matchup_detail_shape() {
+        let cards = |s: &str| -> Vec<Card> { s.split_whitespace().filter_map(Card::from_str).collect() };
+        let board = cards("Ks Th 7s 4d 2c");
+        let detail = matchup_detail(&board, &cards("As Ad"), &cards("Kc Kd")).unwrap();
+        assert_eq!(serde_json::to_value(&detail).unwrap(), serde_json::json!({
+            "equity": 0.0, "result": "lose",
+            "hand1_score": detail.hand1_score, "hand1_name": "One Pair",
+            "hand2_score": detail.hand2_score, "hand2_name": "Three of a Kind",
+        }));
+
+        // Shared cards block the matchup but still score the clean hand
+        let blocked = matchup_detail(&board, &cards("Ks Qd"), &cards("Ac Ad")).unwrap();
+        assert_eq!((blocked.result, blocked.equity, blocked.hand1_score), (MatchupResult::Blocked, None, None));
+        assert_eq!(blocked.hand2_name.as_deref(), Some("One Pair"));
+        assert_eq!(matchup_detail(&board, &cards("Ac Ad"), &cards("As Ad")).unwrap().result, MatchupResult::Blocked);
+        assert!(matchup_detail(&board[..4], &cards("Ac Ad"), &cards("Kc Kd")).is_err());
+    }
+
+    #[test]
+    fn test_range_equity_grids() {
+        let board: Vec<Card> = "Ks Th 7s 4d 2c".split_whitespace().filter_map(Card::from_str).collect();
+        let class = |name: &str| -> Vec<Vec<Card>> {
+            crate::poker::preflop::class_combos(class_index(name).unwrap()).iter().map(|c| c.to_vec()).collect()
+        };
+        let (aces, kings) = (class("AA"), class("KK"));
+        let river = range_equity(&board, [&aces, &kings]).unwrap();
+        // 6 AA against the 3 KK left, all lost to the set
+        assert_eq!((river.matchups, river.equity, river.lose), (18, [0.0, 1.0], 1.0));
+        assert_eq!(river.grids[0][0][0], Some(0.0));
+        assert_eq!(river.grids[1][1][1], Some(1.0));
+        assert_eq!(river.grids[0][1][1], None);
+        let json = serde_json::to_value(&river).unwrap();
+        assert_eq!(json["grids"][1].as_array().unwrap().len(), 13);
+        assert_eq!(json["grids"][1][1].as_array().unwrap().len(), 13);
+
+        // On the turn: 41 blanks of 6 x 3 matchups, 4 aces of 3 x 3 that AA
+        // wins and 3 kings of 6 x 1
+        let turn = range_equity(&board[..4], [&aces, &kings]).unwrap();
+        assert_eq!(turn.matchups, 41 * 18 + 4 * 9 + 3 * 6);
+        assert!((turn.equity[0] - 36.0 / 792.0).abs() < 1e-6);
+        assert_eq!(turn.equity[0] + turn.equity[1], 1.0);
+        assert!(range_equity(&board[..3], [&aces, &kings]).is_err());
+    }
+
+    #[test]
+    fn test_flush_cards_swing_to_the_drawing_range() {
+        let board: Vec<Card> = "Ks 9s 5h 2d".split_whitespace().filter_map(Card::from_str).collect();
+        let draws = parse_range("As Qs, Qs Js, Js Ts, Ts 8s, 8s 7s, 7s 6s, Ah Ad, 5c 5d");
+        let made = parse_range("Kh Kd, Kc Qc, Kd Jd, 9c 9d, Ac Kc, Kc Tc");
+        let impacts = river_impact(&board, [&draws, &made]).unwrap();
+        assert_eq!(impacts.len(), 48);
+
+        // The eleven spades come first, each swinging towards player 0
+        let top: Vec<&RiverImpact> = impacts.iter().take(11).collect();
+        for impact in &top {
+            assert!(impact.river.ends_with('s'), "{:?}", impact);
+            assert_eq!(impact.improves, 0);
+            assert!(impact.equity_swing > 0.0);
+        }
+        assert!(impacts[0].new_nut_combos[0] > 0.0 && impacts[0].new_nut_combos[1] == 0.0);
+        assert!(river_impact(&board[..3], [&draws, &made]).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Batch solving: one config and pair of ranges solved on many boards, one
+//! board after the other, for scripted study sessions.
+//!
+//! [`BatchSolver`] keeps the session of the last board and hands its
+//! allocations to the next one: the trainer's buffers always, and the tree
+//! too while the boards are rivers (a river tree depends on the config
+//! alone).
+//!
+//! Spec format for [`run_batch`] (exported to JS as `solve_batch`):
+//! ```json
+//! {
+//!   "config": { "initial_pot": 100, "stacks": [200, 200], "bet_sizes": [0.5], "raise_sizes": [1.0] },
+//!   "range0": "As Ad, Qc Qd",
+//!   "range1": "Kc Kd, Jh Jc",
+//!   "boards": ["Ks Th 7s 4d 2c", "Ks Th 7s 4d 2h"],
+//!   "iterations": 1000,
+//!   "target_exploitability": 0.5
+//! }
+//! ```
+//! `iterations` and `target_exploitability` apply to each board as in
+//! [`crate::solve`]. The result is `{"ok": true, "results": { <board>: {
+//! "iterations", "exploitability_pct", "ev", "root_strategy" } }}`, or
+//! `{"ok": false, "errors": [...]}`. `ev` is each player's value in chips
+//! and `root_strategy` is as in [`crate::solve`].
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::solve::{SolveSpec, CHECK_EVERY};
+use crate::solver::GameConfig;
+use crate::SolverSession;
+
+/// A batch request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchSpec {
+    pub config: GameConfig,
+    pub range0: String,
+    pub range1: String,
+    pub boards: Vec<String>,
+    #[serde(default)]
+    pub iterations: Option<usize>,
+    #[serde(default)]
+    pub target_exploitability: Option<f32>,
+}
+
+impl BatchSpec {
+    /// The one-board solve spec for `board`.
+    fn solve_spec(&self, board: &str) -> SolveSpec {
+        SolveSpec {
+            config: self.config.clone(),
+            board: board.to_string(),
+            range0: self.range0.clone(),
+            range1: self.range1.clone(),
+            iterations: self.iterations,
+            target_exploitability: self.target_exploitability,
+            outputs: None,
+        }
+    }
+
+    /// Every problem with the spec, boards included.
+    pub fn validate(&self) -> Vec<String> {
+        if self.boards.is_empty() {
+            return vec!["boards: list at least one board".to_string()];
+        }
+        self.boards.iter()
+            .flat_map(|board| self.solve_spec(board).validate().into_iter().map(move |e| format!("{}: {}", board, e)))
+            .collect()
+    }
+}
+
+/// Solves one config and pair of ranges on board after board, reusing the
+/// previous board's allocations (see the module docs).
+pub struct BatchSolver {
+    config: GameConfig,
+    range0: String,
+    range1: String,
+    max_iterations: usize,
+    target_pct: Option<f32>,
+    session: Option<SolverSession>,
+}
+
+impl BatchSolver {
+    /// A solver running `max_iterations` per board, or stopping early once
+    /// exploitability drops to `target_pct` percent of the starting pot.
+    pub fn new(config: GameConfig, range0: &str, range1: &str, max_iterations: usize, target_pct: Option<f32>) -> Self {
+        BatchSolver {
+            config,
+            range0: range0.to_string(),
+            range1: range1.to_string(),
+            max_iterations,
+            target_pct,
+            session: None,
+        }
+    }
+
+    /// The solver for a spec (see [`BatchSpec::validate`]).
+    pub fn from_spec(spec: &BatchSpec) -> Self {
+        let max_iterations = spec.solve_spec("").max_iterations();
+        Self::new(spec.config.clone(), &spec.range0, &spec.range1, max_iterations, spec.target_exploitability)
+    }
+
+    /// Solve `board` and return its session, which stays available until
+    /// the next board, with the final exploitability in percent of the
+    /// starting pot.
+    pub fn solve_board(&mut self, board: &str) -> Result<(&SolverSession, f32), String> {
+        let previous = self.session.take();
+        let mut session = SolverSession::from_config_reusing(self.config.clone(), board, &self.range0, &self.range1, previous)?;
+        let exploitability_pct = session.solve_until(self.max_iterations, self.target_pct, CHECK_EVERY);
+        Ok((self.session.insert(session), exploitability_pct))
+    }
+
+    /// Solve every board in turn, summarizing each as in [`run_batch`]
+    /// (keyed by board) and calling `progress(done, total, board)` after
+    /// each one. Stops at the first board that fails.
+    pub fn solve_all(&mut self, boards: &[String], mut progress: impl FnMut(usize, usize, &str)) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+        let mut results = serde_json::Map::new();
+        for (done, board) in boards.iter().enumerate() {
+            let (session, exploitability_pct) = self.solve_board(board).map_err(|e| format!("{}: {}", board, e))?;
+            let trainer = session.combo_trainer(&session.trainer);
+            let values = crate::solver::player_exploitability(&session.tree, &trainer, &session.equity_matrix, &session.initial_reach);
+            results.insert(board.clone(), json!({
+                "iterations": session.iterations(),
+                "exploitability_pct": exploitability_pct,
+                "ev": values.average,
+                "root_strategy": session.node_strategy_table(0)?,
+            }));
+            progress(done + 1, boards.len(), board);
+        }
+        Ok(results)
+    }
+}
+
+/// Run a batch from a JSON spec and return the JSON result document.
+/// `progress(done, total, board)` is called after each board. Never fails:
+/// problems are reported in the document's `errors`.
+pub fn run_batch(spec_json: &str, progress: impl FnMut(usize, usize, &str)) -> String {
+    let result = serde_json::from_str::<BatchSpec>(spec_json)
+        .map_err(|e| vec![format!("spec: {}", e)])
+        .and_then(|spec| {
+            let errors = spec.validate();
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+            BatchSolver::from_spec(&spec).solve_all(&spec.boards, progress).map_err(|e| vec![e])
+        });
+
+    match result {
+        Ok(results) => json!({ "ok": true, "results": results }),
+        Err(errors) => json!({ "ok": false, "errors": errors }),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": [], "raise_limit": 0}"#;
+
+    #[test]
+    fn test_batch_matches_separate_solves() {
+        let config: GameConfig = serde_json::from_str(CONFIG).unwrap();
+        let (range0, range1) = ("As Ad, 6h 5h, Qh Jh", "Kc Kd, 9s 8s");
+        let mut batch = BatchSolver::new(config.clone(), range0, range1, 40, None);
+        for board in ["Ks Th 7s 4d 2c", "Qs Jd 7s 4d", "Ks Th 7s 4d 2h"] {
+            let (session, pct) = batch.solve_board(board).unwrap();
+            let mut fresh = SolverSession::from_config(config.clone(), board, range0, range1).unwrap();
+            let fresh_pct = fresh.solve_until(40, None, CHECK_EVERY);
+            assert_eq!(session.tree.nodes.len(), fresh.tree.nodes.len());
+            assert_eq!(session.trainer.regrets, fresh.trainer.regrets, "{}", board);
+            assert_eq!(session.trainer.strategy_sum, fresh.trainer.strategy_sum, "{}", board);
+            assert_eq!(pct, fresh_pct);
+        }
+        assert!(batch.solve_board("Ks Th").is_err());
+    }
+
+    #[test]
+    fn test_run_batch() {
+        let spec = format!(r#"{{"config": {}, "range0": "As Ad, 6h 5h", "range1": "Kc Kd, 9s 8s",
+            "boards": ["Ks Th 7s 4d 2c", "Kh Td 3s 3d 2c"], "iterations": 30}}"#, CONFIG);
+        let mut calls = Vec::new();
+        let result: serde_json::Value = serde_json::from_str(&run_batch(&spec, |done, total, board| {
+            calls.push((done, total, board.to_string()));
+        })).unwrap();
+        assert_eq!(result["ok"], true, "{}", result);
+        assert_eq!(calls, [(1, 2, "Ks Th 7s 4d 2c".to_string()), (2, 2, "Kh Td 3s 3d 2c".to_string())]);
+        let paired = &result["results"]["Kh Td 3s 3d 2c"];
+        assert_eq!(paired["iterations"], 30);
+        assert_eq!(paired["ev"].as_array().unwrap().len(), 2);
+        assert_eq!(paired["root_strategy"]["player"], 0);
+
+        let bad = spec.replace("\"Kh Td 3s 3d 2c\"", "\"Kh Td\"");
+        let result: serde_json::Value = serde_json::from_str(&run_batch(&bad, |_, _, _| {})).unwrap();
+        assert_eq!(result["ok"], false);
+        assert!(result["errors"][0].as_str().unwrap().starts_with("Kh Td: board:"), "{}", result);
+    }
+}
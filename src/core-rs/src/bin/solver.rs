@@ -0,0 +1,292 @@
+//! Command-line river and turn solver.
+//!
+//! Reads a JSON solve spec from a file (or stdin), runs the same
+//! `GameConfig` → `build_river_tree` / `build_turn_tree` → `DCFRTrainer`
+//! pipeline as the WASM `SolverSession`, and writes the solution plus a
+//! summary as JSON. A 4-card board solves the turn with every river dealt.
+//!
+//! The spec is the one-shot `solve` document (see `poker_solver_core::solve`):
+//! ```json
+//! {
+//!   "config": { "initial_pot": 100, "stacks": [200, 200], "bet_sizes": [0.5], "raise_sizes": [1.0] },
+//!   "board": "Ks Th 7s 4d 2c",
+//!   "range0": "As Ad, Qc Qd",
+//!   "range1": "Kc Kd, Jh Jc",
+//!   "iterations": 1000,
+//!   "target_exploitability": 0.5
+//! }
+//! ```
+//! `target_exploitability` is in percent of the starting pot; when set, the
+//! solve stops as soon as it is reached (`iterations` is then the cap).
+//! `outputs` is ignored: the CLI always writes the full solution.
+//!
+//! With `--aggregate`, SPEC is an aggregate report spec instead (see
+//! `poker_solver_core::aggregate`): the same ranges solved on many boards,
+//! tabulated by board and texture group. With `--batch`, SPEC is a batch
+//! spec (see `poker_solver_core::batch`): one config and pair of ranges
+//! solved on a list of boards, with a summary and root strategy per board.
+//!
+//! Library diagnostics go through `log`; enable them with e.g. `RUST_LOG=debug`.
+//!
+//! The CLI is native only: wasm32 builds of the package (which build the
+//! binaries along with the tests) get an empty `main`.
+#![cfg_attr(target_arch = "wasm32", allow(dead_code, unused_imports))]
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use poker_solver_core::solve::SolveSpec;
+use poker_solver_core::{aggregate, batch, parallel, SolverSession};
+use serde_json::json;
+
+const USAGE: &str = "\
+Usage: solver-cli [OPTIONS] [SPEC]
+
+Solve the river or turn spot described by the JSON file SPEC (stdin if omitted or \"-\").
+
+Options:
+  -o, --output <PATH>           Write the solution to PATH instead of stdout
+  -s, --save-solution <PATH>    Also save the binary solution file (loadable by the web app and Python)
+      --browse-only             Save only the average strategies: a much smaller file for browsing the solve
+      --check-every <N>         Iterations between exploitability checks [default: 50]
+      --checkpoint-every <N>    Rewrite the output file every N iterations (requires --output)
+  -t, --threads <N>             Worker threads (needs the `parallel` feature; default: one per core)
+      --aggregate               SPEC is an aggregate report spec: solve every board and tabulate the results
+      --batch                   SPEC is a batch spec: solve every board and report each one
+  -q, --quiet                   Do not print progress to stderr
+  -h, --help                    Print this help";
+
+#[derive(Debug, Default)]
+struct Options {
+    spec_path: Option<String>,
+    output: Option<String>,
+    save_solution: Option<String>,
+    browse_only: bool,
+    check_every: usize,
+    checkpoint_every: Option<usize>,
+    threads: Option<usize>,
+    aggregate: bool,
+    batch: bool,
+    quiet: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut opts = Options { check_every: 50, ..Default::default() };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} requires a value", name));
+        match arg.as_str() {
+            "-o" | "--output" => opts.output = Some(value(&arg)?),
+            "-s" | "--save-solution" => opts.save_solution = Some(value(&arg)?),
+            "--check-every" => opts.check_every = parse_count(&arg, &value(&arg)?)?,
+            "--checkpoint-every" => opts.checkpoint_every = Some(parse_count(&arg, &value(&arg)?)?),
+            "-t" | "--threads" => opts.threads = Some(parse_count(&arg, &value(&arg)?)?),
+            "--browse-only" => opts.browse_only = true,
+            "--aggregate" => opts.aggregate = true,
+            "--batch" => opts.batch = true,
+            "-q" | "--quiet" => opts.quiet = true,
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') && arg != "-" => return Err(format!("Unknown option '{}'", arg)),
+            _ => {
+                if opts.spec_path.is_some() {
+                    return Err("Only one SPEC file may be given".to_string());
+                }
+                opts.spec_path = Some(arg);
+            }
+        }
+    }
+
+    if opts.aggregate && opts.batch {
+        return Err("--aggregate and --batch cannot be combined".to_string());
+    }
+    if (opts.aggregate || opts.batch) && (opts.save_solution.is_some() || opts.checkpoint_every.is_some()) {
+        let mode = if opts.batch { "--batch" } else { "--aggregate" };
+        return Err(format!("{} cannot be combined with --save-solution or --checkpoint-every", mode));
+    }
+    if opts.browse_only && opts.save_solution.is_none() {
+        return Err("--browse-only requires --save-solution".to_string());
+    }
+    if opts.checkpoint_every.is_some() && opts.output.is_none() {
+        return Err("--checkpoint-every requires --output".to_string());
+    }
+
+    Ok(opts)
+}
+
+fn parse_count(name: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("{} expects a positive integer, got '{}'", name, value)),
+    }
+}
+
+fn read_text(path: Option<&str>) -> Result<String, String> {
+    match path {
+        None | Some("-") => {
+            let mut buf = String::new();
+            io::stdin().read_to_string(&mut buf).map_err(|e| format!("Failed to read stdin: {}", e))?;
+            Ok(buf)
+        }
+        Some(p) => fs::read_to_string(p).map_err(|e| format!("Failed to read '{}': {}", p, e)),
+    }
+}
+
+fn read_spec(path: Option<&str>) -> Result<SolveSpec, String> {
+    let text = read_text(path)?;
+    let spec: SolveSpec = serde_json::from_str(&text).map_err(|e| format!("Invalid spec: {}", e))?;
+    let errors = spec.validate();
+    if !errors.is_empty() {
+        return Err(format!("Invalid spec: {}", errors.join("; ")));
+    }
+    Ok(spec)
+}
+
+/// Average strategies for every decision node in the tree.
+fn solution_json(session: &SolverSession) -> serde_json::Value {
+    let nodes: Vec<serde_json::Value> = (0..session.num_nodes())
+        .filter_map(|idx| session.node_strategy_table(idx).ok())
+        .collect();
+    json!({ "nodes": nodes })
+}
+
+fn write_output(path: Option<&str>, document: &serde_json::Value) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(document).map_err(|e| e.to_string())?;
+    match path {
+        Some(p) => fs::write(p, text).map_err(|e| format!("Failed to write '{}': {}", p, e)),
+        None => writeln!(io::stdout(), "{}", text).map_err(|e| e.to_string()),
+    }
+}
+
+/// `--aggregate` and `--batch`: many boards from one spec.
+fn run_boards(opts: &Options) -> Result<(), String> {
+    let text = read_text(opts.spec_path.as_deref())?;
+    let progress = |done, total, board: &str| {
+        if !opts.quiet {
+            eprintln!("board {}/{}: {}", done, total, board);
+        }
+    };
+    let report = if opts.batch { batch::run_batch(&text, progress) } else { aggregate::run_aggregate_report(&text, progress) };
+    let document: serde_json::Value = serde_json::from_str(&report).map_err(|e| e.to_string())?;
+    if document["ok"] != true {
+        let errors: Vec<&str> = document["errors"].as_array().into_iter().flatten().filter_map(|e| e.as_str()).collect();
+        return Err(format!("Invalid spec: {}", errors.join("; ")));
+    }
+    write_output(opts.output.as_deref(), &document)
+}
+
+fn run(opts: &Options) -> Result<(), String> {
+    if let Some(threads) = opts.threads {
+        parallel::configure_threads(threads)?;
+    }
+    if opts.aggregate || opts.batch {
+        return run_boards(opts);
+    }
+
+    let spec = read_spec(opts.spec_path.as_deref())?;
+    let start = Instant::now();
+
+    let mut session = SolverSession::from_config(spec.config.clone(), &spec.board, &spec.range0, &spec.range1)?;
+    session.set_perf_counters(true);
+
+    let max_iterations = spec.max_iterations();
+    let chunk = opts.checkpoint_every.map_or(opts.check_every, |c| c.min(opts.check_every));
+
+    if !opts.quiet {
+        eprintln!("Tree: {} nodes, {} infosets ({} threads)",
+                  session.num_nodes(), session.num_infosets(), parallel::num_threads());
+    }
+
+    while session.iterations() < max_iterations {
+        let n = chunk.min(max_iterations - session.iterations());
+        session.step(n);
+        let done = session.iterations();
+
+        let reached_target = match spec.target_exploitability {
+            Some(target) => {
+                let point = session.record_exploitability();
+                if !opts.quiet {
+                    eprintln!("iteration {}: exploitability {:.3}% pot (best-response gain {:.3}% / {:.3}%)",
+                              done, point.exploitability_pct, point.gain_pct[0], point.gain_pct[1]);
+                }
+                point.exploitability_pct <= target
+            }
+            None => {
+                if !opts.quiet {
+                    eprintln!("iteration {}", done);
+                }
+                false
+            }
+        };
+
+        if let Some(every) = opts.checkpoint_every
+            && done % every == 0
+        {
+            write_output(opts.output.as_deref(), &json!({ "solution": solution_json(&session) }))?;
+        }
+
+        if reached_target {
+            break;
+        }
+    }
+
+    let point = session.record_exploitability();
+    let summary = json!({
+        "iterations": session.iterations(),
+        "exploitability": point.exploitability,
+        "exploitability_pct": point.exploitability_pct,
+        "gain": point.gain,
+        "gain_pct": point.gain_pct,
+        "convergence": session.convergence_history(),
+        "time_ms": start.elapsed().as_millis() as u64,
+        "nodes": session.num_nodes(),
+        "infosets": session.num_infosets(),
+        "threads": parallel::num_threads(),
+        "perf": session.perf_counters()
+    });
+
+    if !opts.quiet {
+        eprintln!("Done: {}", summary);
+    }
+
+    if let Some(path) = &opts.save_solution {
+        if opts.browse_only {
+            session.save_browse_solution(path)?;
+        } else {
+            session.save_solution(path)?;
+        }
+    }
+
+    write_output(opts.output.as_deref(), &json!({
+        "summary": summary,
+        "solution": solution_json(&session)
+    }))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let opts = match parse_args(std::env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(msg) => {
+            if !msg.is_empty() {
+                eprintln!("error: {}\n", msg);
+            }
+            eprintln!("{}", USAGE);
+            return if msg.is_empty() { ExitCode::SUCCESS } else { ExitCode::from(2) };
+        }
+    };
+
+    match run(&opts) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(msg) => {
+            eprintln!("error: {}", msg);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,698 +1,5372 @@
-use wasm_bindgen::prelude::*;
-
-// Poker primitives module
-pub mod poker;
-pub mod solver;
-
-// Re-export poker types and WASM functions
-pub use poker::Card;
-pub use poker::card::{parse_card, card_to_string, card_bitmask, card_rank, card_suit};
-pub use poker::evaluator::{evaluate_7_cards, evaluate_5_cards, get_hand_rank_name, init_lookup_tables};
-pub use poker::equity::{compute_equity_matrix, compute_single_equity};
-
-use solver::{GameConfig, build_river_tree, DCFRTrainer, GameTree};
-use solver::types::ActionType;
-use serde_json::json;
-
-/// Initialize panic hook for better error messages in browser console.
-/// This should be called once at startup.
-fn init_panic_hook() {
-    #[cfg(feature = "console_error_panic_hook")]
-    console_error_panic_hook::set_once();
-    
-    // Always set the hook in debug builds
-    #[cfg(debug_assertions)]
-    console_error_panic_hook::set_once();
-}
-
-/// Macro for convenient console logging using web-sys
-macro_rules! log {
-    ($($t:tt)*) => (web_sys::console::log_1(&format!($($t)*).into()))
-}
-
-/// Re-export the macro for use in submodules
-pub(crate) use log;
-
-/// Initialize the Rust core module and lookup tables.
-/// Returns Ok(()) on success, or a JsValue error on failure.
-#[wasm_bindgen]
-pub fn init() -> Result<(), JsValue> {
-    init_panic_hook();
-    init_lookup_tables();
-    log!("🦀 Rust Core Initialized (with lookup tables)");
-    Ok(())
-}
-
-/// Simple greet function to verify the toolchain works.
-#[wasm_bindgen]
-pub fn greet(name: &str) -> String {
-    format!("Hello, {}! Poker Solver Rust Core is ready.", name)
-}
-
-/// Returns the version of the Rust core module.
-#[wasm_bindgen]
-pub fn version() -> String {
-    env!("CARGO_PKG_VERSION").to_string()
-}
-
-// ============================================================================
-// WASM EXPORTS FOR EVALUATOR
-// ============================================================================
-
-/// Test hand evaluation from JS.
-/// Accepts a space-separated string of card codes (e.g., "As Kh Qd Jc Ts 2h 3c")
-/// Returns the hand score (lower = better, 1 = royal flush)
-#[wasm_bindgen]
-pub fn test_evaluation(cards_str: &str) -> u16 {
-    let cards: Vec<Card> = cards_str
-        .split_whitespace()
-        .filter_map(|s| Card::from_str(s))
-        .collect();
-    
-    if cards.len() < 5 {
-        return 7462; // Worst possible
-    }
-    
-    evaluate_7_cards(&cards)
-}
-
-/// Get the hand rank name from a score
-#[wasm_bindgen]
-pub fn get_hand_name(score: u16) -> String {
-    get_hand_rank_name(score).to_string()
-}
-
-/// Evaluate a single hand comparison on a board
-/// Returns 1.0 (win), 0.0 (loss), 0.5 (tie), or -1.0 (blocked/impossible)
-#[wasm_bindgen]
-pub fn evaluate_matchup(board_str: &str, hand1_str: &str, hand2_str: &str) -> f32 {
-    let board: Vec<Card> = board_str.split_whitespace().filter_map(|s| Card::from_str(s)).collect();
-    let hand1: Vec<Card> = hand1_str.split_whitespace().filter_map(|s| Card::from_str(s)).collect();
-    let hand2: Vec<Card> = hand2_str.split_whitespace().filter_map(|s| Card::from_str(s)).collect();
-    
-    match compute_single_equity(&board, &hand1, &hand2) {
-        Some(eq) => eq,
-        None => -1.0, // Blocked
-    }
-}
-
-/// Build a test tree and return stats as JSON string.
-///
-/// # Arguments
-/// * `initial_pot` - Pot size at start of river
-/// * `stack` - Effective stack size (for both players)
-#[wasm_bindgen]
-pub fn test_tree_build(initial_pot: f32, stack: f32) -> String {
-    let config = GameConfig {
-        initial_pot,
-        stacks: [stack, stack],
-        bet_sizes: vec![0.5, 1.0], // 50% and 100% pot bets
-        raise_sizes: vec![1.0],    // 100% pot raises
-        raise_limit: 3,            // Allow up to 3 raises
-    };
-
-    let tree = build_river_tree(&config);
-
-    let stats = json!({
-        "nodes": tree.nodes.len(),
-        "infosets": tree.infoset_map.len(),
-        "root_pot": tree.nodes[0].pot,
-        "config": config
-    });
-
-    stats.to_string()
-}
-
-
-
-#[wasm_bindgen]
-pub struct SolverSession {
-    tree: GameTree,
-    trainer: DCFRTrainer,
-    equity_matrix: Vec<f32>,
-    initial_reach: [Vec<f32>; 2],
-    ranges: [Vec<Vec<Card>>; 2],
-}
-
-#[wasm_bindgen]
-impl SolverSession {
-    #[wasm_bindgen(constructor)]
-    pub fn new(config_json: &str, board_str: &str, range0_str: &str, range1_str: &str) -> Result<SolverSession, JsValue> {
-        log!("[SolverSession::new] Init session...");
-
-        // 1. Parse Config
-        let config: GameConfig = serde_json::from_str(config_json)
-            .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
-        log!("[SolverSession::new] Config parsed: pot={}, stacks={:?}", config.initial_pot, config.stacks);
-
-        // 2. Parse Board
-        let board: Vec<Card> = board_str.split_whitespace()
-            .filter_map(|s| Card::from_str(s))
-            .collect();
-        if board.len() != 5 {
-             return Err(JsValue::from_str("Board must have 5 cards"));
-        }
-        // Log board as integer values to verify they aren't 0
-        let board_ints: Vec<u8> = board.iter().map(|c| c.index()).collect();
-        log!("[SolverSession::new] Board parsed: {:?} (ints: {:?})", board_str, board_ints);
-
-        // 3. Parse Ranges
-        let parse_range = |s: &str| -> Vec<Vec<Card>> {
-            s.split(',')
-             .map(|hand_str| {
-                 hand_str.split_whitespace()
-                         .filter_map(|cs| Card::from_str(cs))
-                         .collect::<Vec<Card>>()
-             })
-             .filter(|h| h.len() == 2)
-             .collect()
-        };
-
-        let range0 = parse_range(range0_str);
-        let range1 = parse_range(range1_str);
-
-        if range0.is_empty() || range1.is_empty() {
-            return Err(JsValue::from_str("Ranges cannot be empty"));
-        }
-        log!("[SolverSession::new] Ranges: P0={} hands, P1={} hands", range0.len(), range1.len());
-
-        // 4. Compute Equity Matrix
-        let equity_matrix = compute_equity_matrix(&board, &range0, &range1);
-        log!("[SolverSession::new] Equity Matrix size: {} (expected {}x{}={})",
-             equity_matrix.len(), range0.len(), range1.len(), range0.len() * range1.len());
-        // Log first few equity values
-        if equity_matrix.len() >= 3 {
-            log!("[SolverSession::new] Equity sample [0..3]: [{:.3}, {:.3}, {:.3}]",
-                 equity_matrix[0], equity_matrix[1], equity_matrix[2]);
-        }
-
-        // 5. Build Tree
-        let tree = build_river_tree(&config);
-        log!("[SolverSession::new] Tree built. Nodes: {}, Infosets: {}",
-             tree.nodes.len(), tree.infoset_map.len());
-
-        // 6. Initialize Trainer
-        let num_infosets = tree.infoset_map.len();
-        let max_actions = tree.nodes.iter().map(|n| n.num_actions as usize).max().unwrap_or(0);
-        let num_hands = [range0.len(), range1.len()];
-
-        let trainer = DCFRTrainer::new(num_infosets, max_actions, num_hands);
-        log!("[SolverSession::new] Trainer created. regrets.len={}, strategy_sum.len={}, max_actions={}",
-             trainer.regrets.len(), trainer.strategy_sum.len(), max_actions);
-
-        // 7. Initial Reach
-        let initial_reach = [vec![1.0; num_hands[0]], vec![1.0; num_hands[1]]];
-
-        log!("[SolverSession::new] Session ready!");
-        Ok(SolverSession {
-            tree,
-            trainer,
-            equity_matrix,
-            initial_reach,
-            ranges: [range0, range1],
-        })
-    }
-    
-    pub fn step(&mut self, iterations: usize) {
-        self.trainer.train(&self.tree, &self.equity_matrix, iterations, &self.initial_reach);
-    }
-    
-    pub fn get_stats(&self) -> String {
-        json!({
-            "iterations": self.trainer.iterations,
-            "nodes": self.tree.nodes.len(),
-            "infosets": self.tree.infoset_map.len()
-        }).to_string()
-    }
-
-    pub fn get_strategy_ptr(&self) -> *const f32 {
-        let ptr = self.trainer.strategy_sum.as_ptr();
-        let len = self.trainer.strategy_sum.len();
-        log!("[get_strategy_ptr] Returning ptr: {:p}, len: {}", ptr, len);
-
-        // Print first 3 floats to prove Rust has data
-        if len >= 3 {
-            log!("[get_strategy_ptr] First 3 floats: [{:.6}, {:.6}, {:.6}]",
-                 self.trainer.strategy_sum[0],
-                 self.trainer.strategy_sum[1],
-                 self.trainer.strategy_sum[2]);
-        }
-
-        // Also check if any values are non-zero
-        let non_zero_count = self.trainer.strategy_sum.iter().filter(|&&x| x != 0.0).count();
-        log!("[get_strategy_ptr] Non-zero values: {} / {}", non_zero_count, len);
-
-        ptr
-    }
-
-    pub fn get_strategy_len(&self) -> usize {
-        self.trainer.strategy_sum.len()
-    }
-
-    pub fn get_num_actions(&self) -> usize {
-        self.trainer.max_actions()
-    }
-    
-    /// Get available actions at the root node as JSON.
-    /// Returns [{ "type": "check", "amount": 0 }, { "type": "bet", "amount": 75 }, ...]
-    /// This is used by the UI to display action buttons with correct amounts.
-    pub fn get_node_actions(&self) -> String {
-        let node_id: u32 = 0; // Root node
-        let node = &self.tree.nodes[node_id as usize];
-
-        let mut actions = Vec::new();
-
-        for i in 0..node.num_actions {
-            let child_id = node.children_start + i as u32;
-            let child = &self.tree.nodes[child_id as usize];
-
-            if let Some(action_type) = child.action_from_parent {
-                let type_str = match action_type {
-                    ActionType::Fold => "fold",
-                    ActionType::Check => "check",
-                    ActionType::Call => "call",
-                    ActionType::Bet => "bet",
-                    ActionType::Raise => "raise",
-                };
-
-                actions.push(json!({
-                    "type": type_str,
-                    "amount": child.amount_from_parent
-                }));
-            }
-        }
-
-        serde_json::to_string(&actions).unwrap_or_else(|_| "[]".to_string())
-    }
-
-    /// Get strategy for a specific hand (e.g., "As Kh") as JSON.
-    /// Returns { "actions": ["check", "bet"], "probs": [0.5, 0.5] }
-    pub fn get_hand_strategy(&self, hand_str: &str) -> Result<String, JsValue> {
-        let cards: Vec<Card> = hand_str.split_whitespace()
-            .filter_map(|s| Card::from_str(s))
-            .collect();
-            
-        if cards.len() != 2 {
-            return Err(JsValue::from_str("Hand must have 2 cards"));
-        }
-        
-        // Find player and hand index
-        let mut player = 0;
-        let mut hand_idx = None;
-        
-        // Check range 0
-        for (i, h) in self.ranges[0].iter().enumerate() {
-            if h[0] == cards[0] && h[1] == cards[1] || h[0] == cards[1] && h[1] == cards[0] {
-                hand_idx = Some(i);
-                player = 0;
-                break;
-            }
-        }
-        
-        // Check range 1
-        if hand_idx.is_none() {
-            for (i, h) in self.ranges[1].iter().enumerate() {
-                if h[0] == cards[0] && h[1] == cards[1] || h[0] == cards[1] && h[1] == cards[0] {
-                    hand_idx = Some(i);
-                    player = 1;
-                    break;
-                }
-            }
-        }
-        
-        let hand_idx = hand_idx.ok_or_else(|| JsValue::from_str("Hand not found in ranges"))?;
-        
-        // Find node
-        let node_id = if player == 0 {
-            0 // Root
-        } else {
-            // Find child of root that is ActionType::Check
-            // Root is P0. Children are P0's actions.
-            // We need the node where P0 checked.
-            // That node is where P1 acts.
-            let root = &self.tree.nodes[0];
-            let mut target_id = None;
-            for i in 0..root.num_actions {
-                let child_id = root.children_start + i as u32;
-                let child = &self.tree.nodes[child_id as usize];
-                // The child node represents the state AFTER P0's action.
-                // If P0 checked, child.action_from_parent == Check.
-                // And child.player should be 1 (P1).
-                if let Some(ActionType::Check) = child.action_from_parent {
-                    target_id = Some(child_id);
-                    break;
-                }
-            }
-            target_id.ok_or_else(|| JsValue::from_str("No check action found for P0"))?
-        };
-        
-        let node = &self.tree.nodes[node_id as usize];
-        if node.infoset_id == u32::MAX {
-             return Err(JsValue::from_str("Node has no infoset"));
-        }
-        
-        let strategy = self.trainer.get_average_strategy_with_actions(
-            node.infoset_id as usize,
-            hand_idx,
-            node.num_actions as usize
-        );
-
-        // Get action names
-        let mut actions = Vec::new();
-        for i in 0..node.num_actions {
-            let child_id = node.children_start + i as u32;
-            let child = &self.tree.nodes[child_id as usize];
-            if let Some(action_type) = child.action_from_parent {
-                let mut name = format!("{:?}", action_type).to_lowercase();
-                if action_type == ActionType::Bet || action_type == ActionType::Raise {
-                    name = format!("{} {:.1}", name, child.amount_from_parent);
-                }
-                actions.push(name);
-            } else {
-                actions.push("unknown".to_string());
-            }
-        }
-        
-        // Filter strategy to match num_actions
-        let probs = &strategy[0..node.num_actions as usize];
-
-        Ok(json!({
-            "actions": actions,
-            "probs": probs
-        }).to_string())
-    }
-
-    /// Get the node index for a given action history.
-    /// history_actions_js should be a JS array of action strings, e.g., ["check", "bet 75"]
-    /// Returns the node index, or an error if the path is invalid.
-    #[wasm_bindgen]
-    pub fn get_strategy_for_history(&self, history_actions_js: JsValue) -> Result<String, JsValue> {
-        // Parse JS array into Vec<String>
-        let history: Vec<String> = serde_wasm_bindgen::from_value(history_actions_js)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse history: {}", e)))?;
-
-        log!("[get_strategy_for_history] History: {:?}", history);
-
-        // Start at root node
-        let mut node_idx: usize = 0;
-
-        // Traverse the tree following the action history
-        for action_str in &history {
-            let current_node = &self.tree.nodes[node_idx];
-
-            // Parse the action string into ActionType and optional amount
-            let (target_action, target_amount) = Self::parse_action_string(action_str);
-
-            log!("[get_strategy_for_history] At node {} (player={}), looking for action {:?} (amount: {:?}). Available: {}",
-                 node_idx, current_node.player, target_action, target_amount,
-                 self.get_available_actions_at_node(node_idx));
-
-            // Find the matching child
-            let mut found_child: Option<usize> = None;
-            let mut best_amount_match: Option<(usize, f32)> = None; // (child_idx, amount_diff)
-
-            for i in 0..current_node.num_actions {
-                let child_idx = (current_node.children_start + i as u32) as usize;
-                let child = &self.tree.nodes[child_idx];
-
-                if let Some(child_action) = child.action_from_parent {
-                    log!("[get_strategy_for_history]   Child {}: {:?} amount={}",
-                         child_idx, child_action, child.amount_from_parent);
-
-                    if child_action == target_action {
-                        // For bet/raise, we may need to match amount
-                        if target_action == ActionType::Bet || target_action == ActionType::Raise {
-                            if let Some(target_amt) = target_amount {
-                                // Match by closest amount (with 10% tolerance for rounding)
-                                let diff = (child.amount_from_parent - target_amt).abs();
-                                let tolerance = target_amt * 0.15; // 15% tolerance
-
-                                log!("[get_strategy_for_history]     Bet/Raise match: child_amt={}, target_amt={}, diff={}, tolerance={}",
-                                     child.amount_from_parent, target_amt, diff, tolerance);
-
-                                if best_amount_match.is_none() || diff < best_amount_match.unwrap().1 {
-                                    best_amount_match = Some((child_idx, diff));
-                                }
-                            } else {
-                                // No amount specified - take first matching action type
-                                if found_child.is_none() {
-                                    found_child = Some(child_idx);
-                                }
-                            }
-                        } else {
-                            // Non-bet/raise action: exact match
-                            found_child = Some(child_idx);
-                            break;
-                        }
-                    }
-                }
-            }
-
-            // Use amount match if available for bet/raise
-            if found_child.is_none() {
-                if let Some((child_idx, diff)) = best_amount_match {
-                    log!("[get_strategy_for_history] Using best amount match: child {} with diff {}", child_idx, diff);
-                    found_child = Some(child_idx);
-                }
-            }
-
-            match found_child {
-                Some(child_idx) => {
-                    let child = &self.tree.nodes[child_idx];
-                    log!("[get_strategy_for_history] Found child at index {}, next player={}",
-                         child_idx, child.player);
-                    node_idx = child_idx;
-                }
-                None => {
-                    return Err(JsValue::from_str(&format!(
-                        "Action '{}' not found at node {} (player={}). Available actions: {}",
-                        action_str, node_idx, current_node.player, self.get_available_actions_at_node(node_idx)
-                    )));
-                }
-            }
-        }
-
-        // Now we're at the target node
-        let target_node = &self.tree.nodes[node_idx];
-        log!("[get_strategy_for_history] Reached target node {}. Player: {}, infoset_id: {}, num_actions: {}",
-             node_idx, target_node.player, target_node.infoset_id, target_node.num_actions);
-
-        // Check if this is a terminal node or has no infoset
-        if target_node.infoset_id == u32::MAX {
-            // Terminal node or opponent node without infoset
-            return Ok(json!({
-                "nodeIdx": node_idx,
-                "isTerminal": target_node.num_actions == 0,
-                "player": target_node.player,
-                "pot": target_node.pot,
-                "actions": [],
-                "strategy": null,
-                "message": "Node has no infoset (terminal or opponent's decision point)"
-            }).to_string());
-        }
-
-        // Get the available actions at this node
-        let actions = self.get_actions_at_node(node_idx);
-
-        // Return node info and infoset data
-        Ok(json!({
-            "nodeIdx": node_idx,
-            "isTerminal": false,
-            "player": target_node.player,
-            "pot": target_node.pot,
-            "infosetId": target_node.infoset_id,
-            "numActions": target_node.num_actions,
-            "actions": actions
-        }).to_string())
-    }
-
-    /// Get strategy for a specific hand at a specific node (reached via history).
-    /// hand_str: e.g., "As Kh"
-    /// node_idx: the node index (obtained from get_strategy_for_history)
-    #[wasm_bindgen]
-    pub fn get_hand_strategy_at_node(&self, hand_str: &str, node_idx: usize) -> Result<String, JsValue> {
-        // Parse hand
-        let cards: Vec<Card> = hand_str.split_whitespace()
-            .filter_map(|s| Card::from_str(s))
-            .collect();
-
-        if cards.len() != 2 {
-            return Err(JsValue::from_str("Hand must have 2 cards"));
-        }
-
-        // Get the node
-        if node_idx >= self.tree.nodes.len() {
-            return Err(JsValue::from_str("Invalid node index"));
-        }
-
-        let node = &self.tree.nodes[node_idx];
-
-        // Check if it's a terminal node
-        if node.num_actions == 0 {
-            return Err(JsValue::from_str("Cannot get strategy at terminal node"));
-        }
-
-        // Check if it has an infoset
-        if node.infoset_id == u32::MAX {
-            return Err(JsValue::from_str("Node has no infoset"));
-        }
-
-        // Determine which player acts at this node
-        let acting_player = node.player as usize;
-
-        // Find hand index in the acting player's range
-        let mut hand_idx = None;
-        for (i, h) in self.ranges[acting_player].iter().enumerate() {
-            if (h[0] == cards[0] && h[1] == cards[1]) || (h[0] == cards[1] && h[1] == cards[0]) {
-                hand_idx = Some(i);
-                break;
-            }
-        }
-
-        let hand_idx = hand_idx.ok_or_else(||
-            JsValue::from_str(&format!("Hand not found in player {}'s range", acting_player)))?;
-
-        // Get the strategy with correct number of actions
-        let strategy = self.trainer.get_average_strategy_with_actions(
-            node.infoset_id as usize,
-            hand_idx,
-            node.num_actions as usize
-        );
-
-        // Get action names
-        let actions = self.get_actions_at_node(node_idx);
-
-        // Filter strategy to match num_actions
-        let probs = &strategy[0..node.num_actions as usize];
-
-        Ok(json!({
-            "player": acting_player,
-            "handIdx": hand_idx,
-            "actions": actions,
-            "probs": probs
-        }).to_string())
-    }
-
-    /// Get actions at a specific node as JSON array
-    #[wasm_bindgen]
-    pub fn get_node_actions_at(&self, node_idx: usize) -> String {
-        if node_idx >= self.tree.nodes.len() {
-            return "[]".to_string();
-        }
-        serde_json::to_string(&self.get_actions_at_node(node_idx)).unwrap_or_else(|_| "[]".to_string())
-    }
-
-    // ========================================================================
-    // HELPER METHODS (not exposed to WASM)
-    // ========================================================================
-
-    /// Parse an action string like "check", "bet", "bet 75", "raise 150" into ActionType and optional amount
-    fn parse_action_string(action_str: &str) -> (ActionType, Option<f32>) {
-        let parts: Vec<&str> = action_str.trim().split_whitespace().collect();
-
-        if parts.is_empty() {
-            return (ActionType::Check, None); // Default fallback
-        }
-
-        let action_type = match parts[0].to_lowercase().as_str() {
-            "fold" => ActionType::Fold,
-            "check" => ActionType::Check,
-            "call" => ActionType::Call,
-            "bet" => ActionType::Bet,
-            "raise" => ActionType::Raise,
-            _ => ActionType::Check, // Default fallback
-        };
-
-        // Parse amount if present
-        let amount = if parts.len() > 1 {
-            parts[1].parse::<f32>().ok()
-        } else {
-            None
-        };
-
-        (action_type, amount)
-    }
-
-    /// Get available actions at a node as a comma-separated string (for error messages)
-    fn get_available_actions_at_node(&self, node_idx: usize) -> String {
-        let node = &self.tree.nodes[node_idx];
-        let mut actions = Vec::new();
-
-        for i in 0..node.num_actions {
-            let child_idx = (node.children_start + i as u32) as usize;
-            let child = &self.tree.nodes[child_idx];
-
-            if let Some(action_type) = child.action_from_parent {
-                let name = match action_type {
-                    ActionType::Fold => "fold".to_string(),
-                    ActionType::Check => "check".to_string(),
-                    ActionType::Call => "call".to_string(),
-                    ActionType::Bet => format!("bet {:.0}", child.amount_from_parent),
-                    ActionType::Raise => format!("raise {:.0}", child.amount_from_parent),
-                };
-                actions.push(name);
-            }
-        }
-
-        actions.join(", ")
-    }
-
-    /// Get actions at a node as a vector of JSON objects
-    fn get_actions_at_node(&self, node_idx: usize) -> Vec<serde_json::Value> {
-        let node = &self.tree.nodes[node_idx];
-        let mut actions = Vec::new();
-
-        for i in 0..node.num_actions {
-            let child_idx = (node.children_start + i as u32) as usize;
-            let child = &self.tree.nodes[child_idx];
-
-            if let Some(action_type) = child.action_from_parent {
-                let type_str = match action_type {
-                    ActionType::Fold => "fold",
-                    ActionType::Check => "check",
-                    ActionType::Call => "call",
-                    ActionType::Bet => "bet",
-                    ActionType::Raise => "raise",
-                };
-
-                actions.push(json!({
-                    "type": type_str,
-                    "amount": child.amount_from_parent
-                }));
-            }
-        }
-
-        actions
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_greet() {
-        let result = greet("World");
-        assert!(result.contains("Hello, World!"));
-    }
-
-    #[test]
-    fn test_version() {
-        let v = version();
-        assert!(!v.is_empty());
-    }
-
-    #[test]
-    fn test_evaluation_wasm() {
-        let score = test_evaluation("As Ks Qs Js Ts");
-        assert_eq!(score, 1, "Royal flush should be 1");
-    }
-
-    #[test]
-    fn test_hand_name() {
-        assert_eq!(get_hand_name(1), "Royal Flush");
-        assert_eq!(get_hand_name(5), "Straight Flush");
-        assert_eq!(get_hand_name(100), "Four of a Kind");
-        assert_eq!(get_hand_name(200), "Full House");
-    }
-}
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Poker primitives module
+pub mod poker;
+pub mod solver;
+pub mod parallel;
+pub mod platform;
+pub mod simd;
+pub mod solve;
+pub mod aggregate;
+pub mod batch;
+pub mod analysis;
+pub mod play;
+#[cfg(feature = "python")]
+pub mod python;
+
+// Re-export poker types and WASM functions
+pub use poker::Card;
+pub use poker::card::{parse_card, card_to_string, card_bitmask, card_rank, card_suit};
+pub use poker::evaluator::{evaluate_7_cards, evaluate_5_cards, evaluate_short_deck, get_hand_rank_name, init_lookup_tables, GameVariant};
+pub use poker::equity::{compute_equity_matrix, compute_equity_matrix_for, compute_equity_matrix_with_rivers, compute_single_equity, range_equities, total_equity};
+pub use poker::range::{format_range, hand_class, parse_range, DroppedCombo};
+
+use solver::{Abstraction, BetSize, GameConfig, NodeLock, ProfileRule, RaiseSize, StrategyProfile, RiverCards, build_river_tree, build_turn_tree, ConvergencePoint, DCFRTrainer, PlayerExploitability, GameTree, NodeType, SnapshotDelta, Solution};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use poker::equity::hand_mask;
+use poker::isomorphism;
+use solver::types::{ActionType, LineStep};
+use serde_json::json;
+use log::{debug, info, trace, warn};
+
+/// Initialize panic hook for better error messages in browser console.
+/// This should be called once at startup.
+#[cfg(feature = "wasm")]
+fn init_panic_hook() {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+    
+    // Always set the hook in debug builds
+    #[cfg(debug_assertions)]
+    console_error_panic_hook::set_once();
+}
+
+/// Initialize the Rust core module and lookup tables.
+/// Returns Ok(()) on success, or a JsValue error on failure.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn init() -> Result<(), JsValue> {
+    init_panic_hook();
+    platform::init_logger();
+    init_lookup_tables();
+    info!("🦀 Rust Core Initialized (with lookup tables)");
+    Ok(())
+}
+
+/// Like [`init`], but routes logging and timing through host callbacks:
+/// `log(message: string, level: string)` and `now() -> number` (milliseconds).
+/// Either may be omitted to keep the default console / `performance.now` lookup.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[wasm_bindgen]
+pub fn init_with_hooks(log: Option<js_sys::Function>, now: Option<js_sys::Function>) -> Result<(), JsValue> {
+    platform::set_hooks(log, now);
+    init()
+}
+
+/// Set the runtime log level: "off", "error", "warn", "info", "debug" or "trace".
+/// Defaults to "warn"; "trace" enables the per-iteration solver dumps.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn set_log_level(level: &str) -> Result<(), JsValue> {
+    let filter: log::LevelFilter = level.parse()
+        .map_err(|_| JsValue::from_str(&format!("Unknown log level '{}'", level)))?;
+    log::set_max_level(filter);
+    Ok(())
+}
+
+/// Start a Web Worker pool of `threads` for the equity matrix and
+/// best-response passes. Requires a `wasm-threads` build on a cross-origin
+/// isolated page (check [`threads_supported`] first); rejects otherwise.
+/// Work stays sequential until the promise resolves.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[wasm_bindgen]
+pub fn init_thread_pool(threads: usize) -> js_sys::Promise {
+    parallel::init_thread_pool(threads)
+}
+
+/// Whether this build and page can run worker threads.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn threads_supported() -> bool {
+    parallel::threads_supported()
+}
+
+/// Worker threads currently used by parallel passes (1 when sequential).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn thread_count() -> usize {
+    parallel::num_threads()
+}
+
+/// Whether this is the simd128 build (`npm run wasm:build:simd`).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn simd_enabled() -> bool {
+    simd::ENABLED
+}
+
+/// Simple greet function to verify the toolchain works.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn greet(name: &str) -> String {
+    format!("Hello, {}! Poker Solver Rust Core is ready.", name)
+}
+
+/// Returns the version of the Rust core module.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+// ============================================================================
+// WASM EXPORTS FOR EVALUATOR
+// ============================================================================
+
+/// Test hand evaluation from JS.
+/// Accepts a space-separated string of card codes (e.g., "As Kh Qd Jc Ts 2h 3c")
+/// Returns the hand score (lower = better, 1 = royal flush)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn test_evaluation(cards_str: &str) -> u16 {
+    let cards: Vec<Card> = cards_str
+        .split_whitespace()
+        .filter_map(Card::from_str)
+        .collect();
+    
+    if cards.len() < 5 {
+        return 7462; // Worst possible
+    }
+    
+    evaluate_7_cards(&cards)
+}
+
+/// Get the hand rank name from a score
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_hand_name(score: u16) -> String {
+    get_hand_rank_name(score).to_string()
+}
+
+/// Evaluate a single hand comparison on a board
+/// Returns 1.0 (win), 0.0 (loss), 0.5 (tie), or -1.0 (blocked/impossible)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn evaluate_matchup(board_str: &str, hand1_str: &str, hand2_str: &str) -> f32 {
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let hand1: Vec<Card> = hand1_str.split_whitespace().filter_map(Card::from_str).collect();
+    let hand2: Vec<Card> = hand2_str.split_whitespace().filter_map(Card::from_str).collect();
+    
+    compute_single_equity(&board, &hand1, &hand2).unwrap_or(-1.0)
+}
+
+/// Structured [`evaluate_matchup`]: `{equity, result, hand1_score,
+/// hand1_name, hand2_score, hand2_name}` with `result` one of
+/// `"win" | "lose" | "tie" | "blocked"` (see [`analysis::matchup_detail`]).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn matchup_detail(board_str: &str, hand1_str: &str, hand2_str: &str) -> Result<JsValue, JsValue> {
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let hand1: Vec<Card> = hand1_str.split_whitespace().filter_map(Card::from_str).collect();
+    let hand2: Vec<Card> = hand2_str.split_whitespace().filter_map(Card::from_str).collect();
+    let detail = analysis::matchup_detail(&board, &hand1, &hand2).map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&detail).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// A PioSolver or GTO+ range (see [`poker::range_import`]) as a range
+/// string for `SolverSession`: JSON `{ "format": "pio" | "gto_plus",
+/// "range", "combos" }`. `format` is detected when omitted.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn import_range(text: &str, format: Option<String>) -> Result<String, JsValue> {
+    let format = format
+        .map(|f| serde_json::from_value(json!(f)).map_err(|_| JsValue::from_str(&format!("Unknown range format '{}'", f))))
+        .transpose()?;
+    let combos = poker::range_import::import_range(text, format).map_err(|e| JsValue::from_str(&e))?;
+    let (hands, weights): (Vec<_>, Vec<_>) = combos.into_iter().unzip();
+    Ok(json!({
+        "format": format.unwrap_or_else(|| poker::range_import::RangeFormat::detect(text)),
+        "range": poker::range::format_weighted_range(&hands, &weights),
+        "combos": hands.len(),
+    }).to_string())
+}
+
+/// The canonical suits of a spot (see [`poker::canonical`]) as JSON
+/// `{ "board", "ranges": [range0, range1], "suits", "inverse" }`. `suits`
+/// maps each of the caller's suit letters to its canonical one and
+/// `inverse` maps back, e.g. for [`relabel_range`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn canonicalize_spot(board_str: &str, range0: &str, range1: &str) -> String {
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let ranges = [poker::range::parse_weighted_range(range0), poker::range::parse_weighted_range(range1)];
+    let spot = poker::canonical::canonicalize(&board, [&ranges[0], &ranges[1]]);
+    let format = |range: &[(Vec<Card>, f32)]| {
+        let (hands, weights): (Vec<_>, Vec<_>) = range.iter().cloned().unzip();
+        poker::range::format_weighted_range(&hands, &weights)
+    };
+    json!({
+        "board": spot.board.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" "),
+        "ranges": [format(&spot.ranges[0]), format(&spot.ranges[1])],
+        "suits": spot.map,
+        "inverse": spot.map.inverse(),
+    }).to_string()
+}
+
+/// A range with its suits relabelled by `suits_json`, a map such as
+/// `canonicalize_spot`'s `"inverse"`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn relabel_range(range: &str, suits_json: &str) -> Result<String, JsValue> {
+    let map: poker::canonical::SuitMap = serde_json::from_str(suits_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid suit map: {}", e)))?;
+    let (hands, weights): (Vec<_>, Vec<_>) = map.range(&poker::range::parse_weighted_range(range)).into_iter().unzip();
+    Ok(poker::range::format_weighted_range(&hands, &weights))
+}
+
+/// Range against range on a turn or river board: the aggregate split and
+/// each player's 13x13 class equity grid (see [`analysis::range_equity`]).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn range_equity(board_str: &str, range0: &str, range1: &str) -> Result<JsValue, JsValue> {
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let (range0, range1) = (parse_range(range0), parse_range(range1));
+    let equity = analysis::range_equity(&board, [&range0, &range1]).map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&equity).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Tournament equity of every player from JSON arrays of chip stacks and
+/// payouts for first, second, ... place (see [`solver::icm`]).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn icm_equities(stacks_json: &str, payouts_json: &str) -> Result<String, JsValue> {
+    let parse = |name: &str, text: &str| {
+        serde_json::from_str::<Vec<f32>>(text).map_err(|e| JsValue::from_str(&format!("Invalid {}: {}", name, e)))
+    };
+    let (stacks, payouts) = (parse("stacks", stacks_json)?, parse("payouts", payouts_json)?);
+    if stacks.len() > solver::types::IcmConfig::MAX_PLAYERS {
+        return Err(JsValue::from_str(&format!("ICM supports at most {} players", solver::types::IcmConfig::MAX_PLAYERS)));
+    }
+    Ok(json!(solver::icm::icm_equities(&stacks, &payouts)).to_string())
+}
+
+/// Stateless solve: JSON spec in, JSON result out (see [`solve::solve`]).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn solve(spec_json: &str) -> String {
+    solve::solve(spec_json)
+}
+
+/// Aggregate report over many boards: JSON spec in, JSON tables out (see
+/// [`aggregate::run_aggregate_report`]). `progress(done, total, board)` is
+/// called after each board is solved.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn run_aggregate_report(spec_json: &str, progress: Option<js_sys::Function>) -> String {
+    aggregate::run_aggregate_report(spec_json, |done, total, board| {
+        if let Some(f) = &progress {
+            let _ = f.call3(&JsValue::NULL, &JsValue::from(done as u32), &JsValue::from(total as u32), &JsValue::from_str(board));
+        }
+    })
+}
+
+/// Batch of boards solved one after the other: JSON spec in, JSON results
+/// keyed by board out (see [`batch::run_batch`]). `progress(done, total,
+/// board)` is called after each board is solved.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn solve_batch(spec_json: &str, progress: Option<js_sys::Function>) -> String {
+    batch::run_batch(spec_json, |done, total, board| {
+        if let Some(f) = &progress {
+            let _ = f.call3(&JsValue::NULL, &JsValue::from(done as u32), &JsValue::from(total as u32), &JsValue::from_str(board));
+        }
+    })
+}
+
+/// Canonical flops as JSON: `{"count", "total_weight", "flops": [{"board",
+/// "weight"}]}`. `subset` names a subset from
+/// [`poker::flops::FLOP_SUBSETS`]; all 1755 flops by default.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn get_canonical_flops(subset: Option<String>) -> Result<String, JsValue> {
+    let flops = poker::flops::flop_subset(subset.as_deref().unwrap_or("full")).map_err(|e| JsValue::from_str(&e))?;
+    let rows: Vec<serde_json::Value> = flops.iter()
+        .map(|(flop, weight)| serde_json::json!({ "board": poker::flops::flop_string(flop), "weight": weight }))
+        .collect();
+    Ok(serde_json::json!({
+        "count": rows.len(),
+        "total_weight": flops.iter().map(|(_, w)| w).sum::<f64>(),
+        "flops": rows,
+    }).to_string())
+}
+
+/// How each river of a turn board shifts range-vs-range equity and nut
+/// combos (see [`analysis::river_impact`]), largest swings first:
+/// `[{river, equity, equity_swing, improves, nut_combos, new_nut_combos}]`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn river_impact(board_str: &str, range0_str: &str, range1_str: &str) -> Result<String, JsValue> {
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let ranges = [parse_range(range0_str), parse_range(range1_str)];
+    let impacts = analysis::river_impact(&board, [&ranges[0], &ranges[1]]).map_err(|e| JsValue::from_str(&e))?;
+    serde_json::to_string(&impacts).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// All-in preflop equity of hand class `a` against class `b`, e.g.
+/// `preflop_class_equity("AKs", "QQ")` (see [`poker::preflop`]).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn preflop_class_equity(a: &str, b: &str) -> Result<f32, JsValue> {
+    poker::preflop::preflop_class_equity(a, b).map_err(|e| JsValue::from_str(&e))
+}
+
+/// Sampled all-in preflop equity of class `a` against class `b` with its
+/// uncertainty: `{mean, std_err, samples, ci95}`. `options_json` is
+/// `{samples, seed, target_half_width}`, all optional (see
+/// [`poker::McOptions`]).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn sample_preflop_class_equity(a: &str, b: &str, options_json: Option<String>) -> Result<String, JsValue> {
+    let options = mc_options(options_json.as_deref()).map_err(|e| JsValue::from_str(&e))?;
+    let estimate = poker::preflop::sample_class_equity(a, b, &options).map_err(|e| JsValue::from_str(&e))?;
+    serde_json::to_string(&estimate).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Monte Carlo equity of one range against another on a board of zero to
+/// five cards, as `{mean, std_err, samples, ci95}` (see
+/// [`poker::monte_carlo::sample_equity`]). With `target_half_width` among
+/// the options, sampling stops once the 95% interval is that narrow.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn monte_carlo_equity(board_str: &str, range0_str: &str, range1_str: &str, options_json: Option<String>) -> Result<String, JsValue> {
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let ranges = [parse_range(range0_str), parse_range(range1_str)];
+    let options = mc_options(options_json.as_deref()).map_err(|e| JsValue::from_str(&e))?;
+    let estimate = poker::monte_carlo::sample_equity(&board, [&ranges[0], &ranges[1]], &options).map_err(|e| JsValue::from_str(&e))?;
+    serde_json::to_string(&estimate).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Solve a heads-up preflop game (see [`solver::preflop`]) for
+/// `iterations` DCFR iterations and return its charts: every decision
+/// node with each hand class's strategy, and the exploitability in big
+/// blinds per hand.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn solve_preflop(config_json: &str, iterations: usize) -> Result<String, JsValue> {
+    let config: solver::PreflopConfig = serde_json::from_str(config_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid preflop config: {}", e)))?;
+    let mut preflop = solver::PreflopSolver::new(config).map_err(|e| JsValue::from_str(&e))?;
+    preflop.train(iterations);
+    serde_json::to_string(&preflop.charts()).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Solve a three-handed river spot (see [`solver::multiway`]) for
+/// `iterations` DCFR iterations and return every decision node with each
+/// hand's strategy, and the exploitability.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn solve_multiway(config_json: &str, board_str: &str, range0: &str, range1: &str, range2: &str, iterations: usize) -> Result<String, JsValue> {
+    let config: solver::MultiwayConfig = serde_json::from_str(config_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid multiway config: {}", e)))?;
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let mut multiway = solver::MultiwaySolver::new(config, &board, [range0, range1, range2]).map_err(|e| JsValue::from_str(&e))?;
+    multiway.train(iterations);
+    serde_json::to_string(&multiway.report()).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[cfg(feature = "wasm")]
+fn mc_options(json: Option<&str>) -> Result<poker::McOptions, String> {
+    match json {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(json).map_err(|e| format!("Invalid sampling options: {}", e)),
+        _ => Ok(poker::McOptions::default()),
+    }
+}
+
+/// Route hand evaluation through a two-plus-two table supplied as bytes
+/// (see [`poker::eval_table`]), e.g. a fetched `HandRanks.dat`.
+#[cfg(all(feature = "eval-table", feature = "wasm"))]
+#[wasm_bindgen]
+pub fn load_eval_table(bytes: &[u8]) -> Result<(), JsValue> {
+    poker::eval_table::load_eval_table(bytes).map_err(|e| JsValue::from_str(&e))
+}
+
+/// Number of combos of a range, counted at their weights, making a
+/// `category` hand (snake_case, e.g. `"two_pair"`) on the board, ignoring combos that hold a board card or
+/// one of `dead_str`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn count_made_hands(board_str: &str, range_str: &str, category: &str, dead_str: Option<String>) -> Result<f32, JsValue> {
+    let category = poker::HandRank::from_name(category).ok_or_else(|| JsValue::from_str(&format!("Unknown hand category '{}'", category)))?;
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let dead: Vec<Card> = dead_str.unwrap_or_default().split_whitespace().filter_map(Card::from_str).collect();
+    let (range, weights): (Vec<_>, Vec<_>) = poker::range::parse_weighted_range(range_str).into_iter().unzip();
+    Ok(poker::made_hands::count_made_hands(&board, &dead, &range, &weights, category))
+}
+
+/// Combo counts of a range by hand detail (see
+/// [`poker::made_hands::HandDetail`]), strongest first, as a JSON list of
+/// `{detail, combos}`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn count_hand_details(board_str: &str, range_str: &str, dead_str: Option<String>) -> String {
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    let dead: Vec<Card> = dead_str.unwrap_or_default().split_whitespace().filter_map(Card::from_str).collect();
+    let (range, weights): (Vec<_>, Vec<_>) = poker::range::parse_weighted_range(range_str).into_iter().unzip();
+    let counts = poker::made_hands::count_hand_details(&board, &dead, &range, &weights);
+    serde_json::Value::from(counts.into_iter()
+        .map(|(detail, combos)| serde_json::json!({ "detail": detail, "combos": combos }))
+        .collect::<Vec<_>>())
+        .to_string()
+}
+
+/// Stable cache key of a spot, e.g. for storing solutions in IndexedDB:
+/// takes the same inputs as the `SolverSession` constructor and ignores
+/// whitespace, card and combo order and the order of the sizes (see
+/// [`solver::hash::solve_key`]).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn compute_solve_key(config_json: &str, board_str: &str, range0_str: &str, range1_str: &str) -> Result<String, JsValue> {
+    let config: GameConfig = serde_json::from_str(config_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+    let board: Vec<Card> = board_str.split_whitespace().filter_map(Card::from_str).collect();
+    Ok(solver::hash::solve_key_str(&config, &board, [range0_str, range1_str]))
+}
+
+/// Preview of the tree `config_json` builds, without creating a session:
+/// the config is checked as the `SolverSession` constructor checks it, and
+/// the result is JSON `{ "nodes", "action_nodes", "chance_nodes",
+/// "terminal_nodes", "showdown_nodes", "infosets", "max_depth",
+/// "max_actions", "rivers", "actions": { "bet": n, ... }, "errors",
+/// "trainer_bytes" }`. `board_str` picks a turn tree when it has 4 cards;
+/// `trainer_bytes` is only projected when both range sizes are given.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn preview_tree(config_json: &str, board_str: Option<String>, hands0: Option<u32>, hands1: Option<u32>) -> Result<String, JsValue> {
+    let config: GameConfig = serde_json::from_str(config_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+    let board: Vec<Card> = board_str.as_deref().unwrap_or("").split_whitespace().filter_map(Card::from_str).collect();
+    let hands = hands0.zip(hands1).map(|(h0, h1)| [h0 as usize, h1 as usize]);
+    tree_preview(&config, &board, hands)
+        .map(|preview| preview.to_string())
+        .map_err(|e| JsValue::from_str(&e))
+}
+
+/// Native [`preview_tree`]: an empty `board` previews a river tree.
+pub fn tree_preview(config: &GameConfig, board: &[Card], hands: Option<[usize; 2]>) -> Result<serde_json::Value, String> {
+    if !matches!(board.len(), 0 | 4 | 5) {
+        return Err("Board must have 4 (turn) or 5 (river) cards".to_string());
+    }
+    let tree = checked_tree(config, board)?;
+    let stats = tree.stats();
+    let errors: Vec<String> = tree.validate().err().unwrap_or_default().iter().map(|e| e.to_string()).collect();
+    let trainer_bytes = hands.map(|hands| DCFRTrainer::projected_bytes(stats.infosets, stats.max_actions, hands));
+
+    let mut preview = serde_json::to_value(&stats).map_err(|e| e.to_string())?;
+    let fields = preview.as_object_mut().expect("TreeStats serializes to an object");
+    fields.insert("errors".to_string(), json!(errors));
+    fields.insert("trainer_bytes".to_string(), json!(trainer_bytes));
+    Ok(preview)
+}
+
+/// The tree of a session with `config` on `board` (a turn tree for 4
+/// cards), once the config passes the checks the builder relies on.
+fn checked_tree(config: &GameConfig, board: &[Card]) -> Result<GameTree, String> {
+    config.validate()?;
+    if config.chip_scale == 0 {
+        return Err("chip_scale must be at least 1".to_string());
+    }
+    if let RiverCards::Sampled { count: 0, .. } = config.river_cards {
+        return Err("river_cards must sample at least one river".to_string());
+    }
+    let tree = if board.len() == 4 { build_turn_tree(config, board) } else { build_river_tree(config) };
+    tree.map_err(|e| format!("Tree build failed: {}", e))
+}
+
+/// A history from JS: an array of entries (see `SolverSession::history_path`)
+/// or a single compact line such as `"x-b75"`.
+#[cfg(feature = "wasm")]
+fn history_from_js(history: JsValue) -> Result<Vec<String>, JsValue> {
+    if let Some(line) = history.as_string() {
+        return Ok(vec![line]);
+    }
+    serde_wasm_bindgen::from_value(history)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse history: {}", e)))
+}
+
+/// A history from a JSON array of entries, or a bare compact line.
+#[cfg(feature = "wasm")]
+fn history_from_json(history_json: &str) -> Result<Vec<String>, JsValue> {
+    if !history_json.trim_start().starts_with('[') {
+        return Ok(vec![history_json.to_string()]);
+    }
+    serde_json::from_str(history_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid history: {}", e)))
+}
+
+/// Build a test tree and return stats as JSON string, with any broken
+/// structural invariant listed in `errors` (see `GameTree::validate`).
+///
+/// Deprecated: its sizes and raise limit are fixed; `preview_tree` previews
+/// the tree of any config.
+///
+/// # Arguments
+/// * `initial_pot` - Pot size at start of river
+/// * `stack` - Effective stack size (for both players)
+#[deprecated(note = "use preview_tree")]
+pub fn test_tree_build(initial_pot: f32, stack: f32) -> String {
+    let config = GameConfig {
+        initial_pot,
+        stacks: [stack, stack],
+        bet_sizes: vec![BetSize::PotFraction(0.5), BetSize::PotFraction(1.0)], // 50% and 100% pot bets
+        raise_sizes: vec![RaiseSize::PotFraction(1.0)].into(), // 100% pot raises
+        river_bet_sizes: None,     // River sizes only matter from the turn
+        river_raise_sizes: None,
+        river_cards: RiverCards::All,
+        raise_limit: 3,            // Allow up to 3 raises
+        chip_scale: 100,           // Amounts exact to 0.01
+        icm: None,                 // Chip EV
+        bounty: None,              // No knockout bounties
+        abstraction: None,         // Solve every combo
+        suit_isomorphism: false,   // No combo merging
+        profiles: Vec::new(),      // No strategy constraints
+        size_overrides: Vec::new(), // Street sizes at every node
+        sizing_rules: Vec::new(),   // No contextual sizes
+        donk_sizes: None,          // Leads use the river sizes
+        player_sizes: Default::default(), // Shared sizes for both
+        add_allin: true,           // Shove at every bet and raise
+        variant: GameVariant::Holdem, // 52-card Hold'em
+    };
+
+    let tree = match build_river_tree(&config) {
+        Ok(tree) => tree,
+        Err(e) => return json!({ "errors": [e.to_string()], "config": config }).to_string(),
+    };
+
+    let errors: Vec<String> = tree.validate().err().unwrap_or_default().iter().map(|e| e.to_string()).collect();
+    let stats = json!({
+        "nodes": tree.nodes.len(),
+        "infosets": tree.infoset_map.len(),
+        "root_pot": tree.to_amount(tree.nodes[0].pot),
+        "errors": errors,
+        "config": config
+    });
+
+    stats.to_string()
+}
+
+/// JS export of [`test_tree_build`], kept under its old name.
+#[cfg(feature = "wasm")]
+#[allow(deprecated)]
+mod deprecated_exports {
+    use wasm_bindgen::prelude::*;
+
+    /// Build a test tree and return stats as JSON string.
+    ///
+    /// @deprecated Use `preview_tree`, which previews the tree of any config.
+    #[wasm_bindgen(js_name = test_tree_build)]
+    pub fn test_tree_build(initial_pot: f32, stack: f32) -> String {
+        super::test_tree_build(initial_pot, stack)
+    }
+}
+
+/// How far (as a fraction of the pot) a bet or raise may move and still
+/// take over the old solve's strategy in `rebuild_with_transfer`.
+const TRANSFER_TOLERANCE: f32 = 0.25;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct SolverSession {
+    config: GameConfig,
+    board: Vec<Card>,
+    tree: GameTree,
+    trainer: DCFRTrainer,
+    equity_matrix: Vec<f32>,
+    initial_reach: [Vec<f32>; 2],
+    ranges: [Vec<Vec<Card>>; 2],
+    /// Hand buckets the trainer plays instead of combos, if configured.
+    abstraction: Option<Abstraction>,
+    /// Exploitability measurements so far, oldest first.
+    convergence: Vec<ConvergencePoint>,
+    /// Combos of each range left out because they hold a board card.
+    dropped: [Vec<DroppedCombo>; 2],
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SolverSession {
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(constructor)]
+    pub fn new(config_json: &str, board_str: &str, range0_str: &str, range1_str: &str) -> Result<SolverSession, JsValue> {
+        debug!("[SolverSession::new] Init session...");
+
+        // 1. Parse Config
+        let config: GameConfig = serde_json::from_str(config_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+        debug!("[SolverSession::new] Config parsed: pot={}, stacks={:?}", config.initial_pot, config.stacks);
+
+        Self::from_config(config, board_str, range0_str, range1_str)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+    
+    pub fn step(&mut self, iterations: usize) {
+        match &self.abstraction {
+            Some(abstraction) => self.trainer.train_weighted(
+                &self.tree, &abstraction.equity_matrix, Some(&abstraction.matchup_weights), iterations, &abstraction.reach,
+            ),
+            None => self.trainer.train(&self.tree, &self.equity_matrix, iterations, &self.initial_reach),
+        }
+    }
+
+    /// `step(iterations)` in chunks of `every`, calling `progress(stats)`
+    /// after each with the `get_stats` JSON, so a long solve can drive a
+    /// progress bar. An exception thrown by `progress` is ignored.
+    #[cfg(feature = "wasm")]
+    pub fn step_with_progress(&mut self, iterations: usize, every: usize, progress: js_sys::Function) {
+        self.step_with(iterations, every, |session| {
+            let _ = progress.call1(&JsValue::NULL, &JsValue::from_str(&session.get_stats()));
+        });
+    }
+
+    /// Run iterations until `budget_ms` milliseconds have elapsed (at least one).
+    /// Returns the number of iterations performed.
+    pub fn step_for_ms(&mut self, budget_ms: f64) -> usize {
+        let start = platform::now_ms();
+        let mut done = 0;
+        loop {
+            self.step(1);
+            done += 1;
+            if platform::now_ms() - start >= budget_ms {
+                return done;
+            }
+        }
+    }
+    
+    /// Size and progress of the solve as JSON `{ "iterations", "nodes",
+    /// "infosets", "hands", "dropped", "exploitability", "regret",
+    /// "regret_pct", "memory" }`. `exploitability` is the latest recorded
+    /// measurement (or null); `regret` is the cheap convergence measure of
+    /// [`SolverSession::average_regret`], in chips per matchup and percent
+    /// of the starting pot, updated on every call; `memory` is
+    /// [`SolverSession::memory_usage`] in bytes.
+    pub fn get_stats(&self) -> String {
+        let regret = self.average_regret();
+        json!({
+            "iterations": self.trainer.iterations,
+            "nodes": self.tree.nodes.len(),
+            "infosets": self.tree.infoset_map.len(),
+            "hands": [self.ranges[0].len(), self.ranges[1].len()],
+            "dropped": [self.dropped[0].len(), self.dropped[1].len()],
+            "exploitability": self.convergence.last(),
+            "regret": regret,
+            "regret_pct": regret / self.initial_pot() * 100.0,
+            "memory": self.memory_usage()
+        }).to_string()
+    }
+
+    /// `player`'s combos as JSON `["As Ad", ...]`, in the order every
+    /// hand-index API uses.
+    #[cfg(feature = "wasm")]
+    pub fn get_combos(&self, player: usize) -> Result<String, JsValue> {
+        let range = self.ranges.get(player).ok_or_else(|| JsValue::from_str("Player must be 0 or 1"))?;
+        let combos: Vec<String> = range.iter().map(|hand| format_range(std::slice::from_ref(hand))).collect();
+        Ok(json!(combos).to_string())
+    }
+
+    /// Combos removed from `player`'s range because they share a card with
+    /// the board, as JSON `[{ "hand", "reason" }]`.
+    #[cfg(feature = "wasm")]
+    pub fn get_dropped_combos(&self, player: usize) -> Result<String, JsValue> {
+        self.dropped_combos(player)
+            .map(|dropped| json!(dropped).to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Measure exploitability now and add it to the convergence history.
+    /// Returns the measurement as JSON: `{ "iteration", "exploitability",
+    /// "exploitability_pct", "gain": [p0, p1], "gain_pct": [p0, p1] }`,
+    /// where `gain` is what each player would win per matchup by switching
+    /// to a best response (chips, and percent of the starting pot).
+    /// `get_stats` reports the latest measurement.
+    pub fn check_exploitability(&mut self) -> String {
+        serde_json::to_string(&self.record_exploitability()).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Measure exploitability without recording it, per player: JSON `{
+    /// "iteration", "exploitability", "exploitability_pct", "players": [{
+    /// "player", "best_response", "average", "gain", "gain_pct" }] }`, where
+    /// `best_response` and `average` are the player's value per matchup
+    /// best-responding and playing the average strategy, in chips.
+    pub fn get_exploitability(&self) -> String {
+        let values = self.player_exploitability();
+        let point = ConvergencePoint::new(self.iterations(), &values, self.initial_pot());
+        let players: Vec<serde_json::Value> = (0..2)
+            .map(|p| json!({
+                "player": p,
+                "best_response": values.best_response[p],
+                "average": values.average[p],
+                "gain": point.gain[p],
+                "gain_pct": point.gain_pct[p],
+            }))
+            .collect();
+        json!({
+            "iteration": point.iteration,
+            "exploitability": point.exploitability,
+            "exploitability_pct": point.exploitability_pct,
+            "players": players,
+        }).to_string()
+    }
+
+    /// Turn the trainer's invariant checks on (clearing earlier findings)
+    /// or off: while on, each iteration checks that utilities are finite,
+    /// that strategies sum to 1 and that the root is zero sum. Slow; meant
+    /// for debugging.
+    pub fn set_invariant_checks(&mut self, enabled: bool) {
+        self.trainer.check_invariants(enabled);
+    }
+
+    /// Findings of the invariant checks as JSON `{ "iterations", "total",
+    /// "violations": [{ "iteration", "node", "kind", "detail" }] }`, or
+    /// `null` while checks are off.
+    pub fn get_invariant_report(&self) -> String {
+        json!(self.invariant_report()).to_string()
+    }
+
+    /// Turn performance counters on (from zero) or off. Cheap enough to
+    /// leave on while diagnosing a slow solve.
+    pub fn set_perf_counters(&mut self, enabled: bool) {
+        self.trainer.count_perf(enabled);
+    }
+
+    /// Performance counters as JSON `{ "iterations", "nodes_visited",
+    /// "terminal_evaluations", "estimated_allocations", "traversal_ms",
+    /// "discount_ms", "iterations_per_second" }` (throughput over the last
+    /// 50 iterations), or `null` while counting is off.
+    /// `estimated_allocations` comes from a per-node model of the
+    /// traversal, not from the allocator.
+    pub fn get_perf(&self) -> String {
+        json!(self.perf_counters()).to_string()
+    }
+
+    /// Every measurement from `check_exploitability`, oldest first.
+    pub fn get_convergence_history(&self) -> String {
+        serde_json::to_string(&self.convergence).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Solution file bytes (config, board, ranges and solver state); the same
+    /// format native `load_solution` reads.
+    pub fn export_solution(&self) -> Vec<u8> {
+        self.to_solution().to_bytes()
+    }
+
+    /// The whole solve as one JSON document for browsing offline (see
+    /// `strategy_tree`). Unlike `export_solution` it holds average
+    /// strategies rather than solver state, so it cannot be imported back.
+    pub fn export_strategy_tree(&self) -> String {
+        self.strategy_tree().to_string()
+    }
+
+    /// Browse-only solution file bytes: the average strategies without the
+    /// regrets, a fraction of the size of `export_solution`. Importing them
+    /// (or native `load_solution`) shows the same strategies; solving on from
+    /// them starts the regrets over.
+    pub fn export_browse_solution(&self) -> Vec<u8> {
+        self.to_browse_solution().to_bytes()
+    }
+
+    /// Restore solver state from `export_solution` bytes of the same spot.
+    #[cfg(feature = "wasm")]
+    pub fn import_solution(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        Solution::from_bytes(bytes)
+            .and_then(|solution| self.restore_solution(&solution))
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Compact diff of the solver state: the infosets written after
+    /// `since_iteration` (the iteration of the last checkpoint or delta
+    /// persisted). Replaying deltas in order over an `export_solution`
+    /// checkpoint with `apply_snapshot_delta` restores the solve.
+    pub fn snapshot_delta(&self, since_iteration: usize) -> Vec<u8> {
+        self.to_snapshot_delta(since_iteration).to_bytes()
+    }
+
+    /// Apply a `snapshot_delta` taken from this spot. Fails if the delta starts
+    /// after this session's iteration (a delta in between is missing).
+    #[cfg(feature = "wasm")]
+    pub fn apply_snapshot_delta(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        SnapshotDelta::from_bytes(bytes)
+            .and_then(|delta| self.restore_snapshot_delta(&delta))
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn get_strategy_ptr(&self) -> *const f32 {
+        let ptr = self.trainer.strategy_sum.as_ptr();
+        let len = self.trainer.strategy_sum.len();
+
+        if log::log_enabled!(log::Level::Trace) {
+            trace!("[get_strategy_ptr] Returning ptr: {:p}, len: {}", ptr, len);
+
+            // Print first 3 floats to prove Rust has data
+            if len >= 3 {
+                trace!("[get_strategy_ptr] First 3 floats: [{:.6}, {:.6}, {:.6}]",
+                       self.trainer.strategy_sum[0],
+                       self.trainer.strategy_sum[1],
+                       self.trainer.strategy_sum[2]);
+            }
+
+            // Also check if any values are non-zero
+            let non_zero_count = self.trainer.strategy_sum.iter().filter(|&&x| x != 0.0).count();
+            trace!("[get_strategy_ptr] Non-zero values: {} / {}", non_zero_count, len);
+        }
+
+        ptr
+    }
+
+    pub fn get_strategy_len(&self) -> usize {
+        self.trainer.strategy_sum.len()
+    }
+
+    pub fn get_num_actions(&self) -> usize {
+        self.trainer.max_actions()
+    }
+    
+    /// Get available actions at the root node as JSON.
+    /// Returns [{ "type": "check", "amount": 0 }, { "type": "bet", "amount": 75 }, ...]
+    /// This is used by the UI to display action buttons with correct amounts.
+    pub fn get_node_actions(&self) -> String {
+        let node_id: u32 = 0; // Root node
+        let node = &self.tree.nodes[node_id as usize];
+
+        let mut actions = Vec::new();
+
+        for i in 0..node.num_actions {
+            let child_id = node.children_start + i as u32;
+            let child = &self.tree.nodes[child_id as usize];
+
+            if let Some(action_type) = child.action_from_parent {
+                actions.push(json!({
+                    "type": action_type.as_str(),
+                    "amount": self.tree.to_amount(child.amount_from_parent)
+                }));
+            }
+        }
+
+        serde_json::to_string(&actions).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Get strategy for a specific hand (e.g., "As Kh") as JSON.
+    /// Returns { "node": 0, "player": 0, "actions": ["check", "bet 75.0"], "probs": [0.5, 0.5], "evs": [12.5, 14.0] }
+    /// where `evs` is each action's EV for the hand (see `hand_action_evs`),
+    /// `null` when no opposing combo reaches the node.
+    ///
+    /// `history_actions_js` (e.g. ["bet 75"]) picks the decision node; the
+    /// hand is then looked up in the acting player's range. Without it, a
+    /// hand in P0's range gets the root and a hand in P1's range the node
+    /// after P0 checks.
+    #[cfg(feature = "wasm")]
+    pub fn get_hand_strategy(&self, hand_str: &str, history_actions_js: Option<js_sys::Array>) -> Result<String, JsValue> {
+        let history: Option<Vec<String>> = history_actions_js
+            .map(|history| serde_wasm_bindgen::from_value(history.into()))
+            .transpose()
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse history: {}", e)))?;
+
+        self.hand_strategy(hand_str, history.as_deref())
+            .map(|strategy| strategy.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Get the node index for a given action history.
+    /// history_actions_js should be a JS array of action strings, e.g., ["check", "bet 75"].
+    /// On turn sessions the river is part of the history: ["check", "check", "river Kd", "bet 75"].
+    /// A compact line string works too: "x-b75", "x-x|Kd-b75".
+    /// Returns the node index, or an error if the path is invalid.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn get_strategy_for_history(&self, history_actions_js: JsValue) -> Result<String, JsValue> {
+        let history = history_from_js(history_actions_js)?;
+
+        self.strategy_for_history(&history)
+            .map(|info| info.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Root strategy of every river dealt at the chance node reached by
+    /// `history_actions_js` (e.g. ["check", "check"]) on a turn session, as
+    /// `{ "node", "rivers": [{ "card", "node", "actions", "frequencies" }] }`.
+    /// Frequencies are weighted by how often each hand reaches the river.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn get_river_strategies(&self, history_actions_js: JsValue) -> Result<String, JsValue> {
+        let history = history_from_js(history_actions_js)?;
+
+        self.river_strategies(&history)
+            .map(|report| report.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Runout report of the chance node reached by `history_actions_js`
+    /// (e.g. ["check", "check"]) on a turn session: how each river shifts
+    /// the strategy and the players' values, like `get_river_strategies`
+    /// with EVs. JSON `{ "node", "player", "actions", "average": { "ev",
+    /// "frequencies" }, "rivers": [{ "card", "node", "ev", "ev_shift",
+    /// "frequencies", "frequency_shift" }] }`. `ev` is each player's value
+    /// in chips per matchup from the start of the hand (payout units with
+    /// ICM), `frequencies` the range-weighted root strategy of the river
+    /// (empty on all-in runouts), and the shifts are against the average,
+    /// which weighs each river by how often it comes with the ranges that
+    /// reach it.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn get_runout_report(&self, history_actions_js: JsValue) -> Result<String, JsValue> {
+        let history = history_from_js(history_actions_js)?;
+        self.runout_report(&history)
+            .map(|report| report.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Each range's overall equity on the board as JSON `[p0, p1]`: every
+    /// matchup of two combos that share no card, weighted by both range
+    /// weights. Turn sessions pool every dealt river.
+    pub fn get_total_equity(&self) -> String {
+        json!(self.total_equity()).to_string()
+    }
+
+    /// Range advantage report as JSON: for each player the share of their
+    /// range in each equity bucket (0-25/25-50/50-75/75-100%), their mean
+    /// equity and nut share (combos above 90% equity), plus the differences
+    /// (player 0 minus player 1). See `analysis::RangeAdvantage`.
+    pub fn get_range_advantage(&self) -> String {
+        serde_json::to_string(&self.range_advantage()).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Both ranges split into `num_buckets` equal-width buckets of equity
+    /// against the other range, as JSON `{ "players": [[{ "low", "high",
+    /// "share", "hands" }]] }`: `share` is the bucket's part of the range's
+    /// weight and `hands` its combos. Turn sessions pool every dealt river.
+    #[cfg(feature = "wasm")]
+    pub fn get_equity_distribution(&self, num_buckets: usize) -> Result<String, JsValue> {
+        self.equity_distribution(num_buckets)
+            .map(|distribution| distribution.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Bluff suitability of each hand at the acting player's node `node_idx`:
+    /// how much holding it shrinks the opponent's continuing range after the
+    /// node's first bet or raise, compared with their folding range. JSON
+    /// `{ "node", "response_node", "player", "baseline_continue",
+    ///   "combos": [{ "hand", "continue", "score" }] }`, highest score first,
+    /// where `score = baseline_continue - continue`.
+    #[cfg(feature = "wasm")]
+    pub fn get_blocker_scores(&self, node_idx: usize) -> Result<String, JsValue> {
+        self.blocker_scores(node_idx)
+            .map(|report| report.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Value/bluff make-up of every bet and raise at the acting player's node
+    /// `node_idx`, next to the bluff ratio that makes a bluff catcher
+    /// indifferent to the size. `thresholds_json` optionally overrides the
+    /// defaults, e.g. `{"value_equity": 0.6, "bluff_equity": 0.3,
+    /// "top_combos": 5}`. See `analysis::PolarizationReport`.
+    #[cfg(feature = "wasm")]
+    pub fn get_polarization_report(&self, node_idx: usize, thresholds_json: Option<String>) -> Result<String, JsValue> {
+        let thresholds = match thresholds_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| JsValue::from_str(&format!("Invalid thresholds: {}", e)))?,
+            None => analysis::PolarizationThresholds::default(),
+        };
+        let report = self.polarization_report(node_idx, &thresholds).map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string(&report).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// What-if report for the acting player at `node_idx` when the opponent
+    /// deviates: their value there with their own average strategy and
+    /// with a best response, before and after the shifts. JSON input, e.g.
+    /// `[{"node": 2, "from": "call", "to": "fold", "fraction": 0.1}]` moves
+    /// 10% of every hand's calls at node 2 to folds. See
+    /// `analysis::ExploitReport`.
+    #[cfg(feature = "wasm")]
+    pub fn simulate_exploit(&self, node_idx: usize, perturbation_json: &str) -> Result<String, JsValue> {
+        let shifts: Vec<analysis::StrategyShift> = serde_json::from_str(perturbation_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid perturbation: {}", e)))?;
+        let report = self.exploit_simulation(node_idx, shifts).map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string(&report).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// A new, unsolved session for the subtree at `node_idx`: the pot and
+    /// stacks there, and both ranges weighted by how often each hand reaches
+    /// the node. `new_config_json` optionally overrides config fields such as
+    /// `bet_sizes` or `raise_limit` (e.g. finer sizes for the re-solve); the
+    /// pot and stacks always come from the node. Only nodes where a betting
+    /// round starts can be re-solved: the root, or a river on a turn session.
+    #[cfg(feature = "wasm")]
+    pub fn resolve_subtree(&self, node_idx: usize, new_config_json: Option<String>) -> Result<SolverSession, JsValue> {
+        let overrides = match new_config_json {
+            Some(json) => Some(serde_json::from_str(&json).map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?),
+            None => None,
+        };
+        self.subtree_session(node_idx, overrides).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// `player`'s range after the line `history_json` (a JSON array of
+    /// action strings as for `get_strategy_for_history`, or a compact line
+    /// such as `x-b75`), weighting each
+    /// combo by how often it takes that line. JSON `{ "node", "player",
+    /// "total", "combos": [{ "hand", "class", "reach", "weight" }],
+    /// "classes": [{ "class", "combos", "weight" }] }`: `reach` is the share
+    /// of the combo that takes the line, `weight` its share of the filtered
+    /// range and `total` the share of the whole starting range that is left.
+    /// A line nobody takes leaves `total` and every weight at 0.
+    #[cfg(feature = "wasm")]
+    pub fn get_filtered_range(&self, player: usize, history_json: &str) -> Result<String, JsValue> {
+        let history = history_from_json(history_json)?;
+        self.filtered_range(player, &history)
+            .map(|range| range.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Strategy of the player acting after `history_json` (as for
+    /// `get_filtered_range`) as a 13x13 hand grid, aces first, pairs on the
+    /// diagonal, suited hands above it and offsuit below. JSON `{ "node",
+    /// "player", "actions", "frequencies", "grid": [[{ "class", "combos",
+    /// "weight", "frequencies" } | null]] }`: each cell averages its combos'
+    /// action frequencies weighted by how often they reach the node,
+    /// `weight` is the cell's share of the range there and `frequencies`
+    /// at the top level are the whole range's. Cells with no live combo are
+    /// `null`; cells no combo reaches have `null` frequencies.
+    #[cfg(feature = "wasm")]
+    pub fn get_strategy_grid(&self, history_json: &str) -> Result<String, JsValue> {
+        let history = history_from_json(history_json)?;
+        self.strategy_grid(&history)
+            .map(|grid| grid.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Hand buckets of an abstracted solve (config `"abstraction": {"buckets": 20}`,
+    /// with optional `"turn"` and `"river"` counts for spots starting on that
+    /// street, or `"suit_isomorphism": true` on a board with a suit symmetry) as JSON
+    /// `{ "enabled", "method": "buckets" | "suit_isomorphism", "num_buckets":
+    /// [p0, p1], "players": [[{ "bucket", "weight", "min_equity",
+    /// "max_equity", "hands": [...] }]] }`. Strategy queries for a combo
+    /// return its bucket's strategy; the raw buffer from `get_strategy_ptr`
+    /// is laid out per bucket. Without an abstraction `enabled` is false and
+    /// every combo is solved on its own.
+    pub fn get_abstraction_info(&self) -> String {
+        self.abstraction_info().to_string()
+    }
+
+    /// Hold `player` to a profile for the rest of the solve, replacing any
+    /// earlier profile of theirs. `profile_json` is an array of rules:
+    /// `{"rule": "no_bluff", "max_equity": 0.5}` (combos below that equity
+    /// never bet or raise), `{"rule": "always_bet", "frequency": 0.75,
+    /// "size": 0.75}` (wherever the player can open the betting, the
+    /// strongest 75% of the range bets, with the size closest to 75% pot if
+    /// given) and `{"rule": "check_only"}`. Equities are against the
+    /// opponent's starting range. Apply profiles before stepping: strategy
+    /// accumulated earlier stays in the averages. Returns the generated
+    /// locks as `{ "player", "rules", "locks": [{ "node", "rule",
+    /// "actions", "hands" }] }`, where `actions` are the ones still allowed.
+    #[cfg(feature = "wasm")]
+    pub fn apply_profile(&mut self, player: usize, profile_json: &str) -> Result<String, JsValue> {
+        let rules: Vec<ProfileRule> = serde_json::from_str(profile_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid profile: {}", e)))?;
+        self.apply_profile_rules(player, rules)
+            .map(|report| report.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Let the solver pick the bet sizes: a coarse solve of a tree with a
+    /// dense size grid, then, at every node offering several bets or raises,
+    /// only the one or two carrying the betting frequency are kept and the
+    /// slim tree is solved from the coarse solution. The session is replaced
+    /// by the final solve. `options_json` may set `sizes` (pot fractions,
+    /// default 25/50/75/100/150%; the all-in is always offered),
+    /// `coarse_iterations` (200), `iterations` (1000, in total),
+    /// `max_sizes` (2) and `coverage` (0.9: sizes are kept until they hold
+    /// 90% of a node's betting). Returns `{ "dense_nodes", "nodes",
+    /// "iterations", "exploitability_pct", "ev", "kept": [{ "line",
+    /// "sizes": [{ "amount", "frequency", "kept" }] }] }`, where `ev` is
+    /// each player's value in chips and `line` the history to the node.
+    #[cfg(feature = "wasm")]
+    pub fn auto_size_solve(&mut self, options_json: &str) -> Result<String, JsValue> {
+        let options: analysis::AutoSizeOptions = serde_json::from_str(options_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid options: {}", e)))?;
+        self.auto_size(&options)
+            .map(|report| report.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Replace the session with one built from `new_config_json` (same
+    /// board and ranges), seeded with the current solve so a small change,
+    /// such as one bet size moving from 75% to 66% of the pot, converges in
+    /// far fewer iterations than a cold start. Old and new trees are
+    /// aligned by betting line, with bets and raises matched to the nearest
+    /// old amount within a quarter of the pot; matched infosets keep their
+    /// strategy sums, and their regrets too when `keep_regrets` is true (the
+    /// default), unmatched ones start uniform. The iteration count carries
+    /// over. Returns `{ "nodes", "matched_nodes", "infosets",
+    /// "matched_infosets", "match_rate", "iterations", "regrets" }`.
+    #[cfg(feature = "wasm")]
+    pub fn rebuild_with_transfer(&mut self, new_config_json: &str, keep_regrets: Option<bool>) -> Result<String, JsValue> {
+        let config: GameConfig = serde_json::from_str(new_config_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+        self.transfer_to(config, keep_regrets.unwrap_or(true))
+            .map(|report| report.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Split a combo's EV into the lines it can take from the root: for each
+    /// action (or dealt river), the probability of the line given the combo
+    /// times the EV given the line. The contributions of a node's lines sum
+    /// to the node's own contribution, and the root's is the combo's EV.
+    /// `player` picks the range when the combo is in both (default: the
+    /// first range holding it); lines are expanded `max_depth` levels deep
+    /// (default 1). Returns `{ "hand", "player", "ev", "lines": [{
+    /// "action", "node", "probability", "ev", "contribution", "lines" }] }`
+    /// with EVs in chips.
+    #[cfg(feature = "wasm")]
+    pub fn get_ev_decomposition(&self, hand_str: &str, player: Option<usize>, max_depth: Option<usize>) -> Result<String, JsValue> {
+        self.ev_decomposition(hand_str, player, max_depth.unwrap_or(1))
+            .map(|report| report.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Every terminal and showdown node with what it contributes to the
+    /// players' EVs: `{ "ev": [p0, p1], "probability", "leaves": [{ "node",
+    /// "line", "type": "fold" | "showdown", "winner" (folds only), "pot",
+    /// "probability", "ev": [p0, p1] }] }`. A leaf's probability is the
+    /// chance of reaching it over all hand pairs (range weights times both
+    /// average strategies) and its EVs are the chips each player expects to
+    /// win there, in the units of the root EVs; both sum over the leaves
+    /// to the totals at the top, the players' EVs at the root.
+    pub fn get_leaf_ev_map(&self) -> String {
+        self.leaf_ev_map().to_string()
+    }
+
+    /// Each combo's EV at `node_idx` split by how its hands end: `fold_ev`
+    /// from the pots it takes when the opponent folds and `showdown_ev`
+    /// from the pots it wins or chops at showdown (on any later street),
+    /// both players following their average strategies. EVs are the
+    /// expected share of the final pot in chips, so losing costs nothing
+    /// and a bluff's value is all fold equity. Returns `{ "node", "pot",
+    /// "players": [{ "player", "combos": [{ "hand", "fold_ev",
+    /// "showdown_ev", "total" }] }] }` for the combos reaching the node.
+    #[cfg(feature = "wasm")]
+    pub fn get_ev_components(&self, node_idx: usize) -> Result<String, JsValue> {
+        self.ev_components(node_idx)
+            .map(|report| report.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Equity realization of every combo at the root: equity against the
+    /// opposing range, root EV, and EV as a share of the equity's pot
+    /// share, with badly under-realizing combos flagged. Returns
+    /// `[{player, realization, combos: [{hand, weight, equity, ev,
+    /// realization, under_realizes}]}]` as JSON.
+    #[cfg(feature = "wasm")]
+    pub fn get_equity_realization(&self) -> Result<String, JsValue> {
+        let players = self.equity_realization().map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string(&players).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Opposing combos that `hand_str` beats, loses to and chops with on
+    /// the board at `node_idx`, weighted by the opponent's reach there.
+    /// The hand is looked up in the acting player's range first. Each list
+    /// is sorted by weight and cut to `max_len` entries (50 by default);
+    /// the totals cover every combo. Returns `{node, player, hand, wins,
+    /// loses, chops: [{hand, weight}], totals: {wins, loses, chops},
+    /// counts, blocked}` as JSON.
+    #[cfg(feature = "wasm")]
+    pub fn get_matchup_lists(&self, node_idx: usize, hand_str: &str, max_len: Option<usize>) -> Result<String, JsValue> {
+        let lists = self.matchup_lists(node_idx, hand_str, max_len.unwrap_or(50)).map_err(|e| JsValue::from_str(&e))?;
+        Ok(lists.to_string())
+    }
+
+    /// Sample an action for a hand at a decision node from the solver's
+    /// mixed strategy, for play and training modes: the same `seed` always
+    /// gives the same action. Returns `{node, player, hand, action:
+    /// {type, amount, child, prob}, distribution: [...]}` as JSON, the
+    /// distribution listing every action for display once the user has
+    /// answered. Fails for hands outside the acting range or blocked by
+    /// the board.
+    #[cfg(feature = "wasm")]
+    pub fn sample_action(&self, node_idx: usize, hand_str: &str, seed: u64) -> Result<String, JsValue> {
+        let sample = self.draw_action(node_idx, hand_str, seed).map_err(|e| JsValue::from_str(&e))?;
+        Ok(sample.to_string())
+    }
+
+    /// HUD hint for one combo after the line `history_json` (a JSON array
+    /// of action strings as for `get_strategy_for_history`, or a compact
+    /// line): every legal
+    /// action with the solver's frequency and its EV for this combo, and a
+    /// one-line recommendation. JSON `{node, player, hand, pot, ev,
+    /// actions: [{action, frequency, ev}], best, ev_gap, close,
+    /// recommendation}`; `close` marks an EV gap within 2% of the pot.
+    /// Fails when no one is to act there or the hand is not the acting
+    /// player's.
+    #[cfg(feature = "wasm")]
+    pub fn get_hint(&self, hand_str: &str, history_json: &str) -> Result<String, JsValue> {
+        let history = history_from_json(history_json)?;
+        let hint = self.hint(hand_str, &history).map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string(&hint).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Coaching report on a user strategy for `player`. `strategy_json` is
+    /// a JSON array of `{line, hands}`: the history reaching one of the
+    /// player's decisions and, keyed by combo (`"Kc Kd"`), class (`"KK"`)
+    /// or `"*"`, the frequency of each action (`{"fold": 0.7, "call":
+    /// 0.3}`). `options_json` holds `{top, min_loss}`, `{}` for defaults.
+    /// Each decision is graded against the solver's strategy with the
+    /// solver playing the rest of the game; JSON `{player, total_loss,
+    /// lines: [{node, line, loss}], classes: [{class, loss}], leaks:
+    /// [{node, line, action, classes, loss, description}]}`, losses in
+    /// chips per hand dealt and costliest first.
+    #[cfg(feature = "wasm")]
+    pub fn leak_report(&self, player: usize, strategy_json: &str, options_json: &str) -> Result<String, JsValue> {
+        let strategy: Vec<analysis::UserNode> = serde_json::from_str(strategy_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid strategy: {}", e)))?;
+        let options: analysis::LeakOptions = serde_json::from_str(options_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid options: {}", e)))?;
+        let report = self.leaks(player, &strategy, &options).map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string(&report).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Range explorer: the acting player's combos at `node_idx` that match
+    /// a filter, with their data. `query_json` is `{filter, sort,
+    /// ascending, limit}`, every field optional. Filters nest: `{"all":
+    /// [...]}`, `{"any": [...]}`, `{"not": {...}}`, `{"frequency":
+    /// {"action": "bet", "min": 0.7}}` (a bare `"bet"` sums every size),
+    /// `{"ev": {"min", "max"}}`, `{"reach": {...}}`, `{"blocker": {...}}`,
+    /// `{"category": "flush"}` (a hand category or detail such as
+    /// `"top_pair"`) and `{"holds": "As"}`. `sort` is `"hand"` (range
+    /// order), `"reach"`, `"ev"`, `"blocker"` or `{"frequency": "check"}`,
+    /// highest first. JSON `{node, player, actions, combos, hands: [{hand,
+    /// class, category, detail, reach, frequencies, ev, blocker}]}`, `ev`
+    /// in chips per opposing combo and `blocker` as in
+    /// `get_blocker_scores`.
+    #[cfg(feature = "wasm")]
+    pub fn query_hands(&self, node_idx: usize, query_json: &str) -> Result<String, JsValue> {
+        let query: analysis::HandQuery = serde_json::from_str(query_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid query: {}", e)))?;
+        let result = self.hand_query(node_idx, &query).map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string(&result).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Where the solution mixes: every decision node somebody reaches,
+    /// sorted by the acting player's average strategy entropy in bits
+    /// (weighted by how often each combo gets there), most mixed first.
+    /// JSON `{entropy, nodes: [{node, line, player, actions, reach,
+    /// entropy, pure, mixed, examples: [{hand, reach, entropy,
+    /// frequencies}]}]}`: `pure` and `mixed` count combos reaching the node,
+    /// a combo being pure when one action has 99% or more, and `examples`
+    /// are the three most mixed. Nodes high on the list are the hardest to
+    /// play and the first candidates for simplification.
+    pub fn get_mixing_report(&self) -> String {
+        serde_json::to_string(&self.mixing_report()).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Which nodes share each infoset, as JSON `[{ "infoset", "player",
+    /// "actions", "nodes": [{ "node", "line": ["check", "bet 100"] }] }]`
+    /// indexed by infoset id, the index into the trainer's buffers.
+    pub fn get_infoset_map(&self) -> String {
+        self.infoset_map().to_string()
+    }
+
+    /// Infoset id of the decision node `node_idx`; `undefined` for chance,
+    /// showdown and fold nodes.
+    #[cfg(feature = "wasm")]
+    pub fn get_node_infoset(&self, node_idx: usize) -> Result<Option<u32>, JsValue> {
+        self.node_infoset(node_idx).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// How often the player acting at decision node `node_idx` takes each
+    /// action, averaged over their range weighted by each combo's reach, as
+    /// JSON `{ "node", "player", "reach", "actions": [{ "action",
+    /// "frequency" }] }`. `reach` is the share of the starting range that
+    /// gets to the node; a node no combo reaches averages the range
+    /// unweighted.
+    #[cfg(feature = "wasm")]
+    pub fn get_node_frequencies(&self, node_idx: usize) -> Result<String, JsValue> {
+        self.node_frequencies(node_idx)
+            .map(|frequencies| frequencies.to_string())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Get strategy for a specific hand at a specific node (reached via history).
+    /// hand_str: e.g., "As Kh"
+    /// node_idx: the node index (obtained from get_strategy_for_history)
+    /// Returns { "player", "handIdx", "actions", "probs", "evs" }, with the
+    /// EVs as in `get_hand_strategy`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen]
+    pub fn get_hand_strategy_at_node(&self, hand_str: &str, node_idx: usize) -> Result<String, JsValue> {
+        // Parse hand
+        let cards: Vec<Card> = hand_str.split_whitespace()
+            .filter_map(Card::from_str)
+            .collect();
+
+        if cards.len() != 2 {
+            return Err(JsValue::from_str("Hand must have 2 cards"));
+        }
+
+        // Get the node
+        if node_idx >= self.tree.nodes.len() {
+            return Err(JsValue::from_str("Invalid node index"));
+        }
+
+        let node = &self.tree.nodes[node_idx];
+
+        // Check if it's a terminal node
+        if node.num_actions == 0 {
+            return Err(JsValue::from_str("Cannot get strategy at terminal node"));
+        }
+
+        // Check if it has an infoset
+        if node.infoset_id == u32::MAX {
+            return Err(JsValue::from_str("Node has no infoset"));
+        }
+
+        if let Some(river) = node.river
+            && cards.iter().any(|c| c.index() == river)
+        {
+            return Err(JsValue::from_str(&format!("Hand {} is blocked by the river card", hand_str)));
+        }
+
+        // Determine which player acts at this node
+        let acting_player = node.player as usize;
+
+        // Find hand index in the acting player's range
+        let mut hand_idx = None;
+        for (i, h) in self.ranges[acting_player].iter().enumerate() {
+            if (h[0] == cards[0] && h[1] == cards[1]) || (h[0] == cards[1] && h[1] == cards[0]) {
+                hand_idx = Some(i);
+                break;
+            }
+        }
+
+        let hand_idx = hand_idx.ok_or_else(||
+            JsValue::from_str(&format!("Hand not found in player {}'s range", acting_player)))?;
+
+        // Get the strategy with correct number of actions
+        let strategy = self.trainer.get_average_strategy_with_actions(
+            node.infoset_id as usize,
+            self.trainer_hand(acting_player, hand_idx),
+            node.num_actions as usize
+        );
+
+        // Get action names
+        let actions = self.get_actions_at_node(node_idx);
+
+        // Filter strategy to match num_actions
+        let probs = &strategy[0..node.num_actions as usize];
+
+        Ok(json!({
+            "player": acting_player,
+            "handIdx": hand_idx,
+            "actions": actions,
+            "probs": probs,
+            "evs": self.hand_action_evs(node_idx, hand_idx)
+        }).to_string())
+    }
+
+    /// Get actions at a specific node as JSON array
+    #[cfg_attr(feature = "wasm", wasm_bindgen)]
+    pub fn get_node_actions_at(&self, node_idx: usize) -> String {
+        if node_idx >= self.tree.nodes.len() {
+            return "[]".to_string();
+        }
+        serde_json::to_string(&self.get_actions_at_node(node_idx)).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    // ========================================================================
+    // HELPER METHODS (not exposed to WASM)
+    // ========================================================================
+
+    /// Get available actions at a node as a comma-separated string (for error messages)
+    fn get_available_actions_at_node(&self, node_idx: usize) -> String {
+        let node = &self.tree.nodes[node_idx];
+        if node.node_type == NodeType::Chance {
+            return self.tree.rivers.iter().map(|&c| format!("river {}", Card::from_index(c))).collect::<Vec<_>>().join(", ");
+        }
+        let mut actions = Vec::new();
+
+        for child_idx in self.tree.children(node_idx as u32) {
+            let child = &self.tree.nodes[child_idx as usize];
+
+            if let Some(action_type) = child.action_from_parent {
+                let name = match action_type {
+                    ActionType::Fold => "fold".to_string(),
+                    ActionType::Check => "check".to_string(),
+                    ActionType::Call => "call".to_string(),
+                    ActionType::Bet => format!("bet {:.0}", self.tree.to_amount(child.amount_from_parent)),
+                    ActionType::Raise => format!("raise {:.0}", self.tree.to_amount(child.amount_from_parent)),
+                };
+                actions.push(name);
+            }
+        }
+
+        actions.join(", ")
+    }
+
+    /// Get actions at a node as a vector of JSON objects
+    fn get_actions_at_node(&self, node_idx: usize) -> Vec<serde_json::Value> {
+        let mut actions = Vec::new();
+
+        for child_idx in self.tree.children(node_idx as u32) {
+            let child = &self.tree.nodes[child_idx as usize];
+
+            if let Some(action_type) = child.action_from_parent {
+                actions.push(json!({
+                    "type": action_type.as_str(),
+                    "amount": self.tree.to_amount(child.amount_from_parent)
+                }));
+            }
+        }
+
+        actions
+    }
+}
+
+// ============================================================================
+// NATIVE API (not exposed to WASM)
+// ============================================================================
+
+impl SolverSession {
+    /// Build a session from an already-parsed config.
+    ///
+    /// This is the shared construction path for the WASM constructor and
+    /// native consumers such as the CLI; errors are plain strings so it can
+    /// run outside a JS environment. Range strings may weight combos
+    /// (`"As Ad:0.5"`, see [`poker::range`]); a combo's weight is its reach
+    /// at the root, so it scales both its strategy's share of the range and
+    /// what it wins or loses at showdown.
+    pub fn from_config(config: GameConfig, board_str: &str, range0_str: &str, range1_str: &str) -> Result<SolverSession, String> {
+        Self::from_config_reusing(config, board_str, range0_str, range1_str, None)
+    }
+
+    /// `from_config`, taking over the allocations of `previous`, a session
+    /// done with: its trainer's buffers, and its tree when it has the same
+    /// config and both boards are rivers, where the tree does not depend on
+    /// the board.
+    pub(crate) fn from_config_reusing(config: GameConfig, board_str: &str, range0_str: &str, range1_str: &str, previous: Option<SolverSession>) -> Result<SolverSession, String> {
+        // 1. Parse Board
+        let board: Vec<Card> = board_str.split_whitespace()
+            .filter_map(Card::from_str)
+            .collect();
+        if board.len() != 4 && board.len() != 5 {
+             return Err("Board must have 4 (turn) or 5 (river) cards".to_string());
+        }
+        if let Some(card) = board.iter().find(|&&c| !config.variant.in_deck(c)) {
+            return Err(format!("Board card {} is not in the short deck", card));
+        }
+        debug!("[SolverSession::new] Board parsed: {:?} (ints: {:?})",
+               board_str, board.iter().map(|c| c.index()).collect::<Vec<u8>>());
+
+        // 2. Parse Ranges
+        let range0 = poker::range::parse_weighted_range(range0_str);
+        let range1 = poker::range::parse_weighted_range(range1_str);
+
+        if range0.is_empty() || range1.is_empty() {
+            return Err("Ranges cannot be empty".to_string());
+        }
+
+        // Nor can combos below six in short deck
+        let [range0, range1] = [range0, range1].map(|mut range| {
+            let before = range.len();
+            range.retain(|(hand, _)| hand.iter().all(|&c| config.variant.in_deck(c)));
+            if range.len() < before {
+                warn!("[SolverSession::new] Dropped {} combos with cards outside the deck", before - range.len());
+            }
+            range
+        });
+
+        // Combos holding a board card can never be dealt
+        let (range0, dropped0) = poker::range::drop_board_blocked(range0, &board);
+        let (range1, dropped1) = poker::range::drop_board_blocked(range1, &board);
+        for (player, dropped) in [&dropped0, &dropped1].into_iter().enumerate() {
+            if !dropped.is_empty() {
+                warn!("[SolverSession::new] Dropped {} board-blocked combos from P{}'s range", dropped.len(), player);
+            }
+        }
+        if range0.is_empty() || range1.is_empty() {
+            return Err("Every combo of a range shares a card with the board".to_string());
+        }
+        debug!("[SolverSession::new] Ranges: P0={} hands, P1={} hands", range0.len(), range1.len());
+        // Weights are the reach at the root
+        let initial_reach = [&range0, &range1].map(|range| range.iter().map(|(_, w)| *w).collect::<Vec<f32>>());
+        let ranges = [range0, range1].map(|range| range.into_iter().map(|(hand, _)| hand).collect::<Vec<_>>());
+
+        // 3. Build Tree
+        let (tree, trainer) = match previous {
+            Some(previous) if previous.config == config && previous.board.len() == 5 && board.len() == 5 => (previous.tree, Some(previous.trainer)),
+            Some(previous) => (checked_tree(&config, &board)?, Some(previous.trainer)),
+            None => (checked_tree(&config, &board)?, None),
+        };
+        debug!("[SolverSession::new] Tree built. Nodes: {}, Infosets: {}, Rivers: {}",
+               tree.nodes.len(), tree.infoset_map.len(), tree.rivers.len());
+
+        // 4. Compute Equity Matrix: the root board, then one block per dealt river
+        let equity_matrix = compute_equity_matrix_with_rivers(config.variant, &board, &tree.rivers, &ranges[0], &ranges[1]);
+        trace!("[SolverSession::new] Equity Matrix size: {} (expected {}x{}x{}={})",
+               equity_matrix.len(), ranges[0].len(), ranges[1].len(), tree.rivers.len() + 1,
+               ranges[0].len() * ranges[1].len() * (tree.rivers.len() + 1));
+        // Log first few equity values
+        if equity_matrix.len() >= 3 {
+            trace!("[SolverSession::new] Equity sample [0..3]: [{:.3}, {:.3}, {:.3}]",
+                   equity_matrix[0], equity_matrix[1], equity_matrix[2]);
+        }
+
+        // 5. Initialize Trainer
+        let num_infosets = tree.infoset_map.len();
+        let max_actions = tree.max_actions();
+        let num_hands = [ranges[0].len(), ranges[1].len()];
+
+        // With an abstraction or suit isomorphism the trainer plays groups of combos
+        if config.abstraction.as_ref().is_some_and(|a| a.buckets_for(board.len()) == 0) {
+            return Err("abstraction needs at least one bucket".to_string());
+        }
+        let abstraction = build_abstraction(&config, &board, &tree, &ranges, &equity_matrix, &initial_reach);
+        let trainer_hands = abstraction.as_ref().map_or(num_hands, |a| a.num_buckets);
+        let trainer = match trainer {
+            Some(mut trainer) => {
+                trainer.reset(num_infosets, max_actions, trainer_hands);
+                trainer
+            }
+            None => DCFRTrainer::new(num_infosets, max_actions, trainer_hands),
+        };
+        debug!("[SolverSession::new] Trainer created. regrets.len={}, strategy_sum.len={}, max_actions={}, trainer hands={:?}",
+               trainer.regrets.len(), trainer.strategy_sum.len(), max_actions, trainer_hands);
+
+        info!("[SolverSession::new] Session ready: {} nodes, {}x{} hands",
+              tree.nodes.len(), num_hands[0], num_hands[1]);
+        let mut session = SolverSession {
+            config,
+            board,
+            tree,
+            trainer,
+            equity_matrix,
+            initial_reach,
+            ranges,
+            abstraction,
+            convergence: Vec::new(),
+            dropped: [dropped0, dropped1],
+        };
+        for size_override in &session.config.size_overrides {
+            session.history_path(&size_override.line).map_err(|e| format!("Invalid size override: {}", e))?;
+        }
+        session.apply_profiles()?;
+        Ok(session)
+    }
+
+    /// Combos removed from `player`'s range at construction (see
+    /// `get_dropped_combos`).
+    pub fn dropped_combos(&self, player: usize) -> Result<&[DroppedCombo], String> {
+        self.dropped.get(player).map(Vec::as_slice).ok_or_else(|| "Player must be 0 or 1".to_string())
+    }
+
+    /// Findings of the trainer's invariant checks (see
+    /// `set_invariant_checks`), if they are on.
+    pub fn invariant_report(&self) -> Option<&solver::InvariantReport> {
+        self.trainer.invariant_report()
+    }
+
+    /// Performance counters (see `set_perf_counters`), if counting is on.
+    pub fn perf_counters(&self) -> Option<&solver::PerfCounters> {
+        self.trainer.perf_counters()
+    }
+
+    /// Number of CFR iterations run so far.
+    pub fn iterations(&self) -> usize {
+        self.trainer.iterations
+    }
+
+    /// Number of nodes in the game tree.
+    pub fn num_nodes(&self) -> usize {
+        self.tree.nodes.len()
+    }
+
+    /// Number of information sets in the game tree.
+    pub fn num_infosets(&self) -> usize {
+        self.tree.infoset_map.len()
+    }
+
+    /// Pot size at the root of the tree.
+    pub fn initial_pot(&self) -> f32 {
+        self.tree.to_amount(self.tree.nodes[0].pot)
+    }
+
+    /// Exploitability of the current average strategy, in chips.
+    /// Abstracted solves are measured on the real (combo) game.
+    pub fn exploitability(&self) -> f32 {
+        solver::exploitability(&self.tree, &self.combo_trainer(&self.trainer), &self.equity_matrix, &self.initial_reach)
+    }
+
+    /// Exploitability split by player, added to the convergence history
+    /// (replacing an earlier measurement at the same iteration).
+    pub fn record_exploitability(&mut self) -> ConvergencePoint {
+        let values = self.player_exploitability();
+        let point = ConvergencePoint::new(self.iterations(), &values, self.initial_pot());
+        if self.convergence.last().is_some_and(|last| last.iteration == point.iteration) {
+            self.convergence.pop();
+        }
+        self.convergence.push(point);
+        point
+    }
+
+    /// Both players' best-response and average-strategy values, in chips
+    /// per matchup, measured on the real (combo) game.
+    pub fn player_exploitability(&self) -> PlayerExploitability {
+        solver::player_exploitability(&self.tree, &self.combo_trainer(&self.trainer), &self.equity_matrix, &self.initial_reach)
+    }
+
+    /// Run `iterations` in chunks of `every` (at least one), calling
+    /// `progress` with the session after each chunk, the last included.
+    pub fn step_with(&mut self, iterations: usize, every: usize, mut progress: impl FnMut(&SolverSession)) {
+        let mut done = 0;
+        while done < iterations {
+            let chunk = every.max(1).min(iterations - done);
+            self.step(chunk);
+            done += chunk;
+            progress(self);
+        }
+    }
+
+    /// Heap bytes the session holds, as JSON `{ "tree", "equity_matrix",
+    /// "regrets", "strategy_sum", "other", "total" }`. `equity_matrix`
+    /// includes the bucket matrices of an abstracted solve; `other` is the
+    /// rest of the trainer, the ranges and their reach.
+    pub fn memory_usage(&self) -> serde_json::Value {
+        let f32s = |v: &Vec<f32>| v.capacity() * std::mem::size_of::<f32>();
+        let trainer = self.trainer.memory();
+        let mut equity = f32s(&self.equity_matrix);
+        let mut other = trainer.other
+            + self.initial_reach.iter().map(f32s).sum::<usize>()
+            + self.ranges.iter().flatten().map(|hand| std::mem::size_of::<Vec<Card>>() + hand.capacity() * std::mem::size_of::<Card>()).sum::<usize>();
+        if let Some(abstraction) = &self.abstraction {
+            equity += f32s(&abstraction.equity_matrix) + f32s(&abstraction.matchup_weights);
+            other += abstraction.equities.iter().chain(&abstraction.reach).map(f32s).sum::<usize>()
+                + abstraction.buckets.iter().map(|b| b.capacity() * std::mem::size_of::<usize>()).sum::<usize>();
+        }
+        let tree = self.tree.heap_bytes();
+        json!({
+            "tree": tree,
+            "equity_matrix": equity,
+            "regrets": trainer.regrets,
+            "strategy_sum": trainer.strategy_sum,
+            "other": other,
+            "total": tree + equity + trainer.regrets + trainer.strategy_sum + other,
+        })
+    }
+
+    /// Average positive regret of the trainer's strategies, in chips per
+    /// matchup (see `DCFRTrainer::regret_bound`). Much cheaper than
+    /// `exploitability` (one pass over the regrets, no tree traversal), so
+    /// fit for progress indicators; abstracted solves report the bucket game.
+    pub fn average_regret(&self) -> f32 {
+        let reach = self.abstraction.as_ref().map_or(&self.initial_reach, |a| &a.reach);
+        let total_weight = solver::best_response::matchup_weight(&self.equity_matrix, &self.initial_reach);
+        if total_weight > 0.0 { self.trainer.regret_bound(&self.tree, reach) / total_weight } else { 0.0 }
+    }
+
+    /// Exploitability measurements recorded so far, oldest first.
+    pub fn convergence_history(&self) -> &[ConvergencePoint] {
+        &self.convergence
+    }
+
+    /// Run until `max_iterations` in total, checking every `check_every`
+    /// iterations whether exploitability has dropped to `target_pct` percent
+    /// of the starting pot. Returns the final exploitability in that unit.
+    /// Every check is recorded in the convergence history.
+    pub fn solve_until(&mut self, max_iterations: usize, target_pct: Option<f32>, check_every: usize) -> f32 {
+        let chunk = if target_pct.is_some() { check_every.max(1) } else { usize::MAX };
+        while self.iterations() < max_iterations {
+            self.step(chunk.min(max_iterations - self.iterations()));
+            let pct = self.record_exploitability().exploitability_pct;
+            if target_pct.is_some_and(|target| pct <= target) || self.iterations() >= max_iterations {
+                return pct;
+            }
+        }
+        self.record_exploitability().exploitability_pct
+    }
+
+    /// Average strategy of the acting player at `node_idx` for every hand in
+    /// their range, as `{ "player", "actions": [...], "strategies": { "As Kh": [...] } }`.
+    pub fn node_strategy_table(&self, node_idx: usize) -> Result<serde_json::Value, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        if node.num_actions == 0 || node.infoset_id == u32::MAX {
+            return Err("Node has no infoset".to_string());
+        }
+
+        let player = node.player as usize;
+        let mut strategies = serde_json::Map::new();
+        for (h, hand) in self.ranges[player].iter().enumerate() {
+            if node.river.is_some_and(|river| hand.iter().any(|c| c.index() == river)) {
+                continue;
+            }
+            let probs = self.trainer.average_strategy(node.infoset_id as usize, self.trainer_hand(player, h), node.num_actions as usize);
+            strategies.insert(format!("{} {}", hand[0], hand[1]), json!(probs));
+        }
+
+        Ok(json!({
+            "node": node_idx,
+            "player": player,
+            "actions": self.get_actions_at_node(node_idx),
+            "strategies": strategies
+        }))
+    }
+
+    /// Range-weighted action frequencies at `node_idx` (see
+    /// `get_node_frequencies`).
+    pub fn node_frequencies(&self, node_idx: usize) -> Result<serde_json::Value, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        if node.node_type != NodeType::Action {
+            return Err("Node has no infoset".to_string());
+        }
+        let player = node.player as usize;
+        let reach = &self.path_reach(&self.path_to(node_idx))[player];
+        let total: f32 = reach.iter().sum();
+        let weight = |h: usize| if total > 0.0 { reach[h] / total } else { 1.0 / reach.len() as f32 };
+        let mut frequencies = vec![0.0; node.num_actions as usize];
+        for h in 0..reach.len() {
+            let probs = self.trainer.average_strategy(node.infoset_id as usize, self.trainer_hand(player, h), node.num_actions as usize);
+            for (f, p) in frequencies.iter_mut().zip(probs) {
+                *f += weight(h) * p;
+            }
+        }
+        let prior: f32 = self.initial_reach[player].iter().sum();
+
+        Ok(json!({
+            "node": node_idx,
+            "player": player,
+            "reach": if prior > 0.0 { total / prior } else { 0.0 },
+            "actions": frequencies.iter().enumerate()
+                .map(|(a, f)| json!({ "action": self.action_label(node.children_start as usize + a), "frequency": f }))
+                .collect::<Vec<_>>(),
+        }))
+    }
+
+    /// Average strategy of one hand at a decision node (see
+    /// `get_hand_strategy`): the node reached by `history`, or by default
+    /// the first node where the hand's owner acts without P0 betting.
+    pub fn hand_strategy<S: AsRef<str>>(&self, hand_str: &str, history: Option<&[S]>) -> Result<serde_json::Value, String> {
+        let cards: Vec<Card> = hand_str.split_whitespace()
+            .filter_map(Card::from_str)
+            .collect();
+        if cards.len() != 2 {
+            return Err("Hand must have 2 cards".to_string());
+        }
+
+        let (node_idx, player, hand_idx) = match history {
+            Some(history) => {
+                let node_idx = *self.history_path(history)?.last().expect("path starts at the root");
+                if self.tree.nodes[node_idx].node_type != NodeType::Action {
+                    return Err("Node has no infoset".to_string());
+                }
+                let player = self.tree.nodes[node_idx].player as usize;
+                let hand_idx = self.find_combo(player, &cards)
+                    .ok_or_else(|| format!("Hand not found in P{}'s range", player))?;
+                (node_idx, player, hand_idx)
+            }
+            None => {
+                let (player, hand_idx) = (0..2)
+                    .find_map(|p| self.find_combo(p, &cards).map(|h| (p, h)))
+                    .ok_or("Hand not found in ranges")?;
+                // P1 first acts after P0 checks
+                let node_idx = if player == 0 {
+                    0
+                } else {
+                    self.tree.children(0)
+                        .find(|&c| self.tree.nodes[c as usize].action_from_parent == Some(ActionType::Check))
+                        .ok_or("No check action found for P0")? as usize
+                };
+                (node_idx, player, hand_idx)
+            }
+        };
+
+        let node = &self.tree.nodes[node_idx];
+        if node.infoset_id == u32::MAX {
+            return Err("Node has no infoset".to_string());
+        }
+        let num_actions = node.num_actions as usize;
+        let strategy = self.trainer.get_average_strategy_with_actions(node.infoset_id as usize, self.trainer_hand(player, hand_idx), num_actions);
+
+        let actions: Vec<String> = self.tree.children(node_idx as u32)
+            .map(|child_id| {
+                let child = &self.tree.nodes[child_id as usize];
+                match child.action_from_parent {
+                    Some(action @ (ActionType::Bet | ActionType::Raise)) => format!("{} {:.1}", action.as_str(), self.tree.to_amount(child.amount_from_parent)),
+                    Some(action) => action.as_str().to_string(),
+                    None => "unknown".to_string(),
+                }
+            })
+            .collect();
+
+        Ok(json!({
+            "node": node_idx,
+            "player": player,
+            "actions": actions,
+            "probs": &strategy[..num_actions],
+            "evs": self.hand_action_evs(node_idx, hand_idx)
+        }))
+    }
+
+    /// Node reached by an action history, with its actions (see
+    /// `get_strategy_for_history`). On a turn session the river dealt is part
+    /// of the history, e.g. `["check", "check", "river Kd", "bet 75"]`.
+    pub fn strategy_for_history<S: AsRef<str>>(&self, history: &[S]) -> Result<serde_json::Value, String> {
+        let node_idx = *self.history_path(history)?.last().expect("path starts at the root");
+
+        // Now we're at the target node
+        let target_node = &self.tree.nodes[node_idx];
+        debug!("[get_strategy_for_history] Reached target node {}. Player: {}, infoset_id: {}, num_actions: {}",
+               node_idx, target_node.player, target_node.infoset_id, target_node.num_actions);
+        let river = target_node.river.map(|c| Card::from_index(c).to_string());
+
+        if target_node.node_type == NodeType::Chance {
+            let rivers: Vec<String> = self.tree.rivers.iter().map(|&c| Card::from_index(c).to_string()).collect();
+            return Ok(json!({
+                "nodeIdx": node_idx,
+                "isTerminal": false,
+                "isChance": true,
+                "player": target_node.player,
+                "pot": self.tree.to_amount(target_node.pot),
+                "rivers": rivers,
+                "actions": [],
+                "strategy": null
+            }));
+        }
+
+        // Check if this is a terminal node or has no infoset
+        if target_node.infoset_id == u32::MAX {
+            // Terminal node or opponent node without infoset
+            return Ok(json!({
+                "nodeIdx": node_idx,
+                "isTerminal": target_node.num_actions == 0,
+                "player": target_node.player,
+                "pot": self.tree.to_amount(target_node.pot),
+                "river": river,
+                "actions": [],
+                "strategy": null,
+                "message": "Node has no infoset (terminal or opponent's decision point)"
+            }));
+        }
+
+        // Return node info and infoset data
+        Ok(json!({
+            "nodeIdx": node_idx,
+            "isTerminal": false,
+            "player": target_node.player,
+            "pot": self.tree.to_amount(target_node.pot),
+            "river": river,
+            "infosetId": target_node.infoset_id,
+            "numActions": target_node.num_actions,
+            "actions": self.get_actions_at_node(node_idx)
+        }))
+    }
+
+    /// Root strategy of each river dealt at the chance node reached by
+    /// `history` (see `get_river_strategies`). A river's frequencies average
+    /// the acting player's strategy over their range, weighted by how often
+    /// each hand gets there; hands holding the river card are left out.
+    pub fn river_strategies<S: AsRef<str>>(&self, history: &[S]) -> Result<serde_json::Value, String> {
+        let path = self.history_path(history)?;
+        let chance_idx = *path.last().expect("path starts at the root");
+        let chance = &self.tree.nodes[chance_idx];
+        if chance.node_type != NodeType::Chance {
+            return Err(format!("History does not end where the river is dealt (node {})", chance_idx));
+        }
+
+        let reach = self.path_reach(&path);
+        let rivers: Vec<serde_json::Value> = (0..chance.num_actions as usize)
+            .map(|i| {
+                let node_idx = chance.children_start as usize + i;
+                let node = &self.tree.nodes[node_idx];
+                let card = Card::from_index(self.tree.rivers[i]);
+
+                json!({
+                    "card": card.to_string(),
+                    "node": node_idx,
+                    "player": node.player,
+                    "actions": self.get_actions_at_node(node_idx),
+                    "frequencies": self.river_frequencies(node_idx, &reach).0
+                })
+            })
+            .collect();
+
+        Ok(json!({ "node": chance_idx, "rivers": rivers }))
+    }
+
+    /// Range-weighted root frequencies of river node `node_idx` and the
+    /// acting player's reach of it, from each player's reach of the chance
+    /// node above. All-in runouts go straight to showdown and have no
+    /// strategy: no frequencies and no reach.
+    fn river_frequencies(&self, node_idx: usize, reach: &[Vec<f32>; 2]) -> (Vec<f32>, f32) {
+        let node = &self.tree.nodes[node_idx];
+        let mut frequencies = vec![0.0; node.num_actions as usize];
+        let mut total = 0.0;
+        if node.node_type == NodeType::Action {
+            let player = node.player as usize;
+            let card = Card::from_index(node.river.expect("river nodes have a river"));
+            for (h, hand) in self.ranges[player].iter().enumerate() {
+                if hand.contains(&card) {
+                    continue;
+                }
+                let probs = self.trainer.average_strategy(node.infoset_id as usize, self.trainer_hand(player, h), node.num_actions as usize);
+                for (f, p) in frequencies.iter_mut().zip(probs) {
+                    *f += reach[player][h] * p;
+                }
+                total += reach[player][h];
+            }
+            if total > 0.0 {
+                frequencies.iter_mut().for_each(|f| *f /= total);
+            }
+        }
+        (frequencies, total)
+    }
+
+    /// Runout report of the chance node reached by `history` (see
+    /// `get_runout_report`).
+    pub fn runout_report<S: AsRef<str>>(&self, history: &[S]) -> Result<serde_json::Value, String> {
+        let path = self.history_path(history)?;
+        let chance_idx = *path.last().expect("path starts at the root");
+        let chance = &self.tree.nodes[chance_idx];
+        if chance.node_type != NodeType::Chance {
+            return Err(format!("History does not end where the river is dealt (node {})", chance_idx));
+        }
+
+        let trainer = self.combo_trainer(&self.trainer);
+        let reach = self.path_reach(&path);
+        let mut actions = Vec::new();
+        // Value and matchup weight of each river for each player, and the
+        // frequencies and reach of the player to act
+        let mut rows = Vec::new();
+        for node_idx in self.tree.children(chance_idx as u32).map(|c| c as usize) {
+            let river_reach = self.path_reach(&[path.as_slice(), &[node_idx]].concat());
+            let values = [0, 1].map(|p| {
+                let opp_reach = &river_reach[1 - p];
+                let cfv = solver::counterfactual_values(&self.tree, &trainer, self.subtree_matrix(node_idx), node_idx as u32, p, opp_reach);
+                river_reach[p].iter().enumerate()
+                    .filter(|&(_, &r)| r > 0.0)
+                    .fold((0.0, 0.0), |(value, weight), (h, &r)| (value + r * cfv[h], weight + r * self.opponent_mass(node_idx, p, h, opp_reach)))
+            });
+            let (frequencies, total) = self.river_frequencies(node_idx, &reach);
+            if actions.is_empty() && total > 0.0 {
+                actions = self.get_actions_at_node(node_idx);
+            }
+            rows.push((node_idx, values, frequencies, total));
+        }
+
+        // Rivers weigh in by how often they come with the ranges that get here
+        let ev = |value: f32, weight: f32| if weight > 0.0 { value / weight } else { 0.0 };
+        let average_ev = [0, 1].map(|p| {
+            let (value, weight) = rows.iter().fold((0.0, 0.0), |(v, w), row| (v + row.1[p].0, w + row.1[p].1));
+            ev(value, weight)
+        });
+        let reach_total: f32 = rows.iter().map(|row| row.3).sum();
+        let average_frequencies: Vec<f32> = (0..actions.len())
+            .map(|a| ev(rows.iter().filter(|row| row.2.len() == actions.len()).map(|row| row.2[a] * row.3).sum(), reach_total))
+            .collect();
+
+        let rivers: Vec<serde_json::Value> = rows.iter()
+            .map(|(node_idx, values, frequencies, total)| {
+                let node = &self.tree.nodes[*node_idx];
+                let river_ev = values.map(|(value, weight)| ev(value, weight));
+                let shifts: Vec<f32> = if *total > 0.0 && frequencies.len() == actions.len() {
+                    frequencies.iter().zip(&average_frequencies).map(|(f, a)| f - a).collect()
+                } else {
+                    Vec::new()
+                };
+                json!({
+                    "card": Card::from_index(node.river.expect("chance children are dealt a river")).to_string(),
+                    "node": node_idx,
+                    "ev": river_ev,
+                    "ev_shift": [river_ev[0] - average_ev[0], river_ev[1] - average_ev[1]],
+                    "frequencies": if *total > 0.0 { frequencies.clone() } else { Vec::new() },
+                    "frequency_shift": shifts,
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "node": chance_idx,
+            "player": rows.iter().find(|row| row.3 > 0.0).map(|row| self.tree.nodes[row.0].player),
+            "actions": actions,
+            "average": { "ev": average_ev, "frequencies": average_frequencies },
+            "rivers": rivers,
+        }))
+    }
+
+    /// Blocker scores at `node_idx` (see `get_blocker_scores`). The
+    /// opponent's continue frequency is weighted by how often each of their
+    /// hands reaches the response; a hand's own cards are then taken out of
+    /// the opponent's range to see how that frequency moves.
+    pub fn blocker_scores(&self, node_idx: usize) -> Result<serde_json::Value, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        if node.node_type != NodeType::Action {
+            return Err("Node has no infoset".to_string());
+        }
+        let response_idx = self.tree.children(node_idx as u32)
+            .find(|&c| self.tree.nodes[c as usize].action_from_parent.is_some_and(|a| a.is_aggressive()))
+            .ok_or("Node has no bet or raise to respond to")? as usize;
+        let response = &self.tree.nodes[response_idx];
+        if response.node_type != NodeType::Action {
+            return Err("The bet ends the hand; there is no response to score".to_string());
+        }
+
+        let hero = node.player as usize;
+        let villain = response.player as usize;
+        let mut path = self.path_to(node_idx);
+        path.push(response_idx);
+        let reach = self.path_reach(&path);
+        let dead = self.dead_cards(node_idx);
+
+        // Weight and continue probability of each live villain hand
+        let fold = (0..response.num_actions as usize)
+            .find(|&a| self.tree.nodes[response.children_start as usize + a].action_from_parent == Some(ActionType::Fold));
+        let villain_hands: Vec<(u64, f32, f32)> = self.ranges[villain].iter().enumerate()
+            .filter_map(|(h, hand)| {
+                let mask = hand_mask(hand);
+                if mask & dead != 0 || reach[villain][h] <= 0.0 {
+                    return None;
+                }
+                let probs = self.trainer.average_strategy(response.infoset_id as usize, self.trainer_hand(villain, h), response.num_actions as usize);
+                let continues = 1.0 - fold.map_or(0.0, |f| probs[f]);
+                Some((mask, reach[villain][h], continues))
+            })
+            .collect();
+        let continue_rate = |blocked: u64| {
+            let (mut weight, mut continuing) = (0.0, 0.0);
+            for &(mask, w, c) in &villain_hands {
+                if mask & blocked == 0 {
+                    weight += w;
+                    continuing += w * c;
+                }
+            }
+            if weight > 0.0 { continuing / weight } else { 0.0 }
+        };
+
+        let baseline = continue_rate(0);
+        let mut combos: Vec<(String, f32)> = self.ranges[hero].iter()
+            .filter(|hand| hand_mask(hand) & dead == 0)
+            .map(|hand| (format!("{} {}", hand[0], hand[1]), continue_rate(hand_mask(hand))))
+            .collect();
+        combos.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        Ok(json!({
+            "node": node_idx,
+            "response_node": response_idx,
+            "player": hero,
+            "baseline_continue": baseline,
+            "combos": combos.into_iter()
+                .map(|(hand, rate)| json!({ "hand": hand, "continue": rate, "score": baseline - rate }))
+                .collect::<Vec<_>>()
+        }))
+    }
+
+    /// Polarization report at `node_idx` (see `get_polarization_report`).
+    /// Each combo that bets is classified by its equity against the hands
+    /// that continue (do not fold) facing the bet, weighted by how often
+    /// they reach it.
+    pub fn polarization_report(
+        &self,
+        node_idx: usize,
+        thresholds: &analysis::PolarizationThresholds,
+    ) -> Result<analysis::PolarizationReport, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        if node.node_type != NodeType::Action {
+            return Err("Node has no infoset".to_string());
+        }
+        let hero = node.player as usize;
+        let villain = 1 - hero;
+        let path = self.path_to(node_idx);
+        let matrix = self.showdown_matrix_at(node_idx);
+        let node_reach: f32 = self.path_reach(&path)[hero].iter().sum();
+
+        let mut actions = Vec::new();
+        for child_idx in self.tree.children(node_idx as u32).map(|c| c as usize) {
+            let child = &self.tree.nodes[child_idx];
+            let Some(action) = child.action_from_parent.filter(|a| a.is_aggressive()) else {
+                continue;
+            };
+            let mut child_path = path.clone();
+            child_path.push(child_idx);
+            let [reach0, reach1] = self.path_reach(&child_path);
+            let mut weights = [reach0, reach1];
+
+            // Villain hands that fold to the bet drop out of the showdown range
+            if child.node_type == NodeType::Action {
+                let fold = (0..child.num_actions as usize)
+                    .find(|&a| self.tree.nodes[child.children_start as usize + a].action_from_parent == Some(ActionType::Fold));
+                if let Some(fold) = fold {
+                    for (h, w) in weights[villain].iter_mut().enumerate() {
+                        *w *= 1.0 - self.trainer.average_strategy(child.infoset_id as usize, self.trainer_hand(villain, h), child.num_actions as usize)[fold];
+                    }
+                }
+            }
+
+            let equities = range_equities(matrix, [&weights[0], &weights[1]]);
+            let combos = self.ranges[hero].iter().zip(&weights[hero]).zip(&equities[hero])
+                .map(|((hand, &weight), &equity)| analysis::ComboEquity {
+                    hand: format!("{} {}", hand[0], hand[1]),
+                    weight,
+                    equity,
+                })
+                .collect();
+
+            let size = self.tree.to_amount(child.amount_from_parent);
+            let call = self.tree.to_amount(child.bets[hero] - child.bets[villain]);
+            let bet_reach: f32 = weights[hero].iter().sum();
+            actions.push(analysis::BetPolarization {
+                action: format!("{} {:.0}", action.as_str(), size),
+                node: child_idx,
+                size,
+                frequency: if node_reach > 0.0 { bet_reach / node_reach } else { 0.0 },
+                alpha: analysis::alpha(size, self.tree.to_amount(node.pot)),
+                optimal_bluff_ratio: analysis::optimal_bluff_ratio(call, self.tree.to_amount(child.pot)),
+                split: analysis::split_range(combos, thresholds),
+            });
+        }
+
+        Ok(analysis::PolarizationReport { node: node_idx, player: hero, thresholds: *thresholds, actions })
+    }
+
+    /// Exploit simulation at `node_idx` (see `simulate_exploit`).
+    pub fn exploit_simulation(&self, node_idx: usize, shifts: Vec<analysis::StrategyShift>) -> Result<analysis::ExploitReport, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        if node.node_type != NodeType::Action {
+            return Err("Node has no infoset".to_string());
+        }
+        let hero = node.player as usize;
+        let villain = 1 - hero;
+
+        let mut perturbed = self.trainer.clone();
+        for shift in &shifts {
+            let target = self.tree.nodes.get(shift.node).ok_or_else(|| format!("Invalid node index {}", shift.node))?;
+            if target.node_type != NodeType::Action || target.player as usize != villain {
+                return Err(format!("Node {} is not a decision of player {}", shift.node, villain));
+            }
+            if !(0.0..=1.0).contains(&shift.fraction) {
+                return Err(format!("Fraction {} is outside [0, 1]", shift.fraction));
+            }
+            let action = |name: &str| {
+                LineStep::parse(name)
+                    .and_then(|step| self.tree.find_child(shift.node as u32, &step, f32::INFINITY))
+                    .map(|child| (child - target.children_start) as usize)
+                    .ok_or_else(|| format!("No action '{}' at node {} (available: {})", name, shift.node,
+                                           self.get_available_actions_at_node(shift.node)))
+            };
+            let (from, to) = (action(&shift.from)?, action(&shift.to)?);
+            let (infoset, num_actions) = (target.infoset_id as usize, target.num_actions as usize);
+            for h in 0..perturbed.num_hands()[villain] {
+                let mut probs = perturbed.average_strategy(infoset, h, num_actions);
+                analysis::shift_strategy(&mut probs, from, to, shift.fraction);
+                perturbed.set_average_strategy(infoset, h, &probs);
+            }
+        }
+
+        let path = self.path_to(node_idx);
+        let matrix = self.subtree_matrix(node_idx);
+        let values = |trainer: &DCFRTrainer| {
+            let reach = self.path_reach_with(trainer, &path);
+            let trainer = self.combo_trainer(trainer);
+            let value = |play| solver::node_value(&self.tree, &trainer, matrix, node_idx as u32, &reach, hero, play);
+            analysis::NodeValues { equilibrium: value(solver::Play::Average), best_response: value(solver::Play::BestResponse) }
+        };
+
+        Ok(analysis::ExploitReport::new(
+            node_idx, hero, self.tree.to_amount(node.pot), shifts, values(&self.trainer), values(&perturbed),
+        ))
+    }
+
+    /// Session for re-solving the subtree at `node_idx` (see
+    /// `resolve_subtree`). `overrides` is a JSON object of `GameConfig`
+    /// fields. Hands that cannot reach the node are dropped; the others keep
+    /// their reach as range weight.
+    pub fn subtree_session(&self, node_idx: usize, overrides: Option<serde_json::Value>) -> Result<SolverSession, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        let path = self.path_to(node_idx);
+        let starts_round = path.len() == 1 || self.tree.nodes[path[path.len() - 2]].node_type == NodeType::Chance;
+        if node.node_type != NodeType::Action || !starts_round {
+            return Err("Only the root or a dealt river can be re-solved".to_string());
+        }
+
+        // A dealt river becomes the board of a river session with the river sizes
+        let mut config = self.config.clone();
+        let mut board = self.board.clone();
+        if let Some(river) = node.river {
+            board.push(Card::from_index(river));
+            config = config.river_config();
+        }
+        config.initial_pot = self.tree.to_amount(node.pot);
+        config.stacks = [0, 1].map(|p| self.tree.to_amount(self.tree.stacks[p] - node.bets[p]));
+
+        if let Some(overrides) = overrides {
+            let serde_json::Value::Object(overrides) = overrides else {
+                return Err("Config overrides must be a JSON object".to_string());
+            };
+            if overrides.contains_key("initial_pot") || overrides.contains_key("stacks") {
+                return Err("The pot and stacks come from the node and cannot be overridden".to_string());
+            }
+            let mut merged = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+            merged.as_object_mut().expect("GameConfig serializes to an object").extend(overrides);
+            config = serde_json::from_value(merged).map_err(|e| format!("Invalid config: {}", e))?;
+        }
+
+        let reach = self.path_reach(&path);
+        let kept: [Vec<usize>; 2] = [0, 1].map(|p| (0..self.ranges[p].len()).filter(|&h| reach[p][h] > 0.0).collect());
+        let range = |p: usize| format_range(&kept[p].iter().map(|&h| self.ranges[p][h].clone()).collect::<Vec<_>>());
+        let board_str = board.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+
+        let mut session = Self::from_config(config, &board_str, &range(0), &range(1))
+            .map_err(|e| if kept.iter().any(|k| k.is_empty()) { "A player has no hands that reach the node".to_string() } else { e })?;
+        session.set_initial_reach([0, 1].map(|p| kept[p].iter().map(|&h| reach[p][h]).collect()))?;
+        Ok(session)
+    }
+
+    /// 13x13 strategy grid of the player acting after `history` (see
+    /// `get_strategy_grid`).
+    pub fn strategy_grid<S: AsRef<str>>(&self, history: &[S]) -> Result<serde_json::Value, String> {
+        let path = self.history_path(history)?;
+        let node_idx = *path.last().expect("path starts at the root");
+        let node = &self.tree.nodes[node_idx];
+        if node.node_type != NodeType::Action {
+            return Err("Node has no infoset".to_string());
+        }
+        let (player, num_actions) = (node.player as usize, node.num_actions as usize);
+        let dead = self.dead_cards(node_idx);
+        let reach = &self.path_reach(&path)[player];
+
+        // Per class: live combos, reach and reach-weighted action frequencies
+        let mut combos = vec![0usize; poker::preflop::NUM_CLASSES];
+        let mut weights = vec![0.0f32; poker::preflop::NUM_CLASSES];
+        let mut sums = vec![vec![0.0f32; num_actions]; poker::preflop::NUM_CLASSES];
+        for (h, hand) in self.ranges[player].iter().enumerate() {
+            let Some(class) = poker::preflop::class_index(&hand_class(hand)) else { continue };
+            if hand_mask(hand) & dead != 0 {
+                continue;
+            }
+            combos[class] += 1;
+            weights[class] += reach[h];
+            let probs = self.trainer.average_strategy(node.infoset_id as usize, self.trainer_hand(player, h), num_actions);
+            for (sum, p) in sums[class].iter_mut().zip(probs) {
+                *sum += reach[h] * p;
+            }
+        }
+
+        let total: f32 = weights.iter().sum();
+        let frequencies = |sums: &[f32], weight: f32| (weight > 0.0).then(|| sums.iter().map(|s| s / weight).collect::<Vec<_>>());
+        let range_sums: Vec<f32> = (0..num_actions).map(|a| sums.iter().map(|s| s[a]).sum()).collect();
+        let grid: Vec<Vec<serde_json::Value>> = (0..13)
+            .map(|row| (0..13).map(|col| {
+                let class = row * 13 + col;
+                if combos[class] == 0 {
+                    return json!(null);
+                }
+                json!({
+                    "class": poker::preflop::class_name(class),
+                    "combos": combos[class],
+                    "weight": if total > 0.0 { weights[class] / total } else { 0.0 },
+                    "frequencies": frequencies(&sums[class], weights[class]),
+                })
+            }).collect())
+            .collect();
+
+        Ok(json!({
+            "node": node_idx,
+            "player": player,
+            "actions": (0..num_actions).map(|a| self.action_label(node.children_start as usize + a)).collect::<Vec<_>>(),
+            "frequencies": frequencies(&range_sums, total),
+            "grid": grid,
+        }))
+    }
+
+    /// Filtered range of `player` after `history` (see `get_filtered_range`).
+    /// Combos blocked by the board, or by a river dealt on the line, drop out.
+    pub fn filtered_range<S: AsRef<str>>(&self, player: usize, history: &[S]) -> Result<serde_json::Value, String> {
+        if player > 1 {
+            return Err(format!("Invalid player {}", player));
+        }
+        let path = self.history_path(history)?;
+        let node_idx = *path.last().expect("path starts at the root");
+        let dead = self.dead_cards(node_idx);
+        let prior = &self.initial_reach[player];
+        let reach: Vec<f32> = self.path_reach(&path)[player].iter().zip(&self.ranges[player])
+            .map(|(&r, hand)| if hand_mask(hand) & dead == 0 { r } else { 0.0 })
+            .collect();
+
+        let prior_mass: f32 = prior.iter().sum();
+        let mass: f32 = reach.iter().sum();
+        let share = |r: f32, total: f32| if total > 0.0 { r / total } else { 0.0 };
+
+        let mut classes: Vec<(String, usize, f32)> = Vec::new();
+        let combos: Vec<serde_json::Value> = self.ranges[player].iter().enumerate()
+            .map(|(h, hand)| {
+                let class = hand_class(hand);
+                let weight = share(reach[h], mass);
+                match classes.iter_mut().find(|(c, _, _)| *c == class) {
+                    Some((_, n, w)) => {
+                        *n += 1;
+                        *w += weight;
+                    }
+                    None => classes.push((class.clone(), 1, weight)),
+                }
+                json!({
+                    "hand": format!("{} {}", hand[0], hand[1]),
+                    "class": class,
+                    "reach": share(reach[h], prior[h]),
+                    "weight": weight
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "node": node_idx,
+            "player": player,
+            "total": share(mass, prior_mass),
+            "combos": combos,
+            "classes": classes.into_iter()
+                .map(|(class, combos, weight)| json!({ "class": class, "combos": combos, "weight": weight }))
+                .collect::<Vec<_>>()
+        }))
+    }
+
+    /// Replace `player`'s profile with `rules` (see `apply_profile`) and
+    /// recompile the locks. An empty rule list removes the profile. On error
+    /// the previous profiles stay in force.
+    pub fn apply_profile_rules(&mut self, player: usize, rules: Vec<ProfileRule>) -> Result<serde_json::Value, String> {
+        if player > 1 {
+            return Err(format!("Invalid player {}", player));
+        }
+        let previous = self.config.profiles.clone();
+        self.config.profiles.retain(|profile| profile.player != player);
+        if !rules.is_empty() {
+            self.config.profiles.push(StrategyProfile { player, rules: rules.clone() });
+        }
+        let compiled = match self.apply_profiles() {
+            Ok(compiled) => compiled,
+            Err(e) => {
+                self.config.profiles = previous;
+                self.apply_profiles()?;
+                return Err(e);
+            }
+        };
+
+        let locks: Vec<serde_json::Value> = compiled.iter()
+            .filter(|(p, _)| *p == player)
+            .flat_map(|(_, locks)| locks)
+            .map(|lock| {
+                let node = &self.tree.nodes[lock.node];
+                let actions: Vec<String> = (0..node.num_actions as usize)
+                    .filter(|&a| lock.allowed & (1 << a) != 0)
+                    .map(|a| {
+                        let child = &self.tree.nodes[node.children_start as usize + a];
+                        let action = child.action_from_parent.expect("action children have an action");
+                        if action.is_aggressive() {
+                            format!("{} {:.0}", action.as_str(), self.tree.to_amount(child.amount_from_parent))
+                        } else {
+                            action.as_str().to_string()
+                        }
+                    })
+                    .collect();
+                json!({
+                    "node": lock.node,
+                    "rule": lock.rule,
+                    "actions": actions,
+                    "hands": lock.hands.iter().map(|&h| format!("{} {}", self.ranges[player][h][0], self.ranges[player][h][1])).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        Ok(json!({ "player": player, "rules": rules, "locks": locks }))
+    }
+
+    /// Automatic sizing solve (see `auto_size_solve`).
+    pub fn auto_size(&mut self, options: &analysis::AutoSizeOptions) -> Result<serde_json::Value, String> {
+        let board_str = self.board.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+        let respawn = |config: GameConfig, reach: &[Vec<f32>; 2]| {
+            let mut session = Self::from_config(config, &board_str, &format_range(&self.ranges[0]), &format_range(&self.ranges[1]))?;
+            session.set_initial_reach(reach.clone())?;
+            Ok::<_, String>(session)
+        };
+
+        let mut config = self.config.clone();
+        config.bet_sizes = options.sizes.iter().map(|&size| size.into()).collect();
+        config.river_bet_sizes = None;
+        for own in &mut config.player_sizes {
+            (own.bet_sizes, own.river_bet_sizes) = (None, None);
+        }
+        config.size_overrides.clear();
+        let mut dense = respawn(config.clone(), &self.initial_reach)?;
+        dense.step(options.coarse_iterations);
+
+        // Prune every node offering a choice of bets or raises
+        let mut kept = Vec::new();
+        for (node_idx, line, frequencies) in dense.action_frequencies() {
+            let node = &dense.tree.nodes[node_idx];
+            let aggressive: Vec<usize> = (0..node.num_actions as usize)
+                .filter(|&a| dense.tree.nodes[node.children_start as usize + a].action_from_parent.is_some_and(|t| t.is_aggressive()))
+                .collect();
+            if aggressive.len() < 2 {
+                continue;
+            }
+            let amounts: Vec<f32> = aggressive.iter().map(|&a| dense.tree.to_amount(dense.tree.nodes[node.children_start as usize + a].amount_from_parent)).collect();
+            let betting: Vec<f32> = aggressive.iter().map(|&a| frequencies[a]).collect();
+            let picked = analysis::pick_sizes(&betting, options.max_sizes, options.coverage);
+            config.size_overrides.push(solver::SizeOverride {
+                line: line.clone(),
+                sizes: picked.iter().map(|&i| amounts[i]).collect(),
+            });
+            kept.push(json!({
+                "line": line,
+                "sizes": (0..amounts.len()).map(|i| json!({ "amount": amounts[i], "frequency": betting[i], "kept": picked.contains(&i) })).collect::<Vec<_>>(),
+            }));
+        }
+
+        let mut slim = respawn(config, &self.initial_reach)?;
+        slim.warm_start(&dense);
+        slim.step(options.iterations.saturating_sub(slim.iterations()));
+        let values = solver::player_exploitability(&slim.tree, &slim.combo_trainer(&slim.trainer), &slim.equity_matrix, &slim.initial_reach);
+        let point = slim.record_exploitability();
+        info!("[auto_size] {} nodes pruned to {}", dense.tree.nodes.len(), slim.tree.nodes.len());
+
+        let report = json!({
+            "dense_nodes": dense.tree.nodes.len(),
+            "nodes": slim.tree.nodes.len(),
+            "iterations": slim.iterations(),
+            "exploitability_pct": point.exploitability_pct,
+            "ev": values.average,
+            "kept": kept,
+        });
+        *self = slim;
+        Ok(report)
+    }
+
+    /// Rebuild with `config` and a transferred solve (see
+    /// `rebuild_with_transfer`).
+    pub fn transfer_to(&mut self, config: GameConfig, regrets: bool) -> Result<serde_json::Value, String> {
+        if self.config.abstraction.is_some() || config.abstraction.is_some() {
+            return Err("Strategy transfer needs solves without an abstraction".to_string());
+        }
+        let board_str = self.board.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+        let mut session = Self::from_config(config, &board_str, &format_range(&self.ranges[0]), &format_range(&self.ranges[1]))?;
+        session.set_initial_reach(self.initial_reach.clone())?;
+        let (matched_nodes, matched_infosets) = session.transfer_from(self, regrets);
+        let infosets = session.num_infosets();
+        info!("[transfer] {} of {} infosets matched", matched_infosets, infosets);
+
+        let report = json!({
+            "nodes": session.num_nodes(),
+            "matched_nodes": matched_nodes,
+            "infosets": infosets,
+            "matched_infosets": matched_infosets,
+            "match_rate": if infosets > 0 { matched_infosets as f32 / infosets as f32 } else { 1.0 },
+            "iterations": session.iterations(),
+            "regrets": regrets,
+        });
+        *self = session;
+        Ok(report)
+    }
+
+    /// Fold and showdown EV of every combo at a node (see
+    /// `get_ev_components`).
+    pub fn ev_components(&self, node_idx: usize) -> Result<serde_json::Value, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        if self.config.icm.is_some() {
+            return Err("EV components need chip payoffs; ICM values are in payout units".to_string());
+        }
+        let reach = self.path_reach(&self.path_to(node_idx));
+        let trainer = self.combo_trainer(&self.trainer);
+        let matrix = self.subtree_matrix(node_idx);
+        let players: Vec<serde_json::Value> = (0..2).map(|player| {
+            let opp_reach = &reach[1 - player];
+            let [folds, showdowns] = solver::pot_share_values(&self.tree, &trainer, matrix, node_idx as u32, player, opp_reach);
+            let combos: Vec<serde_json::Value> = self.ranges[player].iter().enumerate()
+                .filter(|&(h, _)| reach[player][h] > 0.0)
+                .filter_map(|(h, hand)| {
+                    let mass = self.opponent_mass(node_idx, player, h, opp_reach);
+                    (mass > 0.0).then(|| json!({
+                        "hand": format!("{} {}", hand[0], hand[1]),
+                        "fold_ev": folds[h] / mass,
+                        "showdown_ev": showdowns[h] / mass,
+                        "total": (folds[h] + showdowns[h]) / mass,
+                    }))
+                })
+                .collect();
+            json!({ "player": player, "combos": combos })
+        }).collect();
+        Ok(json!({ "node": node_idx, "pot": self.tree.to_amount(node.pot), "players": players }))
+    }
+
+    /// EV decomposition of one combo by line (see `get_ev_decomposition`).
+    pub fn ev_decomposition(&self, hand_str: &str, player: Option<usize>, max_depth: usize) -> Result<serde_json::Value, String> {
+        let cards: Vec<Card> = hand_str.split_whitespace().filter_map(Card::from_str).collect();
+        if cards.len() != 2 {
+            return Err("Hand must have 2 cards".to_string());
+        }
+        let find = |p: usize| self.find_combo(p, &cards);
+        let (player, hand) = match player {
+            Some(p) if p > 1 => return Err(format!("Invalid player {}", p)),
+            Some(p) => (p, find(p).ok_or_else(|| format!("Hand not found in player {}'s range", p))?),
+            None => (0..2).find_map(|p| find(p).map(|h| (p, h))).ok_or("Hand not found in either range")?,
+        };
+
+        let opp_reach = self.initial_reach[1 - player].clone();
+        let root_mass = self.opponent_mass(0, player, hand, &opp_reach);
+        if root_mass <= 0.0 {
+            return Err(format!("No opposing hand can be dealt against {}", hand_str));
+        }
+        let trainer = self.combo_trainer(&self.trainer);
+        let line = LineWeight { own: 1.0, chance: 1.0, opp_reach };
+        let root = self.decompose(&trainer, 0, player, hand, line, root_mass, max_depth);
+        Ok(json!({ "hand": hand_str, "player": player, "ev": root["ev"], "lines": root["lines"] }))
+    }
+
+    /// One node of `ev_decomposition`: `root_mass` is the opposing reach
+    /// the root EV is normalized by.
+    #[allow(clippy::too_many_arguments)]
+    fn decompose(&self, trainer: &DCFRTrainer, node_idx: usize, player: usize, hand: usize, line: LineWeight, root_mass: f32, depth: usize) -> serde_json::Value {
+        let node = &self.tree.nodes[node_idx];
+        let values = solver::counterfactual_values(&self.tree, trainer, self.subtree_matrix(node_idx), node_idx as u32, player, &line.opp_reach);
+        let mass = line.chance * self.opponent_mass(node_idx, player, hand, &line.opp_reach);
+        let mut result = json!({
+            "node": node_idx,
+            "probability": line.own * mass / root_mass,
+            "ev": if mass > 0.0 { line.chance * values[hand] / mass } else { 0.0 },
+            "contribution": line.own * line.chance * values[hand] / root_mass,
+        });
+        if depth == 0 || node.num_actions == 0 {
+            return result;
+        }
+
+        let num_actions = node.num_actions as usize;
+        let acting = node.player as usize;
+        let opp_strategies: Vec<Vec<f32>> = if node.node_type == NodeType::Action && acting != player {
+            (0..line.opp_reach.len()).map(|h| trainer.average_strategy(node.infoset_id as usize, h, num_actions)).collect()
+        } else {
+            Vec::new()
+        };
+        let own_strategy = if node.node_type == NodeType::Action && acting == player {
+            trainer.average_strategy(node.infoset_id as usize, hand, num_actions)
+        } else {
+            Vec::new()
+        };
+
+        let lines: Vec<serde_json::Value> = (0..num_actions).map(|a| {
+            let child = node.children_start as usize + a;
+            let next = match node.node_type {
+                NodeType::Chance => LineWeight { chance: line.chance * self.tree.river_weight, ..line.clone() },
+                _ if acting == player => LineWeight { own: line.own * own_strategy[a], ..line.clone() },
+                _ => LineWeight {
+                    opp_reach: line.opp_reach.iter().zip(&opp_strategies).map(|(r, s)| r * s[a]).collect(),
+                    ..line.clone()
+                },
+            };
+            let mut child_result = self.decompose(trainer, child, player, hand, next, root_mass, depth - 1);
+            child_result["action"] = json!(self.action_label(child));
+            child_result
+        }).collect();
+        result["lines"] = json!(lines);
+        result
+    }
+
+    /// Value of every action of combo `hand` of the player acting at
+    /// decision node `node_idx`, both players following their average
+    /// strategies afterwards and the opposing combos weighted by
+    /// `opp_reach`. In chips per opposing combo that can be dealt; `None`
+    /// if there is none.
+    fn action_values(&self, node_idx: usize, hand: usize, opp_reach: &[f32]) -> Option<Vec<f32>> {
+        let node = &self.tree.nodes[node_idx];
+        let player = node.player as usize;
+        let mass = self.opponent_mass(node_idx, player, hand, opp_reach);
+        if mass <= 0.0 {
+            return None;
+        }
+        Some(self.counterfactual_action_values(node_idx, opp_reach).iter().map(|values| values[hand] / mass).collect())
+    }
+
+    /// `action_values` of combo `hand` at decision node `node_idx`, the
+    /// opponent reaching the node on their average strategy: what each
+    /// action is worth to the hand in chips, counted from the start of the
+    /// hand, against the opposing combos that get there.
+    fn hand_action_evs(&self, node_idx: usize, hand: usize) -> Option<Vec<f32>> {
+        let player = self.tree.nodes[node_idx].player as usize;
+        let opp_reach = &self.path_reach(&self.path_to(node_idx))[1 - player];
+        self.action_values(node_idx, hand, opp_reach)
+    }
+
+    /// Counterfactual value of every action (outer) for every combo
+    /// (inner) of the player acting at decision node `node_idx`, as in
+    /// `action_values` but not divided by the opposing mass.
+    fn counterfactual_action_values(&self, node_idx: usize, opp_reach: &[f32]) -> Vec<Vec<f32>> {
+        let node = &self.tree.nodes[node_idx];
+        let trainer = self.combo_trainer(&self.trainer);
+        self.tree.children(node_idx as u32)
+            .map(|child| solver::counterfactual_values(&self.tree, &trainer, self.subtree_matrix(child as usize), child, node.player as usize, opp_reach))
+            .collect()
+    }
+
+    /// Opposing reach that can be dealt against `hand` of `player` on the
+    /// board of `node_idx`.
+    fn opponent_mass(&self, node_idx: usize, player: usize, hand: usize, opp_reach: &[f32]) -> f32 {
+        // The first block of the subtree's matrix is the node's own board
+        let matrix = self.subtree_matrix(node_idx);
+        let n1 = self.ranges[1].len();
+        opp_reach.iter().enumerate()
+            .filter(|&(o, _)| {
+                let pair = if player == 0 { hand * n1 + o } else { o * n1 + hand };
+                !matrix[pair].is_nan()
+            })
+            .map(|(_, r)| r)
+            .sum()
+    }
+
+    /// Where the chips change hands (see `get_leaf_ev_map`).
+    pub fn leaf_ev_map(&self) -> serde_json::Value {
+        let trainer = self.combo_trainer(&self.trainer);
+        let num_hands = [self.ranges[0].len(), self.ranges[1].len()];
+        let total_weight = solver::best_response::matchup_weight(&self.equity_matrix, &self.initial_reach);
+        let mut leaves = Vec::new();
+        let (mut probability, mut ev) = (0.0, [0.0f32; 2]);
+        self.walk_reach(&trainer, |node_idx, line, reach, chance| {
+            let node = &self.tree.nodes[node_idx];
+            if !node.is_terminal() || total_weight <= 0.0 {
+                return;
+            }
+            let matrix = self.subtree_matrix(node_idx);
+            let matchups = solver::dcfr::Matchups::unweighted(matrix);
+            let (u0, u1) = solver::dcfr::terminal_values(node, self.tree.outcome(node), matchups, num_hands, &reach[0], &reach[1]);
+            let value = |u: &[f32], own: &[f32]| chance * u.iter().zip(own).map(|(u, r)| u * r).sum::<f32>() / total_weight;
+            let leaf_ev = [value(&u0, &reach[0]), value(&u1, &reach[1])];
+            let reached = chance * solver::best_response::matchup_weight(matrix, reach) / total_weight;
+            probability += reached;
+            ev[0] += leaf_ev[0];
+            ev[1] += leaf_ev[1];
+
+            let mut leaf = json!({
+                "node": node_idx,
+                "line": line,
+                "type": if node.node_type == NodeType::Terminal { "fold" } else { "showdown" },
+                "pot": self.tree.to_amount(node.pot),
+                "probability": reached,
+                "ev": leaf_ev,
+            });
+            if node.node_type == NodeType::Terminal {
+                leaf["winner"] = json!(node.player);
+            }
+            leaves.push(leaf);
+        });
+        leaves.sort_by_key(|leaf| leaf["node"].as_u64());
+        json!({ "ev": ev, "probability": probability, "leaves": leaves })
+    }
+
+    /// Equity realization of both ranges (see `get_equity_realization`).
+    pub fn equity_realization(&self) -> Result<[analysis::PlayerRealization; 2], String> {
+        if self.config.icm.is_some() {
+            return Err("Equity realization needs chip payoffs; ICM values are in payout units".to_string());
+        }
+        let pot = self.initial_pot();
+        let equities = self.range_equities();
+        let trainer = self.combo_trainer(&self.trainer);
+        Ok([0, 1].map(|player| {
+            let opp_reach = &self.initial_reach[1 - player];
+            let values = solver::counterfactual_values(&self.tree, &trainer, &self.equity_matrix, 0, player, opp_reach);
+            let (mut pot_share, mut equity_share) = (0.0, 0.0);
+            let combos = self.ranges[player].iter().enumerate()
+                .filter_map(|(h, hand)| {
+                    let weight = self.initial_reach[player][h];
+                    let mass = self.opponent_mass(0, player, h, opp_reach);
+                    if weight <= 0.0 || mass <= 0.0 {
+                        return None;
+                    }
+                    let (equity, ev) = (equities[player][h], values[h] / mass);
+                    pot_share += weight * mass * (ev + pot / 2.0);
+                    equity_share += weight * mass * equity * pot;
+                    let realization = analysis::realization(equity, ev, pot);
+                    Some(analysis::ComboRealization {
+                        hand: format!("{} {}", hand[0], hand[1]),
+                        weight,
+                        equity,
+                        ev,
+                        realization,
+                        under_realizes: realization.is_some_and(|r| r < analysis::UNDER_REALIZATION),
+                    })
+                })
+                .collect();
+            let realization = if equity_share > 0.0 { pot_share / equity_share } else { 0.0 };
+            analysis::PlayerRealization { player, realization, combos }
+        }))
+    }
+
+    /// Matchup lists of one combo (see `get_matchup_lists`).
+    pub fn matchup_lists(&self, node_idx: usize, hand_str: &str, max_len: usize) -> Result<serde_json::Value, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        let cards: Vec<Card> = hand_str.split_whitespace().filter_map(Card::from_str).collect();
+        if cards.len() != 2 {
+            return Err("Hand must have 2 cards".to_string());
+        }
+        let first = if node.node_type == NodeType::Action { node.player as usize } else { 0 };
+        let (player, hand) = [first, 1 - first].into_iter()
+            .find_map(|p| self.find_combo(p, &cards).map(|h| (p, h)))
+            .ok_or("Hand not found in either range")?;
+        if hand_mask(&cards) & self.dead_cards(node_idx) != 0 {
+            return Err(format!("{} is blocked by the board", hand_str));
+        }
+
+        let villain = 1 - player;
+        let reach = self.path_reach(&self.path_to(node_idx));
+        // The first block of the subtree's matrix is the node's own board
+        let matrix = self.subtree_matrix(node_idx);
+        let n1 = self.ranges[1].len();
+        let (mut lists, mut totals, mut blocked) = ([vec![], vec![], vec![]], [0.0f32; 3], 0.0f32);
+        for (v, combo) in self.ranges[villain].iter().enumerate() {
+            let weight = reach[villain][v];
+            if weight <= 0.0 {
+                continue;
+            }
+            let equity = if player == 0 { matrix[hand * n1 + v] } else { 1.0 - matrix[v * n1 + hand] };
+            let list = match equity {
+                e if e.is_nan() => {
+                    blocked += weight;
+                    continue;
+                }
+                e if e > 0.5 => 0,
+                e if e < 0.5 => 1,
+                _ => 2,
+            };
+            totals[list] += weight;
+            lists[list].push((format!("{} {}", combo[0], combo[1]), weight));
+        }
+
+        let [wins, loses, chops] = lists.map(|mut list| {
+            list.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let count = list.len();
+            list.truncate(max_len);
+            (count, list.into_iter().map(|(hand, weight)| json!({ "hand": hand, "weight": weight })).collect::<Vec<_>>())
+        });
+        Ok(json!({
+            "node": node_idx,
+            "player": player,
+            "hand": hand_str,
+            "wins": wins.1,
+            "loses": loses.1,
+            "chops": chops.1,
+            "totals": { "wins": totals[0], "loses": totals[1], "chops": totals[2] },
+            "counts": { "wins": wins.0, "loses": loses.0, "chops": chops.0 },
+            "blocked": blocked,
+        }))
+    }
+
+    /// Draw one action for `hand_str` at decision node `node_idx` from the
+    /// average strategy (see `sample_action`). The same seed always draws
+    /// the same action; actions a profile locks out are never drawn.
+    pub fn draw_action(&self, node_idx: usize, hand_str: &str, seed: u64) -> Result<serde_json::Value, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        if node.node_type != NodeType::Action {
+            return Err("Node has no infoset".to_string());
+        }
+        let cards: Vec<Card> = hand_str.split_whitespace().filter_map(Card::from_str).collect();
+        if cards.len() != 2 {
+            return Err("Hand must have 2 cards".to_string());
+        }
+        let player = node.player as usize;
+        let hand = self.find_combo(player, &cards).ok_or_else(|| format!("Hand not found in player {}'s range", player))?;
+        if hand_mask(&cards) & self.dead_cards(node_idx) != 0 {
+            return Err(format!("{} is blocked by the board", hand_str));
+        }
+
+        let probs = self.playable_strategy(node_idx, hand).ok_or_else(|| format!("No action left for {} at node {}", hand_str, node_idx))?;
+        let mut state = seed;
+        let chosen = sample_index(&probs, &mut state);
+
+        let action = |a: usize| {
+            let child = node.children_start as usize + a;
+            json!({
+                "type": self.tree.nodes[child].action_from_parent.map(|t| t.as_str()),
+                "amount": self.tree.to_amount(self.tree.nodes[child].amount_from_parent),
+                "child": child,
+                "prob": probs[a],
+            })
+        };
+        Ok(json!({
+            "node": node_idx,
+            "player": player,
+            "hand": hand_str.trim(),
+            "action": action(chosen),
+            "distribution": (0..probs.len()).map(action).collect::<Vec<_>>(),
+        }))
+    }
+
+    /// Average strategy of combo `hand` of the player acting at
+    /// `node_idx`, with actions their profile locks out removed; `None` if
+    /// nothing is left.
+    fn playable_strategy(&self, node_idx: usize, hand: usize) -> Option<Vec<f32>> {
+        let node = &self.tree.nodes[node_idx];
+        let (infoset, t) = (node.infoset_id as usize, self.trainer_hand(node.player as usize, hand));
+        let allowed = self.trainer.allowed_actions(infoset, t);
+        let mut probs = self.trainer.average_strategy(infoset, t, node.num_actions as usize);
+        for (a, p) in probs.iter_mut().enumerate() {
+            if allowed & (1 << a) == 0 {
+                *p = 0.0;
+            }
+        }
+        let total: f32 = probs.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        probs.iter_mut().for_each(|p| *p /= total);
+        Some(probs)
+    }
+
+    /// Hint for `hand_str` after `history` (see `get_hint`).
+    pub fn hint<S: AsRef<str>>(&self, hand_str: &str, history: &[S]) -> Result<analysis::Hint, String> {
+        let path = self.history_path(history)?;
+        let node_idx = *path.last().expect("path starts at the root");
+        let node = &self.tree.nodes[node_idx];
+        match node.node_type {
+            NodeType::Terminal | NodeType::Showdown => return Err(format!("The hand is over at node {}; there is nothing to decide", node_idx)),
+            NodeType::Chance => return Err(format!("Node {} deals the river; no player is to act", node_idx)),
+            NodeType::Action => {}
+        }
+        let cards: Vec<Card> = hand_str.split_whitespace().filter_map(Card::from_str).collect();
+        if cards.len() != 2 {
+            return Err("Hand must have 2 cards".to_string());
+        }
+        let player = node.player as usize;
+        let hand = match self.find_combo(player, &cards) {
+            Some(hand) => hand,
+            None if self.find_combo(1 - player, &cards).is_some() => {
+                return Err(format!("{} is in player {}'s range, but player {} is to act", hand_str, 1 - player, player));
+            }
+            None => return Err(format!("Hand not found in player {}'s range", player)),
+        };
+        if hand_mask(&cards) & self.dead_cards(node_idx) != 0 {
+            return Err(format!("{} is blocked by the board", hand_str));
+        }
+
+        let opp_reach = &self.path_reach(&path)[1 - player];
+        let values = self.action_values(node_idx, hand, opp_reach)
+            .ok_or_else(|| format!("No opposing combo reaches node {} against {}", node_idx, hand_str))?;
+        let probs = self.playable_strategy(node_idx, hand).ok_or_else(|| format!("No action left for {} at node {}", hand_str, node_idx))?;
+        let actions = (0..node.num_actions as usize)
+            .map(|a| analysis::ActionHint {
+                action: self.action_label(node.children_start as usize + a),
+                frequency: probs[a],
+                ev: values[a],
+            })
+            .collect();
+        Ok(analysis::Hint::new(node_idx, player, hand_str.trim().to_string(), self.tree.to_amount(node.pot), actions))
+    }
+
+    /// Leak report on a user strategy for `player` (see `leak_report`).
+    pub fn leaks(&self, player: usize, strategy: &[analysis::UserNode], options: &analysis::LeakOptions) -> Result<analysis::LeakReport, String> {
+        if player > 1 {
+            return Err(format!("Invalid player {}", player));
+        }
+        let total_weight = solver::best_response::matchup_weight(&self.equity_matrix, &self.initial_reach);
+        let mut nodes = Vec::new();
+        for user in strategy {
+            let path = self.history_path(&user.line)?;
+            let node_idx = *path.last().expect("path starts at the root");
+            let node = &self.tree.nodes[node_idx];
+            if node.node_type != NodeType::Action || node.player as usize != player {
+                return Err(format!("Line [{}] does not reach a decision of player {}", user.line.join(", "), player));
+            }
+            let frequencies = self.user_frequencies(node_idx, &user.hands)?;
+            let reach = self.path_reach(&path);
+            let opp_reach = &reach[1 - player];
+            let chance: f32 = path.iter().filter(|&&n| self.tree.nodes[n].node_type == NodeType::Chance).map(|_| self.tree.river_weight).product();
+            let values = self.counterfactual_action_values(node_idx, opp_reach);
+
+            let mut classes: Vec<analysis::ClassDelta> = Vec::new();
+            for (h, hand) in self.ranges[player].iter().enumerate() {
+                let mask = hand_mask(hand);
+                let Some((_, user_probs)) = frequencies.iter().find(|(key, _)| key.matches(mask, hand)) else { continue };
+                let Some(solver_probs) = self.playable_strategy(node_idx, h) else { continue };
+                let weight = reach[player][h] * chance / total_weight;
+                if weight <= 0.0 || mask & self.dead_cards(node_idx) != 0 {
+                    continue;
+                }
+                let mass = self.opponent_mass(node_idx, player, h, opp_reach);
+                let class = poker::range::hand_class(hand);
+                let index = match classes.iter().position(|c| c.class == class) {
+                    Some(index) => index,
+                    None => {
+                        classes.push(analysis::ClassDelta { class, loss: 0.0, excess: vec![0.0; values.len()] });
+                        classes.len() - 1
+                    }
+                };
+                let delta = &mut classes[index];
+                for (a, action_values) in values.iter().enumerate() {
+                    delta.loss += weight * (solver_probs[a] - user_probs[a]) * action_values[h];
+                    delta.excess[a] += weight * mass * (user_probs[a] - solver_probs[a]);
+                }
+            }
+            nodes.push(analysis::NodeDeltas {
+                node: node_idx,
+                line: user.line.clone(),
+                context: self.decision_context(&path),
+                actions: (0..node.num_actions as usize).map(|a| self.action_label(node.children_start as usize + a)).collect(),
+                classes,
+            });
+        }
+        Ok(analysis::leak_report(player, nodes, options))
+    }
+
+    /// The action frequencies of a user strategy's hands at decision node
+    /// `node_idx`, most specific key first.
+    fn user_frequencies(&self, node_idx: usize, hands: &std::collections::BTreeMap<String, std::collections::BTreeMap<String, f32>>) -> Result<Vec<(UserHand, Vec<f32>)>, String> {
+        let node = &self.tree.nodes[node_idx];
+        let mut frequencies = Vec::new();
+        for (key, actions) in hands {
+            let cards: Vec<Card> = key.split_whitespace().filter_map(Card::from_str).collect();
+            let hand = match key.trim() {
+                "*" => UserHand::Any,
+                _ if cards.len() == 2 => UserHand::Combo(hand_mask(&cards)),
+                class => UserHand::Class(poker::preflop::class_index(class).ok_or_else(|| format!("Unknown hand '{}'", key))?),
+            };
+            let mut probs = vec![0.0; node.num_actions as usize];
+            for (action, &frequency) in actions {
+                let child = LineStep::parse(action)
+                    .and_then(|step| self.tree.find_child(node_idx as u32, &step, f32::INFINITY))
+                    .ok_or_else(|| format!("Action '{}' is not available at node {}", action, node_idx))?;
+                if frequency.is_nan() || frequency < 0.0 {
+                    return Err(format!("Frequency of '{}' for {} must not be negative", action, key));
+                }
+                probs[(child - node.children_start) as usize] += frequency;
+            }
+            let total: f32 = probs.iter().sum();
+            if total <= 0.0 {
+                return Err(format!("No action frequency for {} at node {}", key, node_idx));
+            }
+            probs.iter_mut().for_each(|p| *p /= total);
+            frequencies.push((hand, probs));
+        }
+        frequencies.sort_by_key(|(hand, _)| match hand {
+            UserHand::Combo(_) => 0,
+            UserHand::Class(_) => 1,
+            UserHand::Any => 2,
+        });
+        Ok(frequencies)
+    }
+
+    /// Where the decision at the end of `path` is, for descriptions:
+    /// `"facing bet 75 on the turn"`, `"after check on the river"`.
+    fn decision_context(&self, path: &[usize]) -> String {
+        let node_idx = *path.last().expect("path starts at the root");
+        let street = if self.tree.rivers.is_empty() || self.tree.nodes[node_idx].river.is_some() { "river" } else { "turn" };
+        let (Some(&parent), Some(step)) = (path.len().checked_sub(2).and_then(|i| path.get(i)), self.tree.step_to(node_idx as u32)) else {
+            return format!("first to act on the {}", street);
+        };
+        match step {
+            LineStep::Action(action, _) if action.is_aggressive() && self.tree.nodes[parent].player != self.tree.nodes[node_idx].player => {
+                format!("facing {} on the {}", step, street)
+            }
+            LineStep::River(card) => format!("on the river {}", card),
+            _ => format!("after {} on the {}", step, street),
+        }
+    }
+
+    /// Range explorer query at `node_idx` (see `query_hands`).
+    pub fn hand_query(&self, node_idx: usize, query: &analysis::HandQuery) -> Result<analysis::HandQueryResult, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        if node.node_type != NodeType::Action {
+            return Err("Node has no infoset".to_string());
+        }
+        let player = node.player as usize;
+        let path = self.path_to(node_idx);
+        let reach = self.path_reach(&path);
+        let values = self.counterfactual_action_values(node_idx, &reach[1 - player]);
+        let blockers: HashMap<String, f32> = self.blocker_scores(node_idx)
+            .map(|report| {
+                report["combos"].as_array().into_iter().flatten()
+                    .filter_map(|c| Some((c["hand"].as_str()?.to_string(), c["score"].as_f64()? as f32)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let dead = self.dead_cards(node_idx);
+        let board: Vec<Card> = (0..52).map(Card::from_index).filter(|c| dead & c.bitmask() != 0).collect();
+
+        let rows = self.ranges[player].iter().enumerate()
+            .filter(|(_, hand)| hand_mask(hand) & dead == 0)
+            .map(|(h, hand)| {
+                let frequencies = self.trainer.average_strategy(node.infoset_id as usize, self.trainer_hand(player, h), node.num_actions as usize);
+                let mass = self.opponent_mass(node_idx, player, h, &reach[1 - player]);
+                let ev = (mass > 0.0).then(|| frequencies.iter().zip(&values).map(|(p, v)| p * v[h]).sum::<f32>() / mass);
+                let name = format!("{} {}", hand[0], hand[1]);
+                analysis::HandRow {
+                    class: poker::hand_class(hand),
+                    category: self.config.variant.hand_rank(self.config.variant.evaluate(&[hand.as_slice(), &board].concat())).name(),
+                    detail: poker::made_hands::classify_hand(&board, hand),
+                    reach: reach[player][h],
+                    ev,
+                    blocker: blockers.get(&name).copied(),
+                    cards: hand_mask(hand),
+                    frequencies,
+                    hand: name,
+                }
+            })
+            .collect();
+        let actions: Vec<LineStep> = self.tree.children(node_idx as u32)
+            .map(|child| self.tree.step_to(child).expect("only the root has no incoming step"))
+            .collect();
+        analysis::query_hands(node_idx, player, &actions, rows, query)
+    }
+
+    /// Strategy entropy at every node (see `get_mixing_report`).
+    pub fn mixing_report(&self) -> analysis::MixingReport {
+        let mut nodes = Vec::new();
+        self.mixing_sweep(0, self.initial_reach.clone(), 1.0, &mut nodes);
+        analysis::MixingReport::new(nodes)
+    }
+
+    /// Add the mixing of every decision node below `node_idx` to `nodes`,
+    /// `reach` being each player's reach of the node and `chance` the
+    /// probability of the rivers dealt on the way.
+    fn mixing_sweep(&self, node_idx: usize, reach: [Vec<f32>; 2], chance: f32, nodes: &mut Vec<analysis::NodeMixing>) {
+        let node = &self.tree.nodes[node_idx];
+        let children = self.tree.children(node_idx as u32).map(|c| c as usize);
+        match node.node_type {
+            NodeType::Terminal | NodeType::Showdown => {}
+            NodeType::Chance => {
+                for child in children {
+                    let river = self.tree.nodes[child].river.expect("chance children are dealt a river");
+                    let mut reach = reach.clone();
+                    for (r, range) in reach.iter_mut().zip(&self.ranges) {
+                        for (r, hand) in r.iter_mut().zip(range) {
+                            if hand.iter().any(|c| c.index() == river) {
+                                *r = 0.0;
+                            }
+                        }
+                    }
+                    self.mixing_sweep(child, reach, chance * self.tree.river_weight, nodes);
+                }
+            }
+            NodeType::Action => {
+                let player = node.player as usize;
+                let num_actions = node.num_actions as usize;
+                let strategies: Vec<Vec<f32>> = (0..self.ranges[player].len())
+                    .map(|h| self.trainer.average_strategy(node.infoset_id as usize, self.trainer_hand(player, h), num_actions))
+                    .collect();
+                let combos = self.ranges[player].iter().zip(&strategies).zip(&reach[player])
+                    .filter(|(_, r)| **r > 0.0)
+                    .map(|((hand, probs), &r)| analysis::MixedCombo {
+                        hand: format!("{} {}", hand[0], hand[1]),
+                        reach: r,
+                        entropy: analysis::entropy(probs),
+                        frequencies: probs.clone(),
+                    })
+                    .collect();
+                let share = |p: usize| reach[p].iter().sum::<f32>() / self.initial_reach[p].iter().sum::<f32>().max(f32::MIN_POSITIVE);
+                nodes.push(analysis::NodeMixing::new(
+                    node_idx,
+                    self.tree.line_of(node_idx as u32).iter().map(|s| s.to_string()).collect(),
+                    player,
+                    children.clone().map(|c| self.action_label(c)).collect(),
+                    chance * share(0) * share(1),
+                    combos,
+                ));
+                for (a, child) in children.enumerate() {
+                    let mut reach = reach.clone();
+                    for (r, probs) in reach[player].iter_mut().zip(&strategies) {
+                        *r *= probs[a];
+                    }
+                    self.mixing_sweep(child, reach, chance, nodes);
+                }
+            }
+        }
+    }
+
+    /// Every node of the tree with the average strategy of each hand at
+    /// each decision: `{ "board", "ranges": [[combo, ...], [...]],
+    /// "iterations", "nodes": [...] }`, node `i` at index `i`. A node is `{
+    /// "node", "type": "action" | "chance" | "fold" | "showdown", "line",
+    /// "pot", "children" }`; decision nodes add "player", "infoset",
+    /// "actions" (as in `get_node_actions_at`) and "strategy", one row of
+    /// probabilities per combo of the acting player's range in `ranges`
+    /// order (`null` for combos the node's river blocks), chance nodes the
+    /// "rivers" they deal and folds the "winner".
+    pub fn strategy_tree(&self) -> serde_json::Value {
+        let nodes: Vec<serde_json::Value> = self.tree.nodes.iter().enumerate()
+            .map(|(node_idx, node)| {
+                let kind = match node.node_type {
+                    NodeType::Action => "action",
+                    NodeType::Chance => "chance",
+                    NodeType::Terminal => "fold",
+                    NodeType::Showdown => "showdown",
+                };
+                let mut entry = json!({
+                    "node": node_idx,
+                    "type": kind,
+                    "line": solver::format_line(&self.tree.line_of(node_idx as u32)),
+                    "pot": self.tree.to_amount(node.pot),
+                    "children": self.tree.children(node_idx as u32).collect::<Vec<_>>(),
+                });
+                match node.node_type {
+                    NodeType::Action => {
+                        let player = node.player as usize;
+                        let strategy: Vec<Option<Vec<f32>>> = self.ranges[player].iter().enumerate()
+                            .map(|(h, hand)| {
+                                let blocked = node.river.is_some_and(|river| hand.iter().any(|c| c.index() == river));
+                                (!blocked).then(|| self.trainer.average_strategy(node.infoset_id as usize, self.trainer_hand(player, h), node.num_actions as usize))
+                            })
+                            .collect();
+                        entry["player"] = json!(player);
+                        entry["infoset"] = json!(node.infoset_id);
+                        entry["actions"] = json!(self.get_actions_at_node(node_idx));
+                        entry["strategy"] = json!(strategy);
+                    }
+                    NodeType::Chance => {
+                        entry["rivers"] = json!(self.tree.rivers.iter().map(|&c| Card::from_index(c).to_string()).collect::<Vec<_>>());
+                    }
+                    NodeType::Terminal => entry["winner"] = json!(node.player),
+                    NodeType::Showdown => {}
+                }
+                entry
+            })
+            .collect();
+        let ranges = self.ranges.each_ref().map(|range| range.iter().map(|hand| format_range(std::slice::from_ref(hand))).collect::<Vec<_>>());
+        json!({
+            "board": self.board.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" "),
+            "ranges": ranges,
+            "iterations": self.iterations(),
+            "nodes": nodes,
+        })
+    }
+
+    /// Nodes of every infoset (see `get_infoset_map`).
+    pub fn infoset_map(&self) -> serde_json::Value {
+        let mut infosets: Vec<serde_json::Value> = vec![json!(null); self.tree.infoset_map.len()];
+        for (node_idx, node) in self.tree.nodes.iter().enumerate() {
+            if node.node_type != NodeType::Action || node.infoset_id == u32::MAX {
+                continue;
+            }
+            let line: Vec<String> = self.tree.line_of(node_idx as u32).iter().map(|step| step.to_string()).collect();
+            let entry = &mut infosets[node.infoset_id as usize];
+            if entry.is_null() {
+                *entry = json!({ "infoset": node.infoset_id, "player": node.player, "actions": node.num_actions, "nodes": [] });
+            }
+            entry["nodes"].as_array_mut().expect("set above").push(json!({ "node": node_idx, "line": line }));
+        }
+        json!(infosets)
+    }
+
+    /// Infoset id of `node_idx`, if it is a decision node.
+    pub fn node_infoset(&self, node_idx: usize) -> Result<Option<u32>, String> {
+        let node = self.tree.nodes.get(node_idx).ok_or("Invalid node index")?;
+        Ok((node.node_type == NodeType::Action && node.infoset_id != u32::MAX).then_some(node.infoset_id))
+    }
+
+    /// Bucket of every combo (see `get_abstraction_info`).
+    pub fn abstraction_info(&self) -> serde_json::Value {
+        let Some(abstraction) = &self.abstraction else {
+            return json!({ "enabled": false, "method": null, "num_buckets": null, "players": [] });
+        };
+        let players: Vec<Vec<serde_json::Value>> = (0..2)
+            .map(|p| {
+                (0..abstraction.num_buckets[p])
+                    .map(|b| {
+                        let combos: Vec<usize> = (0..self.ranges[p].len()).filter(|&h| abstraction.buckets[p][h] == b).collect();
+                        let equities = combos.iter().map(|&h| abstraction.equities[p][h]).filter(|e| !e.is_nan());
+                        let (min, max) = equities.fold((None, None), |(lo, hi): (Option<f32>, Option<f32>), e| {
+                            (Some(lo.map_or(e, |lo| lo.min(e))), Some(hi.map_or(e, |hi| hi.max(e))))
+                        });
+                        json!({
+                            "bucket": b,
+                            "weight": abstraction.reach[p][b],
+                            "min_equity": min,
+                            "max_equity": max,
+                            "hands": combos.iter().map(|&h| format!("{} {}", self.ranges[p][h][0], self.ranges[p][h][1])).collect::<Vec<_>>()
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        let method = if self.config.abstraction.is_some() { "buckets" } else { "suit_isomorphism" };
+        json!({ "enabled": true, "method": method, "num_buckets": abstraction.num_buckets, "players": players })
+    }
+
+    /// Each hand's showdown equity against the other range, weighted by the
+    /// range weights. Turn sessions pool every dealt river.
+    pub fn range_equities(&self) -> [Vec<f32>; 2] {
+        range_equities(self.showdown_matrix(), [&self.initial_reach[0], &self.initial_reach[1]])
+    }
+
+    /// Each range's overall showdown equity against the other (see
+    /// `get_total_equity`).
+    pub fn total_equity(&self) -> [f32; 2] {
+        total_equity(self.showdown_matrix(), [&self.initial_reach[0], &self.initial_reach[1]])
+    }
+
+    /// Equity distribution of both ranges (see `get_equity_distribution`).
+    pub fn equity_distribution(&self, num_buckets: usize) -> Result<serde_json::Value, String> {
+        if !(1..=100).contains(&num_buckets) {
+            return Err(format!("Number of buckets must be 1 to 100, got {}", num_buckets));
+        }
+        let equities = self.range_equities();
+        let players: Vec<serde_json::Value> = (0..2)
+            .map(|p| {
+                let buckets = analysis::equity_buckets(&equities[p], &self.initial_reach[p], num_buckets);
+                json!(buckets.iter().map(|b| json!({
+                    "low": b.low,
+                    "high": b.high,
+                    "share": b.share,
+                    "hands": b.combos.iter().map(|&h| format!("{} {}", self.ranges[p][h][0], self.ranges[p][h][1])).collect::<Vec<_>>(),
+                })).collect::<Vec<_>>())
+            })
+            .collect();
+        Ok(json!({ "players": players }))
+    }
+
+    /// Equity buckets and nut shares of both ranges (see `get_range_advantage`).
+    pub fn range_advantage(&self) -> analysis::RangeAdvantage {
+        let [eq0, eq1] = self.range_equities();
+        analysis::range_advantage([&eq0, &eq1], [&self.initial_reach[0], &self.initial_reach[1]])
+    }
+
+    /// Equity matrix blocks of the boards that reach showdown: the board
+    /// itself on the river, every dealt river on the turn.
+    fn showdown_matrix(&self) -> &[f32] {
+        showdown_blocks(&self.equity_matrix, [self.ranges[0].len(), self.ranges[1].len()], self.tree.rivers.len())
+    }
+
+    /// Equity blocks for hands at `node_idx`: the dealt river's block below
+    /// a chance node, otherwise `showdown_matrix`.
+    fn showdown_matrix_at(&self, node_idx: usize) -> &[f32] {
+        self.river_block(node_idx).unwrap_or_else(|| self.showdown_matrix())
+    }
+
+    /// Equity matrix the solver passes into the subtree at `node_idx`: the
+    /// dealt river's block below a chance node, otherwise all of it.
+    fn subtree_matrix(&self, node_idx: usize) -> &[f32] {
+        self.river_block(node_idx).unwrap_or(&self.equity_matrix)
+    }
+
+    /// Equity block of the river dealt on the way to `node_idx`, if any.
+    fn river_block(&self, node_idx: usize) -> Option<&[f32]> {
+        let block = self.ranges[0].len() * self.ranges[1].len();
+        let river = self.tree.nodes[node_idx].river?;
+        let i = self.tree.rivers.iter().position(|&c| c == river)?;
+        Some(&self.equity_matrix[(i + 1) * block..(i + 2) * block])
+    }
+
+    /// Nodes visited by an action history, root first. Actions are matched
+    /// as in `get_strategy_for_history`; a chance node takes `"river <card>"`.
+    /// Any entry may be a compact line instead (see `solver::line`).
+    fn history_path<S: AsRef<str>>(&self, history: &[S]) -> Result<Vec<usize>, String> {
+        debug!("[get_strategy_for_history] History: {:?}", history.iter().map(|s| s.as_ref()).collect::<Vec<_>>());
+
+        let line = solver::parse_history(history).map_err(|e| format!("Invalid history: {}", e))?;
+
+        // Start at root node
+        let mut path = vec![0];
+        for step in &line {
+            let node_idx = *path.last().expect("path starts at the root");
+            let current_node = &self.tree.nodes[node_idx];
+
+            let found_child = self.tree.find_child(node_idx as u32, step, f32::INFINITY)
+                .map(|child| child as usize);
+
+            match found_child {
+                Some(child_idx) => {
+                    trace!("[get_strategy_for_history] Found child at index {}, next player={}",
+                           child_idx, self.tree.nodes[child_idx].player);
+                    path.push(child_idx);
+                }
+                None => {
+                    return Err(format!(
+                        "Action '{}' not found at node {} (player={}). Available actions: {}",
+                        step, node_idx, current_node.player, self.get_available_actions_at_node(node_idx)
+                    ));
+                }
+            }
+        }
+        Ok(path)
+    }
+
+    /// Every decision node with its history (as in `get_strategy_for_history`,
+    /// amounts exact) and how often the acting player takes each action,
+    /// averaged over their range weighted by reach. Nodes no hand reaches
+    /// average over the range unweighted.
+    fn action_frequencies(&self) -> Vec<(usize, Vec<String>, Vec<f32>)> {
+        let trainer = self.combo_trainer(&self.trainer);
+        let mut result = Vec::new();
+        self.walk_reach(&trainer, |node_idx, line, reach, _| {
+            let node = &self.tree.nodes[node_idx];
+            if node.node_type != NodeType::Action {
+                return;
+            }
+            let player = node.player as usize;
+            let total: f32 = reach[player].iter().sum();
+            let weight = |h: usize| if total > 0.0 { reach[player][h] / total } else { 1.0 / reach[player].len() as f32 };
+            let mut frequencies = vec![0.0; node.num_actions as usize];
+            for h in 0..reach[player].len() {
+                let probs = trainer.average_strategy(node.infoset_id as usize, h, node.num_actions as usize);
+                for (f, p) in frequencies.iter_mut().zip(probs) {
+                    *f += weight(h) * p;
+                }
+            }
+            result.push((node_idx, line.to_vec(), frequencies));
+        });
+        result.sort_by_key(|(node_idx, _, _)| *node_idx);
+        result
+    }
+
+    /// Walk the tree depth-first, calling `visit` on every node before its
+    /// children with the node's history (as in `get_strategy_for_history`,
+    /// amounts exact), each player's reach of every combo (range weight
+    /// times their average strategy in `trainer`, a combo-level trainer,
+    /// along the line; zero for combos holding a dealt river) and the
+    /// product of the weights of the rivers dealt on the way.
+    fn walk_reach(&self, trainer: &DCFRTrainer, mut visit: impl FnMut(usize, &[String], &[Vec<f32>; 2], f32)) {
+        let mut stack = vec![(0usize, Vec::<String>::new(), self.initial_reach.clone(), 1.0)];
+        while let Some((node_idx, line, reach, chance)) = stack.pop() {
+            visit(node_idx, &line, &reach, chance);
+            let node = &self.tree.nodes[node_idx];
+            let children = self.tree.children(node_idx as u32).map(|c| c as usize);
+            match node.node_type {
+                NodeType::Action => {
+                    let player = node.player as usize;
+                    let probs: Vec<Vec<f32>> = (0..reach[player].len())
+                        .map(|h| trainer.average_strategy(node.infoset_id as usize, h, node.num_actions as usize))
+                        .collect();
+                    for (a, child) in children.enumerate() {
+                        let mut next = reach.clone();
+                        for (r, p) in next[player].iter_mut().zip(&probs) {
+                            *r *= p[a];
+                        }
+                        let mut child_line = line.clone();
+                        child_line.push(self.action_label(child));
+                        stack.push((child, child_line, next, chance));
+                    }
+                }
+                NodeType::Chance => {
+                    for child in children {
+                        let river = Card::from_index(self.tree.nodes[child].river.expect("chance children are dealt a river"));
+                        let mut next = reach.clone();
+                        for (r, range) in next.iter_mut().zip(&self.ranges) {
+                            for (w, hand) in r.iter_mut().zip(range) {
+                                if hand_mask(hand) & river.bitmask() != 0 {
+                                    *w = 0.0;
+                                }
+                            }
+                        }
+                        let mut child_line = line.clone();
+                        child_line.push(self.action_label(child));
+                        stack.push((child, child_line, next, chance * self.tree.river_weight));
+                    }
+                }
+                NodeType::Terminal | NodeType::Showdown => {}
+            }
+        }
+    }
+
+    /// History entry leading to `node_idx`: `"bet 75"` with the exact
+    /// amount, `"check"`, or `"river Kd"`.
+    fn action_label(&self, node_idx: usize) -> String {
+        self.tree.step_to(node_idx as u32).expect("only the root has no incoming step").to_string()
+    }
+
+    /// Start from `source`'s solve of a tree this session's was pruned from:
+    /// every infoset takes the regrets and strategy sums of the matching
+    /// infoset there, and the iteration count carries over.
+    fn warm_start(&mut self, source: &SolverSession) {
+        self.trainer.resume_at(source.trainer.iterations);
+        let mut stack = vec![(0usize, 0usize)];
+        while let Some((node_idx, source_idx)) = stack.pop() {
+            let (node, source_node) = (&self.tree.nodes[node_idx], &source.tree.nodes[source_idx]);
+            let mut actions = Vec::with_capacity(node.num_actions as usize);
+            for child in self.tree.children(node_idx as u32) {
+                let c = &self.tree.nodes[child as usize];
+                let matching = source.tree.children(source_idx as u32).find(|&s| {
+                    let s = &source.tree.nodes[s as usize];
+                    s.action_from_parent == c.action_from_parent && s.amount_from_parent == c.amount_from_parent && s.river == c.river
+                }).expect("a pruned tree only drops actions");
+                actions.push((matching - source_node.children_start) as usize);
+                stack.push((child as usize, matching as usize));
+            }
+            if node.node_type == NodeType::Action {
+                self.trainer.copy_infoset(&source.trainer, source_node.infoset_id as usize, node.infoset_id as usize, &actions, true);
+            }
+        }
+    }
+
+    /// Start from `source`'s solve of a tree built with other sizes: nodes
+    /// are matched down from the root by line, a bet or raise to the
+    /// unused one of the same kind nearest in amount, within
+    /// `TRANSFER_TOLERANCE` of the pot. Infosets whose node and every action
+    /// matched take the strategy sums (and regrets if `regrets` is set) of
+    /// the matching infoset; the rest start from scratch, i.e. uniform.
+    /// Returns the numbers of matched nodes and infosets.
+    fn transfer_from(&mut self, source: &SolverSession, regrets: bool) -> (usize, usize) {
+        self.trainer.resume_at(source.trainer.iterations);
+        let mut copied = vec![false; self.tree.infoset_map.len()];
+        let mut matched_nodes = 1;
+        let mut stack = vec![(0usize, 0usize)];
+        while let Some((node_idx, source_idx)) = stack.pop() {
+            let (node, source_node) = (&self.tree.nodes[node_idx], &source.tree.nodes[source_idx]);
+            let tolerance = TRANSFER_TOLERANCE * self.tree.to_amount(node.pot);
+            // Every same-kind pair within the tolerance, closest first
+            let mut pairs = Vec::new();
+            for child in self.tree.children(node_idx as u32) {
+                let c = &self.tree.nodes[child as usize];
+                for s in source.tree.children(source_idx as u32) {
+                    let s_node = &source.tree.nodes[s as usize];
+                    let distance = (self.tree.to_amount(c.amount_from_parent) - source.tree.to_amount(s_node.amount_from_parent)).abs();
+                    if s_node.action_from_parent == c.action_from_parent && s_node.river == c.river && distance <= tolerance {
+                        pairs.push((distance, child, s));
+                    }
+                }
+            }
+            pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut actions: Vec<Option<usize>> = vec![None; node.num_actions as usize];
+            let mut taken = vec![false; source_node.num_actions as usize];
+            for (_, child, s) in pairs {
+                let (a, from) = ((child - node.children_start) as usize, (s - source_node.children_start) as usize);
+                if actions[a].is_none() && !taken[from] {
+                    actions[a] = Some(from);
+                    taken[from] = true;
+                    stack.push((child as usize, s as usize));
+                    matched_nodes += 1;
+                }
+            }
+            if node.node_type == NodeType::Action && source_node.player == node.player
+                && let Some(actions) = actions.into_iter().collect::<Option<Vec<usize>>>()
+            {
+                self.trainer.copy_infoset(&source.trainer, source_node.infoset_id as usize, node.infoset_id as usize, &actions, regrets);
+                copied[node.infoset_id as usize] = true;
+            }
+        }
+        (matched_nodes, copied.iter().filter(|&&c| c).count())
+    }
+
+    /// Nodes from the root to `node_idx`, both included.
+    fn path_to(&self, node_idx: usize) -> Vec<usize> {
+        self.tree.path_to(node_idx as u32).into_iter().map(|n| n as usize).collect()
+    }
+
+    /// Card mask of the board at `node_idx`, including a dealt river.
+    fn dead_cards(&self, node_idx: usize) -> u64 {
+        let board = hand_mask(&self.board);
+        match self.tree.nodes[node_idx].river {
+            Some(river) => board | Card::from_index(river).bitmask(),
+            None => board,
+        }
+    }
+
+    /// Each player's reach for every hand along `path` (root first): the
+    /// product of their average strategy at their own decisions, zero for
+    /// hands holding a river card dealt on the way.
+    fn path_reach(&self, path: &[usize]) -> [Vec<f32>; 2] {
+        self.path_reach_with(&self.trainer, path)
+    }
+
+    /// `path_reach` with the average strategies of `trainer` (one of this
+    /// session's, so bucket-level under an abstraction).
+    fn path_reach_with(&self, trainer: &DCFRTrainer, path: &[usize]) -> [Vec<f32>; 2] {
+        let mut reach = self.initial_reach.clone();
+        for step in path.windows(2) {
+            let (parent, child) = (&self.tree.nodes[step[0]], &self.tree.nodes[step[1]]);
+            match parent.node_type {
+                NodeType::Action => {
+                    let player = parent.player as usize;
+                    let a = step[1] - parent.children_start as usize;
+                    for (h, r) in reach[player].iter_mut().enumerate() {
+                        *r *= trainer.average_strategy(parent.infoset_id as usize, self.trainer_hand(player, h), parent.num_actions as usize)[a];
+                    }
+                }
+                NodeType::Chance => {
+                    let river = child.river.expect("chance children are dealt a river");
+                    for (r, range) in reach.iter_mut().zip(&self.ranges) {
+                        for (r, hand) in r.iter_mut().zip(range) {
+                            if hand.iter().any(|c| c.index() == river) {
+                                *r = 0.0;
+                            }
+                        }
+                    }
+                }
+                NodeType::Terminal | NodeType::Showdown => {}
+            }
+        }
+        reach
+    }
+
+    /// Index of the combo made of `cards` (in either order) in `player`'s range.
+    fn find_combo(&self, player: usize, cards: &[Card]) -> Option<usize> {
+        self.ranges[player].iter().position(|h| (h[0] == cards[0] && h[1] == cards[1]) || (h[0] == cards[1] && h[1] == cards[0]))
+    }
+
+    /// Trainer index of combo `h` of `player`: its bucket under an
+    /// abstraction, otherwise the combo itself.
+    fn trainer_hand(&self, player: usize, h: usize) -> usize {
+        self.abstraction.as_ref().map_or(h, |a| a.buckets[player][h])
+    }
+
+    /// `trainer` (one of this session's) with a strategy for every combo,
+    /// for evaluating the real game with best responses.
+    fn combo_trainer<'a>(&self, trainer: &'a DCFRTrainer) -> Cow<'a, DCFRTrainer> {
+        match &self.abstraction {
+            Some(abstraction) => Cow::Owned(abstraction.expand(&self.tree, trainer)),
+            None => Cow::Borrowed(trainer),
+        }
+    }
+
+    /// Replace the range weights of a fresh session, regrouping the combos
+    /// the trainer plays and recompiling the profiles to match.
+    fn set_initial_reach(&mut self, reach: [Vec<f32>; 2]) -> Result<(), String> {
+        self.initial_reach = reach;
+        self.abstraction = build_abstraction(&self.config, &self.board, &self.tree, &self.ranges, &self.equity_matrix, &self.initial_reach);
+        let trainer_hands = self.abstraction.as_ref().map_or([self.ranges[0].len(), self.ranges[1].len()], |a| a.num_buckets);
+        if self.trainer.num_hands() != trainer_hands {
+            self.trainer = DCFRTrainer::new(self.tree.infoset_map.len(), self.tree.max_actions(), trainer_hands);
+        }
+        self.apply_profiles().map(|_| ())
+    }
+
+    /// Compile every profile in the config and lock the trainer to them,
+    /// replacing earlier locks. Returns each profile's locks. Fails when
+    /// rules leave a hand no action.
+    fn apply_profiles(&mut self) -> Result<Vec<(usize, Vec<NodeLock>)>, String> {
+        self.trainer.clear_locks();
+        if self.config.profiles.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Equities against the opponent's range on each board a node can have
+        let weights = [&self.initial_reach[0][..], &self.initial_reach[1][..]];
+        let block = self.ranges[0].len() * self.ranges[1].len();
+        let mut equities: HashMap<Option<u8>, [Vec<f32>; 2]> = HashMap::new();
+        equities.insert(None, range_equities(self.showdown_matrix(), weights));
+        for (i, &river) in self.tree.rivers.iter().enumerate() {
+            equities.insert(Some(river), range_equities(&self.equity_matrix[(i + 1) * block..(i + 2) * block], weights));
+        }
+        let equity_at = |node: usize, player: usize| -> &[f32] { &equities[&self.tree.nodes[node].river][player] };
+
+        let mut compiled = Vec::new();
+        let mut masks: HashMap<(usize, usize), u32> = HashMap::new();
+        for profile in &self.config.profiles {
+            let player = profile.player;
+            if player > 1 {
+                return Err(format!("Invalid player {} in profile", player));
+            }
+            let locks = solver::profile::compile(&self.tree, player, &profile.rules, |node| equity_at(node, player), weights[player])?;
+            for lock in &locks {
+                for &h in &lock.hands {
+                    let mask = masks.entry((lock.node, h)).or_insert(lock.allowed);
+                    *mask &= lock.allowed;
+                    if *mask == 0 {
+                        let hand = &self.ranges[player][h];
+                        return Err(format!("Profile rules leave {} {} no action at node {}", hand[0], hand[1], lock.node));
+                    }
+                }
+            }
+            compiled.push((player, locks));
+        }
+
+        // A trainer hand standing for several combos is locked only when
+        // its live combos all carry the same lock
+        for (&(node_idx, h), &mask) in &masks {
+            let node = &self.tree.nodes[node_idx];
+            let player = node.player as usize;
+            let t = self.trainer_hand(player, h);
+            let agree = (0..self.ranges[player].len())
+                .filter(|&other| self.trainer_hand(player, other) == t && !equity_at(node_idx, player)[other].is_nan())
+                .all(|other| masks.get(&(node_idx, other)) == Some(&mask));
+            if agree {
+                self.trainer.lock_actions(node.infoset_id as usize, t, mask);
+            }
+        }
+        debug!("[SolverSession] {} hand locks from {} profiles", masks.len(), compiled.len());
+        Ok(compiled)
+    }
+
+    /// Cache key of this session's spot (see [`solver::hash::solve_key`]).
+    pub fn solve_key(&self) -> String {
+        solver::hash::solve_key(&self.config, &self.board, [&self.ranges[0], &self.ranges[1]], [&self.initial_reach[0], &self.initial_reach[1]])
+    }
+
+    /// Snapshot of the spot and solver state in the shared solution format.
+    pub fn to_solution(&self) -> Solution {
+        Solution {
+            config: self.config.clone(),
+            board: self.board.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" "),
+            ranges: [0, 1].map(|p| poker::range::format_weighted_range(&self.ranges[p], &self.initial_reach[p])),
+            tree_hash: self.tree.structure_hash(),
+            iterations: self.trainer.iterations as u64,
+            regrets: self.trainer.regrets.clone(),
+            strategy_sum: self.trainer.strategy_sum.clone(),
+            locks: self.trainer.locked_hands().map(|(i, h, allowed)| [i as u32, h as u32, allowed]).collect(),
+        }
+    }
+
+    /// `to_solution` with each hand's average strategy in place of the
+    /// strategy sums and no regrets (see [`Solution::is_browse_only`]).
+    pub fn to_browse_solution(&self) -> Solution {
+        let mut strategy_sum = self.trainer.strategy_sum.clone();
+        for row in strategy_sum.chunks_exact_mut(self.trainer.max_actions().max(1)) {
+            let total: f32 = row.iter().filter(|&&s| s > 0.0).sum();
+            for s in row.iter_mut() {
+                *s = if total > 0.0 && *s > 0.0 { *s / total } else { 0.0 };
+            }
+        }
+        Solution { regrets: Vec::new(), strategy_sum, ..self.to_solution() }
+    }
+
+    /// Rebuild the session a solution was produced from and restore its state.
+    pub fn from_solution(solution: &Solution) -> Result<SolverSession, String> {
+        let mut session = Self::from_config(
+            solution.config.clone(), &solution.board, &solution.ranges[0], &solution.ranges[1],
+        )?;
+        session.restore_solution(solution)?;
+        Ok(session)
+    }
+
+    /// Load solver state into this session. Fails if the solution was
+    /// produced on a different tree or range sizes.
+    pub fn restore_solution(&mut self, solution: &Solution) -> Result<(), String> {
+        solution.check_tree(&self.tree, self.trainer.strategy_sum.len())?;
+        let board: Vec<Card> = solution.board.split_whitespace().filter_map(Card::from_str).collect();
+        if solver::hash::solve_key_str(&solution.config, &board, [&solution.ranges[0], &solution.ranges[1]]) != self.solve_key() {
+            return Err("Solution was solved for a different spot (config, board or ranges)".to_string());
+        }
+        // Locks are compiled from the config's profiles; stored ones replace them
+        if !solution.locks.is_empty() {
+            let (num_infosets, num_hands) = (self.tree.infoset_map.len(), self.trainer.block_len() / self.trainer.max_actions());
+            if let Some(&[i, h, _]) = solution.locks.iter().find(|&&[i, h, _]| i as usize >= num_infosets || h as usize >= num_hands) {
+                return Err(format!("Solution locks hand {} at infoset {}, outside this tree", h, i));
+            }
+            self.trainer.clear_locks();
+            for &[i, h, allowed] in &solution.locks {
+                self.trainer.lock_actions(i as usize, h as usize, allowed);
+            }
+        }
+        if solution.is_browse_only() {
+            self.trainer.regrets.fill(0.0);
+        } else {
+            self.trainer.regrets.copy_from_slice(&solution.regrets);
+        }
+        self.trainer.strategy_sum.copy_from_slice(&solution.strategy_sum);
+        self.trainer.iterations = solution.iterations as usize;
+        self.trainer.mark_changed(0..self.tree.infoset_map.len());
+        self.convergence.clear();
+        Ok(())
+    }
+
+    /// Infosets whose regrets or strategy sum were written after
+    /// `since_iteration`, with their current blocks; the others were only
+    /// discounted, which restoring the delta redoes. Applied in order on top
+    /// of the checkpoint they follow, deltas rebuild the trainer exactly.
+    pub fn to_snapshot_delta(&self, since_iteration: usize) -> SnapshotDelta {
+        let block = self.trainer.block_len();
+        let changed = self.trainer.changed_since(since_iteration);
+        let mut regrets = Vec::with_capacity(changed.len() * block);
+        let mut strategy_sum = Vec::with_capacity(changed.len() * block);
+        for &i in &changed {
+            regrets.extend_from_slice(&self.trainer.regrets[i * block..(i + 1) * block]);
+            strategy_sum.extend_from_slice(&self.trainer.strategy_sum[i * block..(i + 1) * block]);
+        }
+
+        SnapshotDelta {
+            tree_hash: self.tree.structure_hash(),
+            since_iteration: since_iteration as u64,
+            iterations: self.trainer.iterations as u64,
+            block_len: block as u32,
+            infosets: changed.into_iter().map(|i| i as u32).collect(),
+            regrets,
+            strategy_sum,
+        }
+    }
+
+    /// Apply a delta taken from this spot on top of the current state.
+    pub fn restore_snapshot_delta(&mut self, delta: &SnapshotDelta) -> Result<(), String> {
+        if delta.tree_hash != self.tree.structure_hash() {
+            return Err("Snapshot delta was taken on a different game tree".to_string());
+        }
+        let block = self.trainer.block_len();
+        if delta.block_len as usize != block {
+            return Err(format!("Snapshot delta blocks have {} entries, this session needs {}", delta.block_len, block));
+        }
+        let current = self.trainer.iterations as u64;
+        if delta.since_iteration > current {
+            return Err(format!(
+                "Snapshot delta starts after iteration {} but this session is at {}; apply deltas in order",
+                delta.since_iteration, current
+            ));
+        }
+        if delta.iterations < current {
+            return Err(format!("Snapshot delta (iteration {}) is older than this session ({})", delta.iterations, current));
+        }
+        let num_infosets = self.tree.infoset_map.len();
+        if let Some(&bad) = delta.infosets.iter().find(|&&i| i as usize >= num_infosets) {
+            return Err(format!("Snapshot delta references infoset {} of {}", bad, num_infosets));
+        }
+
+        for (k, &i) in delta.infosets.iter().enumerate() {
+            let (dst, src) = (i as usize * block, k * block);
+            self.trainer.regrets[dst..dst + block].copy_from_slice(&delta.regrets[src..src + block]);
+            self.trainer.strategy_sum[dst..dst + block].copy_from_slice(&delta.strategy_sum[src..src + block]);
+        }
+        self.trainer.iterations = delta.iterations as usize;
+        self.trainer.mark_changed(delta.infosets.iter().map(|&i| i as usize));
+        // The infosets the delta leaves out were only discounted meanwhile
+        self.trainer.settle_all(self.trainer.iterations);
+        Ok(())
+    }
+
+    /// Write the solution to `path` (see [`solver::io`] for the format).
+    pub fn save_solution(&self, path: impl AsRef<std::path::Path>) -> Result<(), String> {
+        solver::io::save_solution(path, &self.to_solution())
+    }
+
+    /// Write the browse-only solution (see `export_browse_solution`) to `path`.
+    pub fn save_browse_solution(&self, path: impl AsRef<std::path::Path>) -> Result<(), String> {
+        solver::io::save_solution(path, &self.to_browse_solution())
+    }
+
+    /// Rebuild a session from a file written by [`SolverSession::save_solution`]
+    /// or a browser `export_solution` download.
+    pub fn load_solution(path: impl AsRef<std::path::Path>) -> Result<SolverSession, String> {
+        Self::from_solution(&solver::io::load_solution(path)?)
+    }
+}
+
+/// Grouping of combos the trainer plays for `config`: equity buckets, or
+/// classes of suit-isomorphic combos. `None` when every combo is solved on
+/// its own, including when the spot has no suit symmetry to exploit.
+fn build_abstraction(
+    config: &GameConfig,
+    board: &[Card],
+    tree: &GameTree,
+    ranges: &[Vec<Vec<Card>>; 2],
+    equity_matrix: &[f32],
+    reach: &[Vec<f32>; 2],
+) -> Option<Abstraction> {
+    let num_hands = [ranges[0].len(), ranges[1].len()];
+    let showdown = showdown_blocks(equity_matrix, num_hands, tree.rivers.len());
+    if let Some(abstraction) = &config.abstraction {
+        return Some(Abstraction::new(abstraction.buckets_for(board.len()), equity_matrix, showdown, reach));
+    }
+    if !config.suit_isomorphism {
+        return None;
+    }
+
+    // Every dealt river has its own subtree, so a symmetry must leave each one in place
+    let rivers: Vec<Card> = tree.rivers.iter().map(|&c| Card::from_index(c)).collect();
+    let symmetries = isomorphism::board_symmetries(board, &rivers);
+    let classes = solver::abstraction::suit_classes(&symmetries, ranges, reach);
+    let merged = classes.iter().zip(num_hands).any(|(c, n)| c.iter().max().is_some_and(|&m| m + 1 < n));
+    if !merged {
+        return None;
+    }
+    let abstraction = Abstraction::from_buckets(classes, equity_matrix, showdown, reach);
+    info!("[SolverSession::new] Suit isomorphism: {:?} combos solved as {:?} classes", num_hands, abstraction.num_buckets);
+    Some(abstraction)
+}
+
+/// How likely a line is for one combo, as `ev_decomposition` walks it: the
+/// combo's own action probabilities, the dealt rivers' weights, and the
+/// opposing hands' reach.
+#[derive(Clone)]
+struct LineWeight {
+    own: f32,
+    chance: f32,
+    opp_reach: Vec<f32>,
+}
+
+/// Equity matrix blocks of the boards that reach showdown (see
+/// `SolverSession::showdown_matrix`), for ranges of `num_hands` combos and
+/// a tree dealing `num_rivers` rivers.
+fn showdown_blocks(equity_matrix: &[f32], num_hands: [usize; 2], num_rivers: usize) -> &[f32] {
+    if num_rivers == 0 {
+        equity_matrix
+    } else {
+        &equity_matrix[num_hands[0] * num_hands[1]..]
+    }
+}
+
+/// Which hands a key of a user strategy covers.
+enum UserHand {
+    /// One combo, as a card mask.
+    Combo(u64),
+    /// A preflop class, by grid index.
+    Class(usize),
+    Any,
+}
+
+impl UserHand {
+    fn matches(&self, mask: u64, hand: &[Card]) -> bool {
+        match *self {
+            UserHand::Combo(combo) => combo == mask,
+            UserHand::Class(class) => poker::preflop::class_index(&poker::range::hand_class(hand)) == Some(class),
+            UserHand::Any => true,
+        }
+    }
+}
+
+/// Index drawn from the distribution `probs` (summing to 1), advancing the
+/// SplitMix64 `state`.
+fn sample_index(probs: &[f32], state: &mut u64) -> usize {
+    // Uniform in [0, 1) from the top 53 bits
+    let u = (solver::builder::splitmix64(state) >> 11) as f64 / (1u64 << 53) as f64;
+    let mut cumulative = 0.0;
+    probs.iter()
+        .position(|&p| {
+            cumulative += p as f64;
+            u < cumulative
+        })
+        // Rounding can leave the total a hair under 1
+        .unwrap_or_else(|| probs.iter().rposition(|&p| p > 0.0).unwrap_or(probs.len() - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_greet() {
+        let result = greet("World");
+        assert!(result.contains("Hello, World!"));
+    }
+
+    #[test]
+    fn test_version() {
+        let v = version();
+        assert!(!v.is_empty());
+    }
+
+    #[test]
+    fn test_evaluation_wasm() {
+        let score = test_evaluation("As Ks Qs Js Ts");
+        assert_eq!(score, 1, "Royal flush should be 1");
+    }
+
+    #[test]
+    fn test_hand_name() {
+        assert_eq!(get_hand_name(1), "Royal Flush");
+        assert_eq!(get_hand_name(5), "Straight Flush");
+        assert_eq!(get_hand_name(100), "Four of a Kind");
+        assert_eq!(get_hand_name(200), "Full House");
+    }
+
+    #[test]
+    fn test_step_for_ms_runs_until_budget() {
+        let mut session = tiny_session();
+        let start = platform::now_ms();
+        let done = session.step_for_ms(5.0);
+        assert!(done >= 1);
+        assert_eq!(session.iterations(), done);
+        assert!(platform::now_ms() - start >= 5.0);
+    }
+
+    fn tiny_session() -> SolverSession {
+        let config = GameConfig {
+            initial_pot: 100.0,
+            stacks: [100.0, 100.0],
+            bet_sizes: vec![BetSize::PotFraction(1.0)],
+            raise_sizes: vec![].into(),
+            river_bet_sizes: None,
+            river_raise_sizes: None,
+            river_cards: RiverCards::All,
+            raise_limit: 0,
+            chip_scale: 100,
+            icm: None,
+            bounty: None,
+            abstraction: None,
+            suit_isomorphism: false,
+            profiles: Vec::new(),
+            size_overrides: Vec::new(),
+            sizing_rules: Vec::new(),
+            donk_sizes: None,
+            player_sizes: Default::default(),
+            add_allin: true,
+            variant: GameVariant::Holdem,
+        };
+        SolverSession::from_config(config, "Ks Th 7s 4d 2c", "As Ad, 6h 5h", "Kc Kd, 9s 8s").unwrap()
+    }
+
+    #[test]
+    fn test_solution_round_trip() {
+        let mut solved = tiny_session();
+        solved.step(20);
+        let bytes = solved.export_solution();
+
+        let restored = SolverSession::from_solution(&Solution::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(restored.iterations(), 20);
+        assert_eq!(restored.node_strategy_table(0), solved.node_strategy_table(0));
+        assert_eq!(restored.export_solution(), bytes);
+    }
+
+    #[test]
+    fn test_snapshot_deltas_replay_onto_checkpoint() {
+        let mut live = tiny_session();
+        live.step(10);
+        let checkpoint = live.export_solution();
+
+        let mut deltas = Vec::new();
+        let mut since = live.iterations();
+        for _ in 0..8 {
+            live.step(15);
+            deltas.push(live.snapshot_delta(since));
+            since = live.iterations();
+        }
+
+        let mut replay = tiny_session();
+        replay.restore_solution(&Solution::from_bytes(&checkpoint).unwrap()).unwrap();
+        for delta in &deltas {
+            replay.restore_snapshot_delta(&SnapshotDelta::from_bytes(delta).unwrap()).unwrap();
+        }
+        assert_eq!(replay.iterations(), live.iterations());
+        assert_eq!(replay.trainer.regrets, live.trainer.regrets);
+        assert_eq!(replay.trainer.strategy_sum, live.trainer.strategy_sum);
+
+        // The replay resumes exactly where the live solve is
+        live.step(7);
+        replay.step(7);
+        assert_eq!(replay.trainer.regrets, live.trainer.regrets);
+        assert_eq!(replay.trainer.strategy_sum, live.trainer.strategy_sum);
+
+        // Nothing was written since the last iteration
+        assert!(SnapshotDelta::from_bytes(&live.snapshot_delta(live.iterations())).unwrap().infosets.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_delta_skips_infosets_out_of_reach() {
+        // Narrow ranges settle on pure strategies, so whole turn and river
+        // lines are out of both players' reach and only owe the discount
+        let config = turn_session(300.0, RiverCards::All).config;
+        let mut live = SolverSession::from_config(config, "Ks Th 7s 4d", "As Ad, 7h 7c", "Qh Jh, 3c 2c").unwrap();
+        live.step(20);
+        let checkpoint = live.export_solution();
+        live.step(3);
+        let delta = live.snapshot_delta(20);
+        let infosets = SnapshotDelta::from_bytes(&delta).unwrap().infosets.len();
+        assert!(infosets < live.num_infosets(), "{} of {} infosets", infosets, live.num_infosets());
+        assert!(delta.len() < checkpoint.len(), "delta {} bytes, checkpoint {}", delta.len(), checkpoint.len());
+
+        // Restoring discounts the infosets the delta leaves out
+        let mut replay = SolverSession::from_solution(&Solution::from_bytes(&checkpoint).unwrap()).unwrap();
+        replay.restore_snapshot_delta(&SnapshotDelta::from_bytes(&delta).unwrap()).unwrap();
+        assert_eq!(replay.trainer.regrets, live.trainer.regrets);
+        assert_eq!(replay.trainer.strategy_sum, live.trainer.strategy_sum);
+    }
+
+    #[test]
+    fn test_snapshot_delta_rejects_gaps() {
+        let mut live = tiny_session();
+        live.step(10);
+        let checkpoint = live.export_solution();
+        live.step(10);
+        live.step(10);
+        let late = live.snapshot_delta(20);
+
+        let mut replay = tiny_session();
+        replay.restore_solution(&Solution::from_bytes(&checkpoint).unwrap()).unwrap();
+        let err = replay.restore_snapshot_delta(&SnapshotDelta::from_bytes(&late).unwrap()).unwrap_err();
+        assert!(err.contains("in order"), "{}", err);
+    }
+
+    #[test]
+    fn test_restore_rejects_other_spot() {
+        let mut solution = tiny_session().to_solution();
+        solution.ranges[1] = solution.ranges[1].replace("Kc Kd", "Kh Kd");
+        let err = tiny_session().restore_solution(&solution).unwrap_err();
+        assert!(err.contains("different spot"), "{}", err);
+        // Reformatting the same spot is fine
+        let mut solution = tiny_session().to_solution();
+        solution.board = format!("  {}", solution.board.split(' ').rev().collect::<Vec<_>>().join(" "));
+        tiny_session().restore_solution(&solution).unwrap();
+    }
+
+    #[test]
+    fn test_restore_rejects_other_tree() {
+        let mut solution = tiny_session().to_solution();
+        solution.config.bet_sizes = vec![BetSize::PotFraction(0.5)];
+        let err = SolverSession::from_solution(&solution).err().unwrap();
+        assert!(err.contains("different game tree"), "{}", err);
+    }
+
+    fn turn_session(stacks: f32, river_cards: RiverCards) -> SolverSession {
+        let config = GameConfig {
+            initial_pot: 100.0,
+            stacks: [stacks, stacks],
+            bet_sizes: vec![BetSize::PotFraction(1.0)],
+            raise_sizes: vec![].into(),
+            river_bet_sizes: Some(vec![BetSize::PotFraction(0.5)]),
+            river_raise_sizes: None,
+            river_cards,
+            raise_limit: 0,
+            chip_scale: 100,
+            icm: None,
+            bounty: None,
+            abstraction: None,
+            suit_isomorphism: false,
+            profiles: Vec::new(),
+            size_overrides: Vec::new(),
+            sizing_rules: Vec::new(),
+            donk_sizes: None,
+            player_sizes: Default::default(),
+            add_allin: true,
+            variant: GameVariant::Holdem,
+        };
+        SolverSession::from_config(config, "Ks Th 7s 4d", "As Ad, 6h 5h, Qs Js", "Kc Kd, 9s 8s, Ah 4h").unwrap()
+    }
+
+    #[test]
+    fn test_turn_session_solves_through_the_river() {
+        let mut session = turn_session(300.0, RiverCards::All);
+        assert_eq!(session.tree.rivers.len(), 48);
+        let uniform = session.exploitability();
+        session.step(300);
+        let solved = session.exploitability();
+        assert!(solved < uniform / 20.0 && solved < 0.02 * session.initial_pot(), "exploitability {} (uniform {})", solved, uniform);
+
+        // Check-check deals the river; player 0 then bets half the pot.
+        let chance = session.strategy_for_history(&["check", "check"]).unwrap();
+        assert_eq!(chance["isChance"], true);
+        assert_eq!(chance["rivers"].as_array().unwrap().len(), 48);
+        let info = session.strategy_for_history(&["check", "check", "river Qd", "bet 50"]).unwrap();
+        assert_eq!(info["river"], "Qd");
+        assert_eq!(info["player"], 1);
+        assert_eq!(info["pot"], 150.0);
+        let err = session.strategy_for_history(&["check", "check", "river Ks"]).unwrap_err();
+        assert!(err.contains("river 2c"), "{}", err);
+
+        // Hands holding the river card have no strategy there.
+        let ace_river = session.strategy_for_history(&["check", "check", "river Ad"]).unwrap();
+        let table = session.node_strategy_table(ace_river["nodeIdx"].as_u64().unwrap() as usize).unwrap();
+        assert!(table["strategies"].get("As Ad").is_none() && table["strategies"].get("6h 5h").is_some());
+
+        let report = session.river_strategies(&["check", "check"]).unwrap();
+        let rivers = report["rivers"].as_array().unwrap();
+        assert_eq!(rivers.len(), 48);
+        for river in rivers {
+            let total: f64 = river["frequencies"].as_array().unwrap().iter().map(|f| f.as_f64().unwrap()).sum();
+            assert!((total - 1.0).abs() < 1e-4, "{}", river);
+        }
+        assert_eq!(rivers[0]["card"], "2c");
+        assert_eq!(rivers[0]["actions"][1]["amount"], 50.0);
+
+        // The runout report adds what each river is worth, against the average
+        let report = session.runout_report(&["check", "check"]).unwrap();
+        assert_eq!((report["player"].as_u64(), report["actions"][1]["amount"].as_f64()), (Some(0), Some(50.0)));
+        let average: Vec<f64> = report["average"]["ev"].as_array().unwrap().iter().map(|e| e.as_f64().unwrap()).collect();
+        assert!((average[0] + average[1]).abs() < 1e-3, "{:?}", average);
+        let rivers = report["rivers"].as_array().unwrap();
+        assert_eq!(rivers.len(), 48);
+        let evs: Vec<f64> = rivers.iter().map(|r| r["ev"][0].as_f64().unwrap()).collect();
+        assert!(evs.iter().any(|&e| e < average[0]) && evs.iter().any(|&e| e > average[0]));
+        for river in rivers {
+            let [ev0, ev1] = [0, 1].map(|p| river["ev"][p].as_f64().unwrap());
+            assert!((ev0 + ev1).abs() < 1e-3, "{}", river);
+            assert!((river["ev_shift"][0].as_f64().unwrap() - (ev0 - average[0])).abs() < 1e-4);
+            let shift: f64 = river["frequency_shift"].as_array().unwrap().iter().map(|f| f.as_f64().unwrap()).sum();
+            assert!(shift.abs() < 1e-4, "{}", river);
+        }
+        assert_eq!(rivers[0]["card"], "2c");
+
+        // An all-in call on the turn runs the river out with no betting.
+        let runout = session.river_strategies(&["bet 300", "call"]).unwrap();
+        assert!(runout["rivers"][0]["frequencies"].as_array().unwrap().is_empty());
+        assert!(session.river_strategies(&["check"]).is_err());
+        let runout = session.runout_report(&["bet 300", "call"]).unwrap();
+        assert!(runout["player"].is_null() && runout["actions"].as_array().unwrap().is_empty());
+        assert!(runout["rivers"][0]["frequencies"].as_array().unwrap().is_empty());
+        assert!(session.runout_report(&["check"]).is_err());
+    }
+
+    #[test]
+    fn test_turn_runout_values_match_equity() {
+        // No chips behind: check down and run out the river.
+        let config = GameConfig {
+            initial_pot: 100.0,
+            stacks: [0.0, 0.0],
+            bet_sizes: vec![],
+            raise_sizes: vec![].into(),
+            river_bet_sizes: None,
+            river_raise_sizes: None,
+            river_cards: RiverCards::All,
+            raise_limit: 0,
+            chip_scale: 100,
+            icm: None,
+            bounty: None,
+            abstraction: None,
+            suit_isomorphism: false,
+            profiles: Vec::new(),
+            size_overrides: Vec::new(),
+            sizing_rules: Vec::new(),
+            donk_sizes: None,
+            player_sizes: Default::default(),
+            add_allin: true,
+            variant: GameVariant::Holdem,
+        };
+        let session = SolverSession::from_config(config, "Ks Th 7s 4d", "As Ad", "9s 8s").unwrap();
+
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let (hand0, hand1) = (&session.ranges[0][0], &session.ranges[1][0]);
+        let equities: Vec<f32> = (0..52u8)
+            .map(Card::from_index)
+            .filter(|river| !board.contains(river) && !hand0.contains(river) && !hand1.contains(river))
+            .map(|river| {
+                let mut cards = board.clone();
+                cards.push(river);
+                compute_single_equity(&cards, hand0, hand1).unwrap()
+            })
+            .collect();
+        assert_eq!(equities.len(), 44);
+        let expected = (equities.iter().sum::<f32>() / 44.0 - 0.5) * 100.0;
+
+        let value = solver::best_response_value(&session.tree, &session.trainer, &session.equity_matrix, &session.initial_reach, 0);
+        assert!((value - expected).abs() < 1e-3, "{} vs {}", value, expected);
+    }
+
+    #[test]
+    fn test_sampled_rivers() {
+        let mut session = turn_session(300.0, RiverCards::Sampled { count: 6, seed: 7 });
+        let full = turn_session(300.0, RiverCards::All);
+        assert_eq!(session.tree.rivers.len(), 6);
+        assert!(session.num_nodes() * 6 < full.num_nodes());
+        assert_eq!(session.tree.rivers, turn_session(300.0, RiverCards::Sampled { count: 6, seed: 7 }).tree.rivers);
+        assert_ne!(session.tree.rivers, turn_session(300.0, RiverCards::Sampled { count: 6, seed: 8 }).tree.rivers);
+
+        let dealt = Card::from_index(session.tree.rivers[0]).to_string();
+        let skipped = (0..52u8).find(|c| !session.tree.rivers.contains(c) && !"Ks Th 7s 4d".contains(&Card::from_index(*c).to_string())).unwrap();
+        assert!(session.strategy_for_history(&["check", "check", &format!("river {}", dealt)]).is_ok());
+        let err = session.strategy_for_history(&["check", "check", &format!("river {}", Card::from_index(skipped))]).unwrap_err();
+        assert!(err.contains(&format!("river {}", dealt)), "{}", err);
+
+        // The sample is part of the config, so solutions rebuild the same tree.
+        session.step(20);
+        let restored = SolverSession::from_solution(&session.to_solution()).unwrap();
+        assert_eq!(restored.tree.rivers, session.tree.rivers);
+        assert_eq!(restored.river_strategies(&["check", "check"]), session.river_strategies(&["check", "check"]));
+    }
+
+    #[test]
+    fn test_range_advantage_of_a_nutted_range() {
+        // Three sets against one pair and a missed draw: player 0 wins
+        // every matchup.
+        let session = SolverSession::from_config(
+            tiny_session().config, "Ks Th 7s 4d 2c", "Kd Kh, Tc Td, 7h 7c", "Ac Kc, Qh Qd, Jc Jd, 9h 8h",
+        ).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&session.get_range_advantage()).unwrap();
+
+        assert_eq!(report["players"][0]["buckets"], json!([0.0, 0.0, 0.0, 1.0]));
+        assert_eq!(report["players"][1]["buckets"], json!([1.0, 0.0, 0.0, 0.0]));
+        assert_eq!(report["players"][0]["nut_share"], 1.0);
+        assert_eq!(report["players"][1]["combos"], 4.0);
+        assert_eq!(report["mean_equity_diff"], 1.0);
+        assert_eq!(report["nut_share_diff"], 1.0);
+        assert_eq!(report["bucket_edges"].as_array().unwrap().len(), 5);
+
+        let distribution = session.equity_distribution(10).unwrap();
+        let top = &distribution["players"][0][9];
+        assert_eq!((top["low"].as_f64(), top["share"].as_f64()), (Some(0.9f32 as f64), Some(1.0)));
+        assert_eq!(top["hands"].as_array().unwrap().len(), 3);
+        assert_eq!(distribution["players"][1][0]["hands"].as_array().unwrap().len(), 4);
+        assert!(session.equity_distribution(0).is_err());
+
+        // Turn sessions pool the rivers, so the draws keep some equity.
+        let turn = SolverSession::from_config(
+            tiny_session().config, "Ks Th 7s 4d", "Kd Kh, Tc Td", "Qs Js, 9h 8h",
+        ).unwrap();
+        let advantage = turn.range_advantage();
+        assert!(advantage.players[1].mean_equity > 0.1 && advantage.players[1].mean_equity < 0.5);
+        assert!(advantage.mean_equity_diff > 0.0);
+    }
+
+    #[test]
+    fn test_nut_flush_blocker_scores_near_the_top() {
+        // Three spades out. Player 1 calls a shove with flushes, sets and pairs and
+        // folds air; player 0's As takes two of the five flushes out.
+        let mut session = SolverSession::from_config(
+            tiny_session().config, "Qs 9s 6s 3d 2c",
+            "As 4h, Ah 4c, 8c 5h, Jh 4d, 8h 8d, 5d 4c, 9h 9d, Td 8d",
+            "As Ks, As Js, Ks Js, Ts 8s, 7s 4s, 6h 6d, Kc Qc, Jh Th, 8c 7c, Ac Kd, Qh Jd",
+        ).unwrap();
+        session.step(500);
+
+        let report = session.blocker_scores(0).unwrap();
+        assert_eq!(report["response_node"], child_by_action(&session, 0, ActionType::Bet));
+        let combos = report["combos"].as_array().unwrap();
+        let hands: Vec<&str> = combos.iter().map(|c| c["hand"].as_str().unwrap()).collect();
+        assert_eq!(hands[0], "As 4h", "{:?}", combos);
+        assert!(combos[0]["score"].as_f64().unwrap() > 0.0);
+        let scores: Vec<f64> = combos.iter().map(|c| c["score"].as_f64().unwrap()).collect();
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]));
+        // Holding 8c or Jh removes hands that fold, so the score goes negative.
+        let score = |hand: &str| scores[hands.iter().position(|&h| h == hand).unwrap()];
+        assert!(score("8c 5h") < 0.0 && score("Jh 4d") < 0.0);
+
+        assert!(session.blocker_scores(child_by_action(&session, 0, ActionType::Bet)).is_err());
+    }
+
+    #[test]
+    fn test_polarized_bet_matches_the_theoretical_ratio() {
+        let session = polarized_session();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+
+        let report = session.polarization_report(0, &analysis::PolarizationThresholds::default()).unwrap();
+        assert_eq!(report.actions.len(), 1);
+        let action = &report.actions[0];
+        assert_eq!((action.action.as_str(), action.node), ("bet 100", bet));
+        assert_eq!((action.alpha, action.optimal_bluff_ratio), (0.5, 0.5));
+        assert_eq!((action.split.value_combos, action.split.bluff_combos), (3.0, 1.5));
+        assert_eq!(action.split.bluff_ratio, Some(action.optimal_bluff_ratio));
+        assert_eq!(action.frequency, 0.5);
+        assert_eq!(action.split.value.len(), 3);
+        assert!(action.split.bluffs.iter().all(|c| c.equity == 0.0 && c.weight == 0.25));
+
+        // Nothing is value once the bar is above the nuts' equity.
+        let strict = analysis::PolarizationThresholds { value_equity: 1.1, bluff_equity: 0.0, top_combos: 1 };
+        let split = &session.polarization_report(0, &strict).unwrap().actions[0].split;
+        assert_eq!((split.value_combos, split.bluff_combos, split.bluff_ratio), (0.0, 0.0, None));
+        assert!(session.polarization_report(session.num_nodes(), &strict).is_err());
+    }
+
+    #[test]
+    fn test_over_folding_villain_pays_the_best_response() {
+        let session = polarized_session();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        let shift = |fraction| vec![analysis::StrategyShift {
+            node: bet, from: "call".to_string(), to: "fold".to_string(), fraction,
+        }];
+
+        let unchanged = session.exploit_simulation(0, shift(0.0)).unwrap();
+        assert_eq!(unchanged.baseline, unchanged.perturbed);
+        assert_eq!((unchanged.equilibrium_delta, unchanged.best_response_delta), (0.0, 0.0));
+
+        let report = session.exploit_simulation(0, shift(0.2)).unwrap();
+        assert!(report.best_response_delta > 1.0, "{:?}", report);
+        assert!(report.perturbed.best_response > report.perturbed.equilibrium);
+        assert_eq!(report.pot, 100.0);
+
+        assert!(session.exploit_simulation(0, vec![analysis::StrategyShift { node: 0, ..shift(0.1).remove(0) }]).is_err());
+        assert!(session.exploit_simulation(0, shift(1.5)).is_err());
+    }
+
+    #[test]
+    fn test_locked_player_gains_nothing_from_best_responding() {
+        // Player 1 always calls the bet and always bets when checked to. Both
+        // are best responses to player 0's strategy, but calling everything
+        // is easy to exploit: player 0 should stop bluffing.
+        let mut session = polarized_session();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        let check = child_by_action(&session, 0, ActionType::Check);
+        set_average_strategy(&mut session, bet, |_| vec![0.0, 1.0]);
+        set_average_strategy(&mut session, check, |_| vec![0.0, 1.0]);
+
+        let point = session.record_exploitability();
+        assert!(point.gain[1].abs() < 1e-3, "{:?}", point);
+        assert!(point.gain[0] > 5.0, "{:?}", point);
+        assert_eq!(point.gain_pct[0], point.gain[0]);
+        assert_eq!(point.exploitability, session.exploitability());
+
+        // Reading it without recording leaves the history alone
+        let report: serde_json::Value = serde_json::from_str(&session.get_exploitability()).unwrap();
+        assert_eq!(report["players"][0]["gain"], point.gain[0]);
+        assert_eq!(report["players"][1]["best_response"], session.player_exploitability().best_response[1]);
+        session.record_exploitability();
+        assert_eq!(session.convergence_history().len(), 1);
+        let stats: serde_json::Value = serde_json::from_str(&session.get_stats()).unwrap();
+        assert_eq!(stats["exploitability"]["gain"][1], point.gain[1]);
+    }
+
+    #[test]
+    fn test_average_regret_tracks_convergence() {
+        let mut session = tiny_session();
+        assert_eq!(session.average_regret(), 0.0);
+        session.step(10);
+        let early = session.average_regret();
+        session.step(190);
+        let late = session.average_regret();
+        assert!(late > 0.0 && late < early, "regret {} after 10 iterations, {} after 200", early, late);
+
+        let stats: serde_json::Value = serde_json::from_str(&session.get_stats()).unwrap();
+        assert_eq!(stats["regret"], late);
+        assert_eq!(stats["regret_pct"], late / session.initial_pot() * 100.0);
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let session = tiny_session();
+        let memory = session.memory_usage();
+        let bytes = |key: &str| memory[key].as_u64().unwrap() as usize;
+        assert_eq!(bytes("regrets"), session.trainer.regrets.capacity() * 4);
+        assert_eq!(bytes("strategy_sum"), session.trainer.strategy_sum.capacity() * 4);
+        assert!(bytes("equity_matrix") >= session.equity_matrix.len() * 4);
+        assert!(bytes("tree") >= session.tree.nodes.len() * std::mem::size_of::<solver::arena::Node>());
+        let parts = ["tree", "equity_matrix", "regrets", "strategy_sum", "other"].map(bytes);
+        assert_eq!(bytes("total"), parts.iter().sum::<usize>());
+
+        let stats: serde_json::Value = serde_json::from_str(&session.get_stats()).unwrap();
+        assert_eq!(stats["memory"], memory);
+    }
+
+    #[test]
+    fn test_progress_during_training() {
+        let mut session = tiny_session();
+        let mut seen = Vec::new();
+        session.step_with(25, 10, |session| seen.push((session.iterations(), session.average_regret())));
+        assert_eq!(seen.iter().map(|&(i, _)| i).collect::<Vec<_>>(), [10, 20, 25]);
+        assert!(seen.iter().all(|&(_, regret)| regret > 0.0));
+
+        // Same iterations as one plain step
+        let mut plain = tiny_session();
+        plain.step(25);
+        assert_eq!(plain.trainer.strategy_sum, session.trainer.strategy_sum);
+    }
+
+    #[test]
+    fn test_resolving_the_root_reproduces_the_solve() {
+        let mut original = tiny_session();
+        original.step(100);
+        let mut resolved = original.subtree_session(0, None).unwrap();
+        assert_eq!(resolved.config, original.config);
+        resolved.step(100);
+        for node in [0, child_by_action(&original, 0, ActionType::Bet)] {
+            let (a, b) = (original.node_strategy_table(node).unwrap(), resolved.node_strategy_table(node).unwrap());
+            for (hand, probs) in a["strategies"].as_object().unwrap() {
+                let other = b["strategies"][hand].as_array().unwrap();
+                for (p, q) in probs.as_array().unwrap().iter().zip(other) {
+                    assert!((p.as_f64().unwrap() - q.as_f64().unwrap()).abs() < 1e-4, "{} at node {}", hand, node);
+                }
+            }
+        }
+
+        // Finer sizes for the re-solve; the pot and stacks are fixed.
+        let finer = original.subtree_session(0, Some(json!({ "bet_sizes": [0.33, 0.66, 1.0] }))).unwrap();
+        assert_eq!(finer.get_actions_at_node(0).len(), 4);
+        assert!(original.subtree_session(0, Some(json!({ "initial_pot": 50 }))).is_err());
+        assert!(original.subtree_session(child_by_action(&original, 0, ActionType::Check), None).is_err());
+    }
+
+    #[test]
+    fn test_resolving_a_river_narrows_the_ranges() {
+        let mut turn = turn_session(300.0, RiverCards::Sampled { count: 4, seed: 7 });
+        turn.step(50);
+        let bet = child_by_action(&turn, 0, ActionType::Bet);
+        let call = child_by_action(&turn, bet, ActionType::Call);
+        let river = turn.tree.nodes[call].children_start as usize;
+        let card = Card::from_index(turn.tree.nodes[river].river.unwrap());
+
+        let resolved = turn.subtree_session(river, None).unwrap();
+        assert_eq!(resolved.board.len(), 5);
+        assert_eq!(resolved.board[4], card);
+        assert_eq!(resolved.tree.rivers.len(), 0);
+        assert_eq!(resolved.config.bet_sizes, vec![0.5]);
+        assert_eq!(resolved.initial_pot(), turn.tree.to_amount(turn.tree.nodes[river].pot));
+        let behind = turn.config.stacks[0] - turn.tree.to_amount(turn.tree.nodes[river].bets[0]);
+        assert_eq!(resolved.config.stacks, [behind, behind]);
+
+        let reach = turn.path_reach(&turn.path_to(river));
+        for ((reach, weights), range) in reach.iter().zip(&resolved.initial_reach).zip(&resolved.ranges) {
+            assert_eq!(*weights, reach.iter().copied().filter(|&r| r > 0.0).collect::<Vec<_>>());
+            assert!(range.iter().all(|hand| !hand.contains(&card)));
+        }
+    }
+
+    #[test]
+    fn test_filtered_range_keeps_the_hands_that_take_the_line() {
+        // Only the full houses bet, and player 1 always calls.
+        let mut session = polarized_session();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        set_average_strategy(&mut session, 0, |h| if h < 3 { vec![0.0, 1.0] } else { vec![1.0, 0.0] });
+        set_average_strategy(&mut session, bet, |_| vec![0.0, 1.0]);
+
+        let range = session.filtered_range(0, &["bet 100"]).unwrap();
+        assert!((range["total"].as_f64().unwrap() - 1.0 / 3.0).abs() < 1e-6);
+        let kept: Vec<&str> = range["combos"].as_array().unwrap().iter()
+            .filter(|c| c["weight"].as_f64().unwrap() > 0.0)
+            .map(|c| c["hand"].as_str().unwrap())
+            .collect();
+        assert_eq!(kept, ["Kc Kd", "9h 9s", "5d 5h"]);
+        let classes = range["classes"].as_array().unwrap();
+        assert_eq!(classes[0]["class"], "KK");
+        assert!((classes[0]["weight"].as_f64().unwrap() - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(classes[3]["class"], "76s");
+        assert_eq!((classes[3]["combos"].as_u64(), classes[3]["weight"].as_f64()), (Some(3), Some(0.0)));
+
+        let folds = session.filtered_range(1, &["bet 100", "fold"]).unwrap();
+        assert_eq!(folds["total"], 0.0);
+        assert!(folds["combos"].as_array().unwrap().iter().all(|c| c["weight"] == 0.0));
+        assert!(session.filtered_range(2, &["bet 100"]).is_err());
+    }
+
+    #[test]
+    fn test_node_frequencies() {
+        let session = polarized_session();
+        let root = session.node_frequencies(0).unwrap();
+        assert_eq!((root["player"].as_u64(), root["reach"].as_f64()), (Some(0), Some(1.0)));
+        let actions = root["actions"].as_array().unwrap();
+        assert_eq!(actions.iter().map(|a| a["action"].as_str().unwrap()).collect::<Vec<_>>(), ["check", "bet 100"]);
+        assert!(actions.iter().all(|a| (a["frequency"].as_f64().unwrap() - 0.5).abs() < 1e-6));
+
+        // Player 1 faces the bet with the whole range
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        let call = session.node_frequencies(bet).unwrap();
+        assert_eq!((call["player"].as_u64(), call["reach"].as_f64()), (Some(1), Some(1.0)));
+        assert_eq!(call["actions"][1]["frequency"], 0.5);
+
+        // Only the bluffs check, three quarters of them
+        let check = child_by_action(&session, 0, ActionType::Check);
+        let raise = child_by_action(&session, check, ActionType::Bet);
+        let frequencies = session.node_frequencies(raise).unwrap();
+        assert_eq!(frequencies["player"], 0);
+        assert!((frequencies["reach"].as_f64().unwrap() - 0.5).abs() < 1e-6);
+        assert!(session.node_frequencies(child_by_action(&session, bet, ActionType::Fold)).is_err());
+        assert!(session.node_frequencies(session.tree.nodes.len()).is_err());
+    }
+
+    #[test]
+    fn test_strategy_grid() {
+        let session = polarized_session();
+        let root = session.strategy_grid::<&str>(&[]).unwrap();
+        assert_eq!((root["player"].as_u64(), root["actions"][1].as_str()), (Some(0), Some("bet 100")));
+        assert_eq!(root["frequencies"], json!([0.5, 0.5]));
+        let grid = root["grid"].as_array().unwrap();
+        assert_eq!(grid.len(), 13);
+        assert!(grid.iter().all(|row| row.as_array().unwrap().len() == 13));
+        // Aces first: KK on the diagonal, 76s above it
+        let kings = &grid[1][1];
+        assert_eq!((kings["class"].as_str(), kings["combos"].as_u64()), (Some("KK"), Some(1)));
+        assert!((kings["weight"].as_f64().unwrap() - 1.0 / 9.0).abs() < 1e-6);
+        assert_eq!(kings["frequencies"], json!([0.0, 1.0]));
+        let suited = &grid[7][8];
+        assert_eq!((suited["class"].as_str(), suited["combos"].as_u64()), (Some("76s"), Some(3)));
+        assert_eq!(suited["frequencies"], json!([0.75, 0.25]));
+        assert!(grid[0][0].is_null() && grid[8][7].is_null());
+
+        let call = session.strategy_grid(&["bet 100"]).unwrap();
+        assert_eq!(call["player"], 1);
+        assert_eq!(call["grid"][2][2]["combos"], 6);
+        assert_eq!(call["grid"][2][2]["weight"], 1.0);
+
+        // The kings always bet, so nothing of them is left after a check
+        let bet = session.strategy_grid(&["check"]).unwrap()["actions"][1].as_str().unwrap().to_string();
+        let raise = session.strategy_grid(&["check", &bet]).unwrap();
+        assert_eq!(raise["player"], 0);
+        assert_eq!((raise["grid"][1][1]["weight"].as_f64(), raise["grid"][1][1]["frequencies"].is_null()), (Some(0.0), true));
+        assert!(session.strategy_grid(&["bet 100", "fold"]).is_err());
+    }
+
+    #[test]
+    fn test_bucketed_solve_tracks_the_exact_solve() {
+        // Every two-card combo of a handful of cards: 66 combos per player
+        let combos = |cards: &str| {
+            let cards: Vec<&str> = cards.split_whitespace().collect();
+            let mut hands = Vec::new();
+            for (i, a) in cards.iter().enumerate() {
+                hands.extend(cards[i + 1..].iter().map(|b| format!("{} {}", a, b)));
+            }
+            hands.join(", ")
+        };
+        let range0 = combos("Ah Ad Kh Qh Qd Jh 9h 9d 8c 6h 5h 3c");
+        let range1 = combos("Ac Kd Kc Qc Jd Jc Td 9c 8d 7h 6d 4h");
+        let solve = |abstraction: Option<solver::AbstractionConfig>| {
+            let config = GameConfig {
+                initial_pot: 100.0,
+                stacks: [100.0, 100.0],
+                bet_sizes: vec![BetSize::PotFraction(1.0)],
+                raise_sizes: vec![].into(),
+                river_bet_sizes: None,
+                river_raise_sizes: None,
+                river_cards: RiverCards::All,
+                raise_limit: 0,
+                chip_scale: 100,
+                icm: None,
+                bounty: None,
+                abstraction,
+                suit_isomorphism: false,
+                profiles: Vec::new(),
+                size_overrides: Vec::new(),
+                sizing_rules: Vec::new(),
+                donk_sizes: None,
+                player_sizes: Default::default(),
+                add_allin: true,
+                variant: GameVariant::Holdem,
+            };
+            let mut session = SolverSession::from_config(config, "Ks Th 7s 4d 2c", &range0, &range1).unwrap();
+            session.step(1000);
+            session
+        };
+        let exact = solve(None);
+        let bucketed = solve(Some(solver::AbstractionConfig::new(20)));
+
+        let info = bucketed.abstraction_info();
+        assert_eq!(info["num_buckets"], json!([20, 20]));
+        assert_eq!(bucketed.trainer.num_hands(), [20, 20]);
+        let hands: usize = info["players"][0].as_array().unwrap().iter().map(|b| b["hands"].as_array().unwrap().len()).sum();
+        assert_eq!(hands, 66);
+
+        // Range-wide action frequencies at every decision agree
+        for node_idx in [0, child_by_action(&exact, 0, ActionType::Check), child_by_action(&exact, 0, ActionType::Bet)] {
+            let frequencies = |session: &SolverSession| {
+                let table = session.node_strategy_table(node_idx).unwrap();
+                let strategies = table["strategies"].as_object().unwrap();
+                let mut total = vec![0.0; table["actions"].as_array().unwrap().len()];
+                for probs in strategies.values() {
+                    for (t, p) in total.iter_mut().zip(probs.as_array().unwrap()) {
+                        *t += p.as_f64().unwrap() / strategies.len() as f64;
+                    }
+                }
+                total
+            };
+            for (a, b) in frequencies(&exact).iter().zip(frequencies(&bucketed)) {
+                assert!((a - b).abs() < 0.1, "node {}: exact {} vs bucketed {}", node_idx, a, b);
+            }
+        }
+
+        // Measured on the real game, the abstraction costs little
+        let pot = exact.initial_pot();
+        let bucketed_pct = bucketed.exploitability() / pot * 100.0;
+        assert!(bucketed_pct < 1.0, "bucketed exploitability {}% of the pot", bucketed_pct);
+    }
+
+    #[test]
+    fn test_suit_isomorphism_solves_the_same_game() {
+        // Clubs and diamonds are missing from the board and both ranges are
+        // symmetric in them
+        let range0 = "Ac Ad, Ac Kc, Ad Kd, Jc 9c, Jd 9d, Ah Kh, Jh 9h, 8c 8d, 6c 5c, 6d 5d";
+        let range1 = "Qc Jc, Qd Jd, Ac Jc, Ad Jd, 9c 9d, 5h 4h, Kc Kd, Ah Qh, 7c 6c, 7d 6d";
+        let solve = |suit_isomorphism| {
+            let mut config = tiny_session().config;
+            config.suit_isomorphism = suit_isomorphism;
+            let mut session = SolverSession::from_config(config, "Ks Qs Ts 7h 2h", range0, range1).unwrap();
+            session.step(1000);
+            session
+        };
+        let full = solve(false);
+        let reduced = solve(true);
+        assert_eq!(reduced.trainer.num_hands(), [7, 7]);
+        assert_eq!(reduced.abstraction_info()["method"], "suit_isomorphism");
+
+        let table = reduced.node_strategy_table(0).unwrap();
+        assert_eq!(table["strategies"]["Ac Kc"], table["strategies"]["Ad Kd"]);
+        assert_eq!(table["strategies"]["6c 5c"], table["strategies"]["6d 5d"]);
+        let bet = child_by_action(&reduced, 0, ActionType::Bet);
+        let table = reduced.node_strategy_table(bet).unwrap();
+        assert_eq!(table["strategies"]["Qc Jc"], table["strategies"]["Qd Jd"]);
+
+        // Measured on the full game, the reduced solve is as good
+        let pct = |session: &SolverSession| session.exploitability() / session.initial_pot() * 100.0;
+        assert!((pct(&full) - pct(&reduced)).abs() < 0.05, "full {}% vs reduced {}%", pct(&full), pct(&reduced));
+    }
+
+    #[test]
+    fn test_no_bluff_profile_stops_calls() {
+        // Player 1's average calling frequency against the root bet after a solve
+        fn call_frequency(profile: Option<&str>) -> f32 {
+            let mut session = polarized_session();
+            session.trainer = DCFRTrainer::new(session.tree.infoset_map.len(), session.tree.max_actions(), session.trainer.num_hands());
+            if let Some(rules) = profile {
+                let report = session.apply_profile_rules(0, serde_json::from_str(rules).unwrap()).unwrap();
+                // Only the six air hands are held back, at the root
+                let locks = report["locks"].as_array().unwrap();
+                assert_eq!(locks.len(), 1);
+                assert_eq!(locks[0]["node"], 0);
+                assert_eq!(locks[0]["actions"], json!(["check"]));
+                assert_eq!(locks[0]["hands"].as_array().unwrap().len(), 6);
+            }
+            session.step(500);
+            let bet = child_by_action(&session, 0, ActionType::Bet);
+            let call = child_by_action(&session, bet, ActionType::Call) - session.tree.nodes[bet].children_start as usize;
+            let node = &session.tree.nodes[bet];
+            (0..6).map(|h| session.trainer.average_strategy(node.infoset_id as usize, h, node.num_actions as usize)[call]).sum::<f32>() / 6.0
+        }
+
+        let free = call_frequency(None);
+        let honest = call_frequency(Some(r#"[{"rule": "no_bluff"}]"#));
+        assert!(free > 0.3, "unconstrained calling frequency {}", free);
+        assert!(honest < 0.05, "calling frequency against a no-bluff range {}", honest);
+    }
+
+    #[test]
+    fn test_profile_conflicts_keep_previous_rules() {
+        let mut session = polarized_session();
+        session.apply_profile_rules(0, vec![ProfileRule::CheckOnly]).unwrap();
+        let err = session.apply_profile_rules(0, vec![ProfileRule::CheckOnly, ProfileRule::AlwaysBet { frequency: 1.0, size: None }]);
+        assert!(err.is_err());
+        assert_eq!(session.config.profiles, vec![StrategyProfile { player: 0, rules: vec![ProfileRule::CheckOnly] }]);
+        assert_eq!(session.trainer.allowed_actions(0, 0), 1);
+
+        session.apply_profile_rules(0, vec![]).unwrap();
+        assert!(session.config.profiles.is_empty());
+        assert_eq!(session.trainer.allowed_actions(0, 0), u32::MAX);
+    }
+
+    #[test]
+    fn test_auto_size_keeps_the_overbet_of_a_polarized_range() {
+        // Nuts or air against a bluff catcher that only checks, two pots
+        // deep: the shove dominates every smaller bet
+        let spot = |bet_sizes: Vec<BetSize>| {
+            let mut config = tiny_session().config;
+            config.stacks = [200.0, 200.0];
+            config.bet_sizes = bet_sizes;
+            config.profiles = vec![StrategyProfile { player: 1, rules: vec![ProfileRule::CheckOnly] }];
+            SolverSession::from_config(
+                config, "Kh 9d 5c 2s 2h",
+                "Kc Kd, 9h 9s, 5d 5h, 7c 6c, 7d 6d, 7h 6h, 4c 3c, 4d 3d, 4h 3h",
+                "Qc Qd, Qc Qh, Qc Qs, Qd Qh, Qd Qs, Qh Qs",
+            ).unwrap()
+        };
+        let options = analysis::AutoSizeOptions::default();
+        let mut dense = spot(options.sizes.iter().map(|&size| size.into()).collect());
+        dense.step(options.iterations);
+        let dense_ev = solver::player_exploitability(&dense.tree, &dense.trainer, &dense.equity_matrix, &dense.initial_reach).average[0];
+
+        let mut session = spot(vec![BetSize::PotFraction(0.5)]);
+        let report = session.auto_size(&options).unwrap();
+        let root = &report["kept"][0];
+        assert_eq!(root["line"], json!([]));
+        let kept: Vec<f64> = root["sizes"].as_array().unwrap().iter()
+            .filter(|s| s["kept"] == json!(true))
+            .map(|s| s["amount"].as_f64().unwrap())
+            .collect();
+        assert_eq!(kept, vec![200.0]);
+        let bets: Vec<f32> = session.get_actions_at_node(0).iter()
+            .filter(|a| a["type"] == "bet")
+            .map(|a| a["amount"].as_f64().unwrap() as f32)
+            .collect();
+        assert_eq!(bets, vec![200.0]);
+
+        assert!(report["nodes"].as_u64() < report["dense_nodes"].as_u64());
+        assert_eq!(session.iterations(), options.iterations);
+        let ev = report["ev"][0].as_f64().unwrap() as f32;
+        assert!((ev - dense_ev).abs() < 0.01 * session.initial_pot(), "slim EV {} vs dense {}", ev, dense_ev);
+    }
+
+    #[test]
+    fn test_ev_decomposition_sums_to_the_root_ev() {
+        // Every line's contribution is the sum of its sub-lines'
+        fn check(line: &serde_json::Value, tolerance: f64) {
+            let Some(lines) = line["lines"].as_array() else { return };
+            let total: f64 = lines.iter().map(|l| l["contribution"].as_f64().unwrap()).sum();
+            let own = line["contribution"].as_f64().unwrap();
+            assert!((total - own).abs() < tolerance, "{} vs {} at node {}", total, own, line["node"]);
+            lines.iter().for_each(|l| check(l, tolerance));
+        }
+
+        let mut session = turn_session(300.0, RiverCards::Sampled { count: 3, seed: 11 });
+        session.step(50);
+        let pot = session.initial_pot() as f64;
+        let values = solver::player_exploitability(&session.tree, &session.trainer, &session.equity_matrix, &session.initial_reach);
+        for player in 0..2 {
+            let mut weighted = 0.0;
+            for (h, hand) in session.ranges[player].iter().enumerate() {
+                let hand_str = format!("{} {}", hand[0], hand[1]);
+                let report = session.ev_decomposition(&hand_str, Some(player), 4).unwrap();
+                let root = json!({ "contribution": report["ev"], "lines": report["lines"], "node": 0 });
+                check(&root, 1e-3 * pot);
+                let mass = session.opponent_mass(0, player, h, &session.initial_reach[1 - player]) as f64;
+                weighted += session.initial_reach[player][h] as f64 * mass * report["ev"].as_f64().unwrap();
+            }
+            // The combos' EVs average to the player's value
+            let pairs: f64 = (0..session.ranges[player].len())
+                .map(|h| session.initial_reach[player][h] as f64 * session.opponent_mass(0, player, h, &session.initial_reach[1 - player]) as f64)
+                .sum();
+            assert!((weighted / pairs - values.average[player] as f64).abs() < 1e-3 * pot);
+        }
+    }
+
+    #[test]
+    fn test_leaf_ev_map_reconciles_with_the_root() {
+        for mut session in [tiny_session(), turn_session(300.0, RiverCards::Sampled { count: 3, seed: 11 })] {
+            session.step(50);
+            let values = solver::player_exploitability(&session.tree, &session.trainer, &session.equity_matrix, &session.initial_reach);
+            let map = session.leaf_ev_map();
+            let leaves = map["leaves"].as_array().unwrap();
+            assert_eq!(leaves.len(), session.tree.nodes.iter().filter(|n| n.is_terminal()).count());
+
+            let tolerance = 1e-4 * session.initial_pot() as f64;
+            for player in 0..2 {
+                let total: f64 = leaves.iter().map(|leaf| leaf["ev"][player].as_f64().unwrap()).sum();
+                assert!((total - values.average[player] as f64).abs() < tolerance, "{} vs {}", total, values.average[player]);
+                assert!((map["ev"][player].as_f64().unwrap() - total).abs() < tolerance);
+            }
+            if session.tree.rivers.is_empty() {
+                assert!((map["probability"].as_f64().unwrap() - 1.0).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_equity_realization_averages_to_one_in_a_symmetric_spot() {
+        let config = GameConfig { bet_sizes: [0.5, 1.0].map(BetSize::from).to_vec(), ..tiny_session().config.clone() };
+        let range = "Ac Ad, Kc Kd, 9h 8h, 6h 5h, Qs Js, 5c 3c";
+        let mut session = SolverSession::from_config(config, "Ks Th 7s 4d 2c", range, range).unwrap();
+        session.step(500);
+
+        let players = session.equity_realization().unwrap();
+        for player in &players {
+            assert!((player.realization - 1.0).abs() < 0.15, "player {}: {}", player.player, player.realization);
+            let sets = player.combos.iter().find(|c| c.hand == "Kc Kd").unwrap();
+            assert!(sets.equity > 0.8 && sets.realization.unwrap() > 1.0);
+        }
+        // Zero-sum with equal equity shares: one side realizes what the other gives up
+        assert!((players[0].realization + players[1].realization - 2.0).abs() < 1e-3);
+        // Weak hands give up more than their equity
+        let draw = players[0].combos.iter().find(|c| c.hand == "6h 5h").unwrap();
+        assert!(draw.under_realizes);
+        assert_eq!(players[0].combos.iter().find(|c| c.hand == "5c 3c").unwrap().realization, None);
+    }
+
+    #[test]
+    fn test_matchup_lists_of_a_five_combo_range() {
+        // Board Ks Th 7s 4d 2c; the Qs Js flush draw missed
+        let mut session = SolverSession::from_config(tiny_session().config.clone(), "Ks Th 7s 4d 2c",
+            "Ac Kc, Qs Js", "Kd Qd, Ah Kh, Ad Kd, 7c 7d, As Ac").unwrap();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        let call = child_by_action(&session, bet, ActionType::Call);
+        // Villain calls the bet with everything, KQ half the time only
+        let (num_actions, call_action) = (session.tree.nodes[bet].num_actions as usize, call - session.tree.nodes[bet].children_start as usize);
+        set_average_strategy(&mut session, bet, |h| {
+            let mut p = vec![0.0; num_actions];
+            p[call_action] = if h == 0 { 0.5 } else { 1.0 };
+            p[0] += 1.0 - p[call_action];
+            p
+        });
+
+        let lists = session.matchup_lists(call, "Ac Kc", 2).unwrap();
+        assert_eq!(lists["player"], 0);
+        // Top pair top kicker beats KQ, chops with the other AK and loses to the set;
+        // AsAc is blocked by the ace of clubs
+        assert_eq!(lists["wins"], json!([{ "hand": "Kd Qd", "weight": 0.5 }]));
+        assert_eq!(lists["chops"], json!([{ "hand": "Ad Kd", "weight": 1.0 }, { "hand": "Ah Kh", "weight": 1.0 }]));
+        assert_eq!(lists["loses"], json!([{ "hand": "7c 7d", "weight": 1.0 }]));
+        assert_eq!(lists["blocked"], 1.0);
+        assert_eq!(lists["totals"], json!({ "wins": 0.5, "loses": 1.0, "chops": 2.0 }));
+
+        let short = session.matchup_lists(call, "Ac Kc", 1).unwrap();
+        assert_eq!(short["chops"].as_array().unwrap().len(), 1);
+        assert_eq!(short["counts"]["chops"], 2);
+        assert!(session.matchup_lists(call, "Qs Js", 5).unwrap()["wins"].as_array().unwrap().is_empty());
+        assert!(session.matchup_lists(call, "Ks Qs", 5).is_err());
+    }
+
+    #[test]
+    fn test_sample_action_follows_the_strategy() {
+        let mut session = tiny_session();
+        set_average_strategy(&mut session, 0, |_| vec![0.3, 0.7]);
+        let sample = |session: &SolverSession, seed| session.draw_action(0, "As Ad", seed).unwrap();
+        assert_eq!(sample(&session, 42), sample(&session, 42));
+        assert_eq!(sample(&session, 42)["distribution"].as_array().unwrap().len(), 2);
+
+        let bets = (0..4000).filter(|&seed| sample(&session, seed)["action"]["type"] == "bet").count();
+        assert!((bets as f32 / 4000.0 - 0.7).abs() < 0.03, "{} bets", bets);
+        let bet = &sample(&session, 0)["distribution"][1];
+        assert_eq!(bet["child"], child_by_action(&session, 0, ActionType::Bet));
+        assert!((bet["prob"].as_f64().unwrap() - 0.7).abs() < 1e-6);
+
+        // A locked-out action is never drawn
+        session.trainer.lock_actions(session.tree.nodes[0].infoset_id as usize, 0, 0b01);
+        assert!((0..100).all(|seed| sample(&session, seed)["action"]["type"] == "check"));
+
+        assert!(session.draw_action(0, "Kc Kd", 1).unwrap_err().contains("not found"));
+        let showdown = session.tree.nodes.iter().position(|n| n.node_type == NodeType::Showdown).unwrap();
+        assert!(session.draw_action(showdown, "As Ad", 1).unwrap_err().contains("no infoset"));
+    }
+
+    #[test]
+    fn test_hint_ranks_actions_by_ev() {
+        let mut session = tiny_session();
+        // Player 1 checks back and calls everything
+        let (check, bet) = (child_by_action(&session, 0, ActionType::Check), child_by_action(&session, 0, ActionType::Bet));
+        set_average_strategy(&mut session, check, |_| vec![1.0, 0.0]);
+        set_average_strategy(&mut session, bet, |_| vec![0.0, 1.0]);
+        set_average_strategy(&mut session, 0, |_| vec![0.5, 0.5]);
+
+        // Five-six high loses 50 checked down and 150 all in
+        let hint = session.hint("5h 6h", &[] as &[&str]).unwrap();
+        assert_eq!((hint.node, hint.player, hint.best.as_str()), (0, 0, "check"));
+        assert!((hint.actions[0].ev + 50.0).abs() < 1e-3 && (hint.actions[1].ev + 150.0).abs() < 1e-3, "{:?}", hint);
+        assert!((hint.ev + 100.0).abs() < 1e-3);
+        assert!((hint.ev_gap.unwrap() - 100.0).abs() < 1e-3 && !hint.close);
+        assert_eq!(hint.recommendation, "Check (-50.00 EV, 100.00 more than bet 100)");
+
+        assert!(session.hint("Kc Kd", &[] as &[&str]).unwrap_err().contains("player 0 is to act"));
+        assert!(session.hint("Qc Qd", &[] as &[&str]).unwrap_err().contains("not found"));
+        assert!(session.hint("Kc Kd", &["check", "check"]).unwrap_err().contains("over"));
+        assert!(session.hint("Kc Kd", &["check"]).is_ok());
+    }
+
+    #[test]
+    fn test_leak_report_pins_the_always_fold_leak() {
+        let mut session = tiny_session();
+        // Aces always bet, five-six half the time; kings value bet when
+        // checked to and call, nine-eight gives up; checked-to aces call
+        let (check, bet) = (child_by_action(&session, 0, ActionType::Check), child_by_action(&session, 0, ActionType::Bet));
+        let check_bet = child_by_action(&session, check, ActionType::Bet);
+        set_average_strategy(&mut session, 0, |h| if h == 0 { vec![0.0, 1.0] } else { vec![0.5, 0.5] });
+        set_average_strategy(&mut session, bet, |h| if h == 0 { vec![0.0, 1.0] } else { vec![1.0, 0.0] });
+        set_average_strategy(&mut session, check, |h| if h == 0 { vec![0.0, 1.0] } else { vec![1.0, 0.0] });
+        set_average_strategy(&mut session, check_bet, |_| vec![0.0, 1.0]);
+
+        let strategy: Vec<analysis::UserNode> = serde_json::from_str(r#"[
+            {"line": ["bet 100"], "hands": {"*": {"fold": 1}}},
+            {"line": ["check"], "hands": {"Kc Kd": {"check": 1}, "98s": {"bet": 0.25, "check": 0.75}, "*": {"bet": 1}}}
+        ]"#).unwrap();
+        let report = session.leaks(1, &strategy, &analysis::LeakOptions::default()).unwrap();
+
+        // Kings fold to 1.5 bets they win 150 against instead of losing
+        // their half of the pot, 50, a quarter of the deals; checking back
+        // wins 50 instead of 150 against half a five-six. Nine-eight folds
+        // anyway, and betting it a quarter of the time beats five-six for
+        // 100 more: a gain
+        assert_eq!(report.leaks.len(), 2);
+        let top = &report.leaks[0];
+        assert_eq!((top.node, top.action.as_str(), top.classes.clone()), (bet, "fold", vec!["KK".to_string()]));
+        assert!((top.loss - 0.25 * 1.5 * (150.0 + 50.0)).abs() < 1e-3, "{:?}", report);
+        assert_eq!(top.description, "You fold too often with KK facing bet 100 on the river, costing 7500.00 per 100 hands");
+        assert_eq!(report.leaks[1].description, "You check too often with KK after check on the river, costing 1250.00 per 100 hands");
+        assert_eq!(report.lines[0].node, bet);
+        assert_eq!(report.classes[0].class, "KK");
+        assert_eq!(report.classes[1].class, "98s");
+        assert!((report.classes[1].loss + 0.25 * 0.25 * 50.0).abs() < 1e-3, "{:?}", report);
+        assert!((report.total_loss - (75.0 + 12.5 - 3.125)).abs() < 1e-3);
+
+        let only_top = session.leaks(1, &strategy, &analysis::LeakOptions { top: 1, min_loss: 0.0 }).unwrap();
+        assert_eq!(only_top.leaks, report.leaks[..1]);
+
+        let node = |json: &str| serde_json::from_str::<Vec<analysis::UserNode>>(json).unwrap();
+        assert!(session.leaks(1, &node(r#"[{"line": [], "hands": {"*": {"check": 1}}}]"#), &Default::default()).unwrap_err().contains("decision of player 1"));
+        assert!(session.leaks(1, &node(r#"[{"line": ["bet 100"], "hands": {"*": {"raise": 1}}}]"#), &Default::default()).unwrap_err().contains("not available"));
+        assert!(session.leaks(1, &node(r#"[{"line": ["bet 100"], "hands": {"K": {"fold": 1}}}]"#), &Default::default()).unwrap_err().contains("Unknown hand"));
+    }
+
+    #[test]
+    fn test_query_hands_with_composite_filters() {
+        let mut session = SolverSession::from_config(
+            tiny_session().config, "Ks Th 7s 4s 2c",
+            "As Qs, 9s 8s, Ah Ad, 6h 5h, Kh Qh",
+            "Kc Kd, Qc Jc",
+        ).unwrap();
+        // The flushes and aces bet, nine-eight of spades a bit less often;
+        // five-six always checks and king-queen mixes
+        set_average_strategy(&mut session, 0, |h| match h {
+            1 => vec![0.2, 0.8],
+            3 => vec![1.0, 0.0],
+            4 => vec![0.5, 0.5],
+            _ => vec![0.0, 1.0],
+        });
+        let check = child_by_action(&session, 0, ActionType::Check);
+        set_average_strategy(&mut session, check, |_| vec![1.0, 0.0]);
+        let query = |json: &str| session.hand_query(0, &serde_json::from_str(json).unwrap());
+        let hands = |json: &str| query(json).unwrap().hands.into_iter().map(|row| row.hand).collect::<Vec<_>>();
+
+        let strong_flushes = hands(r#"{"filter": {"all": [{"category": "Flush"}, {"frequency": {"action": "bet", "min": 0.9}}]}}"#);
+        assert_eq!(strong_flushes, vec!["As Qs"]);
+        assert_eq!(hands(r#"{"filter": {"frequency": {"action": "check", "min": 1.0}}}"#), vec!["6h 5h"]);
+        assert_eq!(hands(r#"{"filter": {"any": [{"holds": "Ah"}, {"category": "top_pair"}]}, "sort": "hand"}"#), vec!["Ah Ad", "Kh Qh"]);
+        assert_eq!(hands(r#"{"filter": {"not": {"category": "flush"}}, "sort": {"frequency": "bet 100"}, "limit": 2}"#), vec!["Ah Ad", "Kh Qh"]);
+        assert_eq!(hands(r#"{"sort": {"frequency": "bet"}, "ascending": true}"#)[..2], ["6h 5h", "Kh Qh"]);
+
+        let all = query("{}").unwrap();
+        assert_eq!((all.player, all.combos, all.actions.clone()), (0, 5, vec!["check".to_string(), "bet 100".to_string()]));
+        let flush = &all.hands[0];
+        assert_eq!((flush.category, flush.detail, flush.class.as_str()), ("flush", poker::made_hands::HandDetail::Flush, "AQs"));
+        // Checked down, five-six loses 50 to both of player 1's hands
+        let air = all.hands.iter().find(|row| row.hand == "6h 5h").unwrap();
+        assert!((air.ev.unwrap() + 50.0).abs() < 1e-3, "{:?}", air);
+        let by_ev = hands(r#"{"filter": {"ev": {"max": 0}}, "sort": "ev"}"#);
+        assert_eq!(by_ev.last().map(String::as_str), Some("6h 5h"));
+
+        assert!(query(r#"{"filter": {"category": "monster"}}"#).unwrap_err().contains("Unknown hand category"));
+        assert!(query(r#"{"filter": {"frequency": {"action": "raise"}}}"#).unwrap_err().contains("not available"));
+        assert!(session.hand_query(child_by_action(&session, check, ActionType::Check), &Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_mixing_report_drops_to_zero_after_purification() {
+        // The bluffs bet a quarter of the time, the queens call half the time
+        let session = polarized_session();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        let report = session.mixing_report();
+        let node = |idx: usize| report.nodes.iter().find(|n| n.node == idx).unwrap();
+        assert!((node(bet).entropy - 1.0).abs() < 1e-5);
+        assert_eq!((node(bet).pure, node(bet).mixed, node(bet).examples.len()), (0, 6, 3));
+        assert_eq!(node(bet).line, vec!["bet 100"]);
+        assert!((node(bet).reach - (3.0 + 6.0 * 0.25) / 9.0).abs() < 1e-5);
+        assert!((node(0).entropy - 6.0 / 9.0 * analysis::entropy(&[0.75, 0.25])).abs() < 1e-5);
+        assert_eq!((node(0).pure, node(0).mixed), (3, 6));
+        assert!(report.nodes.windows(2).all(|w| w[0].entropy >= w[1].entropy));
+
+        // A solve mixes somewhere; rounding every strategy to its most
+        // frequent action leaves nothing mixed
+        let mut session = tiny_session();
+        session.step(300);
+        assert!(session.mixing_report().entropy > 0.0);
+        for node_idx in 0..session.tree.nodes.len() {
+            if session.tree.nodes[node_idx].node_type != NodeType::Action {
+                continue;
+            }
+            let node = &session.tree.nodes[node_idx];
+            let (infoset, player, num_actions) = (node.infoset_id as usize, node.player as usize, node.num_actions as usize);
+            let pure: Vec<Vec<f32>> = (0..session.ranges[player].len())
+                .map(|h| {
+                    let probs = session.trainer.average_strategy(infoset, h, num_actions);
+                    let best = (0..num_actions).max_by(|&a, &b| probs[a].total_cmp(&probs[b])).unwrap();
+                    (0..num_actions).map(|a| if a == best { 1.0 } else { 0.0 }).collect()
+                })
+                .collect();
+            set_average_strategy(&mut session, node_idx, |h| pure[h].clone());
+        }
+        let report = session.mixing_report();
+        assert!(report.entropy.abs() < 1e-6);
+        assert!(report.nodes.iter().all(|n| n.entropy.abs() < 1e-6 && n.mixed == 0 && n.examples.is_empty()), "{:?}", report);
+    }
+
+    #[test]
+    fn test_board_blocked_combos_are_dropped() {
+        let config = tiny_session().config.clone();
+        let session = SolverSession::from_config(config.clone(), "Ks Th 7s 4d 2c", "As Ad, Ks Qs, 6h 5h", "Kc Kd, 9s 8s, Th 9h").unwrap();
+        #[cfg(feature = "wasm")]
+        assert_eq!(session.get_combos(0).unwrap(), r#"["As Ad","6h 5h"]"#);
+        assert_eq!(session.dropped_combos(0).unwrap(), [DroppedCombo { hand: "Ks Qs".to_string(), reason: "shares Ks with the board".to_string() }]);
+        assert_eq!(session.dropped_combos(1).unwrap()[0].reason, "shares Th with the board");
+        assert!(session.dropped_combos(2).is_err());
+        let stats: serde_json::Value = serde_json::from_str(&session.get_stats()).unwrap();
+        assert_eq!((stats["hands"].clone(), stats["dropped"].clone()), (json!([2, 2]), json!([1, 1])));
+
+        // Indices, strategy tables and equities match the clean ranges
+        assert_eq!(session.node_strategy_table(0), tiny_session().node_strategy_table(0));
+        assert!(session.range_equities().iter().flatten().all(|e| !e.is_nan()));
+        assert!(SolverSession::from_config(config, "Ks Th 7s 4d 2c", "Ks Qs", "Kc Kd").is_err());
+    }
+
+    #[test]
+    fn test_hand_strategy_after_a_root_bet() {
+        let mut config = tiny_session().config.clone();
+        config.bet_sizes = vec![BetSize::PotFraction(0.75)];
+        let mut session = SolverSession::from_config(config, "Ks Th 7s 4d 2c", "As Ad, 6h 5h", "Kc Kd, 9s 8s").unwrap();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        // Kings always call, the straight draw that missed always folds
+        set_average_strategy(&mut session, bet, |h| if h == 0 { vec![0.0, 1.0] } else { vec![1.0, 0.0] });
+
+        let mut strategy = session.hand_strategy("Kd Kc", Some(&["bet 75"])).unwrap();
+        assert!(strategy.as_object_mut().unwrap().remove("evs").is_some_and(|evs| evs.as_array().unwrap().len() == 2));
+        assert_eq!(strategy, json!({ "node": bet, "player": 1, "actions": ["fold", "call"], "probs": [0.0, 1.0] }));
+        assert_eq!(session.hand_strategy("9s 8s", Some(&["bet 75"])).unwrap()["probs"], json!([1.0, 0.0]));
+        assert!(session.hand_strategy("As Ad", Some(&["bet 75"])).unwrap_err().contains("P1's range"));
+
+        // Without a history P1 answers a check, and P0 acts at the root
+        let check = child_by_action(&session, 0, ActionType::Check);
+        assert_eq!(session.hand_strategy::<&str>("Kc Kd", None).unwrap()["node"], json!(check));
+        assert_eq!(session.hand_strategy::<&str>("As Ad", None).unwrap()["actions"], json!(["check", "bet 75.0", "bet 100.0"]));
+        assert!(session.hand_strategy("Kc Kd", Some(&["bet 75", "call"])).is_err());
+    }
+
+    #[test]
+    fn test_compact_lines_are_accepted_as_histories() {
+        let session = tiny_session();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        let call = child_by_action(&session, bet, ActionType::Call);
+        assert_eq!(session.history_path(&["bet 100", "call"]).unwrap(), [0, bet, call]);
+        assert_eq!(session.history_path(&["b100-c"]).unwrap(), [0, bet, call]);
+        assert_eq!(session.history_path(&["b100", "call"]).unwrap(), [0, bet, call]);
+        assert_eq!(session.strategy_for_history(&["x"]).unwrap(), session.strategy_for_history(&["check"]).unwrap());
+        assert!(session.history_path(&["b100-q"]).unwrap_err().contains("unknown step 'q'"));
+
+        // Every node's own line, formatted compactly, leads back to it
+        for node_idx in 0..session.num_nodes() {
+            let line = solver::format_line(&session.tree.line_of(node_idx as u32));
+            assert_eq!(session.history_path(&[&line]).unwrap().last(), Some(&node_idx), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_transfer_after_a_size_change_converges_faster() {
+        let config = |bet: f32| {
+            let mut config = tiny_session().config.clone();
+            config.bet_sizes = [0.33, bet].map(BetSize::from).to_vec();
+            config.stacks = [400.0, 400.0];
+            config.raise_sizes = vec![RaiseSize::PotFraction(1.0)].into();
+            config.raise_limit = 2;
+            config
+        };
+        let ranges = ["As Ad, Ks Kd, Th Tc, 7h 7c, Ah Qh, Qc Jc, 6s 5s, Ac 3c, 9h 8h", "Kc Qc, Jh Js, 8s 8d, Ah Kh, Qs Js, 9c 9d, Tc 9c, 6h 6c, As 5s"];
+        let session = |bet: f32| SolverSession::from_config(config(bet), "Ks Th 7s 4d 2c", ranges[0], ranges[1]).unwrap();
+        // Iterations until the exploitability is under 1% of the pot
+        let solve = |session: &mut SolverSession| {
+            let start = session.iterations();
+            while session.record_exploitability().exploitability_pct > 1.0 && session.iterations() < start + 2000 {
+                session.step(10);
+            }
+            session.iterations() - start
+        };
+
+        let mut transferred = session(0.75);
+        transferred.step(400);
+        // 75% of the pot becomes 67.5%
+        let report = transferred.transfer_to(config(0.675), true).unwrap();
+        assert_eq!(report["matched_nodes"], json!(transferred.num_nodes()));
+        assert_eq!((report["match_rate"].clone(), report["iterations"].clone()), (json!(1.0), json!(400)));
+        assert!(transferred.get_actions_at_node(0).contains(&json!({ "type": "bet", "amount": 67.5 })));
+        let warm = solve(&mut transferred);
+        let cold = solve(&mut session(0.675));
+        assert!(warm * 4 < cold, "{} iterations warm, {} cold", warm, cold);
+
+        // A new size far from every old one starts from scratch
+        let mut config = config(0.675);
+        config.bet_sizes = [0.33, 2.0].map(BetSize::from).to_vec();
+        let report = transferred.transfer_to(config, false).unwrap();
+        assert!(report["match_rate"].as_f64().unwrap() < 1.0);
+        assert!(report["matched_nodes"].as_u64().unwrap() < transferred.num_nodes() as u64);
+    }
+
+    #[test]
+    fn test_preview_tree_matches_the_session() {
+        let session = tiny_session();
+        let preview = tree_preview(&session.config, &[], Some([2, 2])).unwrap();
+        assert_eq!(preview["nodes"], json!(session.num_nodes()));
+        assert_eq!(preview["infosets"], json!(session.num_infosets()));
+        assert_eq!((preview["terminal_nodes"].clone(), preview["showdown_nodes"].clone(), preview["max_depth"].clone()), (json!(2), json!(3), json!(3)));
+        assert_eq!(preview["actions"], json!({ "bet": 2, "call": 2, "check": 2, "fold": 2 }));
+        assert_eq!(preview["errors"], json!([]));
+        let trainer = &session.trainer;
+        let allocated = 4 * (trainer.regrets.len() + trainer.strategy_sum.len()) + 16 * session.num_infosets();
+        assert_eq!(preview["trainer_bytes"], json!(allocated));
+
+        let turn = turn_session(100.0, RiverCards::All);
+        let preview = tree_preview(&turn.config, &turn.board, None).unwrap();
+        assert_eq!(preview["nodes"], json!(turn.num_nodes()));
+        assert_eq!(preview["rivers"], json!(turn.tree.rivers.len()));
+        assert_eq!(preview["trainer_bytes"], json!(null));
+
+        let mut broken = session.config.clone();
+        broken.stacks = [100.0, -50.0];
+        assert!(tree_preview(&broken, &[], None).is_err());
+    }
+
+    #[test]
+    fn test_infoset_map_covers_every_decision_node_once() {
+        let session = turn_session(100.0, RiverCards::All);
+        let map = session.infoset_map();
+        let infosets = map.as_array().unwrap();
+        assert_eq!(infosets.len(), session.num_infosets());
+
+        let mut seen = vec![0; session.num_nodes()];
+        for (id, infoset) in infosets.iter().enumerate() {
+            assert_eq!(infoset["infoset"], json!(id));
+            for entry in infoset["nodes"].as_array().unwrap() {
+                let node_idx = entry["node"].as_u64().unwrap() as usize;
+                seen[node_idx] += 1;
+                let node = &session.tree.nodes[node_idx];
+                assert_eq!((infoset["player"].clone(), infoset["actions"].clone()), (json!(node.player), json!(node.num_actions)));
+                assert_eq!(session.history_path(&serde_json::from_value::<Vec<String>>(entry["line"].clone()).unwrap()).unwrap().last(), Some(&node_idx));
+                assert_eq!(session.node_infoset(node_idx), Ok(Some(id as u32)));
+            }
+        }
+        for (node_idx, node) in session.tree.nodes.iter().enumerate() {
+            let expected = usize::from(node.node_type == NodeType::Action);
+            assert_eq!(seen[node_idx], expected, "node {} ({:?})", node_idx, node.node_type);
+            if node.node_type != NodeType::Action {
+                assert_eq!(session.node_infoset(node_idx), Ok(None));
+            }
+        }
+        assert!(session.node_infoset(session.num_nodes()).is_err());
+    }
+
+    #[test]
+    fn test_invariant_checks_flag_a_corrupted_solve() {
+        let mut session = tiny_session();
+        assert_eq!(session.get_invariant_report(), "null");
+        session.set_invariant_checks(true);
+        session.step(20);
+        let report = session.invariant_report().unwrap();
+        assert_eq!((report.iterations, report.total), (20, 0));
+
+        // An infinite regret turns the root strategy, and so its utilities, into NaN
+        session.trainer.regrets[0] = f32::INFINITY;
+        session.step(1);
+        let report = session.invariant_report().unwrap();
+        let kinds: Vec<solver::InvariantKind> = report.violations.iter().map(|v| v.kind).collect();
+        assert!(kinds.contains(&solver::InvariantKind::NegativeProbability));
+        assert!(kinds.contains(&solver::InvariantKind::NonFiniteUtility));
+        assert!(report.violations.iter().all(|v| v.iteration == 21));
+        // The root's strategy is checked before its children are visited
+        assert_eq!((report.violations[0].node, report.violations[0].kind), (0, solver::InvariantKind::NegativeProbability));
+        let json: serde_json::Value = serde_json::from_str(&session.get_invariant_report()).unwrap();
+        assert_eq!(json["violations"][0]["kind"], "negative_probability");
+
+        session.set_invariant_checks(false);
+        assert!(session.invariant_report().is_none());
+    }
+
+    #[test]
+    fn test_perf_counters_count_every_node() {
+        let mut session = turn_session(100.0, RiverCards::All);
+        assert_eq!(session.get_perf(), "null");
+        session.set_perf_counters(true);
+        session.step(3);
+        let perf = session.perf_counters().unwrap().clone();
+        let nodes = session.num_nodes() as u64;
+        let leaves = session.tree.nodes.iter().filter(|n| n.is_terminal()).count() as u64;
+        assert_eq!(perf.iterations, 3);
+        // One traversal per player and iteration
+        assert_eq!(perf.nodes_visited, 2 * 3 * nodes);
+        assert_eq!(perf.terminal_evaluations, 2 * 3 * leaves);
+        assert!(perf.estimated_allocations > 2 * perf.nodes_visited);
+        assert!(perf.traversal_ms >= 0.0 && perf.discount_ms >= 0.0);
+
+        let json: serde_json::Value = serde_json::from_str(&session.get_perf()).unwrap();
+        assert_eq!(json["nodes_visited"], json!(2 * 3 * nodes));
+        assert!(json.get("finished").is_none());
+        session.set_perf_counters(false);
+        session.step(1);
+        assert!(session.perf_counters().is_none());
+    }
+
+    #[test]
+    fn test_ev_components_split_bluffs_from_value() {
+        let session = polarized_session();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        let pot_after = |action| session.tree.to_amount(session.tree.nodes[child_by_action(&session, bet, action)].pot);
+        let (fold_pot, call_pot) = (pot_after(ActionType::Fold), pot_after(ActionType::Call));
+
+        // Player 1 folds half the time to the bet
+        let report = session.ev_components(bet).unwrap();
+        let combos = report["players"][0]["combos"].as_array().unwrap();
+        assert_eq!(combos.len(), 9);
+        let get = |hand: &str, key: &str| {
+            combos.iter().find(|c| c["hand"] == hand).unwrap()[key].as_f64().unwrap() as f32
+        };
+        for combo in combos {
+            let sum = combo["fold_ev"].as_f64().unwrap() + combo["showdown_ev"].as_f64().unwrap();
+            assert!((sum - combo["total"].as_f64().unwrap()).abs() < 1e-4, "{}", combo);
+        }
+        // The bluff's value is all fold equity, the nuts' mostly showdown
+        assert!((get("7c 6c", "fold_ev") - fold_pot / 2.0).abs() < 1e-3);
+        assert_eq!(get("7c 6c", "showdown_ev"), 0.0);
+        assert!((get("Kc Kd", "fold_ev") - fold_pot / 2.0).abs() < 1e-3);
+        assert!((get("Kc Kd", "showdown_ev") - call_pot / 2.0).abs() < 1e-3);
+
+        // Less half the starting pot and the bet player 0 put in, which every
+        // pot they take includes, the total is the chip EV
+        let invested = session.initial_pot() / 2.0 + session.tree.to_amount(session.tree.nodes[bet].bets[0]);
+        let trainer = session.combo_trainer(&session.trainer);
+        let opp_reach = &session.path_reach(&session.path_to(bet))[1];
+        let chips = solver::counterfactual_values(&session.tree, &trainer, session.subtree_matrix(bet), bet as u32, 0, opp_reach);
+        let hand = session.find_combo(0, &[Card::from_str("Kc").unwrap(), Card::from_str("Kd").unwrap()]).unwrap();
+        let chip_ev = chips[hand] / session.opponent_mass(bet, 0, hand, opp_reach);
+        assert!((get("Kc Kd", "total") - invested - chip_ev).abs() < 1e-3, "{}", chip_ev);
+
+        assert!(session.ev_components(session.num_nodes()).is_err());
+    }
+
+    #[test]
+    fn test_weighted_ranges() {
+        let board = "Kh 9d 5c 2s 2h";
+        let session = SolverSession::from_config(tiny_session().config, board, "Qc Qd", "Ac Ah:0.25, 7c 7d").unwrap();
+        assert_eq!(session.initial_reach[1], [0.25, 1.0]);
+        // Queens lose to the aces a fifth of the time
+        assert!((session.range_equities()[0][0] - 0.8).abs() < 1e-6);
+        let [eq0, eq1] = session.total_equity();
+        assert!((eq0 - 0.8).abs() < 1e-6 && (eq1 - 0.2).abs() < 1e-6);
+        assert_eq!(session.get_total_equity(), json!([eq0, eq1]).to_string());
+
+        // Weights are part of the spot, and solution files keep them
+        let mut unweighted = SolverSession::from_config(tiny_session().config, board, "Qc Qd", "Ac Ah, 7c 7d").unwrap();
+        assert_ne!(session.solve_key(), unweighted.solve_key());
+        let solution = session.to_solution();
+        assert_eq!(solution.ranges[1], "Ac Ah:0.25, 7c 7d");
+        let restored = SolverSession::from_solution(&solution).unwrap();
+        assert_eq!(restored.initial_reach, session.initial_reach);
+        assert!(unweighted.restore_solution(&solution).is_err());
+    }
+
+    #[test]
+    fn test_hand_strategy_reports_action_evs() {
+        let session = polarized_session();
+        let strategy = session.hand_strategy("Qc Qd", Some(&["bet"])).unwrap();
+        // Queens beat the 1.5 bluffing combos and lose to the 3 value
+        // combos, each worth half of the 300 pot
+        let evs = strategy["evs"].as_array().unwrap();
+        assert_eq!(evs.len(), 2);
+        assert!((evs[1].as_f64().unwrap() - (1.5 - 3.0) * 150.0 / 4.5).abs() < 1e-3, "{}", strategy);
+
+        #[cfg(feature = "wasm")]
+        {
+            let node = child_by_action(&session, 0, ActionType::Bet);
+            let at_node: serde_json::Value = serde_json::from_str(&session.get_hand_strategy_at_node("Qd Qc", node).unwrap()).unwrap();
+            assert_eq!(at_node["evs"], strategy["evs"]);
+        }
+    }
+
+    #[test]
+    fn test_strategy_tree_export() {
+        let mut session = turn_session(100.0, RiverCards::All);
+        session.step(20);
+        let tree = session.strategy_tree();
+        let nodes = tree["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), session.num_nodes());
+        assert_eq!(tree["ranges"][0].as_array().unwrap().len(), session.ranges[0].len());
+
+        // Decision rows match the per-node queries, combo for combo
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        let table = session.node_strategy_table(bet).unwrap();
+        for (h, combo) in tree["ranges"][1].as_array().unwrap().iter().enumerate() {
+            assert_eq!(table["strategies"][combo.as_str().unwrap()], nodes[bet]["strategy"][h]);
+        }
+        assert_eq!(nodes[bet]["actions"], json!(session.get_actions_at_node(bet)));
+
+        let chance = nodes.iter().find(|n| n["type"] == "chance").unwrap();
+        assert_eq!(chance["rivers"].as_array().unwrap().len(), chance["children"].as_array().unwrap().len());
+        // Combos holding a dealt river have no strategy below it
+        let river_root = &nodes[chance["children"][0].as_u64().unwrap() as usize];
+        let river = chance["rivers"][0].as_str().unwrap();
+        let player = river_root["player"].as_u64().unwrap() as usize;
+        for (h, combo) in tree["ranges"][player].as_array().unwrap().iter().enumerate() {
+            assert_eq!(river_root["strategy"][h].is_null(), combo.as_str().unwrap().contains(river));
+        }
+        assert!(nodes.iter().any(|n| n["type"] == "fold" && n["winner"].is_u64()));
+    }
+
+    #[test]
+    fn test_browse_only_solutions() {
+        let mut session = turn_session(100.0, RiverCards::All);
+        session.step(30);
+        let (full, browse) = (session.export_solution(), session.export_browse_solution());
+        assert!(browse.len() * 2 < full.len(), "{} vs {}", browse.len(), full.len());
+
+        let mut restored = turn_session(100.0, RiverCards::All);
+        restored.restore_solution(&Solution::from_bytes(&browse).unwrap()).unwrap();
+        assert_eq!(restored.iterations(), 30);
+        assert!(restored.trainer.regrets.iter().all(|&r| r == 0.0));
+        for node in session.tree.iter_dfs().filter(|&n| session.tree.nodes[n as usize].node_type == NodeType::Action) {
+            let infoset = session.tree.nodes[node as usize].infoset_id as usize;
+            let actions = session.tree.nodes[node as usize].num_actions as usize;
+            for h in 0..session.ranges[session.tree.nodes[node as usize].player as usize].len() {
+                let (a, b) = (session.trainer.average_strategy(infoset, h, actions), restored.trainer.average_strategy(infoset, h, actions));
+                assert!(a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-4), "{:?} vs {:?} at node {}", a, b, node);
+            }
+        }
+        // The strategies can be browsed and solved on
+        assert!((restored.exploitability() - session.exploitability()).abs() < 0.05);
+        restored.step(5);
+        assert_eq!(restored.iterations(), 35);
+    }
+
+    #[test]
+    fn test_short_deck_sessions() {
+        let mut config = tiny_session().config.clone();
+        config.variant = GameVariant::ShortDeck;
+
+        // The flush beats the full house, and the deuces are not in the deck
+        let session = SolverSession::from_config(config.clone(), "Ks Kh 9s 7s 6d", "As Js, 2c 2d", "9h 9d").unwrap();
+        assert_eq!(session.ranges[0].len(), 1);
+        assert_eq!(session.equity_matrix, [1.0]);
+        assert!(SolverSession::from_config(config.clone(), "Ks Kh 9s 7s 2d", "As Js", "9h 9d").is_err());
+
+        // Turn trees deal the 32 rivers left in the short deck
+        let turn = SolverSession::from_config(config, "Ks Kh 9s 7s", "As Js", "9h 9d").unwrap();
+        assert_eq!(turn.tree.rivers.len(), 32);
+        assert!(turn.tree.rivers.iter().all(|&c| Card::from_index(c).rank() >= 4));
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_canonical_spots_map_back() {
+        let spot: serde_json::Value = serde_json::from_str(&canonicalize_spot("Ah Kd 2c 2d 9s", "As Ks:0.5, Qh Jh", "2h 2s")).unwrap();
+        let other: serde_json::Value = serde_json::from_str(&canonicalize_spot("9d 2h As Kh 2c", "Ad Kd:0.5, Qs Js", "2s 2d")).unwrap();
+        assert_eq!((&spot["board"], &spot["ranges"]), (&other["board"], &other["ranges"]));
+
+        let back = |spot: &serde_json::Value, player: usize| relabel_range(spot["ranges"][player].as_str().unwrap(), &spot["inverse"].to_string()).unwrap();
+        assert_eq!(back(&spot, 0), "As Ks:0.5, Qh Jh");
+        assert_eq!(back(&other, 1), "2s 2d");
+    }
+
+    /// Player 0 has three full houses and six hands that lose to every
+    /// queen pair player 1 holds. Facing a pot-sized bet, 1:2 bluffs make
+    /// the queens indifferent, and calling half the time makes the bluffs
+    /// indifferent. That equilibrium is written in directly.
+    fn polarized_session() -> SolverSession {
+        let mut session = SolverSession::from_config(
+            tiny_session().config, "Kh 9d 5c 2s 2h",
+            "Kc Kd, 9h 9s, 5d 5h, 7c 6c, 7d 6d, 7h 6h, 4c 3c, 4d 3d, 4h 3h",
+            "Qc Qd, Qc Qh, Qc Qs, Qd Qh, Qd Qs, Qh Qs",
+        ).unwrap();
+        let bet = child_by_action(&session, 0, ActionType::Bet);
+        set_average_strategy(&mut session, 0, |h| if h < 3 { vec![0.0, 1.0] } else { vec![0.75, 0.25] });
+        set_average_strategy(&mut session, bet, |_| vec![0.5, 0.5]);
+        session
+    }
+
+    /// Overwrite the average strategy at `node_idx` with `probs(hand)`.
+    fn set_average_strategy(session: &mut SolverSession, node_idx: usize, probs: impl Fn(usize) -> Vec<f32>) {
+        let infoset = session.tree.nodes[node_idx].infoset_id as usize;
+        let player = session.tree.nodes[node_idx].player as usize;
+        for h in 0..session.ranges[player].len() {
+            session.trainer.set_average_strategy(infoset, h, &probs(h));
+        }
+    }
+
+    /// Child of `node_idx` reached by `action`.
+    fn child_by_action(session: &SolverSession, node_idx: usize, action: ActionType) -> usize {
+        session.tree.children(node_idx as u32)
+            .find(|&c| session.tree.nodes[c as usize].action_from_parent == Some(action))
+            .unwrap() as usize
+    }
+
+    /// Average probability that player 1 calls a root shove in a 400 chip
+    /// deep spot over a 100 chip pot.
+    fn shove_call_frequency(range0: &str, range1: &str, icm: Option<solver::IcmConfig>, bounty: Option<solver::BountyConfig>) -> f32 {
+        let config = GameConfig {
+            initial_pot: 100.0,
+            stacks: [400.0, 400.0],
+            bet_sizes: vec![],
+            raise_sizes: vec![].into(),
+            river_bet_sizes: None,
+            river_raise_sizes: None,
+            river_cards: RiverCards::All,
+            raise_limit: 0,
+            chip_scale: 100,
+            icm,
+            bounty,
+            abstraction: None,
+            suit_isomorphism: false,
+            profiles: Vec::new(),
+            size_overrides: Vec::new(),
+            sizing_rules: Vec::new(),
+            donk_sizes: None,
+            player_sizes: Default::default(),
+            add_allin: true,
+            variant: GameVariant::Holdem,
+        };
+        let mut session = SolverSession::from_config(config, "Ks Th 7s 4d 2c", range0, range1).unwrap();
+        session.step(1000);
+
+        let shove = child_by_action(&session, 0, ActionType::Bet);
+        let call = child_by_action(&session, shove, ActionType::Call) - session.tree.nodes[shove].children_start as usize;
+        let node = &session.tree.nodes[shove];
+        let hands = session.ranges[1].len();
+        let calls: f32 = (0..hands)
+            .map(|h| session.trainer.average_strategy(node.infoset_id as usize, h, node.num_actions as usize)[call])
+            .sum();
+        calls / hands as f32
+    }
+
+    #[test]
+    fn test_icm_tightens_calling_range() {
+        let range0 = "As Ad, Kc Kd, Ac Kh, 6h 5h, 9h 8h, Qh Jh, 5c 3c, 8c 6c";
+        let range1 = "Kh Qh, Kd Jd, Ah Th, Td 9d, 7h 7c, Ac 7d, 9s 9c, Jc Tc, Ks 4h, As 2d";
+        let chip_ev = shove_call_frequency(range0, range1, None, None);
+        // Bubble: four players left, three paid, with a short stack behind.
+        let icm = shove_call_frequency(range0, range1, Some(solver::IcmConfig {
+            payouts: vec![50.0, 30.0, 20.0],
+            stacks_behind: vec![600.0, 100.0],
+        }), None);
+        assert!(icm < chip_ev - 0.1, "ICM calls {:.3} vs chip EV {:.3}", icm, chip_ev);
+    }
+
+    #[test]
+    fn test_bounty_widens_calling_range() {
+        // Every 7th unblocked combo for each player, so card removal varies
+        // across hands.
+        let board: Vec<Card> = "Ks Th 7s 4d 2c".split_whitespace().filter_map(Card::from_str).collect();
+        let combos: Vec<String> = (0..52u8)
+            .flat_map(|a| (a + 1..52).map(move |b| [Card::from_index(a), Card::from_index(b)]))
+            .filter(|hand| !hand.iter().any(|c| board.contains(c)))
+            .map(|hand| format!("{} {}", hand[0], hand[1]))
+            .collect();
+        let range0 = combos.iter().step_by(7).cloned().collect::<Vec<_>>().join(", ");
+        let range1 = combos.iter().skip(3).step_by(7).cloned().collect::<Vec<_>>().join(", ");
+
+        // Winner-take-all ICM is chip EV with each player's stack accounted
+        // for, with one payout unit per chip.
+        let chips = || Some(solver::IcmConfig { payouts: vec![900.0], stacks_behind: vec![] });
+        let baseline = shove_call_frequency(&range0, &range1, chips(), None);
+        // Felting player 0 is worth another 1000 chips to player 1. Player 0
+        // answers by bluffing less, which keeps most of player 1's mixed
+        // calls at their indifference frequency, so the range widens through
+        // card removal rather than wholesale.
+        let bounty = Some(solver::BountyConfig { own: 2000.0, opponent: 0.0, conversion: 0.5 });
+        let with_bounty = shove_call_frequency(&range0, &range1, chips(), bounty);
+        assert!(with_bounty > baseline + 0.02, "calls {:.3} with bounty vs {:.3} without", with_bounty, baseline);
+    }
+}
@@ -0,0 +1,117 @@
+//! Thread-pool configuration for the `parallel` and `wasm-threads` features.
+//!
+//! Native builds with `parallel` run the equity matrix, CFR training and
+//! best-response passes on rayon's global pool. Its size defaults to one thread per core
+//! and can be set with the `RAYON_NUM_THREADS` environment variable or by
+//! calling [`configure_threads`] (the CLI's `--threads` flag) before any
+//! parallel work starts.
+//!
+//! WASM builds are sequential unless built with `wasm-threads` (see
+//! build.rs for the toolchain flags). Even then, work only fans out once the
+//! page has called `init_thread_pool(n)` and its promise has resolved, so a
+//! threaded build served without cross-origin isolation still works, just
+//! on one thread. [`threads_supported`] tells the frontend which case it is in.
+
+/// Whether this build contains the parallel code paths.
+pub const ENABLED: bool = cfg!(rayon_pool);
+
+#[cfg(all(rayon_pool, target_arch = "wasm32"))]
+static POOL_READY: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Whether parallel passes may use the rayon pool right now. Always true for
+/// native parallel builds; on WASM, true once `init_thread_pool` resolved.
+#[inline]
+pub fn pool_ready() -> bool {
+    #[cfg(all(rayon_pool, target_arch = "wasm32"))]
+    {
+        POOL_READY.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    #[cfg(not(all(rayon_pool, target_arch = "wasm32")))]
+    {
+        ENABLED
+    }
+}
+
+/// Size the global thread pool. Must be called before the first parallel
+/// computation; fails if the pool is already running or the crate was built
+/// without the `parallel` feature. WASM pools are started from JS instead.
+pub fn configure_threads(threads: usize) -> Result<(), String> {
+    if threads == 0 {
+        return Err("Thread count must be at least 1".to_string());
+    }
+
+    #[cfg(all(rayon_pool, not(target_arch = "wasm32")))]
+    {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| format!("Failed to configure thread pool: {}", e))
+    }
+
+    #[cfg(all(rayon_pool, target_arch = "wasm32"))]
+    {
+        Err("WASM worker threads are started with init_thread_pool() from JS".to_string())
+    }
+
+    #[cfg(not(rayon_pool))]
+    {
+        if threads == 1 {
+            Ok(())
+        } else {
+            Err("Built without the `parallel` feature; only 1 thread is available".to_string())
+        }
+    }
+}
+
+/// Number of worker threads parallel passes will use.
+pub fn num_threads() -> usize {
+    #[cfg(rayon_pool)]
+    if pool_ready() {
+        return rayon::current_num_threads();
+    }
+
+    1
+}
+
+/// Whether this runtime can start worker threads: a `wasm-threads` build on
+/// a cross-origin isolated page (native: a `parallel` build).
+pub fn threads_supported() -> bool {
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    {
+        use wasm_bindgen::JsValue;
+
+        let isolated = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("crossOriginIsolated"))
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        ENABLED && isolated
+    }
+
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+    {
+        ENABLED
+    }
+}
+
+/// Start `threads` Web Workers for the rayon pool (wraps `wasm-bindgen-rayon`).
+/// Parallel passes switch over once the returned promise resolves.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn init_thread_pool(threads: usize) -> js_sys::Promise {
+    #[cfg(rayon_pool)]
+    {
+        use wasm_bindgen::closure::Closure;
+        use wasm_bindgen::JsValue;
+
+        let on_ready = Closure::once(|_: JsValue| POOL_READY.store(true, std::sync::atomic::Ordering::Release));
+        let promise = wasm_bindgen_rayon::init_thread_pool(threads.max(1)).then(&on_ready);
+        on_ready.forget();
+        promise
+    }
+
+    #[cfg(not(rayon_pool))]
+    {
+        let _ = threads;
+        js_sys::Promise::reject(&"Built without the `wasm-threads` feature".into())
+    }
+}
@@ -0,0 +1,133 @@
+//! Host platform shims: a `log` backend for the JS console and a monotonic clock.
+//!
+//! The WASM build runs in browsers (main thread and workers) and under
+//! Node.js. Neither `window` nor a DOM `performance` object can be assumed,
+//! so JS globals are resolved through `globalThis` at call time, with
+//! `Date.now()` as the clock fallback. Hosts that want logs routed elsewhere
+//! (or a custom clock) can inject callbacks with [`set_hooks`].
+//!
+//! On wasm32 with the `wasm` feature, [`init_logger`] installs a `log`
+//! backend that forwards records to `console.error/warn/info/debug` by
+//! level, starting at [`DEFAULT_LOG_LEVEL`]. Other builds leave the backend
+//! to the embedding binary (the CLI uses `env_logger`) and use
+//! `std::time::Instant`.
+
+use log::LevelFilter;
+
+/// Level enabled by [`init_logger`]; raise it at runtime with `set_log_level`.
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Warn;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod imp {
+    use std::cell::RefCell;
+
+    use js_sys::{Function, Reflect};
+    use wasm_bindgen::{JsCast, JsValue};
+
+    thread_local! {
+        static LOG_HOOK: RefCell<Option<Function>> = const { RefCell::new(None) };
+        static CLOCK_HOOK: RefCell<Option<Function>> = const { RefCell::new(None) };
+    }
+
+    /// Look up `globalThis[object][method]` and return it with its receiver.
+    fn global_method(object: &str, method: &str) -> Option<(JsValue, Function)> {
+        let target = Reflect::get(&js_sys::global(), &JsValue::from_str(object)).ok()?;
+        if target.is_undefined() || target.is_null() {
+            return None;
+        }
+        let func = Reflect::get(&target, &JsValue::from_str(method)).ok()?;
+        func.dyn_into::<Function>().ok().map(|f| (target, f))
+    }
+
+    pub fn set_hooks(log: Option<Function>, now: Option<Function>) {
+        LOG_HOOK.with(|h| *h.borrow_mut() = log);
+        CLOCK_HOOK.with(|h| *h.borrow_mut() = now);
+    }
+
+    /// `log` backend writing to the injected hook or the JS console.
+    struct ConsoleLogger;
+
+    impl log::Log for ConsoleLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::max_level()
+        }
+
+        fn log(&self, record: &log::Record) {
+            if !self.enabled(record.metadata()) {
+                return;
+            }
+            let msg = JsValue::from_str(&format!("[{}] {}", record.target(), record.args()));
+            let level = JsValue::from_str(record.level().as_str());
+
+            let hooked = LOG_HOOK.with(|h| h.borrow().as_ref().map(|f| f.call2(&JsValue::NULL, &msg, &level).is_ok()));
+            if hooked == Some(true) {
+                return;
+            }
+
+            let method = match record.level() {
+                log::Level::Error => "error",
+                log::Level::Warn => "warn",
+                log::Level::Info => "info",
+                log::Level::Debug | log::Level::Trace => "debug",
+            };
+            if let Some((console, func)) = global_method("console", method).or_else(|| global_method("console", "log")) {
+                let _ = func.call1(&console, &msg);
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: ConsoleLogger = ConsoleLogger;
+
+    pub fn init_logger() {
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(super::DEFAULT_LOG_LEVEL);
+        }
+    }
+
+    pub fn now_ms() -> f64 {
+        let hooked = CLOCK_HOOK.with(|h| {
+            h.borrow().as_ref().and_then(|f| f.call0(&JsValue::NULL).ok()).and_then(|v| v.as_f64())
+        });
+        if let Some(t) = hooked {
+            return t;
+        }
+        global_method("performance", "now")
+            .and_then(|(perf, now)| now.call0(&perf).ok())
+            .and_then(|v| v.as_f64())
+            .unwrap_or_else(js_sys::Date::now)
+    }
+}
+
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+mod imp {
+    use std::sync::LazyLock;
+    use std::time::Instant;
+
+    static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+    /// The embedding binary installs its own backend (e.g. `env_logger`).
+    pub fn init_logger() {}
+
+    pub fn now_ms() -> f64 {
+        EPOCH.elapsed().as_secs_f64() * 1000.0
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use imp::set_hooks;
+pub use imp::{init_logger, now_ms};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_is_monotonic() {
+        let a = now_ms();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let b = now_ms();
+        assert!(b >= a + 1.0, "clock did not advance: {} -> {}", a, b);
+    }
+}
@@ -0,0 +1,334 @@
+//! Playing hands against the solver, for trainer apps.
+//!
+//! A [`PlaySession`] owns a solved [`SolverSession`] and deals one hand at
+//! a time: the hero gets a combo from their range, the villain one from
+//! theirs, and the villain's decisions and the river are drawn from the
+//! solution with a seeded generator, so a seed replays the same hand as
+//! long as the hero plays the same way. Every hero decision is scored
+//! against the solver's mix for that combo: the EV of the action taken
+//! (the solver playing on from there) next to the EV of the mix.
+
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::poker::equity::hand_mask;
+use crate::poker::Card;
+use crate::solver::{LineStep, NodeType};
+use crate::{sample_index, SolverSession};
+
+/// One step of a played hand.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayStep {
+    /// `"hero"`, `"villain"` or `"dealer"` for a river card.
+    pub actor: &'static str,
+    /// The history entry, e.g. `"bet 75"` or `"river Kd"`.
+    pub action: String,
+    /// Node the step leads to.
+    pub node: usize,
+    /// For hero decisions: EV of the action taken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ev: Option<f32>,
+    /// For hero decisions: EV of the solver's mix for the hero's combo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solver_ev: Option<f32>,
+    /// `solver_ev - ev`: what the decision gave up (negative if it beat
+    /// the mix).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ev_loss: Option<f32>,
+}
+
+/// How a finished hand went for the hero.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayResult {
+    /// `"hero"`, `"villain"` or `"split"`.
+    pub winner: &'static str,
+    /// Whether the hand was decided by a fold.
+    pub fold: bool,
+    /// The hero's payoff, in the solver's utilities.
+    pub hero_value: f32,
+    pub villain_hand: String,
+    /// Sum of the hero's `ev_loss` over the hand.
+    pub ev_loss: f32,
+    /// E.g. `"You lost 0.40 by checking"` for the costliest decision.
+    pub message: String,
+}
+
+/// A hand being played against the solver (see the module docs).
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PlaySession {
+    session: SolverSession,
+    hero: usize,
+    /// SplitMix64 state for dealing and villain decisions.
+    state: u64,
+    /// Combo index of each player's hand in their range.
+    hands: [usize; 2],
+    node: usize,
+    /// River dealt for a turn showdown after an all-in.
+    runout: Option<Card>,
+    log: Vec<PlayStep>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl PlaySession {
+    /// Take over a solved `session` (the JS object is consumed) with the
+    /// hero as `hero` (0 or 1), and deal the first hand.
+    #[wasm_bindgen(constructor)]
+    pub fn new(session: SolverSession, hero: usize, seed: u64) -> Result<PlaySession, JsValue> {
+        PlaySession::start(session, hero, seed).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Deal the next hand and play up to the hero's first decision.
+    /// Returns the state as in `get_state`.
+    pub fn deal(&mut self) -> Result<String, JsValue> {
+        self.deal_hand().map_err(|e| JsValue::from_str(&e))?;
+        Ok(self.status().to_string())
+    }
+
+    /// Take `action` (e.g. `"bet 75"`, `"call"`) for the hero, then play
+    /// on to their next decision or the end of the hand. Amounts must be
+    /// one of the sizes offered. Returns the state as in `get_state`.
+    pub fn act(&mut self, action: &str) -> Result<String, JsValue> {
+        self.play(action).map_err(|e| JsValue::from_str(&e))?;
+        Ok(self.status().to_string())
+    }
+
+    /// The hand so far as JSON: `{hero, hand, board, node, pot, to_act,
+    /// actions, log, result}`. `actions` lists the hero's options when it
+    /// is their turn; `result` is `null` until the hand is over.
+    pub fn get_state(&self) -> String {
+        self.status().to_string()
+    }
+}
+
+impl PlaySession {
+    /// Native constructor (see `new`).
+    pub fn start(session: SolverSession, hero: usize, seed: u64) -> Result<PlaySession, String> {
+        if hero > 1 {
+            return Err(format!("Invalid player {}", hero));
+        }
+        let mut play = PlaySession { session, hero, state: seed, hands: [0, 0], node: 0, runout: None, log: Vec::new() };
+        play.deal_hand()?;
+        Ok(play)
+    }
+
+    /// The solved session being played.
+    pub fn session(&self) -> &SolverSession {
+        &self.session
+    }
+
+    /// The hero's combo, e.g. `"Kc Kd"`.
+    pub fn hero_hand(&self) -> String {
+        self.hand_string(self.hero)
+    }
+
+    /// Whether the current node ends the hand.
+    pub fn finished(&self) -> bool {
+        self.session.tree.nodes[self.node].is_terminal()
+    }
+
+    /// Steps taken in this hand so far.
+    pub fn log(&self) -> &[PlayStep] {
+        &self.log
+    }
+
+    /// Deal both hands and play up to the hero's first decision.
+    pub fn deal_hand(&mut self) -> Result<(), String> {
+        let board = hand_mask(&self.session.board);
+        let hero = self.draw_combo(self.hero, board).ok_or("The hero's range has no combo to deal")?;
+        let hero_cards = hand_mask(&self.session.ranges[self.hero][hero]);
+        let villain = self.draw_combo(1 - self.hero, board | hero_cards).ok_or("The villain's range has no combo to deal")?;
+        self.hands[self.hero] = hero;
+        self.hands[1 - self.hero] = villain;
+        self.node = 0;
+        self.runout = None;
+        self.log.clear();
+        self.advance()
+    }
+
+    /// Take a hero action (see `act`).
+    pub fn play(&mut self, action: &str) -> Result<(), String> {
+        let node = &self.session.tree.nodes[self.node];
+        if node.is_terminal() {
+            return Err("The hand is over; deal the next one".to_string());
+        }
+        if node.node_type != NodeType::Action || node.player as usize != self.hero {
+            return Err("It is not the hero's turn".to_string());
+        }
+        // Amounts within half a chip of an offered size
+        let tolerance = 0.5 / self.session.tree.chip_scale as f32;
+        let child = LineStep::parse(action)
+            .and_then(|step| self.session.tree.find_child(self.node as u32, &step, tolerance))
+            .ok_or_else(|| format!("'{}' is not an option here (available: {})", action,
+                                   self.session.get_available_actions_at_node(self.node)))? as usize;
+
+        let a = child - node.children_start as usize;
+        let opp_reach = &self.session.path_reach(&self.session.path_to(self.node))[1 - self.hero];
+        let hand = self.hands[self.hero];
+        let (ev, solver_ev) = match (self.session.action_values(self.node, hand, opp_reach), self.session.playable_strategy(self.node, hand)) {
+            (Some(values), Some(probs)) => (Some(values[a]), Some(values.iter().zip(&probs).map(|(v, p)| v * p).sum::<f32>())),
+            _ => (None, None),
+        };
+        self.log.push(PlayStep {
+            actor: "hero",
+            action: self.session.action_label(child),
+            node: child,
+            ev,
+            solver_ev,
+            ev_loss: ev.zip(solver_ev).map(|(ev, best)| best - ev),
+        });
+        self.node = child;
+        self.advance()
+    }
+
+    /// The state as in `get_state`.
+    pub fn status(&self) -> serde_json::Value {
+        let node = &self.session.tree.nodes[self.node];
+        let mut board: Vec<String> = self.session.board.iter().map(|c| c.to_string()).collect();
+        board.extend(node.river.map(Card::from_index).or(self.runout).map(|c| c.to_string()));
+        let hero_turn = node.node_type == NodeType::Action && node.player as usize == self.hero;
+        let actions: Vec<String> = if hero_turn {
+            self.session.tree.children(self.node as u32)
+                .map(|c| self.session.action_label(c as usize))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        serde_json::json!({
+            "hero": self.hero,
+            "hand": self.hero_hand(),
+            "board": board.join(" "),
+            "node": self.node,
+            "pot": self.session.tree.to_amount(node.pot),
+            "to_act": if hero_turn { Some("hero") } else { None },
+            "actions": actions,
+            "log": self.log,
+            "result": self.result(),
+        })
+    }
+
+    /// How the hand went, once it is over.
+    pub fn result(&self) -> Option<PlayResult> {
+        let node = &self.session.tree.nodes[self.node];
+        if !node.is_terminal() {
+            return None;
+        }
+        let outcome = self.session.tree.outcome(node);
+        let (winner, hero_value) = match node.node_type {
+            // A fold node's player is the one who did not fold
+            NodeType::Terminal => {
+                let won = node.player as usize == self.hero;
+                let values = if node.player == 0 { outcome.p0_wins } else { outcome.p1_wins };
+                (if won { "hero" } else { "villain" }, values[self.hero])
+            }
+            _ => {
+                let board: Vec<Card> = self.session.board.iter().copied().chain(node.river.map(Card::from_index).or(self.runout)).collect();
+                let score = |p: usize| self.session.config.variant.evaluate(&[self.session.ranges[p][self.hands[p]].as_slice(), &board].concat());
+                let (hero, villain) = (score(self.hero), score(1 - self.hero));
+                let hero_wins = if self.hero == 0 { outcome.p0_wins } else { outcome.p1_wins }[self.hero];
+                let villain_wins = if self.hero == 0 { outcome.p1_wins } else { outcome.p0_wins }[self.hero];
+                // Lower scores are better
+                match hero.cmp(&villain) {
+                    std::cmp::Ordering::Less => ("hero", hero_wins),
+                    std::cmp::Ordering::Greater => ("villain", villain_wins),
+                    std::cmp::Ordering::Equal => ("split", (hero_wins + villain_wins) / 2.0),
+                }
+            }
+        };
+
+        let ev_loss = self.log.iter().filter_map(|s| s.ev_loss).sum();
+        let costliest = self.log.iter()
+            .filter(|s| s.ev_loss.is_some_and(|l| l > 0.005))
+            .max_by(|a, b| a.ev_loss.partial_cmp(&b.ev_loss).expect("losses are finite"));
+        let message = match costliest {
+            Some(step) => format!("You lost {:.2} by {}", step.ev_loss.expect("filtered on the loss"), gerund(&step.action)),
+            None => "You matched the solver".to_string(),
+        };
+        Some(PlayResult {
+            winner,
+            fold: node.node_type == NodeType::Terminal,
+            hero_value,
+            villain_hand: self.hand_string(1 - self.hero),
+            ev_loss,
+            message,
+        })
+    }
+
+    /// Deal rivers and play the villain until the hero is to act or the
+    /// hand is over.
+    fn advance(&mut self) -> Result<(), String> {
+        loop {
+            let node = &self.session.tree.nodes[self.node];
+            let children = self.session.tree.children(self.node as u32).map(|c| c as usize);
+            let (actor, child) = match node.node_type {
+                NodeType::Terminal => return Ok(()),
+                NodeType::Showdown => {
+                    // All in on the turn: run the river out
+                    if self.session.board.len() == 4 && node.river.is_none() {
+                        let held = self.held();
+                        let live: Vec<Card> = (0..52).map(Card::from_index)
+                            .filter(|&c| self.session.config.variant.in_deck(c) && c.bitmask() & (held | hand_mask(&self.session.board)) == 0)
+                            .collect();
+                        let river = live[sample_index(&vec![1.0 / live.len() as f32; live.len()], &mut self.state)];
+                        self.runout = Some(river);
+                        self.log.push(PlayStep { actor: "dealer", action: format!("river {}", river), node: self.node, ev: None, solver_ev: None, ev_loss: None });
+                    }
+                    return Ok(());
+                }
+                NodeType::Action if node.player as usize == self.hero => return Ok(()),
+                NodeType::Action => {
+                    let probs = self.session.playable_strategy(self.node, self.hands[1 - self.hero])
+                        .ok_or_else(|| format!("The villain has no action at node {}", self.node))?;
+                    ("villain", node.children_start as usize + sample_index(&probs, &mut self.state))
+                }
+                NodeType::Chance => {
+                    let held = self.held();
+                    let live: Vec<usize> = children
+                        .filter(|&c| self.session.tree.nodes[c].river.is_some_and(|r| Card::from_index(r).bitmask() & held == 0))
+                        .collect();
+                    if live.is_empty() {
+                        return Err("No river can be dealt to these hands".to_string());
+                    }
+                    let probs = vec![1.0 / live.len() as f32; live.len()];
+                    ("dealer", live[sample_index(&probs, &mut self.state)])
+                }
+            };
+            self.log.push(PlayStep { actor, action: self.session.action_label(child), node: child, ev: None, solver_ev: None, ev_loss: None });
+            self.node = child;
+        }
+    }
+
+    /// A combo of `player`'s range drawn by range weight among those not
+    /// touching `dead`.
+    fn draw_combo(&mut self, player: usize, dead: u64) -> Option<usize> {
+        let weights: Vec<f32> = self.session.ranges[player].iter().zip(&self.session.initial_reach[player])
+            .map(|(hand, &w)| if hand_mask(hand) & dead == 0 { w.max(0.0) } else { 0.0 })
+            .collect();
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let probs: Vec<f32> = weights.iter().map(|w| w / total).collect();
+        Some(sample_index(&probs, &mut self.state))
+    }
+
+    /// Card mask of both players' hands.
+    fn held(&self) -> u64 {
+        hand_mask(&self.session.ranges[0][self.hands[0]]) | hand_mask(&self.session.ranges[1][self.hands[1]])
+    }
+
+    fn hand_string(&self, player: usize) -> String {
+        self.session.ranges[player][self.hands[player]].iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// `"checking"`, `"betting 75"`, ... for a history entry.
+fn gerund(action: &str) -> String {
+    let (verb, rest) = action.split_once(' ').unwrap_or((action, ""));
+    let verb = match verb {
+        "bet" => "betting".to_string(),
+        verb => format!("{}ing", verb.trim_end_matches('e')),
+    };
+    if rest.is_empty() { verb } else { format!("{} {}", verb, rest) }
+}
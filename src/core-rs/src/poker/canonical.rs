@@ -0,0 +1,183 @@
+//! Canonical suits for spots.
+//!
+//! Spots that a suit relabelling maps onto each other play the same (see
+//! [`crate::poker::isomorphism`]), so a solve of `Ah Kd 2c 2d 9s` also
+//! answers `As Kh 2c 2h 9d`. [`canonicalize`] picks one member of each such
+//! class and the [`SuitMap`] that leads to it; the map's
+//! [`inverse`](SuitMap::inverse) takes the cards of a solution cached for
+//! the canonical spot back to the caller's suits.
+//!
+//! The canonical member is the relabelling with the smallest encoding of
+//! the board and then the ranges, the same one [`crate::solver::hash::solve_key`]
+//! hashes with `suit_isomorphism` on.
+
+use std::collections::BTreeMap;
+
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::poker::card::SUIT_CHARS;
+use crate::poker::isomorphism::{permute_card, suit_permutations, SuitPermutation};
+use crate::poker::Card;
+
+/// A relabelling of the suits. Serializes as a map between suit letters,
+/// e.g. `{"c": "d", "d": "c", "h": "h", "s": "s"}`; each suit must appear
+/// once on either side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuitMap(pub SuitPermutation);
+
+impl SuitMap {
+    pub const IDENTITY: SuitMap = SuitMap([0, 1, 2, 3]);
+
+    pub fn card(&self, card: Card) -> Card {
+        permute_card(card, &self.0)
+    }
+
+    pub fn cards(&self, cards: &[Card]) -> Vec<Card> {
+        cards.iter().map(|&c| self.card(c)).collect()
+    }
+
+    /// Relabel every combo of a weighted range, keeping the weights.
+    pub fn range(&self, range: &[(Vec<Card>, f32)]) -> Vec<(Vec<Card>, f32)> {
+        range.iter().map(|(hand, weight)| (self.cards(hand), *weight)).collect()
+    }
+
+    /// The relabelling that undoes this one.
+    pub fn inverse(&self) -> SuitMap {
+        let mut inverse = [0; 4];
+        for (suit, &to) in self.0.iter().enumerate() {
+            inverse[to as usize] = suit as u8;
+        }
+        SuitMap(inverse)
+    }
+
+}
+
+impl Serialize for SuitMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(4))?;
+        for (suit, &to) in self.0.iter().enumerate() {
+            map.serialize_entry(&SUIT_CHARS[suit], &SUIT_CHARS[to as usize])?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for SuitMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let letters = BTreeMap::<char, char>::deserialize(deserializer)?;
+        let suit = |c: char| SUIT_CHARS.iter().position(|&s| s == c.to_ascii_lowercase()).ok_or_else(|| D::Error::custom(format!("Unknown suit '{}'", c)));
+        let mut perm = [u8::MAX; 4];
+        for (from, to) in letters {
+            perm[suit(from)?] = suit(to)? as u8;
+        }
+        let mut seen = perm;
+        seen.sort_unstable();
+        if seen != [0, 1, 2, 3] {
+            return Err(D::Error::custom("A suit map must send each of c, d, h, s to a different suit"));
+        }
+        Ok(SuitMap(perm))
+    }
+}
+
+/// A spot in canonical suits, and the map from the caller's suits to them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanonicalSpot {
+    /// Board cards, highest index first.
+    pub board: Vec<Card>,
+    pub ranges: [Vec<(Vec<Card>, f32)>; 2],
+    pub map: SuitMap,
+}
+
+/// The canonical member of the spot's suit isomorphism class.
+pub fn canonicalize(board: &[Card], ranges: [&[(Vec<Card>, f32)]; 2]) -> CanonicalSpot {
+    let hands = ranges.map(|range| range.iter().map(|(hand, _)| hand.clone()).collect::<Vec<_>>());
+    let weights = ranges.map(|range| range.iter().map(|(_, weight)| *weight).collect::<Vec<_>>());
+    let map = SuitMap(canonical_permutation(board, [&hands[0], &hands[1]], [&weights[0], &weights[1]]));
+
+    let mut board = map.cards(board);
+    board.sort_unstable_by_key(|c| std::cmp::Reverse(c.index()));
+    CanonicalSpot { board, ranges: ranges.map(|range| map.range(range)), map }
+}
+
+/// [`canonicalize`] of a board alone.
+pub fn canonical_board(board: &[Card]) -> (Vec<Card>, SuitMap) {
+    let spot = canonicalize(board, [&[], &[]]);
+    (spot.board, spot.map)
+}
+
+/// The suit permutation that takes a spot to its canonical member: the
+/// first one with the smallest [`spot_encoding`].
+pub fn canonical_permutation(board: &[Card], ranges: [&[Vec<Card>]; 2], weights: [&[f32]; 2]) -> SuitPermutation {
+    suit_permutations()
+        .into_iter()
+        .min_by_key(|perm| spot_encoding(board, ranges, weights, perm))
+        .expect("at least the identity")
+}
+
+/// Card indices of a combo, highest first.
+pub(crate) type CardCodes = Vec<u8>;
+
+/// A spot relabelled by `perm` in a form that ignores card and combo order:
+/// the board's card indices, highest first, and each range's combos (also
+/// highest card first) with their weight bits, sorted.
+pub(crate) fn spot_encoding(
+    board: &[Card],
+    ranges: [&[Vec<Card>]; 2],
+    weights: [&[f32]; 2],
+    perm: &SuitPermutation,
+) -> (CardCodes, [Vec<(CardCodes, u32)>; 2]) {
+    let relabel = |cards: &[Card]| -> CardCodes {
+        let mut indices: CardCodes = cards.iter().map(|&c| permute_card(c, perm).index()).collect();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        indices
+    };
+    let mut combos = [0, 1].map(|p| {
+        ranges[p].iter().zip(weights[p]).map(|(hand, w)| (relabel(hand), w.to_bits())).collect::<Vec<_>>()
+    });
+    combos.iter_mut().for_each(|c| c.sort_unstable());
+    (relabel(board), combos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::range::parse_weighted_range;
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().filter_map(Card::from_str).collect()
+    }
+
+    #[test]
+    fn test_isomorphic_spots_share_a_canonical_form() {
+        let (a, map_a) = canonical_board(&cards("Ah Kd 2c 2d 9s"));
+        let (b, map_b) = canonical_board(&cards("9d 2h As Kh 2c"));
+        assert_eq!(a, b);
+        assert_ne!(map_a, map_b);
+        // Suited ace-king is another board
+        assert_ne!(canonical_board(&cards("Ah Kh 2c 2d 9s")).0, a);
+
+        // Ranges follow the relabelling, so isomorphic spots agree on them too
+        let board = cards("Ah Kd 2c 2d 9s");
+        let range = parse_weighted_range("As Ks:0.5, Qh Jh");
+        let spot = canonicalize(&board, [&range, &range]);
+        let relabel = SuitMap([2, 1, 3, 0]);
+        let moved = canonicalize(&relabel.cards(&board), [&relabel.range(&range), &relabel.range(&range)]);
+        assert_eq!((&moved.board, &moved.ranges), (&spot.board, &spot.ranges));
+
+        // The inverse takes the canonical spot back to the caller's suits
+        assert_eq!(spot.map.inverse().range(&spot.ranges[0]), range);
+        assert_eq!(moved.map.inverse().range(&moved.ranges[1]), relabel.range(&range));
+    }
+
+    #[test]
+    fn test_suit_map_letters() {
+        let map = SuitMap([1, 0, 2, 3]);
+        assert_eq!(serde_json::to_value(map).unwrap(), serde_json::json!({"c": "d", "d": "c", "h": "h", "s": "s"}));
+        assert_eq!(serde_json::from_value::<SuitMap>(serde_json::to_value(map).unwrap()).unwrap(), map);
+        assert_eq!(map.inverse(), map);
+        assert!(serde_json::from_str::<SuitMap>(r#"{"c": "d", "d": "d", "h": "h", "s": "s"}"#).is_err());
+        assert!(serde_json::from_str::<SuitMap>(r#"{"c": "d", "d": "c", "h": "h"}"#).is_err());
+        assert_eq!(SuitMap([1, 2, 3, 0]).inverse(), SuitMap([3, 0, 1, 2]));
+    }
+}
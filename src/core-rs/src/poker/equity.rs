@@ -1,217 +1,413 @@
-//! Equity Matrix Computation
-//! 
-//! Computes win/loss/tie equity between all hand combinations in two ranges.
-//! Used by the solver for O(1) equity lookups during CFR iterations.
-
-use crate::poker::{Card, evaluate_7_cards};
-
-/// Check if two card sets share any cards (blockers)
-#[inline]
-fn has_blocker(cards1: &[Card], cards2: &[Card]) -> bool {
-    for c1 in cards1 {
-        for c2 in cards2 {
-            if c1 == c2 {
-                return true;
-            }
-        }
-    }
-    false
-}
-
-/// Check if hand shares any cards with board
-#[inline]
-fn hand_blocked_by_board(hand: &[Card], board: &[Card]) -> bool {
-    has_blocker(hand, board)
-}
-
-/// Compute equity matrix between two ranges on a given board.
-/// 
-/// # Arguments
-/// * `board` - Community cards (5 cards for river)
-/// * `range1` - First player's range (list of hands, each hand is 2 cards)
-/// * `range2` - Second player's range (list of hands, each hand is 2 cards)
-/// 
-/// # Returns
-/// Flattened matrix of size `range1.len() * range2.len()` where:
-/// * `1.0` = hand1 wins
-/// * `0.0` = hand1 loses  
-/// * `0.5` = tie
-/// * `NaN` = impossible matchup (blocked)
-pub fn compute_equity_matrix(
-    board: &[Card],
-    range1: &[Vec<Card>],
-    range2: &[Vec<Card>],
-) -> Vec<f32> {
-    let n1 = range1.len();
-    let n2 = range2.len();
-    let mut result = vec![f32::NAN; n1 * n2];
-    
-    for (i, hand1) in range1.iter().enumerate() {
-        // Skip if hand1 blocked by board
-        if hand_blocked_by_board(hand1, board) {
-            continue;
-        }
-        
-        // Build 7-card hand for player 1
-        let mut cards1: Vec<Card> = hand1.clone();
-        cards1.extend(board.iter().cloned());
-        let score1 = evaluate_7_cards(&cards1);
-        
-        for (j, hand2) in range2.iter().enumerate() {
-            let idx = i * n2 + j;
-            
-            // Check blockers
-            if hand_blocked_by_board(hand2, board) {
-                continue; // result[idx] stays NaN
-            }
-            
-            if has_blocker(hand1, hand2) {
-                continue; // result[idx] stays NaN
-            }
-            
-            // Build 7-card hand for player 2
-            let mut cards2: Vec<Card> = hand2.clone();
-            cards2.extend(board.iter().cloned());
-            let score2 = evaluate_7_cards(&cards2);
-            
-            // Compare (lower score = better hand)
-            result[idx] = if score1 < score2 {
-                1.0 // hand1 wins
-            } else if score1 > score2 {
-                0.0 // hand1 loses
-            } else {
-                0.5 // tie
-            };
-        }
-    }
-    
-    result
-}
-
-/// Compute single matchup equity between two hands on a board
-/// 
-/// # Returns
-/// * `Some(1.0)` = hand1 wins
-/// * `Some(0.0)` = hand1 loses
-/// * `Some(0.5)` = tie
-/// * `None` = impossible matchup (blocked)
-pub fn compute_single_equity(
-    board: &[Card],
-    hand1: &[Card],
-    hand2: &[Card],
-) -> Option<f32> {
-    // Check blockers
-    if hand_blocked_by_board(hand1, board) ||
-       hand_blocked_by_board(hand2, board) ||
-       has_blocker(hand1, hand2) {
-        return None;
-    }
-    
-    let mut cards1: Vec<Card> = hand1.to_vec();
-    cards1.extend(board.iter().cloned());
-    
-    let mut cards2: Vec<Card> = hand2.to_vec();
-    cards2.extend(board.iter().cloned());
-    
-    let score1 = evaluate_7_cards(&cards1);
-    let score2 = evaluate_7_cards(&cards2);
-    
-    Some(if score1 < score2 {
-        1.0
-    } else if score1 > score2 {
-        0.0
-    } else {
-        0.5
-    })
-}
-
-// ============================================================================
-// TESTS
-// ============================================================================
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::poker::Card;
-
-    fn cards_from_str(s: &str) -> Vec<Card> {
-        s.split_whitespace()
-            .map(|cs| Card::from_str(cs).expect(&format!("Invalid card: {}", cs)))
-            .collect()
-    }
-
-    #[test]
-    fn test_single_equity_win() {
-        let board = cards_from_str("Kh Qd Jc 2s 3h");
-        let hand1 = cards_from_str("As Ks"); // Pair of kings with A kicker
-        let hand2 = cards_from_str("Kd 5c"); // Pair of kings with 5 kicker
-        
-        let equity = compute_single_equity(&board, &hand1, &hand2);
-        assert_eq!(equity, Some(1.0), "AK should beat K5");
-    }
-
-    #[test]
-    fn test_single_equity_loss() {
-        let board = cards_from_str("Kh Qd Jc 2s 3h");
-        let hand1 = cards_from_str("Kd 5c");
-        let hand2 = cards_from_str("As Ks");
-        
-        let equity = compute_single_equity(&board, &hand1, &hand2);
-        assert_eq!(equity, Some(0.0), "K5 should lose to AK");
-    }
-
-    #[test]
-    fn test_single_equity_tie() {
-        let board = cards_from_str("As Ks Qs Js Ts"); // Royal flush on board
-        let hand1 = cards_from_str("2c 3c");
-        let hand2 = cards_from_str("4c 5c");
-        
-        let equity = compute_single_equity(&board, &hand1, &hand2);
-        assert_eq!(equity, Some(0.5), "Should tie on board royal flush");
-    }
-
-    #[test]
-    fn test_single_equity_blocked() {
-        let board = cards_from_str("Kh Qd Jc 2s 3h");
-        let hand1 = cards_from_str("As Kh"); // Kh is on board!
-        let hand2 = cards_from_str("Kd 5c");
-        
-        let equity = compute_single_equity(&board, &hand1, &hand2);
-        assert_eq!(equity, None, "Should be blocked - Kh on board");
-    }
-
-    #[test]
-    fn test_single_equity_hand_collision() {
-        let board = cards_from_str("Kh Qd Jc 2s 3h");
-        let hand1 = cards_from_str("As Ks");
-        let hand2 = cards_from_str("As Kd"); // As overlaps!
-        
-        let equity = compute_single_equity(&board, &hand1, &hand2);
-        assert_eq!(equity, None, "Should be blocked - As in both hands");
-    }
-
-    #[test]
-    fn test_equity_matrix() {
-        let board = cards_from_str("Kh Qd Jc 2s 3h");
-        let range1 = vec![
-            cards_from_str("As Ks"),
-            cards_from_str("9c 9d"),
-        ];
-        let range2 = vec![
-            cards_from_str("Kd 5c"),
-            cards_from_str("4c 5d"),
-        ];
-        
-        let matrix = compute_equity_matrix(&board, &range1, &range2);
-        
-        assert_eq!(matrix.len(), 4);
-        // AKs vs K5o - AK wins
-        assert!(!matrix[0].is_nan());
-        // AKs vs 45o - AK wins  
-        assert!(!matrix[1].is_nan());
-        // 99 vs K5o
-        assert!(!matrix[2].is_nan());
-        // 99 vs 45o
-        assert!(!matrix[3].is_nan());
-    }
-}
+//! Equity Matrix Computation
+//! 
+//! Computes win/loss/tie equity between all hand combinations in two ranges.
+//! Used by the solver for O(1) equity lookups during CFR iterations.
+
+use crate::poker::{Card, GameVariant, evaluate_7_cards};
+use crate::simd;
+
+/// Check if two card sets share any cards (blockers)
+#[inline]
+fn has_blocker(cards1: &[Card], cards2: &[Card]) -> bool {
+    for c1 in cards1 {
+        for c2 in cards2 {
+            if c1 == c2 {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Check if hand shares any cards with board
+#[inline]
+fn hand_blocked_by_board(hand: &[Card], board: &[Card]) -> bool {
+    has_blocker(hand, board)
+}
+
+/// Compute equity matrix between two ranges on a given board.
+/// 
+/// # Arguments
+/// * `board` - Community cards (5 cards for river)
+/// * `range1` - First player's range (list of hands, each hand is 2 cards)
+/// * `range2` - Second player's range (list of hands, each hand is 2 cards)
+/// 
+/// # Returns
+/// Flattened matrix of size `range1.len() * range2.len()` where:
+/// * `1.0` = hand1 wins
+/// * `0.0` = hand1 loses  
+/// * `0.5` = tie
+/// * `NaN` = impossible matchup (blocked)
+///
+/// Rows are filled concurrently when the rayon pool is available (`parallel`
+/// natively, `wasm-threads` after `init_thread_pool` on WASM).
+pub fn compute_equity_matrix(
+    board: &[Card],
+    range1: &[Vec<Card>],
+    range2: &[Vec<Card>],
+) -> Vec<f32> {
+    compute_equity_matrix_for(GameVariant::Holdem, board, range1, range2)
+}
+
+/// [`compute_equity_matrix`] with the hand rankings of `variant`.
+pub fn compute_equity_matrix_for(
+    variant: GameVariant,
+    board: &[Card],
+    range1: &[Vec<Card>],
+    range2: &[Vec<Card>],
+) -> Vec<f32> {
+    let n1 = range1.len();
+    let n2 = range2.len();
+    let mut result = vec![f32::NAN; n1 * n2];
+    if n2 == 0 {
+        return result;
+    }
+
+    // Each hand is evaluated once; board-blocked hands get a mask that
+    // conflicts with everything so their cells come out NaN
+    let scores2: Vec<u16> = range2.iter().map(|h| hand_score(variant, h, board).unwrap_or(0)).collect();
+    let masks2: Vec<u64> = range2
+        .iter()
+        .map(|h| if hand_blocked_by_board(h, board) { u64::MAX } else { hand_mask(h) })
+        .collect();
+
+    #[cfg(rayon_pool)]
+    if crate::parallel::pool_ready() {
+        use rayon::prelude::*;
+        result
+            .par_chunks_mut(n2)
+            .zip(range1.par_iter())
+            .for_each(|(row, hand1)| fill_equity_row(variant, row, hand1, board, &scores2, &masks2));
+        return result;
+    }
+
+    for (row, hand1) in result.chunks_mut(n2).zip(range1.iter()) {
+        fill_equity_row(variant, row, hand1, board, &scores2, &masks2);
+    }
+
+    result
+}
+
+/// [`compute_equity_matrix_for`] on `board`, followed by one block per card
+/// index in `rivers` on the board with that river dealt: the matrix a turn
+/// tree trains on (just the first block for a river board). The rivers are
+/// computed concurrently when the rayon pool is available.
+pub fn compute_equity_matrix_with_rivers(
+    variant: GameVariant,
+    board: &[Card],
+    rivers: &[u8],
+    range1: &[Vec<Card>],
+    range2: &[Vec<Card>],
+) -> Vec<f32> {
+    let river_block = |&river: &u8| {
+        let mut river_board = board.to_vec();
+        river_board.push(Card::from_index(river));
+        compute_equity_matrix_for(variant, &river_board, range1, range2)
+    };
+    let mut result = compute_equity_matrix_for(variant, board, range1, range2);
+
+    #[cfg(rayon_pool)]
+    if crate::parallel::pool_ready() {
+        use rayon::prelude::*;
+        let blocks: Vec<Vec<f32>> = rivers.par_iter().map(river_block).collect();
+        result.extend(blocks.into_iter().flatten());
+        return result;
+    }
+
+    for river in rivers {
+        result.extend(river_block(river));
+    }
+    result
+}
+
+/// Score of a hole-card combo on the board, or None if a card is on the board.
+fn hand_score(variant: GameVariant, hand: &[Card], board: &[Card]) -> Option<u16> {
+    if hand_blocked_by_board(hand, board) {
+        return None;
+    }
+    let mut cards: Vec<Card> = hand.to_vec();
+    cards.extend(board.iter().cloned());
+    Some(variant.evaluate(&cards))
+}
+
+/// Bitmask of a set of cards (see [`Card::bitmask`]).
+#[inline]
+pub fn hand_mask(hand: &[Card]) -> u64 {
+    hand.iter().fold(0, |mask, c| mask | c.bitmask())
+}
+
+/// Fill one row of the equity matrix (hand1 against every hand of range2).
+/// Rows of board-blocked hands are left untouched (NaN).
+#[inline]
+fn fill_equity_row(variant: GameVariant, row: &mut [f32], hand1: &[Card], board: &[Card], scores2: &[u16], masks2: &[u64]) {
+    let Some(score1) = hand_score(variant, hand1, board) else {
+        return;
+    };
+    simd::equity_row(score1, hand_mask(hand1), scores2, masks2, row);
+}
+
+/// Each hand's showdown equity against the other player's range.
+///
+/// `matrix` holds one or more `weights[0].len() x weights[1].len()` blocks
+/// of [`compute_equity_matrix`] output (a turn spot has one per river);
+/// every possible matchup in every block counts, weighted by the opponent
+/// hand's entry in `weights` (range weights or reach). Hands without a
+/// possible matchup get NaN.
+pub fn range_equities(matrix: &[f32], weights: [&[f32]; 2]) -> [Vec<f32>; 2] {
+    let (n0, n1) = (weights[0].len(), weights[1].len());
+    let mut equity = [vec![0.0; n0], vec![0.0; n1]];
+    let mut total = [vec![0.0; n0], vec![0.0; n1]];
+
+    if n0 > 0 && n1 > 0 {
+        for block in matrix.chunks_exact(n0 * n1) {
+            for (h0, row) in block.chunks_exact(n1).enumerate() {
+                let (e, t) = simd::masked_sums(row, weights[1]);
+                equity[0][h0] += e;
+                total[0][h0] += t;
+                let [_, equity1] = &mut equity;
+                let [_, total1] = &mut total;
+                simd::accumulate_opponent(row, weights[0][h0], equity1, total1);
+            }
+        }
+    }
+
+    let mut result = equity;
+    for (eq, t) in result.iter_mut().zip(&total) {
+        for (e, &t) in eq.iter_mut().zip(t) {
+            *e = if t > 0.0 { *e / t } else { f32::NAN };
+        }
+    }
+    result
+}
+
+/// Each player's overall equity of their range against the other's: the
+/// equity of every possible matchup in `matrix` (laid out as for
+/// [`range_equities`]), weighted by both hands' entries in `weights`, so
+/// card removal shapes the result. NaN when no matchup is possible.
+pub fn total_equity(matrix: &[f32], weights: [&[f32]; 2]) -> [f32; 2] {
+    let (n0, n1) = (weights[0].len(), weights[1].len());
+    let (mut equity, mut total) = (0.0f64, 0.0f64);
+    if n0 > 0 && n1 > 0 {
+        for block in matrix.chunks_exact(n0 * n1) {
+            for (row, &w0) in block.chunks_exact(n1).zip(weights[0]) {
+                for (&e, &w1) in row.iter().zip(weights[1]) {
+                    if !e.is_nan() {
+                        let w = w0 as f64 * w1 as f64;
+                        equity += w * e as f64;
+                        total += w;
+                    }
+                }
+            }
+        }
+    }
+    if total > 0.0 {
+        let equity = (equity / total) as f32;
+        [equity, 1.0 - equity]
+    } else {
+        [f32::NAN; 2]
+    }
+}
+
+/// Compute single matchup equity between two hands on a board
+/// 
+/// # Returns
+/// * `Some(1.0)` = hand1 wins
+/// * `Some(0.0)` = hand1 loses
+/// * `Some(0.5)` = tie
+/// * `None` = impossible matchup (blocked)
+pub fn compute_single_equity(
+    board: &[Card],
+    hand1: &[Card],
+    hand2: &[Card],
+) -> Option<f32> {
+    // Check blockers
+    if hand_blocked_by_board(hand1, board) ||
+       hand_blocked_by_board(hand2, board) ||
+       has_blocker(hand1, hand2) {
+        return None;
+    }
+    
+    let mut cards1: Vec<Card> = hand1.to_vec();
+    cards1.extend(board.iter().cloned());
+    
+    let mut cards2: Vec<Card> = hand2.to_vec();
+    cards2.extend(board.iter().cloned());
+    
+    let score1 = evaluate_7_cards(&cards1);
+    let score2 = evaluate_7_cards(&cards2);
+    
+    Some(if score1 < score2 {
+        1.0
+    } else if score1 > score2 {
+        0.0
+    } else {
+        0.5
+    })
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::Card;
+
+    fn cards_from_str(s: &str) -> Vec<Card> {
+        s.split_whitespace()
+            .map(|cs| Card::from_str(cs).unwrap_or_else(|| panic!("Invalid card: {}", cs)))
+            .collect()
+    }
+
+    #[test]
+    fn test_single_equity_win() {
+        let board = cards_from_str("Kh Qd Jc 2s 3h");
+        let hand1 = cards_from_str("As Ks"); // Pair of kings with A kicker
+        let hand2 = cards_from_str("Kd 5c"); // Pair of kings with 5 kicker
+        
+        let equity = compute_single_equity(&board, &hand1, &hand2);
+        assert_eq!(equity, Some(1.0), "AK should beat K5");
+    }
+
+    #[test]
+    fn test_single_equity_loss() {
+        let board = cards_from_str("Kh Qd Jc 2s 3h");
+        let hand1 = cards_from_str("Kd 5c");
+        let hand2 = cards_from_str("As Ks");
+        
+        let equity = compute_single_equity(&board, &hand1, &hand2);
+        assert_eq!(equity, Some(0.0), "K5 should lose to AK");
+    }
+
+    #[test]
+    fn test_single_equity_tie() {
+        let board = cards_from_str("As Ks Qs Js Ts"); // Royal flush on board
+        let hand1 = cards_from_str("2c 3c");
+        let hand2 = cards_from_str("4c 5c");
+        
+        let equity = compute_single_equity(&board, &hand1, &hand2);
+        assert_eq!(equity, Some(0.5), "Should tie on board royal flush");
+    }
+
+    #[test]
+    fn test_single_equity_blocked() {
+        let board = cards_from_str("Kh Qd Jc 2s 3h");
+        let hand1 = cards_from_str("As Kh"); // Kh is on board!
+        let hand2 = cards_from_str("Kd 5c");
+        
+        let equity = compute_single_equity(&board, &hand1, &hand2);
+        assert_eq!(equity, None, "Should be blocked - Kh on board");
+    }
+
+    #[test]
+    fn test_single_equity_hand_collision() {
+        let board = cards_from_str("Kh Qd Jc 2s 3h");
+        let hand1 = cards_from_str("As Ks");
+        let hand2 = cards_from_str("As Kd"); // As overlaps!
+        
+        let equity = compute_single_equity(&board, &hand1, &hand2);
+        assert_eq!(equity, None, "Should be blocked - As in both hands");
+    }
+
+    #[test]
+    fn test_equity_matrix_matches_single_equity() {
+        let board = cards_from_str("Kh Qd Jc 2s 3h");
+        let range1: Vec<Vec<Card>> = ["As Ks", "9c 9d", "Kh 5c", "Ah Td", "2c 2d"]
+            .iter().map(|h| cards_from_str(h)).collect();
+        let range2: Vec<Vec<Card>> = ["Kd 5c", "4c 5d", "As Qs", "Tc 9c", "3c 3d", "Ac Kc"]
+            .iter().map(|h| cards_from_str(h)).collect();
+
+        let matrix = compute_equity_matrix(&board, &range1, &range2);
+        for (i, h1) in range1.iter().enumerate() {
+            for (j, h2) in range2.iter().enumerate() {
+                let cell = matrix[i * range2.len() + j];
+                match compute_single_equity(&board, h1, h2) {
+                    Some(eq) => assert_eq!(cell, eq, "{:?} vs {:?}", h1, h2),
+                    None => assert!(cell.is_nan(), "{:?} vs {:?} should be blocked", h1, h2),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_range_equities() {
+        let board = cards_from_str("Kh Qd Jc 2s 3h");
+        let range1: Vec<Vec<Card>> = ["As Ks", "9c 9d", "Kh 5c"].iter().map(|h| cards_from_str(h)).collect();
+        let range2: Vec<Vec<Card>> = ["Kd 5c", "4c 5d", "As Qs"].iter().map(|h| cards_from_str(h)).collect();
+        let matrix = compute_equity_matrix(&board, &range1, &range2);
+
+        let [eq1, eq2] = range_equities(&matrix, [&[1.0; 3], &[1.0, 1.0, 2.0]]);
+        // AK beats K5 and 45 and is blocked by AQ; 99 loses to K5 and AQ.
+        assert_eq!(eq1[0], 1.0);
+        assert_eq!(eq1[1], 0.25);
+        assert!(eq1[2].is_nan(), "board-blocked hand");
+        // Against AK and 99: K5 splits the difference, AQ is blocked by AK.
+        assert_eq!(eq2, vec![0.5, 0.0, 1.0]);
+
+        // Two identical blocks pool to the same equities.
+        let doubled = [matrix.clone(), matrix].concat();
+        let [again1, _] = range_equities(&doubled, [&[1.0; 3], &[1.0, 1.0, 2.0]]);
+        assert_eq!(again1[..2], eq1[..2]);
+    }
+
+    #[test]
+    fn test_total_equity() {
+        let board = cards_from_str("Kh Qd Jc 2s 3h");
+        let range1: Vec<Vec<Card>> = ["As Ks", "9c 9d", "Kh 5c"].iter().map(|h| cards_from_str(h)).collect();
+        let range2: Vec<Vec<Card>> = ["Kd 5c", "4c 5d", "As Qs"].iter().map(|h| cards_from_str(h)).collect();
+        let matrix = compute_equity_matrix(&board, &range1, &range2);
+
+        // AK wins both its matchups, 99 one of four (AQ counts twice)
+        assert_eq!(total_equity(&matrix, [&[1.0; 3], &[1.0, 1.0, 2.0]]), [0.5, 0.5]);
+        assert_eq!(total_equity(&matrix, [&[2.0, 1.0, 1.0], &[1.0, 1.0, 2.0]]), [0.625, 0.375]);
+        assert!(total_equity(&matrix, [&[0.0; 3], &[1.0; 3]])[0].is_nan());
+    }
+
+    #[test]
+    fn test_equity_matrix() {
+        let board = cards_from_str("Kh Qd Jc 2s 3h");
+        let range1 = vec![
+            cards_from_str("As Ks"),
+            cards_from_str("9c 9d"),
+        ];
+        let range2 = vec![
+            cards_from_str("Kd 5c"),
+            cards_from_str("4c 5d"),
+        ];
+        
+        let matrix = compute_equity_matrix(&board, &range1, &range2);
+        
+        assert_eq!(matrix.len(), 4);
+        // AKs vs K5o - AK wins
+        assert!(!matrix[0].is_nan());
+        // AKs vs 45o - AK wins  
+        assert!(!matrix[1].is_nan());
+        // 99 vs K5o
+        assert!(!matrix[2].is_nan());
+        // 99 vs 45o
+        assert!(!matrix[3].is_nan());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_river_blocks_match_sequential() {
+        let board = cards_from_str("Ks Th 7s 4d");
+        let rivers: Vec<u8> = (0..52).filter(|&i| !board.iter().any(|c| c.index() == i)).collect();
+        let range1 = vec![cards_from_str("As Ad"), cards_from_str("6h 5h"), cards_from_str("Qs Js")];
+        let range2 = vec![cards_from_str("Kc Kd"), cards_from_str("9s 8s"), cards_from_str("Ah 4h")];
+
+        let multi = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let parallel = multi.install(|| compute_equity_matrix_with_rivers(GameVariant::Holdem, &board, &rivers, &range1, &range2));
+        let mut sequential = compute_equity_matrix_for(GameVariant::Holdem, &board, &range1, &range2);
+        for &river in &rivers {
+            let river_board: Vec<Card> = board.iter().copied().chain([Card::from_index(river)]).collect();
+            sequential.extend(compute_equity_matrix_for(GameVariant::Holdem, &river_board, &range1, &range2));
+        }
+
+        assert_eq!(parallel.len(), 49 * 9);
+        for (i, (p, s)) in parallel.iter().zip(&sequential).enumerate() {
+            assert!((p.is_nan() && s.is_nan()) || (p - s).abs() < 1e-6, "entry {}: {} vs {}", i, p, s);
+        }
+    }
+}
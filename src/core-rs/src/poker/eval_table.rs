@@ -0,0 +1,237 @@
+//! Two-plus-two style hand evaluation: a precomputed state table walked
+//! one card at a time, seven array reads per 7-card hand.
+//!
+//! The table is not built into the crate (it is ~130MB). Load one with
+//! [`load_eval_table`], either a standard `HandRanks.dat` or one written by
+//! [`generate_eval_table`] and [`table_bytes`] (the `eval_table` example);
+//! from then on [`evaluate_7_cards`] walks it for 5, 6 and 7-card hands.
+//! Without a table it keeps using the lookup evaluator.
+//!
+//! Entries are little-endian `u32`s. Cards are numbered 1-52 as
+//! `Card::index() + 1`. Starting at `p = 53`, each card moves to
+//! `p = table[p + card]`; after the seventh card `p` is the hand value.
+//! After five or six cards, `table[p]` is the value of those cards. Values
+//! are `category << 12 | rank`, the category running from 1 (high card) to
+//! 9 (straight flush) and the rank counting up from 1 within it;
+//! [`to_score`] converts them to the evaluator's 1-7462 scale.
+//!
+//! [`evaluate_7_cards`]: crate::poker::evaluate_7_cards
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::poker::evaluator::evaluate_7_cards_direct;
+use crate::poker::Card;
+
+/// Score (on the evaluator's scale) of the worst hand of each category,
+/// high card first.
+const CATEGORY_WORST: [u16; 9] = [7462, 6185, 3325, 2467, 1609, 1599, 322, 166, 10];
+
+/// Number of entries of a complete table.
+pub const TABLE_LEN: usize = 32_487_834;
+
+static TABLE: OnceLock<Vec<u32>> = OnceLock::new();
+
+/// Install a table for every later evaluation. Checks its size and a
+/// sample of hands against the lookup evaluator; a table can only be
+/// loaded once.
+pub fn load_eval_table(bytes: &[u8]) -> Result<(), String> {
+    if !bytes.len().is_multiple_of(4) || bytes.len() < 4 * 53 {
+        return Err(format!("Evaluation table has {} bytes, expected a whole number of u32 entries", bytes.len()));
+    }
+    let table: Vec<u32> = bytes.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+    check_table(&table)?;
+    TABLE.set(table).map_err(|_| "An evaluation table is already loaded".to_string())
+}
+
+/// The loaded table, if any.
+#[inline]
+pub fn loaded() -> Option<&'static [u32]> {
+    TABLE.get().map(Vec::as_slice)
+}
+
+/// Score of 5 to 7 `cards` on the evaluator's scale, by walking `table`.
+#[inline]
+pub fn evaluate(table: &[u32], cards: &[Card]) -> u16 {
+    let mut p = 53;
+    for card in cards {
+        p = table[p as usize + card.index() as usize + 1];
+    }
+    if cards.len() < 7 {
+        p = table[p as usize];
+    }
+    to_score(p)
+}
+
+/// Convert a table value to the evaluator's scale (1 is a royal flush).
+/// Values that are not hands map to 7463, below the worst hand.
+pub fn to_score(value: u32) -> u16 {
+    let (category, rank) = ((value >> 12) as usize, (value & 0xfff) as u16);
+    if !(1..=9).contains(&category) || rank == 0 {
+        return 7463;
+    }
+    CATEGORY_WORST[category - 1] + 1 - rank
+}
+
+/// Convert a score on the evaluator's scale to a table value.
+pub fn from_score(score: u16) -> u32 {
+    let category = CATEGORY_WORST.iter().rposition(|&worst| score <= worst).expect("scores run from 1 to 7462");
+    ((category as u32 + 1) << 12) | (CATEGORY_WORST[category] + 1 - score) as u32
+}
+
+/// Build a complete table with the lookup evaluator. Takes tens of
+/// seconds in release builds and ~130MB.
+pub fn generate_eval_table() -> Vec<u32> {
+    // Partial hands reachable by adding cards, each with the cards it holds
+    let mut ids: Vec<u64> = vec![0];
+    let mut index: HashMap<u64, u32> = HashMap::from([(0, 0)]);
+    let mut next = 0;
+    while next < ids.len() {
+        let id = ids[next];
+        next += 1;
+        if card_count(id) == 6 {
+            continue;
+        }
+        for card in 0..52 {
+            let new = add_card(id, card);
+            if new != 0 && card_count(new) < 7 && !index.contains_key(&new) {
+                index.insert(new, ids.len() as u32);
+                ids.push(new);
+            }
+        }
+    }
+
+    let mut table = vec![0u32; 53 + 53 * ids.len()];
+    let mut values: HashMap<u64, u32> = HashMap::new();
+    let mut value = |id: u64| *values.entry(id).or_insert_with(|| from_score(evaluate_7_cards_direct(&id_cards(id))));
+    for (i, &id) in ids.iter().enumerate() {
+        let base = 53 + 53 * i;
+        let count = card_count(id);
+        if count >= 5 {
+            table[base] = value(id);
+        }
+        for card in 0..52u8 {
+            let new = add_card(id, card);
+            table[base + card as usize + 1] = match new {
+                0 => 0,
+                _ if count == 6 => value(new),
+                _ => 53 + 53 * index[&new],
+            };
+        }
+    }
+    table
+}
+
+/// Little-endian bytes of `table`, as [`load_eval_table`] reads them.
+pub fn table_bytes(table: &[u32]) -> Vec<u8> {
+    table.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Compare a spread of 7-card hands against the lookup evaluator.
+fn check_table(table: &[u32]) -> Result<(), String> {
+    let mut state = 0x7ab1e;
+    for _ in 0..1000 {
+        let mut deck: Vec<Card> = (0..52).map(Card::from_index).collect();
+        for j in 0..7 {
+            let k = j + (crate::solver::builder::splitmix64(&mut state) % (52 - j) as u64) as usize;
+            deck.swap(j, k);
+        }
+        let hand = &deck[..7];
+        // Bounds first: a truncated or foreign file must not panic
+        let mut p = 53u32;
+        for card in hand {
+            p = *table.get(p as usize + card.index() as usize + 1).ok_or("Evaluation table is truncated or corrupted")?;
+        }
+        if to_score(p) != evaluate_7_cards_direct(hand) {
+            return Err("Evaluation table disagrees with the evaluator".to_string());
+        }
+    }
+    Ok(())
+}
+
+// A partial hand is packed into a u64, one byte per card in descending
+// order: rank + 1 in the high nibble, suit + 1 in the low one. Suits that
+// can no longer make a flush by the seventh card are cleared to 0, so hands
+// that only differ in those suits share an entry.
+
+fn card_count(id: u64) -> usize {
+    (0..7).filter(|i| id >> (8 * i) & 0xff != 0).count()
+}
+
+/// `id` with `card` added, or 0 if that is impossible (a card held twice,
+/// five of a rank).
+fn add_card(id: u64, card: u8) -> u64 {
+    let mut cards: Vec<u8> = (0..7).map(|i| (id >> (8 * i) & 0xff) as u8).filter(|&c| c != 0).collect();
+    let new = ((card / 4 + 1) << 4) | (card % 4 + 1);
+    if cards.contains(&new) {
+        return 0;
+    }
+    cards.push(new);
+
+    let mut ranks = [0; 14];
+    let mut suits = [0; 5];
+    for &c in &cards {
+        ranks[(c >> 4) as usize] += 1;
+        suits[(c & 0xf) as usize] += 1;
+    }
+    if ranks.iter().any(|&n| n > 4) {
+        return 0;
+    }
+    // A suit needs five cards by the seventh to matter
+    let n = cards.len();
+    for c in &mut cards {
+        let suit = (*c & 0xf) as usize;
+        if suit != 0 && suits[suit] + 7 - n < 5 {
+            *c &= 0xf0;
+        }
+    }
+    cards.sort_unstable_by(|a, b| b.cmp(a));
+    cards.iter().enumerate().fold(0, |id, (i, &c)| id | (c as u64) << (8 * i))
+}
+
+/// Concrete cards for `id`: cleared suits are dealt round the other suits
+/// so that they neither clash nor make a flush.
+fn id_cards(id: u64) -> Vec<Card> {
+    let cards: Vec<u8> = (0..7).map(|i| (id >> (8 * i) & 0xff) as u8).filter(|&c| c != 0).collect();
+    let flush_suit = cards.iter().map(|c| c & 0xf).find(|&s| s != 0);
+    let mut spare = (1..=4u8).filter(|&s| Some(s) != flush_suit).cycle();
+    cards.iter()
+        .map(|&c| {
+            let suit = match c & 0xf {
+                0 => spare.next().expect("cycle never ends"),
+                s => s,
+            };
+            Card::new((c >> 4) - 1, suit - 1)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_conversion() {
+        for score in 1..=7462 {
+            assert_eq!(to_score(from_score(score)), score);
+        }
+        // Royal flush, the best quads, the worst hand
+        assert_eq!(from_score(1), 9 << 12 | 10);
+        assert_eq!(from_score(11), 8 << 12 | 156);
+        assert_eq!(from_score(7462), 1 << 12 | 1);
+        assert_eq!(to_score(0), 7463);
+    }
+
+    #[test]
+    fn test_partial_hands() {
+        let id = |cards: &str| cards.split_whitespace().filter_map(Card::from_str).fold(0, |id, c| add_card(id, c.index()));
+        // Order does not matter, and off-suit cards lose their suit once no flush is possible
+        assert_eq!(id("As Kd Qh Jc Ts"), id("Ts Jc Qh Kd As"));
+        assert_eq!(id("As Kd Qh Jc 2s"), id("As Kh Qd Jc 2s"));
+        assert_ne!(id("As Kd Qh Js 2s"), id("As Kd Qh Jc 2s"));
+        assert_eq!(add_card(id("As"), Card::from_str("As").unwrap().index()), 0);
+
+        let cards = id_cards(id("As Kd Qh Jc 2s 3s 7s"));
+        assert_eq!(evaluate_7_cards_direct(&cards), evaluate_7_cards_direct(&"As Kd Qh Jc 2s 3s 7s".split_whitespace().filter_map(Card::from_str).collect::<Vec<_>>()));
+    }
+}
@@ -1,441 +1,686 @@
-//! High-Performance Hand Evaluator for Texas Hold'em
-//! 
-//! Implements a Cactus Kev inspired algorithm using:
-//! - Prime number product for rank combinations
-//! - Bit patterns for flush detection  
-//! - Lookup tables for fast hand classification
-//! 
-//! Lower score = stronger hand (1 = Royal Flush, 7462 = worst high card)
-
-use crate::poker::Card;
-use lazy_static::lazy_static;
-
-// ============================================================================
-// CONSTANTS
-// ============================================================================
-
-/// Prime numbers for each rank (2-A), used for unique hand identification
-/// This allows us to multiply primes to get a unique product for each rank combination
-const PRIMES: [u32; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
-
-/// Hand rank categories (lower = better)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-#[repr(u8)]
-pub enum HandRank {
-    StraightFlush = 1,
-    FourOfAKind = 2,
-    FullHouse = 3,
-    Flush = 4,
-    Straight = 5,
-    ThreeOfAKind = 6,
-    TwoPair = 7,
-    OnePair = 8,
-    HighCard = 9,
-}
-
-impl HandRank {
-    /// Get hand rank from score
-    pub fn from_score(score: u16) -> Self {
-        match score {
-            1..=10 => HandRank::StraightFlush,
-            11..=166 => HandRank::FourOfAKind,
-            167..=322 => HandRank::FullHouse,
-            323..=1599 => HandRank::Flush,
-            1600..=1609 => HandRank::Straight,
-            1610..=2467 => HandRank::ThreeOfAKind,
-            2468..=3325 => HandRank::TwoPair,
-            3326..=6185 => HandRank::OnePair,
-            _ => HandRank::HighCard,
-        }
-    }
-}
-
-/// Get human-readable hand rank name
-pub fn get_hand_rank_name(score: u16) -> &'static str {
-    match score {
-        1 => "Royal Flush",
-        2..=10 => "Straight Flush",
-        11..=166 => "Four of a Kind",
-        167..=322 => "Full House",
-        323..=1599 => "Flush",
-        1600..=1609 => "Straight",
-        1610..=2467 => "Three of a Kind",
-        2468..=3325 => "Two Pair",
-        3326..=6185 => "One Pair",
-        _ => "High Card",
-    }
-}
-
-// ============================================================================
-// LOOKUP TABLES
-// ============================================================================
-
-lazy_static! {
-    /// Lookup table for flush hands (indexed by bit pattern)
-    static ref FLUSH_TABLE: Vec<u16> = generate_flush_table();
-    
-    /// Lookup table for unique5 hands (non-flush, no pairs)
-    static ref UNIQUE5_TABLE: Vec<u16> = generate_unique5_table();
-    
-    /// Lookup table mapping prime products to hand values
-    static ref PRIME_PRODUCT_TABLE: std::collections::HashMap<u32, u16> = generate_prime_product_table();
-}
-
-/// Initialize lookup tables (call once at startup)
-pub fn init_lookup_tables() {
-    // Force lazy_static initialization
-    let _ = FLUSH_TABLE.len();
-    let _ = UNIQUE5_TABLE.len();
-    let _ = PRIME_PRODUCT_TABLE.len();
-}
-
-// ============================================================================
-// TABLE GENERATION
-// ============================================================================
-
-/// Generate flush lookup table
-/// Maps 13-bit pattern (one bit per rank) to hand score
-fn generate_flush_table() -> Vec<u16> {
-    let mut table = vec![0u16; 8192]; // 2^13
-    
-    // Generate all 5-bit combinations for flushes
-    let mut rank = 1u16;
-    
-    // Straight flushes first (scores 1-10)
-    // A-high (royal) to 5-high (wheel)
-    let straight_patterns = [
-        0b1111100000000u16, // A K Q J T (Royal)
-        0b0111110000000u16, // K Q J T 9
-        0b0011111000000u16, // Q J T 9 8
-        0b0001111100000u16, // J T 9 8 7
-        0b0000111110000u16, // T 9 8 7 6
-        0b0000011111000u16, // 9 8 7 6 5
-        0b0000001111100u16, // 8 7 6 5 4
-        0b0000000111110u16, // 7 6 5 4 3
-        0b0000000011111u16, // 6 5 4 3 2
-        0b1000000001111u16, // A 5 4 3 2 (wheel)
-    ];
-    
-    for pattern in &straight_patterns {
-        table[*pattern as usize] = rank;
-        rank += 1;
-    }
-    
-    // Regular flushes (non-straight) - scores 323-1599
-    rank = 323;
-    for bits in (0u16..8192).rev() {
-        if bits.count_ones() == 5 {
-            // Check it's not a straight
-            if !straight_patterns.contains(&bits) {
-                table[bits as usize] = rank;
-                rank += 1;
-            }
-        }
-    }
-    
-    table
-}
-
-/// Generate unique5 (non-flush straights and high cards) lookup table
-fn generate_unique5_table() -> Vec<u16> {
-    let mut table = vec![0u16; 8192];
-    
-    // Straights (non-flush) - scores 1600-1609
-    let straight_patterns = [
-        0b1111100000000u16,
-        0b0111110000000u16,
-        0b0011111000000u16,
-        0b0001111100000u16,
-        0b0000111110000u16,
-        0b0000011111000u16,
-        0b0000001111100u16,
-        0b0000000111110u16,
-        0b0000000011111u16,
-        0b1000000001111u16, // wheel
-    ];
-    
-    let mut rank = 1600u16;
-    for pattern in &straight_patterns {
-        table[*pattern as usize] = rank;
-        rank += 1;
-    }
-    
-    // High cards - scores 6186-7462
-    rank = 6186;
-    for bits in (0u16..8192).rev() {
-        if bits.count_ones() == 5 && !straight_patterns.contains(&bits) {
-            table[bits as usize] = rank;
-            rank += 1;
-        }
-    }
-    
-    table
-}
-
-/// Generate prime product to hand value mapping for paired hands
-fn generate_prime_product_table() -> std::collections::HashMap<u32, u16> {
-    let mut table = std::collections::HashMap::new();
-    
-    // Four of a Kind (scores 11-166)
-    let mut rank = 11u16;
-    for quads in (0..13).rev() {
-        for kicker in (0..13).rev() {
-            if quads != kicker {
-                let product = PRIMES[quads].pow(4) * PRIMES[kicker];
-                table.insert(product, rank);
-                rank += 1;
-            }
-        }
-    }
-    
-    // Full House (scores 167-322)
-    rank = 167;
-    for trips in (0..13).rev() {
-        for pair in (0..13).rev() {
-            if trips != pair {
-                let product = PRIMES[trips].pow(3) * PRIMES[pair].pow(2);
-                table.insert(product, rank);
-                rank += 1;
-            }
-        }
-    }
-    
-    // Three of a Kind (scores 1610-2467)
-    rank = 1610;
-    for trips in (0..13).rev() {
-        for k1 in (0..13).rev() {
-            if k1 == trips { continue; }
-            for k2 in (0..k1).rev() {
-                if k2 == trips { continue; }
-                let product = PRIMES[trips].pow(3) * PRIMES[k1] * PRIMES[k2];
-                table.insert(product, rank);
-                rank += 1;
-            }
-        }
-    }
-    
-    // Two Pair (scores 2468-3325)
-    rank = 2468;
-    for p1 in (0..13).rev() {
-        for p2 in (0..p1).rev() {
-            for kicker in (0..13).rev() {
-                if kicker != p1 && kicker != p2 {
-                    let product = PRIMES[p1].pow(2) * PRIMES[p2].pow(2) * PRIMES[kicker];
-                    table.insert(product, rank);
-                    rank += 1;
-                }
-            }
-        }
-    }
-    
-    // One Pair (scores 3326-6185)
-    rank = 3326;
-    for pair in (0..13).rev() {
-        for k1 in (0..13).rev() {
-            if k1 == pair { continue; }
-            for k2 in (0..k1).rev() {
-                if k2 == pair { continue; }
-                for k3 in (0..k2).rev() {
-                    if k3 == pair { continue; }
-                    let product = PRIMES[pair].pow(2) * PRIMES[k1] * PRIMES[k2] * PRIMES[k3];
-                    table.insert(product, rank);
-                    rank += 1;
-                }
-            }
-        }
-    }
-    
-    table
-}
-
-// ============================================================================
-// EVALUATION FUNCTIONS
-// ============================================================================
-
-/// Evaluate a 5-card hand
-/// Returns a score where lower = better (1 = Royal Flush, 7462 = worst high card)
-#[inline]
-pub fn evaluate_5_cards(cards: &[Card; 5]) -> u16 {
-    // Build rank bit pattern and suit counts
-    let mut rank_bits: u16 = 0;
-    let mut suit_counts = [0u8; 4];
-    let mut prime_product: u32 = 1;
-    
-    for card in cards {
-        let rank = card.rank() as usize;
-        let suit = card.suit() as usize;
-        
-        rank_bits |= 1 << rank;
-        suit_counts[suit] += 1;
-        prime_product *= PRIMES[rank];
-    }
-    
-    // Check for flush
-    let is_flush = suit_counts.iter().any(|&c| c == 5);
-    
-    // Check if all ranks are unique (possible straight or high card)
-    let all_unique = rank_bits.count_ones() == 5;
-    
-    if is_flush {
-        return FLUSH_TABLE[rank_bits as usize];
-    }
-    
-    if all_unique {
-        return UNIQUE5_TABLE[rank_bits as usize];
-    }
-    
-    // Paired hand - lookup by prime product
-    *PRIME_PRODUCT_TABLE.get(&prime_product).unwrap_or(&7462)
-}
-
-/// Evaluate the best 5-card hand from 7 cards
-/// Returns a score where lower = better
-pub fn evaluate_7_cards(cards: &[Card]) -> u16 {
-    if cards.len() < 5 {
-        return 7462; // Worst possible
-    }
-    
-    if cards.len() == 5 {
-        let arr: [Card; 5] = [cards[0], cards[1], cards[2], cards[3], cards[4]];
-        return evaluate_5_cards(&arr);
-    }
-    
-    // For 6 or 7 cards, try all 5-card combinations
-    let n = cards.len();
-    let mut best = 7463u16;
-    
-    // Generate C(n, 5) combinations
-    for i in 0..n {
-        for j in (i+1)..n {
-            for k in (j+1)..n {
-                for l in (k+1)..n {
-                    for m in (l+1)..n {
-                        let hand: [Card; 5] = [
-                            cards[i], cards[j], cards[k], cards[l], cards[m]
-                        ];
-                        let score = evaluate_5_cards(&hand);
-                        if score < best {
-                            best = score;
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    best
-}
-
-// ============================================================================
-// TESTS
-// ============================================================================
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::poker::Card;
-
-    fn cards_from_str(s: &str) -> Vec<Card> {
-        s.split_whitespace()
-            .map(|cs| Card::from_str(cs).expect(&format!("Invalid card: {}", cs)))
-            .collect()
-    }
-
-    fn eval_hand(s: &str) -> u16 {
-        let cards = cards_from_str(s);
-        evaluate_7_cards(&cards)
-    }
-
-    #[test]
-    fn test_royal_flush() {
-        let score = eval_hand("As Ks Qs Js Ts");
-        assert_eq!(score, 1, "Royal flush should be score 1");
-    }
-
-    #[test]
-    fn test_straight_flush() {
-        let score = eval_hand("9h 8h 7h 6h 5h");
-        assert!(score >= 2 && score <= 10, "Straight flush score: {}", score);
-    }
-
-    #[test]
-    fn test_four_of_a_kind() {
-        let score = eval_hand("As Ah Ad Ac Ks");
-        assert!(score >= 11 && score <= 166, "Quads score: {}", score);
-    }
-
-    #[test]
-    fn test_full_house() {
-        let score = eval_hand("As Ah Ad Ks Kh");
-        assert!(score >= 167 && score <= 322, "Full house score: {}", score);
-    }
-
-    #[test]
-    fn test_flush() {
-        let score = eval_hand("As Ks Qs Js 9s");
-        assert!(score >= 323 && score <= 1599, "Flush score: {}", score);
-    }
-
-    #[test]
-    fn test_straight() {
-        let score = eval_hand("Ah Ks Qd Jc Th");
-        assert!(score >= 1600 && score <= 1609, "Straight score: {}", score);
-    }
-
-    #[test]
-    fn test_three_of_a_kind() {
-        let score = eval_hand("As Ah Ad Ks Qh");
-        assert!(score >= 1610 && score <= 2467, "Trips score: {}", score);
-    }
-
-    #[test]
-    fn test_two_pair() {
-        let score = eval_hand("As Ah Ks Kh Qd");
-        assert!(score >= 2468 && score <= 3325, "Two pair score: {}", score);
-    }
-
-    #[test]
-    fn test_one_pair() {
-        let score = eval_hand("As Ah Ks Qh Jd");
-        assert!(score >= 3326 && score <= 6185, "One pair score: {}", score);
-    }
-
-    #[test]
-    fn test_high_card() {
-        let score = eval_hand("As Ks Qd Jc 9h");
-        assert!(score >= 6186 && score <= 7462, "High card score: {}", score);
-    }
-
-    #[test]
-    fn test_quads_beats_full_house() {
-        let quads = eval_hand("As Ah Ad Ac Ks");
-        let full_house = eval_hand("As Ah Ad Ks Kh");
-        assert!(quads < full_house, "Quads ({}) should beat Full House ({})", quads, full_house);
-    }
-
-    #[test]
-    fn test_full_house_beats_flush() {
-        let full_house = eval_hand("As Ah Ad Ks Kh");
-        let flush = eval_hand("As Ks Qs Js 9s");
-        assert!(full_house < flush, "Full House ({}) should beat Flush ({})", full_house, flush);
-    }
-
-    #[test]
-    fn test_7_card_evaluation() {
-        // Royal flush with 2 extra cards
-        let score = eval_hand("As Ks Qs Js Ts 2c 3d");
-        assert_eq!(score, 1, "7-card royal flush should be score 1");
-    }
-
-    #[test]
-    fn test_wheel_straight() {
-        let score = eval_hand("Ah 2s 3d 4c 5h");
-        assert!(score >= 1600 && score <= 1609, "Wheel should be a straight: {}", score);
-    }
-
-    #[test]
-    fn test_wheel_straight_flush() {
-        let score = eval_hand("Ah 2h 3h 4h 5h");
-        assert!(score >= 2 && score <= 10, "Wheel flush should be straight flush: {}", score);
-    }
-}
+//! High-Performance Hand Evaluator for Texas Hold'em
+//! 
+//! Implements a Cactus Kev inspired algorithm using:
+//! - Prime number product for rank combinations
+//! - Bit patterns for flush detection  
+//! - Lookup tables for fast hand classification
+//! 
+//! Lower score = stronger hand (1 = Royal Flush, 7462 = worst high card)
+//!
+//! Short deck (6+) Hold'em, see [`GameVariant`], keeps the same scale with
+//! flushes moved above full houses and A-6-7-8-9 as the lowest straight.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::poker::Card;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Prime numbers for each rank (2-A), used for unique hand identification
+/// This allows us to multiply primes to get a unique product for each rank combination
+const PRIMES: [u32; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// Lowest straight of each variant, as rank bits: A-5-4-3-2 in Hold'em and
+/// A-9-8-7-6 in short deck. The tables score both as the Hold'em wheel.
+const HOLDEM_WHEEL: u16 = 0b1000000001111;
+const SHORT_DECK_WHEEL: u16 = 0b1000011110000;
+
+/// The rules a game is played by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameVariant {
+    /// No-limit Hold'em with the full 52-card deck.
+    #[default]
+    Holdem,
+    /// Short deck (6+) Hold'em: 36 cards, sixes to aces. A flush beats a
+    /// full house, and A-6-7-8-9 is a straight.
+    ShortDeck,
+}
+
+impl GameVariant {
+    pub fn is_holdem(&self) -> bool {
+        *self == GameVariant::Holdem
+    }
+
+    /// Score of the best 5-card hand of 5 to 7 `cards`, lower is better.
+    #[inline]
+    pub fn evaluate(self, cards: &[Card]) -> u16 {
+        match self {
+            GameVariant::Holdem => evaluate_7_cards(cards),
+            GameVariant::ShortDeck => evaluate_short_deck(cards),
+        }
+    }
+
+    /// Category of a score of [`GameVariant::evaluate`].
+    pub fn hand_rank(self, score: u16) -> HandRank {
+        match self {
+            GameVariant::Holdem => HandRank::from_score(score),
+            GameVariant::ShortDeck => HandRank::from_score(holdem_score(score)),
+        }
+    }
+
+    /// Whether `card` is in this variant's deck.
+    pub fn in_deck(self, card: Card) -> bool {
+        match self {
+            GameVariant::Holdem => true,
+            // Ranks count from deuce (0), so sixes are 4
+            GameVariant::ShortDeck => card.rank() >= 4,
+        }
+    }
+
+    /// Number of cards in the deck.
+    pub fn deck_size(self) -> usize {
+        match self {
+            GameVariant::Holdem => 52,
+            GameVariant::ShortDeck => 36,
+        }
+    }
+}
+
+/// Hand rank categories (lower = better)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum HandRank {
+    StraightFlush = 1,
+    FourOfAKind = 2,
+    FullHouse = 3,
+    Flush = 4,
+    Straight = 5,
+    ThreeOfAKind = 6,
+    TwoPair = 7,
+    OnePair = 8,
+    HighCard = 9,
+}
+
+impl HandRank {
+    /// Get hand rank from score
+    pub fn from_score(score: u16) -> Self {
+        match score {
+            1..=10 => HandRank::StraightFlush,
+            11..=166 => HandRank::FourOfAKind,
+            167..=322 => HandRank::FullHouse,
+            323..=1599 => HandRank::Flush,
+            1600..=1609 => HandRank::Straight,
+            1610..=2467 => HandRank::ThreeOfAKind,
+            2468..=3325 => HandRank::TwoPair,
+            3326..=6185 => HandRank::OnePair,
+            _ => HandRank::HighCard,
+        }
+    }
+
+    /// Category from its snake_case name, e.g. `"two_pair"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "straight_flush" => Some(HandRank::StraightFlush),
+            "four_of_a_kind" => Some(HandRank::FourOfAKind),
+            "full_house" => Some(HandRank::FullHouse),
+            "flush" => Some(HandRank::Flush),
+            "straight" => Some(HandRank::Straight),
+            "three_of_a_kind" => Some(HandRank::ThreeOfAKind),
+            "two_pair" => Some(HandRank::TwoPair),
+            "one_pair" => Some(HandRank::OnePair),
+            "high_card" => Some(HandRank::HighCard),
+            _ => None,
+        }
+    }
+
+    /// Snake_case name, the inverse of [`HandRank::from_name`].
+    pub fn name(self) -> &'static str {
+        match self {
+            HandRank::StraightFlush => "straight_flush",
+            HandRank::FourOfAKind => "four_of_a_kind",
+            HandRank::FullHouse => "full_house",
+            HandRank::Flush => "flush",
+            HandRank::Straight => "straight",
+            HandRank::ThreeOfAKind => "three_of_a_kind",
+            HandRank::TwoPair => "two_pair",
+            HandRank::OnePair => "one_pair",
+            HandRank::HighCard => "high_card",
+        }
+    }
+}
+
+/// Get human-readable hand rank name
+pub fn get_hand_rank_name(score: u16) -> &'static str {
+    match score {
+        1 => "Royal Flush",
+        2..=10 => "Straight Flush",
+        11..=166 => "Four of a Kind",
+        167..=322 => "Full House",
+        323..=1599 => "Flush",
+        1600..=1609 => "Straight",
+        1610..=2467 => "Three of a Kind",
+        2468..=3325 => "Two Pair",
+        3326..=6185 => "One Pair",
+        _ => "High Card",
+    }
+}
+
+// ============================================================================
+// LOOKUP TABLES
+// ============================================================================
+
+// The tables are immutable once built and `LazyLock` is `Sync`, so they can be
+// read concurrently by the `parallel` feature's worker threads.
+
+/// Lookup table for flush hands (indexed by bit pattern)
+static FLUSH_TABLE: LazyLock<Vec<u16>> = LazyLock::new(generate_flush_table);
+
+/// Lookup table for unique5 hands (non-flush, no pairs)
+static UNIQUE5_TABLE: LazyLock<Vec<u16>> = LazyLock::new(generate_unique5_table);
+
+/// Lookup table mapping prime products to hand values
+static PRIME_PRODUCT_TABLE: LazyLock<HashMap<u32, u16>> = LazyLock::new(generate_prime_product_table);
+
+/// Initialize lookup tables (call once at startup)
+pub fn init_lookup_tables() {
+    // Force lazy initialization
+    let _ = FLUSH_TABLE.len();
+    let _ = UNIQUE5_TABLE.len();
+    let _ = PRIME_PRODUCT_TABLE.len();
+}
+
+// ============================================================================
+// TABLE GENERATION
+// ============================================================================
+
+/// Generate flush lookup table
+/// Maps 13-bit pattern (one bit per rank) to hand score
+fn generate_flush_table() -> Vec<u16> {
+    let mut table = vec![0u16; 8192]; // 2^13
+    
+    // Generate all 5-bit combinations for flushes
+    let mut rank = 1u16;
+    
+    // Straight flushes first (scores 1-10)
+    // A-high (royal) to 5-high (wheel)
+    let straight_patterns = [
+        0b1111100000000u16, // A K Q J T (Royal)
+        0b0111110000000u16, // K Q J T 9
+        0b0011111000000u16, // Q J T 9 8
+        0b0001111100000u16, // J T 9 8 7
+        0b0000111110000u16, // T 9 8 7 6
+        0b0000011111000u16, // 9 8 7 6 5
+        0b0000001111100u16, // 8 7 6 5 4
+        0b0000000111110u16, // 7 6 5 4 3
+        0b0000000011111u16, // 6 5 4 3 2
+        0b1000000001111u16, // A 5 4 3 2 (wheel)
+    ];
+    
+    for pattern in &straight_patterns {
+        table[*pattern as usize] = rank;
+        rank += 1;
+    }
+    
+    // Regular flushes (non-straight) - scores 323-1599
+    rank = 323;
+    for bits in (0u16..8192).rev() {
+        if bits.count_ones() == 5 {
+            // Check it's not a straight
+            if !straight_patterns.contains(&bits) {
+                table[bits as usize] = rank;
+                rank += 1;
+            }
+        }
+    }
+    
+    table
+}
+
+/// Generate unique5 (non-flush straights and high cards) lookup table
+fn generate_unique5_table() -> Vec<u16> {
+    let mut table = vec![0u16; 8192];
+    
+    // Straights (non-flush) - scores 1600-1609
+    let straight_patterns = [
+        0b1111100000000u16,
+        0b0111110000000u16,
+        0b0011111000000u16,
+        0b0001111100000u16,
+        0b0000111110000u16,
+        0b0000011111000u16,
+        0b0000001111100u16,
+        0b0000000111110u16,
+        0b0000000011111u16,
+        0b1000000001111u16, // wheel
+    ];
+    
+    let mut rank = 1600u16;
+    for pattern in &straight_patterns {
+        table[*pattern as usize] = rank;
+        rank += 1;
+    }
+    
+    // High cards - scores 6186-7462
+    rank = 6186;
+    for bits in (0u16..8192).rev() {
+        if bits.count_ones() == 5 && !straight_patterns.contains(&bits) {
+            table[bits as usize] = rank;
+            rank += 1;
+        }
+    }
+    
+    table
+}
+
+/// Generate prime product to hand value mapping for paired hands
+fn generate_prime_product_table() -> HashMap<u32, u16> {
+    let mut table = HashMap::new();
+    
+    // Four of a Kind (scores 11-166)
+    let mut rank = 11u16;
+    for quads in (0..13).rev() {
+        for kicker in (0..13).rev() {
+            if quads != kicker {
+                let product = PRIMES[quads].pow(4) * PRIMES[kicker];
+                table.insert(product, rank);
+                rank += 1;
+            }
+        }
+    }
+    
+    // Full House (scores 167-322)
+    rank = 167;
+    for trips in (0..13).rev() {
+        for pair in (0..13).rev() {
+            if trips != pair {
+                let product = PRIMES[trips].pow(3) * PRIMES[pair].pow(2);
+                table.insert(product, rank);
+                rank += 1;
+            }
+        }
+    }
+    
+    // Three of a Kind (scores 1610-2467)
+    rank = 1610;
+    for trips in (0..13).rev() {
+        for k1 in (0..13).rev() {
+            if k1 == trips { continue; }
+            for k2 in (0..k1).rev() {
+                if k2 == trips { continue; }
+                let product = PRIMES[trips].pow(3) * PRIMES[k1] * PRIMES[k2];
+                table.insert(product, rank);
+                rank += 1;
+            }
+        }
+    }
+    
+    // Two Pair (scores 2468-3325)
+    rank = 2468;
+    for p1 in (0..13).rev() {
+        for p2 in (0..p1).rev() {
+            for kicker in (0..13).rev() {
+                if kicker != p1 && kicker != p2 {
+                    let product = PRIMES[p1].pow(2) * PRIMES[p2].pow(2) * PRIMES[kicker];
+                    table.insert(product, rank);
+                    rank += 1;
+                }
+            }
+        }
+    }
+    
+    // One Pair (scores 3326-6185)
+    rank = 3326;
+    for pair in (0..13).rev() {
+        for k1 in (0..13).rev() {
+            if k1 == pair { continue; }
+            for k2 in (0..k1).rev() {
+                if k2 == pair { continue; }
+                for k3 in (0..k2).rev() {
+                    if k3 == pair { continue; }
+                    let product = PRIMES[pair].pow(2) * PRIMES[k1] * PRIMES[k2] * PRIMES[k3];
+                    table.insert(product, rank);
+                    rank += 1;
+                }
+            }
+        }
+    }
+    
+    table
+}
+
+// ============================================================================
+// EVALUATION FUNCTIONS
+// ============================================================================
+
+/// Evaluate a 5-card hand
+/// Returns a score where lower = better (1 = Royal Flush, 7462 = worst high card)
+#[inline]
+pub fn evaluate_5_cards(cards: &[Card; 5]) -> u16 {
+    // Build rank bit pattern and suit counts
+    let mut rank_bits: u16 = 0;
+    let mut suit_counts = [0u8; 4];
+    let mut prime_product: u32 = 1;
+    
+    for card in cards {
+        let rank = card.rank() as usize;
+        let suit = card.suit() as usize;
+        
+        rank_bits |= 1 << rank;
+        suit_counts[suit] += 1;
+        prime_product *= PRIMES[rank];
+    }
+    
+    // Check for flush
+    let is_flush = suit_counts.contains(&5);
+    
+    // Check if all ranks are unique (possible straight or high card)
+    let all_unique = rank_bits.count_ones() == 5;
+    
+    if is_flush {
+        return FLUSH_TABLE[rank_bits as usize];
+    }
+    
+    if all_unique {
+        return UNIQUE5_TABLE[rank_bits as usize];
+    }
+    
+    // Paired hand - lookup by prime product
+    *PRIME_PRODUCT_TABLE.get(&prime_product).unwrap_or(&7462)
+}
+
+/// Evaluate the best 5-card hand from 7 cards
+/// Returns a score where lower = better
+///
+/// With the `eval-table` feature and a table loaded (see
+/// [`crate::poker::eval_table`]), 5 to 7-card hands walk the table instead.
+#[inline]
+pub fn evaluate_7_cards(cards: &[Card]) -> u16 {
+    #[cfg(feature = "eval-table")]
+    if (5..=7).contains(&cards.len())
+        && let Some(table) = crate::poker::eval_table::loaded()
+    {
+        return crate::poker::eval_table::evaluate(table, cards);
+    }
+    evaluate_7_cards_direct(cards)
+}
+
+/// [`evaluate_7_cards`] with the lookup evaluator, whether or not an
+/// evaluation table is loaded.
+///
+/// Six and seven cards are scored straight from their rank and suit counts
+/// rather than by trying every 5-card subset: the best hand the ranks make
+/// on their own, or the best flush if one suit has five cards.
+pub fn evaluate_7_cards_direct(cards: &[Card]) -> u16 {
+    if cards.len() < 5 {
+        return 7462; // Worst possible
+    }
+    
+    if cards.len() == 5 {
+        let arr: [Card; 5] = [cards[0], cards[1], cards[2], cards[3], cards[4]];
+        return evaluate_5_cards(&arr);
+    }
+    
+    let (best, flush) = best_hands(cards, HOLDEM_WHEEL);
+    flush.map_or(best, |flush| best.min(flush))
+}
+
+/// Score of the best 5-card hand of 5 to 7 `cards` under short deck rules
+/// (see [`GameVariant::ShortDeck`]), on the 1-7462 scale.
+pub fn evaluate_short_deck(cards: &[Card]) -> u16 {
+    if cards.len() < 5 {
+        return 7462;
+    }
+    let (best, flush) = best_hands(cards, SHORT_DECK_WHEEL);
+    let best = short_deck_score(best);
+    flush.map_or(best, |flush| best.min(short_deck_score(flush)))
+}
+
+/// A Hold'em score moved to the short deck scale: flushes (Hold'em 323-1599)
+/// take the places right after quads and full houses (167-322) follow.
+fn short_deck_score(score: u16) -> u16 {
+    match score {
+        167..=322 => score + 1277,
+        323..=1599 => score - 156,
+        _ => score,
+    }
+}
+
+/// The inverse of [`short_deck_score`].
+fn holdem_score(score: u16) -> u16 {
+    match score {
+        167..=1443 => score + 156,
+        1444..=1599 => score - 1277,
+        _ => score,
+    }
+}
+
+/// Hold'em scores of the best hand of `cards` without a flush and of the
+/// best flush, if one suit has five cards. `wheel` is the lowest straight.
+fn best_hands(cards: &[Card], wheel: u16) -> (u16, Option<u16>) {
+    let mut counts = [0u8; 13];
+    let mut suit_bits = [0u16; 4];
+    for card in cards {
+        counts[card.rank() as usize] += 1;
+        suit_bits[card.suit() as usize] |= 1 << card.rank();
+    }
+
+    let flush = suit_bits.iter().find(|bits| bits.count_ones() >= 5).map(|&bits| best_flush(bits, wheel));
+    (best_without_flush(&counts, wheel), flush)
+}
+
+/// Best hand of the given rank counts, ignoring suits: a straight, or the
+/// biggest group of equal ranks filled up with the highest other cards.
+fn best_without_flush(counts: &[u8; 13], wheel: u16) -> u16 {
+    let rank_bits = (0..13).filter(|&r| counts[r] > 0).fold(0u16, |bits, r| bits | 1 << r);
+    let straight = highest_straight(rank_bits, wheel).map_or(7463, |bits| UNIQUE5_TABLE[bits as usize]);
+
+    // The biggest group, the highest of equal size
+    let top = (0..13).max_by_key(|&r| (counts[r], r)).expect("13 ranks");
+    let score = if counts[top] == 1 {
+        UNIQUE5_TABLE[top_bits(rank_bits, 5) as usize]
+    } else {
+        let mut made = counts[top].min(4) as usize;
+        let mut product = PRIMES[top].pow(made as u32);
+        let mut used = 1u16 << top;
+
+        // A full house or two pair adds the highest other pair (or set)
+        if made < 4 && let Some(second) = (0..13).filter(|&r| r != top && counts[r] >= 2).max() {
+            product *= PRIMES[second].pow(2);
+            used |= 1 << second;
+            made += 2;
+        }
+
+        let kickers = top_bits(rank_bits & !used, 5 - made);
+        product *= (0..13).filter(|&r| kickers & 1 << r != 0).map(|r| PRIMES[r]).product::<u32>();
+        *PRIME_PRODUCT_TABLE.get(&product).expect("prime product of a 5-card hand")
+    };
+    score.min(straight)
+}
+
+/// Best straight flush or flush among the ranks of one suit's cards.
+fn best_flush(bits: u16, wheel: u16) -> u16 {
+    let five = highest_straight(bits, wheel).unwrap_or_else(|| top_bits(bits, 5));
+    FLUSH_TABLE[five as usize]
+}
+
+/// Rank bits of the highest straight in `bits`, if any, checking `wheel`
+/// last. A wheel comes back as the Hold'em one, which the tables know.
+fn highest_straight(bits: u16, wheel: u16) -> Option<u16> {
+    (0..=8).rev()
+        .map(|low| 0b11111 << low)
+        .find(|&run| bits & run == run)
+        .or_else(|| (bits & wheel == wheel).then_some(HOLDEM_WHEEL))
+}
+
+/// The `n` highest ranks of `bits`.
+fn top_bits(mut bits: u16, n: usize) -> u16 {
+    while bits.count_ones() as usize > n {
+        // Clear the lowest rank
+        bits &= bits - 1;
+    }
+    bits
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::Card;
+
+    fn cards_from_str(s: &str) -> Vec<Card> {
+        s.split_whitespace()
+            .map(|cs| Card::from_str(cs).unwrap_or_else(|| panic!("Invalid card: {}", cs)))
+            .collect()
+    }
+
+    fn eval_hand(s: &str) -> u16 {
+        let cards = cards_from_str(s);
+        evaluate_7_cards(&cards)
+    }
+
+    #[test]
+    fn test_royal_flush() {
+        let score = eval_hand("As Ks Qs Js Ts");
+        assert_eq!(score, 1, "Royal flush should be score 1");
+    }
+
+    #[test]
+    fn test_straight_flush() {
+        let score = eval_hand("9h 8h 7h 6h 5h");
+        assert!((2..=10).contains(&score), "Straight flush score: {}", score);
+    }
+
+    #[test]
+    fn test_four_of_a_kind() {
+        let score = eval_hand("As Ah Ad Ac Ks");
+        assert!((11..=166).contains(&score), "Quads score: {}", score);
+    }
+
+    #[test]
+    fn test_full_house() {
+        let score = eval_hand("As Ah Ad Ks Kh");
+        assert!((167..=322).contains(&score), "Full house score: {}", score);
+    }
+
+    #[test]
+    fn test_flush() {
+        let score = eval_hand("As Ks Qs Js 9s");
+        assert!((323..=1599).contains(&score), "Flush score: {}", score);
+    }
+
+    #[test]
+    fn test_straight() {
+        let score = eval_hand("Ah Ks Qd Jc Th");
+        assert!((1600..=1609).contains(&score), "Straight score: {}", score);
+    }
+
+    #[test]
+    fn test_three_of_a_kind() {
+        let score = eval_hand("As Ah Ad Ks Qh");
+        assert!((1610..=2467).contains(&score), "Trips score: {}", score);
+    }
+
+    #[test]
+    fn test_two_pair() {
+        let score = eval_hand("As Ah Ks Kh Qd");
+        assert!((2468..=3325).contains(&score), "Two pair score: {}", score);
+    }
+
+    #[test]
+    fn test_one_pair() {
+        let score = eval_hand("As Ah Ks Qh Jd");
+        assert!((3326..=6185).contains(&score), "One pair score: {}", score);
+    }
+
+    #[test]
+    fn test_high_card() {
+        let score = eval_hand("As Ks Qd Jc 9h");
+        assert!((6186..=7462).contains(&score), "High card score: {}", score);
+    }
+
+    #[test]
+    fn test_quads_beats_full_house() {
+        let quads = eval_hand("As Ah Ad Ac Ks");
+        let full_house = eval_hand("As Ah Ad Ks Kh");
+        assert!(quads < full_house, "Quads ({}) should beat Full House ({})", quads, full_house);
+    }
+
+    #[test]
+    fn test_full_house_beats_flush() {
+        let full_house = eval_hand("As Ah Ad Ks Kh");
+        let flush = eval_hand("As Ks Qs Js 9s");
+        assert!(full_house < flush, "Full House ({}) should beat Flush ({})", full_house, flush);
+    }
+
+    #[test]
+    fn test_7_card_evaluation() {
+        // Royal flush with 2 extra cards
+        let score = eval_hand("As Ks Qs Js Ts 2c 3d");
+        assert_eq!(score, 1, "7-card royal flush should be score 1");
+    }
+
+    #[test]
+    fn test_wheel_straight() {
+        let score = eval_hand("Ah 2s 3d 4c 5h");
+        assert!((1600..=1609).contains(&score), "Wheel should be a straight: {}", score);
+    }
+
+    #[test]
+    fn test_wheel_straight_flush() {
+        let score = eval_hand("Ah 2h 3h 4h 5h");
+        assert!((2..=10).contains(&score), "Wheel flush should be straight flush: {}", score);
+    }
+
+    #[test]
+    fn test_short_deck_rules() {
+        let short = |hand: &str| evaluate_short_deck(&cards_from_str(hand));
+        let (flush, full_house) = (short("As Js 9s 7s 6s Kd Kc"), short("Ks Kh Kd 9c 9h 7d 6c"));
+        assert!(flush < full_house, "flush {} should beat full house {}", flush, full_house);
+        assert_eq!(GameVariant::ShortDeck.hand_rank(flush), HandRank::Flush);
+        assert_eq!(GameVariant::ShortDeck.hand_rank(full_house), HandRank::FullHouse);
+        assert!(short("As Kh Kd Kc 9h") > full_house);
+
+        // A-6-7-8-9 is the lowest straight and straight flush
+        let wheel = short("Ah 6s 7d 8c 9h Kd Kc");
+        assert_eq!(GameVariant::ShortDeck.hand_rank(wheel), HandRank::Straight);
+        assert!(wheel > short("6s 7d 8c 9h Th"));
+        assert_eq!(GameVariant::ShortDeck.hand_rank(short("Ah 6h 7h 8h 9h")), HandRank::StraightFlush);
+        assert_eq!(HandRank::from_score(eval_hand("Ah 6s 7d 8c 9h")), HandRank::HighCard);
+
+        // Everything else ranks as in Hold'em
+        assert_eq!(short("As Ad Kh Kd 9c 7d 6c"), eval_hand("As Ad Kh Kd 9c 7d 6c"));
+        for score in 1..=7462 {
+            assert_eq!(holdem_score(short_deck_score(score)), score);
+        }
+    }
+
+    #[test]
+    fn test_direct_evaluation_matches_best_subset() {
+        use rand::seq::SliceRandom;
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let best_subset = |cards: &[Card]| {
+            let mut best = 7463;
+            for skip in 0..1u32 << cards.len() {
+                if (cards.len() - skip.count_ones() as usize) == 5 {
+                    let five: Vec<Card> = cards.iter().enumerate().filter(|(i, _)| skip & 1 << i == 0).map(|(_, c)| *c).collect();
+                    best = best.min(evaluate_5_cards(&five.try_into().unwrap()));
+                }
+            }
+            best
+        };
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut deck: Vec<Card> = (0..52).map(Card::from_index).collect();
+        for n in [6, 7] {
+            for _ in 0..20_000 {
+                deck.shuffle(&mut rng);
+                assert_eq!(evaluate_7_cards_direct(&deck[..n]), best_subset(&deck[..n]), "{:?}", &deck[..n]);
+            }
+        }
+        // Two flushes' worth of one suit, quads with a set, three pairs
+        for hand in ["2h 3h 5h 7h 9h Jh Kh", "9s 9h 9d 9c Ks Kh Kd", "As Ah 3d 3c 7s 7h Kd", "Ah 2h 3h 4h 5h 6h 7d"] {
+            let cards = cards_from_str(hand);
+            assert_eq!(evaluate_7_cards_direct(&cards), best_subset(&cards), "{}", hand);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Strategically distinct flops, and weighted subsets of them for
+//! aggregate reports.
+//!
+//! Two flops that a suit relabelling maps onto each other play the same, so
+//! the 22100 flops fall into 1755 classes. Each class is represented by
+//! one canonical flop, weighted by the number of flops in it.
+
+use crate::poker::isomorphism::{permute_card, suit_permutations};
+use crate::poker::texture::{classify, TEXTURE_FIELDS};
+use crate::poker::Card;
+
+/// Number of distinct flops, C(52, 3).
+pub const NUM_FLOPS: usize = 22100;
+
+/// Named subsets accepted by [`flop_subset`].
+pub const FLOP_SUBSETS: [&str; 3] = ["full", "stratified-184", "stratified-95"];
+
+/// Texture fields the named samplings stratify by.
+const SUBSET_STRATA: [&str; 3] = ["suits", "pairing", "high_card"];
+
+/// The canonical member of `flop`'s isomorphism class, highest card first.
+pub fn canonical_flop(flop: &[Card; 3]) -> [Card; 3] {
+    suit_permutations()
+        .iter()
+        .map(|perm| {
+            let mut cards = flop.map(|c| permute_card(c, perm));
+            cards.sort_by_key(|c| std::cmp::Reverse(c.index()));
+            cards
+        })
+        .max_by_key(|cards| cards.map(|c| c.index()))
+        .expect("there are 24 permutations")
+}
+
+/// Every canonical flop with the number of flops it stands for, highest
+/// flops first. The multiplicities sum to [`NUM_FLOPS`].
+pub fn generate_canonical_flops() -> Vec<([Card; 3], usize)> {
+    let mut counts = std::collections::BTreeMap::new();
+    for a in 0..52u8 {
+        for b in a + 1..52 {
+            for c in b + 1..52 {
+                let flop = canonical_flop(&[Card::from_index(a), Card::from_index(b), Card::from_index(c)]);
+                *counts.entry(std::cmp::Reverse(flop.map(|c| c.index()))).or_insert(0) += 1;
+            }
+        }
+    }
+    counts.into_iter()
+        .map(|(std::cmp::Reverse(indices), count)| (indices.map(Card::from_index), count))
+        .collect()
+}
+
+/// `n` canonical flops sampled evenly across the strata formed by the
+/// texture `fields` (names from [`TEXTURE_FIELDS`]). Each stratum gets a
+/// share of `n` proportional to its weight, and its weight is split evenly
+/// among the flops picked from it; strata too light to get a flop are
+/// dropped and the rest scaled up, so the weights still sum to
+/// [`NUM_FLOPS`]. Deterministic.
+pub fn sample_flops(n: usize, fields: &[String]) -> Result<Vec<([Card; 3], f64)>, String> {
+    if let Some(field) = fields.iter().find(|f| !TEXTURE_FIELDS.contains(&f.as_str())) {
+        return Err(format!("Unknown texture field '{}' (expected one of {:?})", field, TEXTURE_FIELDS));
+    }
+    let flops = generate_canonical_flops();
+    if n == 0 || n > flops.len() {
+        return Err(format!("Sample size must be between 1 and {}, got {}", flops.len(), n));
+    }
+
+    let mut strata: Vec<(String, Vec<usize>, usize)> = Vec::new();
+    for (i, (flop, weight)) in flops.iter().enumerate() {
+        let key = classify(flop).group(fields)?;
+        match strata.iter_mut().find(|s| s.0 == key) {
+            Some(stratum) => {
+                stratum.1.push(i);
+                stratum.2 += weight;
+            }
+            None => strata.push((key, vec![i], *weight)),
+        }
+    }
+
+    // Largest remainders, capped by the stratum size
+    let mut quota: Vec<usize> = strata.iter().map(|s| (n * s.2 / NUM_FLOPS).min(s.1.len())).collect();
+    let mut order: Vec<usize> = (0..strata.len()).collect();
+    order.sort_by_key(|&s| std::cmp::Reverse((n * strata[s].2) % NUM_FLOPS));
+    while quota.iter().sum::<usize>() < n {
+        let s = *order.iter().find(|&&s| quota[s] < strata[s].1.len()).expect("n is at most the number of flops");
+        quota[s] += 1;
+        order.retain(|&o| o != s);
+        order.push(s);
+    }
+
+    let kept: usize = strata.iter().zip(&quota).filter(|(_, q)| **q > 0).map(|(s, _)| s.2).sum();
+    let scale = NUM_FLOPS as f64 / kept as f64;
+    let mut sample = Vec::with_capacity(n);
+    for ((_, members, weight), &k) in strata.iter().zip(&quota) {
+        for j in 0..k {
+            let flop = flops[members[(2 * j + 1) * members.len() / (2 * k)]].0;
+            sample.push((flop, scale * *weight as f64 / k as f64));
+        }
+    }
+    sample.sort_by_key(|(flop, _)| std::cmp::Reverse(flop.map(|c| c.index())));
+    Ok(sample)
+}
+
+/// A named flop subset: `"full"` (all 1755 canonical flops, weighted by
+/// multiplicity), or `"stratified-184"` / `"stratified-95"`, this crate's
+/// stratified samplings of those sizes over suits, pairing and high card
+/// (see [`sample_flops`]). They are not the 184 and 95-flop lists other
+/// tools ship, so results on them do not line up flop for flop.
+pub fn flop_subset(name: &str) -> Result<Vec<([Card; 3], f64)>, String> {
+    match name {
+        "full" | "1755" => Ok(generate_canonical_flops().into_iter().map(|(f, w)| (f, w as f64)).collect()),
+        "stratified-184" | "stratified-95" => {
+            let fields: Vec<String> = SUBSET_STRATA.iter().map(|f| f.to_string()).collect();
+            let size = name["stratified-".len()..].parse().expect("numeric subset size");
+            sample_flops(size, &fields)
+        }
+        _ => Err(format!("Unknown flop subset '{}' (expected one of {:?})", name, FLOP_SUBSETS)),
+    }
+}
+
+/// `"As Ks Qs"`.
+pub fn flop_string(flop: &[Card; 3]) -> String {
+    flop.map(|c| c.to_string()).join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_flops() {
+        let flops = generate_canonical_flops();
+        assert_eq!(flops.len(), 1755);
+        assert_eq!(flops.iter().map(|(_, n)| n).sum::<usize>(), NUM_FLOPS);
+        assert_eq!(flop_string(&flops[0].0), "As Ah Ad");
+
+        // Monotone, two-tone and rainbow versions of one rank set
+        let weight = |s: &str| {
+            let cards: Vec<Card> = s.split_whitespace().filter_map(Card::from_str).collect();
+            let flop = canonical_flop(&[cards[0], cards[1], cards[2]]);
+            flops.iter().find(|(f, _)| *f == flop).unwrap().1
+        };
+        assert_eq!(weight("Kh 8h 3h"), 4);
+        assert_eq!(weight("Kh 8h 3c"), 12);
+        assert_eq!(weight("Kh 8d 3c"), 24);
+    }
+
+    #[test]
+    fn test_flop_subsets() {
+        for (name, size) in [("stratified-184", 184), ("stratified-95", 95), ("full", 1755)] {
+            let subset = flop_subset(name).unwrap();
+            assert_eq!(subset.len(), size);
+            assert!((subset.iter().map(|(_, w)| w).sum::<f64>() - NUM_FLOPS as f64).abs() < 1e-6);
+        }
+        let monotone = flop_subset("stratified-184").unwrap().iter()
+            .filter(|(f, _)| f.iter().all(|c| c.suit() == f[0].suit()))
+            .map(|(_, w)| w)
+            .sum::<f64>();
+        // 1144 of the 22100 flops are monotone
+        assert!((monotone / NUM_FLOPS as f64 - 1144.0 / 22100.0).abs() < 0.01);
+        assert!(flop_subset("184").is_err());
+        assert!(flop_subset("200").is_err());
+        assert!(sample_flops(10, &["wetness".to_string()]).is_err());
+    }
+}
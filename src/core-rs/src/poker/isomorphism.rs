@@ -0,0 +1,71 @@
+//! Suit symmetries: relabellings of the suits that leave a spot unchanged.
+//!
+//! On a board that uses at most two suits, for example, the two missing
+//! suits are interchangeable: `Ac Kc` and `Ad Kd` face exactly the same
+//! situation and have the same equilibrium strategy.
+
+use crate::poker::Card;
+
+/// A relabelling of the four suits: suit `s` becomes `perm[s]`.
+pub type SuitPermutation = [u8; 4];
+
+/// All 24 suit permutations, the identity first.
+pub fn suit_permutations() -> Vec<SuitPermutation> {
+    let mut result = Vec::with_capacity(24);
+    for a in 0..4u8 {
+        for b in (0..4).filter(|&b| b != a) {
+            for c in (0..4).filter(|&c| c != a && c != b) {
+                result.push([a, b, c, 6 - a - b - c]);
+            }
+        }
+    }
+    result
+}
+
+/// `card` with its suit relabelled by `perm`.
+#[inline]
+pub fn permute_card(card: Card, perm: &SuitPermutation) -> Card {
+    Card::new(card.rank(), perm[card.suit() as usize])
+}
+
+/// Card mask of `cards` relabelled by `perm`.
+pub fn permuted_mask(cards: &[Card], perm: &SuitPermutation) -> u64 {
+    cards.iter().fold(0, |mask, &c| mask | permute_card(c, perm).bitmask())
+}
+
+/// Suit permutations that map `board` onto itself (as a set) and leave
+/// every card of `fixed` where it is, e.g. rivers that each get their own
+/// subtree. Always contains the identity.
+pub fn board_symmetries(board: &[Card], fixed: &[Card]) -> Vec<SuitPermutation> {
+    let board_mask = permuted_mask(board, &[0, 1, 2, 3]);
+    suit_permutations()
+        .into_iter()
+        .filter(|perm| permuted_mask(board, perm) == board_mask)
+        .filter(|perm| fixed.iter().all(|&c| permute_card(c, perm) == c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().filter_map(Card::from_str).collect()
+    }
+
+    #[test]
+    fn test_board_symmetries() {
+        assert_eq!(suit_permutations().len(), 24);
+        assert_eq!(suit_permutations()[0], [0, 1, 2, 3]);
+
+        // Rainbow river: only the identity
+        assert_eq!(board_symmetries(&cards("Ks Th 7d 4c 2s"), &[]).len(), 1);
+        // Clubs and diamonds are missing, so they can swap
+        let two_tone = board_symmetries(&cards("Ks Qs Ts 7h 2h"), &[]);
+        assert_eq!(two_tone, vec![[0, 1, 2, 3], [1, 0, 2, 3]]);
+        // A paired card may swap suits along with its partner
+        assert_eq!(board_symmetries(&cards("Kh Ks 7c 7d 2h"), &[]), vec![[0, 1, 2, 3], [1, 0, 2, 3]]);
+        // A fixed club river rules the swap out
+        assert_eq!(board_symmetries(&cards("Ks Qs Ts 7h"), &cards("3c")).len(), 1);
+    }
+}
@@ -0,0 +1,207 @@
+//! What a range holds on a board: combo counts by hand category and by a
+//! finer description of how the hole cards connect with the board ("top
+//! pair top kicker", "set", ...), for text summaries such as "villain has
+//! 34 value combos".
+
+use serde::Serialize;
+
+use crate::poker::equity::hand_mask;
+use crate::poker::{evaluate_7_cards, Card, HandRank};
+
+/// How a combo's hole cards make its hand on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandDetail {
+    StraightFlush,
+    Quads,
+    FullHouse,
+    Flush,
+    Straight,
+    /// A pocket pair matching a board card.
+    Set,
+    /// One hole card matching a board pair.
+    Trips,
+    /// Both hole cards pairing the board.
+    TwoPair,
+    /// A pocket pair above every board card.
+    Overpair,
+    /// A hole card pairing the highest board card, with the best kicker
+    /// not on the board.
+    TopPairTopKicker,
+    /// Top pair with a weaker kicker.
+    TopPair,
+    /// A hole card pairing the second board rank, or a pocket pair between
+    /// the two highest.
+    SecondPair,
+    /// Any lower pair made with the hole cards.
+    WeakPair,
+    /// The hole cards make no pair or better; the board may.
+    NoMadeHand,
+}
+
+impl HandDetail {
+    /// All details, strongest first.
+    pub const ALL: [HandDetail; 14] = [
+        HandDetail::StraightFlush,
+        HandDetail::Quads,
+        HandDetail::FullHouse,
+        HandDetail::Flush,
+        HandDetail::Straight,
+        HandDetail::Set,
+        HandDetail::Trips,
+        HandDetail::TwoPair,
+        HandDetail::Overpair,
+        HandDetail::TopPairTopKicker,
+        HandDetail::TopPair,
+        HandDetail::SecondPair,
+        HandDetail::WeakPair,
+        HandDetail::NoMadeHand,
+    ];
+
+    /// Detail from its snake_case name, e.g. `"top_pair_top_kicker"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| serde_json::to_value(d).is_ok_and(|v| v == name))
+    }
+}
+
+/// The [`HandDetail`] of `hand` on `board` (three to five cards).
+pub fn classify_hand(board: &[Card], hand: &[Card]) -> HandDetail {
+    let score = evaluate_7_cards(&[hand, board].concat());
+    let mut board_ranks: Vec<u8> = board.iter().map(|c| c.rank()).collect();
+    board_ranks.sort_unstable_by(|a, b| b.cmp(a));
+    board_ranks.dedup();
+    let on_board = |rank: u8| board.iter().filter(|c| c.rank() == rank).count();
+    let (hi, lo) = (hand[0].rank().max(hand[1].rank()), hand[0].rank().min(hand[1].rank()));
+
+    // The pair the hole cards make, by where it sits among the board ranks
+    let pair = |rank: u8, kicker: Option<u8>| {
+        let position = board_ranks.iter().filter(|&&r| r > rank).count();
+        let paired_board = on_board(rank) > 0;
+        match (position, paired_board) {
+            (0, false) => HandDetail::Overpair,
+            (0, true) => {
+                let best_kicker = (0..13).rev().find(|r| !board_ranks.contains(r));
+                if kicker.is_some() && kicker == best_kicker { HandDetail::TopPairTopKicker } else { HandDetail::TopPair }
+            }
+            (1, _) => HandDetail::SecondPair,
+            _ => HandDetail::WeakPair,
+        }
+    };
+    // The best pair a hole card makes with the board, with the other card
+    let hole_pair = || {
+        [(hi, lo), (lo, hi)].into_iter()
+            .find(|&(rank, _)| on_board(rank) > 0)
+            .map(|(rank, kicker)| pair(rank, Some(kicker)))
+    };
+
+    match HandRank::from_score(score) {
+        HandRank::StraightFlush => HandDetail::StraightFlush,
+        HandRank::FourOfAKind => HandDetail::Quads,
+        HandRank::FullHouse => HandDetail::FullHouse,
+        HandRank::Flush => HandDetail::Flush,
+        HandRank::Straight => HandDetail::Straight,
+        HandRank::ThreeOfAKind if hi == lo && on_board(hi) > 0 => HandDetail::Set,
+        HandRank::ThreeOfAKind if on_board(hi) == 2 || on_board(lo) == 2 => HandDetail::Trips,
+        HandRank::TwoPair if hi != lo && on_board(hi) > 0 && on_board(lo) > 0 => HandDetail::TwoPair,
+        HandRank::TwoPair | HandRank::OnePair if hi == lo => pair(hi, None),
+        HandRank::TwoPair | HandRank::OnePair => hole_pair().unwrap_or(HandDetail::NoMadeHand),
+        _ => HandDetail::NoMadeHand,
+    }
+}
+
+/// Live combos of `range` (not touching `board` or `dead`) with their
+/// weights.
+fn live_combos<'a>(board: &'a [Card], dead: &'a [Card], range: &'a [Vec<Card>], weights: &'a [f32]) -> impl Iterator<Item = (&'a [Card], f32)> + 'a {
+    let blocked = hand_mask(board) | hand_mask(dead);
+    range.iter().zip(weights)
+        .filter(move |(hand, w)| **w > 0.0 && hand_mask(hand) & blocked == 0)
+        .map(|(hand, &w)| (hand.as_slice(), w))
+}
+
+/// Weighted number of combos of `range` that make a `category` hand on
+/// `board`. Combos holding a board card or one of `dead` don't count.
+pub fn count_made_hands(board: &[Card], dead: &[Card], range: &[Vec<Card>], weights: &[f32], category: HandRank) -> f32 {
+    live_combos(board, dead, range, weights)
+        .filter(|(hand, _)| HandRank::from_score(evaluate_7_cards(&[hand, board].concat())) == category)
+        .map(|(_, w)| w)
+        .sum()
+}
+
+/// Weighted combo count of every [`HandDetail`] (strongest first, zero
+/// counts included), with the same dead-card rules as
+/// [`count_made_hands`].
+pub fn count_hand_details(board: &[Card], dead: &[Card], range: &[Vec<Card>], weights: &[f32]) -> Vec<(HandDetail, f32)> {
+    let mut counts = HandDetail::ALL.map(|d| (d, 0.0));
+    for (hand, w) in live_combos(board, dead, range, weights) {
+        let detail = classify_hand(board, hand);
+        counts.iter_mut().find(|(d, _)| *d == detail).expect("ALL lists every detail").1 += w;
+    }
+    counts.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::preflop::{class_combos, class_index};
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().filter_map(Card::from_str).collect()
+    }
+
+    fn classes(names: &[&str]) -> Vec<Vec<Card>> {
+        names.iter().flat_map(|n| class_combos(class_index(n).unwrap())).map(|c| c.to_vec()).collect()
+    }
+
+    #[test]
+    fn test_classify_hand() {
+        let board = cards("Ks Th 8s 7d 2c");
+        let detail = |hand: &str| classify_hand(&board, &cards(hand));
+        assert_eq!(detail("Kd Kc"), HandDetail::Set);
+        assert_eq!(detail("Ad Kd"), HandDetail::TopPairTopKicker);
+        assert_eq!(detail("Kd Qd"), HandDetail::TopPair);
+        assert_eq!(detail("Kd Td"), HandDetail::TwoPair);
+        assert_eq!(detail("Ad Ac"), HandDetail::Overpair);
+        assert_eq!(detail("Jd Jc"), HandDetail::SecondPair);
+        assert_eq!(detail("Td 9d"), HandDetail::SecondPair);
+        assert_eq!(detail("6d 6c"), HandDetail::WeakPair);
+        assert_eq!(detail("9d 6d"), HandDetail::Straight);
+        assert_eq!(detail("Ad Qd"), HandDetail::NoMadeHand);
+
+        let paired = cards("Ks Kh 7s 4d 2c");
+        assert_eq!(classify_hand(&paired, &cards("Kd Qd")), HandDetail::Trips);
+        assert_eq!(classify_hand(&paired, &cards("7d 6d")), HandDetail::SecondPair);
+        assert_eq!(classify_hand(&paired, &cards("Ad Qd")), HandDetail::NoMadeHand);
+        assert_eq!(HandDetail::from_name("top_pair_top_kicker"), Some(HandDetail::TopPairTopKicker));
+    }
+
+    #[test]
+    fn test_combo_counts() {
+        let board = cards("Ks Th 8s 7d 2c");
+        let range = classes(&["KK", "77", "AKs", "AKo", "KQo", "T9s", "96s", "AA", "66"]);
+        let weights = vec![1.0; range.len()];
+
+        // 3 KK and 3 77 not using a board card
+        assert_eq!(count_made_hands(&board, &[], &range, &weights, HandRank::ThreeOfAKind), 6.0);
+        assert_eq!(count_made_hands(&board, &[], &range, &weights, HandRank::Straight), 4.0);
+        // 12 AK, 9 KQ, 3 T9, 6 AA and 6 66
+        assert_eq!(count_made_hands(&board, &[], &range, &weights, HandRank::OnePair), 36.0);
+        assert_eq!(count_made_hands(&board, &[], &range, &weights, HandRank::Flush), 0.0);
+
+        let details: std::collections::HashMap<HandDetail, f32> = count_hand_details(&board, &[], &range, &weights).into_iter().collect();
+        assert_eq!(details[&HandDetail::Set], 6.0);
+        assert_eq!(details[&HandDetail::TopPairTopKicker], 12.0);
+        assert_eq!(details[&HandDetail::TopPair], 9.0);
+        assert_eq!(details[&HandDetail::SecondPair], 3.0);
+        assert_eq!(details[&HandDetail::Overpair], 6.0);
+        assert_eq!(details[&HandDetail::WeakPair], 6.0);
+
+        // Holding the ace of spades leaves 3 AA and 9 AK; half-weight KQ
+        let weights: Vec<f32> = range.iter().map(|h| if h[0].rank() == 11 && h[1].rank() == 10 { 0.5 } else { 1.0 }).collect();
+        let dead = cards("As");
+        let details: std::collections::HashMap<HandDetail, f32> = count_hand_details(&board, &dead, &range, &weights).into_iter().collect();
+        assert_eq!(details[&HandDetail::Overpair], 3.0);
+        assert_eq!(details[&HandDetail::TopPairTopKicker], 9.0);
+        assert_eq!(details[&HandDetail::TopPair], 4.5);
+        assert_eq!(count_made_hands(&board, &dead, &range, &weights, HandRank::OnePair), 3.0 + 9.0 + 4.5 + 3.0 + 6.0);
+    }
+}
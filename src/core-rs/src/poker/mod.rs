@@ -1,10 +1,23 @@
-//! Poker primitives module
-//! Contains Card, Hand Evaluator, and Equity computation for the poker solver core.
-
-pub mod card;
-pub mod evaluator;
-pub mod equity;
-
-pub use card::Card;
-pub use evaluator::{evaluate_7_cards, evaluate_5_cards, HandRank, get_hand_rank_name};
-pub use equity::compute_equity_matrix;
+//! Poker primitives module
+//! Contains Card, Hand Evaluator, and Equity computation for the poker solver core.
+
+pub mod card;
+pub mod canonical;
+pub mod evaluator;
+pub mod equity;
+pub mod range;
+pub mod range_import;
+pub mod isomorphism;
+pub mod texture;
+pub mod flops;
+pub mod preflop;
+pub mod made_hands;
+pub mod monte_carlo;
+#[cfg(feature = "eval-table")]
+pub mod eval_table;
+
+pub use card::Card;
+pub use evaluator::{evaluate_7_cards, evaluate_5_cards, GameVariant, HandRank, get_hand_rank_name};
+pub use equity::{compute_equity_matrix, compute_equity_matrix_for, compute_equity_matrix_with_rivers, range_equities};
+pub use monte_carlo::{McEstimate, McOptions};
+pub use range::{format_range, hand_class, parse_range};
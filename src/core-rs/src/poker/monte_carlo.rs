@@ -0,0 +1,258 @@
+//! Monte Carlo equity with its uncertainty: every estimate carries the
+//! standard error of its mean, from the per-sample variance accumulated
+//! online (Welford's algorithm), and a 95% confidence interval.
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::poker::equity::hand_mask;
+use crate::poker::{evaluate_7_cards, Card};
+use crate::solver::builder::splitmix64;
+
+/// z-score of a two-sided 95% confidence interval.
+const Z_95: f64 = 1.959_964;
+
+/// Samples drawn between checks of `McOptions::target_half_width`, and the
+/// fewest an auto-sampled estimate stops at.
+const BATCH: usize = 100;
+
+/// Attempts at drawing a pair of combos that share no card (with each
+/// other or the board) before the ranges are taken not to have one.
+const MAX_REJECTIONS: usize = 10_000;
+
+/// A sampled mean with its standard error. Serializes with a `ci95` field,
+/// the 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct McEstimate {
+    pub mean: f64,
+    pub std_err: f64,
+    pub samples: usize,
+}
+
+impl McEstimate {
+    /// Half the width of the 95% confidence interval.
+    pub fn half_width(&self) -> f64 {
+        Z_95 * self.std_err
+    }
+
+    /// The 95% confidence interval (normal approximation).
+    pub fn ci95(&self) -> [f64; 2] {
+        [self.mean - self.half_width(), self.mean + self.half_width()]
+    }
+}
+
+impl Serialize for McEstimate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Fields {
+            mean: f64,
+            std_err: f64,
+            samples: usize,
+            ci95: [f64; 2],
+        }
+        Fields { mean: self.mean, std_err: self.std_err, samples: self.samples, ci95: self.ci95() }.serialize(serializer)
+    }
+}
+
+/// Running mean and variance of a stream of samples (Welford).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Welford {
+    samples: usize,
+    mean: f64,
+    /// Sum of squared deviations from the running mean.
+    m2: f64,
+}
+
+impl Welford {
+    pub fn push(&mut self, x: f64) {
+        self.samples += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.samples as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// The mean so far, with the standard error from the sample variance
+    /// (0 with fewer than two samples).
+    pub fn estimate(&self) -> McEstimate {
+        let std_err = if self.samples > 1 {
+            (self.m2 / (self.samples - 1) as f64 / self.samples as f64).sqrt()
+        } else {
+            0.0
+        };
+        McEstimate { mean: self.mean, std_err, samples: self.samples }
+    }
+}
+
+/// How much to sample.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McOptions {
+    /// Samples to draw; with a target, the most to draw.
+    #[serde(default = "default_samples")]
+    pub samples: usize,
+    #[serde(default)]
+    pub seed: u64,
+    /// Stop once the 95% confidence interval is narrower than this on
+    /// either side (checked every 100 samples).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_half_width: Option<f64>,
+}
+
+fn default_samples() -> usize {
+    10_000
+}
+
+impl Default for McOptions {
+    fn default() -> Self {
+        McOptions { samples: default_samples(), seed: 0, target_half_width: None }
+    }
+}
+
+/// Draw samples with `sample` (given the generator state) until `options`
+/// says to stop.
+pub fn sample_until(options: &McOptions, mut sample: impl FnMut(&mut u64) -> f64) -> McEstimate {
+    let mut state = options.seed;
+    let mut stats = Welford::default();
+    while stats.samples < options.samples {
+        stats.push(sample(&mut state));
+        if let Some(target) = options.target_half_width
+            && stats.samples.is_multiple_of(BATCH)
+            && stats.estimate().half_width() <= target
+        {
+            break;
+        }
+    }
+    stats.estimate()
+}
+
+/// Equity of `ranges[0]` against `ranges[1]` on `board` (zero to five
+/// cards): each sample draws a combo from each range, uniformly among the
+/// pairs sharing no card, and deals the rest of the board. Ties count half.
+pub fn sample_equity(board: &[Card], ranges: [&[Vec<Card>]; 2], options: &McOptions) -> Result<McEstimate, String> {
+    if board.len() > 5 {
+        return Err(format!("A board has at most 5 cards, got {}", board.len()));
+    }
+    if ranges.iter().any(|r| r.is_empty()) {
+        return Err("Both ranges need at least one combo".to_string());
+    }
+    let board_mask = hand_mask(board);
+    let masks = ranges.map(|range| range.iter().map(|hand| hand_mask(hand)).collect::<Vec<u64>>());
+    let live = |i: usize, j: usize| masks[0][i] & masks[1][j] == 0 && (masks[0][i] | masks[1][j]) & board_mask == 0;
+    if !(0..ranges[0].len()).any(|i| (0..ranges[1].len()).any(|j| live(i, j))) {
+        return Err("No pair of combos can be dealt on this board".to_string());
+    }
+
+    let mut failed = None;
+    let mut deck: Vec<Card> = Vec::with_capacity(52);
+    let mut cards = [board.to_vec(), board.to_vec()];
+    let estimate = sample_until(options, |state| {
+        let pick = |state: &mut u64, n: usize| (splitmix64(state) % n as u64) as usize;
+        let Some((i, j)) = (0..MAX_REJECTIONS)
+            .map(|_| (pick(state, ranges[0].len()), pick(state, ranges[1].len())))
+            .find(|&(i, j)| live(i, j))
+        else {
+            failed = Some("Could not draw a pair of combos sharing no card".to_string());
+            return 0.0;
+        };
+        let dead = board_mask | masks[0][i] | masks[1][j];
+        deck.clear();
+        deck.extend((0..52).map(Card::from_index).filter(|c| c.bitmask() & dead == 0));
+        // Partial Fisher-Yates: the first cards complete the board
+        let missing = 5 - board.len();
+        for k in 0..missing {
+            let l = k + pick(state, deck.len() - k);
+            deck.swap(k, l);
+        }
+        for (p, hand) in [&ranges[0][i], &ranges[1][j]].into_iter().enumerate() {
+            cards[p].truncate(board.len());
+            cards[p].extend_from_slice(&deck[..missing]);
+            cards[p].extend_from_slice(hand);
+        }
+        // Lower scores are better
+        match evaluate_7_cards(&cards[0]).cmp(&evaluate_7_cards(&cards[1])) {
+            std::cmp::Ordering::Less => 1.0,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Greater => 0.0,
+        }
+    });
+    match failed {
+        Some(e) => Err(e),
+        None => Ok(estimate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::equity::compute_single_equity;
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().filter_map(Card::from_str).collect()
+    }
+
+    #[test]
+    fn test_welford() {
+        let mut stats = Welford::default();
+        assert_eq!(stats.estimate(), McEstimate { mean: 0.0, std_err: 0.0, samples: 0 });
+        for x in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            stats.push(x);
+        }
+        let estimate = stats.estimate();
+        assert_eq!((estimate.mean, estimate.samples), (5.0, 8));
+        // Sample variance 32/7
+        assert!((estimate.std_err - (32.0f64 / 7.0 / 8.0).sqrt()).abs() < 1e-12);
+        let json = serde_json::to_value(estimate).unwrap();
+        assert_eq!(json["ci95"][0].as_f64().unwrap(), estimate.ci95()[0]);
+    }
+
+    #[test]
+    fn test_error_shrinks_with_the_square_root_of_samples() {
+        let board = cards("Ks Th 7s");
+        let ranges = [vec![cards("As Ad")], vec![cards("9s 8s")]];
+        let estimate = |samples: usize| {
+            sample_equity(&board, [&ranges[0], &ranges[1]], &McOptions { samples, seed: 7, target_half_width: None }).unwrap()
+        };
+        let (small, large) = (estimate(1_000), estimate(16_000));
+        assert_eq!((small.samples, large.samples), (1_000, 16_000));
+        let ratio = small.std_err / large.std_err;
+        assert!((3.5..4.5).contains(&ratio), "{}", ratio);
+        assert!(large.ci95()[0] > 0.3 && large.ci95()[1] < 0.6, "{:?}", large);
+
+        // Sampling stops once the interval is narrow enough
+        let auto = sample_equity(&board, [&ranges[0], &ranges[1]], &McOptions { samples: 100_000, seed: 7, target_half_width: Some(0.02) }).unwrap();
+        assert!(auto.half_width() <= 0.02 && auto.samples < 100_000 && auto.samples.is_multiple_of(BATCH), "{:?}", auto);
+    }
+
+    #[test]
+    fn test_intervals_cover_the_enumerated_equity() {
+        let board = cards("Ks Th 7s 4d");
+        let (hero, villain) = (cards("As Ad"), cards("9s 8s"));
+        let rivers: Vec<f32> = (0..52).map(Card::from_index)
+            .filter(|river| !board.contains(river))
+            .filter_map(|river| compute_single_equity(&[&board[..], &[river]].concat(), &hero, &villain))
+            .collect();
+        let exact = rivers.iter().sum::<f32>() as f64 / rivers.len() as f64;
+
+        let ranges = [vec![hero], vec![villain]];
+        let seeds = 200;
+        let covered = (0..seeds)
+            .filter(|&seed| {
+                let ci = sample_equity(&board, [&ranges[0], &ranges[1]], &McOptions { samples: 400, seed, target_half_width: None }).unwrap().ci95();
+                ci[0] <= exact && exact <= ci[1]
+            })
+            .count();
+        // About 95% of the intervals hold the exact answer
+        assert!((180..=198).contains(&covered), "{} of {}", covered, seeds);
+    }
+
+    #[test]
+    fn test_ranges_and_blockers() {
+        let board = cards("Ks Th 7s 4d");
+        let ranges = [vec![cards("As Ad"), cards("Ks Kd")], vec![cards("Ac Ah"), cards("As Kc")]];
+        // Only As Ad against Ac Ah can be dealt: a chopped pot but for runner-runner flushes
+        let estimate = sample_equity(&board, [&ranges[0], &ranges[1]], &McOptions { samples: 500, ..McOptions::default() }).unwrap();
+        assert!((estimate.mean - 0.5).abs() < 0.1, "{:?}", estimate);
+
+        let blocked = [vec![cards("As Ad")], vec![cards("As Kc")]];
+        assert!(sample_equity(&board, [&blocked[0], &blocked[1]], &McOptions::default()).unwrap_err().contains("No pair"));
+        assert!(sample_equity(&cards("Ks Th 7s 4d 2c 3c"), [&ranges[0], &ranges[1]], &McOptions::default()).is_err());
+    }
+}
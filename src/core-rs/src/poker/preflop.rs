@@ -0,0 +1,252 @@
+//! Preflop hand classes and their all-in equities against each other.
+//!
+//! The 169 classes are laid out as the usual 13x13 grid, aces first: row
+//! `r` and column `c` index ranks from A down to 2, pairs sit on the
+//! diagonal, suited hands above it (`AKs` is row A, column K) and offsuit
+//! hands below it (`AKo` is row K, column A).
+//!
+//! The class-vs-class equity table ships with the crate
+//! (`preflop_equity.bin`, written by `cargo run --release --example
+//! preflop_table`). Each entry averages every pair of non-overlapping
+//! combos of the two classes, so card removal is accounted for.
+
+use std::sync::OnceLock;
+
+use crate::poker::monte_carlo::{sample_until, McEstimate, McOptions};
+use crate::poker::{evaluate_7_cards, Card};
+use crate::solver::format::{self, V1_PAYLOAD};
+
+/// Number of preflop hand classes.
+pub const NUM_CLASSES: usize = 169;
+
+/// Leading bytes of the equity table file.
+pub const TABLE_MAGIC: &[u8; 4] = b"PFEQ";
+
+const RANKS: &[u8; 13] = b"AKQJT98765432";
+
+static TABLE_BYTES: &[u8] = include_bytes!("preflop_equity.bin");
+
+static TABLE: OnceLock<Vec<f32>> = OnceLock::new();
+
+/// Grid index of a class name such as `"AKs"`, `"T9o"` or `"77"`. The
+/// ranks may come in either order.
+pub fn class_index(name: &str) -> Option<usize> {
+    let bytes = name.trim().as_bytes();
+    let rank = |b: u8| RANKS.iter().position(|&r| r == b.to_ascii_uppercase());
+    let (a, b) = (rank(*bytes.first()?)?, rank(*bytes.get(1)?)?);
+    let (hi, lo) = (a.min(b), a.max(b));
+    match (bytes.get(2).map(u8::to_ascii_lowercase), bytes.len()) {
+        (None, 2) if hi == lo => Some(hi * 13 + hi),
+        (Some(b's'), 3) if hi != lo => Some(hi * 13 + lo),
+        (Some(b'o'), 3) if hi != lo => Some(lo * 13 + hi),
+        _ => None,
+    }
+}
+
+/// Name of the class at grid `index`.
+pub fn class_name(index: usize) -> String {
+    let (row, col) = (index / 13, index % 13);
+    let (hi, lo) = (RANKS[row.min(col)] as char, RANKS[row.max(col)] as char);
+    match row.cmp(&col) {
+        std::cmp::Ordering::Equal => format!("{}{}", hi, lo),
+        std::cmp::Ordering::Less => format!("{}{}s", hi, lo),
+        std::cmp::Ordering::Greater => format!("{}{}o", hi, lo),
+    }
+}
+
+/// Every combo of the class at grid `index`: 6 for pairs, 4 suited, 12
+/// offsuit.
+pub fn class_combos(index: usize) -> Vec<[Card; 2]> {
+    let (row, col) = (index / 13, index % 13);
+    // Card ranks run from 2 (0) up to A (12)
+    let (hi, lo) = (12 - row.min(col) as u8, 12 - row.max(col) as u8);
+    let mut combos = Vec::new();
+    for s1 in 0..4 {
+        for s2 in 0..4 {
+            let keep = match row.cmp(&col) {
+                std::cmp::Ordering::Equal => s1 < s2,
+                std::cmp::Ordering::Less => s1 == s2,
+                std::cmp::Ordering::Greater => s1 != s2,
+            };
+            if keep {
+                combos.push([Card::new(hi, s1), Card::new(lo, s2)]);
+            }
+        }
+    }
+    combos
+}
+
+/// Equity of class `a` against class `b` when all-in preflop, e.g.
+/// `preflop_class_equity("AA", "KK")` is about 0.82. Ties count half.
+pub fn preflop_class_equity(a: &str, b: &str) -> Result<f32, String> {
+    let index = |name: &str| class_index(name).ok_or_else(|| format!("Unknown hand class '{}'", name));
+    let (a, b) = (index(a)?, index(b)?);
+    Ok(preflop_table()[a * NUM_CLASSES + b])
+}
+
+/// The shipped table: entry `a * NUM_CLASSES + b` is class `a`'s equity
+/// against class `b`.
+pub fn preflop_table() -> &'static [f32] {
+    TABLE.get_or_init(|| decode_table(TABLE_BYTES).expect("the shipped preflop table is valid"))
+}
+
+/// Monte Carlo estimate of the table, `samples` boards per pair of
+/// classes, cycling through the pairs' non-overlapping combo pairs. Slow:
+/// this is the offline build step behind `preflop_equity.bin`.
+pub fn generate_preflop_table(samples: usize, seed: u64) -> Vec<f32> {
+    let row = |a: usize| -> Vec<f32> {
+        (0..NUM_CLASSES)
+            .map(|b| {
+                if b < a {
+                    return f32::NAN;
+                }
+                let options = McOptions { samples, seed: seed ^ (a * NUM_CLASSES + b) as u64, target_half_width: None };
+                class_vs_class(a, b, &options).mean as f32
+            })
+            .collect()
+    };
+
+    #[cfg(rayon_pool)]
+    let rows: Vec<Vec<f32>> = if crate::parallel::pool_ready() {
+        use rayon::prelude::*;
+        (0..NUM_CLASSES).into_par_iter().map(row).collect()
+    } else {
+        (0..NUM_CLASSES).map(row).collect()
+    };
+
+    #[cfg(not(rayon_pool))]
+    let rows: Vec<Vec<f32>> = (0..NUM_CLASSES).map(row).collect();
+
+    // Only the upper triangle was sampled; the rest mirrors it
+    let mut table = rows.concat();
+    for a in 0..NUM_CLASSES {
+        for b in 0..a {
+            table[a * NUM_CLASSES + b] = 1.0 - table[b * NUM_CLASSES + a];
+        }
+    }
+    table
+}
+
+/// Sampled equity of class `a` against class `b`, with its standard
+/// error: a live table entry, rather than the shipped, fixed one.
+pub fn sample_class_equity(a: &str, b: &str, options: &McOptions) -> Result<McEstimate, String> {
+    let index = |name: &str| class_index(name).ok_or_else(|| format!("Unknown hand class '{}'", name));
+    Ok(class_vs_class(index(a)?, index(b)?, options))
+}
+
+/// Sampled equity of class `a` against class `b`.
+fn class_vs_class(a: usize, b: usize, options: &McOptions) -> McEstimate {
+    let matchups: Vec<[Card; 4]> = class_combos(a).iter()
+        .flat_map(|&[a0, a1]| class_combos(b).into_iter().map(move |[b0, b1]| [a0, a1, b0, b1]))
+        .filter(|cards| (0..4).all(|i| (i + 1..4).all(|j| cards[i] != cards[j])))
+        .collect();
+
+    let mut next = 0;
+    let mut deck: Vec<Card> = Vec::with_capacity(52);
+    sample_until(options, |state| {
+        let hands = &matchups[next % matchups.len()];
+        next += 1;
+        deck.clear();
+        deck.extend((0..52).map(Card::from_index).filter(|c| !hands.contains(c)));
+        // Partial Fisher-Yates: the first five cards are the board
+        for j in 0..5 {
+            let k = j + (crate::solver::builder::splitmix64(state) % (deck.len() - j) as u64) as usize;
+            deck.swap(j, k);
+        }
+        let score = |hole: &[Card]| evaluate_7_cards(&[hole, &deck[..5]].concat());
+        // Lower scores are better
+        match score(&hands[..2]).cmp(&score(&hands[2..])) {
+            std::cmp::Ordering::Less => 1.0,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Greater => 0.0,
+        }
+    })
+}
+
+/// Encode a table as stored in `preflop_equity.bin`: a container (see
+/// [`format`]) with magic [`TABLE_MAGIC`] and one `EQTY` section holding
+/// every entry as a little-endian `u16` fraction of 65535.
+pub fn encode_table(table: &[f32]) -> Vec<u8> {
+    let entries: Vec<u8> = table.iter()
+        .flat_map(|e| ((e.clamp(0.0, 1.0) * 65535.0).round() as u16).to_le_bytes())
+        .collect();
+    format::write(TABLE_MAGIC, &[(*b"EQTY", entries)])
+}
+
+/// Decode [`encode_table`] output (or a version 1 table), checking its
+/// checksums and size.
+pub fn decode_table(bytes: &[u8]) -> Result<Vec<f32>, String> {
+    let sections = format::read(TABLE_MAGIC, "preflop equity table", bytes)?;
+    let entries = match sections.version() {
+        1 => sections.require(&V1_PAYLOAD, "Preflop equity table")?,
+        _ => sections.require(b"EQTY", "Preflop equity table")?,
+    };
+    if entries.len() != 2 * NUM_CLASSES * NUM_CLASSES {
+        return Err(format!("Preflop equity table has {} bytes, expected {}", entries.len(), 2 * NUM_CLASSES * NUM_CLASSES));
+    }
+    Ok(entries.chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]]) as f32 / 65535.0).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_grid() {
+        assert_eq!(class_index("AA"), Some(0));
+        assert_eq!(class_index("AKs"), Some(1));
+        assert_eq!(class_index("ka0"), None);
+        assert_eq!(class_index("kao"), Some(13));
+        assert_eq!(class_index("22"), Some(168));
+        assert_eq!(class_index("AAs"), None);
+        for index in 0..NUM_CLASSES {
+            assert_eq!(class_index(&class_name(index)), Some(index));
+        }
+        assert_eq!(class_combos(0).len(), 6);
+        assert_eq!(class_combos(1).len(), 4);
+        assert_eq!(class_combos(13).len(), 12);
+        assert_eq!((0..NUM_CLASSES).map(|i| class_combos(i).len()).sum::<usize>(), 1326);
+    }
+
+    #[test]
+    fn test_published_matchups() {
+        // Exact all-in equities, ties counted half
+        for (a, b, equity) in [
+            ("AA", "KK", 0.8195),
+            ("AKo", "QQ", 0.4316),
+            ("AKs", "QQ", 0.4620),
+            ("22", "AKo", 0.5265),
+            ("AA", "72o", 0.8817),
+            ("KK", "AKs", 0.6598),
+        ] {
+            let table = preflop_class_equity(a, b).unwrap();
+            assert!((table - equity).abs() < 0.01, "{} vs {}: {} (expected {})", a, b, table, equity);
+            assert!((preflop_class_equity(b, a).unwrap() - (1.0 - table)).abs() < 1e-4);
+        }
+        assert!((preflop_class_equity("AA", "AA").unwrap() - 0.5).abs() < 0.01);
+        assert!(preflop_class_equity("AA", "AKx").is_err());
+    }
+
+    #[test]
+    fn test_sampled_class_equity() {
+        let options = McOptions { samples: 4_000, seed: 3, target_half_width: None };
+        let estimate = sample_class_equity("AA", "KK", &options).unwrap();
+        assert_eq!(estimate.samples, 4_000);
+        let [lo, hi] = estimate.ci95();
+        // Generous: a 95% interval misses now and then
+        assert!(lo - 0.01 < 0.8195 && 0.8195 < hi + 0.01, "{:?}", estimate);
+        assert!(sample_class_equity("AA", "AKx", &options).is_err());
+    }
+
+    #[test]
+    fn test_table_round_trip() {
+        let table = generate_preflop_table(4, 1);
+        let decoded = decode_table(&encode_table(&table)).unwrap();
+        assert!(table.iter().zip(&decoded).all(|(a, b)| (a - b).abs() < 1e-4));
+
+        let mut corrupted = encode_table(&table);
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        assert!(decode_table(&corrupted).unwrap_err().contains("checksum"));
+    }
+}
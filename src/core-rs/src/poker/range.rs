@@ -0,0 +1,120 @@
+//! Range strings: comma-separated two-card hands such as `"As Ad, Kc Kd"`.
+//! A hand may carry a weight after a colon, `"As Ad:0.5"`: the share of the
+//! combo in the range, 1 when left out.
+
+use serde::Serialize;
+
+use crate::poker::Card;
+
+/// Parse a comma-separated list of hands, ignoring their weights (see
+/// [`parse_weighted_range`] for which entries are skipped).
+pub fn parse_range(s: &str) -> Vec<Vec<Card>> {
+    parse_weighted_range(s).into_iter().map(|(hand, _)| hand).collect()
+}
+
+/// Parse a comma-separated list of hands with their weights.
+///
+/// Entries that do not contain exactly two valid cards are skipped, matching
+/// how the WASM session has always treated its range strings, and so are
+/// entries whose weight is not a number from 0 to 1. A combo at weight 0 is
+/// not in the range and is left out too.
+pub fn parse_weighted_range(s: &str) -> Vec<(Vec<Card>, f32)> {
+    s.split(',')
+        .filter_map(|entry| {
+            let (hand, weight) = match entry.split_once(':') {
+                Some((hand, weight)) => (hand, weight.trim().parse::<f32>().ok().filter(|w| (0.0..=1.0).contains(w))?),
+                None => (entry, 1.0),
+            };
+            let hand: Vec<Card> = hand.split_whitespace().filter_map(Card::from_str).collect();
+            (hand.len() == 2 && weight > 0.0).then_some((hand, weight))
+        })
+        .collect()
+}
+
+/// Format hands in the form accepted by [`parse_range`].
+pub fn format_range(range: &[Vec<Card>]) -> String {
+    range
+        .iter()
+        .map(|hand| hand.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format hands with their weights in the form accepted by
+/// [`parse_weighted_range`]; full weights are left out.
+pub fn format_weighted_range(range: &[Vec<Card>], weights: &[f32]) -> String {
+    range
+        .iter()
+        .zip(weights)
+        .map(|(hand, &weight)| {
+            let hand = hand.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+            if weight == 1.0 { hand } else { format!("{}:{}", hand, weight) }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Starting-hand class of a two-card hand: `"AA"`, `"AKs"` or `"AKo"`,
+/// higher rank first.
+pub fn hand_class(hand: &[Card]) -> String {
+    let (hi, lo) = if hand[0].rank() >= hand[1].rank() { (hand[0], hand[1]) } else { (hand[1], hand[0]) };
+    let ranks = format!("{}{}", hi.to_string().remove(0), lo.to_string().remove(0));
+    if hi.rank() == lo.rank() {
+        ranks
+    } else if hi.suit() == lo.suit() {
+        ranks + "s"
+    } else {
+        ranks + "o"
+    }
+}
+
+/// A combo taken out of a range, with why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DroppedCombo {
+    pub hand: String,
+    pub reason: String,
+}
+
+/// Split weighted `range` into the combos that can be dealt alongside
+/// `board` and the ones sharing a card with it, in range order.
+pub fn drop_board_blocked(range: Vec<(Vec<Card>, f32)>, board: &[Card]) -> (Vec<(Vec<Card>, f32)>, Vec<DroppedCombo>) {
+    let mut dropped = Vec::new();
+    let kept = range.into_iter()
+        .filter(|(hand, _)| match hand.iter().find(|c| board.contains(c)) {
+            Some(card) => {
+                dropped.push(DroppedCombo { hand: format_range(std::slice::from_ref(hand)), reason: format!("shares {} with the board", card) });
+                false
+            }
+            None => true,
+        })
+        .collect();
+    (kept, dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let range = parse_range("As Ad, kc KD ,, Qh, 7s 7s 7s, Tc 9c");
+        assert_eq!(format_range(&range), "As Ad, Kc Kd, Tc 9c");
+        assert_eq!(parse_range(&format_range(&range)), range);
+    }
+
+    #[test]
+    fn test_weighted_ranges() {
+        let range = parse_weighted_range("As Ad:0.5, Kc Kd, Qc Qd : 0.25, Jc Jd:0, Tc Td:1.5, 9c 9d:x, 8c 8d:1");
+        let weights: Vec<f32> = range.iter().map(|(_, w)| *w).collect();
+        assert_eq!(weights, [0.5, 1.0, 0.25, 1.0]);
+        let hands: Vec<Vec<Card>> = range.into_iter().map(|(hand, _)| hand).collect();
+        assert_eq!(format_weighted_range(&hands, &weights), "As Ad:0.5, Kc Kd, Qc Qd:0.25, 8c 8d");
+        assert_eq!(parse_range("As Ad:0.5, Kc Kd"), parse_range("As Ad, Kc Kd"));
+    }
+
+    #[test]
+    fn test_hand_class() {
+        let classes: Vec<String> = parse_range("As Ad, Kh Ac, 5c Tc, 9d 8s").iter().map(|h| hand_class(h)).collect();
+        assert_eq!(classes, ["AA", "AKo", "T5s", "98o"]);
+    }
+}
@@ -0,0 +1,251 @@
+//! Ranges in the text formats of other solvers, turned into the weighted
+//! combos of [`crate::poker::range`].
+//!
+//! Both formats list hands separated by commas: classes (`"AA"`, `"AKs"`,
+//! `"AKo"`, or `"AK"` for both), runs (`"QQ+"`, `"ATs+"`, `"QQ-99"`,
+//! `"A5s-A2s"`) and single combos (`"AsKs"`). They differ in weights:
+//!
+//! - PioSolver: a weight from 0 to 1 after a colon, `"AA,KK:0.5"`.
+//! - GTO+: a percentage in brackets around a group of hands,
+//!   `"[50.0]AA,KK[/50.0],AKs"`.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::poker::preflop::{class_combos, class_index};
+use crate::poker::Card;
+
+/// Ranks from deuce (0) up to ace (12), as card ranks count them.
+const RANKS: &[u8; 13] = b"23456789TJQKA";
+
+/// A range file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeFormat {
+    Pio,
+    GtoPlus,
+}
+
+impl RangeFormat {
+    /// The format `text` is in: GTO+ if it has bracketed weights, else Pio
+    /// (which also reads plain lists of hands).
+    pub fn detect(text: &str) -> Self {
+        if text.contains('[') { RangeFormat::GtoPlus } else { RangeFormat::Pio }
+    }
+}
+
+/// Parse `text` in `format` (detected when `None`) into combos with their
+/// weights, in the order they first appear. A combo listed twice takes its
+/// last weight, and combos at weight 0 are left out.
+pub fn import_range(text: &str, format: Option<RangeFormat>) -> Result<Vec<(Vec<Card>, f32)>, String> {
+    let entries = match format.unwrap_or_else(|| RangeFormat::detect(text)) {
+        RangeFormat::Pio => pio_entries(text)?,
+        RangeFormat::GtoPlus => gto_plus_entries(text)?,
+    };
+
+    let mut combos: Vec<(Vec<Card>, f32)> = Vec::new();
+    let mut seen: HashMap<u64, usize> = HashMap::new();
+    for (token, weight) in entries {
+        for hand in expand(&token)? {
+            let mask = hand[0].bitmask() | hand[1].bitmask();
+            match seen.get(&mask) {
+                Some(&i) => combos[i].1 = weight,
+                None => {
+                    seen.insert(mask, combos.len());
+                    combos.push((hand.to_vec(), weight));
+                }
+            }
+        }
+    }
+    combos.retain(|(_, weight)| *weight > 0.0);
+    Ok(combos)
+}
+
+/// `hand:weight` tokens separated by commas or whitespace.
+fn pio_entries(text: &str) -> Result<Vec<(String, f32)>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(|token| match token.split_once(':') {
+            Some((hand, weight)) => {
+                let weight = weight.parse::<f32>().ok()
+                    .filter(|w| (0.0..=1.0).contains(w))
+                    .ok_or_else(|| format!("Invalid weight in '{}': expected a number from 0 to 1", token))?;
+                Ok((hand.to_string(), weight))
+            }
+            None => Ok((token.to_string(), 1.0)),
+        })
+        .collect()
+}
+
+/// Hands separated by commas or whitespace, inside `[w]...[/w]` groups
+/// weighted `w` percent (full weight outside any group).
+fn gto_plus_entries(text: &str) -> Result<Vec<(String, f32)>, String> {
+    let mut entries = Vec::new();
+    let mut groups: Vec<f32> = Vec::new();
+    let mut token = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != ',' && c != '[' && !c.is_whitespace() {
+            token.push(c);
+            continue;
+        }
+        if !token.is_empty() {
+            entries.push((std::mem::take(&mut token), groups.last().copied().unwrap_or(1.0)));
+        }
+        if c != '[' {
+            continue;
+        }
+        let tag: String = chars.by_ref().take_while(|&c| c != ']').collect();
+        let percent = |s: &str| s.trim().parse::<f32>().ok().filter(|p| (0.0..=100.0).contains(p));
+        match tag.strip_prefix('/') {
+            Some(closing) => {
+                let open = groups.pop().ok_or_else(|| format!("'[{}]' closes no group", tag))?;
+                if percent(closing).is_none_or(|p| p / 100.0 != open) {
+                    return Err(format!("'[{}]' does not close the group weighted {}%", tag, open * 100.0));
+                }
+            }
+            None => groups.push(percent(&tag).ok_or_else(|| format!("Invalid weight '[{}]': expected a percentage", tag))? / 100.0),
+        }
+    }
+    if !token.is_empty() {
+        entries.push((token, groups.last().copied().unwrap_or(1.0)));
+    }
+    if let Some(open) = groups.last() {
+        return Err(format!("The group weighted {}% is never closed", open * 100.0));
+    }
+    Ok(entries)
+}
+
+/// A class token split up: the ranks (high first) and `s`, `o` or neither.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Class {
+    hi: usize,
+    lo: usize,
+    suit: Option<char>,
+}
+
+impl Class {
+    fn parse(token: &str) -> Option<Class> {
+        let bytes = token.as_bytes();
+        let rank = |b: &u8| RANKS.iter().position(|r| *r == b.to_ascii_uppercase());
+        let (a, b) = (rank(bytes.first()?)?, rank(bytes.get(1)?)?);
+        let suit = match bytes.get(2).map(u8::to_ascii_lowercase) {
+            None => None,
+            Some(s @ (b's' | b'o')) if bytes.len() == 3 && a != b => Some(s as char),
+            Some(_) => return None,
+        };
+        Some(Class { hi: a.max(b), lo: a.min(b), suit })
+    }
+
+    fn with_ranks(self, hi: usize, lo: usize) -> Class {
+        Class { hi, lo, ..self }
+    }
+
+    fn combos(self) -> Vec<[Card; 2]> {
+        let ranks = format!("{}{}", RANKS[self.hi] as char, RANKS[self.lo] as char);
+        let names = match self.suit {
+            _ if self.hi == self.lo => vec![ranks],
+            Some(suit) => vec![format!("{}{}", ranks, suit)],
+            None => vec![format!("{}s", ranks), format!("{}o", ranks)],
+        };
+        names.iter().flat_map(|name| class_combos(class_index(name).expect("a valid class"))).collect()
+    }
+}
+
+/// Every combo a hand token stands for.
+fn expand(token: &str) -> Result<Vec<[Card; 2]>, String> {
+    let unknown = || format!("Unknown hand '{}'", token);
+    if token.len() == 4
+        && let (Some(a), Some(b)) = (Card::from_str(&token[..2]), Card::from_str(&token[2..]))
+    {
+        return if a == b { Err(unknown()) } else { Ok(vec![[a, b]]) };
+    }
+
+    let classes: Vec<Class> = if let Some(base) = token.strip_suffix('+') {
+        // Pairs up to aces; other hands raise the kicker up to one below the top card
+        let class = Class::parse(base).ok_or_else(unknown)?;
+        if class.hi == class.lo {
+            (class.lo..13).map(|r| class.with_ranks(r, r)).collect()
+        } else {
+            (class.lo..class.hi).map(|r| class.with_ranks(class.hi, r)).collect()
+        }
+    } else if let Some((from, to)) = token.split_once('-') {
+        let (from, to) = (Class::parse(from).ok_or_else(unknown)?, Class::parse(to).ok_or_else(unknown)?);
+        let (low, high) = if from.lo <= to.lo { (from.lo, to.lo) } else { (to.lo, from.lo) };
+        if from.suit != to.suit || (from.hi == from.lo) != (to.hi == to.lo) {
+            return Err(format!("'{}' mixes different kinds of hands", token));
+        }
+        if from.hi == from.lo {
+            (low..=high).map(|r| from.with_ranks(r, r)).collect()
+        } else if from.hi == to.hi {
+            (low..=high).map(|r| from.with_ranks(from.hi, r)).collect()
+        } else {
+            return Err(format!("'{}' must keep the top card, as in 'A5s-A2s'", token));
+        }
+    } else {
+        vec![Class::parse(token).ok_or_else(unknown)?]
+    };
+    Ok(classes.into_iter().flat_map(Class::combos).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::range::{format_weighted_range, parse_weighted_range};
+
+    fn count(text: &str) -> usize {
+        import_range(text, None).unwrap().len()
+    }
+
+    #[test]
+    fn test_hand_notation() {
+        assert_eq!(count("AA"), 6);
+        assert_eq!(count("AKs, AKo"), 16);
+        assert_eq!(count("AK"), 16);
+        // QQ, KK and AA
+        assert_eq!(count("QQ+"), 18);
+        // ATs, AJs, AQs, AKs
+        assert_eq!(count("ATs+"), 16);
+        assert_eq!(count("KTo+"), 36);
+        assert_eq!(count("QQ-99"), 24);
+        assert_eq!(count("A2s-A5s"), count("A5s-A2s"));
+        assert_eq!(count("A5s-A2s"), 16);
+        assert_eq!(import_range("AsKs", None).unwrap(), [(vec![Card::from_str("As").unwrap(), Card::from_str("Ks").unwrap()], 1.0)]);
+
+        for bad in ["AX", "AAs", "AKs-KQs", "AKs-AQo", "QQ-AKs", "AsAs", "Z+"] {
+            assert!(import_range(bad, None).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_pio_weights() {
+        let range = import_range("AA,KK:0.5,AKs:0,AhKh:0.25\nQQ-JJ:0.75", Some(RangeFormat::Pio)).unwrap();
+        assert_eq!(range.len(), 6 + 6 + 1 + 12);
+        let weight = |hand: &str| range.iter().find(|(h, _)| format_weighted_range(std::slice::from_ref(h), &[1.0]) == hand).map(|(_, w)| *w);
+        assert_eq!(weight("Kc Kd"), Some(0.5));
+        // The single combo overrides the class that left it out
+        assert_eq!(weight("Ah Kh"), Some(0.25));
+        assert_eq!(weight("As Ks"), None);
+        assert_eq!(weight("Jc Jd"), Some(0.75));
+        assert!(import_range("AA:1.5", Some(RangeFormat::Pio)).is_err());
+
+        // Sessions read the import back unchanged
+        let (hands, weights): (Vec<_>, Vec<_>) = range.into_iter().unzip();
+        let text = format_weighted_range(&hands, &weights);
+        let (parsed_hands, parsed_weights): (Vec<_>, Vec<_>) = parse_weighted_range(&text).into_iter().unzip();
+        assert_eq!((parsed_hands, parsed_weights), (hands, weights));
+    }
+
+    #[test]
+    fn test_gto_plus_weights() {
+        let text = "[50.0]AA, KK[/50.0], AKs,[25]QQ[/25]";
+        assert_eq!(RangeFormat::detect(text), RangeFormat::GtoPlus);
+        let weights: Vec<f32> = import_range(text, None).unwrap().into_iter().map(|(_, w)| w).collect();
+        assert_eq!(weights, [[0.5; 12].as_slice(), &[1.0; 4], &[0.25; 6]].concat());
+
+        for bad in ["[50]AA", "AA[/50]", "[50]AA[/40]", "[x]AA[/x]", "[150]AA[/150]"] {
+            assert!(import_range(bad, Some(RangeFormat::GtoPlus)).is_err(), "{}", bad);
+        }
+    }
+}
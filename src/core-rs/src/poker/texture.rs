@@ -0,0 +1,129 @@
+//! Board texture classes, for grouping boards in aggregate reports.
+
+use serde::Serialize;
+
+use crate::poker::Card;
+
+/// How the board's ranks pair up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pairing {
+    Unpaired,
+    Paired,
+    TwoPair,
+    Trips,
+    FullHouse,
+    Quads,
+}
+
+/// How many cards share the board's most common suit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Suits {
+    /// Every card a different suit.
+    Rainbow,
+    /// Two cards of a suit at most.
+    TwoTone,
+    /// Three of a suit: two more make a flush.
+    FlushPossible,
+    /// Four or more of a suit: one more makes a flush.
+    FourFlush,
+}
+
+/// The texture of a board.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoardTexture {
+    /// Rank of the highest card, e.g. `"A"`.
+    pub high_card: String,
+    pub pairing: Pairing,
+    pub suits: Suits,
+    /// Whether two hole cards can make a straight: some five consecutive
+    /// ranks (the wheel included) hold three of the board's ranks.
+    pub straight_possible: bool,
+}
+
+/// Names of the texture fields boards can be grouped by.
+pub const TEXTURE_FIELDS: [&str; 4] = ["high_card", "pairing", "suits", "straight_possible"];
+
+impl BoardTexture {
+    /// Label of the group the board falls in when grouping by `fields`
+    /// (names from [`TEXTURE_FIELDS`]), e.g. `"paired, two_tone"`.
+    pub fn group(&self, fields: &[String]) -> Result<String, String> {
+        let values = serde_json::to_value(self).expect("BoardTexture is always serializable");
+        let parts = fields.iter()
+            .map(|field| match &values[field.as_str()] {
+                serde_json::Value::String(s) => Ok(s.clone()),
+                serde_json::Value::Bool(true) => Ok(field.clone()),
+                serde_json::Value::Bool(false) => Ok(format!("no_{}", field)),
+                _ => Err(format!("Unknown texture field '{}' (expected one of {:?})", field, TEXTURE_FIELDS)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(parts.join(", "))
+    }
+}
+
+/// Classify `board` (three to five cards).
+pub fn classify(board: &[Card]) -> BoardTexture {
+    let mut rank_counts = [0u8; 13];
+    let mut suit_counts = [0u8; 4];
+    for card in board {
+        rank_counts[card.rank() as usize] += 1;
+        suit_counts[card.suit() as usize] += 1;
+    }
+
+    let groups = |n: u8| rank_counts.iter().filter(|&&c| c == n).count();
+    let pairing = match (groups(4), groups(3), groups(2)) {
+        (1, _, _) => Pairing::Quads,
+        (_, 1, p) if p > 0 => Pairing::FullHouse,
+        (_, t, _) if t > 1 => Pairing::FullHouse,
+        (_, 1, _) => Pairing::Trips,
+        (_, _, p) if p > 1 => Pairing::TwoPair,
+        (_, _, 1) => Pairing::Paired,
+        _ => Pairing::Unpaired,
+    };
+
+    let suits = match suit_counts.iter().max().copied().unwrap_or(0) {
+        0 | 1 => Suits::Rainbow,
+        2 => Suits::TwoTone,
+        3 => Suits::FlushPossible,
+        _ => Suits::FourFlush,
+    };
+
+    // Windows of five ranks from A-5 up to T-A; the ace plays low in the first
+    let present = |rank: i32| rank_counts[rank.rem_euclid(13) as usize] > 0;
+    let straight_possible = (-1..=8).any(|low| (low..low + 5).filter(|&r| present(r)).count() >= 3);
+
+    let high = board.iter().map(|c| c.rank()).max().unwrap_or(0);
+    let high_card = board.iter().find(|c| c.rank() == high).map(|c| c.to_string()[..1].to_string()).unwrap_or_default();
+
+    BoardTexture { high_card, pairing, suits, straight_possible }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texture(s: &str) -> BoardTexture {
+        classify(&s.split_whitespace().filter_map(Card::from_str).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_classify() {
+        let t = texture("Ks Th 7s 4d 2c");
+        assert_eq!((t.high_card.as_str(), t.pairing, t.suits, t.straight_possible), ("K", Pairing::Unpaired, Suits::TwoTone, false));
+
+        assert!(texture("Ah 5d 3c").straight_possible);
+        assert_eq!(texture("Ah 9d 3c").suits, Suits::Rainbow);
+        assert_eq!(texture("Kh 9h 5h 2h").suits, Suits::FourFlush);
+        assert_eq!(texture("Kh 9d 5c 2s 2h").pairing, Pairing::Paired);
+        assert_eq!(texture("Kh Kd 5c 5s 2h").pairing, Pairing::TwoPair);
+        assert_eq!(texture("Kh Kd Kc 5s 2h").pairing, Pairing::Trips);
+        assert_eq!(texture("Kh Kd Kc 5s 5h").pairing, Pairing::FullHouse);
+        assert_eq!(texture("Kh Kd Kc Ks 5h").pairing, Pairing::Quads);
+
+        let fields = vec!["pairing".to_string(), "suits".to_string(), "straight_possible".to_string()];
+        assert_eq!(texture("Jh Td 9h 2c").group(&fields).unwrap(), "unpaired, two_tone, straight_possible");
+        assert_eq!(texture("Kh 9d 5c 2s 2h").group(&fields).unwrap(), "paired, two_tone, no_straight_possible");
+        assert!(texture("Kh 9d 5c").group(&["wetness".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Python bindings (PyO3), enabled with the `python` feature.
+//!
+//! Build and install into the active environment with
+//! `maturin develop --release` (see `pyproject.toml`), then:
+//!
+//! ```python
+//! import numpy as np
+//! import poker_solver_core as psc
+//!
+//! config = {"initial_pot": 100, "stacks": [200, 200], "bet_sizes": [0.5, 1.0], "raise_sizes": [1.0]}
+//! session = psc.SolverSession(config, "Ks Th 7s 4d 2c", ["As Ad", "Qh Jh"], "Kc Kd, 9s 8s")
+//! session.step(500)
+//! print(session.exploitability())
+//!
+//! root = session.get_range_strategy(0)
+//! strategy = np.array(root["strategy"])   # shape (hands, actions)
+//!
+//! blob = session.export_solution()          # bytes, reload with import_solution
+//! session.save_solution("river.psol")       # same format as browser exports
+//! restored = psc.SolverSession.load_solution("river.psol")
+//! psc.evaluate("As Ks Qs Js Ts")            # 1 (royal flush)
+//! psc.solve({"config": config, "board": "Ks Th 7s 4d 2c",
+//!            "range0": "As Ad", "range1": "Kc Kd", "iterations": 200})
+//! ```
+//!
+//! Ranges may be given either as the comma-separated string accepted by the
+//! WASM session or as a list of hand strings.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::poker::equity::{compute_equity_matrix, compute_single_equity};
+use crate::poker::{evaluate_7_cards, get_hand_rank_name, parse_range, Card};
+use crate::solver::{GameConfig, Solution};
+use crate::SolverSession;
+
+fn value_error(msg: String) -> PyErr {
+    PyValueError::new_err(msg)
+}
+
+/// Convert a JSON value into the equivalent Python object via the `json` module.
+fn json_to_py<'py>(py: Python<'py>, value: &serde_json::Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?.call_method1("loads", (value.to_string(),))
+}
+
+fn parse_cards(s: &str) -> Vec<Card> {
+    s.split_whitespace().filter_map(Card::from_str).collect()
+}
+
+/// Accept a range as `"As Ad, Kc Kd"` or `["As Ad", "Kc Kd"]`.
+fn range_arg(range: &Bound<'_, PyAny>) -> PyResult<String> {
+    if let Ok(s) = range.extract::<String>() {
+        return Ok(s);
+    }
+    let hands: Vec<String> = range.extract()?;
+    Ok(hands.join(", "))
+}
+
+fn range_cards(range: &Bound<'_, PyAny>) -> PyResult<Vec<Vec<Card>>> {
+    Ok(parse_range(&range_arg(range)?))
+}
+
+/// River solver session, mirroring the WASM `SolverSession`.
+#[pyclass(name = "SolverSession", module = "poker_solver_core")]
+pub struct PySolverSession {
+    inner: SolverSession,
+}
+
+#[pymethods]
+impl PySolverSession {
+    /// `config` is a dict with the same keys as the WASM config JSON.
+    #[new]
+    fn new(config: &Bound<'_, PyDict>, board: &str, range0: &Bound<'_, PyAny>, range1: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let json = config.py().import("json")?.call_method1("dumps", (config,))?;
+        let config: GameConfig = serde_json::from_str(&json.extract::<String>()?)
+            .map_err(|e| value_error(format!("Invalid config: {}", e)))?;
+
+        let inner = SolverSession::from_config(config, board, &range_arg(range0)?, &range_arg(range1)?)
+            .map_err(value_error)?;
+        Ok(PySolverSession { inner })
+    }
+
+    /// Run `iterations` CFR iterations (the GIL is released meanwhile).
+    fn step(&mut self, py: Python<'_>, iterations: usize) {
+        py.allow_threads(|| self.inner.step(iterations));
+    }
+
+    #[getter]
+    fn iterations(&self) -> usize {
+        self.inner.iterations()
+    }
+
+    #[getter]
+    fn num_nodes(&self) -> usize {
+        self.inner.num_nodes()
+    }
+
+    #[getter]
+    fn num_infosets(&self) -> usize {
+        self.inner.num_infosets()
+    }
+
+    /// Exploitability of the average strategy, in chips.
+    fn exploitability(&self, py: Python<'_>) -> f32 {
+        py.allow_threads(|| self.inner.exploitability())
+    }
+
+    /// Average strategy of the acting player at `node` for their whole range.
+    ///
+    /// Returns `{"node", "player", "actions", "hands", "strategy"}` where
+    /// `strategy[i][a]` is the probability that `hands[i]` takes `actions[a]`
+    /// (rows follow range order, so `numpy.array(...)` gives a hands x actions matrix).
+    #[pyo3(signature = (node = 0))]
+    fn get_range_strategy<'py>(&self, py: Python<'py>, node: usize) -> PyResult<Bound<'py, PyDict>> {
+        let session = &self.inner;
+        let tree_node = session.tree.nodes.get(node).ok_or_else(|| value_error("Invalid node index".to_string()))?;
+        if tree_node.num_actions == 0 || tree_node.infoset_id == u32::MAX {
+            return Err(value_error("Node has no infoset".to_string()));
+        }
+
+        let player = tree_node.player as usize;
+        let range = &session.ranges[player];
+        let hands: Vec<String> = range.iter().map(|h| format!("{} {}", h[0], h[1])).collect();
+        let strategy: Vec<Vec<f32>> = (0..range.len())
+            .map(|h| session.trainer.average_strategy(tree_node.infoset_id as usize, session.trainer_hand(player, h), tree_node.num_actions as usize))
+            .collect();
+
+        let dict = PyDict::new(py);
+        dict.set_item("node", node)?;
+        dict.set_item("player", player)?;
+        dict.set_item("actions", json_to_py(py, &serde_json::Value::from(session.get_actions_at_node(node)))?)?;
+        dict.set_item("hands", hands)?;
+        dict.set_item("strategy", strategy)?;
+        Ok(dict)
+    }
+
+    /// Solution file bytes, interchangeable with browser exports and CLI files.
+    fn export_solution<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.inner.export_solution())
+    }
+
+    /// Browse-only solution bytes: the average strategies without regrets.
+    fn export_browse_solution<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.inner.export_browse_solution())
+    }
+
+    /// Restore state produced by `export_solution` on an identical spot.
+    fn import_solution(&mut self, data: &[u8]) -> PyResult<()> {
+        Solution::from_bytes(data)
+            .and_then(|solution| self.inner.restore_solution(&solution))
+            .map_err(value_error)
+    }
+
+    /// Write the solution file to `path`.
+    fn save_solution(&self, path: std::path::PathBuf) -> PyResult<()> {
+        self.inner.save_solution(path).map_err(value_error)
+    }
+
+    /// Write the browse-only solution file to `path`.
+    fn save_browse_solution(&self, path: std::path::PathBuf) -> PyResult<()> {
+        self.inner.save_browse_solution(path).map_err(value_error)
+    }
+
+    /// Rebuild a session (spot and solver state) from a solution file.
+    #[staticmethod]
+    fn load_solution(path: std::path::PathBuf) -> PyResult<Self> {
+        let inner = SolverSession::load_solution(path).map_err(value_error)?;
+        Ok(PySolverSession { inner })
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SolverSession(nodes={}, infosets={}, iterations={})",
+                self.inner.num_nodes(), self.inner.num_infosets(), self.inner.iterations())
+    }
+}
+
+/// Hand-strength score of 5-7 cards (1 = royal flush, 7462 = worst high card).
+#[pyfunction]
+fn evaluate(cards: &str) -> PyResult<u16> {
+    let cards = parse_cards(cards);
+    if !(5..=7).contains(&cards.len()) {
+        return Err(value_error(format!("Expected 5 to 7 cards, got {}", cards.len())));
+    }
+    Ok(evaluate_7_cards(&cards))
+}
+
+/// Hand category name for a score returned by `evaluate`.
+#[pyfunction]
+fn hand_name(score: u16) -> &'static str {
+    get_hand_rank_name(score)
+}
+
+/// Showdown equity of `hand1` against `hand2` (1.0 win, 0.5 tie, 0.0 loss),
+/// or `None` when the cards conflict.
+#[pyfunction]
+fn equity(board: &str, hand1: &str, hand2: &str) -> Option<f32> {
+    compute_single_equity(&parse_cards(board), &parse_cards(hand1), &parse_cards(hand2))
+}
+
+/// Equity matrix between two ranges as a list of rows (NaN for blocked pairs).
+#[pyfunction]
+fn equity_matrix(board: &str, range0: &Bound<'_, PyAny>, range1: &Bound<'_, PyAny>) -> PyResult<Vec<Vec<f32>>> {
+    let range0 = range_cards(range0)?;
+    let range1 = range_cards(range1)?;
+    let matrix = compute_equity_matrix(&parse_cards(board), &range0, &range1);
+    Ok(matrix.chunks(range1.len().max(1)).map(|row| row.to_vec()).collect())
+}
+
+/// One-shot solve of a spec dict (see `solve` in the WASM API); returns the
+/// result dict, with `ok` false and the problems in `errors` on bad input.
+#[pyfunction]
+fn solve<'py>(spec: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyAny>> {
+    let py = spec.py();
+    let spec_json: String = py.import("json")?.call_method1("dumps", (spec,))?.extract()?;
+    py.import("json")?.call_method1("loads", (crate::solve::solve(&spec_json),))
+}
+
+#[pymodule]
+fn poker_solver_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add_class::<PySolverSession>()?;
+    m.add_function(wrap_pyfunction!(evaluate, m)?)?;
+    m.add_function(wrap_pyfunction!(hand_name, m)?)?;
+    m.add_function(wrap_pyfunction!(equity, m)?)?;
+    m.add_function(wrap_pyfunction!(equity_matrix, m)?)?;
+    m.add_function(wrap_pyfunction!(solve, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::py_run;
+
+    fn with_module(f: impl FnOnce(Python<'_>, Bound<'_, PyModule>)) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let m = PyModule::new(py, "poker_solver_core").unwrap();
+            poker_solver_core(&m).unwrap();
+            f(py, m);
+        });
+    }
+
+    #[test]
+    fn test_session_from_python() {
+        with_module(|py, psc| {
+            py_run!(py, psc, r#"
+config = {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": [], "raise_limit": 0}
+s = psc.SolverSession(config, "Ks Th 7s 4d 2c", ["As Ad", "6h 5h"], "Kc Kd, 9s 8s")
+s.step(20)
+assert s.iterations == 20
+
+root = s.get_range_strategy(0)
+assert root["hands"] == ["As Ad", "6h 5h"]
+assert len(root["strategy"]) == 2
+for row in root["strategy"]:
+    assert len(row) == len(root["actions"])
+    assert abs(sum(row) - 1.0) < 1e-4
+
+blob = s.export_solution()
+assert isinstance(blob, bytes)
+t = psc.SolverSession(config, "Ks Th 7s 4d 2c", "As Ad, 6h 5h", ["Kc Kd", "9s 8s"])
+t.import_solution(blob)
+assert t.get_range_strategy(0) == root
+
+import os, tempfile
+path = os.path.join(tempfile.mkdtemp(), "spot.psol")
+s.save_solution(path)
+u = psc.SolverSession.load_solution(path)
+assert u.iterations == 20 and u.get_range_strategy(0) == root
+"#);
+        });
+    }
+
+    #[test]
+    fn test_module_functions() {
+        with_module(|py, psc| {
+            py_run!(py, psc, r#"
+assert psc.evaluate("As Ks Qs Js Ts") == 1
+assert psc.hand_name(1) == "Royal Flush"
+assert psc.equity("Kh Qd Jc 2s 3h", "As Ks", "Kd 5c") == 1.0
+assert psc.equity("Kh Qd Jc 2s 3h", "As Kh", "Kd 5c") is None
+
+m = psc.equity_matrix("Kh Qd Jc 2s 3h", ["As Ks", "9c 9d"], "Kd 5c, 4c 5d, As 2d")
+assert len(m) == 2 and len(m[0]) == 3
+assert m[0][2] != m[0][2]  # NaN: As is in both hands
+
+r = psc.solve({"config": {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": []},
+               "board": "Ks Th 7s 4d 2c", "range0": "As Ad", "range1": "Kc Kd", "iterations": 10})
+assert r["ok"] and r["iterations"] == 10 and "As Ad" in r["root_strategy"]["strategies"]
+assert psc.solve({"board": ""})["ok"] is False
+"#);
+        });
+    }
+
+    #[test]
+    fn test_invalid_input_raises() {
+        with_module(|py, psc| {
+            py_run!(py, psc, r#"
+try:
+    psc.SolverSession({"initial_pot": 100}, "Ks Th 7s 4d 2c", "As Ad", "Kc Kd")
+    raise AssertionError("expected ValueError")
+except ValueError:
+    pass
+try:
+    psc.evaluate("As Ks")
+    raise AssertionError("expected ValueError")
+except ValueError:
+    pass
+"#);
+        });
+    }
+}
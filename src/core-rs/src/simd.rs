@@ -0,0 +1,355 @@
+//! Vector kernels for the equity matrix, the showdown payoffs and the CFR
+//! traversal and discounting.
+//!
+//! Built with `-C target-feature=+simd128` (`npm run wasm:build:simd`), the
+//! WASM build runs these loops four lanes at a time with `core::arch::wasm32`
+//! intrinsics. Every other build uses the plain loops in [`scalar`], which
+//! also serve as the reference for the differential tests. [`ENABLED`]
+//! (exported to JS as `simd_enabled`) reports which one was compiled in.
+//!
+//! [`equity_row`], [`accumulate_opponent`] and the elementwise CFR kernels
+//! ([`scale`], [`add`], [`mul_add`], [`discount`], [`decay_add`]) perform the
+//! same operations per lane as the scalar code, so both paths agree bit for
+//! bit. [`masked_sums`]
+//! keeps four partial sums, so it matches the scalar path only up to float
+//! rounding.
+
+/// Whether the simd128 kernels were compiled in.
+pub const ENABLED: bool = cfg!(all(target_arch = "wasm32", target_feature = "simd128"));
+
+/// Showdown result of a hand with `score` and card `mask` against each
+/// opponent hand: 1.0 win, 0.5 tie, 0.0 loss (lower scores win), or NaN when
+/// the card masks overlap. Give board-blocked opponents a mask of `u64::MAX`.
+#[inline]
+pub fn equity_row(score: u16, mask: u64, scores: &[u16], masks: &[u64], out: &mut [f32]) {
+    debug_assert!(scores.len() == out.len() && masks.len() == out.len());
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    wasm::equity_row(score, mask, scores, masks, out);
+
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    scalar::equity_row(score, mask, scores, masks, out);
+}
+
+/// `(Σ row[j] * weights[j], Σ weights[j])` over the entries where `row[j]`
+/// is not NaN: a hand's reach-weighted equity and the weight it is taken over.
+#[inline]
+pub fn masked_sums(row: &[f32], weights: &[f32]) -> (f32, f32) {
+    debug_assert_eq!(row.len(), weights.len());
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        wasm::masked_sums(row, weights)
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    {
+        scalar::masked_sums(row, weights)
+    }
+}
+
+/// For every non-NaN `row[j]`: `equity[j] += (1 - row[j]) * weight` and
+/// `total[j] += weight`. Walking the matrix row by row with this gives the
+/// second player's showdown sums without strided column reads.
+#[inline]
+pub fn accumulate_opponent(row: &[f32], weight: f32, equity: &mut [f32], total: &mut [f32]) {
+    debug_assert!(equity.len() == row.len() && total.len() == row.len());
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    wasm::accumulate_opponent(row, weight, equity, total);
+
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    scalar::accumulate_opponent(row, weight, equity, total);
+}
+
+/// `values[i] *= factors[i]`: reach probabilities after an action, given
+/// each hand's probability of taking it.
+#[inline]
+pub fn scale(values: &mut [f32], factors: &[f32]) {
+    debug_assert_eq!(values.len(), factors.len());
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    wasm::scale(values, factors);
+
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    scalar::scale(values, factors);
+}
+
+/// `acc[i] += values[i]`: the opponent's utilities summed over actions.
+#[inline]
+pub fn add(acc: &mut [f32], values: &[f32]) {
+    debug_assert_eq!(acc.len(), values.len());
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    wasm::add(acc, values);
+
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    scalar::add(acc, values);
+}
+
+/// `acc[i] += weights[i] * values[i]`: the acting player's utilities, each
+/// action's weighted by the strategy.
+#[inline]
+pub fn mul_add(acc: &mut [f32], weights: &[f32], values: &[f32]) {
+    debug_assert!(acc.len() == weights.len() && acc.len() == values.len());
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    wasm::mul_add(acc, weights, values);
+
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    scalar::mul_add(acc, weights, values);
+}
+
+/// `values[i] *= positive` where `values[i] > 0`, else `*= negative`: the
+/// DCFR discount of accumulated regrets.
+#[inline]
+pub fn discount(values: &mut [f32], positive: f32, negative: f32) {
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    wasm::discount(values, positive, negative);
+
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    scalar::discount(values, positive, negative);
+}
+
+/// `acc[i] = acc[i] * decay + values[i] * weight`: the DCFR update of the
+/// strategy sums with the current strategy.
+#[inline]
+pub fn decay_add(acc: &mut [f32], decay: f32, values: &[f32], weight: f32) {
+    debug_assert_eq!(acc.len(), values.len());
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    wasm::decay_add(acc, decay, values, weight);
+
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    scalar::decay_add(acc, decay, values, weight);
+}
+
+/// Reference implementations, also used for the tails of the SIMD loops.
+pub mod scalar {
+    #[inline]
+    pub fn equity_row(score: u16, mask: u64, scores: &[u16], masks: &[u64], out: &mut [f32]) {
+        for ((cell, &other), &other_mask) in out.iter_mut().zip(scores).zip(masks) {
+            *cell = if mask & other_mask != 0 {
+                f32::NAN
+            } else if score < other {
+                1.0
+            } else if score > other {
+                0.0
+            } else {
+                0.5
+            };
+        }
+    }
+
+    #[inline]
+    pub fn masked_sums(row: &[f32], weights: &[f32]) -> (f32, f32) {
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+        for (&eq, &w) in row.iter().zip(weights) {
+            if !eq.is_nan() {
+                weighted += eq * w;
+                total += w;
+            }
+        }
+        (weighted, total)
+    }
+
+    #[inline]
+    pub fn accumulate_opponent(row: &[f32], weight: f32, equity: &mut [f32], total: &mut [f32]) {
+        for ((&eq, e), t) in row.iter().zip(equity.iter_mut()).zip(total.iter_mut()) {
+            if !eq.is_nan() {
+                *e += (1.0 - eq) * weight;
+                *t += weight;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn scale(values: &mut [f32], factors: &[f32]) {
+        for (v, &f) in values.iter_mut().zip(factors) {
+            *v *= f;
+        }
+    }
+
+    #[inline]
+    pub fn add(acc: &mut [f32], values: &[f32]) {
+        for (a, &v) in acc.iter_mut().zip(values) {
+            *a += v;
+        }
+    }
+
+    #[inline]
+    pub fn mul_add(acc: &mut [f32], weights: &[f32], values: &[f32]) {
+        for ((a, &w), &v) in acc.iter_mut().zip(weights).zip(values) {
+            *a += w * v;
+        }
+    }
+
+    #[inline]
+    pub fn discount(values: &mut [f32], positive: f32, negative: f32) {
+        for v in values {
+            *v *= if *v > 0.0 { positive } else { negative };
+        }
+    }
+
+    #[inline]
+    pub fn decay_add(acc: &mut [f32], decay: f32, values: &[f32], weight: f32) {
+        for (a, &v) in acc.iter_mut().zip(values) {
+            *a = *a * decay + v * weight;
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm {
+    use core::arch::wasm32::*;
+
+    const LANES: usize = 4;
+
+    pub fn equity_row(score: u16, mask: u64, scores: &[u16], masks: &[u64], out: &mut [f32]) {
+        let n = out.len();
+        let body = n - n % LANES;
+        let score_v = u32x4_splat(score as u32);
+        let mask_v = u64x2_splat(mask);
+        let (win, tie, loss, nan) = (f32x4_splat(1.0), f32x4_splat(0.5), f32x4_splat(0.0), f32x4_splat(f32::NAN));
+
+        for i in (0..body).step_by(LANES) {
+            // SAFETY: i + LANES <= n for every slice, and wasm loads/stores may be unaligned.
+            unsafe {
+                let other = u32x4_load_extend_u16x4(scores.as_ptr().add(i));
+                let lo = v128_load(masks.as_ptr().add(i) as *const v128);
+                let hi = v128_load(masks.as_ptr().add(i + 2) as *const v128);
+                // Per-u64 "no shared card" flags, narrowed to one u32 lane per hand.
+                let free_lo = i64x2_eq(v128_and(lo, mask_v), i64x2_splat(0));
+                let free_hi = i64x2_eq(v128_and(hi, mask_v), i64x2_splat(0));
+                let free = i32x4_shuffle::<0, 2, 4, 6>(free_lo, free_hi);
+
+                let result = v128_bitselect(win, v128_bitselect(loss, tie, u32x4_gt(score_v, other)), u32x4_lt(score_v, other));
+                v128_store(out.as_mut_ptr().add(i) as *mut v128, v128_bitselect(result, nan, free));
+            }
+        }
+        super::scalar::equity_row(score, mask, &scores[body..], &masks[body..], &mut out[body..]);
+    }
+
+    pub fn masked_sums(row: &[f32], weights: &[f32]) -> (f32, f32) {
+        let n = row.len();
+        let body = n - n % LANES;
+        let mut weighted_v = f32x4_splat(0.0);
+        let mut total_v = f32x4_splat(0.0);
+
+        for i in (0..body).step_by(LANES) {
+            // SAFETY: i + LANES <= n for both slices.
+            let (eq, w) = unsafe {
+                (v128_load(row.as_ptr().add(i) as *const v128), v128_load(weights.as_ptr().add(i) as *const v128))
+            };
+            let valid = f32x4_eq(eq, eq); // false for NaN lanes
+            let w = v128_and(w, valid);
+            weighted_v = f32x4_add(weighted_v, f32x4_mul(v128_and(eq, valid), w));
+            total_v = f32x4_add(total_v, w);
+        }
+
+        let (tail_weighted, tail_total) = super::scalar::masked_sums(&row[body..], &weights[body..]);
+        (horizontal_sum(weighted_v) + tail_weighted, horizontal_sum(total_v) + tail_total)
+    }
+
+    pub fn accumulate_opponent(row: &[f32], weight: f32, equity: &mut [f32], total: &mut [f32]) {
+        let n = row.len();
+        let body = n - n % LANES;
+        let weight_v = f32x4_splat(weight);
+        let one = f32x4_splat(1.0);
+
+        for i in (0..body).step_by(LANES) {
+            // SAFETY: i + LANES <= n for all three slices.
+            unsafe {
+                let eq = v128_load(row.as_ptr().add(i) as *const v128);
+                let valid = f32x4_eq(eq, eq);
+                let w = v128_and(weight_v, valid);
+                let e_ptr = equity.as_mut_ptr().add(i) as *mut v128;
+                let t_ptr = total.as_mut_ptr().add(i) as *mut v128;
+                let gain = f32x4_mul(f32x4_sub(one, v128_and(eq, valid)), w);
+                v128_store(e_ptr, f32x4_add(v128_load(e_ptr), gain));
+                v128_store(t_ptr, f32x4_add(v128_load(t_ptr), w));
+            }
+        }
+        super::scalar::accumulate_opponent(&row[body..], weight, &mut equity[body..], &mut total[body..]);
+    }
+
+    pub fn scale(values: &mut [f32], factors: &[f32]) {
+        let n = values.len();
+        let body = n - n % LANES;
+        for i in (0..body).step_by(LANES) {
+            // SAFETY: i + LANES <= n for both slices.
+            unsafe {
+                let v_ptr = values.as_mut_ptr().add(i) as *mut v128;
+                let f = v128_load(factors.as_ptr().add(i) as *const v128);
+                v128_store(v_ptr, f32x4_mul(v128_load(v_ptr), f));
+            }
+        }
+        super::scalar::scale(&mut values[body..], &factors[body..]);
+    }
+
+    pub fn add(acc: &mut [f32], values: &[f32]) {
+        let n = acc.len();
+        let body = n - n % LANES;
+        for i in (0..body).step_by(LANES) {
+            // SAFETY: i + LANES <= n for both slices.
+            unsafe {
+                let a_ptr = acc.as_mut_ptr().add(i) as *mut v128;
+                let v = v128_load(values.as_ptr().add(i) as *const v128);
+                v128_store(a_ptr, f32x4_add(v128_load(a_ptr), v));
+            }
+        }
+        super::scalar::add(&mut acc[body..], &values[body..]);
+    }
+
+    pub fn mul_add(acc: &mut [f32], weights: &[f32], values: &[f32]) {
+        let n = acc.len();
+        let body = n - n % LANES;
+        for i in (0..body).step_by(LANES) {
+            // SAFETY: i + LANES <= n for all three slices.
+            unsafe {
+                let a_ptr = acc.as_mut_ptr().add(i) as *mut v128;
+                let w = v128_load(weights.as_ptr().add(i) as *const v128);
+                let v = v128_load(values.as_ptr().add(i) as *const v128);
+                v128_store(a_ptr, f32x4_add(v128_load(a_ptr), f32x4_mul(w, v)));
+            }
+        }
+        super::scalar::mul_add(&mut acc[body..], &weights[body..], &values[body..]);
+    }
+
+    pub fn discount(values: &mut [f32], positive: f32, negative: f32) {
+        let n = values.len();
+        let body = n - n % LANES;
+        let (positive_v, negative_v, zero) = (f32x4_splat(positive), f32x4_splat(negative), f32x4_splat(0.0));
+        for i in (0..body).step_by(LANES) {
+            // SAFETY: i + LANES <= n.
+            unsafe {
+                let v_ptr = values.as_mut_ptr().add(i) as *mut v128;
+                let v = v128_load(v_ptr);
+                let factor = v128_bitselect(positive_v, negative_v, f32x4_gt(v, zero));
+                v128_store(v_ptr, f32x4_mul(v, factor));
+            }
+        }
+        super::scalar::discount(&mut values[body..], positive, negative);
+    }
+
+    pub fn decay_add(acc: &mut [f32], decay: f32, values: &[f32], weight: f32) {
+        let n = acc.len();
+        let body = n - n % LANES;
+        let (decay_v, weight_v) = (f32x4_splat(decay), f32x4_splat(weight));
+        for i in (0..body).step_by(LANES) {
+            // SAFETY: i + LANES <= n for both slices.
+            unsafe {
+                let a_ptr = acc.as_mut_ptr().add(i) as *mut v128;
+                let v = v128_load(values.as_ptr().add(i) as *const v128);
+                v128_store(a_ptr, f32x4_add(f32x4_mul(v128_load(a_ptr), decay_v), f32x4_mul(v, weight_v)));
+            }
+        }
+        super::scalar::decay_add(&mut acc[body..], decay, &values[body..], weight);
+    }
+
+    #[inline]
+    fn horizontal_sum(v: v128) -> f32 {
+        (f32x4_extract_lane::<0>(v) + f32x4_extract_lane::<1>(v)) + (f32x4_extract_lane::<2>(v) + f32x4_extract_lane::<3>(v))
+    }
+}
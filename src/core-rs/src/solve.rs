@@ -0,0 +1,253 @@
+//! One-shot solving: a JSON spec in, the requested artifacts out.
+//!
+//! [`solve`] (exported to JS as `solve`) is for integrations that do not
+//! want to manage a `SolverSession`: it builds the session, drives
+//! [`SolverSession::solve_until`] and serializes what the spec asks for.
+//!
+//! Spec format (the CLI reads the same document and ignores `outputs`):
+//! ```json
+//! {
+//!   "config": { "initial_pot": 100, "stacks": [200, 200], "bet_sizes": [0.5], "raise_sizes": [1.0] },
+//!   "board": "Ks Th 7s 4d 2c",
+//!   "range0": "As Ad, Qc Qd",
+//!   "range1": "Kc Kd, Jh Jc",
+//!   "iterations": 1000,
+//!   "target_exploitability": 0.5,
+//!   "outputs": ["root_strategy", "exploitability", "export"]
+//! }
+//! ```
+//! `target_exploitability` is in percent of the starting pot; when set, the
+//! solve stops as soon as it is reached (`iterations` is then the cap).
+//! `outputs` defaults to `["root_strategy", "exploitability"]`; `export` is
+//! the solution file, base64 encoded. A 4-card board solves from the turn
+//! (`river_bet_sizes`, `river_raise_sizes` and `river_cards` in the config
+//! set up the river).
+//!
+//! `exploitability` also reports `gain_pct`: what each player would win by
+//! best-responding, in percent of the starting pot.
+//!
+//! `runouts` lists the runout report (see `SolverSession::get_runout_report`)
+//! of every line that deals the river, each with its `line`: how the
+//! strategy and values shift per river card. It is empty on river boards.
+//!
+//! The result is `{"ok": true, "iterations": ..., <outputs>}`, or
+//! `{"ok": false, "errors": [...]}` listing every problem found in the spec.
+
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::poker::{parse_range, Card};
+use crate::solver::{GameConfig, NodeType, RiverCards};
+use crate::SolverSession;
+
+/// Iteration count when the spec sets neither iterations nor a target.
+pub const DEFAULT_ITERATIONS: usize = 1000;
+
+/// Iteration cap when solving to a target exploitability without an explicit limit.
+pub const DEFAULT_MAX_ITERATIONS: usize = 10_000;
+
+/// Iterations between exploitability checks when solving to a target.
+pub const CHECK_EVERY: usize = 50;
+
+/// Artifacts [`solve`] can return.
+pub const OUTPUTS: [&str; 4] = ["root_strategy", "exploitability", "export", "runouts"];
+
+/// A complete solve request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SolveSpec {
+    pub config: GameConfig,
+    pub board: String,
+    pub range0: String,
+    pub range1: String,
+    #[serde(default)]
+    pub iterations: Option<usize>,
+    /// Stop once exploitability drops below this (% of the starting pot).
+    #[serde(default)]
+    pub target_exploitability: Option<f32>,
+    /// Names from [`OUTPUTS`]; `None` selects the root strategy and exploitability.
+    #[serde(default)]
+    pub outputs: Option<Vec<String>>,
+}
+
+impl SolveSpec {
+    /// Iteration cap implied by `iterations` and `target_exploitability`.
+    pub fn max_iterations(&self) -> usize {
+        self.iterations.unwrap_or(match self.target_exploitability {
+            Some(_) => DEFAULT_MAX_ITERATIONS,
+            None => DEFAULT_ITERATIONS,
+        })
+    }
+
+    /// Every problem with the spec, rather than just the first one.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        let tokens: Vec<&str> = self.board.split_whitespace().collect();
+        let bad: Vec<&str> = tokens.iter().copied().filter(|t| Card::from_str(t).is_none()).collect();
+        if !bad.is_empty() {
+            errors.push(format!("board: invalid cards {:?}", bad));
+        } else if tokens.len() != 4 && tokens.len() != 5 {
+            errors.push(format!("board: expected 4 (turn) or 5 (river) cards, got {}", tokens.len()));
+        }
+        for (name, range) in [("range0", &self.range0), ("range1", &self.range1)] {
+            if parse_range(range).is_empty() {
+                errors.push(format!("{}: no valid hands", name));
+            }
+        }
+        if let Err(e) = self.config.validate() {
+            errors.push(format!("config: {}", e));
+        }
+        if self.config.chip_scale == 0 {
+            errors.push("config.chip_scale: must be at least 1".to_string());
+        }
+        if let RiverCards::Sampled { count: 0, .. } = self.config.river_cards {
+            errors.push("config.river_cards: must sample at least one river".to_string());
+        }
+        if self.iterations == Some(0) {
+            errors.push("iterations: must be positive".to_string());
+        }
+        if let Some(target) = self.target_exploitability
+            && target <= 0.0
+        {
+            errors.push(format!("target_exploitability: must be positive, got {}", target));
+        }
+        for name in self.outputs.iter().flatten() {
+            if !OUTPUTS.contains(&name.as_str()) {
+                errors.push(format!("outputs: unknown output '{}' (expected one of {:?})", name, OUTPUTS));
+            }
+        }
+        errors
+    }
+
+    fn wants(&self, output: &str) -> bool {
+        match &self.outputs {
+            Some(names) => names.iter().any(|n| n == output),
+            None => output != "export" && output != "runouts",
+        }
+    }
+}
+
+/// Run a whole solve from a JSON spec and return the JSON result document.
+/// Never fails: problems are reported in the document's `errors`.
+pub fn solve(spec_json: &str) -> String {
+    let result = serde_json::from_str::<SolveSpec>(spec_json)
+        .map_err(|e| vec![format!("spec: {}", e)])
+        .and_then(|spec| solve_spec(&spec));
+
+    match result {
+        Ok(document) => document,
+        Err(errors) => json!({ "ok": false, "errors": errors }),
+    }
+    .to_string()
+}
+
+/// [`solve`] for an already parsed spec.
+pub fn solve_spec(spec: &SolveSpec) -> Result<serde_json::Value, Vec<String>> {
+    let errors = spec.validate();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut session = SolverSession::from_config(spec.config.clone(), &spec.board, &spec.range0, &spec.range1)
+        .map_err(|e| vec![e])?;
+    let exploitability_pct = session.solve_until(spec.max_iterations(), spec.target_exploitability, CHECK_EVERY);
+
+    let mut document = json!({ "ok": true, "iterations": session.iterations() });
+    if spec.wants("root_strategy") {
+        document["root_strategy"] = session.node_strategy_table(0).map_err(|e| vec![e])?;
+    }
+    if spec.wants("exploitability") {
+        document["exploitability"] = json!(exploitability_pct / 100.0 * session.initial_pot());
+        document["exploitability_pct"] = json!(exploitability_pct);
+        if let Some(point) = session.convergence_history().last() {
+            document["gain_pct"] = json!(point.gain_pct);
+        }
+    }
+    if spec.wants("export") {
+        let bytes = session.to_solution().to_bytes();
+        document["export"] = json!(base64::engine::general_purpose::STANDARD.encode(bytes));
+    }
+    if spec.wants("runouts") {
+        let chance_nodes = session.tree.nodes.iter().enumerate().filter(|(_, n)| n.node_type == NodeType::Chance);
+        let reports = chance_nodes
+            .map(|(node_idx, _)| {
+                let line: Vec<String> = session.path_to(node_idx)[1..].iter().map(|&n| session.action_label(n)).collect();
+                let mut report = session.runout_report(&line)?;
+                report["line"] = json!(line);
+                Ok(report)
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(|e| vec![e])?;
+        document["runouts"] = json!(reports);
+    }
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"{
+        "config": {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": [], "raise_limit": 0},
+        "board": "Ks Th 7s 4d 2c",
+        "range0": "As Ad, 6h 5h",
+        "range1": "Kc Kd, 9s 8s",
+        "iterations": 40
+    }"#;
+
+    #[test]
+    fn test_solve_default_outputs() {
+        let result: serde_json::Value = serde_json::from_str(&solve(SPEC)).unwrap();
+        assert_eq!(result["ok"], true);
+        assert_eq!(result["iterations"], 40);
+        assert_eq!(result["root_strategy"]["strategies"].as_object().unwrap().len(), 2);
+        assert!(result["exploitability"].as_f64().unwrap() >= 0.0);
+        assert_eq!(result["gain_pct"].as_array().unwrap().len(), 2);
+        assert!(result.get("export").is_none());
+        assert!(result.get("runouts").is_none());
+    }
+
+    #[test]
+    fn test_solve_runouts() {
+        let spec = r#"{
+            "config": {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": [], "raise_limit": 0},
+            "board": "Ks Th 7s 4d",
+            "range0": "As Ad, 6h 5h",
+            "range1": "Kc Kd, 9s 8s",
+            "iterations": 20,
+            "outputs": ["runouts"]
+        }"#;
+        let result: serde_json::Value = serde_json::from_str(&solve(spec)).unwrap();
+        let runouts = result["runouts"].as_array().unwrap();
+        // Check-check, bet-call and check-bet-call deal the river
+        assert_eq!(runouts.len(), 3);
+        let check_check = runouts.iter().find(|r| r["line"] == json!(["check", "check"])).unwrap();
+        assert_eq!(check_check["rivers"].as_array().unwrap().len(), 48);
+        assert!(result.get("root_strategy").is_none());
+
+        let river = solve(&spec.replace("7s 4d", "7s 4d 2c"));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&river).unwrap()["runouts"], json!([]));
+    }
+
+    #[test]
+    fn test_solve_reports_every_error() {
+        let spec = r#"{
+            "config": {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": [], "chip_scale": 0},
+            "board": "Ks Th 7s",
+            "range0": "As Ad",
+            "range1": "Kc",
+            "iterations": 0,
+            "outputs": ["root_strategy", "grid"]
+        }"#;
+        let result: serde_json::Value = serde_json::from_str(&solve(spec)).unwrap();
+        assert_eq!(result["ok"], false);
+        let errors: Vec<&str> = result["errors"].as_array().unwrap().iter().map(|e| e.as_str().unwrap()).collect();
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert!(errors[0].starts_with("board:") && errors[1].starts_with("range1:"));
+        assert!(errors[4].contains("'grid'"));
+
+        let result: serde_json::Value = serde_json::from_str(&solve("{")).unwrap();
+        assert!(result["errors"][0].as_str().unwrap().starts_with("spec:"));
+    }
+}
@@ -0,0 +1,253 @@
+//! Information abstraction: solving on hand buckets instead of combos.
+//!
+//! Each player's combos are sorted by showdown equity against the other
+//! range and cut into buckets of (nearly) equal size. The trainer then
+//! plays buckets as if they were hands: a bucket's range weight is the sum
+//! of its combos' weights, the bucket-vs-bucket equity is the weighted
+//! average over the possible matchups of their combos, and the share of
+//! their combo pairs that do not conflict becomes a matchup weight (see
+//! `DCFRTrainer::train_weighted`). Combos of one bucket are assumed to
+//! play alike, which is where the abstraction loses accuracy.
+//!
+//! The same machinery merges suit-isomorphic combos (see [`suit_classes`]).
+//! Those do play alike at equilibrium, so that reduction is lossless.
+
+use std::collections::HashMap;
+
+use crate::poker::isomorphism::{permuted_mask, SuitPermutation};
+use crate::poker::{range_equities, Card};
+use crate::solver::arena::{GameTree, NodeType};
+use crate::solver::dcfr::DCFRTrainer;
+
+/// Bucketing of both ranges, with the bucket-level game it induces.
+#[derive(Debug, Clone)]
+pub struct Abstraction {
+    /// Bucket of every combo, per player.
+    pub buckets: [Vec<usize>; 2],
+    /// Number of buckets per player.
+    pub num_buckets: [usize; 2],
+    /// Each combo's equity against the other range (NaN without a matchup).
+    pub equities: [Vec<f32>; 2],
+    /// Bucket-vs-bucket equities, one block per block of the combo matrix.
+    pub equity_matrix: Vec<f32>,
+    /// Share of each pair of buckets' combined weight in combo pairs that
+    /// do not conflict, laid out like `equity_matrix`.
+    pub matchup_weights: Vec<f32>,
+    /// Range weight of every bucket.
+    pub reach: [Vec<f32>; 2],
+}
+
+impl Abstraction {
+    /// Bucket both ranges into at most `buckets` buckets each.
+    ///
+    /// `equity_matrix` is the session's combo matrix (every block), and
+    /// `showdown` the blocks that decide the bucketing equities: the board
+    /// itself on the river, every dealt river on the turn. Combos without a
+    /// possible matchup keep a bucket but add no weight to it.
+    pub fn new(buckets: usize, equity_matrix: &[f32], showdown: &[f32], reach: &[Vec<f32>; 2]) -> Self {
+        let equities = range_equities(showdown, [&reach[0], &reach[1]]);
+        let combo_buckets = [0, 1].map(|p| by_equity(&equities[p], buckets));
+        Self::with_buckets(combo_buckets, equities, equity_matrix, reach)
+    }
+
+    /// Play the given grouping of combos (bucket indices from 0 with none
+    /// left empty), e.g. [`suit_classes`].
+    pub fn from_buckets(buckets: [Vec<usize>; 2], equity_matrix: &[f32], showdown: &[f32], reach: &[Vec<f32>; 2]) -> Self {
+        let equities = range_equities(showdown, [&reach[0], &reach[1]]);
+        Self::with_buckets(buckets, equities, equity_matrix, reach)
+    }
+
+    fn with_buckets(combo_buckets: [Vec<usize>; 2], equities: [Vec<f32>; 2], equity_matrix: &[f32], reach: &[Vec<f32>; 2]) -> Self {
+        let num_buckets = [0, 1].map(|p| combo_buckets[p].iter().max().map_or(1, |&b| b + 1));
+        let weights: [Vec<f32>; 2] = [0, 1].map(|p| {
+            reach[p].iter().zip(&equities[p]).map(|(&r, e)| if e.is_nan() { 0.0 } else { r }).collect()
+        });
+
+        let mut bucket_reach = [vec![0.0; num_buckets[0]], vec![0.0; num_buckets[1]]];
+        for ((bucket_reach, buckets), weights) in bucket_reach.iter_mut().zip(&combo_buckets).zip(&weights) {
+            for (&b, &w) in buckets.iter().zip(weights) {
+                bucket_reach[b] += w;
+            }
+        }
+
+        let (bucket_equities, matchup_weights) = bucket_matrix(equity_matrix, &combo_buckets, num_buckets, &weights);
+        Self {
+            equity_matrix: bucket_equities,
+            matchup_weights,
+            buckets: combo_buckets,
+            num_buckets,
+            equities,
+            reach: bucket_reach,
+        }
+    }
+
+    /// Combo-level copy of a bucket-level `trainer`: every combo plays its
+    /// bucket's average strategy. Only the average strategy is carried over,
+    /// which is what best-response evaluation needs.
+    pub fn expand(&self, tree: &GameTree, trainer: &DCFRTrainer) -> DCFRTrainer {
+        let num_combos = [self.buckets[0].len(), self.buckets[1].len()];
+        let mut combos = DCFRTrainer::new(tree.infoset_map.len(), trainer.max_actions(), num_combos);
+        combos.resume_at(trainer.iterations);
+        for node in &tree.nodes {
+            if node.node_type != NodeType::Action || node.infoset_id == u32::MAX {
+                continue;
+            }
+            let (infoset, num_actions) = (node.infoset_id as usize, node.num_actions as usize);
+            for (h, &b) in self.buckets[node.player as usize].iter().enumerate() {
+                combos.set_average_strategy(infoset, h, &trainer.average_strategy(infoset, b, num_actions));
+            }
+        }
+        combos
+    }
+}
+
+/// Bucket of each combo when the combos are cut into `buckets` groups of
+/// equal count by ascending equity. Combos with a NaN equity sort last.
+/// With at least as many buckets as combos, every combo is its own bucket.
+pub fn by_equity(equities: &[f32], buckets: usize) -> Vec<usize> {
+    let n = equities.len();
+    let buckets = buckets.min(n).max(1);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| {
+        let (ea, eb) = (equities[a], equities[b]);
+        ea.is_nan().cmp(&eb.is_nan()).then(ea.total_cmp(&eb))
+    });
+
+    let mut result = vec![0; n];
+    for (rank, &h) in order.iter().enumerate() {
+        result[h] = rank * buckets / n;
+    }
+    result
+}
+
+/// Classes of suit-isomorphic combos under `symmetries` (see
+/// [`crate::poker::isomorphism::board_symmetries`]), numbered in range
+/// order. Only the symmetries that also map both ranges onto themselves,
+/// weights included, are used, so merged combos are strategically
+/// identical.
+pub fn suit_classes(symmetries: &[SuitPermutation], ranges: &[Vec<Vec<Card>>; 2], reach: &[Vec<f32>; 2]) -> [Vec<usize>; 2] {
+    let identity = [0, 1, 2, 3];
+    let index: [HashMap<u64, usize>; 2] =
+        [0, 1].map(|p| ranges[p].iter().enumerate().map(|(h, hand)| (permuted_mask(hand, &identity), h)).collect());
+    let symmetries: Vec<&SuitPermutation> = symmetries
+        .iter()
+        .filter(|perm| {
+            (0..2).all(|p| {
+                ranges[p].iter().zip(&reach[p]).all(|(hand, &w)| {
+                    index[p].get(&permuted_mask(hand, perm)).is_some_and(|&image| (reach[p][image] - w).abs() <= 1e-6 * w.abs().max(1.0))
+                })
+            })
+        })
+        .collect();
+
+    [0, 1].map(|p| {
+        let mut classes: HashMap<u64, usize> = HashMap::new();
+        ranges[p]
+            .iter()
+            .map(|hand| {
+                let canonical = symmetries.iter().map(|perm| permuted_mask(hand, perm)).min().unwrap_or(0);
+                let next = classes.len();
+                *classes.entry(canonical).or_insert(next)
+            })
+            .collect()
+    })
+}
+
+/// Bucket-vs-bucket equity matrix and matchup weights: per block of
+/// `matrix`, the average of the possible matchups (non-NaN cells) between
+/// two buckets' combos, weighted by the product of the combo weights, and
+/// the share of the buckets' combined weight those matchups carry. Pairs of
+/// buckets without a weighted matchup get NaN and weight 0.
+pub fn bucket_matrix(
+    matrix: &[f32],
+    buckets: &[Vec<usize>; 2],
+    num_buckets: [usize; 2],
+    weights: &[Vec<f32>; 2],
+) -> (Vec<f32>, Vec<f32>) {
+    let (n0, n1) = (buckets[0].len(), buckets[1].len());
+    let (b0, b1) = (num_buckets[0], num_buckets[1]);
+    let capacity = matrix.len() / (n0 * n1).max(1) * b0 * b1;
+    let (mut result, mut shares) = (Vec::with_capacity(capacity), Vec::with_capacity(capacity));
+
+    let mut bucket_weight = [vec![0.0f64; b0], vec![0.0f64; b1]];
+    for ((bucket_weight, buckets), weights) in bucket_weight.iter_mut().zip(buckets).zip(weights) {
+        for (&b, &w) in buckets.iter().zip(weights) {
+            bucket_weight[b] += w.max(0.0) as f64;
+        }
+    }
+
+    for block in matrix.chunks_exact((n0 * n1).max(1)) {
+        let mut equity = vec![0.0f64; b0 * b1];
+        let mut total = vec![0.0f64; b0 * b1];
+        for (h0, row) in block.chunks_exact(n1).enumerate() {
+            let w0 = weights[0][h0] as f64;
+            if w0 <= 0.0 {
+                continue;
+            }
+            let base = buckets[0][h0] * b1;
+            for ((&cell, &bucket1), &w1) in row.iter().zip(&buckets[1]).zip(&weights[1]) {
+                if cell.is_nan() || w1 <= 0.0 {
+                    continue;
+                }
+                let w = w0 * w1 as f64;
+                equity[base + bucket1] += w * cell as f64;
+                total[base + bucket1] += w;
+            }
+        }
+        result.extend(equity.iter().zip(&total).map(|(&e, &t)| if t > 0.0 { (e / t) as f32 } else { f32::NAN }));
+        shares.extend(total.iter().enumerate().map(|(i, &t)| {
+            let full = bucket_weight[0][i / b1] * bucket_weight[1][i % b1];
+            if t > 0.0 { (t / full) as f32 } else { 0.0 }
+        }));
+    }
+    (result, shares)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_by_equity() {
+        let equities = [0.9, 0.1, f32::NAN, 0.5, 0.3, 0.7];
+        assert_eq!(by_equity(&equities, 2), vec![1, 0, 1, 0, 0, 1]);
+        assert_eq!(by_equity(&equities, 3), vec![2, 0, 2, 1, 0, 1]);
+        // One bucket per combo, ordered by equity
+        assert_eq!(by_equity(&equities, 10), vec![4, 0, 5, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_suit_classes() {
+        let ranges = [
+            crate::poker::parse_range("Ac Kc, Ad Kd, Ah Kh, Ac Kd, Ad Kc"),
+            crate::poker::parse_range("Qc Qd, Jc Jd"),
+        ];
+        let swap = [[0, 1, 2, 3], [1, 0, 2, 3]];
+        let reach = [vec![1.0; 5], vec![1.0; 2]];
+        assert_eq!(suit_classes(&swap, &ranges, &reach), [vec![0, 0, 1, 2, 2], vec![0, 1]]);
+
+        // Weights that differ between the suits break the symmetry
+        let uneven = [vec![1.0, 0.5, 1.0, 1.0, 1.0], vec![1.0; 2]];
+        assert_eq!(suit_classes(&swap, &ranges, &uneven), [vec![0, 1, 2, 3, 4], vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_bucket_matrix_averages_possible_matchups() {
+        // 2x2 combos into one bucket each; one matchup is blocked
+        let matrix = [1.0, 0.0, f32::NAN, 0.5];
+        let buckets = [vec![0, 0], vec![0, 0]];
+        let weights = [vec![1.0, 2.0], vec![1.0, 3.0]];
+        let (result, shares) = bucket_matrix(&matrix, &buckets, [1, 1], &weights);
+        let expected = (1.0 * 1.0 + 0.0 * 3.0 + 0.5 * 6.0) / (1.0 + 3.0 + 6.0);
+        assert!((result[0] - expected).abs() < 1e-6);
+        // The blocked pair carries 2 of the 3 x 4 total weight
+        assert!((shares[0] - 10.0 / 12.0).abs() < 1e-6);
+
+        // Exact buckets reproduce the matrix
+        let (exact, shares) = bucket_matrix(&matrix, &[vec![0, 1], vec![0, 1]], [2, 2], &weights);
+        assert_eq!(exact[..2], matrix[..2]);
+        assert!(exact[2].is_nan());
+        assert_eq!(exact[3], 0.5);
+        assert_eq!(shares, [1.0, 1.0, 0.0, 1.0]);
+    }
+}
@@ -1,111 +1,809 @@
-//! Arena-based memory model for the game tree.
-//! 
-//! Uses a flat vector to store nodes, improving cache locality and avoiding
-//! pointer chasing. Nodes use u32 indices to reference children.
-
-use std::collections::HashMap;
-use serde::{Serialize, Deserialize};
-use crate::solver::types::ActionType;
-
-/// Type of node in the game tree.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum NodeType {
-    /// Terminal node (game over, money exchanged).
-    Terminal,
-    /// Showdown node (game over, hands compared).
-    Showdown,
-    /// Action node (player must act).
-    Action,
-    /// Chance node (random event, e.g., dealing cards).
-    /// Note: For river subgames, chance nodes are usually implicit or pre-resolved.
-    Chance,
-}
-
-/// A node in the game tree.
-/// 
-/// Designed to be compact (fits in cache line if possible).
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Node {
-    /// Type of the node.
-    pub node_type: NodeType,
-    /// Player whose turn it is (0 or 1), or 255 if terminal/showdown.
-    pub player: u8,
-    /// Current size of the pot.
-    pub pot: f32,
-    /// Index of the first child in the arena.
-    pub children_start: u32,
-    /// Number of available actions/children.
-    pub num_actions: u8,
-    /// ID of the information set this node belongs to.
-    /// u32::MAX if not applicable (e.g., terminal).
-    pub infoset_id: u32,
-    /// The action that led to this node (for visualization/debugging).
-    pub action_from_parent: Option<ActionType>,
-    /// The amount associated with the action (e.g., bet amount).
-    pub amount_from_parent: f32,
-}
-
-impl Node {
-    pub fn new(node_type: NodeType, player: u8, pot: f32) -> Self {
-        Self {
-            node_type,
-            player,
-            pot,
-            children_start: 0,
-            num_actions: 0,
-            infoset_id: u32::MAX,
-            action_from_parent: None,
-            amount_from_parent: 0.0,
-        }
-    }
-
-    pub fn is_terminal(&self) -> bool {
-        matches!(self.node_type, NodeType::Terminal | NodeType::Showdown)
-    }
-}
-
-/// The Game Tree container.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GameTree {
-    /// Flat storage for all nodes.
-    pub nodes: Vec<Node>,
-    /// Map from canonical infoset hash to infoset ID.
-    pub infoset_map: HashMap<u64, u32>,
-}
-
-impl GameTree {
-    pub fn new() -> Self {
-        Self {
-            nodes: Vec::with_capacity(10000), // Pre-allocate reasonable size
-            infoset_map: HashMap::new(),
-        }
-    }
-
-    /// Add a node to the arena and return its index.
-    pub fn add_node(&mut self, node: Node) -> u32 {
-        let id = self.nodes.len() as u32;
-        self.nodes.push(node);
-        id
-    }
-
-    /// Get a reference to a node by index.
-    pub fn get_node(&self, id: u32) -> &Node {
-        &self.nodes[id as usize]
-    }
-
-    /// Get a mutable reference to a node by index.
-    pub fn get_node_mut(&mut self, id: u32) -> &mut Node {
-        &mut self.nodes[id as usize]
-    }
-
-    /// Get or create an infoset ID for a given key.
-    pub fn get_infoset_id(&mut self, key: u64) -> u32 {
-        if let Some(&id) = self.infoset_map.get(&key) {
-            id
-        } else {
-            let id = self.infoset_map.len() as u32;
-            self.infoset_map.insert(key, id);
-            id
-        }
-    }
-}
+//! Arena-based memory model for the game tree.
+//! 
+//! Uses a flat vector to store nodes, improving cache locality and avoiding
+//! pointer chasing. Nodes use u32 indices to reference children.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use serde::{Serialize, Deserialize};
+use crate::solver::hash::Fnv1a;
+use crate::solver::icm::icm_equities;
+use crate::poker::Card;
+use crate::solver::types::{ActionType, BountyConfig, IcmConfig, LineStep};
+
+/// Type of node in the game tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeType {
+    /// Terminal node (game over, money exchanged).
+    Terminal,
+    /// Showdown node (game over, hands compared).
+    Showdown,
+    /// Action node (player must act).
+    Action,
+    /// Chance node (random event, e.g., dealing cards). Turn trees deal the
+    /// river here: child `i` follows river card `GameTree::rivers[i]`.
+    Chance,
+}
+
+/// A node in the game tree.
+/// 
+/// Designed to be compact (fits in cache line if possible).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    /// Type of the node.
+    pub node_type: NodeType,
+    /// Player whose turn it is (0 or 1), or 255 if terminal/showdown/chance.
+    pub player: u8,
+    /// Current size of the pot, in chips (see [`GameTree::to_amount`]).
+    pub pot: i32,
+    /// Chips each player has put in since the root. Bets are level whenever
+    /// a street closes, so the difference is still the amount to call.
+    pub bets: [i32; 2],
+    /// Whether each player is all in (no chips behind), so losing this pot
+    /// at a terminal node busts them.
+    pub all_in: [bool; 2],
+    /// Index of the first child in the arena.
+    pub children_start: u32,
+    /// Number of available actions/children.
+    pub num_actions: u8,
+    /// ID of the information set this node belongs to.
+    /// u32::MAX if not applicable (e.g., terminal).
+    pub infoset_id: u32,
+    /// The action that led to this node (for visualization/debugging).
+    pub action_from_parent: Option<ActionType>,
+    /// The amount associated with the action (e.g., bet amount), in chips.
+    pub amount_from_parent: i32,
+    /// Index of the river card dealt on the way to this node, if any.
+    pub river: Option<u8>,
+}
+
+impl Node {
+    pub fn new(node_type: NodeType, player: u8, pot: i32) -> Self {
+        Self {
+            node_type,
+            player,
+            pot,
+            bets: [0, 0],
+            all_in: [false, false],
+            children_start: 0,
+            num_actions: 0,
+            infoset_id: u32::MAX,
+            action_from_parent: None,
+            amount_from_parent: 0,
+            river: None,
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(self.node_type, NodeType::Terminal | NodeType::Showdown)
+    }
+}
+
+/// The Game Tree container.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameTree {
+    /// Flat storage for all nodes.
+    pub nodes: Vec<Node>,
+    /// Map from infoset hash (see [`GameTree::infoset_hasher`]) to infoset ID.
+    pub infoset_map: HashMap<u64, u32>,
+    /// Key material of every infoset, by ID. Lookups compare it, so two
+    /// keys with the same hash are reported instead of sharing an infoset.
+    pub infoset_keys: Vec<Vec<u8>>,
+    /// Hash of infoset key material: [`infoset_hash`], unless a test swaps
+    /// in a weaker one.
+    #[serde(skip, default = "default_infoset_hasher")]
+    pub infoset_hasher: fn(&[u8]) -> u64,
+    /// Chips per unit of the config amounts (`GameConfig::chip_scale`).
+    pub chip_scale: u32,
+    /// Stacks behind at the root, in chips.
+    pub stacks: [i32; 2],
+    /// Payouts for ICM utilities; chip EV when `None`.
+    pub icm: Option<IcmConfig>,
+    /// Knockout bounties added to the winner's utility when the loser busts.
+    pub bounty: Option<BountyConfig>,
+    /// Card indices of the rivers dealt at every chance node, in child order.
+    /// Empty for river trees.
+    pub rivers: Vec<u8>,
+    /// Probability weight of each dealt river for a pair of hands: one over
+    /// the rivers left once both hands are dealt, scaled up when only a
+    /// sample of the rivers is dealt.
+    pub river_weight: f32,
+}
+
+/// Hash of infoset key material: 64-bit FNV-1a, so infoset IDs are the
+/// same on every run and platform (unlike `HashMap`'s randomly keyed
+/// SipHash).
+pub fn infoset_hash(material: &[u8]) -> u64 {
+    let mut hash = Fnv1a::new();
+    hash.write(material);
+    hash.finish()
+}
+
+fn default_infoset_hasher() -> fn(&[u8]) -> u64 {
+    infoset_hash
+}
+
+/// A broken structural invariant found by [`GameTree::validate`], or an
+/// infoset collision found while building.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeError {
+    /// The node's children run past the arena or do not come after it.
+    ChildrenOutOfBounds { node: u32 },
+    /// A child also falls in the children range of `other`.
+    OverlappingChildren { node: u32, other: u32 },
+    /// An action or chance node without children.
+    NoChildren { node: u32 },
+    /// An action node whose infoset id is not in the infoset map.
+    MissingInfoset { node: u32 },
+    /// A terminal or showdown node with children.
+    LeafWithChildren { node: u32 },
+    /// A terminal or showdown node with an infoset id.
+    LeafWithInfoset { node: u32 },
+    /// The pot is smaller than at the node's parent.
+    PotDecreases { node: u32, parent: u32 },
+    /// The node does not follow from its parent: a fold that does not end
+    /// the hand with the other player winning, a decision by the player who
+    /// just acted, a river dealt without a card or to player 1, or a child
+    /// with no incoming step.
+    BadTransition { node: u32, parent: u32 },
+    /// The node's infoset key hashes to the same value as the key of
+    /// another infoset (see [`GameTree::get_infoset_id`]).
+    CollisionDetected { node: u32, infoset: u32 },
+}
+
+impl std::fmt::Display for TreeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            TreeError::ChildrenOutOfBounds { node } => write!(f, "node {}: children out of bounds", node),
+            TreeError::OverlappingChildren { node, other } => write!(f, "node {}: children overlap those of node {}", node, other),
+            TreeError::NoChildren { node } => write!(f, "node {}: no children", node),
+            TreeError::MissingInfoset { node } => write!(f, "node {}: action node without a valid infoset", node),
+            TreeError::LeafWithChildren { node } => write!(f, "node {}: terminal node with children", node),
+            TreeError::LeafWithInfoset { node } => write!(f, "node {}: terminal node with an infoset", node),
+            TreeError::PotDecreases { node, parent } => write!(f, "node {}: pot smaller than at parent {}", node, parent),
+            TreeError::BadTransition { node, parent } => write!(f, "node {}: does not follow from parent {}", node, parent),
+            TreeError::CollisionDetected { node, infoset } => write!(f, "node {}: infoset key hash collides with infoset {}", node, infoset),
+        }
+    }
+}
+
+/// Utilities `[u0, u1]` of a terminal node for each possible pot winner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub p0_wins: [f32; 2],
+    pub p1_wins: [f32; 2],
+}
+
+/// Size and shape of a tree (see [`GameTree::stats`]).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeStats {
+    pub nodes: usize,
+    pub action_nodes: usize,
+    pub chance_nodes: usize,
+    /// Fold nodes.
+    pub terminal_nodes: usize,
+    pub showdown_nodes: usize,
+    pub infosets: usize,
+    /// Edges on the longest path from the root; a dealt river counts as one.
+    pub max_depth: usize,
+    /// Most actions at a decision node.
+    pub max_actions: usize,
+    /// Rivers dealt at each chance node.
+    pub rivers: usize,
+    /// Number of edges of each action type, by name ("bet", "call", ...).
+    pub actions: BTreeMap<&'static str, usize>,
+}
+
+impl Default for GameTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameTree {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::with_capacity(10000), // Pre-allocate reasonable size
+            infoset_map: HashMap::new(),
+            infoset_keys: Vec::new(),
+            infoset_hasher: infoset_hash,
+            chip_scale: 1,
+            stacks: [0, 0],
+            icm: None,
+            bounty: None,
+            rivers: Vec::new(),
+            river_weight: 0.0,
+        }
+    }
+
+    /// Convert a node amount in chips back to config units. Only payoffs and
+    /// user-facing output should see the `f32`; the tree itself stays integral.
+    pub fn to_amount(&self, chips: i32) -> f32 {
+        (chips as f64 / self.chip_scale as f64) as f32
+    }
+
+    /// Most actions at any decision node (chance nodes do not count, as they
+    /// have no strategy). This is the width of the solver's strategy rows.
+    pub fn max_actions(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|n| n.node_type == NodeType::Action)
+            .map(|n| n.num_actions as usize)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Heap bytes the tree holds: the node arena, the infoset keys and
+    /// their hash index (entries plus hashbrown's control byte), and the
+    /// rivers.
+    pub fn heap_bytes(&self) -> usize {
+        let keys: usize = self.infoset_keys.iter().map(Vec::capacity).sum();
+        self.nodes.capacity() * std::mem::size_of::<Node>()
+            + self.infoset_keys.capacity() * std::mem::size_of::<Vec<u8>>()
+            + keys
+            + self.infoset_map.capacity() * (std::mem::size_of::<(u64, u32)>() + 1)
+            + self.rivers.capacity()
+    }
+
+    /// Node counts by type, depth and the action histogram.
+    pub fn stats(&self) -> TreeStats {
+        let count = |node_type: NodeType| self.nodes.iter().filter(|n| n.node_type == node_type).count();
+        let mut max_depth = 0;
+        self.visit(|_, depth, _| max_depth = max_depth.max(depth));
+        let mut actions = BTreeMap::new();
+        for action in self.nodes.iter().filter_map(|n| n.action_from_parent) {
+            *actions.entry(action.as_str()).or_insert(0) += 1;
+        }
+        TreeStats {
+            nodes: self.nodes.len(),
+            action_nodes: count(NodeType::Action),
+            chance_nodes: count(NodeType::Chance),
+            terminal_nodes: count(NodeType::Terminal),
+            showdown_nodes: count(NodeType::Showdown),
+            infosets: self.infoset_map.len(),
+            max_depth,
+            max_actions: self.max_actions(),
+            rivers: self.rivers.len(),
+            actions,
+        }
+    }
+
+    /// Add a node to the arena and return its index.
+    pub fn add_node(&mut self, node: Node) -> u32 {
+        let id = self.nodes.len() as u32;
+        self.nodes.push(node);
+        id
+    }
+
+    /// Get a reference to a node by index.
+    pub fn get_node(&self, id: u32) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    /// Get a mutable reference to a node by index.
+    pub fn get_node_mut(&mut self, id: u32) -> &mut Node {
+        &mut self.nodes[id as usize]
+    }
+
+    /// Utilities at terminal `node` if player 0 or player 1 takes the pot.
+    ///
+    /// In chip EV each player owns half of the pot at the root: the winner
+    /// gains the other half and everything the loser put in since, and the
+    /// loser gives that up. A bet nobody called goes back to the bettor, so
+    /// it is worth nothing either way. With ICM each player's tournament stack is their stack behind minus
+    /// what they put in since the root, plus the pot if they win it, and the
+    /// utility is their ICM equity of the resulting stacks. Either way, a
+    /// winner who busts an all-in opponent also gets the converted bounty.
+    pub fn outcome(&self, node: &Node) -> Outcome {
+        let mut outcome = self.pot_outcome(node);
+        if let Some(bounty) = &self.bounty {
+            if node.all_in[1] {
+                outcome.p0_wins[0] += bounty.opponent * bounty.conversion;
+            }
+            if node.all_in[0] {
+                outcome.p1_wins[1] += bounty.own * bounty.conversion;
+            }
+        }
+        outcome
+    }
+
+    /// [`GameTree::outcome`] without bounties.
+    fn pot_outcome(&self, node: &Node) -> Outcome {
+        let Some(icm) = &self.icm else {
+            let half_root = self.to_amount(node.pot - node.bets[0] - node.bets[1]) / 2.0;
+            let [lost0, lost1] = node.bets.map(|b| half_root + self.to_amount(b));
+            return Outcome { p0_wins: [lost1, -lost1], p1_wins: [-lost0, lost0] };
+        };
+
+        let equities = |winner: usize| {
+            let mut stacks: Vec<f32> = (0..2)
+                .map(|p| {
+                    let won = if p == winner { node.pot } else { 0 };
+                    self.to_amount(self.stacks[p] - node.bets[p] + won)
+                })
+                .collect();
+            stacks.extend_from_slice(&icm.stacks_behind);
+            let eq = icm_equities(&stacks, &icm.payouts);
+            [eq[0], eq[1]]
+        };
+        Outcome { p0_wins: equities(0), p1_wins: equities(1) }
+    }
+
+    /// Stable 64-bit fingerprint of the tree layout (FNV-1a over the chip
+    /// scale, the dealt rivers and every node's type, player, pot, children,
+    /// infoset and incoming action). Amounts are integers, so the hash is the
+    /// same on every platform.
+    ///
+    /// Two trees with the same hash index their strategy buffers identically,
+    /// so saved solutions use it to detect a mismatched config or builder.
+    pub fn structure_hash(&self) -> u64 {
+        let mut hash = Fnv1a::new();
+        hash.write(&self.chip_scale.to_le_bytes());
+        hash.write(&self.rivers);
+        for node in &self.nodes {
+            let action = node.action_from_parent.map_or(u8::MAX, |a| a as u8);
+            hash.write(&[node.node_type as u8, node.player, node.num_actions, action]);
+            hash.write(&node.pot.to_le_bytes());
+            hash.write(&node.children_start.to_le_bytes());
+            hash.write(&node.infoset_id.to_le_bytes());
+            hash.write(&node.amount_from_parent.to_le_bytes());
+        }
+        hash.finish()
+    }
+
+    /// Node reached by following `line` from the root, or `None` if a step
+    /// has no match (see [`GameTree::find_child`]).
+    pub fn find_node(&self, line: &[LineStep], tolerance: f32) -> Option<u32> {
+        line.iter().try_fold(0, |node_id, step| self.find_child(node_id, step, tolerance))
+    }
+
+    /// Child of `node_id` that `step` leads to. Folds, checks and calls match
+    /// by type. A bet or raise takes the child of its type whose amount is
+    /// closest to the step's, if no more than `tolerance` (config units)
+    /// away; without an amount it takes the first size. A river matches the
+    /// chance child dealing that card.
+    pub fn find_child(&self, node_id: u32, step: &LineStep, tolerance: f32) -> Option<u32> {
+        let node = self.nodes.get(node_id as usize)?;
+        let children = node.children_start..node.children_start + node.num_actions as u32;
+        match *step {
+            LineStep::River(card) if node.node_type == NodeType::Chance => {
+                children.into_iter().find(|&c| self.nodes[c as usize].river == Some(card.index()))
+            }
+            LineStep::Action(action, amount) if node.node_type == NodeType::Action => {
+                let mut matching = children.filter(|&c| self.nodes[c as usize].action_from_parent == Some(action));
+                match amount {
+                    Some(amount) if action.is_aggressive() => matching
+                        .map(|c| (c, (self.to_amount(self.nodes[c as usize].amount_from_parent) - amount).abs()))
+                        .filter(|&(_, diff)| diff <= tolerance)
+                        // The first of equally close sizes
+                        .fold(None, |best: Option<(u32, f32)>, (c, diff)| match best {
+                            Some((_, best_diff)) if best_diff <= diff => best,
+                            _ => Some((c, diff)),
+                        })
+                        .map(|(c, _)| c),
+                    _ => matching.next(),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The root's id, unless the tree is empty.
+    fn root(&self) -> Option<u32> {
+        (!self.nodes.is_empty()).then_some(0)
+    }
+
+    /// Children of `node_id`, in action (or river) order.
+    pub fn children(&self, node_id: u32) -> std::ops::Range<u32> {
+        let node = &self.nodes[node_id as usize];
+        node.children_start..node.children_start + node.num_actions as u32
+    }
+
+    /// Every node id depth-first from the root, each node before its
+    /// children and siblings in action order.
+    pub fn iter_dfs(&self) -> Dfs<'_> {
+        Dfs { tree: self, stack: self.root().into_iter().collect() }
+    }
+
+    /// The subtree at `node_id` in [`GameTree::iter_dfs`] order.
+    pub fn iter_dfs_from(&self, node_id: u32) -> Dfs<'_> {
+        Dfs { tree: self, stack: vec![node_id] }
+    }
+
+    /// Every node id breadth-first from the root: by depth, and within a
+    /// depth in the order their parents were visited.
+    pub fn iter_bfs(&self) -> Bfs<'_> {
+        Bfs { tree: self, queue: self.root().into_iter().collect() }
+    }
+
+    /// Call `visit(node_id, depth, parent)` on every node in
+    /// [`GameTree::iter_dfs`] order; the root has depth 0 and no parent.
+    pub fn visit(&self, mut visit: impl FnMut(u32, usize, Option<u32>)) {
+        let mut stack: Vec<(u32, usize, Option<u32>)> = self.root().map(|root| (root, 0, None)).into_iter().collect();
+        while let Some((node_id, depth, parent)) = stack.pop() {
+            visit(node_id, depth, parent);
+            stack.extend(self.children(node_id).rev().map(|child| (child, depth + 1, Some(node_id))));
+        }
+    }
+
+    /// Nodes from the root to `node_id`, both included.
+    pub fn path_to(&self, node_id: u32) -> Vec<u32> {
+        let mut parent = vec![u32::MAX; self.nodes.len()];
+        self.visit(|node, _, from| parent[node as usize] = from.unwrap_or(u32::MAX));
+        let (mut node, mut path) = (node_id, vec![node_id]);
+        while parent[node as usize] != u32::MAX {
+            node = parent[node as usize];
+            path.push(node);
+        }
+        path.reverse();
+        path
+    }
+
+    /// Step that leads into `node_id` from its parent, with the exact
+    /// amount; `None` for the root.
+    pub fn step_to(&self, node_id: u32) -> Option<LineStep> {
+        if node_id == 0 {
+            return None;
+        }
+        let node = &self.nodes[node_id as usize];
+        match (node.action_from_parent, node.river) {
+            (Some(action), _) => {
+                let amount = action.is_aggressive().then(|| self.to_amount(node.amount_from_parent));
+                Some(LineStep::Action(action, amount))
+            }
+            (None, Some(river)) => Some(LineStep::River(Card::from_index(river))),
+            (None, None) => None,
+        }
+    }
+
+    /// The line from the root to `node_id`, amounts exact, so that
+    /// `find_node(&line_of(id), 0.0) == Some(id)`.
+    pub fn line_of(&self, node_id: u32) -> Vec<LineStep> {
+        self.path_to(node_id).into_iter().filter_map(|n| self.step_to(n)).collect()
+    }
+
+    /// Copy the subtree at `root` into a tree of its own, with `root` as
+    /// node 0. Nodes keep their relative order, so children stay contiguous
+    /// and extracting the root copies the tree exactly. Infoset ids are
+    /// renumbered to the ones the subtree uses, in their original order, so
+    /// a trainer for the new tree is no bigger than it has to be. Pots, bets
+    /// and stacks stay as counted from the original root, leaving every
+    /// payoff unchanged; the new root keeps its dealt river but loses its
+    /// incoming action.
+    ///
+    /// Also returns the new id of every original node, `u32::MAX` for the
+    /// ones outside the subtree.
+    pub fn extract_subtree(&self, root: u32) -> (GameTree, Vec<u32>) {
+        let mut old_ids: Vec<u32> = self.iter_dfs_from(root).collect();
+        old_ids.sort_unstable();
+        let mut mapping = vec![u32::MAX; self.nodes.len()];
+        for (new_id, &old_id) in old_ids.iter().enumerate() {
+            mapping[old_id as usize] = new_id as u32;
+        }
+
+        let mut infosets: Vec<u32> = old_ids.iter()
+            .map(|&n| self.nodes[n as usize].infoset_id)
+            .filter(|&id| id != u32::MAX)
+            .collect();
+        infosets.sort_unstable();
+        infosets.dedup();
+        let keys: HashMap<u32, u64> = self.infoset_map.iter().map(|(&key, &id)| (id, key)).collect();
+
+        let nodes = old_ids.iter()
+            .map(|&old_id| {
+                let mut node = self.nodes[old_id as usize].clone();
+                if node.num_actions > 0 {
+                    node.children_start = mapping[node.children_start as usize];
+                }
+                if node.infoset_id != u32::MAX {
+                    node.infoset_id = infosets.binary_search(&node.infoset_id).expect("collected above") as u32;
+                }
+                if old_id == root {
+                    node.action_from_parent = None;
+                    node.amount_from_parent = 0;
+                }
+                node
+            })
+            .collect();
+        let tree = GameTree {
+            nodes,
+            infoset_map: infosets.iter().enumerate().map(|(new_id, old_id)| (keys[old_id], new_id as u32)).collect(),
+            infoset_keys: infosets.iter().map(|&old_id| self.infoset_keys[old_id as usize].clone()).collect(),
+            infoset_hasher: self.infoset_hasher,
+            chip_scale: self.chip_scale,
+            stacks: self.stacks,
+            icm: self.icm.clone(),
+            bounty: self.bounty.clone(),
+            rivers: self.rivers.clone(),
+            river_weight: self.river_weight,
+        };
+        (tree, mapping)
+    }
+
+    /// Check the structural invariants every builder must keep: children
+    /// ranges in bounds, after their parent and disjoint; action nodes with
+    /// children and an infoset, chance nodes with children; terminal and
+    /// showdown nodes with neither; pots never shrinking along a line; and
+    /// each child consistent with how it is reached. Returns every error
+    /// found.
+    pub fn validate(&self) -> Result<(), Vec<TreeError>> {
+        let mut errors = Vec::new();
+        let mut parent = vec![u32::MAX; self.nodes.len()];
+        for (id, node) in self.nodes.iter().enumerate() {
+            let id = id as u32;
+            let (start, end) = (node.children_start as usize, node.children_start as usize + node.num_actions as usize);
+            match node.node_type {
+                NodeType::Action | NodeType::Chance if node.num_actions == 0 => errors.push(TreeError::NoChildren { node: id }),
+                NodeType::Terminal | NodeType::Showdown if node.num_actions > 0 => errors.push(TreeError::LeafWithChildren { node: id }),
+                _ => {}
+            }
+            match node.node_type {
+                NodeType::Action if node.infoset_id as usize >= self.infoset_map.len() => errors.push(TreeError::MissingInfoset { node: id }),
+                NodeType::Terminal | NodeType::Showdown if node.infoset_id != u32::MAX => errors.push(TreeError::LeafWithInfoset { node: id }),
+                _ => {}
+            }
+            if node.num_actions == 0 {
+                continue;
+            }
+            if start <= id as usize || end > self.nodes.len() {
+                errors.push(TreeError::ChildrenOutOfBounds { node: id });
+                continue;
+            }
+            for claimed in &mut parent[start..end] {
+                if *claimed != u32::MAX {
+                    errors.push(TreeError::OverlappingChildren { node: id, other: *claimed });
+                    break;
+                }
+                *claimed = id;
+            }
+        }
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            let (id, from) = (id as u32, parent[id]);
+            if from == u32::MAX {
+                continue;
+            }
+            let up = &self.nodes[from as usize];
+            if node.pot < up.pot {
+                errors.push(TreeError::PotDecreases { node: id, parent: from });
+            }
+            let follows = match (up.node_type, node.action_from_parent) {
+                (NodeType::Chance, None) => node.river.is_some() && (node.node_type != NodeType::Action || node.player == 0),
+                (NodeType::Action, Some(ActionType::Fold)) => node.node_type == NodeType::Terminal && node.player == up.player ^ 1,
+                (NodeType::Action, Some(_)) => node.node_type != NodeType::Action || node.player == up.player ^ 1,
+                _ => false,
+            };
+            if !follows {
+                errors.push(TreeError::BadTransition { node: id, parent: from });
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Get or create the infoset ID of decision node `node`, whose infoset
+    /// is keyed by `material`. IDs are handed out in order of first use.
+    /// Fails if another infoset's key hashes to the same value.
+    pub fn get_infoset_id(&mut self, node: u32, material: &[u8]) -> Result<u32, TreeError> {
+        let hash = (self.infoset_hasher)(material);
+        if let Some(&id) = self.infoset_map.get(&hash) {
+            if self.infoset_keys[id as usize] != material {
+                return Err(TreeError::CollisionDetected { node, infoset: id });
+            }
+            return Ok(id);
+        }
+        let id = self.infoset_map.len() as u32;
+        self.infoset_map.insert(hash, id);
+        self.infoset_keys.push(material.to_vec());
+        Ok(id)
+    }
+}
+
+/// Depth-first iterator over node ids (see [`GameTree::iter_dfs`]).
+pub struct Dfs<'a> {
+    tree: &'a GameTree,
+    stack: Vec<u32>,
+}
+
+impl Iterator for Dfs<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let node_id = self.stack.pop()?;
+        self.stack.extend(self.tree.children(node_id).rev());
+        Some(node_id)
+    }
+}
+
+/// Breadth-first iterator over node ids (see [`GameTree::iter_bfs`]).
+pub struct Bfs<'a> {
+    tree: &'a GameTree,
+    queue: VecDeque<u32>,
+}
+
+impl Iterator for Bfs<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let node_id = self.queue.pop_front()?;
+        self.queue.extend(self.tree.children(node_id));
+        Some(node_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::builder::{build_river_tree, build_turn_tree};
+    use crate::solver::types::GameConfig;
+
+    fn config() -> GameConfig {
+        serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.5, 1], "raise_sizes": [1],
+                "river_cards": {"sampled": {"count": 3, "seed": 7}}}"#,
+        ).unwrap()
+    }
+
+    fn line(s: &str) -> Vec<LineStep> {
+        s.split(',').map(|step| LineStep::parse(step).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_find_node_round_trips_every_line() {
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let tree = build_turn_tree(&config(), &board).unwrap();
+        for id in 0..tree.nodes.len() as u32 {
+            assert_eq!(tree.find_node(&tree.line_of(id), 0.0), Some(id));
+        }
+        assert_eq!(tree.line_of(0), vec![]);
+    }
+
+    #[test]
+    fn test_find_node_deep_lines() {
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let tree = build_turn_tree(&config(), &board).unwrap();
+        let river = Card::from_index(tree.rivers[1]);
+
+        // Bet 50, a pot raise to 250 (the 50 called plus the 200 pot), call,
+        // then a half-pot river bet
+        let deep = line(&format!("check, bet 50, raise 250, call, river {}, bet 300", river));
+        let id = tree.find_node(&deep, 0.0).unwrap();
+        let labels: Vec<String> = tree.line_of(id).iter().map(|s| s.to_string()).collect();
+        assert_eq!(labels, ["check", "bet 50", "raise 250", "call", &format!("river {}", river), "bet 300"]);
+        assert_eq!(tree.nodes[id as usize].bets, [55000, 25000]);
+
+        // Amounts match within the tolerance, the closest size winning
+        assert_eq!(tree.find_node(&line("bet 48"), 0.0), None);
+        assert_eq!(tree.find_node(&line("bet 48"), 5.0), tree.find_node(&line("bet 50"), 0.0));
+        assert_eq!(tree.find_node(&line("bet 80"), 50.0), tree.find_node(&line("bet 100"), 0.0));
+        // Without an amount, the first size
+        assert_eq!(tree.find_node(&line("bet"), 0.0), tree.find_node(&line("bet 50"), 0.0));
+
+        // Steps that do not fit the node
+        assert_eq!(tree.find_node(&line("check, check, call"), f32::INFINITY), None);
+        assert_eq!(tree.find_node(&line(&format!("check, river {}", river)), f32::INFINITY), None);
+        let dealt = Card::from_index((0..52).find(|&c| !tree.rivers.contains(&c) && board.iter().all(|b| b.index() != c)).unwrap());
+        assert_eq!(tree.find_node(&line(&format!("check, check, river {}", dealt)), f32::INFINITY), None);
+        assert!(tree.find_node(&line("check, check"), 0.0).is_some_and(|id| tree.nodes[id as usize].node_type == NodeType::Chance));
+
+        let river_tree = build_river_tree(&config()).unwrap();
+        assert_eq!(river_tree.path_to(river_tree.find_node(&line("bet 100, call"), 0.0).unwrap()).len(), 3);
+    }
+
+    #[test]
+    fn test_walk_orders() {
+        // Check or bet 100; a check is checked back or bet into, a bet
+        // folded to or called, and a bet after a check likewise
+        let small: GameConfig = serde_json::from_str(r#"{"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1], "raise_sizes": []}"#).unwrap();
+        let tree = build_river_tree(&small).unwrap();
+        let label = |id: u32| tree.step_to(id).map_or("root".to_string(), |s| s.to_string());
+        let dfs: Vec<String> = tree.iter_dfs().map(label).collect();
+        assert_eq!(dfs, ["root", "check", "check", "bet 100", "fold", "call", "bet 100", "fold", "call"]);
+        let bfs: Vec<String> = tree.iter_bfs().map(label).collect();
+        assert_eq!(bfs, ["root", "check", "bet 100", "check", "bet 100", "fold", "call", "fold", "call"]);
+        assert_eq!(tree.children(0).map(label).collect::<Vec<_>>(), ["check", "bet 100"]);
+
+        let mut visits = Vec::new();
+        tree.visit(|id, depth, parent| visits.push((id, depth, parent)));
+        assert_eq!(visits.iter().map(|v| v.0).collect::<Vec<_>>(), tree.iter_dfs().collect::<Vec<_>>());
+        for &(id, depth, parent) in &visits {
+            assert_eq!(depth + 1, tree.path_to(id).len());
+            assert!(parent.is_none_or(|p| tree.children(p).contains(&id)));
+        }
+        assert_eq!(visits[0], (0, 0, None));
+        assert_eq!(GameTree::new().iter_dfs().count(), 0);
+
+        // Every node exactly once, in both orders
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let tree = build_turn_tree(&config(), &board).unwrap();
+        for order in [tree.iter_dfs().collect::<Vec<_>>(), tree.iter_bfs().collect()] {
+            let mut sorted = order.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..tree.nodes.len() as u32).collect::<Vec<_>>());
+        }
+        let depths: Vec<usize> = tree.iter_bfs().map(|id| tree.path_to(id).len()).collect();
+        assert!(depths.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_validate_flags_each_corruption() {
+        let small: GameConfig = serde_json::from_str(r#"{"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1], "raise_sizes": []}"#).unwrap();
+        let corrupt = |edit: &dyn Fn(&mut GameTree)| {
+            let mut tree = build_river_tree(&small).unwrap();
+            edit(&mut tree);
+            tree.validate().unwrap_err()
+        };
+        let find = |tree: &GameTree, line_str: &str| tree.find_node(&line(line_str), 0.0).unwrap() as usize;
+
+        assert_eq!(build_river_tree(&small).unwrap().validate(), Ok(()));
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        assert_eq!(build_turn_tree(&config(), &board).unwrap().validate(), Ok(()));
+
+        let errors = corrupt(&|tree| tree.nodes[0].children_start = 100);
+        assert_eq!(errors, vec![TreeError::ChildrenOutOfBounds { node: 0 }]);
+        let errors = corrupt(&|tree| {
+            let bet = find(tree, "bet 100");
+            tree.nodes[bet].children_start = tree.nodes[find(tree, "check")].children_start;
+        });
+        assert!(errors.contains(&TreeError::OverlappingChildren { node: 2, other: 1 }), "{:?}", errors);
+        let errors = corrupt(&|tree| {
+            let bet = find(tree, "bet 100");
+            tree.nodes[bet].num_actions = 0;
+        });
+        assert!(errors.contains(&TreeError::NoChildren { node: 2 }), "{:?}", errors);
+        let errors = corrupt(&|tree| tree.nodes[0].infoset_id = u32::MAX);
+        assert_eq!(errors, vec![TreeError::MissingInfoset { node: 0 }]);
+
+        let showdown = find(&build_river_tree(&small).unwrap(), "check, check") as u32;
+        let errors = corrupt(&|tree| tree.nodes[showdown as usize].num_actions = 1);
+        assert!(errors.contains(&TreeError::LeafWithChildren { node: showdown }), "{:?}", errors);
+        let errors = corrupt(&|tree| tree.nodes[showdown as usize].infoset_id = 0);
+        assert_eq!(errors, vec![TreeError::LeafWithInfoset { node: showdown }]);
+
+        let call = find(&build_river_tree(&small).unwrap(), "bet 100, call") as u32;
+        let errors = corrupt(&|tree| tree.nodes[call as usize].pot = 0);
+        assert_eq!(errors, vec![TreeError::PotDecreases { node: call, parent: 2 }]);
+        let fold = find(&build_river_tree(&small).unwrap(), "bet 100, fold") as u32;
+        let errors = corrupt(&|tree| tree.nodes[fold as usize].player = 1);
+        assert_eq!(errors, vec![TreeError::BadTransition { node: fold, parent: 2 }]);
+        let errors = corrupt(&|tree| {
+            let check = find(tree, "check");
+            tree.nodes[check].player = 0;
+        });
+        // Both the check and the bet after it are out of turn
+        let check_bet = find(&build_river_tree(&small).unwrap(), "check, bet 100") as u32;
+        assert_eq!(errors, vec![TreeError::BadTransition { node: 1, parent: 0 }, TreeError::BadTransition { node: check_bet, parent: 1 }]);
+        assert_eq!(TreeError::PotDecreases { node: 5, parent: 2 }.to_string(), "node 5: pot smaller than at parent 2");
+    }
+
+    #[test]
+    fn test_extract_subtree() {
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let tree = build_turn_tree(&config(), &board).unwrap();
+
+        let (copy, mapping) = tree.extract_subtree(0);
+        assert_eq!(copy.structure_hash(), tree.structure_hash());
+        assert_eq!(copy.infoset_map, tree.infoset_map);
+        assert_eq!(mapping, (0..tree.nodes.len() as u32).collect::<Vec<_>>());
+
+        // Below a turn bet and call, on the second river
+        let river = Card::from_index(tree.rivers[1]);
+        let mid = tree.find_node(&line(&format!("bet 50, call, river {}, check", river)), 0.0).unwrap();
+        let (sub, mapping) = tree.extract_subtree(mid);
+        assert_eq!(sub.validate(), Ok(()));
+        let size = (0..tree.nodes.len() as u32).filter(|&id| tree.path_to(id).contains(&mid)).count();
+        assert_eq!(sub.nodes.len(), size);
+        assert_eq!(mapping.iter().filter(|&&m| m != u32::MAX).count(), size);
+        assert_eq!(mapping[mid as usize], 0);
+        assert_eq!(sub.nodes[0].river, Some(river.index()));
+        assert_eq!(sub.line_of(0), vec![]);
+
+        // Only the subtree's infosets, and the same lines below the root
+        let decisions = sub.nodes.iter().filter(|n| n.node_type == NodeType::Action).count();
+        assert_eq!(sub.infoset_map.len(), decisions);
+        for (old_id, &new_id) in mapping.iter().enumerate() {
+            if new_id != u32::MAX && new_id != 0 {
+                assert_eq!(sub.step_to(new_id), tree.step_to(old_id as u32));
+                assert_eq!(sub.nodes[new_id as usize].pot, tree.nodes[old_id].pot);
+            }
+        }
+    }
+}
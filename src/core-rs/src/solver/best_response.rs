@@ -0,0 +1,503 @@
+//! Best-response evaluation and exploitability.
+//!
+//! A best response fixes the opponent's average strategy and lets one player
+//! pick the highest-value action for every hand at every decision point.
+//! Since the game is zero-sum, the average of both players' best-response
+//! values is the exploitability of the current strategy profile (0 at a
+//! Nash equilibrium). [`node_value`] also evaluates a player's own average
+//! strategy, from any node.
+
+use serde::Serialize;
+
+use crate::solver::arena::{GameTree, NodeType, Outcome};
+use crate::solver::dcfr::{terminal_values, DCFRTrainer, Matchups};
+
+/// Expected value (in chips, per matchup) that `player` achieves by
+/// best-responding to the opponent's average strategy.
+///
+/// Values are normalized by the total reach weight of all non-conflicting
+/// hand pairs, so the result is directly comparable to the pot size.
+/// `equity_matrix` is laid out as for [`DCFRTrainer::train`].
+pub fn best_response_value(
+    tree: &GameTree,
+    trainer: &DCFRTrainer,
+    equity_matrix: &[f32],
+    initial_reach: &[Vec<f32>; 2],
+    player: usize,
+) -> f32 {
+    node_value(tree, trainer, equity_matrix, 0, initial_reach, player, Play::BestResponse)
+}
+
+/// How the evaluated player chooses actions in [`node_value`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Play {
+    /// The highest-value action for every hand.
+    BestResponse,
+    /// The player's own average strategy.
+    Average,
+}
+
+/// Expected value (in chips, per matchup) of `player` in the subtree at
+/// `node_idx` against the opponent's average strategy, normalized like
+/// [`best_response_value`]. `reach` is both players' reach of the node and
+/// `equity_matrix` the matrix the solver passes into that subtree.
+pub fn node_value(
+    tree: &GameTree,
+    trainer: &DCFRTrainer,
+    equity_matrix: &[f32],
+    node_idx: u32,
+    reach: &[Vec<f32>; 2],
+    player: usize,
+    play: Play,
+) -> f32 {
+    let opponent = 1 - player;
+    let values = player_values(tree, trainer, equity_matrix, node_idx, player, &reach[opponent], play);
+
+    let total_weight = matchup_weight(equity_matrix, reach);
+    if total_weight <= 0.0 {
+        return 0.0;
+    }
+
+    let weighted: f32 = values
+        .iter()
+        .zip(reach[player].iter())
+        .map(|(v, r)| v * r)
+        .sum();
+    weighted / total_weight
+}
+
+/// Counterfactual value of every hand of `player` at `node_idx` with both
+/// players on their average strategies: the sum over opposing hands of
+/// their reach in `opp_reach` times the payoff, without normalizing.
+pub fn counterfactual_values(
+    tree: &GameTree,
+    trainer: &DCFRTrainer,
+    equity_matrix: &[f32],
+    node_idx: u32,
+    player: usize,
+    opp_reach: &[f32],
+) -> Vec<f32> {
+    player_values(tree, trainer, equity_matrix, node_idx, player, opp_reach, Play::Average)
+}
+
+/// Counterfactual value of every hand of `player` at `node_idx` with both
+/// players on their average strategies, split by how the hand ends:
+/// `[folds, showdowns]`. Unlike [`counterfactual_values`], a leaf is worth
+/// the share of the pot the player takes there (their utility above losing
+/// it), so a hand that never wins at showdown has no showdown value.
+pub fn pot_share_values(
+    tree: &GameTree,
+    trainer: &DCFRTrainer,
+    equity_matrix: &[f32],
+    node_idx: u32,
+    player: usize,
+    opp_reach: &[f32],
+) -> [Vec<f32>; 2] {
+    let node = tree.get_node(node_idx);
+    let num_hands = trainer.num_hands();
+    let mut values = [vec![0.0; num_hands[player]], vec![0.0; num_hands[player]]];
+    // Add a child's values, hand `h`'s scaled by `scale(h)`
+    let add = |values: &mut [Vec<f32>; 2], child: [Vec<f32>; 2], scale: &dyn Fn(usize) -> f32| {
+        for (v, c) in values.iter_mut().zip(child) {
+            v.iter_mut().zip(c).enumerate().for_each(|(h, (v, c))| *v += scale(h) * c);
+        }
+    };
+
+    match node.node_type {
+        NodeType::Terminal | NodeType::Showdown => {
+            // Shift both outcomes so that losing the pot is worth nothing
+            let outcome = tree.outcome(node);
+            let lose = [outcome.p1_wins[0], outcome.p0_wins[1]];
+            let shifted = Outcome {
+                p0_wins: [outcome.p0_wins[0] - lose[0], 0.0],
+                p1_wins: [0.0, outcome.p1_wins[1] - lose[1]],
+            };
+            let own_reach = vec![1.0; num_hands[player]];
+            let (u0, u1) = if player == 0 {
+                terminal_values(node, shifted, Matchups::unweighted(equity_matrix), num_hands, &own_reach, opp_reach)
+            } else {
+                terminal_values(node, shifted, Matchups::unweighted(equity_matrix), num_hands, opp_reach, &own_reach)
+            };
+            let leaf = if node.node_type == NodeType::Terminal { 0 } else { 1 };
+            values[leaf] = if player == 0 { u0 } else { u1 };
+        },
+        NodeType::Action => {
+            let num_actions = node.num_actions as usize;
+            let infoset_id = node.infoset_id as usize;
+            let acting = node.player as usize;
+            let strategies: Vec<Vec<f32>> = (0..num_hands[acting])
+                .map(|h| trainer.average_strategy(infoset_id, h, num_actions))
+                .collect();
+            for a in 0..num_actions {
+                let child_idx = node.children_start + a as u32;
+                if acting == player {
+                    let child = pot_share_values(tree, trainer, equity_matrix, child_idx, player, opp_reach);
+                    add(&mut values, child, &|h| strategies[h][a]);
+                } else {
+                    let next_reach: Vec<f32> = opp_reach.iter().zip(&strategies).map(|(r, s)| r * s[a]).collect();
+                    add(&mut values, pot_share_values(tree, trainer, equity_matrix, child_idx, player, &next_reach), &|_| 1.0);
+                }
+            }
+        },
+        NodeType::Chance => {
+            let block = num_hands[0] * num_hands[1];
+            for i in 0..node.num_actions as usize {
+                let child_idx = node.children_start + i as u32;
+                let river_matrix = &equity_matrix[(i + 1) * block..(i + 2) * block];
+                add(&mut values, pot_share_values(tree, trainer, river_matrix, child_idx, player, opp_reach), &|_| tree.river_weight);
+            }
+        },
+    }
+    values
+}
+
+/// Exploitability of the average strategy profile in chips:
+/// the mean of both players' best-response values.
+///
+/// With ICM utilities the values are in payout units and the game is no
+/// longer zero-sum, so this is only a convergence proxy there: it does not
+/// reach zero at equilibrium.
+pub fn exploitability(
+    tree: &GameTree,
+    trainer: &DCFRTrainer,
+    equity_matrix: &[f32],
+    initial_reach: &[Vec<f32>; 2],
+) -> f32 {
+    let br = |player| best_response_value(tree, trainer, equity_matrix, initial_reach, player);
+
+    #[cfg(rayon_pool)]
+    let (br0, br1) = if crate::parallel::pool_ready() { rayon::join(|| br(0), || br(1)) } else { (br(0), br(1)) };
+
+    #[cfg(not(rayon_pool))]
+    let (br0, br1) = (br(0), br(1));
+
+    (br0 + br1) / 2.0
+}
+
+/// Exploitability split by player, in chips per matchup.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PlayerExploitability {
+    /// Each player's value when best-responding to the other's average strategy.
+    pub best_response: [f32; 2],
+    /// Each player's value when both play their average strategies.
+    pub average: [f32; 2],
+}
+
+impl PlayerExploitability {
+    /// How much each player gains by switching to a best response. One
+    /// side can be close to zero while the other is far off.
+    pub fn gain(&self) -> [f32; 2] {
+        [self.best_response[0] - self.average[0], self.best_response[1] - self.average[1]]
+    }
+
+    /// The same total as [`exploitability`].
+    pub fn total(&self) -> f32 {
+        (self.best_response[0] + self.best_response[1]) / 2.0
+    }
+}
+
+/// Best-response and average-strategy values of both players.
+pub fn player_exploitability(
+    tree: &GameTree,
+    trainer: &DCFRTrainer,
+    equity_matrix: &[f32],
+    initial_reach: &[Vec<f32>; 2],
+) -> PlayerExploitability {
+    let values = |player| {
+        let value = |play| node_value(tree, trainer, equity_matrix, 0, initial_reach, player, play);
+        (value(Play::BestResponse), value(Play::Average))
+    };
+
+    #[cfg(rayon_pool)]
+    let ((br0, avg0), (br1, avg1)) = if crate::parallel::pool_ready() { rayon::join(|| values(0), || values(1)) } else { (values(0), values(1)) };
+
+    #[cfg(not(rayon_pool))]
+    let ((br0, avg0), (br1, avg1)) = (values(0), values(1));
+
+    PlayerExploitability { best_response: [br0, br1], average: [avg0, avg1] }
+}
+
+/// One exploitability measurement during a solve, for convergence charts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ConvergencePoint {
+    pub iteration: usize,
+    /// Exploitability in chips and in percent of the starting pot.
+    pub exploitability: f32,
+    pub exploitability_pct: f32,
+    /// What each player gains by best-responding (see
+    /// [`PlayerExploitability::gain`]), in chips and percent of the pot.
+    pub gain: [f32; 2],
+    pub gain_pct: [f32; 2],
+}
+
+impl ConvergencePoint {
+    pub fn new(iteration: usize, values: &PlayerExploitability, pot: f32) -> Self {
+        let pct = |chips: f32| chips / pot * 100.0;
+        let gain = values.gain();
+        Self {
+            iteration,
+            exploitability: values.total(),
+            exploitability_pct: pct(values.total()),
+            gain,
+            gain_pct: gain.map(pct),
+        }
+    }
+}
+
+/// Evaluate `f` for every action index, in parallel when the rayon pool is available.
+/// Results are returned in action order so reductions stay deterministic.
+fn map_actions<F>(num_actions: usize, f: F) -> Vec<Vec<f32>>
+where
+    F: Fn(usize) -> Vec<f32> + Sync + Send,
+{
+    #[cfg(rayon_pool)]
+    if crate::parallel::pool_ready() {
+        use rayon::prelude::*;
+        return (0..num_actions).into_par_iter().map(f).collect();
+    }
+
+    (0..num_actions).map(f).collect()
+}
+
+/// Total reach weight of all hand pairs that can occur together.
+pub(crate) fn matchup_weight(equity_matrix: &[f32], initial_reach: &[Vec<f32>; 2]) -> f32 {
+    let n1 = initial_reach[1].len();
+    let mut total = 0.0;
+    for (h0, r0) in initial_reach[0].iter().enumerate() {
+        for (h1, r1) in initial_reach[1].iter().enumerate() {
+            if !equity_matrix[h0 * n1 + h1].is_nan() {
+                total += r0 * r1;
+            }
+        }
+    }
+    total
+}
+
+/// Recursive traversal.
+/// Returns the counterfactual value of every hand of `player` at `node_idx`.
+fn player_values(
+    tree: &GameTree,
+    trainer: &DCFRTrainer,
+    equity_matrix: &[f32],
+    node_idx: u32,
+    player: usize,
+    opp_reach: &[f32],
+    play: Play,
+) -> Vec<f32> {
+    let node = tree.get_node(node_idx);
+    let num_hands = trainer.num_hands();
+
+    match node.node_type {
+        NodeType::Terminal | NodeType::Showdown => {
+            // The player's own reach does not affect its counterfactual values.
+            let own_reach = vec![1.0; num_hands[player]];
+            let (u0, u1) = if player == 0 {
+                terminal_values(node, tree.outcome(node), Matchups::unweighted(equity_matrix), num_hands, &own_reach, opp_reach)
+            } else {
+                terminal_values(node, tree.outcome(node), Matchups::unweighted(equity_matrix), num_hands, opp_reach, &own_reach)
+            };
+            if player == 0 { u0 } else { u1 }
+        },
+        NodeType::Action => {
+            let num_actions = node.num_actions as usize;
+            let infoset_id = node.infoset_id as usize;
+            let acting = node.player as usize;
+
+            if acting == player {
+                let children = map_actions(num_actions, |a| {
+                    let child_idx = node.children_start + a as u32;
+                    player_values(tree, trainer, equity_matrix, child_idx, player, opp_reach, play)
+                });
+
+                match play {
+                    // Best response: take the best action independently for each hand
+                    Play::BestResponse => {
+                        let mut best = vec![f32::NEG_INFINITY; num_hands[player]];
+                        for child_values in children {
+                            for (b, v) in best.iter_mut().zip(child_values) {
+                                if v > *b {
+                                    *b = v;
+                                }
+                            }
+                        }
+                        best
+                    },
+                    Play::Average => {
+                        let mut values = vec![0.0; num_hands[player]];
+                        for (h, v) in values.iter_mut().enumerate() {
+                            let strategy = trainer.average_strategy(infoset_id, h, num_actions);
+                            *v = children.iter().zip(&strategy).map(|(c, s)| s * c[h]).sum();
+                        }
+                        values
+                    },
+                }
+            } else {
+                // Opponent plays its average strategy
+                let strategies: Vec<Vec<f32>> = (0..num_hands[acting])
+                    .map(|h| trainer.average_strategy(infoset_id, h, num_actions))
+                    .collect();
+
+                let children = map_actions(num_actions, |a| {
+                    let child_idx = node.children_start + a as u32;
+                    let next_reach: Vec<f32> = opp_reach
+                        .iter()
+                        .zip(strategies.iter())
+                        .map(|(r, s)| r * s[a])
+                        .collect();
+                    player_values(tree, trainer, equity_matrix, child_idx, player, &next_reach, play)
+                });
+
+                let mut values = vec![0.0; num_hands[player]];
+                for child_values in children {
+                    for (v, c) in values.iter_mut().zip(child_values) {
+                        *v += c;
+                    }
+                }
+                values
+            }
+        },
+        NodeType::Chance => {
+            // Rivers are independent, so they are evaluated like actions
+            let block = num_hands[0] * num_hands[1];
+            let children = map_actions(node.num_actions as usize, |i| {
+                let child_idx = node.children_start + i as u32;
+                let river_matrix = &equity_matrix[(i + 1) * block..(i + 2) * block];
+                player_values(tree, trainer, river_matrix, child_idx, player, opp_reach, play)
+            });
+
+            let mut values = vec![0.0; num_hands[player]];
+            for child_values in children {
+                for (v, c) in values.iter_mut().zip(child_values) {
+                    *v += tree.river_weight * c;
+                }
+            }
+            values
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{compute_equity_matrix, Card, GameVariant};
+    use crate::solver::{build_river_tree, BetSize, GameConfig, RiverCards};
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().filter_map(Card::from_str).collect()
+    }
+
+    fn setup(range0: &[&str], range1: &[&str]) -> (GameTree, DCFRTrainer, Vec<f32>, [Vec<f32>; 2]) {
+        let config = GameConfig {
+            initial_pot: 100.0,
+            stacks: [100.0, 100.0],
+            bet_sizes: vec![BetSize::PotFraction(1.0)],
+            raise_sizes: vec![].into(),
+            river_bet_sizes: None,
+            river_raise_sizes: None,
+            river_cards: RiverCards::All,
+            raise_limit: 0,
+            chip_scale: 100,
+            icm: None,
+            bounty: None,
+            abstraction: None,
+            suit_isomorphism: false,
+            profiles: Vec::new(),
+            size_overrides: Vec::new(),
+            sizing_rules: Vec::new(),
+            donk_sizes: None,
+            player_sizes: Default::default(),
+            add_allin: true,
+            variant: GameVariant::Holdem,
+        };
+        let board = cards("Ks Th 7s 4d 2c");
+        let r0: Vec<Vec<Card>> = range0.iter().map(|h| cards(h)).collect();
+        let r1: Vec<Vec<Card>> = range1.iter().map(|h| cards(h)).collect();
+        let matrix = compute_equity_matrix(&board, &r0, &r1);
+        let tree = build_river_tree(&config).unwrap();
+        let max_actions = tree.max_actions();
+        let trainer = DCFRTrainer::new(tree.infoset_map.len(), max_actions, [r0.len(), r1.len()]);
+        let reach = [vec![1.0; r0.len()], vec![1.0; r1.len()]];
+        (tree, trainer, matrix, reach)
+    }
+
+    #[test]
+    fn test_exploitability_non_negative() {
+        let (tree, trainer, matrix, reach) = setup(&["As Ad", "6h 5h"], &["Kc Kd", "9s 8s"]);
+        assert!(exploitability(&tree, &trainer, &matrix, &reach) >= 0.0);
+    }
+
+    #[test]
+    fn test_nuts_vs_air_converges() {
+        // P0 always wins at showdown, so both players' best responses are pinned:
+        // P1 can only lose, and P0 cannot do better than value-betting.
+        let (tree, mut trainer, matrix, reach) = setup(&["As Ad"], &["9s 8s"]);
+        let uniform = exploitability(&tree, &trainer, &matrix, &reach);
+
+        trainer.train(&tree, &matrix, 500, &reach);
+        let trained = exploitability(&tree, &trainer, &matrix, &reach);
+
+        assert!(trained < uniform, "trained {} should beat uniform {}", trained, uniform);
+        assert!(trained < 1.0, "exploitability {} should be near zero", trained);
+        assert!(best_response_value(&tree, &trainer, &matrix, &reach, 1) < 0.0);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_matches_single_thread() {
+        let (tree, mut trainer, matrix, reach) = setup(&["As Ad", "Kc Kd", "6h 5h"], &["Ac Kh", "Tc Td", "9s 8s"]);
+        trainer.train(&tree, &matrix, 50, &reach);
+
+        let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let multi = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let a = single.install(|| exploitability(&tree, &trainer, &matrix, &reach));
+        let b = multi.install(|| exploitability(&tree, &trainer, &matrix, &reach));
+        assert_eq!(a, b);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_training_matches_sequential() {
+        let (tree, mut parallel, matrix, reach) = setup(&["As Ad", "Kc Kd", "6h 5h"], &["Ac Kh", "Tc Td", "9s 8s"]);
+        // Counting keeps training on the sequential traversal
+        let mut sequential = parallel.clone();
+        sequential.count_perf(true);
+
+        let multi = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        multi.install(|| parallel.train(&tree, &matrix, 30, &reach));
+        sequential.train(&tree, &matrix, 30, &reach);
+        assert_eq!(parallel.regrets, sequential.regrets);
+        assert_eq!(parallel.strategy_sum, sequential.strategy_sum);
+    }
+
+    #[test]
+    fn test_average_play_never_beats_best_response() {
+        let (tree, mut trainer, matrix, reach) = setup(&["As Ad", "Kc Kd", "6h 5h"], &["Ac Kh", "Tc Td", "9s 8s"]);
+        trainer.train(&tree, &matrix, 20, &reach);
+        for player in 0..2 {
+            let average = node_value(&tree, &trainer, &matrix, 0, &reach, player, Play::Average);
+            let best = node_value(&tree, &trainer, &matrix, 0, &reach, player, Play::BestResponse);
+            assert!(average <= best + 1e-4, "player {}: {} > {}", player, average, best);
+        }
+        // Both sides on their average strategies: what one wins the other loses.
+        let v0 = node_value(&tree, &trainer, &matrix, 0, &reach, 0, Play::Average);
+        let v1 = node_value(&tree, &trainer, &matrix, 0, &reach, 1, Play::Average);
+        assert!((v0 + v1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_player_exploitability_matches_the_total() {
+        let (tree, mut trainer, matrix, reach) = setup(&["As Ad", "Kc Kd", "6h 5h"], &["Ac Kh", "Tc Td", "9s 8s"]);
+        trainer.train(&tree, &matrix, 20, &reach);
+        let players = player_exploitability(&tree, &trainer, &matrix, &reach);
+        assert_eq!(players.total(), exploitability(&tree, &trainer, &matrix, &reach));
+        assert!(players.gain().iter().all(|&g| g >= -1e-4));
+        // Zero-sum: the mean gain is the mean best-response value.
+        assert!((players.gain().iter().sum::<f32>() / 2.0 - players.total()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_fully_blocked_ranges_have_zero_value() {
+        let (tree, trainer, matrix, reach) = setup(&["As Ad"], &["As Kd"]);
+        assert_eq!(best_response_value(&tree, &trainer, &matrix, &reach, 0), 0.0);
+    }
+}
@@ -1,225 +1,903 @@
-//! Recursive tree builder for River subgames.
-
-use crate::solver::arena::{GameTree, Node, NodeType};
-use crate::solver::types::{GameConfig, ActionType};
-
-/// Build the game tree for a River subgame.
-pub fn build_river_tree(config: &GameConfig) -> GameTree {
-    let mut tree = GameTree::new();
-
-    // Calculate initial facing bet (if any)
-    // For simplicity in this phase, we assume start of river with no pending bets unless specified
-    // But typically solver starts with 0 bets on new street.
-    // If we want to support mid-street solving, we'd need more state in config.
-    // Here we assume standard river start: pot is set, bets are 0.
-
-    let root_node = Node::new(NodeType::Action, 0, config.initial_pot); // Player 0 starts (OOP)
-    let root_id = tree.add_node(root_node);
-
-    // Recursive build
-    build_subtree(
-        &mut tree,
-        root_id,
-        config,
-        0, // current player
-        [0.0, 0.0], // current bets
-        config.stacks, // current stacks
-        0, // recursion depth (for safety)
-        0  // raise count (for raise_limit)
-    );
-
-    tree
-}
-
-/// Recursive function to build the tree.
-fn build_subtree(
-    tree: &mut GameTree,
-    node_id: u32,
-    config: &GameConfig,
-    player: u8,
-    bets: [f32; 2],
-    stacks: [f32; 2],
-    depth: u32,
-    raise_count: u8, // Track number of raises for raise_limit
-) {
-    if depth > 20 {
-        // Safety break for infinite recursion
-        return;
-    }
-
-    let opponent = 1 - player;
-    let current_pot = config.initial_pot + bets[0] + bets[1];
-    let facing_bet = bets[opponent as usize] - bets[player as usize];
-
-    // 1. Identify valid actions
-    let mut actions: Vec<(ActionType, f32)> = Vec::new();
-
-    // FOLD
-    if facing_bet > 0.0 {
-        actions.push((ActionType::Fold, 0.0));
-    }
-
-    // CHECK / CALL
-    if facing_bet == 0.0 {
-        actions.push((ActionType::Check, 0.0));
-    } else {
-        // Call amount is min(facing_bet, stack)
-        let call_amount = facing_bet.min(stacks[player as usize]);
-        actions.push((ActionType::Call, call_amount));
-    }
-
-    // BET / RAISE
-    // Only if not facing all-in and have chips
-    // Also check raise_limit for raises (not for initial bets)
-    let is_raise = facing_bet > 0.0;
-    let can_raise = !is_raise || raise_count < config.raise_limit;
-    let can_bet = stacks[player as usize] > facing_bet
-        && stacks[opponent as usize] > 0.0
-        && can_raise;
-
-    if can_bet {
-        // Determine sizes
-        let sizes = if facing_bet == 0.0 { &config.bet_sizes } else { &config.raise_sizes };
-        
-        for &size_pct in sizes {
-            let mut amount = if facing_bet == 0.0 {
-                // Bet: % of pot
-                current_pot * size_pct
-            } else {
-                // Raise: (call + raise_amt) where raise_amt is % of pot after call
-                // Standard geometric sizing often uses (pot + 2*bet) * pct
-                // Here we use simple pot fraction for the raise part
-                let pot_after_call = current_pot + facing_bet;
-                facing_bet + (pot_after_call * size_pct)
-            };
-            
-            // Cap at stack (All-in)
-            if amount >= stacks[player as usize] {
-                amount = stacks[player as usize];
-            }
-            
-            // Ensure min-raise (unless all-in)
-            // Min raise is usually 2x the previous bet or 1BB
-            // Simplified: just ensure it's greater than call
-            if amount <= facing_bet {
-                continue; 
-            }
-            
-            // Avoid duplicate all-ins
-            let is_all_in = amount == stacks[player as usize];
-            let already_have_all_in = actions.iter().any(|(t, a)| t.is_aggressive() && *a == stacks[player as usize]);
-            
-            if is_all_in && already_have_all_in {
-                continue;
-            }
-            
-            let action_type = if facing_bet == 0.0 { ActionType::Bet } else { ActionType::Raise };
-            actions.push((action_type, amount));
-        }
-        
-        // Always add All-in if not covered by sizes
-        let all_in_amount = stacks[player as usize];
-        let already_have_all_in = actions.iter().any(|(t, a)| t.is_aggressive() && *a == all_in_amount);
-        if !already_have_all_in && all_in_amount > facing_bet {
-             let action_type = if facing_bet == 0.0 { ActionType::Bet } else { ActionType::Raise };
-             actions.push((action_type, all_in_amount));
-        }
-    }
-    
-    // 2. Update current node
-    let num_actions = actions.len() as u8;
-    
-    // Generate infoset ID
-    // Key: (player << 60) | hash(history)
-    // Simple history hash: sum of (action_type * depth) or similar
-    // For now, we just use a placeholder unique ID generation strategy would be needed for real solver
-    // We'll use a simple counter for unique paths in this builder for now, 
-    // but in reality we need to map equivalent histories to same infoset.
-    // Since this is a tree builder, we are visiting unique history nodes.
-    // So we just assign a new infoset ID for this node.
-    // Optimization: In a real solver, we'd hash the betting sequence.
-    let infoset_key = (player as u64) << 60 | (node_id as u64); 
-    let infoset_id = tree.get_infoset_id(infoset_key);
-    
-    let children_start = tree.nodes.len() as u32;
-    
-    {
-        let node = tree.get_node_mut(node_id);
-        node.num_actions = num_actions;
-        node.children_start = children_start; // Children will be appended next
-        node.infoset_id = infoset_id;
-    }
-    
-    // 3. Create children
-    // We must collect children indices to recurse on them, to avoid borrowing issues
-    let mut children_configs = Vec::new();
-
-    for (action_type, amount) in actions {
-        let mut next_node = Node::new(NodeType::Action, opponent, current_pot); // Default, updated below
-        next_node.action_from_parent = Some(action_type);
-        next_node.amount_from_parent = amount;
-
-        let mut next_bets = bets;
-        let mut next_stacks = stacks;
-        let mut is_terminal = false;
-        let mut is_showdown = false;
-        let mut next_raise_count = raise_count;
-
-        match action_type {
-            ActionType::Fold => {
-                next_node.node_type = NodeType::Terminal;
-                next_node.player = opponent; // Winner of the pot
-                next_node.pot = current_pot; // Pot doesn't increase on fold
-                is_terminal = true;
-            },
-            ActionType::Check => {
-                if player == 1 { // IP checked back
-                    next_node.node_type = NodeType::Showdown;
-                    next_node.player = 255;
-                    is_showdown = true;
-                } else {
-                    // OOP checked, now IP acts
-                    next_node.node_type = NodeType::Action;
-                    next_node.player = 1;
-                }
-                // Check resets raise count (new betting round within street)
-                next_raise_count = 0;
-            },
-            ActionType::Call => {
-                next_bets[player as usize] += amount;
-                next_stacks[player as usize] -= amount;
-                next_node.pot = config.initial_pot + next_bets[0] + next_bets[1];
-
-                // Call ends the betting round?
-                // If closing action (IP calls or OOP calls raise)
-                // And we are on River, so round end = Showdown
-                next_node.node_type = NodeType::Showdown;
-                next_node.player = 255;
-                is_showdown = true;
-            },
-            ActionType::Bet | ActionType::Raise => {
-                next_bets[player as usize] += amount;
-                next_stacks[player as usize] -= amount;
-                next_node.pot = config.initial_pot + next_bets[0] + next_bets[1];
-
-                // Action passes to opponent
-                next_node.node_type = NodeType::Action;
-                next_node.player = opponent;
-
-                // Increment raise count for bet/raise actions
-                next_raise_count = raise_count + 1;
-            }
-        }
-
-        let child_id = tree.add_node(next_node);
-
-        if !is_terminal && !is_showdown {
-            children_configs.push((child_id, opponent, next_bets, next_stacks, next_raise_count));
-        }
-    }
-    
-    // 4. Recurse
-    for (child_id, next_player, next_bets, next_stacks, next_raise_count) in children_configs {
-        build_subtree(tree, child_id, config, next_player, next_bets, next_stacks, depth + 1, next_raise_count);
-    }
-}
+//! Recursive tree builders for river and turn subgames.
+
+use std::collections::HashMap;
+
+use crate::poker::{Card, GameVariant};
+use crate::solver::arena::{infoset_hash, GameTree, Node, NodeType, TreeError};
+use crate::solver::line::parse_history;
+use crate::solver::types::{geometric_fraction, BetSize, GameConfig, ActionType, LineStep, RaiseSize, RaiseSizes, RiverCards, StreetName};
+
+/// Each player's bet and raise sizes on one street, and the street dealt
+/// when its betting closes (`None` on the river, where it goes to showdown).
+struct Street<'a> {
+    bet_sizes: [&'a [BetSize]; 2],
+    raise_sizes: [&'a RaiseSizes; 2],
+    next: Option<&'a Street<'a>>,
+}
+
+impl<'a> Street<'a> {
+    /// The players' sizes on the tree's first street, or on the river of a
+    /// turn tree when `river`.
+    fn of(config: &'a GameConfig, river: bool, next: Option<&'a Street<'a>>) -> Self {
+        Street {
+            bet_sizes: [0, 1].map(|p| config.bet_sizes_for(p, river)),
+            raise_sizes: [0, 1].map(|p| config.raise_sizes_for(p, river)),
+            next,
+        }
+    }
+}
+
+/// One step of the line from the root to a node: an action with the chips
+/// it puts in (0 for folds, checks and calls), or a dealt river.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Step {
+    Action(ActionType, i32),
+    River(u8),
+}
+
+impl Step {
+    /// Bytes of the step in infoset keys: the action and its chips, or a
+    /// marker and the river card.
+    fn push_key(&self, key: &mut Vec<u8>) {
+        match *self {
+            Step::Action(action, amount) => {
+                key.push(action as u8);
+                key.extend_from_slice(&amount.to_le_bytes());
+            }
+            Step::River(card) => key.extend_from_slice(&[u8::MAX, card]),
+        }
+    }
+}
+
+/// Infoset key of `player` acting after `line`. Both players see every
+/// action and card, so the betting sequence, river included, identifies
+/// the decision point; the hand is not part of it.
+fn infoset_key(player: u8, line: &[Step]) -> Vec<u8> {
+    let mut key = vec![player];
+    line.iter().for_each(|step| step.push_key(&mut key));
+    key
+}
+
+/// Aggressive amounts (in chips) kept at the nodes of `config.size_overrides`,
+/// by line. Lines that do not parse can never match and are left out.
+fn size_overrides(config: &GameConfig) -> HashMap<Vec<Step>, Vec<i32>> {
+    let step = |step: &LineStep| match *step {
+        LineStep::River(card) => Some(Step::River(card.index())),
+        LineStep::Action(action, Some(amount)) if action.is_aggressive() => Some(Step::Action(action, config.to_chips(amount))),
+        LineStep::Action(action, _) if !action.is_aggressive() => Some(Step::Action(action, 0)),
+        LineStep::Action(..) => None,
+    };
+    config.size_overrides.iter()
+        .filter_map(|o| {
+            let line = parse_history(&o.line).ok()?.iter().map(step).collect::<Option<Vec<Step>>>()?;
+            Some((line, o.sizes.iter().map(|&size| config.to_chips(size)).collect()))
+        })
+        .collect()
+}
+
+/// `line` as a sizing rule's `after` pattern: `"x-b"`, `"x-x|b"`.
+fn line_pattern(line: &[Step]) -> String {
+    let mut pattern = String::new();
+    for step in line {
+        match step {
+            Step::River(_) => pattern.push('|'),
+            Step::Action(action, _) => {
+                if !pattern.is_empty() && !pattern.ends_with('|') {
+                    pattern.push('-');
+                }
+                pattern.push(match action {
+                    ActionType::Fold => 'f',
+                    ActionType::Check => 'x',
+                    ActionType::Call => 'c',
+                    ActionType::Bet => 'b',
+                    ActionType::Raise => 'r',
+                });
+            }
+        }
+    }
+    pattern
+}
+
+/// Whether `player` acting at the end of `line` would lead into the
+/// aggressor: player 0's first river decision after the turn's last bet or
+/// raise was player 1's.
+fn is_donk_spot(line: &[Step], player: u8) -> bool {
+    let Some(river) = line.iter().position(|s| matches!(s, Step::River(_))) else { return false };
+    if player != 0 || river + 1 != line.len() {
+        return false;
+    }
+    // Player 0 acts first, so odd turn actions are player 1's
+    line[..river].iter().rposition(|s| matches!(s, Step::Action(a, _) if a.is_aggressive())).is_some_and(|i| i % 2 == 1)
+}
+
+/// Build the game tree for a River subgame.
+pub fn build_river_tree(config: &GameConfig) -> Result<GameTree, TreeError> {
+    build_tree(config, Vec::new(), 0.0, &Street::of(config, false, None), infoset_hash)
+}
+
+/// Build the game tree for a turn subgame on a 4-card `board`.
+///
+/// Whenever the turn betting closes with both players in, a chance node
+/// deals each river of `config.river_cards` and the river is played with
+/// the river sizes (or goes straight to showdown if someone is all in).
+pub fn build_turn_tree(config: &GameConfig, board: &[Card]) -> Result<GameTree, TreeError> {
+    let rivers = river_cards(config.river_cards, config.variant, board);
+    // Of the cards left after the turn, four are in the players' hands.
+    let deck = (config.variant.deck_size() - board.len()) as f64;
+    let river_weight = (deck / rivers.len().max(1) as f64 / (deck - 4.0)) as f32;
+
+    let river = Street::of(config, true, None);
+    build_tree(config, rivers, river_weight, &Street::of(config, false, Some(&river)), infoset_hash)
+}
+
+/// River cards a turn tree deals on `board` from the deck of `variant`, as
+/// sorted card indices.
+///
+/// Sampling uses a SplitMix64 partial shuffle of the remaining deck, so a
+/// seed always picks the same rivers and saved solutions rebuild the same tree.
+pub fn river_cards(selection: RiverCards, variant: GameVariant, board: &[Card]) -> Vec<u8> {
+    let mut deck: Vec<u8> = (0..52)
+        .filter(|&i| variant.in_deck(Card::from_index(i)) && !board.iter().any(|c| c.index() == i))
+        .collect();
+    if let RiverCards::Sampled { count, seed } = selection {
+        let count = count.min(deck.len());
+        let mut state = seed;
+        for i in 0..count {
+            let j = i + (splitmix64(&mut state) % (deck.len() - i) as u64) as usize;
+            deck.swap(i, j);
+        }
+        deck.truncate(count);
+        deck.sort_unstable();
+    }
+    deck
+}
+
+/// Next value of the SplitMix64 generator.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Shared setup of both builders: the tree-wide fields, then the root
+/// betting round of `street`, with infoset keys hashed by `hasher`.
+fn build_tree(config: &GameConfig, rivers: Vec<u8>, river_weight: f32, street: &Street, hasher: fn(&[u8]) -> u64) -> Result<GameTree, TreeError> {
+    let mut tree = GameTree::new();
+    tree.infoset_hasher = hasher;
+
+    // Calculate initial facing bet (if any)
+    // For simplicity in this phase, we assume start of river with no pending bets unless specified
+    // But typically solver starts with 0 bets on new street.
+    // If we want to support mid-street solving, we'd need more state in config.
+    // Here we assume standard river start: pot is set, bets are 0.
+
+    // All amounts below are whole chips; see `GameConfig::chip_scale`.
+    tree.chip_scale = config.chip_scale;
+    let initial_pot = config.to_chips(config.initial_pot);
+    let stacks = config.stacks.map(|s| config.to_chips(s));
+    tree.stacks = stacks;
+    tree.icm = config.icm.clone();
+    tree.bounty = config.bounty.clone();
+    tree.rivers = rivers;
+    tree.river_weight = river_weight;
+
+    let root_node = Node::new(NodeType::Action, 0, initial_pot); // Player 0 starts (OOP)
+    let root_id = tree.add_node(root_node);
+    let overrides = size_overrides(config);
+
+    // Recursive build
+    build_subtree(
+        &mut tree,
+        root_id,
+        config,
+        &overrides,
+        &mut Vec::new(), // line to the node
+        initial_pot,
+        0, // current player
+        [0, 0], // current bets
+        stacks, // current stacks
+        0, // recursion depth (for safety)
+        0, // raise count (for raise_limit)
+        street,
+    )?;
+
+    #[cfg(debug_assertions)]
+    if let Err(errors) = tree.validate() {
+        panic!("built an invalid tree: {}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; "));
+    }
+    Ok(tree)
+}
+
+/// Recursive function to build the tree.
+#[allow(clippy::too_many_arguments)]
+fn build_subtree(
+    tree: &mut GameTree,
+    node_id: u32,
+    config: &GameConfig,
+    overrides: &HashMap<Vec<Step>, Vec<i32>>,
+    line: &mut Vec<Step>,
+    initial_pot: i32,
+    player: u8,
+    bets: [i32; 2],
+    stacks: [i32; 2],
+    depth: u32,
+    raise_count: u8, // Track number of raises for raise_limit
+    street: &Street,
+) -> Result<(), TreeError> {
+    if depth > 20 {
+        // Safety break for infinite recursion
+        return Ok(());
+    }
+
+    let opponent = 1 - player;
+    let current_pot = initial_pot + bets[0] + bets[1];
+    let facing_bet = bets[opponent as usize] - bets[player as usize];
+
+    // 1. Identify valid actions
+    let mut actions: Vec<(ActionType, i32)> = Vec::new();
+
+    // FOLD
+    if facing_bet > 0 {
+        actions.push((ActionType::Fold, 0));
+    }
+
+    // CHECK / CALL
+    if facing_bet == 0 {
+        actions.push((ActionType::Check, 0));
+    } else {
+        // Call amount is min(facing_bet, stack)
+        let call_amount = facing_bet.min(stacks[player as usize]);
+        actions.push((ActionType::Call, call_amount));
+    }
+
+    // BET / RAISE
+    // Only if not facing all-in and have chips
+    // Also check raise_limit for raises (not for initial bets)
+    let is_raise = facing_bet > 0;
+    let can_raise = !is_raise || raise_count < config.raise_limit;
+    let can_bet = stacks[player as usize] > facing_bet
+        && stacks[opponent as usize] > 0
+        && can_raise;
+
+    if can_bet {
+        // Nobody can be called for more than the opponent has behind, so
+        // the deeper stack shoves for the effective stack
+        let all_in = stacks[player as usize].min(facing_bet + stacks[opponent as usize]);
+
+        // The first sizing rule for this node that has sizes for it
+        let rule = if config.sizing_rules.is_empty() {
+            None
+        } else {
+            let street_name = if street.next.is_some() { StreetName::Turn } else { StreetName::River };
+            let context = line_pattern(line);
+            config.sizing_rules.iter()
+                .filter(|r| r.matches(street_name, player, &context, raise_count))
+                .find(|r| if facing_bet == 0 { r.bet_sizes.is_some() } else { r.raise_sizes.is_some() })
+        };
+
+        // Streets with betting left, this one included, for `"e"` sizes
+        let streets_left = if street.next.is_some() { 2 } else { 1 };
+
+        // A rule's menu, the donk sizes or an empty raise depth may leave no
+        // bet or raise at all
+        let mut aggress = true;
+
+        // Determine sizes
+        let sizes: Vec<i32> = if facing_bet == 0 {
+            // Bet: % of pot
+            let donk = config.donk_sizes.as_deref().filter(|_| is_donk_spot(line, player));
+            let menu = rule.and_then(|r| r.bet_sizes.as_deref()).or(donk);
+            aggress = menu.is_none_or(|sizes| !sizes.is_empty());
+            let bet_sizes = menu.unwrap_or(street.bet_sizes[player as usize]);
+            bet_sizes.iter().map(|&size| match size {
+                BetSize::PotFraction(size_pct) => pot_fraction(current_pot, size_pct),
+                BetSize::Geometric(count) => {
+                    let fraction = geometric_fraction(current_pot as f64, all_in as f64, count.unwrap_or(streets_left));
+                    pot_fraction(current_pot, fraction.min(f32::MAX as f64) as f32)
+                }
+            }).collect()
+        } else {
+            // The bet faced counts towards `raise_count`, so this raise is number `raise_count - 1`
+            let depth = raise_count.saturating_sub(1) as usize;
+            let street_sizes = street.raise_sizes[player as usize];
+            let raise_sizes = match rule.and_then(|r| r.raise_sizes.as_deref()) {
+                Some(sizes) => sizes,
+                None => {
+                    aggress = !street_sizes.forbids(depth);
+                    street_sizes.at_depth(depth)
+                }
+            };
+            raise_sizes.iter().map(|&size| match size {
+                // Raise: (call + raise_amt) where raise_amt is % of pot after call
+                RaiseSize::PotFraction(size_pct) => {
+                    let pot_after_call = current_pot + facing_bet;
+                    facing_bet + pot_fraction(pot_after_call, size_pct)
+                }
+                // Raise to a multiple of the opponent's total bet
+                RaiseSize::Multiple { x } => pot_fraction(bets[opponent as usize], x) - bets[player as usize],
+                RaiseSize::AllIn => stacks[player as usize],
+                // Geometric over this raise and the streets after it, once called
+                RaiseSize::Geometric(count) => {
+                    let pot_after_call = current_pot + facing_bet;
+                    let fraction = geometric_fraction(pot_after_call as f64, (all_in - facing_bet) as f64, count.unwrap_or(streets_left));
+                    facing_bet + pot_fraction(pot_after_call, fraction.min(f32::MAX as f64) as f32)
+                }
+            }).collect()
+        };
+
+        for mut amount in sizes {
+            // A raise must add at least the bet or raise it faces, which
+            // heads-up is the amount to call; smaller raises go up to that
+            if facing_bet > 0 && amount > 0 {
+                amount = amount.max(2 * facing_bet);
+            }
+
+            // Cap at stack (All-in), which may add less than a full raise
+            if amount >= all_in {
+                amount = all_in;
+            }
+            
+            if amount <= facing_bet {
+                continue; 
+            }
+            
+            // Avoid duplicate sizes, e.g. several raises lifted to the minimum
+            if actions.iter().any(|(t, a)| t.is_aggressive() && *a == amount) {
+                continue;
+            }
+            
+            let action_type = if facing_bet == 0 { ActionType::Bet } else { ActionType::Raise };
+            actions.push((action_type, amount));
+        }
+        
+        // Add All-in if not covered by sizes, unless the config leaves it out
+        let already_have_all_in = actions.iter().any(|(t, a)| t.is_aggressive() && *a == all_in);
+        if !already_have_all_in && all_in > facing_bet && aggress && config.add_allin {
+             let action_type = if facing_bet == 0 { ActionType::Bet } else { ActionType::Raise };
+             actions.push((action_type, all_in));
+        }
+    }
+    
+    // Sizes picked for this node replace the street's
+    if let Some(kept) = overrides.get(line.as_slice()) {
+        actions.retain(|(t, a)| !t.is_aggressive() || kept.contains(a));
+    }
+
+    // 2. Update current node
+    let num_actions = actions.len() as u8;
+    
+    // Decision points with the same player and betting sequence share an infoset
+    let infoset_id = tree.get_infoset_id(node_id, &infoset_key(player, line))?;
+    
+    let children_start = tree.nodes.len() as u32;
+    
+    {
+        let node = tree.get_node_mut(node_id);
+        node.num_actions = num_actions;
+        node.children_start = children_start; // Children will be appended next
+        node.infoset_id = infoset_id;
+    }
+    
+    // 3. Create children
+    // We must collect children indices to recurse on them, to avoid borrowing issues
+    let mut children_configs = Vec::new();
+    let mut next_streets = Vec::new();
+    let river = tree.get_node(node_id).river;
+
+    for (action_type, amount) in actions {
+        let step = Step::Action(action_type, if action_type.is_aggressive() { amount } else { 0 });
+        let mut next_node = Node::new(NodeType::Action, opponent, current_pot); // Default, updated below
+        next_node.action_from_parent = Some(action_type);
+        next_node.amount_from_parent = amount;
+        next_node.river = river;
+
+        let mut next_bets = bets;
+        let mut next_stacks = stacks;
+        let mut is_terminal = false;
+        let mut is_showdown = false;
+        let mut next_raise_count = raise_count;
+
+        match action_type {
+            ActionType::Fold => {
+                next_node.node_type = NodeType::Terminal;
+                next_node.player = opponent; // Winner of the pot
+                next_node.pot = current_pot; // Pot doesn't increase on fold
+                is_terminal = true;
+            },
+            ActionType::Check => {
+                if player == 1 { // IP checked back
+                    next_node.node_type = NodeType::Showdown;
+                    next_node.player = 255;
+                    is_showdown = true;
+                } else {
+                    // OOP checked, now IP acts
+                    next_node.node_type = NodeType::Action;
+                    next_node.player = 1;
+                }
+                // Check resets raise count (new betting round within street)
+                next_raise_count = 0;
+            },
+            ActionType::Call => {
+                next_bets[player as usize] += amount;
+                next_stacks[player as usize] -= amount;
+                next_node.pot = initial_pot + next_bets[0] + next_bets[1];
+
+                // Call ends the betting round?
+                // If closing action (IP calls or OOP calls raise)
+                // And we are on River, so round end = Showdown
+                next_node.node_type = NodeType::Showdown;
+                next_node.player = 255;
+                is_showdown = true;
+            },
+            ActionType::Bet | ActionType::Raise => {
+                next_bets[player as usize] += amount;
+                next_stacks[player as usize] -= amount;
+                next_node.pot = initial_pot + next_bets[0] + next_bets[1];
+
+                // Action passes to opponent
+                next_node.node_type = NodeType::Action;
+                next_node.player = opponent;
+
+                // Increment raise count for bet/raise actions
+                next_raise_count = raise_count + 1;
+            }
+        }
+
+        // Closing the turn betting deals the river instead of a showdown
+        let deals_river = is_showdown && street.next.is_some();
+        if deals_river {
+            next_node.node_type = NodeType::Chance;
+        }
+
+        next_node.bets = next_bets;
+        next_node.all_in = next_stacks.map(|s| s == 0);
+        let child_id = tree.add_node(next_node);
+
+        if deals_river {
+            next_streets.push((child_id, next_bets, next_stacks, step));
+        } else if !is_terminal && !is_showdown {
+            children_configs.push((child_id, opponent, next_bets, next_stacks, next_raise_count, step));
+        }
+    }
+    
+    // 4. Recurse
+    for (child_id, next_player, next_bets, next_stacks, next_raise_count, step) in children_configs {
+        line.push(step);
+        build_subtree(tree, child_id, config, overrides, line, initial_pot, next_player, next_bets, next_stacks, depth + 1, next_raise_count, street)?;
+        line.pop();
+    }
+    if let Some(next) = street.next {
+        for (chance_id, next_bets, next_stacks, step) in next_streets {
+            line.push(step);
+            deal_river(tree, chance_id, config, overrides, line, initial_pot, next_bets, next_stacks, depth + 1, next)?;
+            line.pop();
+        }
+    }
+    Ok(())
+}
+
+/// Expand chance node `node_id` with one child per river in `tree.rivers`:
+/// a river betting round started by player 0, or a showdown when a player
+/// is all in and there is nothing left to bet.
+#[allow(clippy::too_many_arguments)]
+fn deal_river(
+    tree: &mut GameTree,
+    node_id: u32,
+    config: &GameConfig,
+    overrides: &HashMap<Vec<Step>, Vec<i32>>,
+    line: &mut Vec<Step>,
+    initial_pot: i32,
+    bets: [i32; 2],
+    stacks: [i32; 2],
+    depth: u32,
+    street: &Street,
+) -> Result<(), TreeError> {
+    let pot = initial_pot + bets[0] + bets[1];
+    let runout = stacks.contains(&0);
+    let rivers = tree.rivers.clone();
+
+    let children_start = tree.nodes.len() as u32;
+    {
+        let node = tree.get_node_mut(node_id);
+        node.children_start = children_start;
+        node.num_actions = rivers.len() as u8;
+    }
+    for &card in &rivers {
+        let mut child = if runout {
+            Node::new(NodeType::Showdown, 255, pot)
+        } else {
+            Node::new(NodeType::Action, 0, pot)
+        };
+        child.bets = bets;
+        child.all_in = stacks.map(|s| s == 0);
+        child.river = Some(card);
+        tree.add_node(child);
+    }
+
+    if !runout {
+        for (i, &card) in rivers.iter().enumerate() {
+            line.push(Step::River(card));
+            build_subtree(tree, children_start + i as u32, config, overrides, line, initial_pot, 0, bets, stacks, depth + 1, 0, street)?;
+            line.pop();
+        }
+    }
+    Ok(())
+}
+
+/// `fraction` of `pot` chips, rounded to the nearest chip. The product is
+/// taken in f64, where it is exact for any realistic pot, so the rounding
+/// cannot differ between targets.
+fn pot_fraction(pot: i32, fraction: f32) -> i32 {
+    (pot as f64 * fraction as f64).round() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Child of `node_id` reached by `action`.
+    fn child(tree: &GameTree, node_id: usize, action: ActionType) -> usize {
+        tree.children(node_id as u32)
+            .find(|&c| tree.nodes[c as usize].action_from_parent == Some(action))
+            .unwrap() as usize
+    }
+
+    #[test]
+    fn test_raise_multiples() {
+        let config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.75], "raise_sizes": [{"x": 3}, 1], "raise_limit": 3, "chip_scale": 1}"#,
+        ).unwrap();
+        assert_eq!(config.raise_sizes, RaiseSizes::Flat(vec![RaiseSize::Multiple { x: 3.0 }, RaiseSize::PotFraction(1.0)]));
+        // Pot fractions still serialize as plain numbers
+        assert!(serde_json::to_string(&config).unwrap().contains(r#""raise_sizes":[{"x":3.0},1.0]"#));
+
+        let tree = build_river_tree(&config).unwrap();
+        let bet = child(&tree, 0, ActionType::Bet);
+        assert_eq!(tree.nodes[bet].amount_from_parent, 75);
+
+        // Facing 75, 3x raises to 225; the pot-sized raise calls 75 and adds 250
+        let facing = &tree.nodes[bet];
+        let raises: Vec<i32> = (0..facing.num_actions as usize)
+            .map(|a| &tree.nodes[facing.children_start as usize + a])
+            .filter(|c| c.action_from_parent == Some(ActionType::Raise))
+            .map(|c| c.amount_from_parent)
+            .collect();
+        assert_eq!(raises, vec![225, 325, 1000]);
+
+        // Facing 225 with 75 already in, 3x raises to 675 by adding 600
+        let raise = child(&tree, bet, ActionType::Raise);
+        let reraise = child(&tree, raise, ActionType::Raise);
+        assert_eq!(tree.nodes[reraise].amount_from_parent, 600);
+        assert_eq!(tree.nodes[reraise].bets, [675, 225]);
+    }
+
+    #[test]
+    fn test_raise_sizes_by_depth() {
+        let config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.5], "raise_sizes": [[1], ["allin"]], "raise_limit": 3, "chip_scale": 1}"#,
+        ).unwrap();
+        let tree = build_river_tree(&config).unwrap();
+        let raises = |node: usize| -> Vec<i32> {
+            tree.children(node as u32)
+                .map(|c| &tree.nodes[c as usize])
+                .filter(|c| c.action_from_parent == Some(ActionType::Raise))
+                .map(|c| c.amount_from_parent)
+                .collect()
+        };
+
+        // Facing 50, the first raise menu: pot-sized (call 50, add 200) and the shove
+        let bet = child(&tree, 0, ActionType::Bet);
+        assert_eq!(raises(bet), vec![250, 1000]);
+        // Facing the raise to 250, only the all-in is left
+        let raise = child(&tree, bet, ActionType::Raise);
+        assert_eq!(raises(raise), vec![950]);
+        assert_eq!(tree.nodes[raise].num_actions, 3);
+
+        // After a check the bet is the first aggressive action again
+        let check_bet = child(&tree, child(&tree, 0, ActionType::Check), ActionType::Bet);
+        assert_eq!(raises(check_bet), vec![250, 1000]);
+
+        // An empty menu ends the raising at its depth
+        let config = GameConfig { raise_sizes: serde_json::from_str("[[2.5], [1.0], []]").unwrap(), raise_limit: 5, ..config };
+        assert!(config.raise_sizes.forbids(2) && config.raise_sizes.forbids(4) && !config.raise_sizes.forbids(1));
+        let tree = build_river_tree(&config).unwrap();
+        let bet = child(&tree, 0, ActionType::Bet);
+        let raise = child(&tree, bet, ActionType::Raise);
+        let reraise = child(&tree, raise, ActionType::Raise);
+        assert_eq!(tree.children(reraise as u32).map(|c| tree.nodes[c as usize].action_from_parent.unwrap()).collect::<Vec<_>>(), [ActionType::Fold, ActionType::Call]);
+        // `["allin"]` at a depth still shoves
+        assert!(!RaiseSizes::ByDepth(vec![vec![RaiseSize::AllIn]]).forbids(0));
+    }
+
+    #[test]
+    fn test_min_raise() {
+        let config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [1], "raise_sizes": [{"x": 1.5}, 0.1, 1], "raise_limit": 3, "chip_scale": 1}"#,
+        ).unwrap();
+        let tree = build_river_tree(&config).unwrap();
+        let raises = |node: usize| -> Vec<i32> {
+            tree.children(node as u32)
+                .map(|c| &tree.nodes[c as usize])
+                .filter(|c| c.action_from_parent == Some(ActionType::Raise))
+                .map(|c| c.amount_from_parent)
+                .collect()
+        };
+
+        // Facing 100, raising to 150 or adding 30 are too small: both become
+        // the min-raise to 200; the pot-sized raise calls 100 and adds 300
+        let bet = child(&tree, 0, ActionType::Bet);
+        assert_eq!(raises(bet), vec![200, 400, 1000]);
+        // The min-reraise adds the 100 the raise added
+        let min_raise = tree.children(bet as u32).find(|&c| tree.nodes[c as usize].amount_from_parent == 200).unwrap() as usize;
+        assert_eq!(tree.nodes[min_raise].bets, [100, 200]);
+        assert_eq!(raises(min_raise)[0], 200);
+
+        // A shove for less than a min-raise is still allowed
+        let short: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [150, 150], "bet_sizes": [1], "raise_sizes": [0.5], "raise_limit": 3, "chip_scale": 1}"#,
+        ).unwrap();
+        let tree = build_river_tree(&short).unwrap();
+        let bet = child(&tree, 0, ActionType::Bet);
+        let shove = child(&tree, bet, ActionType::Raise);
+        assert_eq!(tree.nodes[shove].amount_from_parent, 150);
+        assert_eq!(tree.nodes[bet].num_actions, 3);
+    }
+
+    #[test]
+    fn test_uneven_stacks() {
+        let config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [300, 1000], "bet_sizes": [1], "raise_sizes": [1], "raise_limit": 2, "chip_scale": 1}"#,
+        ).unwrap();
+        let tree = build_river_tree(&config).unwrap();
+        let aggressive = |node: usize| -> Vec<i32> {
+            tree.children(node as u32)
+                .map(|c| &tree.nodes[c as usize])
+                .filter(|c| c.action_from_parent.is_some_and(|a| a.is_aggressive()))
+                .map(|c| c.amount_from_parent)
+                .collect()
+        };
+
+        // The deep stack shoves for what the short one has behind
+        assert_eq!(aggressive(0), vec![100, 300]);
+        let check = child(&tree, 0, ActionType::Check);
+        assert_eq!(aggressive(check), vec![100, 300]);
+        // A pot-sized raise of 100 would add 400; it is capped at 300 in all
+        assert_eq!(aggressive(child(&tree, 0, ActionType::Bet)), vec![300]);
+
+        // Nobody wins chips the other did not put in
+        let shove = tree.children(check as u32).last().unwrap() as usize;
+        assert_eq!(tree.nodes[shove].bets, [0, 300]);
+        assert!(!tree.nodes[shove].all_in[1]);
+        let fold = &tree.nodes[child(&tree, shove, ActionType::Fold)];
+        assert_eq!(tree.outcome(fold).p1_wins, [-50.0, 50.0]);
+        let call = &tree.nodes[child(&tree, shove, ActionType::Call)];
+        assert_eq!(call.all_in, [true, false]);
+        assert_eq!(tree.outcome(call), crate::solver::arena::Outcome { p0_wins: [350.0, -350.0], p1_wins: [-350.0, 350.0] });
+    }
+
+    fn config() -> GameConfig {
+        serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.5, 1], "raise_sizes": [1], "raise_limit": 2, "chip_scale": 1}"#,
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_infoset_collisions_are_reported() {
+        let config = config();
+        let river = Street::of(&config, false, None);
+        // Every key hashes alike: the second decision node, the check, collides with the root
+        let result = build_tree(&config, Vec::new(), 0.0, &river, |_| 7);
+        assert_eq!(result.unwrap_err(), TreeError::CollisionDetected { node: 1, infoset: 0 });
+        assert_eq!(TreeError::CollisionDetected { node: 1, infoset: 0 }.to_string(), "node 1: infoset key hash collides with infoset 0");
+        assert!(build_tree(&config, Vec::new(), 0.0, &river, infoset_hash).is_ok());
+    }
+
+    #[test]
+    fn test_infoset_ids_are_stable() {
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let (a, b) = (build_turn_tree(&config(), &board).unwrap(), build_turn_tree(&config(), &board).unwrap());
+        assert_eq!(a.infoset_map, b.infoset_map);
+        assert_eq!(a.infoset_keys, b.infoset_keys);
+        assert!(a.nodes.iter().zip(&b.nodes).all(|(x, y)| x.infoset_id == y.infoset_id));
+        // Keys hash with FNV-1a, not a per-process random hasher
+        assert_eq!(a.infoset_keys[0], [0]);
+        assert_eq!(a.infoset_map[&0xaf63_bd4c_8601_b7df], 0);
+    }
+
+    #[test]
+    fn test_infoset_keys_are_betting_sequences() {
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let tree = build_turn_tree(&config(), &board).unwrap();
+        let key = |node: usize| &tree.infoset_keys[tree.nodes[node].infoset_id as usize];
+
+        // Player 1 after a check, then player 0 after a bet of 50 and a call
+        let check = child(&tree, 0, ActionType::Check);
+        assert_eq!(key(check), &[1, 1, 0, 0, 0, 0]);
+        let bet = child(&tree, check, ActionType::Bet);
+        assert_eq!(key(bet), &[0, 1, 0, 0, 0, 0, 3, 50, 0, 0, 0]);
+
+        // Every river starts its own infoset, keyed by the card
+        let chance = child(&tree, check, ActionType::Check);
+        for (child, &card) in tree.children(chance as u32).zip(&tree.rivers) {
+            assert_eq!(key(child as usize), &[0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, u8::MAX, card]);
+        }
+        let decisions = tree.nodes.iter().filter(|n| n.node_type == NodeType::Action).count();
+        assert_eq!(tree.infoset_map.len(), decisions);
+    }
+
+    #[test]
+    fn test_sizing_rules() {
+        assert_eq!(line_pattern(&[]), "");
+        assert_eq!(line_pattern(&[Step::Action(ActionType::Check, 0), Step::Action(ActionType::Bet, 75)]), "x-b");
+        assert_eq!(line_pattern(&[Step::Action(ActionType::Check, 0), Step::Action(ActionType::Check, 0), Step::River(3)]), "x-x|");
+
+        // Smaller bets once player 0 checks
+        let config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.75], "raise_sizes": [], "raise_limit": 0, "chip_scale": 1,
+                "sizing_rules": [{"after": "x", "bet_sizes": [0.33]}]}"#,
+        ).unwrap();
+        let tree = build_river_tree(&config).unwrap();
+        let bets = |tree: &GameTree, node: usize| -> Vec<i32> {
+            tree.children(node as u32)
+                .map(|c| &tree.nodes[c as usize])
+                .filter(|c| c.action_from_parent == Some(ActionType::Bet))
+                .map(|c| c.amount_from_parent)
+                .collect()
+        };
+        assert_eq!(bets(&tree, 0), vec![75, 1000]);
+        assert_eq!(bets(&tree, child(&tree, 0, ActionType::Check)), vec![33, 1000]);
+
+        // A delayed river bet after the turn checks through
+        let mut config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.5], "raise_sizes": [], "raise_limit": 0, "chip_scale": 1,
+                "river_cards": {"sampled": {"count": 1, "seed": 7}},
+                "sizing_rules": [{"street": "river", "after": "x-x|*", "player": 1, "bet_sizes": [1.5]}]}"#,
+        ).unwrap();
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let tree = build_turn_tree(&config, &board).unwrap();
+        let checked_through = child(&tree, child(&tree, 0, ActionType::Check), ActionType::Check);
+        let river = tree.nodes[checked_through].children_start as usize;
+        assert_eq!(bets(&tree, river), vec![50, 1000]);
+        assert_eq!(bets(&tree, child(&tree, river, ActionType::Check)), vec![150, 1000]);
+        let called = child(&tree, child(&tree, 0, ActionType::Bet), ActionType::Call);
+        let river = tree.nodes[called].children_start as usize;
+        assert_eq!(bets(&tree, child(&tree, river, ActionType::Check)), vec![100, 950]);
+
+        // Rules without sizes for the node fall through to the street's
+        config.sizing_rules[0].bet_sizes = None;
+        let tree = build_turn_tree(&config, &board).unwrap();
+        let river = tree.nodes[checked_through].children_start as usize;
+        assert_eq!(bets(&tree, child(&tree, river, ActionType::Check)), vec![50, 1000]);
+    }
+
+    #[test]
+    fn test_player_sizes() {
+        let config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.5], "raise_sizes": [1.0], "raise_limit": 2, "chip_scale": 1,
+                "river_cards": {"sampled": {"count": 1, "seed": 7}},
+                "player_sizes": [{"raise_sizes": [{"x": 3}]}, {"bet_sizes": [0.25, 1.5], "river_bet_sizes": [2.0]}]}"#,
+        ).unwrap();
+        let amounts = |tree: &GameTree, node: usize| -> Vec<i32> {
+            tree.children(node as u32)
+                .map(|c| &tree.nodes[c as usize])
+                .filter(|c| c.action_from_parent.is_some_and(|a| a.is_aggressive()))
+                .map(|c| c.amount_from_parent)
+                .collect()
+        };
+        use ActionType::*;
+        let tree = build_river_tree(&config).unwrap();
+        assert_eq!(amounts(&tree, 0), vec![50, 1000]);
+        let check = child(&tree, 0, Check);
+        assert_eq!(amounts(&tree, check), vec![25, 150, 1000]);
+        // Player 0 raises to three times the bet, player 1 by the pot
+        assert_eq!(amounts(&tree, child(&tree, check, Bet)), vec![75, 1000]);
+        assert_eq!(amounts(&tree, child(&tree, 0, Bet)), vec![50 + 200, 1000]);
+
+        // On the turn tree's river player 1's own river sizes come first
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let tree = build_turn_tree(&config, &board).unwrap();
+        let river = tree.nodes[child(&tree, child(&tree, 0, Check), Check)].children_start as usize;
+        assert_eq!(amounts(&tree, river), vec![50, 1000]);
+        assert_eq!(amounts(&tree, child(&tree, river, Check)), vec![200, 1000]);
+
+        assert!(serde_json::to_value(&config).unwrap()["player_sizes"][1]["bet_sizes"].is_array());
+        let shared = GameConfig { player_sizes: Default::default(), ..config };
+        assert!(serde_json::to_value(&shared).unwrap().get("player_sizes").is_none());
+    }
+
+    #[test]
+    fn test_geometric_sizes() {
+        let config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.5, "e", "2e"], "raise_sizes": ["e"], "raise_limit": 2, "chip_scale": 1,
+                "river_cards": {"sampled": {"count": 1, "seed": 7}}}"#,
+        ).unwrap();
+        assert_eq!(config.bet_sizes[1..], [BetSize::Geometric(None), BetSize::Geometric(Some(2))]);
+        assert_eq!(serde_json::to_value(&config.bet_sizes).unwrap(), serde_json::json!([0.5, "e", "2e"]));
+        for bad in [r#"["0e"]"#, r#"["x"]"#, r#"["ee"]"#] {
+            assert!(serde_json::from_str::<Vec<BetSize>>(bad).is_err(), "{}", bad);
+        }
+
+        let amounts = |tree: &GameTree, node: usize| -> Vec<i32> {
+            tree.children(node as u32)
+                .map(|c| &tree.nodes[c as usize])
+                .filter(|c| c.action_from_parent.is_some_and(|a| a.is_aggressive()))
+                .map(|c| c.amount_from_parent)
+                .collect()
+        };
+        // On the river "e" is the shove; two pot-fractions of 1.79 get 1000 in
+        let tree = build_river_tree(&config).unwrap();
+        assert_eq!(amounts(&tree, 0), vec![50, 1000, 179]);
+
+        // From the turn "e" spreads the stacks over both streets
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let tree = build_turn_tree(&config, &board).unwrap();
+        assert_eq!(amounts(&tree, 0), vec![50, 179, 1000]);
+        let bet = child(&tree, 0, ActionType::Bet);
+        // Facing 50, the raise calls and bets 1.12 of the 200 pot
+        assert_eq!(amounts(&tree, bet), vec![50 + 224, 1000]);
+        // After "e" is called the river "e" shoves
+        let geometric = tree.children(0).nth(2).unwrap();
+        let river = tree.nodes[child(&tree, geometric as usize, ActionType::Call)].children_start as usize;
+        assert_eq!(amounts(&tree, river), vec![229, 821, 261]);
+    }
+
+    #[test]
+    fn test_add_allin() {
+        let mut config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.5], "raise_sizes": [1.0], "raise_limit": 4, "chip_scale": 1, "add_allin": false}"#,
+        ).unwrap();
+        let amounts = |tree: &GameTree, node: usize| -> Vec<i32> {
+            tree.children(node as u32)
+                .map(|c| &tree.nodes[c as usize])
+                .filter(|c| c.action_from_parent.is_some_and(|a| a.is_aggressive()))
+                .map(|c| c.amount_from_parent)
+                .collect()
+        };
+        let tree = build_river_tree(&config).unwrap();
+        assert_eq!(amounts(&tree, 0), vec![50]);
+        let bet = child(&tree, 0, ActionType::Bet);
+        assert_eq!(amounts(&tree, bet), vec![250]);
+        let raise = child(&tree, bet, ActionType::Raise);
+        assert_eq!(amounts(&tree, raise), vec![800]);
+        // Sizes past the stack still shove
+        let reraise = child(&tree, raise, ActionType::Raise);
+        assert_eq!(amounts(&tree, reraise), vec![750]);
+
+        config.add_allin = true;
+        assert_eq!(amounts(&build_river_tree(&config).unwrap(), 0), vec![50, 1000]);
+        assert!(serde_json::to_value(&config).unwrap().get("add_allin").is_none());
+    }
+
+    #[test]
+    fn test_donk_sizes() {
+        let mut config: GameConfig = serde_json::from_str(
+            r#"{"initial_pot": 100, "stacks": [1000, 1000], "bet_sizes": [0.5], "raise_sizes": [1.0], "raise_limit": 2, "chip_scale": 1,
+                "river_cards": {"sampled": {"count": 1, "seed": 7}}, "donk_sizes": [0.25]}"#,
+        ).unwrap();
+        let board: Vec<Card> = "Ks Th 7s 4d".split_whitespace().filter_map(Card::from_str).collect();
+        let leads = |config: &GameConfig, turn: &[ActionType]| -> Vec<i32> {
+            let tree = build_turn_tree(config, &board).unwrap();
+            let end = turn.iter().fold(0, |node, &action| child(&tree, node, action));
+            let river = tree.nodes[end].children_start;
+            tree.children(river)
+                .map(|c| &tree.nodes[c as usize])
+                .filter(|c| c.action_from_parent == Some(ActionType::Bet))
+                .map(|c| c.amount_from_parent)
+                .collect()
+        };
+        use ActionType::*;
+        // Player 1 bet the turn: player 0's river lead is a donk
+        assert_eq!(leads(&config, &[Check, Bet, Call]), vec![50, 950]);
+        // Player 0 kept the initiative, or nobody bet
+        assert_eq!(leads(&config, &[Bet, Call]), vec![100, 950]);
+        assert_eq!(leads(&config, &[Check, Bet, Raise, Call]), vec![300, 750]);
+        assert_eq!(leads(&config, &[Check, Check]), vec![50, 1000]);
+
+        // No donking at all, not even all in
+        config.donk_sizes = Some(vec![]);
+        assert!(leads(&config, &[Check, Bet, Call]).is_empty());
+        assert_eq!(leads(&config, &[Bet, Call]), vec![100, 950]);
+    }
+}
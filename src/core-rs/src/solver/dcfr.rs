@@ -1,424 +1,1105 @@
-//! Discounted CFR (DCFR) Engine.
-//!
-//! Implements the core CFR algorithm with discounted regret updates.
-//! Based on TexasSolver implementation.
-
-use crate::solver::arena::{GameTree, NodeType};
-
-/// Local log macro for console output
-macro_rules! log {
-    ($($t:tt)*) => (web_sys::console::log_1(&format!($($t)*).into()))
-}
-
-/// DCFR Discount parameters (from TexasSolver).
-const ALPHA: f32 = 1.5;
-const BETA: f32 = 0.5;
-const GAMMA: f32 = 2.0;
-const THETA: f32 = 0.9;
-
-/// The DCFR Trainer holding the mutable state of the solver.
-pub struct DCFRTrainer {
-    /// Accumulated regrets R+ for each action in each infoset.
-    /// Flattened: [infoset_id * max_hands * max_actions + hand_idx * max_actions + action_idx]
-    pub regrets: Vec<f32>,
-
-    /// Accumulated strategy for averaging (cum_r_plus in TexasSolver).
-    /// Flattened same as regrets.
-    pub strategy_sum: Vec<f32>,
-
-    /// Sum of positive regrets for regret matching.
-    /// Flattened: [infoset_id * max_hands + hand_idx]
-    regret_sum: Vec<f32>,
-
-    /// Maximum number of actions for any node.
-    max_actions: usize,
-
-    /// Maximum number of hands (max(range0, range1)).
-    max_hands: usize,
-
-    /// Number of hands for each player.
-    num_hands: [usize; 2],
-
-    /// Current iteration count.
-    pub iterations: usize,
-}
-
-impl DCFRTrainer {
-    pub fn max_actions(&self) -> usize {
-        self.max_actions
-    }
-
-    /// Get average strategy for a specific infoset and hand.
-    ///
-    /// # Arguments
-    /// * `infoset_id` - The infoset ID
-    /// * `hand_idx` - Index of the hand in the range
-    /// * `num_actions` - Optional: actual number of actions at this node (for correct uniform)
-    pub fn get_average_strategy(&self, infoset_id: usize, hand_idx: usize) -> Vec<f32> {
-        self.get_average_strategy_with_actions(infoset_id, hand_idx, self.max_actions)
-    }
-
-    /// Get average strategy with specific number of actions
-    pub fn get_average_strategy_with_actions(&self, infoset_id: usize, hand_idx: usize, num_actions: usize) -> Vec<f32> {
-        let mut strategy = vec![0.0; self.max_actions];
-        let base_idx = infoset_id * self.max_hands * self.max_actions + hand_idx * self.max_actions;
-
-        // Debug: Log the raw strategy_sum values
-        let raw_values: Vec<f32> = (0..num_actions.min(self.max_actions))
-            .map(|a| self.strategy_sum[base_idx + a])
-            .collect();
-
-        let mut sum = 0.0;
-        // Only sum over actual actions at this node
-        for a in 0..num_actions.min(self.max_actions) {
-            let s = self.strategy_sum[base_idx + a];
-            if s > 0.0 {
-                strategy[a] = s;
-                sum += s;
-            }
-        }
-
-        if sum > 0.0 {
-            for a in 0..num_actions.min(self.max_actions) {
-                strategy[a] /= sum;
-            }
-            log!("[get_average_strategy] infoset={}, hand={}, num_actions={}, sum={:.4}, strategy={:?}",
-                 infoset_id, hand_idx, num_actions, sum, &strategy[0..num_actions]);
-        } else {
-            // Default uniform - use actual num_actions for correct probability
-            let prob = 1.0 / num_actions as f32;
-            for a in 0..num_actions.min(self.max_actions) {
-                strategy[a] = prob;
-            }
-            log!("[get_average_strategy] UNIFORM FALLBACK! infoset={}, hand={}, num_actions={}, raw_values={:?}",
-                 infoset_id, hand_idx, num_actions, raw_values);
-        }
-
-        strategy
-    }
-
-    /// Create a new trainer initialized with zero regrets.
-    pub fn new(num_infosets: usize, max_actions: usize, num_hands: [usize; 2]) -> Self {
-        let max_h = num_hands[0].max(num_hands[1]);
-        let size = num_infosets * max_h * max_actions;
-        let sum_size = num_infosets * max_h;
-
-        Self {
-            regrets: vec![0.0; size],
-            strategy_sum: vec![0.0; size],
-            regret_sum: vec![0.0; sum_size],
-            max_actions,
-            max_hands: max_h,
-            num_hands,
-            iterations: 0,
-        }
-    }
-
-    /// Run CFR iterations with DCFR discounting.
-    pub fn train(&mut self, tree: &GameTree, equity_matrix: &[f32], iterations: usize, initial_reach: &[Vec<f32>; 2]) {
-        for _ in 0..iterations {
-            self.iterations += 1;
-            let iter = self.iterations;
-            let is_first = iter == 1;
-
-            if is_first {
-                log!("[DCFRTrainer::train] First iteration running...");
-            }
-
-            // Run CFR traversal (regrets accumulate without discounting in cfr())
-            let (u0, u1) = self.cfr(tree, equity_matrix, 0, &initial_reach[0], &initial_reach[1]);
-
-            // Apply DCFR discounting to all regrets and update strategy sum
-            self.apply_dcfr_discount(iter);
-
-            if is_first {
-                // Log root utility
-                let u0_sum: f32 = u0.iter().sum();
-                let u1_sum: f32 = u1.iter().sum();
-                log!("[DCFRTrainer::train] Root utility - U0 sum: {:.4}, U1 sum: {:.4}", u0_sum, u1_sum);
-                if !u0.is_empty() {
-                    log!("[DCFRTrainer::train] U0 sample [0..min(3,len)]: {:?}",
-                         &u0[0..u0.len().min(3)]);
-                }
-
-                // Log first 5 regret values AFTER update
-                let regret_sample: Vec<f32> = self.regrets.iter().take(5).cloned().collect();
-                log!("[DCFRTrainer::train] First 5 regrets AFTER discount: {:?}", regret_sample);
-
-                // Check if all regrets are zero
-                let non_zero_regrets = self.regrets.iter().filter(|&&r| r != 0.0).count();
-                log!("[DCFRTrainer::train] Non-zero regrets: {} / {}", non_zero_regrets, self.regrets.len());
-
-                // Also log strategy_sum
-                let strat_sample: Vec<f32> = self.strategy_sum.iter().take(5).cloned().collect();
-                log!("[DCFRTrainer::train] First 5 strategy_sum AFTER discount: {:?}", strat_sample);
-                let non_zero_strat = self.strategy_sum.iter().filter(|&&s| s != 0.0).count();
-                log!("[DCFRTrainer::train] Non-zero strategy_sum: {} / {}", non_zero_strat, self.strategy_sum.len());
-            }
-        }
-    }
-
-    /// Apply DCFR discounting to regrets and update strategy sum.
-    /// This mirrors TexasSolver's DiscountedCfrTrainable::updateRegrets.
-    fn apply_dcfr_discount(&mut self, iteration: usize) {
-        let t = iteration as f32;
-
-        // alpha_coef = t^alpha / (1 + t^alpha)
-        let alpha_pow = t.powf(ALPHA);
-        let alpha_coef = alpha_pow / (1.0 + alpha_pow);
-
-        // strategy_coef = (t / (t+1))^gamma
-        let strategy_coef = (t / (t + 1.0)).powf(GAMMA);
-
-        // Reset regret sums
-        self.regret_sum.fill(0.0);
-
-        // Apply discounting to all regrets
-        for i in 0..self.regrets.len() {
-            let r = self.regrets[i];
-
-            // Apply DCFR discount
-            if r > 0.0 {
-                self.regrets[i] = r * alpha_coef;
-            } else {
-                self.regrets[i] = r * BETA;
-            }
-        }
-
-        // Recompute regret sums for regret matching
-        let num_infosets = self.regret_sum.len() / self.max_hands;
-        for infoset in 0..num_infosets {
-            for h in 0..self.max_hands {
-                let sum_idx = infoset * self.max_hands + h;
-                let base_idx = infoset * self.max_hands * self.max_actions + h * self.max_actions;
-                let mut sum = 0.0;
-                for a in 0..self.max_actions {
-                    let r = self.regrets[base_idx + a];
-                    if r > 0.0 {
-                        sum += r;
-                    }
-                }
-                self.regret_sum[sum_idx] = sum;
-            }
-        }
-
-        // Update strategy_sum using DCFR formula:
-        // cum_r_plus *= theta
-        // cum_r_plus += current_strategy * strategy_coef
-        for infoset in 0..num_infosets {
-            for h in 0..self.max_hands {
-                let sum_idx = infoset * self.max_hands + h;
-                let base_idx = infoset * self.max_hands * self.max_actions + h * self.max_actions;
-                let r_sum = self.regret_sum[sum_idx];
-
-                for a in 0..self.max_actions {
-                    let idx = base_idx + a;
-
-                    // Compute current strategy via regret matching
-                    let current_strat = if r_sum > 0.0 {
-                        let r = self.regrets[idx];
-                        if r > 0.0 { r / r_sum } else { 0.0 }
-                    } else {
-                        1.0 / self.max_actions as f32
-                    };
-
-                    // DCFR strategy accumulation
-                    self.strategy_sum[idx] = self.strategy_sum[idx] * THETA + current_strat * strategy_coef;
-                }
-            }
-        }
-    }
-
-    /// Recursive CFR function.
-    /// Returns (U0, U1) utility vectors.
-    fn cfr(
-        &mut self,
-        tree: &GameTree,
-        equity_matrix: &[f32],
-        node_idx: u32,
-        reach0: &[f32],
-        reach1: &[f32],
-    ) -> (Vec<f32>, Vec<f32>) {
-        let node = tree.get_node(node_idx);
-        
-        match node.node_type {
-            NodeType::Terminal => {
-                // Terminal (Fold)
-                // node.player contains the winner (opponent of folder)
-                //
-                // ZERO-SUM PAYOFF:
-                // - Winner gains pot/2, Loser loses pot/2
-                // - This ensures u0 + u1 = 0 (zero-sum game)
-                let winner = node.player;
-                let half_pot = node.pot / 2.0;
-
-                let u0_val = if winner == 0 { half_pot } else { -half_pot };
-                let u1_val = if winner == 1 { half_pot } else { -half_pot };
-
-                (vec![u0_val; self.num_hands[0]], vec![u1_val; self.num_hands[1]])
-            },
-            NodeType::Showdown => {
-                // Showdown - ZERO-SUM PAYOFF
-                //
-                // For a zero-sum game:
-                // - U0 = (equity - 0.5) * pot (profit/loss relative to fair share)
-                // - U1 = -U0 = (0.5 - equity) * pot
-                //
-                // When equity = 1 (P0 wins): U0 = +pot/2, U1 = -pot/2
-                // When equity = 0 (P0 loses): U0 = -pot/2, U1 = +pot/2
-                // When equity = 0.5 (tie): U0 = 0, U1 = 0
-
-                let mut u0 = vec![0.0; self.num_hands[0]];
-                let mut u1 = vec![0.0; self.num_hands[1]];
-                let n0 = self.num_hands[0];
-                let n1 = self.num_hands[1];
-                let pot = node.pot;
-
-                // Compute U0 - weighted by opponent's reach probabilities
-                for h0 in 0..n0 {
-                    let mut weighted_equity = 0.0;
-                    let mut total_weight = 0.0;
-
-                    for h1 in 0..n1 {
-                        let eq = equity_matrix[h0 * n1 + h1];
-                        if !eq.is_nan() {
-                            weighted_equity += eq * reach1[h1];
-                            total_weight += reach1[h1];
-                        }
-                    }
-
-                    // Zero-sum: (equity - 0.5) * pot
-                    if total_weight > 0.0 {
-                        let avg_equity = weighted_equity / total_weight;
-                        u0[h0] = (avg_equity - 0.5) * pot * total_weight;
-                    }
-                }
-
-                // Compute U1 - weighted by opponent's reach probabilities
-                for h1 in 0..n1 {
-                    let mut weighted_equity = 0.0;
-                    let mut total_weight = 0.0;
-
-                    for h0 in 0..n0 {
-                        let eq = equity_matrix[h0 * n1 + h1];
-                        if !eq.is_nan() {
-                            // P1 equity = 1 - P0 equity
-                            weighted_equity += (1.0 - eq) * reach0[h0];
-                            total_weight += reach0[h0];
-                        }
-                    }
-
-                    // Zero-sum: (equity - 0.5) * pot for P1
-                    if total_weight > 0.0 {
-                        let avg_equity = weighted_equity / total_weight;
-                        u1[h1] = (avg_equity - 0.5) * pot * total_weight;
-                    }
-                }
-
-                (u0, u1)
-            },
-            NodeType::Action => {
-                let player = node.player as usize;
-                let num_actions = node.num_actions as usize;
-                let infoset_id = node.infoset_id as usize;
-                let n_hands = self.num_hands[player];
-                
-                // 1. Get Strategy (Regret Matching)
-                let mut strategy = vec![0.0; n_hands * num_actions];
-                let base_idx = infoset_id * self.max_hands * self.max_actions;
-                
-                for h in 0..n_hands {
-                    let mut sum_pos_regret = 0.0;
-                    for a in 0..num_actions {
-                        let r = self.regrets[base_idx + h * self.max_actions + a];
-                        if r > 0.0 {
-                            sum_pos_regret += r;
-                        }
-                    }
-                    
-                    for a in 0..num_actions {
-                        let idx = h * num_actions + a;
-                        if sum_pos_regret > 0.0 {
-                            let r = self.regrets[base_idx + h * self.max_actions + a];
-                            strategy[idx] = if r > 0.0 { r / sum_pos_regret } else { 0.0 };
-                        } else {
-                            strategy[idx] = 1.0 / num_actions as f32;
-                        }
-                    }
-                }
-                
-                // 2. Recurse
-                let mut u0_node = vec![0.0; self.num_hands[0]];
-                let mut u1_node = vec![0.0; self.num_hands[1]];
-                
-                // Store child utilities for active player to update regrets
-                // [action][hand]
-                let mut active_child_utils = Vec::with_capacity(num_actions);
-                
-                let children_start = node.children_start;
-                
-                for a in 0..num_actions {
-                    let child_idx = children_start + a as u32;
-                    
-                    // Update reach probs
-                    let mut next_reach0 = reach0.to_vec();
-                    let mut next_reach1 = reach1.to_vec();
-                    
-                    if player == 0 {
-                        for h in 0..n_hands {
-                            next_reach0[h] *= strategy[h * num_actions + a];
-                        }
-                    } else {
-                        for h in 0..n_hands {
-                            next_reach1[h] *= strategy[h * num_actions + a];
-                        }
-                    }
-                    
-                    let (u0_child, u1_child) = self.cfr(tree, equity_matrix, child_idx, &next_reach0, &next_reach1);
-                    
-                    // Accumulate node utilities
-                    if player == 0 {
-                        // P0 is active
-                        // U0[h] += sigma[h][a] * U0_child[h]
-                        for h in 0..self.num_hands[0] {
-                            u0_node[h] += strategy[h * num_actions + a] * u0_child[h];
-                        }
-                        // U1[h] += U1_child[h] (sum over actions)
-                        for h in 0..self.num_hands[1] {
-                            u1_node[h] += u1_child[h];
-                        }
-                        active_child_utils.push(u0_child);
-                    } else {
-                        // P1 is active
-                        // U1[h] += sigma[h][a] * U1_child[h]
-                        for h in 0..self.num_hands[1] {
-                            u1_node[h] += strategy[h * num_actions + a] * u1_child[h];
-                        }
-                        // U0[h] += U0_child[h]
-                        for h in 0..self.num_hands[0] {
-                            u0_node[h] += u0_child[h];
-                        }
-                        active_child_utils.push(u1_child);
-                    }
-                }
-                
-                // 3. Update Regrets (for active player)
-                // Strategy sum is updated in apply_dcfr_discount() after full traversal
-                let node_util = if player == 0 { &u0_node } else { &u1_node };
-
-                for h in 0..n_hands {
-                    for a in 0..num_actions {
-                        let regret = active_child_utils[a][h] - node_util[h];
-                        let idx = base_idx + h * self.max_actions + a;
-
-                        // Accumulate raw regret (discounting applied after iteration)
-                        self.regrets[idx] += regret;
-                    }
-                }
-
-                (u0_node, u1_node)
-            },
-            NodeType::Chance => (vec![], vec![]), // Should not happen in River subgame builder
-        }
-    }
-}
+//! Discounted CFR (DCFR) Engine.
+//!
+//! Implements the core CFR algorithm with discounted regret updates.
+//! Based on TexasSolver implementation.
+//!
+//! Each iteration traverses the tree once per player, player 0 first: a
+//! traversal updates only the traversing player's regrets and average
+//! strategy, so player 1's pass already plays against player 0's updated
+//! strategy (alternating updates).
+//!
+//! With the rayon pool available (see `crate::parallel`), each traversal
+//! visits the children of a node on separate threads. Every infoset
+//! belongs to one node, so the subtrees update disjoint regret rows: the
+//! threads read the regrets as of the start of the traversal and hand
+//! their updates back, which are added once the traversal is done. The
+//! result is the same as the sequential traversal's, bit for bit.
+
+use std::collections::VecDeque;
+
+use log::trace;
+use serde::Serialize;
+
+use crate::platform;
+use crate::simd;
+use crate::solver::arena::{GameTree, Node, NodeType, Outcome};
+
+/// DCFR Discount parameters (from TexasSolver).
+const ALPHA: f32 = 1.5;
+const BETA: f32 = 0.5;
+const GAMMA: f32 = 2.0;
+
+/// Tolerance of the invariant checks (see [`DCFRTrainer::check_invariants`]):
+/// on each strategy row's sum, and on the root's zero-sum identity relative
+/// to the size of the utilities.
+const INVARIANT_EPSILON: f32 = 1e-4;
+
+/// Violations an [`InvariantReport`] keeps; later ones are only counted.
+const MAX_VIOLATIONS: usize = 100;
+
+/// Which invariant a traversal broke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvariantKind {
+    /// A utility returned by a node is NaN or infinite.
+    NonFiniteUtility,
+    /// A hand's current strategy does not sum to 1.
+    StrategySum,
+    /// A hand's current strategy has a negative or NaN probability.
+    NegativeProbability,
+    /// Reach-weighted root utilities of the two players do not cancel out
+    /// (only checked for chip EV without bounties).
+    ZeroSum,
+}
+
+/// One broken invariant: where, when, and the offending values.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvariantViolation {
+    pub iteration: usize,
+    pub node: u32,
+    pub kind: InvariantKind,
+    pub detail: String,
+}
+
+/// What invariant checking has found so far.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct InvariantReport {
+    /// Iterations run with checking on.
+    pub iterations: usize,
+    /// Violations found, including those past the first `MAX_VIOLATIONS`.
+    pub total: usize,
+    /// The first violations, oldest first.
+    pub violations: Vec<InvariantViolation>,
+}
+
+/// Iterations the throughput of [`PerfCounters`] is measured over.
+const PERF_WINDOW: usize = 50;
+
+/// Work done by the iterations run with counting on (see
+/// [`DCFRTrainer::count_perf`]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PerfCounters {
+    /// Iterations run with counting on.
+    pub iterations: usize,
+    /// Nodes the traversals (one per player and iteration) entered, every
+    /// visit counted.
+    pub nodes_visited: u64,
+    /// Fold and showdown payoffs computed.
+    pub terminal_evaluations: u64,
+    /// Vectors the traversals allocate (strategies, reaches and utilities,
+    /// not the scratch buffers of payoff evaluation), as modelled per node
+    /// visited rather than measured: an estimate that must be kept in step
+    /// with `cfr`.
+    pub estimated_allocations: u64,
+    /// Time spent in CFR traversals, which discount each row as they reach
+    /// it, and in discounting the rows a `train` call leaves owing, in ms.
+    pub traversal_ms: f64,
+    pub discount_ms: f64,
+    /// Throughput over the last `PERF_WINDOW` iterations; 0 until two
+    /// have finished.
+    pub iterations_per_second: f64,
+    /// When each recent iteration finished, in ms, oldest first.
+    #[serde(skip)]
+    finished: VecDeque<f64>,
+}
+
+/// The DCFR Trainer holding the mutable state of the solver.
+#[derive(Clone)]
+pub struct DCFRTrainer {
+    /// Accumulated regrets R+ for each action in each infoset.
+    /// Flattened: [infoset_id * max_hands * max_actions + hand_idx * max_actions + action_idx]
+    pub regrets: Vec<f32>,
+
+    /// Accumulated strategy for averaging (cum_r_plus in TexasSolver): each
+    /// hand's strategy weighted by its reach, discounted by DCFR's gamma.
+    /// Flattened same as regrets.
+    pub strategy_sum: Vec<f32>,
+
+    /// Maximum number of actions for any node.
+    max_actions: usize,
+
+    /// Maximum number of hands (max(range0, range1)).
+    max_hands: usize,
+
+    /// Number of hands for each player.
+    num_hands: [usize; 2],
+
+    /// Current iteration count.
+    pub iterations: usize,
+
+    /// Per infoset, the last iteration in which its regrets or strategy sum
+    /// were written with a nonzero update; drives snapshot deltas. The
+    /// discount is not a write.
+    changed_at: Vec<usize>,
+
+    /// Per infoset, the last iteration whose discount its rows have had.
+    /// Discounting is lazy (see [`DCFRTrainer::settle`]): a traversal
+    /// discounts each infoset when it reaches its node, and `train` settles
+    /// the rest before returning.
+    discounted_at: Vec<usize>,
+
+    /// Actions each hand may take, as a bit per action (see
+    /// [`DCFRTrainer::lock_actions`]); `ALL_ACTIONS` when unconstrained.
+    /// Flattened: [infoset_id * max_hands + hand_idx], and empty until the
+    /// first lock.
+    locks: Vec<u32>,
+
+    /// Findings of invariant checking; `None` while it is off.
+    invariants: Option<InvariantReport>,
+
+    /// Performance counters; `None` while counting is off.
+    perf: Option<PerfCounters>,
+}
+
+/// Heap bytes of a trainer (see [`DCFRTrainer::memory`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TrainerMemory {
+    pub regrets: usize,
+    pub strategy_sum: usize,
+    /// Change and discount stamps, and action locks.
+    pub other: usize,
+}
+
+/// Lock mask of a hand without constraints.
+const ALL_ACTIONS: u32 = u32::MAX;
+
+/// Utilities of a node for both players' hands, (U0, U1).
+type Utilities = (Vec<f32>, Vec<f32>);
+
+/// Raw regrets one infoset gathered during a parallel traversal: the index
+/// of its first entry in `regrets` and a row of `num_actions` per hand,
+/// with the strategy played and each hand's reach for the strategy sum.
+#[cfg(rayon_pool)]
+struct RegretUpdate {
+    base_idx: usize,
+    num_actions: usize,
+    regrets: Vec<f32>,
+    strategy: Vec<f32>,
+    reach: Vec<f32>,
+}
+
+impl DCFRTrainer {
+    pub fn max_actions(&self) -> usize {
+        self.max_actions
+    }
+
+    /// Get average strategy for a specific infoset and hand.
+    ///
+    /// # Arguments
+    /// * `infoset_id` - The infoset ID
+    /// * `hand_idx` - Index of the hand in the range
+    /// * `num_actions` - Optional: actual number of actions at this node (for correct uniform)
+    pub fn get_average_strategy(&self, infoset_id: usize, hand_idx: usize) -> Vec<f32> {
+        self.get_average_strategy_with_actions(infoset_id, hand_idx, self.max_actions)
+    }
+
+    /// Get average strategy with specific number of actions
+    pub fn get_average_strategy_with_actions(&self, infoset_id: usize, hand_idx: usize, num_actions: usize) -> Vec<f32> {
+        let mut strategy = vec![0.0; self.max_actions];
+        let base_idx = infoset_id * self.max_hands * self.max_actions + hand_idx * self.max_actions;
+
+        let n = num_actions.min(self.max_actions);
+        let mut sum = 0.0;
+        // Only sum over actual actions at this node
+        for (a, slot) in strategy.iter_mut().enumerate().take(n) {
+            let s = self.strategy_sum[base_idx + a];
+            if s > 0.0 {
+                *slot = s;
+                sum += s;
+            }
+        }
+
+        if sum > 0.0 {
+            for slot in strategy.iter_mut().take(n) {
+                *slot /= sum;
+            }
+            trace!("[get_average_strategy] infoset={}, hand={}, num_actions={}, sum={:.4}, strategy={:?}",
+                   infoset_id, hand_idx, num_actions, sum, &strategy[0..n]);
+        } else {
+            // Default uniform - use actual num_actions for correct probability
+            let prob = 1.0 / num_actions as f32;
+            for slot in strategy.iter_mut().take(n) {
+                *slot = prob;
+            }
+            trace!("[get_average_strategy] UNIFORM FALLBACK! infoset={}, hand={}, num_actions={}, raw_values={:?}",
+                   infoset_id, hand_idx, num_actions, &self.strategy_sum[base_idx..base_idx + n]);
+        }
+
+        strategy
+    }
+
+    /// Average strategy for an infoset and hand, without logging.
+    ///
+    /// Returns exactly `num_actions` probabilities (uniform if the hand has
+    /// not accumulated any strategy yet). Used by tree-wide passes such as
+    /// best-response evaluation, which query every hand at every node.
+    pub fn average_strategy(&self, infoset_id: usize, hand_idx: usize, num_actions: usize) -> Vec<f32> {
+        let n = num_actions.min(self.max_actions);
+        let base_idx = infoset_id * self.max_hands * self.max_actions + hand_idx * self.max_actions;
+        let sums = &self.strategy_sum[base_idx..base_idx + n];
+
+        let total: f32 = sums.iter().filter(|&&s| s > 0.0).sum();
+        if total > 0.0 {
+            sums.iter().map(|&s| if s > 0.0 { s / total } else { 0.0 }).collect()
+        } else {
+            vec![1.0 / n as f32; n]
+        }
+    }
+
+    /// Overwrite one hand's average strategy at an infoset with `probs`
+    /// (one probability per action), e.g. to evaluate a what-if strategy.
+    pub fn set_average_strategy(&mut self, infoset_id: usize, hand_idx: usize, probs: &[f32]) {
+        let base_idx = infoset_id * self.max_hands * self.max_actions + hand_idx * self.max_actions;
+        let row = &mut self.strategy_sum[base_idx..base_idx + self.max_actions];
+        row.fill(0.0);
+        row[..probs.len()].copy_from_slice(probs);
+        self.changed_at[infoset_id] = self.iterations;
+    }
+
+    /// Restrict a hand at an infoset to the actions whose bit is set in
+    /// `allowed` (bit `a` for action `a`). Regret matching then only mixes
+    /// those actions, so the average strategy built from here on respects
+    /// the lock; strategy accumulated before locking is kept.
+    pub fn lock_actions(&mut self, infoset_id: usize, hand_idx: usize, allowed: u32) {
+        if self.locks.is_empty() {
+            self.locks = vec![ALL_ACTIONS; self.changed_at.len() * self.max_hands];
+        }
+        self.locks[infoset_id * self.max_hands + hand_idx] = allowed;
+    }
+
+    /// Remove every lock.
+    pub fn clear_locks(&mut self) {
+        self.locks = Vec::new();
+    }
+
+    /// Every locked hand as `(infoset, hand, allowed)`, in infoset order.
+    pub fn locked_hands(&self) -> impl Iterator<Item = (usize, usize, u32)> + '_ {
+        self.locks.iter().enumerate()
+            .filter(|&(_, &allowed)| allowed != ALL_ACTIONS)
+            .map(|(i, &allowed)| (i / self.max_hands, i % self.max_hands, allowed))
+    }
+
+    /// Actions a hand may take at an infoset (see [`DCFRTrainer::lock_actions`]).
+    #[inline]
+    pub fn allowed_actions(&self, infoset_id: usize, hand_idx: usize) -> u32 {
+        if self.locks.is_empty() { ALL_ACTIONS } else { self.locks[infoset_id * self.max_hands + hand_idx] }
+    }
+
+    /// Copy the strategy sums of `source_infoset` in `source` (a trainer for
+    /// the same hands), and its regrets if `regrets` is set, into `infoset`,
+    /// where action `a` here is action `actions[a]` there. Warm-starts the
+    /// solve of a tree pruned from, or resized from, `source`'s.
+    pub fn copy_infoset(&mut self, source: &DCFRTrainer, source_infoset: usize, infoset: usize, actions: &[usize], regrets: bool) {
+        for h in 0..self.max_hands {
+            let base = infoset * self.block_len() + h * self.max_actions;
+            let source_base = source_infoset * source.block_len() + h * source.max_actions;
+            for (a, &from) in actions.iter().enumerate() {
+                if regrets {
+                    self.regrets[base + a] = source.regrets[source_base + from];
+                }
+                self.strategy_sum[base + a] = source.strategy_sum[source_base + from];
+            }
+        }
+        self.changed_at[infoset] = self.iterations;
+    }
+
+    /// Number of hands in each player's range.
+    pub fn num_hands(&self) -> [usize; 2] {
+        self.num_hands
+    }
+
+    /// Create a new trainer initialized with zero regrets.
+    pub fn new(num_infosets: usize, max_actions: usize, num_hands: [usize; 2]) -> Self {
+        let max_h = num_hands[0].max(num_hands[1]);
+        let size = num_infosets * max_h * max_actions;
+
+        Self {
+            regrets: vec![0.0; size],
+            strategy_sum: vec![0.0; size],
+            max_actions,
+            max_hands: max_h,
+            num_hands,
+            iterations: 0,
+            changed_at: vec![0; num_infosets],
+            discounted_at: vec![0; num_infosets],
+            locks: Vec::new(),
+            invariants: None,
+            perf: None,
+        }
+    }
+
+    /// Start over as [`DCFRTrainer::new`] would, for another tree and
+    /// hands, keeping the allocations.
+    pub fn reset(&mut self, num_infosets: usize, max_actions: usize, num_hands: [usize; 2]) {
+        let max_h = num_hands[0].max(num_hands[1]);
+        let size = num_infosets * max_h * max_actions;
+        for values in [&mut self.regrets, &mut self.strategy_sum] {
+            values.clear();
+            values.resize(size, 0.0);
+        }
+        for stamps in [&mut self.changed_at, &mut self.discounted_at] {
+            stamps.clear();
+            stamps.resize(num_infosets, 0);
+        }
+        self.max_actions = max_actions;
+        self.max_hands = max_h;
+        self.num_hands = num_hands;
+        self.iterations = 0;
+        self.locks = Vec::new();
+        self.invariants = None;
+        self.perf = None;
+    }
+
+    /// Turn invariant checking on (with an empty report) or off. While on,
+    /// every traversal checks that node utilities are finite, that current
+    /// strategies are probabilities summing to 1, and that the root is zero
+    /// sum; violations are recorded, never panicked on. It slows solving
+    /// down, so it is meant for tests and debugging.
+    pub fn check_invariants(&mut self, enabled: bool) {
+        self.invariants = enabled.then(InvariantReport::default);
+    }
+
+    /// Turn performance counting on (from zero) or off. Counting times
+    /// every iteration, so it costs a clock read or two per iteration and a
+    /// few additions per node.
+    pub fn count_perf(&mut self, enabled: bool) {
+        self.perf = enabled.then(PerfCounters::default);
+    }
+
+    /// Counters since counting was turned on, if it is.
+    pub fn perf_counters(&self) -> Option<&PerfCounters> {
+        self.perf.as_ref()
+    }
+
+    /// What invariant checking has found, if it is on.
+    pub fn invariant_report(&self) -> Option<&InvariantReport> {
+        self.invariants.as_ref()
+    }
+
+    /// Record a violation at `node` in the current iteration.
+    fn violation(&mut self, node: u32, kind: InvariantKind, detail: String) {
+        let iteration = self.iterations;
+        if let Some(report) = &mut self.invariants {
+            report.total += 1;
+            if report.violations.len() < MAX_VIOLATIONS {
+                report.violations.push(InvariantViolation { iteration, node, kind, detail });
+            }
+        }
+    }
+
+    /// Heap bytes of the trainer's tables as allocated now.
+    pub fn memory(&self) -> TrainerMemory {
+        let f32s = |v: &Vec<f32>| v.capacity() * std::mem::size_of::<f32>();
+        TrainerMemory {
+            regrets: f32s(&self.regrets),
+            strategy_sum: f32s(&self.strategy_sum),
+            other: (self.changed_at.capacity() + self.discounted_at.capacity()) * std::mem::size_of::<usize>()
+                + self.locks.capacity() * std::mem::size_of::<u32>(),
+        }
+    }
+
+    /// Bytes `new` allocates for the same arguments: regrets and strategy
+    /// sums as `f32`, plus two iteration stamps per infoset.
+    pub fn projected_bytes(num_infosets: usize, max_actions: usize, num_hands: [usize; 2]) -> usize {
+        let rows = num_infosets * num_hands[0].max(num_hands[1]);
+        2 * rows * max_actions * std::mem::size_of::<f32>() + 2 * num_infosets * std::mem::size_of::<usize>()
+    }
+
+    /// Length of one infoset's block in `regrets` and `strategy_sum`.
+    pub fn block_len(&self) -> usize {
+        self.max_hands * self.max_actions
+    }
+
+    /// Infosets whose regrets or strategy sum were written after iteration
+    /// `since`, ascending. The others only changed by the discount, which
+    /// [`DCFRTrainer::settle_all`] reapplies.
+    pub fn changed_since(&self, since: usize) -> Vec<usize> {
+        (0..self.changed_at.len()).filter(|&i| self.changed_at[i] > since).collect()
+    }
+
+    /// Record that `infosets` were (re)written as of the current iteration,
+    /// discount included, e.g. after restoring them from a checkpoint.
+    pub fn mark_changed(&mut self, infosets: impl IntoIterator<Item = usize>) {
+        for i in infosets {
+            self.changed_at[i] = self.iterations;
+            self.discounted_at[i] = self.iterations;
+        }
+    }
+
+    /// Continue counting from `iterations`, taking every row to be as of
+    /// then: copied from a solve that far along, or still zero.
+    pub fn resume_at(&mut self, iterations: usize) {
+        self.iterations = iterations;
+        self.discounted_at.fill(iterations);
+    }
+
+    /// Cheap convergence measure: the positive regret of every hand's best
+    /// action, averaged over iterations and summed over decisions, with
+    /// each hand weighted by its range weight in `reach`. Divided by the
+    /// matchup weight, vanilla CFR bounds exploitability (in chips per
+    /// matchup) by this sum; DCFR's discounting makes it a looser guide, but
+    /// it still falls as the solve converges. Zero before the first iteration.
+    pub fn regret_bound(&self, tree: &GameTree, reach: &[Vec<f32>; 2]) -> f32 {
+        if self.iterations == 0 {
+            return 0.0;
+        }
+        let mut seen = vec![false; self.changed_at.len()];
+        let mut total = 0.0;
+        for node in &tree.nodes {
+            let infoset = node.infoset_id as usize;
+            if node.node_type != NodeType::Action || std::mem::replace(&mut seen[infoset], true) {
+                continue;
+            }
+            let player = node.player as usize;
+            for (h, &weight) in reach[player].iter().enumerate() {
+                let base_idx = infoset * self.max_hands * self.max_actions + h * self.max_actions;
+                let allowed = self.allowed_actions(infoset, h);
+                let best = (0..node.num_actions as usize)
+                    .filter(|&a| allowed & (1 << a) != 0)
+                    .map(|a| self.regrets[base_idx + a])
+                    .fold(0.0f32, f32::max);
+                total += weight * best;
+            }
+        }
+        total / self.iterations as f32
+    }
+
+    /// Run CFR iterations with DCFR discounting.
+    ///
+    /// `equity_matrix` is the `num_hands[0] x num_hands[1]` matrix of the
+    /// root board; turn trees append one block per river in `tree.rivers`.
+    pub fn train(&mut self, tree: &GameTree, equity_matrix: &[f32], iterations: usize, initial_reach: &[Vec<f32>; 2]) {
+        self.train_weighted(tree, equity_matrix, None, iterations, initial_reach);
+    }
+
+    /// [`DCFRTrainer::train`] on a game whose hands stand for groups of
+    /// combos (see `solver::abstraction`). `matchup_weights`, laid out like
+    /// `equity_matrix`, holds the share of each pair of groups' combo pairs
+    /// that do not conflict; it replaces the all-or-nothing NaN masking.
+    pub fn train_weighted(
+        &mut self,
+        tree: &GameTree,
+        equity_matrix: &[f32],
+        matchup_weights: Option<&[f32]>,
+        iterations: usize,
+        initial_reach: &[Vec<f32>; 2],
+    ) {
+        let matchups = Matchups { equity: equity_matrix, weights: matchup_weights };
+        for _ in 0..iterations {
+            self.iterations += 1;
+            let iter = self.iterations;
+            let trace_first = iter == 1 && log::log_enabled!(log::Level::Trace);
+
+            if trace_first {
+                trace!("[DCFRTrainer::train] First iteration running...");
+            }
+
+            // Run CFR traversals (regrets accumulate without discounting in cfr())
+            let traversal_start = self.perf.is_some().then(platform::now_ms);
+            let (u0, u1) = self.traverse(tree, matchups, &initial_reach[0], &initial_reach[1]);
+            let traversal_end = self.perf.is_some().then(platform::now_ms);
+
+            // Chip EV without bounties is zero sum: what one player wins, the other loses
+            if let Some(report) = &mut self.invariants {
+                report.iterations += 1;
+                if tree.icm.is_none() && tree.bounty.is_none() {
+                    let weighted = |reach: &[f32], u: &[f32]| reach.iter().zip(u).map(|(r, u)| r * u).collect::<Vec<f32>>();
+                    let (w0, w1) = (weighted(&initial_reach[0], &u0), weighted(&initial_reach[1], &u1));
+                    let total: f32 = w0.iter().chain(&w1).sum();
+                    let scale: f32 = w0.iter().chain(&w1).map(|w| w.abs()).sum();
+                    if total.abs() > INVARIANT_EPSILON * scale.max(1.0) {
+                        self.violation(0, InvariantKind::ZeroSum, format!("root utilities sum to {} (magnitude {})", total, scale));
+                    }
+                }
+            }
+
+            if let (Some(perf), Some(traversal_start), Some(traversal_end)) = (&mut self.perf, traversal_start, traversal_end) {
+                let now = platform::now_ms();
+                perf.iterations += 1;
+                perf.traversal_ms += traversal_end - traversal_start;
+                if perf.finished.len() == PERF_WINDOW {
+                    perf.finished.pop_front();
+                }
+                perf.finished.push_back(now);
+                let span = now - perf.finished[0];
+                if perf.finished.len() > 1 && span > 0.0 {
+                    perf.iterations_per_second = (perf.finished.len() - 1) as f64 * 1000.0 / span;
+                }
+            }
+
+            if trace_first {
+                // Log root utility
+                let u0_sum: f32 = u0.iter().sum();
+                let u1_sum: f32 = u1.iter().sum();
+                trace!("[DCFRTrainer::train] Root utility - U0 sum: {:.4}, U1 sum: {:.4}", u0_sum, u1_sum);
+                if !u0.is_empty() {
+                    trace!("[DCFRTrainer::train] U0 sample [0..min(3,len)]: {:?}",
+                           &u0[0..u0.len().min(3)]);
+                }
+
+                // Log first 5 regret values AFTER update (not yet discounted)
+                let regret_sample: Vec<f32> = self.regrets.iter().take(5).cloned().collect();
+                trace!("[DCFRTrainer::train] First 5 regrets AFTER update: {:?}", regret_sample);
+
+                // Check if all regrets are zero
+                let non_zero_regrets = self.regrets.iter().filter(|&&r| r != 0.0).count();
+                trace!("[DCFRTrainer::train] Non-zero regrets: {} / {}", non_zero_regrets, self.regrets.len());
+
+                // Also log strategy_sum
+                let strat_sample: Vec<f32> = self.strategy_sum.iter().take(5).cloned().collect();
+                trace!("[DCFRTrainer::train] First 5 strategy_sum AFTER update: {:?}", strat_sample);
+                let non_zero_strat = self.strategy_sum.iter().filter(|&&s| s != 0.0).count();
+                trace!("[DCFRTrainer::train] Non-zero strategy_sum: {} / {}", non_zero_strat, self.strategy_sum.len());
+            }
+        }
+
+        // Discount the rows the last iteration's traversals left owing
+        let discount_start = self.perf.is_some().then(platform::now_ms);
+        self.settle_all(self.iterations);
+        if let (Some(perf), Some(discount_start)) = (&mut self.perf, discount_start) {
+            perf.discount_ms += platform::now_ms() - discount_start;
+        }
+    }
+
+    /// Bring an infoset's rows up to the discount of iteration `through`,
+    /// applying the discount of each iteration it has not had yet in turn,
+    /// so a row ends up bit for bit where discounting every row after every
+    /// iteration would have left it.
+    fn settle(&mut self, infoset: usize, through: usize) {
+        for iteration in self.discounted_at[infoset] + 1..=through {
+            self.discount_infoset(infoset, discount_coefs(iteration));
+            self.discounted_at[infoset] = iteration;
+        }
+    }
+
+    /// [`DCFRTrainer::settle`] every infoset, e.g. to bring the infosets a
+    /// snapshot delta leaves out up to the delta's iteration.
+    pub fn settle_all(&mut self, through: usize) {
+        let Some(&oldest) = self.discounted_at.iter().min() else { return };
+        for iteration in oldest + 1..=through {
+            let coefs = discount_coefs(iteration);
+            for infoset in 0..self.discounted_at.len() {
+                if self.discounted_at[infoset] < iteration {
+                    self.discount_infoset(infoset, coefs);
+                    self.discounted_at[infoset] = iteration;
+                }
+            }
+        }
+    }
+
+    /// Apply one iteration's DCFR discount, as `(alpha_coef, strategy_coef)`
+    /// from [`discount_coefs`], to an infoset's regrets and strategy sums.
+    fn discount_infoset(&mut self, infoset: usize, (alpha_coef, strategy_coef): (f32, f32)) {
+        let block = infoset * self.block_len()..(infoset + 1) * self.block_len();
+        simd::discount(&mut self.regrets[block.clone()], alpha_coef, BETA);
+        for s in &mut self.strategy_sum[block] {
+            *s *= strategy_coef;
+        }
+    }
+
+    /// Regret-matching strategy of the first `num_hands` hands at an
+    /// infoset, flattened as `[hand * num_actions + action]`. Locked hands
+    /// only mix their allowed actions.
+    pub(crate) fn current_strategy(&self, infoset_id: usize, num_hands: usize, num_actions: usize) -> Vec<f32> {
+        let mut strategy = vec![0.0; num_hands * num_actions];
+        let base_idx = infoset_id * self.max_hands * self.max_actions;
+        for h in 0..num_hands {
+            let allowed = self.allowed_actions(infoset_id, h);
+            let regrets = &self.regrets[base_idx + h * self.max_actions..][..num_actions];
+            let sum_pos_regret: f32 = regrets.iter().enumerate()
+                .filter(|&(a, &r)| r > 0.0 && allowed & (1 << a) != 0)
+                .map(|(_, r)| r)
+                .sum();
+            let num_allowed = if allowed == ALL_ACTIONS { num_actions } else { allowed.count_ones() as usize };
+
+            for (a, &r) in regrets.iter().enumerate() {
+                strategy[h * num_actions + a] = if allowed & (1 << a) == 0 {
+                    0.0
+                } else if sum_pos_regret > 0.0 {
+                    if r > 0.0 { r / sum_pos_regret } else { 0.0 }
+                } else {
+                    1.0 / num_allowed as f32
+                };
+            }
+        }
+        strategy
+    }
+
+    /// Add `regret` to action `action` of hand `hand` at an infoset.
+    pub(crate) fn add_regret(&mut self, infoset_id: usize, hand: usize, action: usize, regret: f32) {
+        if regret != 0.0 {
+            self.regrets[infoset_id * self.max_hands * self.max_actions + hand * self.max_actions + action] += regret;
+            self.changed_at[infoset_id] = self.iterations;
+        }
+    }
+
+    /// Add the strategy `row` hand `hand` played at an infoset, weighted by
+    /// the hand's `reach`, to its average strategy.
+    pub(crate) fn add_strategy(&mut self, infoset_id: usize, hand: usize, row: &[f32], reach: f32) {
+        if reach == 0.0 {
+            return;
+        }
+        let start = infoset_id * self.max_hands * self.max_actions + hand * self.max_actions;
+        for (s, p) in self.strategy_sum[start..start + row.len()].iter_mut().zip(row) {
+            *s += reach * p;
+        }
+        self.changed_at[infoset_id] = self.iterations;
+    }
+
+    /// Add a hand's raw `regrets` and its played `strategy` weighted by
+    /// `reach` at the block starting at `base_idx`; all three are
+    /// `[hand * num_actions + action]`. Hands out of reach with no regret
+    /// are skipped, and the infoset is stamped only if some hand was not.
+    fn accumulate(&mut self, base_idx: usize, num_actions: usize, regrets: &[f32], strategy: &[f32], reach: &[f32]) {
+        let mut written = false;
+        for (h, (row, played)) in regrets.chunks(num_actions).zip(strategy.chunks(num_actions)).enumerate() {
+            if reach[h] == 0.0 && row.iter().all(|&r| r == 0.0) {
+                continue;
+            }
+            written = true;
+            let start = base_idx + h * self.max_actions;
+            // Accumulate raw regret (discounting applied after iteration)
+            for (r, regret) in self.regrets[start..start + num_actions].iter_mut().zip(row) {
+                *r += regret;
+            }
+            for (s, p) in self.strategy_sum[start..start + num_actions].iter_mut().zip(played) {
+                *s += reach[h] * p;
+            }
+        }
+        if written {
+            let infoset = base_idx / self.block_len();
+            self.changed_at[infoset] = self.iterations;
+        }
+    }
+
+    /// One iteration driven by a traversal other than [`DCFRTrainer::train`]'s
+    /// (the preflop and multiway solvers'): `traverse` accumulates raw
+    /// regrets through [`DCFRTrainer::add_regret`] and reach-weighted
+    /// strategies through [`DCFRTrainer::add_strategy`], then every row is
+    /// discounted.
+    pub(crate) fn run_iteration(&mut self, traverse: impl FnOnce(&mut Self)) {
+        self.iterations += 1;
+        traverse(self);
+        self.settle_all(self.iterations);
+    }
+
+    /// One CFR traversal from the root for each player, accumulating raw
+    /// regrets and strategy sums; returns the utilities of the last one.
+    /// Runs in parallel when the rayon pool is available, unless invariant
+    /// checks or performance counters are on: they are kept by the
+    /// sequential pass. The threads only read, so the parallel pass settles
+    /// every infoset's discount up front.
+    fn traverse(&mut self, tree: &GameTree, matchups: Matchups, reach0: &[f32], reach1: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        #[cfg(rayon_pool)]
+        if crate::parallel::pool_ready() && self.invariants.is_none() && self.perf.is_none() {
+            self.settle_all(self.iterations - 1);
+            let mut utilities = (Vec::new(), Vec::new());
+            for traverser in 0..2 {
+                let (u0, u1, updates) = self.cfr_parallel(tree, matchups, 0, reach0, reach1, traverser);
+                for update in updates {
+                    self.accumulate(update.base_idx, update.num_actions, &update.regrets, &update.strategy, &update.reach);
+                }
+                utilities = (u0, u1);
+            }
+            return utilities;
+        }
+
+        self.cfr(tree, matchups, 0, reach0, reach1, 0);
+        self.cfr(tree, matchups, 0, reach0, reach1, 1)
+    }
+
+    /// Recursive CFR function, updating only `traverser`'s infosets.
+    /// Returns (U0, U1) utility vectors.
+    fn cfr(
+        &mut self,
+        tree: &GameTree,
+        matchups: Matchups,
+        node_idx: u32,
+        reach0: &[f32],
+        reach1: &[f32],
+        traverser: usize,
+    ) -> (Vec<f32>, Vec<f32>) {
+        let node = tree.get_node(node_idx);
+        if let Some(perf) = &mut self.perf {
+            perf.nodes_visited += 1;
+            // Modelled, not measured: the returned utilities, plus an action
+            // node's strategy, child utility list and regrets, and per action
+            // a strategy column and two reach vectors
+            perf.estimated_allocations += 2;
+            match node.node_type {
+                NodeType::Terminal | NodeType::Showdown => perf.terminal_evaluations += 1,
+                NodeType::Action => perf.estimated_allocations += 3 + 3 * node.num_actions as u64,
+                NodeType::Chance => {}
+            }
+        }
+
+        let (u0, u1) = match node.node_type {
+            NodeType::Terminal | NodeType::Showdown => {
+                terminal_values(node, tree.outcome(node), matchups, self.num_hands, reach0, reach1)
+            },
+            NodeType::Action => {
+                let player = node.player as usize;
+                let num_actions = node.num_actions as usize;
+                let infoset_id = node.infoset_id as usize;
+
+                // 1. Get Strategy (Regret Matching), on rows discounted
+                // through the previous iteration
+                self.settle(infoset_id, self.iterations - 1);
+                let strategy = self.current_strategy(infoset_id, self.num_hands[player], num_actions);
+                if self.invariants.is_some() {
+                    self.check_strategy(node_idx, &strategy, num_actions);
+                }
+
+                // 2. Recurse
+                let columns = strategy_columns(&strategy, num_actions);
+                let children: Vec<_> = columns
+                    .iter()
+                    .enumerate()
+                    .map(|(a, column)| {
+                        let (next_reach0, next_reach1) = child_reach(player, column, reach0, reach1);
+                        self.cfr(tree, matchups, node.children_start + a as u32, &next_reach0, &next_reach1, traverser)
+                    })
+                    .collect();
+
+                // 3. Update regrets and strategy sum (for the traverser)
+                let (u0_node, u1_node, regrets) = self.combine_actions(player, &columns, children);
+                if player == traverser {
+                    let base_idx = infoset_id * self.max_hands * self.max_actions;
+                    let reach = if player == 0 { reach0 } else { reach1 };
+                    self.accumulate(base_idx, num_actions, &regrets, &strategy, reach);
+                }
+
+                (u0_node, u1_node)
+            },
+            NodeType::Chance => {
+                // Each river is weighted by its probability given the hands;
+                // the river's block masks out hands holding the river card.
+                let block = self.num_hands[0] * self.num_hands[1];
+                let children: Vec<_> = (0..node.num_actions as usize)
+                    .map(|i| self.cfr(tree, matchups.block(i + 1, block), node.children_start + i as u32, reach0, reach1, traverser))
+                    .collect();
+                self.combine_rivers(tree.river_weight, children)
+            },
+        };
+
+        if self.invariants.is_some() && let Some(u) = u0.iter().chain(&u1).find(|u| !u.is_finite()) {
+            self.violation(node_idx, InvariantKind::NonFiniteUtility, format!("utility {}", u));
+        }
+        (u0, u1)
+    }
+
+    /// [`DCFRTrainer::cfr`] with the children of each node traversed on the
+    /// rayon pool. Regrets are read as they were when the traversal started
+    /// and the raw updates returned rather than applied.
+    #[cfg(rayon_pool)]
+    fn cfr_parallel(
+        &self,
+        tree: &GameTree,
+        matchups: Matchups,
+        node_idx: u32,
+        reach0: &[f32],
+        reach1: &[f32],
+        traverser: usize,
+    ) -> (Vec<f32>, Vec<f32>, Vec<RegretUpdate>) {
+        use rayon::prelude::*;
+
+        let node = tree.get_node(node_idx);
+        match node.node_type {
+            NodeType::Terminal | NodeType::Showdown => {
+                let (u0, u1) = terminal_values(node, tree.outcome(node), matchups, self.num_hands, reach0, reach1);
+                (u0, u1, Vec::new())
+            },
+            NodeType::Action => {
+                let player = node.player as usize;
+                let num_actions = node.num_actions as usize;
+                let infoset_id = node.infoset_id as usize;
+                let strategy = self.current_strategy(infoset_id, self.num_hands[player], num_actions);
+                let columns = strategy_columns(&strategy, num_actions);
+
+                let results: Vec<_> = columns
+                    .par_iter()
+                    .enumerate()
+                    .map(|(a, column)| {
+                        let (next_reach0, next_reach1) = child_reach(player, column, reach0, reach1);
+                        self.cfr_parallel(tree, matchups, node.children_start + a as u32, &next_reach0, &next_reach1, traverser)
+                    })
+                    .collect();
+                let (children, mut updates) = split_updates(results);
+
+                let (u0_node, u1_node, regrets) = self.combine_actions(player, &columns, children);
+                if player == traverser {
+                    let base_idx = infoset_id * self.max_hands * self.max_actions;
+                    let reach = if player == 0 { reach0 } else { reach1 }.to_vec();
+                    updates.push(RegretUpdate { base_idx, num_actions, regrets, strategy, reach });
+                }
+                (u0_node, u1_node, updates)
+            },
+            NodeType::Chance => {
+                let block = self.num_hands[0] * self.num_hands[1];
+                let results: Vec<_> = (0..node.num_actions as usize)
+                    .into_par_iter()
+                    .map(|i| self.cfr_parallel(tree, matchups.block(i + 1, block), node.children_start + i as u32, reach0, reach1, traverser))
+                    .collect();
+                let (children, updates) = split_updates(results);
+                let (u0_node, u1_node) = self.combine_rivers(tree.river_weight, children);
+                (u0_node, u1_node, updates)
+            },
+        }
+    }
+
+    /// Utilities of an action node from its children's, in action order, and
+    /// the acting player's raw regrets as `[hand * num_actions + action]`.
+    /// `columns` holds the strategy one action at a time (see
+    /// [`strategy_columns`]).
+    fn combine_actions(&self, player: usize, columns: &[Vec<f32>], children: Vec<Utilities>) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let num_actions = columns.len();
+        let mut u0_node = vec![0.0; self.num_hands[0]];
+        let mut u1_node = vec![0.0; self.num_hands[1]];
+
+        // Store child utilities for active player to update regrets
+        // [action][hand]
+        let mut active_child_utils = Vec::with_capacity(num_actions);
+
+        for (column, (u0_child, u1_child)) in columns.iter().zip(children) {
+            if player == 0 {
+                // P0 is active
+                // U0[h] += sigma[h][a] * U0_child[h]
+                simd::mul_add(&mut u0_node, column, &u0_child);
+                // U1[h] += U1_child[h] (sum over actions)
+                simd::add(&mut u1_node, &u1_child);
+                active_child_utils.push(u0_child);
+            } else {
+                // P1 is active
+                // U1[h] += sigma[h][a] * U1_child[h]
+                simd::mul_add(&mut u1_node, column, &u1_child);
+                // U0[h] += U0_child[h]
+                simd::add(&mut u0_node, &u0_child);
+                active_child_utils.push(u1_child);
+            }
+        }
+
+        let n_hands = self.num_hands[player];
+        let node_util = if player == 0 { &u0_node } else { &u1_node };
+        let mut regrets = vec![0.0; n_hands * num_actions];
+        for h in 0..n_hands {
+            for (a, child_util) in active_child_utils.iter().enumerate() {
+                regrets[h * num_actions + a] = child_util[h] - node_util[h];
+            }
+        }
+
+        (u0_node, u1_node, regrets)
+    }
+
+    /// Utilities of a chance node: its rivers' utilities, each weighted by
+    /// `river_weight`.
+    fn combine_rivers(&self, river_weight: f32, children: Vec<Utilities>) -> Utilities {
+        let mut u0_node = vec![0.0; self.num_hands[0]];
+        let mut u1_node = vec![0.0; self.num_hands[1]];
+        for (u0_child, u1_child) in children {
+            for (u, c) in u0_node.iter_mut().zip(u0_child) {
+                *u += river_weight * c;
+            }
+            for (u, c) in u1_node.iter_mut().zip(u1_child) {
+                *u += river_weight * c;
+            }
+        }
+        (u0_node, u1_node)
+    }
+
+    /// Check that each hand's row of `strategy` is a probability
+    /// distribution, recording at most one violation of each kind.
+    fn check_strategy(&mut self, node_idx: u32, strategy: &[f32], num_actions: usize) {
+        let rows = strategy.chunks(num_actions).enumerate();
+        if let Some((h, row)) = rows.clone().find(|(_, row)| row.iter().any(|p| p.is_nan() || *p < 0.0)) {
+            self.violation(node_idx, InvariantKind::NegativeProbability, format!("hand {}: {:?}", h, row));
+        }
+        if let Some((h, sum)) = rows.map(|(h, row)| (h, row.iter().sum::<f32>())).find(|(_, sum)| (sum - 1.0).abs() > INVARIANT_EPSILON) {
+            self.violation(node_idx, InvariantKind::StrategySum, format!("hand {}: sums to {}", h, sum));
+        }
+    }
+}
+
+/// Coefficients of iteration `iteration`'s DCFR discount: `alpha_coef`
+/// for positive regrets (negative ones are scaled by `BETA`) and
+/// `strategy_coef` for the strategy sums. Unlike TexasSolver's
+/// DiscountedCfrTrainable::updateRegrets, which decays the average by a
+/// fixed theta, the strategy sums are scaled by `(t / (t + 1))^gamma` as in
+/// the DCFR paper, so early iterations fade out without the average
+/// forgetting all but the last few.
+fn discount_coefs(iteration: usize) -> (f32, f32) {
+    let t = iteration as f32;
+
+    // alpha_coef = t^alpha / (1 + t^alpha)
+    let alpha_pow = t.powf(ALPHA);
+    let alpha_coef = alpha_pow / (1.0 + alpha_pow);
+
+    // strategy_coef = (t / (t+1))^gamma
+    let strategy_coef = (t / (t + 1.0)).powf(GAMMA);
+    (alpha_coef, strategy_coef)
+}
+
+/// A strategy flattened as `[hand * num_actions + action]`, split into one
+/// contiguous column of hand probabilities per action for the vector kernels.
+fn strategy_columns(strategy: &[f32], num_actions: usize) -> Vec<Vec<f32>> {
+    (0..num_actions)
+        .map(|a| strategy.iter().skip(a).step_by(num_actions).copied().collect())
+        .collect()
+}
+
+/// Reach probabilities after the acting `player` takes an action with the
+/// hand probabilities in `column`: their reach scaled, the opponent's unchanged.
+fn child_reach(player: usize, column: &[f32], reach0: &[f32], reach1: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let mut next_reach0 = reach0.to_vec();
+    let mut next_reach1 = reach1.to_vec();
+    simd::scale(if player == 0 { &mut next_reach0 } else { &mut next_reach1 }, column);
+    (next_reach0, next_reach1)
+}
+
+/// Children's utilities and all their regret updates, from the results of
+/// a parallel traversal.
+#[cfg(rayon_pool)]
+fn split_updates(results: Vec<(Vec<f32>, Vec<f32>, Vec<RegretUpdate>)>) -> (Vec<Utilities>, Vec<RegretUpdate>) {
+    let mut updates = Vec::new();
+    let children = results
+        .into_iter()
+        .map(|(u0, u1, child_updates)| {
+            updates.extend(child_updates);
+            (u0, u1)
+        })
+        .collect();
+    (children, updates)
+}
+
+/// Equity matrix of the hands at a node, with optional matchup weights
+/// (see [`DCFRTrainer::train_weighted`]).
+#[derive(Clone, Copy)]
+pub(crate) struct Matchups<'a> {
+    pub equity: &'a [f32],
+    pub weights: Option<&'a [f32]>,
+}
+
+impl<'a> Matchups<'a> {
+    /// Plain equity matrix: every non-NaN matchup counts in full.
+    pub fn unweighted(equity: &'a [f32]) -> Self {
+        Self { equity, weights: None }
+    }
+
+    /// The `i`-th block of `len` entries (the blocks of the dealt rivers).
+    pub fn block(&self, i: usize, len: usize) -> Self {
+        Self {
+            equity: &self.equity[i * len..(i + 1) * len],
+            weights: self.weights.map(|w| &w[i * len..(i + 1) * len]),
+        }
+    }
+
+    /// Row `h0` of player 0's matchups.
+    fn row(&self, h0: usize, n1: usize) -> (&'a [f32], Option<&'a [f32]>) {
+        (&self.equity[h0 * n1..(h0 + 1) * n1], self.weights.map(|w| &w[h0 * n1..(h0 + 1) * n1]))
+    }
+}
+
+/// [`simd::masked_sums`] with each matchup scaled by its weight.
+fn row_sums(row: &[f32], matchup_weights: Option<&[f32]>, reach: &[f32]) -> (f32, f32) {
+    let Some(matchup_weights) = matchup_weights else {
+        return simd::masked_sums(row, reach);
+    };
+    let (mut equity, mut total) = (0.0, 0.0);
+    for ((&e, &m), &r) in row.iter().zip(matchup_weights).zip(reach) {
+        if !e.is_nan() {
+            equity += e * m * r;
+            total += m * r;
+        }
+    }
+    (equity, total)
+}
+
+/// [`simd::accumulate_opponent`] with each matchup scaled by its weight.
+fn accumulate_row(row: &[f32], matchup_weights: Option<&[f32]>, reach: f32, equity: &mut [f32], total: &mut [f32]) {
+    let Some(matchup_weights) = matchup_weights else {
+        return simd::accumulate_opponent(row, reach, equity, total);
+    };
+    for (((&e, &m), eq), t) in row.iter().zip(matchup_weights).zip(equity.iter_mut()).zip(total.iter_mut()) {
+        if !e.is_nan() {
+            *eq += (1.0 - e) * m * reach;
+            *t += m * reach;
+        }
+    }
+}
+
+/// Counterfactual values of a terminal (fold) or showdown node for both players.
+///
+/// Each hand's value is weighted by the opponent's reach over the combos it
+/// does not conflict with (the non-NaN entries of the equity matrix), so fold
+/// and showdown payoffs are on the same scale. Matchup weights, if any,
+/// scale each matchup's share.
+/// `outcome` (from [`GameTree::outcome`]) gives each player's utility for
+/// either pot winner, in chips or ICM equity.
+/// Returns (U0, U1); both are zero for non-terminal nodes.
+pub(crate) fn terminal_values(
+    node: &Node,
+    outcome: Outcome,
+    matchups: Matchups,
+    num_hands: [usize; 2],
+    reach0: &[f32],
+    reach1: &[f32],
+) -> (Vec<f32>, Vec<f32>) {
+    let n0 = num_hands[0];
+    let n1 = num_hands[1];
+    let mut u0 = vec![0.0; n0];
+    let mut u1 = vec![0.0; n1];
+
+    match node.node_type {
+        NodeType::Terminal => {
+            // Terminal (Fold)
+            // node.player contains the winner (opponent of folder)
+            //
+            // In chip EV the winner gains what the folder loses, so
+            // u0 + u1 = 0; ICM utilities are not zero-sum.
+            let [u0_val, u1_val] = if node.player == 0 { outcome.p0_wins } else { outcome.p1_wins };
+
+            // Player 1's blocker-aware opponent weights are accumulated row by
+            // row; the equity sums that come with them are not needed here.
+            let mut unused = vec![0.0; n1];
+            let mut opp_weight1 = vec![0.0; n1];
+            for h0 in 0..n0 {
+                let (row, weights) = matchups.row(h0, n1);
+                let (_, opp_weight) = row_sums(row, weights, reach1);
+                u0[h0] = u0_val * opp_weight;
+                accumulate_row(row, weights, reach0[h0], &mut unused, &mut opp_weight1);
+            }
+
+            for h1 in 0..n1 {
+                u1[h1] = u1_val * opp_weight1[h1];
+            }
+        },
+        NodeType::Showdown => {
+            // Each matchup is worth the winner's outcome, interpolated by
+            // equity: U = L + (W - L) * equity, where W and L are the
+            // player's utilities if they win or lose the pot. Split pots
+            // land halfway between. In chip EV, with bets level (W = pot/2,
+            // L = -pot/2), this is the zero-sum (equity - 0.5) * pot.
+            let (win0, lose0) = (outcome.p0_wins[0], outcome.p1_wins[0]);
+            let (win1, lose1) = (outcome.p1_wins[1], outcome.p0_wins[1]);
+
+            // U0 is a reach-weighted sum along each matrix row. U1 needs the
+            // columns, which are accumulated row by row to keep reads contiguous.
+            let mut weighted_equity1 = vec![0.0; n1];
+            let mut total_weight1 = vec![0.0; n1];
+            for h0 in 0..n0 {
+                let (row, weights) = matchups.row(h0, n1);
+                let (weighted_equity, total_weight) = row_sums(row, weights, reach1);
+                u0[h0] = lose0 * total_weight + (win0 - lose0) * weighted_equity;
+
+                // P1 equity = 1 - P0 equity
+                accumulate_row(row, weights, reach0[h0], &mut weighted_equity1, &mut total_weight1);
+            }
+
+            for h1 in 0..n1 {
+                u1[h1] = lose1 * total_weight1[h1] + (win1 - lose1) * weighted_equity1[h1];
+            }
+        },
+        NodeType::Action | NodeType::Chance => {},
+    }
+
+    (u0, u1)
+}
@@ -0,0 +1,315 @@
+//! The binary container every file the crate writes is stored in: solution
+//! files, snapshot deltas and the preflop equity table.
+//!
+//! Layout (all integers little endian):
+//!
+//! | Offset | Size   | Field                                        |
+//! |--------|--------|----------------------------------------------|
+//! | 0      | 4      | magic, e.g. `b"PSOL"`                        |
+//! | 4      | 2      | format version (`FORMAT_VERSION`)            |
+//! | 6      | 2      | reserved, 0                                  |
+//! | 8      | 4      | section count `n`                            |
+//! | 12     | 4      | CRC-32 of the section table                  |
+//! | 16     | 16 `n` | section table                                |
+//! | ..     | ..     | section data, in table order                 |
+//!
+//! Each table entry is a 4-byte tag, the section's length (`u64`) and the
+//! CRC-32 of its data. Adding a section keeps the version: readers skip
+//! tags they do not know. The version changes only when the layout of an
+//! existing section does, and readers reject versions newer than theirs.
+//!
+//! Version 1 files hold one checksummed payload instead of sections (magic,
+//! version, reserved, payload length as `u64`, CRC-32, payload). They are
+//! still read: [`read`] returns the payload as the [`V1_PAYLOAD`] section,
+//! for the owner of the magic to decode the old way.
+
+/// A section tag, e.g. `*b"CONF"`.
+pub type Tag = [u8; 4];
+
+/// Current format version.
+pub const FORMAT_VERSION: u16 = 2;
+
+/// Pseudo-section holding the whole payload of a version 1 file.
+pub const V1_PAYLOAD: Tag = *b"V1PL";
+
+const HEADER_LEN: usize = 16;
+const ENTRY_LEN: usize = 16;
+const V1_HEADER_LEN: usize = 20;
+
+/// The sections of a container, checksums verified.
+#[derive(Debug, Clone)]
+pub struct Sections<'a> {
+    version: u16,
+    entries: Vec<(Tag, &'a [u8])>,
+}
+
+impl<'a> Sections<'a> {
+    /// Format version the container was written with.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Tags in file order.
+    pub fn tags(&self) -> impl Iterator<Item = Tag> + '_ {
+        self.entries.iter().map(|(tag, _)| *tag)
+    }
+
+    /// Data of the first section tagged `tag`.
+    pub fn get(&self, tag: &Tag) -> Option<&'a [u8]> {
+        self.entries.iter().find(|(t, _)| t == tag).map(|&(_, data)| data)
+    }
+
+    /// Like [`Sections::get`], failing when the section is missing.
+    pub fn require(&self, tag: &Tag, what: &str) -> Result<&'a [u8], String> {
+        self.get(tag).ok_or_else(|| format!("{} has no {} section", what, String::from_utf8_lossy(tag)))
+    }
+}
+
+/// Write `sections` (tag and data, in order) under `magic`.
+pub fn write(magic: &Tag, sections: &[(Tag, Vec<u8>)]) -> Vec<u8> {
+    let mut table = Vec::with_capacity(sections.len() * ENTRY_LEN);
+    for (tag, data) in sections {
+        table.extend_from_slice(tag);
+        table.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        table.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+    }
+
+    let data_len: usize = sections.iter().map(|(_, data)| data.len()).sum();
+    let mut bytes = Vec::with_capacity(HEADER_LEN + table.len() + data_len);
+    bytes.extend_from_slice(magic);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(&table).to_le_bytes());
+    bytes.extend_from_slice(&table);
+    for (_, data) in sections {
+        bytes.extend_from_slice(data);
+    }
+    bytes
+}
+
+/// Check a container written under `magic` and split it into sections.
+/// `what` names the file in errors ("solution file").
+pub fn read<'a>(magic: &Tag, what: &str, bytes: &'a [u8]) -> Result<Sections<'a>, String> {
+    if bytes.len() < 6 || &bytes[..4] != magic {
+        return Err(format!("Not a {} (bad magic bytes)", what));
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    match version {
+        1 => read_v1(what, bytes),
+        FORMAT_VERSION => read_sections(what, bytes),
+        v if v > FORMAT_VERSION => Err(format!("{} format version {} is newer than supported ({})", capitalized(what), v, FORMAT_VERSION)),
+        v => Err(format!("Unsupported {} format version {}", what, v)),
+    }
+}
+
+fn read_sections<'a>(what: &str, bytes: &'a [u8]) -> Result<Sections<'a>, String> {
+    let truncated = || format!("Corrupted {}: truncated", what);
+    if bytes.len() < HEADER_LEN {
+        return Err(truncated());
+    }
+    let count = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+    let table_crc = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+    let table_end = count.checked_mul(ENTRY_LEN).and_then(|len| len.checked_add(HEADER_LEN))
+        .filter(|&end| end <= bytes.len())
+        .ok_or_else(truncated)?;
+    let table = &bytes[HEADER_LEN..table_end];
+    if crc32fast::hash(table) != table_crc {
+        return Err(format!("Corrupted {}: section table checksum mismatch", what));
+    }
+
+    let mut entries = Vec::with_capacity(count);
+    let mut pos = table_end;
+    for entry in table.chunks_exact(ENTRY_LEN) {
+        let tag: Tag = entry[..4].try_into().unwrap();
+        let len = u64::from_le_bytes(entry[4..12].try_into().unwrap());
+        let crc = u32::from_le_bytes(entry[12..16].try_into().unwrap());
+        let end = usize::try_from(len).ok().and_then(|len| pos.checked_add(len))
+            .filter(|&end| end <= bytes.len())
+            .ok_or_else(truncated)?;
+        let data = &bytes[pos..end];
+        if crc32fast::hash(data) != crc {
+            return Err(format!("Corrupted {}: checksum mismatch in section {}", what, String::from_utf8_lossy(&tag)));
+        }
+        entries.push((tag, data));
+        pos = end;
+    }
+    if pos != bytes.len() {
+        return Err(format!("Corrupted {}: {} bytes after the last section", what, bytes.len() - pos));
+    }
+    Ok(Sections { version: FORMAT_VERSION, entries })
+}
+
+fn read_v1<'a>(what: &str, bytes: &'a [u8]) -> Result<Sections<'a>, String> {
+    if bytes.len() < V1_HEADER_LEN {
+        return Err(format!("Corrupted {}: truncated", what));
+    }
+    let payload_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+    let checksum = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+    let payload = &bytes[V1_HEADER_LEN..];
+    if payload.len() != payload_len {
+        return Err(format!("Corrupted {}: {} bytes but the header declares {}", what, payload.len(), payload_len));
+    }
+    if crc32fast::hash(payload) != checksum {
+        return Err(format!("Corrupted {}: checksum mismatch", what));
+    }
+    Ok(Sections { version: 1, entries: vec![(V1_PAYLOAD, payload)] })
+}
+
+fn capitalized(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+}
+
+/// Append a `u32` length and the UTF-8 of `s`.
+pub(crate) fn put_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Append `values` compressed (see `put_compressed`).
+pub(crate) fn put_floats(out: &mut Vec<u8>, values: &[f32]) {
+    let raw: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    put_compressed(out, values.len(), &raw);
+}
+
+/// Append `values` compressed (see `put_compressed`).
+pub(crate) fn put_u32s(out: &mut Vec<u8>, values: &[u32]) {
+    let raw: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    put_compressed(out, values.len(), &raw);
+}
+
+/// Append `values` compressed (see `put_compressed`).
+pub(crate) fn put_u16s(out: &mut Vec<u8>, values: &[u16]) {
+    let raw: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    put_compressed(out, values.len(), &raw);
+}
+
+/// `count` values of 2 or 4 bytes: the count, the compressed length, then
+/// DEFLATE data.
+fn put_compressed(out: &mut Vec<u8>, count: usize, raw: &[u8]) {
+    let compressed = miniz_oxide::deflate::compress_to_vec(raw, 6);
+    out.extend_from_slice(&(count as u32).to_le_bytes());
+    out.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    out.extend_from_slice(&compressed);
+}
+
+/// Bounds-checked cursor over a section (or a version 1 payload).
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.bytes.len())
+            .ok_or("Solution payload is truncated")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String, String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| "Solution contains invalid UTF-8".to_string())
+    }
+
+    pub(crate) fn floats(&mut self) -> Result<Vec<f32>, String> {
+        Ok(self.compressed(4)?.chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap())).collect())
+    }
+
+    pub(crate) fn u32s(&mut self) -> Result<Vec<u32>, String> {
+        Ok(self.compressed(4)?.chunks_exact(4).map(|c| u32::from_le_bytes(c.try_into().unwrap())).collect())
+    }
+
+    pub(crate) fn u16s(&mut self) -> Result<Vec<u16>, String> {
+        Ok(self.compressed(2)?.chunks_exact(2).map(|c| u16::from_le_bytes(c.try_into().unwrap())).collect())
+    }
+
+    /// Raw bytes of a block of `width`-byte values written by
+    /// `put_compressed`.
+    fn compressed(&mut self, width: usize) -> Result<Vec<u8>, String> {
+        let count = self.u32()? as usize;
+        let len = self.u32()? as usize;
+        let raw = miniz_oxide::inflate::decompress_to_vec(self.take(len)?)
+            .map_err(|e| format!("Failed to decompress solution buffer: {:?}", e))?;
+        if raw.len() != count * width {
+            return Err(format!("Solution buffer holds {} bytes, expected {}", raw.len(), count * width));
+        }
+        Ok(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        write(b"TEST", &[(*b"ONE_", b"first".to_vec()), (*b"TWO_", Vec::new()), (*b"ONE_", b"shadowed".to_vec())])
+    }
+
+    #[test]
+    fn test_sections_round_trip() {
+        let bytes = sample();
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), FORMAT_VERSION);
+        let sections = read(b"TEST", "test file", &bytes).unwrap();
+        assert_eq!(sections.version(), FORMAT_VERSION);
+        assert_eq!(sections.tags().collect::<Vec<_>>(), [*b"ONE_", *b"TWO_", *b"ONE_"]);
+        assert_eq!(sections.get(b"ONE_"), Some(&b"first"[..]));
+        assert_eq!(sections.get(b"TWO_"), Some(&b""[..]));
+        assert_eq!(sections.get(b"NONE"), None);
+        assert_eq!(sections.require(b"NONE", "Test file").unwrap_err(), "Test file has no NONE section");
+        assert!(read(b"TEST", "test file", &write(b"TEST", &[])).unwrap().tags().next().is_none());
+    }
+
+    #[test]
+    fn test_rejects_damage() {
+        let bytes = sample();
+        let err = |bytes: &[u8]| read(b"TEST", "test file", bytes).unwrap_err();
+
+        assert!(err(b"TEST").contains("magic"));
+        assert!(err(&bytes[..bytes.len() - 1]).contains("truncated"));
+        assert!(err(&[&bytes[..], b"x"].concat()).contains("after the last section"));
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().unwrap() ^= 0xff;
+        assert!(err(&flipped).contains("checksum mismatch in section ONE_"));
+        let mut table = bytes.clone();
+        table[HEADER_LEN + 4] ^= 1;
+        assert!(err(&table).contains("section table checksum"));
+
+        let mut future = bytes.clone();
+        future[4] = 3;
+        assert_eq!(err(&future), "Test file format version 3 is newer than supported (2)");
+        future[4] = 0;
+        assert!(err(&future).contains("Unsupported"));
+    }
+
+    #[test]
+    fn test_reads_version_1() {
+        let payload = b"old payload";
+        let mut v1 = b"TEST".to_vec();
+        v1.extend_from_slice(&1u16.to_le_bytes());
+        v1.extend_from_slice(&0u16.to_le_bytes());
+        v1.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        v1.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+        v1.extend_from_slice(payload);
+
+        let sections = read(b"TEST", "test file", &v1).unwrap();
+        assert_eq!(sections.version(), 1);
+        assert_eq!(sections.get(&V1_PAYLOAD), Some(&payload[..]));
+        *v1.last_mut().unwrap() ^= 1;
+        assert!(read(b"TEST", "test file", &v1).unwrap_err().contains("checksum"));
+    }
+}
@@ -0,0 +1,184 @@
+//! Stable hashes of trees and spots.
+//!
+//! Everything here hashes with 64-bit FNV-1a over explicitly encoded bytes,
+//! so values are identical on every platform and across releases that keep
+//! the encoding: safe to persist, e.g. as cache keys in IndexedDB.
+
+use crate::poker::canonical::{canonical_permutation, spot_encoding};
+use crate::poker::range::parse_weighted_range;
+use crate::poker::Card;
+use crate::solver::types::{BetSize, GameConfig, RaiseSize};
+
+/// 64-bit FNV-1a.
+#[derive(Debug, Clone, Copy)]
+pub struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new() -> Self {
+        Fnv1a(Self::OFFSET)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cache key of a spot: a 16-digit hex hash of the normalized config
+/// (defaults filled in, sizes sorted), the board and both ranges with their
+/// weights. Card and combo order within the board and the ranges do not
+/// matter, and unweighted ranges keep the keys they had before weights.
+///
+/// With `suit_isomorphism` on, spots that a suit relabelling maps onto each
+/// other share a key: the board and ranges are hashed under the relabelling
+/// that gives the smallest encoding. A cached solution is then in that
+/// spot's suits, not necessarily the caller's; [`crate::poker::canonical`]
+/// finds the relabelling and its inverse.
+pub fn solve_key(config: &GameConfig, board: &[Card], ranges: [&[Vec<Card>]; 2], weights: [&[f32]; 2]) -> String {
+    let relabels = config.suit_isomorphism && config.abstraction.is_none();
+    let mut hash = Fnv1a::new();
+    let config = serde_json::to_string(&normalized_config(config)).expect("GameConfig is always serializable");
+    hash.write(config.as_bytes());
+
+    let perm = if relabels { canonical_permutation(board, ranges, weights) } else { [0, 1, 2, 3] };
+    let (board, combos) = spot_encoding(board, ranges, weights, &perm);
+    hash.write(&(board.len() as u32).to_le_bytes());
+    hash.write(&board);
+    for range in &combos {
+        hash.write(&(range.len() as u32).to_le_bytes());
+        for (hand, weight) in range {
+            hash.write(hand);
+            // Cards are below 52, so the marker cannot be mistaken for one
+            if f32::from_bits(*weight) != 1.0 {
+                hash.write(&[0xff]);
+                hash.write(&weight.to_le_bytes());
+            }
+        }
+    }
+    format!("{:016x}", hash.finish())
+}
+
+/// `config` with every size list sorted and deduplicated, as JSON.
+fn normalized_config(config: &GameConfig) -> serde_json::Value {
+    let sort_bets = |sizes: &mut Vec<BetSize>| {
+        let key = |b: &BetSize| match *b {
+            BetSize::PotFraction(f) => (0, f),
+            BetSize::Geometric(count) => (1, count.map_or(0.0, f32::from)),
+        };
+        sizes.sort_by(|a, b| key(a).0.cmp(&key(b).0).then(key(a).1.total_cmp(&key(b).1)));
+        sizes.dedup();
+    };
+    let sort_raises = |sizes: &mut Vec<RaiseSize>| {
+        let key = |r: &RaiseSize| match *r {
+            RaiseSize::PotFraction(f) => (0, f),
+            RaiseSize::Multiple { x } => (1, x),
+            RaiseSize::AllIn => (2, 0.0),
+            RaiseSize::Geometric(count) => (3, count.map_or(0.0, f32::from)),
+        };
+        sizes.sort_by(|a, b| key(a).0.cmp(&key(b).0).then(key(a).1.total_cmp(&key(b).1)));
+        sizes.dedup();
+    };
+
+    let mut config = config.clone();
+    sort_bets(&mut config.bet_sizes);
+    config.raise_sizes.menus_mut().for_each(sort_raises);
+    if let Some(sizes) = &mut config.river_bet_sizes {
+        sort_bets(sizes);
+    }
+    if let Some(sizes) = &mut config.river_raise_sizes {
+        sizes.menus_mut().for_each(sort_raises);
+    }
+    for own in &mut config.player_sizes {
+        own.bet_sizes.iter_mut().chain(&mut own.river_bet_sizes).for_each(&sort_bets);
+        own.raise_sizes.iter_mut().chain(&mut own.river_raise_sizes).for_each(|sizes| sizes.menus_mut().for_each(sort_raises));
+    }
+    // Rules keep their order, which decides the first match
+    for rule in &mut config.sizing_rules {
+        if let Some(sizes) = &mut rule.bet_sizes {
+            sort_bets(sizes);
+        }
+        if let Some(sizes) = &mut rule.raise_sizes {
+            sort_raises(sizes);
+        }
+    }
+    // Maps serialize with sorted keys, so the JSON is canonical
+    serde_json::to_value(&config).expect("GameConfig is always serializable")
+}
+
+/// [`solve_key`] of ranges given as (weighted) range strings.
+pub fn solve_key_str(config: &GameConfig, board: &[Card], ranges: [&str; 2]) -> String {
+    let [(hands0, weights0), (hands1, weights1)]: [(Vec<_>, Vec<_>); 2] = ranges.map(|r| parse_weighted_range(r).into_iter().unzip());
+    solve_key(config, board, [&hands0, &hands1], [&weights0, &weights1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bet_sizes: Vec<f32>) -> GameConfig {
+        serde_json::from_value(serde_json::json!({
+            "initial_pot": 100.0, "stacks": [100.0, 100.0], "bet_sizes": bet_sizes, "raise_sizes": [1.0],
+        })).unwrap()
+    }
+
+    fn key(config: &GameConfig, board: &str, r0: &str, r1: &str) -> String {
+        let board: Vec<Card> = board.split_whitespace().filter_map(Card::from_str).collect();
+        solve_key_str(config, &board, [r0, r1])
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        // Reference values of 64-bit FNV-1a
+        assert_eq!(Fnv1a::new().finish(), 0xcbf2_9ce4_8422_2325);
+        let mut hash = Fnv1a::new();
+        hash.write(b"a");
+        assert_eq!(hash.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_solve_key() {
+        let base = key(&config(vec![0.5, 1.0]), "Ks Th 7s 4d 2c", "Ac Ad, Kc Kd", "Qc Qd, Jc Jd");
+        assert_eq!(base.len(), 16);
+        // Formatting, combo order, card order and size order do not matter
+        assert_eq!(key(&config(vec![1.0, 0.5]), " 2c 4d 7s Th  Ks", "kd kc ,Ad Ac", "Jd Jc, Qc Qd"), base);
+        // Defaults are filled in before hashing
+        let mut explicit = config(vec![0.5, 1.0]);
+        explicit.raise_limit = 3;
+        assert_eq!(key(&explicit, "Ks Th 7s 4d 2c", "Ac Ad, Kc Kd", "Qc Qd, Jc Jd"), base);
+
+        assert_ne!(key(&config(vec![0.5, 0.75]), "Ks Th 7s 4d 2c", "Ac Ad, Kc Kd", "Qc Qd, Jc Jd"), base);
+        assert_ne!(key(&config(vec![0.5, 1.0]), "Ks Th 7s 4d 3c", "Ac Ad, Kc Kd", "Qc Qd, Jc Jd"), base);
+        assert_ne!(key(&config(vec![0.5, 1.0]), "Ks Th 7s 4d 2c", "Qc Qd, Jc Jd", "Ac Ad, Kc Kd"), base);
+        // Weights count, but a full weight is the same as none
+        assert_ne!(key(&config(vec![0.5, 1.0]), "Ks Th 7s 4d 2c", "Ac Ad:0.5, Kc Kd", "Qc Qd, Jc Jd"), base);
+        assert_eq!(key(&config(vec![0.5, 1.0]), "Ks Th 7s 4d 2c", "Ac Ad:1, Kc Kd", "Qc Qd, Jc Jd"), base);
+
+        // Clubs and diamonds are interchangeable on this board, but only
+        // suit isomorphism treats them as the same spot
+        let swapped = |config: &GameConfig| {
+            (key(config, "Ks Th 7s 4h 2s", "Ac Ad, Kc Kh", "Qc Qd"), key(config, "Ks Th 7s 4h 2s", "Ad Ac, Kd Kh", "Qd Qc"))
+        };
+        let (a, b) = swapped(&config(vec![1.0]));
+        assert_ne!(a, b);
+        let mut iso = config(vec![1.0]);
+        iso.suit_isomorphism = true;
+        let (a, b) = swapped(&iso);
+        assert_eq!(a, b);
+        assert_ne!(a, key(&iso, "Ks Th 7s 4h 2s", "Ac Ad, Ks Kh", "Qc Qd"));
+    }
+}
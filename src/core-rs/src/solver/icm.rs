@@ -0,0 +1,91 @@
+//! Independent Chip Model: tournament equity from chip stacks.
+//!
+//! Uses the Malmuth-Harville model: a player finishes first with probability
+//! proportional to their stack, and the remaining places are decided the
+//! same way among the players left. The recursion visits every ordered
+//! assignment of the paid places, so it is meant for a final-table sized
+//! field (cost grows as `players! / (players - places)!`).
+
+/// Expected prize of every player given their `stacks` and the `payouts` for
+/// first, second, ... place. Players with no chips share the places left
+/// once every player with chips has been placed.
+pub fn icm_equities(stacks: &[f32], payouts: &[f32]) -> Vec<f32> {
+    let mut equities = vec![0.0f64; stacks.len()];
+    let stacks: Vec<f64> = stacks.iter().map(|&s| s.max(0.0) as f64).collect();
+    let mut remaining: Vec<usize> = (0..stacks.len()).collect();
+    distribute(&stacks, payouts, &mut remaining, 1.0, &mut equities);
+    equities.into_iter().map(|e| e as f32).collect()
+}
+
+/// Award `payouts[0]` among the `remaining` players, weighted by `probability`
+/// of reaching this state, then recurse on the next place.
+fn distribute(stacks: &[f64], payouts: &[f32], remaining: &mut Vec<usize>, probability: f64, equities: &mut [f64]) {
+    let Some((&prize, rest)) = payouts.split_first() else { return };
+    if remaining.is_empty() {
+        return;
+    }
+
+    let total: f64 = remaining.iter().map(|&p| stacks[p]).sum();
+    if total <= 0.0 {
+        // Only busted players left: they split the remaining places evenly.
+        let share = payouts.iter().take(remaining.len()).map(|&p| p as f64).sum::<f64>() / remaining.len() as f64;
+        for &p in remaining.iter() {
+            equities[p] += probability * share;
+        }
+        return;
+    }
+
+    for i in 0..remaining.len() {
+        let player = remaining[i];
+        if stacks[player] <= 0.0 {
+            continue;
+        }
+        let p_first = probability * stacks[player] / total;
+        equities[player] += p_first * prize as f64;
+
+        remaining.swap_remove(i);
+        distribute(stacks, rest, remaining, p_first, equities);
+        remaining.push(player);
+        let last = remaining.len() - 1;
+        remaining.swap(i, last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn test_heads_up_is_proportional() {
+        let eq = icm_equities(&[3000.0, 1000.0], &[70.0, 30.0]);
+        assert!(close(eq[0], 0.75 * 70.0 + 0.25 * 30.0));
+        assert!(close(eq[1], 0.25 * 70.0 + 0.75 * 30.0));
+    }
+
+    #[test]
+    fn test_known_three_way_values() {
+        // Classic example: 50/30/20 with 5000/3000/2000 chips.
+        let eq = icm_equities(&[5000.0, 3000.0, 2000.0], &[50.0, 30.0, 20.0]);
+        assert!(close(eq[0], 38.3929), "{:?}", eq);
+        assert!(close(eq[1], 32.75), "{:?}", eq);
+        assert!(close(eq[2], 28.8571), "{:?}", eq);
+        assert!(close(eq.iter().sum(), 100.0));
+    }
+
+    #[test]
+    fn test_busted_players_and_unpaid_places() {
+        // Equal stacks split the prize pool evenly.
+        let eq = icm_equities(&[100.0; 4], &[50.0, 30.0, 20.0]);
+        assert!(eq.iter().all(|&e| close(e, 25.0)), "{:?}", eq);
+
+        // A busted player finishes last; the two alive take first and second.
+        let eq = icm_equities(&[0.0, 100.0, 300.0], &[50.0, 30.0, 20.0]);
+        assert!(close(eq[0], 20.0), "{:?}", eq);
+        assert!(close(eq[1] + eq[2], 80.0));
+        assert!(eq[2] > eq[1]);
+    }
+}
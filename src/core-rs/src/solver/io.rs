@@ -0,0 +1,399 @@
+//! Solution files and snapshot deltas, shared by the WASM `export_solution`
+//! and native `save_solution` / `load_solution`, so browser exports and CLI
+//! outputs are interchangeable. Both are [`format`] containers.
+//!
+//! A solution (magic `b"PSOL"`) has the sections, all required but `LOCK`
+//! and with `AVGS` standing in for `STRT` in browse-only solutions:
+//!
+//! | Tag    | Contents                                                  |
+//! |--------|-----------------------------------------------------------|
+//! | `CONF` | the `GameConfig` as JSON                                  |
+//! | `BORD` | the board, UTF-8                                          |
+//! | `RNGS` | both ranges, each a `u32` length followed by UTF-8        |
+//! | `TREE` | the tree's [`GameTree::structure_hash`] (`u64`)           |
+//! | `ITER` | the iteration count (`u64`)                               |
+//! | `STRT` | the regret and strategy-sum buffers                       |
+//! | `AVGS` | average strategies, as `u16` multiples of 1/65535         |
+//! | `LOCK` | hand locks as `(infoset, hand, allowed)` `u32` triples    |
+//!
+//! Each buffer is a `u32` value count and a `u32` byte length followed by
+//! the DEFLATE-compressed values. Version 1 solutions (the same fields in
+//! one payload, in table order, without locks) still load.
+//!
+//! A browse-only solution keeps what it takes to show the solve, not to
+//! continue it: no regrets, and each hand's average strategy instead of
+//! its strategy sums. It is a fraction of the size of a full one.
+//!
+//! A [`SnapshotDelta`] (magic `b"PSDL"`) has `TREE`; `ITER`, the iteration
+//! the delta starts after and the one it brings the state to (`u64` each);
+//! `INFS`, the per-infoset block length (`u32`) and the changed infoset ids
+//! (compressed like the buffers, as `u32`); and `STRT`, those infosets'
+//! regret and strategy-sum blocks, concatenated in id order.
+
+use crate::solver::arena::GameTree;
+use crate::solver::format::{self, put_floats, put_str, put_u16s, put_u32s, Reader, Sections, V1_PAYLOAD};
+use crate::solver::types::GameConfig;
+
+/// Leading bytes of every solution file.
+pub const MAGIC: &[u8; 4] = b"PSOL";
+
+/// Leading bytes of a snapshot delta.
+pub const DELTA_MAGIC: &[u8; 4] = b"PSDL";
+
+/// A solved (or partially solved) spot: everything needed to rebuild the
+/// session and restore its solver state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub config: GameConfig,
+    /// Board cards, space separated (`"Ks Th 7s 4d 2c"`).
+    pub board: String,
+    /// Both ranges as comma-separated hands (`"As Ad, Kc Kd"`).
+    pub ranges: [String; 2],
+    /// `GameTree::structure_hash` of the tree the buffers belong to.
+    pub tree_hash: u64,
+    pub iterations: u64,
+    /// Empty in a browse-only solution.
+    pub regrets: Vec<f32>,
+    /// In a browse-only solution, each hand's average strategy: the sums
+    /// normalized, to within 1/65535 once read back.
+    pub strategy_sum: Vec<f32>,
+    /// Locked hands as `[infoset, hand, allowed action bits]` (see
+    /// `DCFRTrainer::lock_actions`); empty when nothing is locked.
+    pub locks: Vec<[u32; 3]>,
+}
+
+impl Solution {
+    /// Whether the solution holds average strategies but no regrets.
+    pub fn is_browse_only(&self) -> bool {
+        self.regrets.is_empty() && !self.strategy_sum.is_empty()
+    }
+
+    /// Encode into the on-disk format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let config = serde_json::to_string(&self.config).expect("GameConfig is always serializable");
+        let mut ranges = Vec::new();
+        put_str(&mut ranges, &self.ranges[0]);
+        put_str(&mut ranges, &self.ranges[1]);
+        let mut buffers = Vec::new();
+        let state = if self.is_browse_only() {
+            let quantized: Vec<u16> = self.strategy_sum.iter().map(|p| (p.clamp(0.0, 1.0) * 65535.0).round() as u16).collect();
+            put_u16s(&mut buffers, &quantized);
+            (*b"AVGS", buffers)
+        } else {
+            put_floats(&mut buffers, &self.regrets);
+            put_floats(&mut buffers, &self.strategy_sum);
+            (*b"STRT", buffers)
+        };
+
+        let mut sections = vec![
+            (*b"CONF", config.into_bytes()),
+            (*b"BORD", self.board.as_bytes().to_vec()),
+            (*b"RNGS", ranges),
+            (*b"TREE", self.tree_hash.to_le_bytes().to_vec()),
+            (*b"ITER", self.iterations.to_le_bytes().to_vec()),
+            state,
+        ];
+        if !self.locks.is_empty() {
+            let mut locks = Vec::new();
+            put_u32s(&mut locks, &self.locks.concat());
+            sections.push((*b"LOCK", locks));
+        }
+        format::write(MAGIC, &sections)
+    }
+
+    /// Decode and integrity-check a solution produced by [`Solution::to_bytes`]
+    /// (or by a version 1 writer).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Solution, String> {
+        let sections = format::read(MAGIC, "solution file", bytes)?;
+        if sections.version() == 1 {
+            return Self::from_v1(sections.require(&V1_PAYLOAD, "Solution file")?);
+        }
+        let section = |tag: &[u8; 4]| sections.require(tag, "Solution file").map(Reader::new);
+        let text = |tag: &[u8; 4]| {
+            String::from_utf8(sections.require(tag, "Solution file")?.to_vec()).map_err(|_| "Solution contains invalid UTF-8".to_string())
+        };
+
+        let config = serde_json::from_str(&text(b"CONF")?)
+            .map_err(|e| format!("Invalid config in solution: {}", e))?;
+        let board = text(b"BORD")?;
+        let mut ranges = section(b"RNGS")?;
+        let ranges = [ranges.string()?, ranges.string()?];
+        let tree_hash = section(b"TREE")?.u64()?;
+        let iterations = section(b"ITER")?.u64()?;
+        let (regrets, strategy_sum) = match sections.get(b"AVGS") {
+            Some(averages) => {
+                let averages = Reader::new(averages).u16s()?;
+                (Vec::new(), averages.into_iter().map(|q| q as f32 / 65535.0).collect())
+            }
+            None => {
+                let mut buffers = section(b"STRT")?;
+                (buffers.floats()?, buffers.floats()?)
+            }
+        };
+        let locks = read_locks(&sections)?;
+
+        Ok(Solution { config, board, ranges, tree_hash, iterations, regrets, strategy_sum, locks })
+    }
+
+    /// Decode the single payload of a version 1 solution.
+    fn from_v1(payload: &[u8]) -> Result<Solution, String> {
+        let mut reader = Reader::new(payload);
+        let config = serde_json::from_str(&reader.string()?)
+            .map_err(|e| format!("Invalid config in solution: {}", e))?;
+        let board = reader.string()?;
+        let ranges = [reader.string()?, reader.string()?];
+        let tree_hash = reader.u64()?;
+        let iterations = reader.u64()?;
+        let regrets = reader.floats()?;
+        let strategy_sum = reader.floats()?;
+
+        Ok(Solution { config, board, ranges, tree_hash, iterations, regrets, strategy_sum, locks: Vec::new() })
+    }
+
+    /// Check that the stored buffers belong to `tree` and have `buffer_len`
+    /// entries (a browse-only solution has no regrets to check).
+    pub fn check_tree(&self, tree: &GameTree, buffer_len: usize) -> Result<(), String> {
+        let hash = tree.structure_hash();
+        if hash != self.tree_hash {
+            return Err(format!(
+                "Solution was solved on a different game tree (hash {:016x}, expected {:016x})",
+                self.tree_hash, hash
+            ));
+        }
+        if (self.regrets.len() != buffer_len && !self.is_browse_only()) || self.strategy_sum.len() != buffer_len {
+            return Err(format!(
+                "Solution buffers have {} entries but this tree and ranges need {}",
+                self.strategy_sum.len(), buffer_len
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The infosets whose state was written between two iterations of a solve,
+/// with their current regret and strategy-sum blocks; the rest were only
+/// discounted, which the receiving session redoes. Applied in order on top
+/// of a full [`Solution`] checkpoint, deltas bring a restored session up to
+/// date without rewriting the whole state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotDelta {
+    /// `GameTree::structure_hash` of the tree the blocks belong to.
+    pub tree_hash: u64,
+    /// The delta holds every change made after this iteration.
+    pub since_iteration: u64,
+    /// Iteration count of the state the delta was taken from.
+    pub iterations: u64,
+    /// Entries per infoset block (`max_hands * max_actions`).
+    pub block_len: u32,
+    /// Changed infoset ids, ascending.
+    pub infosets: Vec<u32>,
+    pub regrets: Vec<f32>,
+    pub strategy_sum: Vec<f32>,
+}
+
+impl SnapshotDelta {
+    /// Encode into the delta format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut iterations = self.since_iteration.to_le_bytes().to_vec();
+        iterations.extend_from_slice(&self.iterations.to_le_bytes());
+        let mut infosets = self.block_len.to_le_bytes().to_vec();
+        put_u32s(&mut infosets, &self.infosets);
+        let mut blocks = Vec::new();
+        put_floats(&mut blocks, &self.regrets);
+        put_floats(&mut blocks, &self.strategy_sum);
+        format::write(DELTA_MAGIC, &[
+            (*b"TREE", self.tree_hash.to_le_bytes().to_vec()),
+            (*b"ITER", iterations),
+            (*b"INFS", infosets),
+            (*b"STRT", blocks),
+        ])
+    }
+
+    /// Decode and integrity-check a delta produced by [`SnapshotDelta::to_bytes`]
+    /// (or by a version 1 writer).
+    pub fn from_bytes(bytes: &[u8]) -> Result<SnapshotDelta, String> {
+        let sections = format::read(DELTA_MAGIC, "snapshot delta", bytes)?;
+        // The sections hold the fields of a version 1 payload, in order
+        let payload = match sections.version() {
+            1 => sections.require(&V1_PAYLOAD, "Snapshot delta")?.to_vec(),
+            _ => [b"TREE", b"ITER", b"INFS", b"STRT"].into_iter()
+                .map(|tag| sections.require(tag, "Snapshot delta"))
+                .collect::<Result<Vec<_>, _>>()?
+                .concat(),
+        };
+        let mut reader = Reader::new(&payload);
+        let tree_hash = reader.u64()?;
+        let since_iteration = reader.u64()?;
+        let iterations = reader.u64()?;
+        let block_len = reader.u32()?;
+        let infosets = reader.u32s()?;
+        let regrets = reader.floats()?;
+        let strategy_sum = reader.floats()?;
+
+        let expected = infosets.len() * block_len as usize;
+        if regrets.len() != expected || strategy_sum.len() != expected {
+            return Err(format!("Snapshot delta holds {} values, expected {}", strategy_sum.len(), expected));
+        }
+        Ok(SnapshotDelta { tree_hash, since_iteration, iterations, block_len, infosets, regrets, strategy_sum })
+    }
+}
+
+/// The `LOCK` section's triples, if any.
+fn read_locks(sections: &Sections) -> Result<Vec<[u32; 3]>, String> {
+    let Some(locks) = sections.get(b"LOCK") else { return Ok(Vec::new()) };
+    let values = Reader::new(locks).u32s()?;
+    if values.len() % 3 != 0 {
+        return Err(format!("Solution locks hold {} values, not whole triples", values.len()));
+    }
+    Ok(values.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect())
+}
+
+/// Write a solution file (native targets; the browser uses `export_solution`).
+pub fn save_solution(path: impl AsRef<std::path::Path>, solution: &Solution) -> Result<(), String> {
+    let path = path.as_ref();
+    std::fs::write(path, solution.to_bytes()).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/// Read and verify a solution file.
+pub fn load_solution(path: impl AsRef<std::path::Path>) -> Result<Solution, String> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    Solution::from_bytes(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::GameVariant;
+    use crate::solver::{build_river_tree, BetSize, RiverCards};
+
+    fn sample() -> Solution {
+        Solution {
+            config: GameConfig {
+                initial_pot: 100.0,
+                stacks: [100.0, 100.0],
+                bet_sizes: vec![BetSize::PotFraction(1.0)],
+                raise_sizes: vec![].into(),
+                river_bet_sizes: None,
+                river_raise_sizes: None,
+                river_cards: RiverCards::All,
+                raise_limit: 0,
+                chip_scale: 100,
+                icm: None,
+                bounty: None,
+                abstraction: None,
+                suit_isomorphism: false,
+                profiles: Vec::new(),
+                size_overrides: Vec::new(),
+                sizing_rules: Vec::new(),
+                donk_sizes: None,
+                player_sizes: Default::default(),
+                add_allin: true,
+                variant: GameVariant::Holdem,
+            },
+            board: "Ks Th 7s 4d 2c".to_string(),
+            ranges: ["As Ad, 6h 5h".to_string(), "Kc Kd, 9s 8s".to_string()],
+            tree_hash: 0x1234_5678_9abc_def0,
+            iterations: 42,
+            regrets: vec![0.0, -1.5, 2.25, 0.0, 0.0, 0.0],
+            strategy_sum: vec![0.25, 0.75, 0.0, 0.0, 1.0, 0.0],
+            locks: vec![[0, 1, 0b01]],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let solution = sample();
+        let bytes = solution.to_bytes();
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(Solution::from_bytes(&bytes).unwrap(), solution);
+
+        // Without locks the section is left out
+        let unlocked = Solution { locks: Vec::new(), ..solution };
+        let bytes = unlocked.to_bytes();
+        assert!(format::read(MAGIC, "solution file", &bytes).unwrap().get(b"LOCK").is_none());
+        assert_eq!(Solution::from_bytes(&bytes).unwrap(), unlocked);
+    }
+
+    #[test]
+    fn test_browse_only_round_trip() {
+        let full = sample();
+        let total: f32 = full.strategy_sum.iter().sum();
+        let solution = Solution { regrets: Vec::new(), strategy_sum: full.strategy_sum.iter().map(|s| s / total).collect(), ..full };
+        assert!(solution.is_browse_only());
+        let bytes = solution.to_bytes();
+        let sections = format::read(MAGIC, "solution file", &bytes).unwrap();
+        assert!(sections.get(b"AVGS").is_some() && sections.get(b"STRT").is_none());
+
+        let loaded = Solution::from_bytes(&bytes).unwrap();
+        assert!(loaded.is_browse_only());
+        for (a, b) in loaded.strategy_sum.iter().zip(&solution.strategy_sum) {
+            assert!((a - b).abs() <= 0.5 / 65535.0, "{} vs {}", a, b);
+        }
+        assert_eq!(Solution { strategy_sum: solution.strategy_sum.clone(), ..loaded }, solution);
+    }
+
+    #[test]
+    fn test_unknown_sections_are_skipped() {
+        let solution = sample();
+        let bytes = solution.to_bytes();
+        let sections = format::read(MAGIC, "solution file", &bytes).unwrap();
+        let mut extended: Vec<([u8; 4], Vec<u8>)> = vec![(*b"NEW1", b"from a later release".to_vec())];
+        extended.extend(sections.tags().map(|tag| (tag, sections.get(&tag).unwrap().to_vec())));
+        assert_eq!(Solution::from_bytes(&format::write(MAGIC, &extended)).unwrap(), solution);
+
+        extended.retain(|(tag, _)| tag != b"STRT");
+        assert_eq!(Solution::from_bytes(&format::write(MAGIC, &extended)).unwrap_err(), "Solution file has no STRT section");
+    }
+
+    #[test]
+    fn test_rejects_corruption() {
+        let bytes = sample().to_bytes();
+
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().unwrap() ^= 0xff;
+        assert!(Solution::from_bytes(&flipped).unwrap_err().contains("checksum"));
+
+        assert!(Solution::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err().contains("truncated"));
+        assert!(Solution::from_bytes(b"PSOL").unwrap_err().contains("magic"));
+
+        let mut future = bytes.clone();
+        future[4] = 99;
+        assert!(Solution::from_bytes(&future).unwrap_err().contains("newer"));
+    }
+
+    #[test]
+    fn test_delta_round_trip() {
+        let delta = SnapshotDelta {
+            tree_hash: 7,
+            since_iteration: 10,
+            iterations: 25,
+            block_len: 2,
+            infosets: vec![1, 4],
+            regrets: vec![0.5, -1.0, 0.0, 2.0],
+            strategy_sum: vec![1.0, 0.0, 0.25, 0.75],
+        };
+        let bytes = delta.to_bytes();
+        assert_eq!(&bytes[..4], DELTA_MAGIC);
+        assert_eq!(SnapshotDelta::from_bytes(&bytes).unwrap(), delta);
+        assert!(SnapshotDelta::from_bytes(&sample().to_bytes()).unwrap_err().contains("magic"));
+        assert!(Solution::from_bytes(&bytes).unwrap_err().contains("magic"));
+    }
+
+    #[test]
+    fn test_tree_mismatch_is_reported() {
+        let solution = sample();
+        let tree = build_river_tree(&solution.config).unwrap();
+        let err = solution.check_tree(&tree, solution.strategy_sum.len()).unwrap_err();
+        assert!(err.contains("different game tree"), "{}", err);
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("psol-test-{}.psol", std::process::id()));
+        save_solution(&path, &sample()).unwrap();
+        let loaded = load_solution(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded, sample());
+    }
+}
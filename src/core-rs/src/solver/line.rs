@@ -0,0 +1,198 @@
+//! Compact line strings: a whole history in one token, e.g.
+//! `"x-b75-r225-c"`.
+//!
+//! Steps are `x` (check), `c` (call), `f` (fold), `b75` and `r225` (a bet
+//! or raise putting in that many chips, in config units; a bare `b` or `r`
+//! takes the first size offered) and a card for the dealt river. Steps are
+//! separated by `-`, and a river by `|`: `"x-x|Kd-b50-c"`.
+//!
+//! Everywhere a history is accepted as a list of entries (`"check"`,
+//! `"bet 75"`, `"river Kd"`), an entry may also be a compact line; see
+//! [`parse_history`].
+
+use crate::poker::Card;
+use crate::solver::types::{ActionType, LineStep};
+
+/// Why a line string does not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineError {
+    /// Two separators in a row, or one at either end.
+    EmptyStep { line: String },
+    /// A step that is no action or card.
+    UnknownStep(String),
+    /// A bet or raise whose amount is not a number.
+    BadAmount(String),
+}
+
+impl std::fmt::Display for LineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineError::EmptyStep { line } => write!(f, "empty step in line '{}'", line),
+            LineError::UnknownStep(step) => write!(f, "unknown step '{}'", step),
+            LineError::BadAmount(step) => write!(f, "invalid amount in '{}'", step),
+        }
+    }
+}
+
+/// `line` in the compact form, e.g. `"x-b75-r225-c"` or `"x-x|Kd-b50"`.
+/// [`parse_line`] reads it back unchanged.
+pub fn format_line(line: &[LineStep]) -> String {
+    let mut out = String::new();
+    for (i, step) in line.iter().enumerate() {
+        match *step {
+            LineStep::River(card) => {
+                out.push('|');
+                out.push_str(&card.to_string());
+            }
+            LineStep::Action(action, amount) => {
+                if i > 0 {
+                    out.push('-');
+                }
+                out.push(letter(action));
+                if let Some(amount) = amount.filter(|_| action.is_aggressive()) {
+                    out.push_str(&amount.to_string());
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Parse a compact line (see the module docs). Letters are case-insensitive
+/// and either separator may come before any step; the empty string is the
+/// empty line.
+pub fn parse_line(s: &str) -> Result<Vec<LineStep>, LineError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    // A leading `|` deals the river before anyone acts
+    s.strip_prefix('|').unwrap_or(s)
+        .split(['-', '|'])
+        .map(|token| parse_step(token.trim()).map_err(|e| match e {
+            LineError::EmptyStep { .. } => LineError::EmptyStep { line: s.to_string() },
+            e => e,
+        }))
+        .collect()
+}
+
+/// Parse a history given as a list of entries: each entry is either one
+/// step in the long form (`"bet 75"`, `"check"`, `"river Kd"`) or a compact
+/// line of any number of steps.
+pub fn parse_history<S: AsRef<str>>(history: &[S]) -> Result<Vec<LineStep>, LineError> {
+    let mut line = Vec::new();
+    for entry in history {
+        let entry = entry.as_ref();
+        let first = entry.split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+        if ["fold", "check", "call", "bet", "raise", "river"].contains(&first.as_str()) {
+            line.push(LineStep::parse(entry).ok_or_else(|| LineError::UnknownStep(entry.to_string()))?);
+        } else {
+            line.extend(parse_line(entry)?);
+        }
+    }
+    Ok(line)
+}
+
+fn letter(action: ActionType) -> char {
+    match action {
+        ActionType::Fold => 'f',
+        ActionType::Check => 'x',
+        ActionType::Call => 'c',
+        ActionType::Bet => 'b',
+        ActionType::Raise => 'r',
+    }
+}
+
+fn parse_step(token: &str) -> Result<LineStep, LineError> {
+    if token.is_empty() {
+        return Err(LineError::EmptyStep { line: String::new() });
+    }
+    if token.len() == 2
+        && let Some(card) = Card::from_str(token)
+    {
+        return Ok(LineStep::River(card));
+    }
+    let (head, amount) = token.split_at(1);
+    let action = match head.to_ascii_lowercase().as_str() {
+        "x" => ActionType::Check,
+        "c" => ActionType::Call,
+        "f" => ActionType::Fold,
+        "b" => ActionType::Bet,
+        "r" => ActionType::Raise,
+        _ => return Err(LineError::UnknownStep(token.to_string())),
+    };
+    match amount {
+        "" => Ok(LineStep::Action(action, None)),
+        _ if !action.is_aggressive() => Err(LineError::UnknownStep(token.to_string())),
+        _ => match amount.parse::<f32>() {
+            Ok(amount) if amount.is_finite() && amount >= 0.0 => Ok(LineStep::Action(action, Some(amount))),
+            _ => Err(LineError::BadAmount(token.to_string())),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let line = parse_line("x-b75-R225-c").unwrap();
+        assert_eq!(line, [
+            LineStep::Action(ActionType::Check, None),
+            LineStep::Action(ActionType::Bet, Some(75.0)),
+            LineStep::Action(ActionType::Raise, Some(225.0)),
+            LineStep::Action(ActionType::Call, None),
+        ]);
+        let turn = parse_line("x-x|Kd-b12.5-f").unwrap();
+        assert_eq!(turn[2], LineStep::River(Card::from_str("Kd").unwrap()));
+        assert_eq!(turn[3], LineStep::Action(ActionType::Bet, Some(12.5)));
+        assert_eq!(parse_line(" ").unwrap(), []);
+        assert_eq!(parse_line("b").unwrap(), [LineStep::Action(ActionType::Bet, None)]);
+
+        assert_eq!(parse_line("x--b75"), Err(LineError::EmptyStep { line: "x--b75".to_string() }));
+        assert_eq!(parse_line("x-b75-"), Err(LineError::EmptyStep { line: "x-b75-".to_string() }));
+        assert_eq!(parse_line("x-z"), Err(LineError::UnknownStep("z".to_string())));
+        assert_eq!(parse_line("c75"), Err(LineError::UnknownStep("c75".to_string())));
+        assert_eq!(parse_line("b7x"), Err(LineError::BadAmount("b7x".to_string())));
+        assert_eq!(parse_line("bnan"), Err(LineError::BadAmount("bnan".to_string())));
+    }
+
+    #[test]
+    fn test_format_line_round_trips() {
+        // Every combination of a few steps, amounts with awkward decimals
+        let steps = [
+            LineStep::Action(ActionType::Check, None),
+            LineStep::Action(ActionType::Call, None),
+            LineStep::Action(ActionType::Fold, None),
+            LineStep::Action(ActionType::Bet, Some(0.1)),
+            LineStep::Action(ActionType::Bet, None),
+            LineStep::Action(ActionType::Raise, Some(1234.567)),
+            LineStep::River(Card::from_str("Ts").unwrap()),
+            LineStep::River(Card::from_str("2c").unwrap()),
+        ];
+        for a in steps {
+            for b in steps {
+                for c in steps {
+                    let line = [a, b, c];
+                    let formatted = format_line(&line);
+                    assert_eq!(parse_line(&formatted).unwrap(), line, "{}", formatted);
+                }
+            }
+        }
+        assert_eq!(format_line(&parse_line("x-x|Kd-b50-c").unwrap()), "x-x|Kd-b50-c");
+        assert_eq!(format_line(&[]), "");
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let expected = parse_line("x-b75-r225|Kd").unwrap();
+        assert_eq!(parse_history(&["check", "bet 75", "raise 225", "river Kd"]).unwrap(), expected);
+        assert_eq!(parse_history(&["x-b75-r225|Kd"]).unwrap(), expected);
+        // Both forms mix, entry by entry
+        assert_eq!(parse_history(&["check", "b75-r225", "river Kd"]).unwrap(), expected);
+        assert_eq!(parse_history::<&str>(&[]).unwrap(), []);
+        assert!(parse_history(&["river Zz"]).is_err());
+        assert!(parse_history(&["check", "q"]).is_err());
+    }
+}
@@ -1,11 +1,28 @@
-//! Solver module containing the game tree, CFR algorithm, and tree builder.
-
-pub mod arena;
-pub mod builder;
-pub mod types;
-pub mod dcfr;
-
-pub use arena::{GameTree, Node, NodeType};
-pub use builder::build_river_tree;
-pub use types::{GameConfig, ActionType};
-pub use dcfr::DCFRTrainer;
+//! Solver module containing the game tree, CFR algorithm, and tree builder.
+
+pub mod arena;
+pub mod builder;
+pub mod types;
+pub mod dcfr;
+pub mod best_response;
+pub mod io;
+pub mod format;
+pub mod hash;
+pub mod icm;
+pub mod abstraction;
+pub mod profile;
+pub mod line;
+pub mod preflop;
+pub mod multiway;
+
+pub use arena::{GameTree, Node, NodeType, TreeError, TreeStats};
+pub use builder::{build_river_tree, build_turn_tree};
+pub use abstraction::Abstraction;
+pub use line::{format_line, parse_history, parse_line, LineError};
+pub use preflop::{build_preflop_tree, PreflopConfig, PreflopSolver};
+pub use multiway::{build_multiway_tree, MultiwayConfig, MultiwaySolver};
+pub use profile::{NodeLock, ProfileRule, StrategyProfile};
+pub use types::{GameConfig, AbstractionConfig, BetSize, BountyConfig, IcmConfig, PlayerSizes, RaiseSize, RaiseSizes, RiverCards, SizeOverride, SizingRule, StreetName, ActionType, LineStep};
+pub use dcfr::{DCFRTrainer, InvariantKind, InvariantReport, InvariantViolation, PerfCounters};
+pub use best_response::{best_response_value, counterfactual_values, exploitability, node_value, player_exploitability, pot_share_values, ConvergencePoint, Play, PlayerExploitability};
+pub use io::{SnapshotDelta, Solution};
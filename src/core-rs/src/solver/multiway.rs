@@ -0,0 +1,560 @@
+//! Three-handed river solving.
+//!
+//! The main trees are heads-up throughout (`Node::bets` has two entries and
+//! the trainer keeps two ranges), so a multiway spot gets a game of its own,
+//! restricted to what a river needs: three players on a complete board,
+//! acting in seat order (player 0 first), with the same stack behind each
+//! so that there are never side pots. A player who folds is out of the
+//! pot; when one player is left they take it, else the best hand among
+//! the players left wins it at showdown, ties splitting it.
+//!
+//! Config amounts are in units of the pot, as in [`crate::solver::GameConfig`].
+//! Utilities are chip EV: what a player wins back, less what they put in
+//! on the river and a third of the starting pot, so the players' values
+//! sum to zero.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::poker::range::parse_weighted_range;
+use crate::poker::{Card, GameVariant};
+use crate::solver::dcfr::DCFRTrainer;
+use crate::solver::line::format_line;
+use crate::solver::types::{ActionType, LineStep};
+
+/// Players in a multiway spot.
+pub const PLAYERS: usize = 3;
+
+/// Chips per unit of the config amounts.
+const CHIP_SCALE: u32 = 100;
+
+/// Shape of a three-handed river tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultiwayConfig {
+    pub initial_pot: f32,
+    /// Every player's stack behind at the start of the river.
+    pub stack: f32,
+    /// Bets, as fractions of the pot.
+    #[serde(default = "default_bet_sizes")]
+    pub bet_sizes: Vec<f32>,
+    /// Raises, as fractions of the pot once the raiser has called.
+    #[serde(default = "default_raise_sizes")]
+    pub raise_sizes: Vec<f32>,
+    /// Raises allowed after the first bet.
+    #[serde(default = "default_raise_limit")]
+    pub raise_limit: u8,
+}
+
+fn default_bet_sizes() -> Vec<f32> {
+    vec![0.75]
+}
+
+fn default_raise_sizes() -> Vec<f32> {
+    vec![1.0]
+}
+
+fn default_raise_limit() -> u8 {
+    1
+}
+
+impl MultiwayConfig {
+    /// A config with the default sizes.
+    pub fn new(initial_pot: f32, stack: f32) -> Self {
+        MultiwayConfig {
+            initial_pot,
+            stack,
+            bet_sizes: default_bet_sizes(),
+            raise_sizes: default_raise_sizes(),
+            raise_limit: default_raise_limit(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.initial_pot.is_finite() || self.initial_pot <= 0.0 {
+            return Err(format!("initial_pot must be a finite positive amount, got {}", self.initial_pot));
+        }
+        if !self.stack.is_finite() || self.stack < 0.0 {
+            return Err(format!("stack must be a finite non-negative amount, got {}", self.stack));
+        }
+        for (name, sizes) in [("bet_sizes", &self.bet_sizes), ("raise_sizes", &self.raise_sizes)] {
+            if let Some(size) = sizes.iter().find(|s| !s.is_finite() || **s <= 0.0) {
+                return Err(format!("{} must be positive fractions of the pot, got {}", name, size));
+            }
+        }
+        Ok(())
+    }
+
+    fn to_chips(&self, amount: f32) -> i32 {
+        (amount as f64 * CHIP_SCALE as f64).round() as i32
+    }
+}
+
+/// A node of a three-handed tree. Children are stored together, in action
+/// order, from `children_start`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultiwayNode {
+    /// Player to act, or `None` once the hand is over.
+    pub player: Option<u8>,
+    /// Size of the pot, in chips.
+    pub pot: i32,
+    /// Chips each player has put in on the river.
+    pub bets: [i32; PLAYERS],
+    pub folded: [bool; PLAYERS],
+    pub children_start: u32,
+    pub num_actions: u8,
+    /// Index of the node's strategy in the trainer; `u32::MAX` once the
+    /// hand is over.
+    pub infoset_id: u32,
+    pub action_from_parent: Option<ActionType>,
+    /// Chips the action leading here put in.
+    pub amount_from_parent: i32,
+}
+
+impl MultiwayNode {
+    pub fn is_terminal(&self) -> bool {
+        self.player.is_none()
+    }
+
+    /// Players still in the pot.
+    pub fn active(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..PLAYERS).filter(|&p| !self.folded[p])
+    }
+}
+
+/// Betting state carried down the tree while building it.
+#[derive(Clone, Copy)]
+struct Round {
+    /// Bit `p` set while player `p` has yet to act on the last bet (or at
+    /// all, before one).
+    pending: u8,
+    /// Bets and raises so far.
+    aggressions: u8,
+    /// Chips the last bet or raise added over the one before it, the least
+    /// a raise must add.
+    last_raise: i32,
+}
+
+/// Build the tree of a (valid) config. Node 0 is the root.
+pub fn build_multiway_tree(config: &MultiwayConfig) -> Vec<MultiwayNode> {
+    let root = MultiwayNode {
+        player: Some(0),
+        pot: config.to_chips(config.initial_pot),
+        bets: [0; PLAYERS],
+        folded: [false; PLAYERS],
+        children_start: 0,
+        num_actions: 0,
+        infoset_id: u32::MAX,
+        action_from_parent: None,
+        amount_from_parent: 0,
+    };
+    let mut nodes = vec![root];
+    let mut infosets = 0;
+    let round = Round { pending: (1 << PLAYERS) - 1, aggressions: 0, last_raise: 0 };
+    build_subtree(&mut nodes, config, 0, round, &mut infosets);
+    nodes
+}
+
+/// Expand decision node `node_id`.
+fn build_subtree(nodes: &mut Vec<MultiwayNode>, config: &MultiwayConfig, node_id: usize, round: Round, infosets: &mut u32) {
+    let node = nodes[node_id].clone();
+    let player = node.player.expect("a decision node") as usize;
+    let stack = config.to_chips(config.stack);
+    let top = *node.bets.iter().max().expect("three players");
+    let facing = top - node.bets[player];
+    let behind = stack - node.bets[player];
+
+    let mut actions = Vec::new();
+    if facing > 0 {
+        actions.push((ActionType::Fold, 0));
+        actions.push((ActionType::Call, facing.min(behind)));
+    } else {
+        actions.push((ActionType::Check, 0));
+    }
+    let (kind, sizes) = if facing > 0 { (ActionType::Raise, &config.raise_sizes) } else { (ActionType::Bet, &config.bet_sizes) };
+    if round.aggressions <= config.raise_limit && top < stack && behind > facing {
+        // Raise sizes count the pot once the raiser has called
+        let base = node.pot + facing;
+        let mut amounts: Vec<i32> = sizes.iter()
+            .map(|&size| facing + ((base as f64 * size as f64).round() as i32).max(round.last_raise).max(1))
+            .map(|amount| amount.min(behind))
+            .collect();
+        amounts.sort_unstable();
+        amounts.dedup();
+        actions.extend(amounts.into_iter().map(|amount| (kind, amount)));
+    }
+
+    let children_start = nodes.len() as u32;
+    nodes[node_id].children_start = children_start;
+    nodes[node_id].num_actions = actions.len() as u8;
+    nodes[node_id].infoset_id = *infosets;
+    *infosets += 1;
+
+    let mut decisions = Vec::new();
+    for (action, amount) in actions {
+        let mut child = node.clone();
+        child.children_start = 0;
+        child.num_actions = 0;
+        child.infoset_id = u32::MAX;
+        child.action_from_parent = Some(action);
+        child.amount_from_parent = amount;
+        child.bets[player] += amount;
+        child.pot += amount;
+        let mut next = round;
+        next.pending &= !(1 << player);
+        match action {
+            ActionType::Fold => child.folded[player] = true,
+            ActionType::Bet | ActionType::Raise => {
+                next.aggressions += 1;
+                next.last_raise = next.last_raise.max(amount - facing);
+                // Everyone still in with chips behind acts on it again
+                next.pending = (0..PLAYERS)
+                    .filter(|&p| p != player && !child.folded[p] && child.bets[p] < stack)
+                    .fold(0, |bits, p| bits | 1 << p);
+            }
+            ActionType::Check | ActionType::Call => {}
+        }
+        let left = child.active().count();
+        child.player = (1..PLAYERS)
+            .map(|step| (player + step) % PLAYERS)
+            .find(|&p| left > 1 && next.pending & (1 << p) != 0)
+            .map(|p| p as u8);
+        nodes.push(child);
+        if nodes.last().expect("just pushed").player.is_some() {
+            decisions.push((nodes.len() - 1, next));
+        }
+    }
+    for (child_id, next) in decisions {
+        build_subtree(nodes, config, child_id, next, infosets);
+    }
+}
+
+/// A three-handed river spot and its DCFR solve.
+pub struct MultiwaySolver {
+    pub config: MultiwayConfig,
+    pub board: Vec<Card>,
+    /// Each player's hands, less those the board blocks.
+    pub hands: [Vec<[Card; 2]>; PLAYERS],
+    pub weights: [Vec<f32>; PLAYERS],
+    pub nodes: Vec<MultiwayNode>,
+    pub trainer: DCFRTrainer,
+    /// Hand scores, lower is better (see [`GameVariant::evaluate`]).
+    scores: [Vec<u16>; PLAYERS],
+    /// Every deal of one hand to each player with no card twice.
+    deals: Vec<[usize; PLAYERS]>,
+}
+
+impl MultiwaySolver {
+    pub fn new(config: MultiwayConfig, board: &[Card], ranges: [&str; PLAYERS]) -> Result<Self, String> {
+        config.validate()?;
+        let board_mask = board.iter().fold(0u64, |mask, c| mask | c.bitmask());
+        if board.len() != 5 || board_mask.count_ones() != 5 {
+            return Err(format!("A multiway spot needs a river board of five different cards, got {}", board.len()));
+        }
+        let mut hands: [Vec<[Card; 2]>; PLAYERS] = Default::default();
+        let mut weights: [Vec<f32>; PLAYERS] = Default::default();
+        for (p, range) in ranges.iter().enumerate() {
+            for (hand, weight) in parse_weighted_range(range) {
+                if (hand[0].bitmask() | hand[1].bitmask()) & board_mask == 0 {
+                    hands[p].push([hand[0], hand[1]]);
+                    weights[p].push(weight);
+                }
+            }
+            if hands[p].is_empty() {
+                return Err(format!("Player {}'s range has no hands the board leaves live", p));
+            }
+        }
+        let scores = hands.each_ref().map(|hands| {
+            hands.iter().map(|hand| GameVariant::Holdem.evaluate(&[board, hand.as_slice()].concat())).collect()
+        });
+
+        let masks = hands.each_ref().map(|hands| hands.iter().map(|h| h[0].bitmask() | h[1].bitmask()).collect::<Vec<u64>>());
+        let mut deals = Vec::new();
+        for (a, &ma) in masks[0].iter().enumerate() {
+            for (b, &mb) in masks[1].iter().enumerate().filter(|&(_, &mb)| ma & mb == 0) {
+                for (c, _) in masks[2].iter().enumerate().filter(|&(_, &mc)| (ma | mb) & mc == 0) {
+                    deals.push([a, b, c]);
+                }
+            }
+        }
+        if deals.is_empty() {
+            return Err("The ranges block each other: no deal gives every player a hand".to_string());
+        }
+
+        let nodes = build_multiway_tree(&config);
+        let infosets = nodes.iter().filter(|n| !n.is_terminal()).count();
+        let max_actions = nodes.iter().map(|n| n.num_actions as usize).max().unwrap_or(0);
+        let max_hands = hands.iter().map(Vec::len).max().expect("three players");
+        let trainer = DCFRTrainer::new(infosets, max_actions, [max_hands; 2]);
+        Ok(MultiwaySolver { config, board: board.to_vec(), hands, weights, nodes, trainer, scores, deals })
+    }
+
+    /// Run DCFR iterations.
+    pub fn train(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            let mut trainer = std::mem::replace(&mut self.trainer, DCFRTrainer::new(0, 0, [0, 0]));
+            trainer.run_iteration(|trainer| {
+                let reach = self.weights.each_ref().map(Vec::as_slice);
+                self.cfr(trainer, 0, reach);
+            });
+            self.trainer = trainer;
+        }
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.trainer.iterations
+    }
+
+    fn to_amount(&self, chips: i32) -> f32 {
+        (chips as f64 / CHIP_SCALE as f64) as f32
+    }
+
+    /// What each player gains per deal, in config units, by best-responding
+    /// while the other two keep their average strategies.
+    pub fn best_response_gains(&self) -> [f32; PLAYERS] {
+        let total: f32 = self.deals.iter().map(|d| (0..PLAYERS).map(|p| self.weights[p][d[p]]).product::<f32>()).sum();
+        [0, 1, 2].map(|player| {
+            let ones = vec![1.0; self.hands[player].len()];
+            let mut reach = self.weights.each_ref().map(Vec::as_slice);
+            reach[player] = &ones;
+            let value = |best: bool| -> f32 {
+                self.values(0, player, reach, best).iter().zip(&self.weights[player]).map(|(v, w)| v * w).sum()
+            };
+            (value(true) - value(false)) / total
+        })
+    }
+
+    /// Mean of [`MultiwaySolver::best_response_gains`]. With more than two
+    /// players this bounds how far the strategies are from an equilibrium
+    /// rather than what a single exploiter can win.
+    pub fn exploitability(&self) -> f32 {
+        self.best_response_gains().iter().sum::<f32>() / PLAYERS as f32
+    }
+
+    /// Average strategy of every hand of the player to act at `node_idx`.
+    pub fn strategy(&self, node_idx: usize) -> Option<Vec<Vec<f32>>> {
+        let node = self.nodes.get(node_idx).filter(|n| !n.is_terminal())?;
+        let player = node.player.expect("a decision node") as usize;
+        Some((0..self.hands[player].len())
+            .map(|h| self.trainer.average_strategy(node.infoset_id as usize, h, node.num_actions as usize))
+            .collect())
+    }
+
+    /// Every decision node: `{ "iterations", "exploitability",
+    /// "exploitability_pct", "nodes": [{ "node", "line", "player",
+    /// "actions": [{ "type", "amount" }], "strategy": { "As Ks": [..], .. }
+    /// }] }`, amounts in config units and the exploitability as a
+    /// percentage of the starting pot too.
+    pub fn report(&self) -> serde_json::Value {
+        let mut nodes = Vec::new();
+        let mut stack = vec![(0usize, Vec::new())];
+        while let Some((n, line)) = stack.pop() {
+            let node = &self.nodes[n];
+            let Some(player) = node.player else { continue };
+            let children = node.children_start as usize..node.children_start as usize + node.num_actions as usize;
+            let actions: Vec<serde_json::Value> = children.clone()
+                .map(|c| json!({
+                    "type": self.nodes[c].action_from_parent.map(|a| a.as_str()),
+                    "amount": self.to_amount(self.nodes[c].amount_from_parent),
+                }))
+                .collect();
+            let strategy: serde_json::Map<String, serde_json::Value> = self.strategy(n)
+                .expect("a decision node")
+                .into_iter()
+                .zip(&self.hands[player as usize])
+                .map(|(probs, hand)| (format!("{} {}", hand[0], hand[1]), json!(probs)))
+                .collect();
+            nodes.push(json!({
+                "node": n,
+                "line": format_line(&line),
+                "player": player,
+                "actions": actions,
+                "strategy": strategy,
+            }));
+            for c in children.rev() {
+                let child = &self.nodes[c];
+                let mut line = line.clone();
+                line.push(LineStep::Action(child.action_from_parent.expect("a child"), Some(self.to_amount(child.amount_from_parent))));
+                stack.push((c, line));
+            }
+        }
+        let exploitability = self.exploitability();
+        json!({
+            "iterations": self.iterations(),
+            "exploitability": exploitability,
+            "exploitability_pct": 100.0 * exploitability / self.config.initial_pot,
+            "nodes": nodes,
+        })
+    }
+
+    /// Every player's chip EV of a finished hand, given whose hands are dealt.
+    fn payoff(&self, node: &MultiwayNode, deal: &[usize; PLAYERS]) -> [f32; PLAYERS] {
+        let best = node.active().map(|p| self.scores[p][deal[p]]).min().expect("someone is left");
+        let winners: Vec<usize> = node.active().filter(|&p| self.scores[p][deal[p]] == best).collect();
+        let share = node.pot as f32 / winners.len() as f32;
+        let ante = self.nodes[0].pot as f32 / PLAYERS as f32;
+        [0, 1, 2].map(|p| {
+            let won = if winners.contains(&p) { share } else { 0.0 };
+            (won - node.bets[p] as f32 - ante) / CHIP_SCALE as f32
+        })
+    }
+
+    /// Every player's value of each of their hands at a finished hand,
+    /// summed over the others' hands weighted by their `reach`.
+    fn payoffs(&self, node: &MultiwayNode, reach: [&[f32]; PLAYERS]) -> [Vec<f32>; PLAYERS] {
+        let mut values = self.hands.each_ref().map(|hands| vec![0.0; hands.len()]);
+        for deal in &self.deals {
+            let r = [0, 1, 2].map(|p| reach[p][deal[p]]);
+            if r.iter().filter(|&&r| r == 0.0).count() > 1 {
+                continue;
+            }
+            let payoff = self.payoff(node, deal);
+            for p in 0..PLAYERS {
+                values[p][deal[p]] += payoff[p] * r[(p + 1) % PLAYERS] * r[(p + 2) % PLAYERS];
+            }
+        }
+        values
+    }
+
+    /// One CFR traversal below `node_idx`; returns every player's values.
+    fn cfr(&self, trainer: &mut DCFRTrainer, node_idx: usize, reach: [&[f32]; PLAYERS]) -> [Vec<f32>; PLAYERS] {
+        let node = &self.nodes[node_idx];
+        let Some(player) = node.player.map(usize::from) else {
+            return self.payoffs(node, reach);
+        };
+        let (num_actions, infoset, num_hands) = (node.num_actions as usize, node.infoset_id as usize, self.hands[player].len());
+        let strategy = trainer.current_strategy(infoset, num_hands, num_actions);
+        let mut values = self.hands.each_ref().map(|hands| vec![0.0; hands.len()]);
+        let mut action_values = Vec::with_capacity(num_actions);
+        for a in 0..num_actions {
+            let own: Vec<f32> = reach[player].iter().enumerate().map(|(h, r)| r * strategy[h * num_actions + a]).collect();
+            let mut next = reach;
+            next[player] = &own;
+            let mut child_values = self.cfr(trainer, node.children_start as usize + a, next);
+            for (p, (values, child)) in values.iter_mut().zip(&child_values).enumerate() {
+                for (h, (v, c)) in values.iter_mut().zip(child).enumerate() {
+                    *v += if p == player { strategy[h * num_actions + a] * c } else { *c };
+                }
+            }
+            action_values.push(std::mem::take(&mut child_values[player]));
+        }
+        for (a, child_values) in action_values.iter().enumerate() {
+            for h in 0..num_hands {
+                trainer.add_regret(infoset, h, a, child_values[h] - values[player][h]);
+            }
+        }
+        for (h, row) in strategy.chunks(num_actions).enumerate() {
+            trainer.add_strategy(infoset, h, row, reach[player][h]);
+        }
+        values
+    }
+
+    /// `player`'s value of every hand below `node_idx` against the others'
+    /// average strategies (reaching the node with `reach`), playing a best
+    /// response if `best`, else the average strategy.
+    fn values(&self, node_idx: usize, player: usize, reach: [&[f32]; PLAYERS], best: bool) -> Vec<f32> {
+        let node = &self.nodes[node_idx];
+        let Some(actor) = node.player.map(usize::from) else {
+            return std::mem::take(&mut self.payoffs(node, reach)[player]);
+        };
+        let (num_actions, infoset) = (node.num_actions as usize, node.infoset_id as usize);
+        let strategies: Vec<Vec<f32>> = (0..self.hands[actor].len())
+            .map(|h| self.trainer.average_strategy(infoset, h, num_actions))
+            .collect();
+        let mut values = vec![if actor == player && best { f32::NEG_INFINITY } else { 0.0 }; self.hands[player].len()];
+        for a in 0..num_actions {
+            let child = node.children_start as usize + a;
+            if actor == player {
+                for (h, (v, c)) in values.iter_mut().zip(self.values(child, player, reach, best)).enumerate() {
+                    *v = if best { v.max(c) } else { *v + strategies[h][a] * c };
+                }
+            } else {
+                let next: Vec<f32> = reach[actor].iter().zip(&strategies).map(|(r, s)| r * s[a]).collect();
+                let mut child_reach = reach;
+                child_reach[actor] = &next;
+                for (v, c) in values.iter_mut().zip(self.values(child, player, child_reach, best)) {
+                    *v += c;
+                }
+            }
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().filter_map(Card::from_str).collect()
+    }
+
+    fn child(nodes: &[MultiwayNode], node: usize, action: ActionType) -> usize {
+        let n = &nodes[node];
+        (n.children_start as usize..n.children_start as usize + n.num_actions as usize)
+            .find(|&c| nodes[c].action_from_parent == Some(action))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_multiway_tree() {
+        let nodes = build_multiway_tree(&MultiwayConfig::new(10.0, 100.0));
+        use ActionType::*;
+        // Three checks go to showdown
+        let checks = child(&nodes, child(&nodes, child(&nodes, 0, Check), Check), Check);
+        assert!(nodes[checks].is_terminal() && nodes[checks].folded == [false; 3]);
+
+        // A bet is acted on by both players behind, and a fold leaves two in
+        let bet = child(&nodes, 0, Bet);
+        assert_eq!((nodes[bet].player, nodes[bet].bets), (Some(1), [750, 0, 0]));
+        let fold = child(&nodes, bet, Fold);
+        assert_eq!(nodes[fold].player, Some(2));
+        let call = child(&nodes, fold, Call);
+        assert!(nodes[call].is_terminal() && nodes[call].pot == 1000 + 1500);
+        assert!(nodes[child(&nodes, fold, Fold)].is_terminal());
+
+        // After the one raise allowed, the bettor can only call or fold
+        let raise = child(&nodes, child(&nodes, bet, Call), Raise);
+        assert_eq!(nodes[raise].bets, [750, 750, 750 + 3250]);
+        assert_eq!(nodes[raise].player, Some(0));
+        assert_eq!(nodes[raise].num_actions, 2);
+        let called = child(&nodes, raise, Call);
+        assert_eq!(nodes[called].player, Some(1));
+        assert!(nodes[child(&nodes, called, Call)].is_terminal());
+
+        // Bets stop at the stack
+        let short = build_multiway_tree(&MultiwayConfig::new(10.0, 5.0));
+        assert_eq!(short[child(&short, 0, Bet)].bets[0], 500);
+        assert!(MultiwayConfig::new(0.0, 5.0).validate().is_err());
+    }
+
+    #[test]
+    fn test_multiway_solve() {
+        let board = cards("Ks Th 7s 4d 2c");
+        let ranges = ["As Ad, 6h 5h, Ah Qh", "Kc Kd, 9s 8s, Ac Qc", "Tc Td, Js 9c, Ad Qd"];
+        let mut solver = MultiwaySolver::new(MultiwayConfig::new(10.0, 20.0), &board, ranges).unwrap();
+        solver.train(10);
+        let early = solver.exploitability();
+        solver.train(290);
+        let late = solver.exploitability();
+        assert!(late < early && late < 0.05 * 10.0, "{} -> {}", early, late);
+
+        // The values of every deal sum to zero
+        let showdown = &solver.nodes[child(&solver.nodes, child(&solver.nodes, child(&solver.nodes, 0, ActionType::Check), ActionType::Check), ActionType::Check)];
+        for deal in &solver.deals {
+            assert!(solver.payoff(showdown, deal).iter().sum::<f32>().abs() < 1e-4);
+        }
+
+        // Kings are the best hand: they never fold to a bet
+        let bet = child(&solver.nodes, 0, ActionType::Bet);
+        let kings = solver.strategy(bet).unwrap()[0].clone();
+        assert!(kings[0] < 0.05, "{:?}", kings);
+
+        let report = solver.report();
+        assert_eq!(report["nodes"][0]["line"], "");
+        assert_eq!(report["nodes"][0]["strategy"]["As Ad"].as_array().unwrap().len(), 2);
+        assert_eq!(report["nodes"][1]["line"], "x");
+
+        assert!(MultiwaySolver::new(MultiwayConfig::new(10.0, 20.0), &board, ["As Ad", "Ks Kd", "Qh Qd"]).is_err());
+        assert!(MultiwaySolver::new(MultiwayConfig::new(10.0, 20.0), &board, ["As Ad", "As Ac", "Ad Ac"]).is_err());
+    }
+}
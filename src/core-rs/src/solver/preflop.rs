@@ -0,0 +1,520 @@
+//! Heads-up preflop solving over the 169 hand classes.
+//!
+//! Player 0 is the small blind, who has the button and acts first
+//! preflop; player 1 is the big blind. The tree offers a limp, then raises
+//! level by level (open, 3-bet, 4-bet), each level with its own sizes and
+//! the all-in, and ends at a fold or at a call (or the big blind checking
+//! behind a limp). There is no postflop play: a called pot is split by the
+//! classes' all-in equity (see [`crate::poker::preflop`]), card removal
+//! between the classes included.
+//!
+//! Config amounts are in big blinds. In the tree, `Node::bets` are each
+//! player's whole contribution, blinds included, and the pot is their sum.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::poker::preflop::{class_combos, class_name, preflop_table, NUM_CLASSES};
+use crate::solver::arena::{GameTree, Node, NodeType, TreeError};
+use crate::solver::dcfr::DCFRTrainer;
+use crate::solver::line::format_line;
+use crate::solver::types::ActionType;
+
+/// Chips per big blind in preflop trees.
+const CHIPS_PER_BB: u32 = 100;
+
+/// Shape of a preflop tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreflopConfig {
+    /// Both players' stacks before the blinds are posted.
+    pub stack: f32,
+    #[serde(default = "default_small_blind")]
+    pub small_blind: f32,
+    /// Whether the small blind may complete the big blind.
+    #[serde(default = "default_allow_limp")]
+    pub allow_limp: bool,
+    /// Opening raises, as the amount raised to; the big blind raises a
+    /// limp to the same amounts.
+    #[serde(default = "default_open_sizes")]
+    pub open_sizes: Vec<f32>,
+    /// 3-bets, as a multiple of the amount opened to.
+    #[serde(default = "default_three_bet_sizes")]
+    pub three_bet_sizes: Vec<f32>,
+    /// 4-bets, as a multiple of the amount 3-bet to.
+    #[serde(default = "default_four_bet_sizes")]
+    pub four_bet_sizes: Vec<f32>,
+    /// Raises allowed in a hand. Levels past the size lists only offer the
+    /// all-in, so the default 4 makes the fifth bet a shove.
+    #[serde(default = "default_raise_limit")]
+    pub raise_limit: u8,
+}
+
+fn default_small_blind() -> f32 {
+    0.5
+}
+
+fn default_allow_limp() -> bool {
+    true
+}
+
+fn default_open_sizes() -> Vec<f32> {
+    vec![2.5]
+}
+
+fn default_three_bet_sizes() -> Vec<f32> {
+    vec![3.0]
+}
+
+fn default_four_bet_sizes() -> Vec<f32> {
+    vec![2.25]
+}
+
+fn default_raise_limit() -> u8 {
+    4
+}
+
+impl PreflopConfig {
+    /// A config with the default sizes and `stack` big blinds.
+    pub fn new(stack: f32) -> Self {
+        PreflopConfig {
+            stack,
+            small_blind: default_small_blind(),
+            allow_limp: default_allow_limp(),
+            open_sizes: default_open_sizes(),
+            three_bet_sizes: default_three_bet_sizes(),
+            four_bet_sizes: default_four_bet_sizes(),
+            raise_limit: default_raise_limit(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.stack.is_finite() || self.stack <= 1.0 {
+            return Err(format!("stack must be more than the big blind, got {}", self.stack));
+        }
+        if !(self.small_blind > 0.0 && self.small_blind < 1.0) {
+            return Err(format!("small_blind must be between 0 and 1 big blind, got {}", self.small_blind));
+        }
+        let sizes = [("open_sizes", &self.open_sizes), ("three_bet_sizes", &self.three_bet_sizes), ("four_bet_sizes", &self.four_bet_sizes)];
+        for (name, sizes) in sizes {
+            if let Some(size) = sizes.iter().find(|s| !s.is_finite() || **s <= 1.0) {
+                return Err(format!("{} must be more than 1, got {}", name, size));
+            }
+        }
+        Ok(())
+    }
+
+    fn to_chips(&self, amount: f32) -> i32 {
+        (amount as f64 * CHIPS_PER_BB as f64).round() as i32
+    }
+}
+
+/// Build the tree of a (valid) preflop config.
+pub fn build_preflop_tree(config: &PreflopConfig) -> Result<GameTree, TreeError> {
+    let mut tree = GameTree::new();
+    tree.chip_scale = CHIPS_PER_BB;
+    let stack = config.to_chips(config.stack);
+    let blinds = [config.to_chips(config.small_blind), config.to_chips(1.0)];
+    tree.stacks = [stack - blinds[0], stack - blinds[1]];
+
+    let mut root = Node::new(NodeType::Action, 0, blinds[0] + blinds[1]);
+    root.bets = blinds;
+    let root_id = tree.add_node(root);
+    build_subtree(&mut tree, config, root_id, stack, 0, &mut Vec::new())?;
+
+    #[cfg(debug_assertions)]
+    if let Err(errors) = tree.validate() {
+        panic!("built an invalid preflop tree: {}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; "));
+    }
+    Ok(tree)
+}
+
+/// Expand decision node `node_id`, `raises` raises into the hand, reached
+/// by the actions in `history`: each action's byte and chips.
+fn build_subtree(tree: &mut GameTree, config: &PreflopConfig, node_id: u32, stack: i32, raises: u8, history: &mut Vec<u8>) -> Result<(), TreeError> {
+    let node = tree.nodes[node_id as usize].clone();
+    let (player, opponent) = (node.player as usize, 1 - node.player as usize);
+    let facing = node.bets[opponent] - node.bets[player];
+
+    let mut actions = Vec::new();
+    if facing > 0 {
+        actions.push((ActionType::Fold, 0));
+        // Completing the big blind opens the action for it; other calls close it
+        let limp = raises == 0 && player == 0;
+        if !limp || config.allow_limp {
+            actions.push((ActionType::Call, facing.min(stack - node.bets[player])));
+        }
+    } else {
+        actions.push((ActionType::Check, 0));
+    }
+    if raises < config.raise_limit && node.bets[opponent] < stack {
+        let facing_to = node.bets[opponent];
+        let sizes: &[f32] = match raises {
+            0 => &config.open_sizes,
+            1 => &config.three_bet_sizes,
+            2 => &config.four_bet_sizes,
+            _ => &[],
+        };
+        // A raise adds at least the last bet or raise, the big blind at first
+        let min_to = facing_to + facing.max(config.to_chips(1.0));
+        let mut targets: Vec<i32> = sizes.iter()
+            .map(|&size| if raises == 0 { config.to_chips(size) } else { (facing_to as f64 * size as f64).round() as i32 })
+            .map(|to| to.max(min_to))
+            .filter(|&to| to < stack)
+            .collect();
+        targets.push(stack);
+        targets.sort_unstable();
+        targets.dedup();
+        // The big blind is the first bet, so even raising a limp is a raise
+        actions.extend(targets.into_iter().map(|to| (ActionType::Raise, to - node.bets[player])));
+    }
+
+    // The infoset is the player and the betting sequence
+    let mut key = vec![player as u8];
+    key.extend_from_slice(history);
+    let infoset_id = tree.get_infoset_id(node_id, &key)?;
+    let children_start = tree.nodes.len() as u32;
+    {
+        let node = tree.get_node_mut(node_id);
+        node.num_actions = actions.len() as u8;
+        node.children_start = children_start;
+        node.infoset_id = infoset_id;
+    }
+
+    let mut decisions = Vec::new();
+    for (action, amount) in actions {
+        let mut bets = node.bets;
+        bets[player] += amount;
+        let mut child = Node::new(NodeType::Action, opponent as u8, bets[0] + bets[1]);
+        child.action_from_parent = Some(action);
+        child.amount_from_parent = amount;
+        child.bets = bets;
+        child.all_in = bets.map(|b| b == stack);
+        let next_raises = match action {
+            ActionType::Fold => {
+                // The pot stays as it was, and the player who did not fold wins it
+                child.node_type = NodeType::Terminal;
+                child.pot = node.pot;
+                child.bets = node.bets;
+                child.all_in = node.all_in;
+                None
+            }
+            ActionType::Call if raises == 0 && player == 0 => Some(0),
+            ActionType::Check | ActionType::Call => {
+                child.node_type = NodeType::Showdown;
+                child.player = 255;
+                None
+            }
+            ActionType::Bet | ActionType::Raise => Some(raises + 1),
+        };
+        let child_id = tree.add_node(child);
+        if let Some(next_raises) = next_raises {
+            decisions.push((child_id, next_raises, action, amount));
+        }
+    }
+    for (child_id, next_raises, action, amount) in decisions {
+        let len = history.len();
+        history.push(action as u8);
+        history.extend_from_slice(&amount.to_le_bytes());
+        build_subtree(tree, config, child_id, stack, next_raises, history)?;
+        history.truncate(len);
+    }
+    Ok(())
+}
+
+/// Non-conflicting combo pairs of every two classes, `[a * NUM_CLASSES + b]`.
+fn class_pairs() -> &'static [f32] {
+    static PAIRS: OnceLock<Vec<f32>> = OnceLock::new();
+    PAIRS.get_or_init(|| {
+        let combos: Vec<Vec<u64>> = (0..NUM_CLASSES)
+            .map(|c| class_combos(c).iter().map(|cards| cards[0].bitmask() | cards[1].bitmask()).collect())
+            .collect();
+        let mut pairs = vec![0.0; NUM_CLASSES * NUM_CLASSES];
+        for a in 0..NUM_CLASSES {
+            for b in 0..NUM_CLASSES {
+                pairs[a * NUM_CLASSES + b] = combos[a].iter()
+                    .map(|x| combos[b].iter().filter(|&&y| x & y == 0).count())
+                    .sum::<usize>() as f32;
+            }
+        }
+        pairs
+    })
+}
+
+/// A preflop game and its DCFR solve. Both players' hands are the 169
+/// classes, each weighted by its combos.
+pub struct PreflopSolver {
+    pub config: PreflopConfig,
+    pub tree: GameTree,
+    pub trainer: DCFRTrainer,
+    /// `class_pairs` times the row class's all-in edge, `2 * equity - 1`.
+    edges: Vec<f32>,
+}
+
+impl PreflopSolver {
+    pub fn new(config: PreflopConfig) -> Result<Self, String> {
+        config.validate()?;
+        let tree = build_preflop_tree(&config).map_err(|e| format!("Tree build failed: {}", e))?;
+        let trainer = DCFRTrainer::new(tree.infoset_map.len(), tree.max_actions(), [NUM_CLASSES; 2]);
+        let edges = class_pairs().iter().zip(preflop_table()).map(|(p, e)| p * (2.0 * e - 1.0)).collect();
+        Ok(PreflopSolver { config, tree, trainer, edges })
+    }
+
+    /// Run DCFR iterations.
+    pub fn train(&mut self, iterations: usize) {
+        let reach = vec![1.0; NUM_CLASSES];
+        for _ in 0..iterations {
+            let mut trainer = std::mem::replace(&mut self.trainer, DCFRTrainer::new(0, 0, [0, 0]));
+            trainer.run_iteration(|trainer| {
+                self.cfr(trainer, 0, [&reach, &reach]);
+            });
+            self.trainer = trainer;
+        }
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.trainer.iterations
+    }
+
+    /// Exploitability of the average strategies in big blinds per hand:
+    /// the mean of what each player gains by best-responding instead. The
+    /// game is not symmetric, so each best response is measured against
+    /// the value of the average strategies.
+    pub fn exploitability(&self) -> f32 {
+        let reach = vec![1.0; NUM_CLASSES];
+        let total: f32 = class_pairs().iter().sum();
+        let value = |player: usize, best: bool| -> f32 {
+            self.values(0, player, &reach, best).iter().sum::<f32>() / total
+        };
+        let gains = [0, 1].map(|p| value(p, true) - value(p, false));
+        (gains[0] + gains[1]) / 2.0
+    }
+
+    /// Average strategy of every class at decision node `node_idx`,
+    /// indexed like the preflop grid.
+    pub fn strategy(&self, node_idx: usize) -> Option<Vec<Vec<f32>>> {
+        let node = self.tree.nodes.get(node_idx).filter(|n| n.node_type == NodeType::Action)?;
+        Some((0..NUM_CLASSES)
+            .map(|c| self.trainer.average_strategy(node.infoset_id as usize, c, node.num_actions as usize))
+            .collect())
+    }
+
+    /// Every decision node as a chart: `{ "iterations", "exploitability",
+    /// "nodes": [{ "node", "line", "player", "position", "actions": [{
+    /// "type", "amount", "to" }], "strategy": { "AA": [..], .. } }] }`, where
+    /// `amount` is what the action puts in and `to` the player's total
+    /// contribution after it, in big blinds.
+    pub fn charts(&self) -> serde_json::Value {
+        let nodes: Vec<serde_json::Value> = self.tree.iter_dfs()
+            .filter(|&n| self.tree.nodes[n as usize].node_type == NodeType::Action)
+            .map(|n| {
+                let node = &self.tree.nodes[n as usize];
+                let player = node.player as usize;
+                let actions: Vec<serde_json::Value> = self.tree.children(n)
+                    .map(|c| {
+                        let child = &self.tree.nodes[c as usize];
+                        json!({
+                            "type": child.action_from_parent.map(|a| a.as_str()),
+                            "amount": self.tree.to_amount(child.amount_from_parent),
+                            "to": self.tree.to_amount(node.bets[player] + child.amount_from_parent),
+                        })
+                    })
+                    .collect();
+                let strategy: serde_json::Map<String, serde_json::Value> = self.strategy(n as usize)
+                    .expect("a decision node")
+                    .into_iter()
+                    .enumerate()
+                    .map(|(c, probs)| (class_name(c), json!(probs)))
+                    .collect();
+                json!({
+                    "node": n,
+                    "line": format_line(&self.tree.line_of(n)),
+                    "player": player,
+                    "position": if player == 0 { "SB" } else { "BB" },
+                    "actions": actions,
+                    "strategy": strategy,
+                })
+            })
+            .collect();
+        json!({ "iterations": self.iterations(), "exploitability": self.exploitability(), "nodes": nodes })
+    }
+
+    /// Both players' values at a fold or showdown, per class and summed
+    /// over the opposing classes weighted by `reach` and their combo pairs.
+    fn payoffs(&self, node: &Node, reach: [&[f32]; 2]) -> [Vec<f32>; 2] {
+        let pairs = class_pairs();
+        let against = |matrix: &[f32], opp_reach: &[f32], scale: f32| -> Vec<f32> {
+            matrix.chunks_exact(NUM_CLASSES)
+                .map(|row| scale * row.iter().zip(opp_reach).map(|(m, r)| m * r).sum::<f32>())
+                .collect()
+        };
+        if node.node_type == NodeType::Terminal {
+            // The winner takes what the folder put in
+            let winner = node.player as usize;
+            let lost = self.tree.to_amount(node.bets[1 - winner]);
+            let mut values = [Vec::new(), Vec::new()];
+            values[winner] = against(pairs, reach[1 - winner], lost);
+            values[1 - winner] = against(pairs, reach[winner], -lost);
+            values
+        } else {
+            // Called pots are level: each player stands to win the other's half
+            let half = self.tree.to_amount(node.bets[0]);
+            [against(&self.edges, reach[1], half), against(&self.edges, reach[0], half)]
+        }
+    }
+
+    /// One CFR traversal below `node_idx`; returns both players' values.
+    fn cfr(&self, trainer: &mut DCFRTrainer, node_idx: u32, reach: [&[f32]; 2]) -> [Vec<f32>; 2] {
+        let node = &self.tree.nodes[node_idx as usize];
+        if node.is_terminal() {
+            return self.payoffs(node, reach);
+        }
+        let (player, num_actions, infoset) = (node.player as usize, node.num_actions as usize, node.infoset_id as usize);
+        let strategy = trainer.current_strategy(infoset, NUM_CLASSES, num_actions);
+        let mut values = [vec![0.0; NUM_CLASSES], vec![0.0; NUM_CLASSES]];
+        let mut action_values = Vec::with_capacity(num_actions);
+        for (a, child) in self.tree.children(node_idx).enumerate() {
+            let own: Vec<f32> = reach[player].iter().enumerate().map(|(h, r)| r * strategy[h * num_actions + a]).collect();
+            let mut next = reach;
+            next[player] = &own;
+            let [mut mine, theirs] = {
+                let child_values = self.cfr(trainer, child, next);
+                let [u0, u1] = child_values;
+                if player == 0 { [u0, u1] } else { [u1, u0] }
+            };
+            for (h, v) in values[player].iter_mut().enumerate() {
+                *v += strategy[h * num_actions + a] * mine[h];
+            }
+            for (v, t) in values[1 - player].iter_mut().zip(&theirs) {
+                *v += t;
+            }
+            action_values.push(std::mem::take(&mut mine));
+        }
+        for (a, child_values) in action_values.iter().enumerate() {
+            for h in 0..NUM_CLASSES {
+                trainer.add_regret(infoset, h, a, child_values[h] - values[player][h]);
+            }
+        }
+        for (h, row) in strategy.chunks(num_actions).enumerate() {
+            trainer.add_strategy(infoset, h, row, reach[player][h]);
+        }
+        values
+    }
+
+    /// `player`'s value of every class below `node_idx` against the other
+    /// player's average strategy (reaching the node with `opp_reach`),
+    /// playing a best response if `best`, else the average strategy.
+    fn values(&self, node_idx: u32, player: usize, opp_reach: &[f32], best: bool) -> Vec<f32> {
+        let node = &self.tree.nodes[node_idx as usize];
+        if node.is_terminal() {
+            let own = vec![1.0; NUM_CLASSES];
+            let mut reach: [&[f32]; 2] = [&own, &own];
+            reach[1 - player] = opp_reach;
+            let [u0, u1] = self.payoffs(node, reach);
+            return if player == 0 { u0 } else { u1 };
+        }
+        let (num_actions, infoset) = (node.num_actions as usize, node.infoset_id as usize);
+        let strategies: Vec<Vec<f32>> = (0..NUM_CLASSES).map(|h| self.trainer.average_strategy(infoset, h, num_actions)).collect();
+        let mut values = vec![if node.player as usize == player && best { f32::NEG_INFINITY } else { 0.0 }; NUM_CLASSES];
+        for (a, child) in self.tree.children(node_idx).enumerate() {
+            if node.player as usize == player {
+                let child_values = self.values(child, player, opp_reach, best);
+                for (h, (v, c)) in values.iter_mut().zip(child_values).enumerate() {
+                    *v = if best { v.max(c) } else { *v + strategies[h][a] * c };
+                }
+            } else {
+                let next: Vec<f32> = opp_reach.iter().zip(&strategies).map(|(r, s)| r * s[a]).collect();
+                for (v, c) in values.iter_mut().zip(self.values(child, player, &next, best)) {
+                    *v += c;
+                }
+            }
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::preflop::class_index;
+
+    /// `(type, to)` of every action at `node`.
+    fn actions(tree: &GameTree, node: u32) -> Vec<(ActionType, f32)> {
+        let player = tree.nodes[node as usize].player as usize;
+        tree.children(node)
+            .map(|c| {
+                let child = &tree.nodes[c as usize];
+                (child.action_from_parent.unwrap(), tree.to_amount(tree.nodes[node as usize].bets[player] + child.amount_from_parent))
+            })
+            .collect()
+    }
+
+    fn child(tree: &GameTree, node: u32, action: ActionType) -> u32 {
+        tree.children(node).find(|&c| tree.nodes[c as usize].action_from_parent == Some(action)).unwrap()
+    }
+
+    #[test]
+    fn test_preflop_tree() {
+        let tree = build_preflop_tree(&PreflopConfig::new(100.0)).unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+        use ActionType::*;
+        assert_eq!(actions(&tree, 0), [(Fold, 0.5), (Call, 1.0), (Raise, 2.5), (Raise, 100.0)]);
+        let open = child(&tree, 0, Raise);
+        assert_eq!(actions(&tree, open), [(Fold, 1.0), (Call, 2.5), (Raise, 7.5), (Raise, 100.0)]);
+        let three_bet = child(&tree, open, Raise);
+        assert_eq!(actions(&tree, three_bet), [(Fold, 2.5), (Call, 7.5), (Raise, 16.88), (Raise, 100.0)]);
+        // The fifth bet can only be a shove, and then there is nothing left to raise
+        let four_bet = child(&tree, three_bet, Raise);
+        assert_eq!(actions(&tree, four_bet), [(Fold, 7.5), (Call, 16.88), (Raise, 100.0)]);
+        let jam = child(&tree, four_bet, Raise);
+        assert_eq!(actions(&tree, jam), [(Fold, 16.88), (Call, 100.0)]);
+        assert_eq!(tree.nodes[child(&tree, jam, Call) as usize].pot, 20_000);
+
+        // A limp lets the big blind check or raise to the open sizes
+        let limp = child(&tree, 0, Call);
+        assert_eq!(actions(&tree, limp), [(Check, 1.0), (Raise, 2.5), (Raise, 100.0)]);
+        assert_eq!(tree.nodes[child(&tree, limp, Check) as usize].node_type, NodeType::Showdown);
+        assert_eq!(tree.nodes[child(&tree, 0, Fold) as usize].player, 1);
+
+        let mut config = PreflopConfig::new(100.0);
+        config.allow_limp = false;
+        assert_eq!(actions(&build_preflop_tree(&config).unwrap(), 0).len(), 3);
+        // Opens and 3-bets below a min-raise go up to one
+        config.open_sizes = vec![1.5];
+        config.three_bet_sizes = vec![1.2];
+        let tree = build_preflop_tree(&config).unwrap();
+        assert_eq!(actions(&tree, 0)[1], (Raise, 2.0));
+        assert_eq!(actions(&tree, child(&tree, 0, Raise))[2], (Raise, 3.0));
+        config.open_sizes = vec![0.5];
+        assert!(config.validate().is_err());
+        assert!(PreflopConfig::new(1.0).validate().is_err());
+    }
+
+    #[test]
+    fn test_push_fold_converges() {
+        // Ten big blinds deep: the small blind shoves or folds, the big blind calls or folds
+        let config = PreflopConfig { open_sizes: vec![], allow_limp: false, ..PreflopConfig::new(10.0) };
+        let mut solver = PreflopSolver::new(config).unwrap();
+        assert_eq!(solver.tree.nodes.len(), 5);
+        solver.train(10);
+        let early = solver.exploitability();
+        solver.train(290);
+        let late = solver.exploitability();
+        assert!(late < early && late < 0.001, "{} -> {}", early, late);
+
+        let class = |name: &str| class_index(name).unwrap();
+        let shove = solver.strategy(0).unwrap();
+        let call = solver.strategy(child(&solver.tree, 0, ActionType::Raise) as usize).unwrap();
+        assert!(shove[class("AA")][1] > 0.99 && shove[class("A2o")][1] > 0.99);
+        assert!(shove[class("72o")][0] > 0.9, "{:?}", shove[class("72o")]);
+        assert!(call[class("KK")][1] > 0.99 && call[class("32o")][0] > 0.99);
+
+        let charts = solver.charts();
+        assert_eq!(charts["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(charts["nodes"][0]["position"], "SB");
+        assert_eq!(charts["nodes"][1]["line"], "r9.5");
+        assert_eq!(charts["nodes"][1]["actions"][1]["to"], 10.0);
+        assert_eq!(charts["nodes"][1]["strategy"]["AA"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,178 @@
+//! Strategy profiles: rules that constrain a whole player's strategy, such
+//! as "never bluff" or "always bet at least 75% of the range", compiled to
+//! per-hand action locks (see [`DCFRTrainer::lock_actions`]).
+//!
+//! Rules decide by each combo's showdown equity against the opponent's
+//! starting range on the board of the node (the pooled rivers before the
+//! river is dealt), so they are fixed before training starts.
+//!
+//! [`DCFRTrainer::lock_actions`]: crate::solver::DCFRTrainer::lock_actions
+
+use serde::{Deserialize, Serialize};
+
+use crate::solver::arena::{GameTree, NodeType};
+use crate::solver::types::ActionType;
+
+/// One constraint on a player's strategy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum ProfileRule {
+    /// Combos with less than `max_equity` never bet or raise; they check,
+    /// call or fold.
+    NoBluff {
+        #[serde(default = "default_bluff_equity")]
+        max_equity: f32,
+    },
+    /// Wherever the player can open the betting, the strongest combos
+    /// making up at least `frequency` of the range bet. `size` (a fraction
+    /// of the pot) picks the bet closest to it; without it any size will do.
+    AlwaysBet {
+        frequency: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<f32>,
+    },
+    /// Never bet or raise.
+    CheckOnly,
+}
+
+fn default_bluff_equity() -> f32 {
+    0.5
+}
+
+/// The rules one player is held to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrategyProfile {
+    pub player: usize,
+    pub rules: Vec<ProfileRule>,
+}
+
+/// Hands that one rule restricts at one node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeLock {
+    pub node: usize,
+    /// Index of the rule in the profile.
+    pub rule: usize,
+    /// Actions the hands may take, one bit per action of the node.
+    pub allowed: u32,
+    /// Combo indices in the player's range.
+    pub hands: Vec<usize>,
+}
+
+/// Compile `rules` for `player` into locks. `equities(node)` gives every
+/// combo's equity at a node of the player (NaN for combos that are dead
+/// there) and `weights` the range weights.
+pub fn compile<'a>(
+    tree: &GameTree,
+    player: usize,
+    rules: &[ProfileRule],
+    equities: impl Fn(usize) -> &'a [f32],
+    weights: &[f32],
+) -> Result<Vec<NodeLock>, String> {
+    for rule in rules {
+        if let ProfileRule::AlwaysBet { frequency, .. } = rule
+            && !(0.0..=1.0).contains(frequency)
+        {
+            return Err(format!("always_bet frequency {} is outside [0, 1]", frequency));
+        }
+    }
+
+    let mut locks = Vec::new();
+    for (node_idx, node) in tree.nodes.iter().enumerate() {
+        if node.node_type != NodeType::Action || node.player as usize != player {
+            continue;
+        }
+        let children = tree.children(node_idx as u32).map(|c| c as usize);
+        let action_mask = |keep: &dyn Fn(ActionType, usize) -> bool| {
+            children.clone().enumerate().fold(0u32, |mask, (a, child)| match tree.nodes[child].action_from_parent {
+                Some(action) if keep(action, child) => mask | 1 << a,
+                _ => mask,
+            })
+        };
+        let aggressive = action_mask(&|action, _| action.is_aggressive());
+        if aggressive == 0 {
+            continue;
+        }
+        let passive = action_mask(&|action, _| !action.is_aggressive());
+        let equity = equities(node_idx);
+        let live = || (0..equity.len()).filter(|&h| !equity[h].is_nan());
+
+        for (rule_idx, rule) in rules.iter().enumerate() {
+            let (allowed, hands): (u32, Vec<usize>) = match *rule {
+                ProfileRule::NoBluff { max_equity } => (passive, live().filter(|&h| equity[h] < max_equity).collect()),
+                ProfileRule::CheckOnly => (passive, live().collect()),
+                ProfileRule::AlwaysBet { frequency, size } => {
+                    let bets: Vec<usize> = children.clone()
+                        .filter(|&c| tree.nodes[c].action_from_parent == Some(ActionType::Bet))
+                        .collect();
+                    if bets.is_empty() {
+                        continue;
+                    }
+                    let pot = node.pot as f32;
+                    let chosen = match size {
+                        Some(size) => {
+                            let closest = bets.iter().copied()
+                                .min_by(|&a, &b| {
+                                    let off = |c: usize| (tree.nodes[c].amount_from_parent as f32 / pot - size).abs();
+                                    off(a).total_cmp(&off(b))
+                                })
+                                .expect("at least one bet");
+                            vec![closest]
+                        }
+                        None => bets,
+                    };
+                    let allowed = action_mask(&|_, child| chosen.contains(&child));
+                    (allowed, strongest(equity, weights, frequency))
+                }
+            };
+            if !hands.is_empty() {
+                locks.push(NodeLock { node: node_idx, rule: rule_idx, allowed, hands });
+            }
+        }
+    }
+    Ok(locks)
+}
+
+/// The strongest live combos holding at least `frequency` of the live
+/// weight. Combos tied with the last one taken are included too, so equal
+/// hands are treated alike.
+fn strongest(equity: &[f32], weights: &[f32], frequency: f32) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..equity.len()).filter(|&h| !equity[h].is_nan() && weights[h] > 0.0).collect();
+    order.sort_by(|&a, &b| equity[b].total_cmp(&equity[a]));
+    let quota = frequency * order.iter().map(|&h| weights[h]).sum::<f32>();
+
+    let (mut taken, mut weight) = (0, 0.0);
+    while taken < order.len() && (weight < quota || (taken > 0 && equity[order[taken]] == equity[order[taken - 1]])) {
+        weight += weights[order[taken]];
+        taken += 1;
+    }
+    order.truncate(taken);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strongest_takes_ties_along() {
+        let equity = [0.2, 0.9, f32::NAN, 0.5, 0.5, 0.7];
+        let weights = [1.0; 6];
+        assert_eq!(strongest(&equity, &weights, 0.4), vec![1, 5]);
+        // The quota ends on a 0.5, so the other 0.5 comes along
+        assert_eq!(strongest(&equity, &weights, 0.5), vec![1, 5, 3, 4]);
+        assert_eq!(strongest(&equity, &weights, 0.0), Vec::<usize>::new());
+        assert_eq!(strongest(&equity, &weights, 1.0).len(), 5);
+    }
+
+    #[test]
+    fn test_rules_parse() {
+        let rules: Vec<ProfileRule> = serde_json::from_str(
+            r#"[{"rule": "no_bluff"}, {"rule": "always_bet", "frequency": 0.75, "size": 0.5}, {"rule": "check_only"}]"#,
+        ).unwrap();
+        assert_eq!(rules, vec![
+            ProfileRule::NoBluff { max_equity: 0.5 },
+            ProfileRule::AlwaysBet { frequency: 0.75, size: Some(0.5) },
+            ProfileRule::CheckOnly,
+        ]);
+    }
+}
@@ -1,40 +1,716 @@
-//! Common types for the solver.
-
-use serde::{Deserialize, Serialize};
-
-/// Configuration for building the game tree.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GameConfig {
-    /// Initial pot size at the start of the street.
-    pub initial_pot: f32,
-    /// Player stacks (remaining chips).
-    pub stacks: [f32; 2],
-    /// Available bet sizes (as fraction of pot, e.g., 0.5, 1.0).
-    pub bet_sizes: Vec<f32>,
-    /// Available raise sizes (as fraction of pot).
-    pub raise_sizes: Vec<f32>,
-    /// Maximum number of raises allowed per street (default: 3).
-    /// Set to 0 to disable raises entirely.
-    #[serde(default = "default_raise_limit")]
-    pub raise_limit: u8,
-}
-
-fn default_raise_limit() -> u8 {
-    3 // Default: allow up to 3 raises
-}
-
-/// Type of action taken by a player.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum ActionType {
-    Fold,
-    Check,
-    Call,
-    Bet,
-    Raise,
-}
-
-impl ActionType {
-    pub fn is_aggressive(&self) -> bool {
-        matches!(self, ActionType::Bet | ActionType::Raise)
-    }
-}
+//! Common types for the solver.
+
+use serde::{Deserialize, Serialize};
+
+use crate::poker::{Card, GameVariant};
+use crate::solver::profile::StrategyProfile;
+
+/// Configuration for building the game tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// Initial pot size at the start of the street.
+    pub initial_pot: f32,
+    /// Player stacks (remaining chips). In JSON either `"stacks": [p0, p1]`
+    /// or `"effective_stack": s` for equal stacks; serialized as `stacks`.
+    #[serde(flatten, serialize_with = "stack_fields::serialize", deserialize_with = "stack_fields::deserialize")]
+    pub stacks: [f32; 2],
+    /// Available bet sizes: fractions of the pot (e.g. 0.5, 1.0), or
+    /// geometric sizes such as `"e"` (see [`BetSize`]).
+    pub bet_sizes: Vec<BetSize>,
+    /// Available raise sizes: a plain number is the raise as a fraction of
+    /// the pot after calling, `{"x": 2.7}` raises to 2.7 times the bet faced
+    /// and `"allin"` shoves. A list of lists gives each raise depth its own
+    /// sizes (see [`RaiseSizes`]).
+    pub raise_sizes: RaiseSizes,
+    /// River bet sizes when solving from the turn; `None` reuses `bet_sizes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub river_bet_sizes: Option<Vec<BetSize>>,
+    /// River raise sizes when solving from the turn; `None` reuses `raise_sizes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub river_raise_sizes: Option<RaiseSizes>,
+    /// Each player's own menus, e.g. `[{}, {"bet_sizes": [0.33, 1.0]}]`
+    /// for in-position bets of their own. Sizes a player leaves out are
+    /// the shared ones above (see [`PlayerSizes`]).
+    #[serde(default, skip_serializing_if = "PlayerSizes::all_shared")]
+    pub player_sizes: [PlayerSizes; 2],
+    /// Which river cards a turn tree deals (ignored on the river).
+    #[serde(default, skip_serializing_if = "RiverCards::is_all")]
+    pub river_cards: RiverCards,
+    /// Offer an all-in at every node that can bet or raise, even when no
+    /// size reaches it (default: true). Off, only sizes capped at the stack
+    /// shove, so trees without overbets have no jams either.
+    #[serde(default = "default_add_allin", skip_serializing_if = "is_true")]
+    pub add_allin: bool,
+    /// Maximum number of raises allowed per street (default: 3).
+    /// Set to 0 to disable raises entirely.
+    #[serde(default = "default_raise_limit")]
+    pub raise_limit: u8,
+    /// Integer chips per unit of the amounts above (default: 100, i.e.
+    /// amounts are exact to two decimals). The builder rounds the pot and
+    /// stacks to chips once and does all bet arithmetic in whole chips, so
+    /// trees are bit-identical on every platform and optimization level.
+    #[serde(default = "default_chip_scale")]
+    pub chip_scale: u32,
+    /// Tournament payouts. When set, terminal utilities are ICM equity of
+    /// the resulting stacks instead of chips.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icm: Option<IcmConfig>,
+    /// Progressive knockout bounties. When set, the winner of a pot that
+    /// felts the other player collects the loser's bounty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounty: Option<BountyConfig>,
+    /// Information abstraction. When set, the solver plays hand buckets
+    /// instead of individual combos (see `solver::abstraction`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abstraction: Option<AbstractionConfig>,
+    /// Merge combos that the board's suit symmetry makes identical (e.g.
+    /// `Ac Kc` and `Ad Kd` on a board without clubs or diamonds) and solve
+    /// the smaller game. Queries still answer for every combo. Ignored when
+    /// `abstraction` is set; the buckets already group those combos.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suit_isomorphism: bool,
+    /// Rules that constrain a player's whole strategy, e.g. never bluffing
+    /// (see `solver::profile`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<StrategyProfile>,
+    /// Bet and raise sizes offered at single nodes, replacing the street's
+    /// sizes there (see [`SizeOverride`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub size_overrides: Vec<SizeOverride>,
+    /// Contextual size menus, first match wins, falling back to the
+    /// street's sizes (see [`SizingRule`]). `size_overrides` still apply on
+    /// top.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sizing_rules: Vec<SizingRule>,
+    /// River bet sizes for player 0 leading into the player who made the
+    /// turn's last bet or raise (a donk bet), in turn trees; `[]` never
+    /// leads there. `None` uses the river sizes. Sizing rules that match
+    /// still come first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub donk_sizes: Option<Vec<BetSize>>,
+    /// Deck and hand rankings; short deck games deal only sixes and up.
+    #[serde(default, skip_serializing_if = "GameVariant::is_holdem")]
+    pub variant: GameVariant,
+}
+
+/// The bets and raises offered at one node, e.g. the sizes an automatic
+/// sizing pass kept. The node's other actions are unaffected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeOverride {
+    /// Action history to the node as in `get_strategy_for_history`
+    /// (`"check"`, `"bet 75"`, `"river Kd"`, or compact: `"x-b75"`), with
+    /// exact amounts.
+    pub line: Vec<String>,
+    /// Chips put in by each bet or raise to keep, in config units. Amounts
+    /// the street's sizes (and the all-in) do not produce are ignored.
+    pub sizes: Vec<f32>,
+}
+
+/// One player's bet and raise menus, each replacing the shared one of
+/// [`GameConfig`] for that player. On the river of a turn tree the player's
+/// own river sizes come first, then the shared river sizes, then the
+/// player's own sizes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSizes {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bet_sizes: Option<Vec<BetSize>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raise_sizes: Option<RaiseSizes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub river_bet_sizes: Option<Vec<BetSize>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub river_raise_sizes: Option<RaiseSizes>,
+}
+
+impl PlayerSizes {
+    /// Whether neither player has menus of their own.
+    pub fn all_shared(sizes: &[PlayerSizes; 2]) -> bool {
+        sizes.iter().all(|s| *s == PlayerSizes::default())
+    }
+}
+
+/// A street of the hand, for sizing rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreetName {
+    Turn,
+    River,
+}
+
+/// Sizes for the nodes meeting every condition given, e.g. a delayed bet
+/// after the turn checks through:
+/// `{"street": "river", "after": "x-x|", "bet_sizes": [0.33]}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizingRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub street: Option<StreetName>,
+    /// Player to act.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player: Option<u8>,
+    /// The line to the node, one letter per action (`x` check, `b` bet,
+    /// `c` call, `r` raise) joined by `-`, with `|` where the river is
+    /// dealt: `""` is the root, `"x-x|"` the first river decision after the
+    /// turn checks through. `*` matches any run of characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Bets and raises already made on the node's street: 0 where it can
+    /// bet, 1 facing a bet, 2 facing a raise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raise_depth: Option<u8>,
+    /// Bet sizes at matching nodes that can bet; `[]` leaves them no bet,
+    /// the all-in included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bet_sizes: Option<Vec<BetSize>>,
+    /// Raise sizes at matching nodes facing a bet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raise_sizes: Option<Vec<RaiseSize>>,
+}
+
+impl SizingRule {
+    /// Whether the node described by `street`, `player`, its `line` (as in
+    /// [`SizingRule::after`]) and `raise_depth` meets every condition.
+    pub fn matches(&self, street: StreetName, player: u8, line: &str, raise_depth: u8) -> bool {
+        self.street.is_none_or(|s| s == street)
+            && self.player.is_none_or(|p| p == player)
+            && self.raise_depth.is_none_or(|d| d == raise_depth)
+            && self.after.as_deref().is_none_or(|pattern| glob_match(pattern.as_bytes(), line.as_bytes()))
+    }
+}
+
+/// `pattern` matches all of `text`, `*` standing for any run of bytes.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| glob_match(rest, &text[skip..])),
+        Some((&c, rest)) => text.first() == Some(&c) && glob_match(rest, &text[1..]),
+    }
+}
+
+/// One bet size of the config.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BetSize {
+    /// This fraction of the pot.
+    PotFraction(f32),
+    /// The geometric size: the one fraction of the pot that, bet and called
+    /// this many times in a row, puts the effective stack in. Written
+    /// `"2e"`, `"3e"`, ..., or `"e"` (`None`) for one bet on each street
+    /// left, this one included.
+    #[serde(with = "geometric")]
+    Geometric(Option<u8>),
+}
+
+impl From<f32> for BetSize {
+    fn from(fraction: f32) -> Self {
+        BetSize::PotFraction(fraction)
+    }
+}
+
+impl PartialEq<f32> for BetSize {
+    fn eq(&self, fraction: &f32) -> bool {
+        *self == BetSize::PotFraction(*fraction)
+    }
+}
+
+/// The `"e"`, `"2e"`, ... keywords of the geometric sizes.
+mod geometric {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bets: &Option<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        match bets {
+            None => serializer.serialize_str("e"),
+            Some(bets) => serializer.serialize_str(&format!("{}e", bets)),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        match text.strip_suffix('e') {
+            Some("") => Ok(None),
+            Some(bets) => bets.parse::<u8>().ok().filter(|&b| b > 0).map(Some)
+                .ok_or_else(|| D::Error::custom(format!("unknown size '{}', expected \"e\", \"2e\", ...", text))),
+            None => Err(D::Error::custom(format!("unknown size '{}', expected a number or \"e\"", text))),
+        }
+    }
+}
+
+/// The fraction of `pot` that, bet and called `bets` times, puts `behind`
+/// in: each bet multiplies the pot by `1 + 2f`.
+pub fn geometric_fraction(pot: f64, behind: f64, bets: u8) -> f64 {
+    if pot <= 0.0 {
+        return f64::INFINITY;
+    }
+    ((1.0 + 2.0 * behind / pot).powf(1.0 / bets.max(1) as f64) - 1.0) / 2.0
+}
+
+/// One raise size of the config.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RaiseSize {
+    /// Call, then add this fraction of the pot after the call.
+    PotFraction(f32),
+    /// Raise to `x` times the opponent's total bet.
+    Multiple { x: f32 },
+    /// The whole stack, written `"allin"`. The builder offers an all-in at
+    /// every raise anyway unless `add_allin` is off; listing it alone leaves
+    /// no other size.
+    #[serde(serialize_with = "allin::serialize", deserialize_with = "allin::deserialize")]
+    AllIn,
+    /// The geometric size of [`BetSize::Geometric`], counting this raise
+    /// and the bets after it, with the pot and stacks once the raiser has
+    /// called.
+    #[serde(with = "geometric")]
+    Geometric(Option<u8>),
+}
+
+/// The `"allin"` keyword of [`RaiseSize::AllIn`].
+mod allin {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("allin")
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<(), D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "allin" => Ok(()),
+            other => Err(D::Error::custom(format!("unknown raise size '{}', expected \"allin\"", other))),
+        }
+    }
+}
+
+/// Raise sizes of a street: one menu for every raise (`[0.5, 1.0]`), or
+/// one per raise depth (`[[0.5, 1.0], ["allin"]]`), the first for raising
+/// a bet, the second for re-raising, and so on. Depths past the last menu
+/// reuse it, and an empty menu there allows no raise at all:
+/// `[[2.5], [1.0], []]` stops at the re-raise.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum RaiseSizes {
+    Flat(Vec<RaiseSize>),
+    ByDepth(Vec<Vec<RaiseSize>>),
+}
+
+impl RaiseSizes {
+    /// Sizes of raise number `depth` in a betting round, counting from 0
+    /// for the first raise over a bet.
+    pub fn at_depth(&self, depth: usize) -> &[RaiseSize] {
+        match self {
+            RaiseSizes::Flat(sizes) => sizes,
+            RaiseSizes::ByDepth(menus) => menus.get(depth).or(menus.last()).map_or(&[], Vec::as_slice),
+        }
+    }
+
+    /// Whether raise number `depth` is not allowed: its menu in a
+    /// per-depth list is empty. An empty flat list still leaves the all-in.
+    pub fn forbids(&self, depth: usize) -> bool {
+        matches!(self, RaiseSizes::ByDepth(menus) if menus.get(depth).or(menus.last()).is_some_and(Vec::is_empty))
+    }
+
+    /// Every menu, for edits that apply to all depths.
+    pub fn menus_mut(&mut self) -> std::slice::IterMut<'_, Vec<RaiseSize>> {
+        match self {
+            RaiseSizes::Flat(sizes) => std::slice::from_mut(sizes).iter_mut(),
+            RaiseSizes::ByDepth(menus) => menus.iter_mut(),
+        }
+    }
+}
+
+impl From<Vec<RaiseSize>> for RaiseSizes {
+    fn from(sizes: Vec<RaiseSize>) -> Self {
+        RaiseSizes::Flat(sizes)
+    }
+}
+
+impl<'de> Deserialize<'de> for RaiseSizes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Entry {
+            Size(RaiseSize),
+            Menu(Vec<RaiseSize>),
+        }
+
+        let (mut sizes, mut menus) = (Vec::new(), Vec::new());
+        for entry in Vec::<Entry>::deserialize(deserializer)? {
+            match entry {
+                Entry::Size(size) => sizes.push(size),
+                Entry::Menu(menu) => menus.push(menu),
+            }
+        }
+        match (sizes.is_empty(), menus.is_empty()) {
+            (_, true) => Ok(RaiseSizes::Flat(sizes)),
+            (true, false) => Ok(RaiseSizes::ByDepth(menus)),
+            (false, false) => Err(serde::de::Error::custom("raise sizes must be all sizes or all lists of sizes, not a mix")),
+        }
+    }
+}
+
+/// River cards dealt by a turn tree. Every river gets its own betting
+/// subtree, so this is the main memory control for turn solves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiverCards {
+    /// Every card left in the deck.
+    #[default]
+    All,
+    /// `count` rivers drawn without replacement with a fixed `seed`; each
+    /// stands in for `deck / count` rivers, so values stay unbiased.
+    Sampled { count: usize, seed: u64 },
+}
+
+impl RiverCards {
+    pub fn is_all(&self) -> bool {
+        *self == RiverCards::All
+    }
+}
+
+/// Payout structure for ICM utilities (see `solver::icm`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IcmConfig {
+    /// Prize for each finishing place, first place first.
+    pub payouts: Vec<f32>,
+    /// Stacks of the players not in this hand, in config units.
+    #[serde(default)]
+    pub stacks_behind: Vec<f32>,
+}
+
+impl IcmConfig {
+    /// Most players (the two in the hand and those behind) an ICM config
+    /// may name; `icm_equities` visits every finishing order of the field.
+    pub const MAX_PLAYERS: usize = 10;
+}
+
+fn default_raise_limit() -> u8 {
+    3 // Default: allow up to 3 raises
+}
+
+fn default_add_allin() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn default_chip_scale() -> u32 {
+    100
+}
+
+impl GameConfig {
+    /// Convert an amount in config units to whole chips (round half away from zero).
+    pub fn to_chips(&self, amount: f32) -> i32 {
+        (amount as f64 * self.chip_scale as f64).round() as i32
+    }
+
+    /// Bet sizes of `player` on the tree's first street, or on the river
+    /// of a turn tree when `river`.
+    pub fn bet_sizes_for(&self, player: usize, river: bool) -> &[BetSize] {
+        let own = &self.player_sizes[player];
+        let river_sizes = if river { own.river_bet_sizes.as_ref().or(self.river_bet_sizes.as_ref()) } else { None };
+        river_sizes.or(own.bet_sizes.as_ref()).unwrap_or(&self.bet_sizes)
+    }
+
+    /// Raise sizes of `player`, as [`GameConfig::bet_sizes_for`].
+    pub fn raise_sizes_for(&self, player: usize, river: bool) -> &RaiseSizes {
+        let own = &self.player_sizes[player];
+        let river_sizes = if river { own.river_raise_sizes.as_ref().or(self.river_raise_sizes.as_ref()) } else { None };
+        river_sizes.or(own.raise_sizes.as_ref()).unwrap_or(&self.raise_sizes)
+    }
+
+    /// The config of a river cut from a turn tree: every player's river
+    /// sizes become their sizes, and every river is dealt.
+    pub fn river_config(&self) -> GameConfig {
+        let mut config = self.clone();
+        let (shared_bets, shared_raises) = (config.river_bet_sizes.is_some(), config.river_raise_sizes.is_some());
+        config.bet_sizes = config.river_bet_sizes.take().unwrap_or(config.bet_sizes);
+        config.raise_sizes = config.river_raise_sizes.take().unwrap_or(config.raise_sizes);
+        // A player's own river sizes, else their own sizes unless shared river ones came first
+        for own in &mut config.player_sizes {
+            own.bet_sizes = own.river_bet_sizes.take().or(own.bet_sizes.take().filter(|_| !shared_bets));
+            own.raise_sizes = own.river_raise_sizes.take().or(own.raise_sizes.take().filter(|_| !shared_raises));
+        }
+        config.river_cards = RiverCards::All;
+        config
+    }
+
+    /// Reject a pot, stacks or ICM payouts the builder cannot make a sound
+    /// tree from: non-finite or negative amounts, or a field too large for
+    /// ICM. Stacks may differ; the deeper one
+    /// never bets more than the shorter one can call.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.initial_pot.is_finite() || self.initial_pot < 0.0 {
+            return Err(format!("initial_pot must be a finite non-negative amount, got {}", self.initial_pot));
+        }
+        if let Some(stack) = self.stacks.iter().find(|s| !s.is_finite() || **s < 0.0) {
+            return Err(format!("stacks must be finite non-negative amounts, got {}", stack));
+        }
+        if let Some(icm) = &self.icm {
+            if icm.payouts.is_empty() {
+                return Err("icm.payouts must name the prize for at least first place".to_string());
+            }
+            if let Some(amount) = icm.payouts.iter().chain(&icm.stacks_behind).find(|a| !a.is_finite() || **a < 0.0) {
+                return Err(format!("icm payouts and stacks_behind must be finite non-negative amounts, got {}", amount));
+            }
+            if icm.stacks_behind.len() + 2 > IcmConfig::MAX_PLAYERS {
+                return Err(format!("icm supports at most {} players, got {}", IcmConfig::MAX_PLAYERS, icm.stacks_behind.len() + 2));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `GameConfig::stacks` as one of the mutually exclusive fields `stacks`
+/// and `effective_stack`.
+mod stack_fields {
+    use serde::de::Error;
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    struct Fields {
+        stacks: Option<[f32; 2]>,
+        effective_stack: Option<f32>,
+    }
+
+    pub fn serialize<S: Serializer>(stacks: &[f32; 2], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry("stacks", stacks)?;
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[f32; 2], D::Error> {
+        match Fields::deserialize(deserializer)? {
+            Fields { stacks: Some(stacks), effective_stack: None } => Ok(stacks),
+            Fields { stacks: None, effective_stack: Some(stack) } => Ok([stack, stack]),
+            Fields { stacks: Some(_), effective_stack: Some(_) } => Err(D::Error::custom("`stacks` and `effective_stack` are mutually exclusive; give one")),
+            Fields { stacks: None, effective_stack: None } => Err(D::Error::custom("missing field `stacks` (or `effective_stack`)")),
+        }
+    }
+}
+
+/// Bounties on both players' heads, from player 0's (OOP) point of view.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BountyConfig {
+    /// Bounty on player 0, collected by player 1 for felting them.
+    pub own: f32,
+    /// Bounty on player 1, collected by player 0 for felting them.
+    pub opponent: f32,
+    /// Utility per unit of bounty: chips per bounty dollar in chip EV, or
+    /// the payout-unit value in ICM (e.g. 0.5 when half of a PKO bounty is
+    /// paid out immediately).
+    pub conversion: f32,
+}
+
+/// Hand buckets for information abstraction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbstractionConfig {
+    /// Buckets per player. Combos are grouped by equity against the other
+    /// range; a range with no more combos than this is solved exactly.
+    pub buckets: usize,
+    /// Buckets for spots solved from the turn, instead of `buckets`. The
+    /// bucketing covers the whole tree, river subtrees included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn: Option<usize>,
+    /// Buckets for spots solved from the river, instead of `buckets`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub river: Option<usize>,
+}
+
+impl AbstractionConfig {
+    /// The same bucket count on every street.
+    pub fn new(buckets: usize) -> Self {
+        Self { buckets, turn: None, river: None }
+    }
+
+    /// Buckets for a spot starting on a board of `board_len` cards.
+    pub fn buckets_for(&self, board_len: usize) -> usize {
+        let street = match board_len {
+            4 => self.turn,
+            5 => self.river,
+            _ => None,
+        };
+        street.unwrap_or(self.buckets)
+    }
+}
+
+/// Type of action taken by a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionType {
+    Fold,
+    Check,
+    Call,
+    Bet,
+    Raise,
+}
+
+impl ActionType {
+    pub fn is_aggressive(&self) -> bool {
+        matches!(self, ActionType::Bet | ActionType::Raise)
+    }
+
+    /// Lowercase name used in action strings and JSON ("fold", "bet", ...).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionType::Fold => "fold",
+            ActionType::Check => "check",
+            ActionType::Call => "call",
+            ActionType::Bet => "bet",
+            ActionType::Raise => "raise",
+        }
+    }
+}
+
+/// Parse an action string such as `"bet 75"` or `"Call"`.
+///
+/// Unknown or missing action names fall back to `Check`; the amount is
+/// `None` when absent or not a number.
+pub fn parse_action(action_str: &str) -> (ActionType, Option<f32>) {
+    let mut parts = action_str.split_whitespace();
+
+    let action_type = match parts.next().map(|p| p.to_lowercase()).as_deref() {
+        Some("fold") => ActionType::Fold,
+        Some("call") => ActionType::Call,
+        Some("bet") => ActionType::Bet,
+        Some("raise") => ActionType::Raise,
+        _ => ActionType::Check, // Default fallback
+    };
+    let amount = parts.next().and_then(|p| p.parse::<f32>().ok());
+
+    (action_type, amount)
+}
+
+/// One step of a line through the tree: an action, with the chips it puts
+/// in (config units) for bets and raises, or the river a chance node deals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineStep {
+    /// `None` for a bet or raise takes the first size offered.
+    Action(ActionType, Option<f32>),
+    River(Card),
+}
+
+impl LineStep {
+    /// Parse a history entry: `"river Kd"`, or an action as in
+    /// [`parse_action`]. `None` if a river's card is invalid.
+    pub fn parse(s: &str) -> Option<LineStep> {
+        let mut parts = s.split_whitespace();
+        if parts.next().is_some_and(|p| p.eq_ignore_ascii_case("river")) {
+            return parts.next().and_then(Card::from_str).map(LineStep::River);
+        }
+        let (action, amount) = parse_action(s);
+        Some(LineStep::Action(action, amount))
+    }
+}
+
+/// `"bet 75"`, `"check"` or `"river Kd"`, as [`LineStep::parse`] reads it.
+impl std::fmt::Display for LineStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineStep::Action(action, Some(amount)) if action.is_aggressive() => write!(f, "{} {}", action.as_str(), amount),
+            LineStep::Action(action, _) => f.write_str(action.as_str()),
+            LineStep::River(card) => write!(f, "river {}", card),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(stacks: &str) -> Result<GameConfig, String> {
+        let json = format!(r#"{{"initial_pot": 100, {}"bet_sizes": [1], "raise_sizes": []}}"#, stacks);
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_stack_fields() {
+        let explicit = parse(r#""stacks": [150, 150], "#).unwrap();
+        let effective = parse(r#""effective_stack": 150, "#).unwrap();
+        assert_eq!(effective, explicit);
+        assert_eq!(effective.stacks, [150.0, 150.0]);
+        // Always written back as `stacks`
+        let json = serde_json::to_value(&effective).unwrap();
+        assert_eq!(json["stacks"], serde_json::json!([150.0, 150.0]));
+        assert!(json.get("effective_stack").is_none());
+        assert_eq!(serde_json::from_value::<GameConfig>(json).unwrap(), explicit);
+
+        assert!(parse(r#""stacks": [150, 150], "effective_stack": 150, "#).unwrap_err().contains("mutually exclusive"));
+        assert!(parse("").unwrap_err().contains("missing field `stacks` (or `effective_stack`)"));
+        assert!(parse(r#""stacks": 150, "#).is_err());
+    }
+
+    #[test]
+    fn test_raise_size_shapes() {
+        let sizes = |json: &str| serde_json::from_str::<RaiseSizes>(json).map_err(|e| e.to_string());
+        let flat = sizes(r#"[0.5, {"x": 3}, "allin"]"#).unwrap();
+        assert_eq!(flat, RaiseSizes::Flat(vec![RaiseSize::PotFraction(0.5), RaiseSize::Multiple { x: 3.0 }, RaiseSize::AllIn]));
+        assert_eq!(flat.at_depth(4), flat.at_depth(0));
+        assert_eq!(serde_json::to_string(&flat).unwrap(), r#"[0.5,{"x":3.0},"allin"]"#);
+
+        let by_depth = sizes(r#"[[0.5, 1], ["allin"]]"#).unwrap();
+        assert_eq!(by_depth.at_depth(0), [RaiseSize::PotFraction(0.5), RaiseSize::PotFraction(1.0)]);
+        assert_eq!(by_depth.at_depth(1), [RaiseSize::AllIn]);
+        // Deeper raises reuse the last menu
+        assert_eq!(by_depth.at_depth(5), [RaiseSize::AllIn]);
+        assert_eq!(serde_json::to_string(&by_depth).unwrap(), r#"[[0.5,1.0],["allin"]]"#);
+        assert_eq!(sizes("[]").unwrap(), RaiseSizes::Flat(Vec::new()));
+        assert_eq!(sizes("[[]]").unwrap().at_depth(1), []);
+
+        assert!(sizes(r#"[0.5, ["allin"]]"#).unwrap_err().contains("not a mix"));
+        assert!(sizes(r#"["shove"]"#).is_err());
+    }
+
+    #[test]
+    fn test_street_bucket_counts() {
+        let config: AbstractionConfig = serde_json::from_str(r#"{"buckets": 20, "turn": 50}"#).unwrap();
+        assert_eq!(config.buckets_for(4), 50);
+        assert_eq!(config.buckets_for(5), 20);
+        assert_eq!(serde_json::to_string(&config).unwrap(), r#"{"buckets":20,"turn":50}"#);
+        // Configs without street counts serialize as before
+        assert_eq!(serde_json::to_string(&AbstractionConfig::new(20)).unwrap(), r#"{"buckets":20}"#);
+    }
+
+    #[test]
+    fn test_validate_stacks() {
+        let config = |stacks: [f32; 2]| GameConfig { stacks, ..parse(r#""effective_stack": 100, "#).unwrap() };
+        assert_eq!(config([100.0, 100.0]).validate(), Ok(()));
+        assert_eq!(config([0.0, 0.0]).validate(), Ok(()));
+        assert_eq!(config([0.0, 250.0]).validate(), Ok(()));
+        assert_eq!(config([100.0, 250.0]).validate(), Ok(()));
+        assert!(config([-5.0, -5.0]).validate().unwrap_err().contains("got -5"));
+        assert!(config([f32::NAN, 100.0]).validate().is_err());
+        assert!(config([f32::INFINITY, f32::INFINITY]).validate().is_err());
+        let mut pot = config([100.0, 100.0]);
+        pot.initial_pot = -1.0;
+        assert!(pot.validate().unwrap_err().contains("initial_pot"));
+    }
+
+    #[test]
+    fn test_player_sizes_on_the_river() {
+        let config = parse(r#""effective_stack": 100, "river_bet_sizes": [0.66],
+            "player_sizes": [{"bet_sizes": [0.25]}, {"bet_sizes": [0.33], "river_bet_sizes": [1.5]}], "#).unwrap();
+        assert_eq!(config.bet_sizes_for(0, false), [0.25]);
+        assert_eq!(config.bet_sizes_for(0, true), [0.66]);
+        assert_eq!(config.bet_sizes_for(1, true), [1.5]);
+
+        // Cutting the river out keeps what each player bets there
+        let river = config.river_config();
+        assert_eq!([0, 1].map(|p| river.bet_sizes_for(p, false).to_vec()), [vec![0.66], vec![1.5]]);
+        assert_eq!(river.raise_sizes_for(1, false), &config.raise_sizes);
+    }
+
+    #[test]
+    fn test_validate_icm() {
+        let config = |payouts: Vec<f32>, stacks_behind: Vec<f32>| GameConfig {
+            icm: Some(IcmConfig { payouts, stacks_behind }),
+            ..parse(r#""effective_stack": 100, "#).unwrap()
+        };
+        assert_eq!(config(vec![50.0, 30.0, 20.0], vec![40.0]).validate(), Ok(()));
+        assert!(config(vec![], vec![]).validate().unwrap_err().contains("first place"));
+        assert!(config(vec![50.0, -1.0], vec![]).validate().unwrap_err().contains("got -1"));
+        assert!(config(vec![50.0], vec![f32::NAN]).validate().is_err());
+        assert!(config(vec![50.0], vec![10.0; 9]).validate().unwrap_err().contains("at most 10"));
+    }
+}
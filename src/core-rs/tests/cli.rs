@@ -0,0 +1,115 @@
+//! End-to-end smoke test for the `solver-cli` binary.
+
+use std::process::Command;
+
+#[test]
+fn solves_tiny_spec() {
+    let spec = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tiny_spec.json");
+    let output = Command::new(env!("CARGO_BIN_EXE_solver-cli"))
+        .args(["--quiet", spec])
+        .output()
+        .expect("failed to run solver binary");
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout).expect("stdout is JSON");
+    let summary = &doc["summary"];
+    assert_eq!(summary["iterations"], 200);
+    assert!(summary["nodes"].as_u64().unwrap() > 1);
+    assert!(summary["exploitability"].as_f64().unwrap().is_finite());
+    assert_eq!(summary["gain_pct"].as_array().unwrap().len(), 2);
+    assert_eq!(summary["convergence"].as_array().unwrap().last().unwrap()["iteration"], 200);
+    let perf = &summary["perf"];
+    assert_eq!(perf["iterations"], 200);
+    assert_eq!(perf["nodes_visited"].as_u64().unwrap(), 2 * 200 * summary["nodes"].as_u64().unwrap());
+
+    let root = &doc["solution"]["nodes"][0];
+    assert_eq!(root["node"], 0);
+    assert_eq!(root["strategies"].as_object().unwrap().len(), 4);
+}
+
+#[test]
+fn runs_aggregate_report() {
+    let spec = std::env::temp_dir().join(format!("cli-aggregate-{}.json", std::process::id()));
+    std::fs::write(&spec, r#"{
+        "config": {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": []},
+        "range0": "As Ad, 6h 5h", "range1": "Kc Kd, 9s 8s",
+        "boards": ["Ks Th 7s 4d 2c", "Kh Tc 7s 7d 2c"], "iterations": 20
+    }"#).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_solver-cli"))
+        .args(["--aggregate", spec.to_str().unwrap()])
+        .output()
+        .expect("failed to run solver binary");
+    std::fs::remove_file(&spec).ok();
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("board 2/2: Kh Tc 7s 7d 2c"));
+
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout).expect("stdout is JSON");
+    assert_eq!(doc["boards"].as_array().unwrap().len(), 2);
+    assert_eq!(doc["groups"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn runs_batch() {
+    let spec = std::env::temp_dir().join(format!("cli-batch-{}.json", std::process::id()));
+    let out = std::env::temp_dir().join(format!("cli-batch-{}.out.json", std::process::id()));
+    std::fs::write(&spec, r#"{
+        "config": {"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": []},
+        "range0": "As Ad, 6h 5h", "range1": "Kc Kd, 9s 8s",
+        "boards": ["Ks Th 7s 4d 2c", "Kh Tc 7s 7d 2c"], "iterations": 20
+    }"#).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_solver-cli"))
+        .args(["--batch", "--output", out.to_str().unwrap(), spec.to_str().unwrap()])
+        .output()
+        .expect("failed to run solver binary");
+    std::fs::remove_file(&spec).ok();
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("board 2/2: Kh Tc 7s 7d 2c"));
+
+    let doc: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).expect("output is JSON");
+    std::fs::remove_file(&out).ok();
+    let results = doc["results"].as_object().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results["Ks Th 7s 4d 2c"]["iterations"], 20);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_solver-cli"))
+        .args(["--batch", "--aggregate"])
+        .output()
+        .expect("failed to run solver binary");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn rejects_unknown_option() {
+    let output = Command::new(env!("CARGO_BIN_EXE_solver-cli"))
+        .arg("--bogus")
+        .output()
+        .expect("failed to run solver binary");
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn saved_solution_loads_natively() {
+    let spec = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tiny_spec.json");
+    let path = std::env::temp_dir().join(format!("cli-solution-{}.psol", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_solver-cli"))
+        .args(["--quiet", "--save-solution", path.to_str().unwrap(), spec])
+        .output()
+        .expect("failed to run solver binary");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let session = poker_solver_core::SolverSession::load_solution(&path).expect("solution loads");
+    std::fs::remove_file(&path).ok();
+
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout).expect("stdout is JSON");
+    assert_eq!(session.iterations(), 200);
+    let loaded = session.node_strategy_table(0).unwrap();
+    let printed = &doc["solution"]["nodes"][0];
+    for (hand, probs) in printed["strategies"].as_object().unwrap() {
+        let restored = loaded["strategies"][hand].as_array().unwrap();
+        for (p, q) in probs.as_array().unwrap().iter().zip(restored) {
+            assert!((p.as_f64().unwrap() - q.as_f64().unwrap()).abs() < 1e-6, "{}", hand);
+        }
+    }
+}
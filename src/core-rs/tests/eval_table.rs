@@ -0,0 +1,31 @@
+//! Differential test of the two-plus-two evaluation table against the
+//! lookup evaluator. Builds the table (a few seconds in release, about a
+//! minute in debug), so it only runs with `--features eval-table`.
+
+#![cfg(feature = "eval-table")]
+
+use poker_solver_core::poker::eval_table::{generate_eval_table, load_eval_table, loaded, table_bytes, TABLE_LEN};
+use poker_solver_core::poker::evaluator::evaluate_7_cards_direct;
+use poker_solver_core::{evaluate_7_cards, Card};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+#[test]
+fn table_walk_matches_the_evaluator() {
+    let table = generate_eval_table();
+    assert_eq!(table.len(), TABLE_LEN);
+    let bytes = table_bytes(&table);
+    assert!(load_eval_table(&bytes[..bytes.len() / 2]).is_err());
+    load_eval_table(&bytes).unwrap();
+    assert!(loaded().is_some());
+    assert!(load_eval_table(&bytes).unwrap_err().contains("already loaded"));
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut deck: Vec<Card> = (0..52).map(Card::from_index).collect();
+    for i in 0..300_000 {
+        deck.shuffle(&mut rng);
+        let hand = &deck[..5 + i % 3];
+        assert_eq!(evaluate_7_cards(hand), evaluate_7_cards_direct(hand), "{:?}", hand);
+    }
+}
@@ -0,0 +1,124 @@
+//! Slow, obviously-correct hand evaluator used as an oracle by the fuzz
+//! targets and the property tests.
+//!
+//! Hands are classified by plain rank counting and compared as
+//! `(category, tiebreak ranks)` tuples, so no lookup tables are involved.
+//! Include with `#[path = "..."] mod reference_evaluator;`.
+
+#![allow(dead_code)]
+
+use poker_solver_core::Card;
+
+/// Hand categories, weakest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+/// Comparable hand value: higher is better (the opposite of evaluator scores).
+pub type HandValue = (Category, Vec<u8>);
+
+/// Value of exactly five cards.
+pub fn eval_five(cards: &[Card]) -> HandValue {
+    assert_eq!(cards.len(), 5);
+
+    let mut counts = [0u8; 13];
+    for c in cards {
+        counts[c.rank() as usize] += 1;
+    }
+    let flush = cards.iter().all(|c| c.suit() == cards[0].suit());
+
+    // Ranks grouped by multiplicity, then by rank, both descending.
+    let mut groups: Vec<(u8, u8)> = (0..13u8)
+        .filter(|&r| counts[r as usize] > 0)
+        .map(|r| (counts[r as usize], r))
+        .collect();
+    groups.sort_unstable_by(|a, b| b.cmp(a));
+    let ranks: Vec<u8> = groups.iter().map(|&(_, r)| r).collect();
+
+    let straight_high = if groups.len() == 5 {
+        if ranks[0] - ranks[4] == 4 {
+            Some(ranks[0])
+        } else if ranks == [12, 3, 2, 1, 0] {
+            Some(3) // wheel: A-2-3-4-5 plays as five-high
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let shape: Vec<u8> = groups.iter().map(|&(n, _)| n).collect();
+    let category = match (straight_high, flush, shape.as_slice()) {
+        (Some(_), true, _) => Category::StraightFlush,
+        (_, _, [4, 1]) => Category::FourOfAKind,
+        (_, _, [3, 2]) => Category::FullHouse,
+        (None, true, _) => Category::Flush,
+        (Some(_), false, _) => Category::Straight,
+        (_, _, [3, 1, 1]) => Category::ThreeOfAKind,
+        (_, _, [2, 2, 1]) => Category::TwoPair,
+        (_, _, [2, 1, 1, 1]) => Category::OnePair,
+        _ => Category::HighCard,
+    };
+
+    match straight_high {
+        Some(high) if matches!(category, Category::Straight | Category::StraightFlush) => (category, vec![high]),
+        _ => (category, ranks),
+    }
+}
+
+/// Best five-card value among 5 to 7 cards (brute force over all subsets).
+pub fn eval_best(cards: &[Card]) -> HandValue {
+    assert!((5..=7).contains(&cards.len()));
+    let n = cards.len();
+    let mut best: Option<HandValue> = None;
+    for mask in 0u32..(1 << n) {
+        if mask.count_ones() != 5 {
+            continue;
+        }
+        let five: Vec<Card> = (0..n).filter(|i| mask & (1 << i) != 0).map(|i| cards[i]).collect();
+        let value = eval_five(&five);
+        if best.as_ref().is_none_or(|b| value > *b) {
+            best = Some(value);
+        }
+    }
+    best.unwrap()
+}
+
+/// Category of an evaluator score (1 = royal flush ... 7462 = worst high card).
+pub fn category_of_score(score: u16) -> Category {
+    match score {
+        1..=10 => Category::StraightFlush,
+        11..=166 => Category::FourOfAKind,
+        167..=322 => Category::FullHouse,
+        323..=1599 => Category::Flush,
+        1600..=1609 => Category::Straight,
+        1610..=2467 => Category::ThreeOfAKind,
+        2468..=3325 => Category::TwoPair,
+        3326..=6185 => Category::OnePair,
+        _ => Category::HighCard,
+    }
+}
+
+/// Draw `n` distinct cards, taking each pick from `bytes` (modulo the cards left).
+/// Returns `None` when `bytes` is too short.
+pub fn distinct_cards(bytes: &[u8], n: usize) -> Option<Vec<Card>> {
+    if bytes.len() < n || n > 52 {
+        return None;
+    }
+    let mut deck: Vec<u8> = (0..52).collect();
+    Some(
+        bytes[..n]
+            .iter()
+            .map(|&b| Card::from_index(deck.swap_remove(b as usize % deck.len())))
+            .collect(),
+    )
+}
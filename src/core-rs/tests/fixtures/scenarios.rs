@@ -0,0 +1,138 @@
+//! Pinned solver scenarios shared by the benchmark suite and the golden tests.
+//!
+//! Every scenario is generated from a fixed seed so benchmark inputs and
+//! golden expectations stay identical across runs and machines.
+//! Include with `#[path = "..."] mod scenarios;`.
+
+#![allow(dead_code)]
+
+use poker_solver_core::{Card, GameVariant};
+use poker_solver_core::solver::{BetSize, GameConfig, RaiseSize, RiverCards};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// Seed used by every generator in this module.
+pub const SEED: u64 = 0x5EED_CAFE;
+
+/// River board used by the equity and CFR scenarios.
+pub const BOARD: &str = "Ks Th 7s 4d 2c";
+
+pub fn board() -> Vec<Card> {
+    BOARD.split_whitespace().filter_map(Card::from_str).collect()
+}
+
+/// `count` random 7-card hands (distinct cards within each hand).
+pub fn random_seven_card_hands(count: usize) -> Vec<[Card; 7]> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut deck: Vec<Card> = (0..52).map(Card::from_index).collect();
+    (0..count)
+        .map(|_| {
+            let (hand, _) = deck.partial_shuffle(&mut rng, 7);
+            [hand[0], hand[1], hand[2], hand[3], hand[4], hand[5], hand[6]]
+        })
+        .collect()
+}
+
+/// `count` distinct hole-card combos that do not conflict with [`BOARD`].
+pub fn random_range(count: usize, seed_offset: u64) -> Vec<Vec<Card>> {
+    let board = board();
+    let mut combos: Vec<Vec<Card>> = Vec::new();
+    for a in 0..52u8 {
+        for b in (a + 1)..52u8 {
+            let hand = vec![Card::from_index(a), Card::from_index(b)];
+            if !hand.iter().any(|c| board.contains(c)) {
+                combos.push(hand);
+            }
+        }
+    }
+
+    let mut rng = StdRng::seed_from_u64(SEED + seed_offset);
+    combos.shuffle(&mut rng);
+    combos.truncate(count);
+    combos
+}
+
+/// Format a range in the comma-separated form accepted by `SolverSession`.
+pub fn range_string(range: &[Vec<Card>]) -> String {
+    range
+        .iter()
+        .map(|h| format!("{} {}", h[0], h[1]))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `GameTree::structure_hash` of the `three_size_config` tree. Amounts are
+/// integer chips, so native and WASM builds must both produce exactly this.
+pub const THREE_SIZE_TREE_HASH: u64 = 0x709b_e31c_2c85_f0e4;
+
+/// Three bet sizes, three raise sizes, three raises per street.
+pub fn three_size_config() -> GameConfig {
+    GameConfig {
+        initial_pot: 100.0,
+        stacks: [400.0, 400.0],
+        bet_sizes: [0.33, 0.75, 1.5].map(BetSize::from).to_vec(),
+        raise_sizes: vec![RaiseSize::PotFraction(0.5), RaiseSize::PotFraction(1.0), RaiseSize::PotFraction(2.0)].into(),
+        river_bet_sizes: None,
+        river_raise_sizes: None,
+        river_cards: RiverCards::All,
+        raise_limit: 3,
+        chip_scale: 100,
+        icm: None,
+        bounty: None,
+        abstraction: None,
+        suit_isomorphism: false,
+        profiles: Vec::new(),
+        size_overrides: Vec::new(),
+        sizing_rules: Vec::new(),
+        donk_sizes: None,
+        player_sizes: Default::default(),
+        add_allin: true,
+        variant: GameVariant::Holdem,
+    }
+}
+
+/// Mid-size CFR fixture: two bet sizes, one raise size, 60 combos per player.
+pub fn mid_size_config() -> GameConfig {
+    GameConfig {
+        initial_pot: 100.0,
+        stacks: [200.0, 200.0],
+        bet_sizes: [0.5, 1.0].map(BetSize::from).to_vec(),
+        raise_sizes: vec![RaiseSize::PotFraction(1.0)].into(),
+        river_bet_sizes: None,
+        river_raise_sizes: None,
+        river_cards: RiverCards::All,
+        raise_limit: 2,
+        chip_scale: 100,
+        icm: None,
+        bounty: None,
+        abstraction: None,
+        suit_isomorphism: false,
+        profiles: Vec::new(),
+        size_overrides: Vec::new(),
+        sizing_rules: Vec::new(),
+        donk_sizes: None,
+        player_sizes: Default::default(),
+        add_allin: true,
+        variant: GameVariant::Holdem,
+    }
+}
+
+pub fn mid_size_ranges() -> (Vec<Vec<Card>>, Vec<Vec<Card>>) {
+    (random_range(60, 1), random_range(60, 2))
+}
+
+/// Iterations the mid-size solve runs before its values are compared.
+pub const MID_SIZE_ITERATIONS: usize = 100;
+
+/// Both players' root EVs (average strategies, chips per matchup) of the
+/// mid-size solve after `MID_SIZE_ITERATIONS`.
+pub const MID_SIZE_ROOT_EVS: [f32; 2] = [-1.607_809, 1.607_82];
+
+/// Exploitability of the mid-size solve after `MID_SIZE_ITERATIONS`, in chips.
+pub const MID_SIZE_EXPLOITABILITY: f32 = 0.150_207;
+
+/// Tolerance on the mid-size values, in chips: room for rounding that
+/// differs between platforms and SIMD paths, far below what a change to
+/// the solver moves them by.
+pub const MID_SIZE_TOLERANCE: f32 = 1e-3;
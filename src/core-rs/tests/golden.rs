@@ -0,0 +1,67 @@
+//! Golden checks on the pinned scenarios shared with the benchmark suite.
+
+use poker_solver_core::{compute_equity_matrix, evaluate_7_cards, SolverSession};
+use poker_solver_core::solver::build_river_tree;
+
+#[path = "fixtures/scenarios.rs"]
+mod scenarios;
+
+#[test]
+fn scenarios_are_deterministic() {
+    assert_eq!(scenarios::random_seven_card_hands(100), scenarios::random_seven_card_hands(100));
+    assert_eq!(scenarios::mid_size_ranges(), scenarios::mid_size_ranges());
+}
+
+#[test]
+fn random_hands_evaluate_in_range() {
+    for hand in scenarios::random_seven_card_hands(1_000) {
+        let score = evaluate_7_cards(&hand);
+        assert!((1..=7462).contains(&score), "score {} out of range", score);
+    }
+}
+
+#[test]
+fn three_size_tree_is_stable() {
+    let a = build_river_tree(&scenarios::three_size_config()).unwrap();
+    let b = build_river_tree(&scenarios::three_size_config()).unwrap();
+    assert_eq!(a.nodes.len(), b.nodes.len());
+    assert_eq!(a.infoset_map.len(), b.infoset_map.len());
+}
+
+#[test]
+fn three_size_tree_hash_is_pinned() {
+    let tree = build_river_tree(&scenarios::three_size_config()).unwrap();
+    assert_eq!(tree.structure_hash(), scenarios::THREE_SIZE_TREE_HASH, "tree layout changed: {:016x}", tree.structure_hash());
+}
+
+#[test]
+fn mid_size_solve_matches_pinned_values() {
+    let (range0, range1) = scenarios::mid_size_ranges();
+    let matrix = compute_equity_matrix(&scenarios::board(), &range0, &range1);
+    assert_eq!(matrix.len(), range0.len() * range1.len());
+
+    let config = scenarios::mid_size_config();
+    let mut session = SolverSession::from_config(
+        config,
+        scenarios::BOARD,
+        &scenarios::range_string(&range0),
+        &scenarios::range_string(&range1),
+    )
+    .expect("session");
+
+    let initial = session.exploitability();
+    session.set_invariant_checks(true);
+    session.step(scenarios::MID_SIZE_ITERATIONS);
+    let exploitability = session.exploitability();
+    assert!(exploitability < initial);
+    assert!(
+        (exploitability - scenarios::MID_SIZE_EXPLOITABILITY).abs() < scenarios::MID_SIZE_TOLERANCE,
+        "exploitability {} (pinned {})", exploitability, scenarios::MID_SIZE_EXPLOITABILITY,
+    );
+    let evs = session.player_exploitability().average;
+    for (ev, pinned) in evs.into_iter().zip(scenarios::MID_SIZE_ROOT_EVS) {
+        assert!((ev - pinned).abs() < scenarios::MID_SIZE_TOLERANCE, "root EVs {:?} (pinned {:?})", evs, scenarios::MID_SIZE_ROOT_EVS);
+    }
+    let report = session.invariant_report().expect("checks are on");
+    assert_eq!(report.total, 0, "invariants broken: {:?}", report.violations);
+}
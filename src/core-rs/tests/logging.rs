@@ -0,0 +1,73 @@
+//! Diagnostics go through the `log` facade: nothing is emitted at the default
+//! level during a normal solve, and the trace-level dumps are still there
+//! when asked for.
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use poker_solver_core::platform::DEFAULT_LOG_LEVEL;
+use poker_solver_core::solver::{BetSize, GameConfig, RiverCards};
+use poker_solver_core::{GameVariant, SolverSession};
+
+struct Capture(Mutex<Vec<(Level, String)>>);
+
+impl Log for Capture {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+fn solve() {
+    let config = GameConfig {
+        initial_pot: 100.0,
+        stacks: [100.0, 100.0],
+        bet_sizes: vec![BetSize::PotFraction(1.0)],
+        raise_sizes: vec![].into(),
+        river_bet_sizes: None,
+        river_raise_sizes: None,
+        river_cards: RiverCards::All,
+        raise_limit: 0,
+        chip_scale: 100,
+        icm: None,
+        bounty: None,
+        abstraction: None,
+        suit_isomorphism: false,
+        profiles: Vec::new(),
+        size_overrides: Vec::new(),
+        sizing_rules: Vec::new(),
+        donk_sizes: None,
+        player_sizes: Default::default(),
+        add_allin: true,
+        variant: GameVariant::Holdem,
+    };
+    let mut session = SolverSession::from_config(config, "Ks Th 7s 4d 2c", "As Ad, 6h 5h", "Kc Kd, 9s 8s").unwrap();
+    session.step(10);
+    session.get_strategy_ptr();
+    session.hand_strategy::<&str>("As Ad", None).unwrap();
+}
+
+// A single test: the logger is process-global.
+#[test]
+fn default_level_is_quiet_and_trace_is_available() {
+    log::set_logger(&CAPTURE).unwrap();
+
+    log::set_max_level(DEFAULT_LOG_LEVEL);
+    solve();
+    assert!(CAPTURE.0.lock().unwrap().is_empty(), "unexpected output: {:?}", CAPTURE.0.lock().unwrap());
+
+    log::set_max_level(LevelFilter::Trace);
+    solve();
+    let records = CAPTURE.0.lock().unwrap();
+    assert!(records.iter().any(|(level, msg)| *level == Level::Trace && msg.contains("DCFRTrainer::train")));
+    assert!(records.iter().any(|(level, _)| *level == Level::Info));
+}
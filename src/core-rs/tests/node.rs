@@ -0,0 +1,125 @@
+//! WASM smoke tests that run under Node.js, covering the platform shims
+//! (console logging without a browser `window`, `performance.now` timing)
+//! and the cross-platform tree hash.
+//!
+//! Run with `cargo test --target wasm32-unknown-unknown --test node`
+//! (or `wasm-pack test --node`).
+
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use poker_solver_core::solver::build_river_tree;
+use poker_solver_core::{init, init_with_hooks, solve, platform, set_log_level, thread_count, threads_supported, SolverSession};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+#[path = "fixtures/scenarios.rs"]
+mod scenarios;
+
+const CONFIG: &str = r#"{"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": [], "raise_limit": 0}"#;
+
+fn session() -> SolverSession {
+    SolverSession::new(CONFIG, "Ks Th 7s 4d 2c", "As Ad, 6h 5h", "Kc Kd, 9s 8s").unwrap()
+}
+
+#[wasm_bindgen_test]
+fn init_logs_without_a_browser() {
+    init().unwrap();
+    set_log_level("trace").unwrap();
+    log::info!("logging from node");
+    session().step(1);
+    set_log_level("warn").unwrap();
+}
+
+#[wasm_bindgen_test]
+fn clock_advances() {
+    let start = platform::now_ms();
+    let mut session = session();
+    session.step(50);
+    assert!(platform::now_ms() >= start);
+}
+
+#[wasm_bindgen_test]
+fn step_for_ms_respects_budget() {
+    let mut session = session();
+    let start = platform::now_ms();
+    let done = session.step_for_ms(10.0);
+    assert!(done >= 1);
+    assert!(platform::now_ms() - start >= 10.0);
+}
+
+#[wasm_bindgen_test]
+fn injected_hooks_are_used() {
+    let captured = js_sys::Array::new();
+    let sink = captured.clone();
+    let log = Closure::<dyn Fn(String, String)>::new(move |msg: String, level: String| {
+        sink.push(&JsValue::from_str(&format!("{} {}", level, msg)));
+    });
+    let now = Closure::<dyn Fn() -> f64>::new(|| 42.0);
+
+    init_with_hooks(
+        Some(log.as_ref().unchecked_ref::<js_sys::Function>().clone()),
+        Some(now.as_ref().unchecked_ref::<js_sys::Function>().clone()),
+    )
+    .unwrap();
+
+    set_log_level("info").unwrap();
+    log::info!("through the hook");
+    log::debug!("filtered out");
+    set_log_level("warn").unwrap();
+    assert_eq!(captured.length(), 1);
+    assert!(captured.get(0).as_string().unwrap().starts_with("INFO "));
+    assert_eq!(platform::now_ms(), 42.0);
+
+    init_with_hooks(None, None).unwrap();
+    assert_ne!(platform::now_ms(), 42.0);
+}
+
+#[wasm_bindgen_test]
+fn default_build_is_single_threaded() {
+    assert!(!threads_supported());
+    assert_eq!(thread_count(), 1);
+    let matrix = poker_solver_core::compute_equity_matrix(
+        &"Ks Th 7s 4d 2c".split(' ').filter_map(poker_solver_core::Card::from_str).collect::<Vec<_>>(),
+        &poker_solver_core::parse_range("As Ad, 6h 5h"),
+        &poker_solver_core::parse_range("Kc Kd, 9s 8s"),
+    );
+    assert_eq!(matrix, vec![0.0, 1.0, 0.0, 0.0]);
+}
+
+#[wasm_bindgen_test]
+fn tree_hash_matches_native() {
+    let tree = build_river_tree(&scenarios::three_size_config()).unwrap();
+    assert_eq!(tree.structure_hash(), scenarios::THREE_SIZE_TREE_HASH);
+}
+
+#[wasm_bindgen_test]
+fn one_shot_solve() {
+    let spec = format!(r#"{{"config": {}, "board": "Ks Th 7s 4d 2c", "range0": "As Ad", "range1": "Kc Kd", "iterations": 20}}"#, CONFIG);
+    let result: serde_json::Value = serde_json::from_str(&solve(&spec)).unwrap();
+    assert_eq!(result["ok"], true);
+    assert_eq!(result["iterations"], 20);
+}
+
+#[wasm_bindgen_test]
+fn turn_history_crosses_the_river() {
+    let config = r#"{"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1.0], "raise_sizes": [], "raise_limit": 0,
+                     "river_cards": {"sampled": {"count": 4, "seed": 1}}}"#;
+    let mut session = SolverSession::new(config, "Ks Th 7s 4d", "As Ad, 6h 5h", "Kc Kd, 9s 8s").unwrap();
+    session.step(10);
+
+    let chance: serde_json::Value = serde_json::from_str(&session.get_strategy_for_history(
+        serde_wasm_bindgen::to_value(&["check", "check"]).unwrap(),
+    ).unwrap()).unwrap();
+    let river = chance["rivers"][0].as_str().unwrap().to_string();
+    let history = ["check".to_string(), "check".to_string(), format!("river {}", river)];
+    let info: serde_json::Value = serde_json::from_str(&session.get_strategy_for_history(
+        serde_wasm_bindgen::to_value(&history).unwrap(),
+    ).unwrap()).unwrap();
+    assert_eq!(info["river"], river);
+    assert_eq!(info["player"], 0);
+
+    let report: serde_json::Value = serde_json::from_str(&session.get_river_strategies(
+        serde_wasm_bindgen::to_value(&["check", "check"]).unwrap(),
+    ).unwrap()).unwrap();
+    assert_eq!(report["rivers"].as_array().unwrap().len(), 4);
+}
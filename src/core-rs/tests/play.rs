@@ -0,0 +1,53 @@
+//! A scripted hand against the solver through `PlaySession`.
+
+use poker_solver_core::play::PlaySession;
+use poker_solver_core::solver::GameConfig;
+use poker_solver_core::SolverSession;
+
+/// Queens against a set of kings or a six-high bluff: the queens check and
+/// catch some bluffs, so the kings should bet when checked to.
+fn solved_spot() -> SolverSession {
+    let config: GameConfig = serde_json::from_str(
+        r#"{"initial_pot": 100, "stacks": [100, 100], "bet_sizes": [1], "raise_sizes": [], "raise_limit": 0}"#,
+    ).unwrap();
+    let mut session = SolverSession::from_config(config, "Ks Th 7s 4d 2c", "Qc Qd", "Kc Kd, 6h 5h").unwrap();
+    session.step(2000);
+    session
+}
+
+#[test]
+fn checking_behind_with_the_nuts_costs_ev() {
+    let mut play = PlaySession::start(solved_spot(), 1, 7).unwrap();
+    assert_eq!(play.hero_hand(), "Kc Kd");
+    let state = play.status();
+    assert_eq!(state["to_act"], "hero");
+    assert_eq!(state["actions"], serde_json::json!(["check", "bet 100"]));
+    assert_eq!(state["log"][0]["actor"], "villain");
+    assert_eq!(state["log"][0]["action"], "check");
+
+    assert!(play.play("bet 60").unwrap_err().contains("not an option"));
+    play.play("check").unwrap();
+    assert!(play.finished());
+    let result = play.result().unwrap();
+    assert_eq!(result.winner, "hero");
+    assert!(!result.fold);
+    assert_eq!(result.villain_hand, "Qc Qd");
+    assert!((result.hero_value - 50.0).abs() < 1e-3);
+    assert!(play.play("check").unwrap_err().contains("over"));
+
+    // Betting was worth more than checking
+    let step = &play.log()[1];
+    assert_eq!(step.actor, "hero");
+    assert!((step.ev.unwrap() - 50.0).abs() < 0.5, "{:?}", step);
+    assert!((result.ev_loss - 50.0).abs() < 1.0, "{:?}", result);
+    assert_eq!(result.message, format!("You lost {:.2} by checking", result.ev_loss));
+
+    // The same seed deals the same hand; a best-play line loses nothing.
+    // The queens are indifferent against the shove, so they may call
+    let mut replay = PlaySession::start(solved_spot(), 1, 7).unwrap();
+    replay.play("bet 100").unwrap();
+    let result = replay.result().unwrap();
+    assert_eq!(result.winner, "hero");
+    assert!((result.hero_value - if result.fold { 50.0 } else { 150.0 }).abs() < 1e-3, "{:?}", result);
+    assert!(result.ev_loss.abs() < 1.0, "{:?}", result);
+}
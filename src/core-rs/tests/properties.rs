@@ -0,0 +1,111 @@
+//! Property tests for the hand evaluator and the equity matrix.
+//!
+//! The table-driven evaluator is checked against the brute-force reference
+//! in tests/fixtures/reference_evaluator.rs (shared with the fuzz targets).
+//! Scores are "lower is better" while reference values are "higher is
+//! better", so orderings are compared reversed.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use proptest::prelude::*;
+use proptest::sample::subsequence;
+
+use poker_solver_core::{compute_equity_matrix, evaluate_5_cards, evaluate_7_cards, Card};
+
+#[path = "fixtures/reference_evaluator.rs"]
+mod reference_evaluator;
+
+use reference_evaluator::{category_of_score, eval_best};
+
+/// `n` distinct cards in random order.
+fn cards(n: usize) -> impl Strategy<Value = Vec<Card>> {
+    subsequence((0u8..52).collect::<Vec<_>>(), n)
+        .prop_shuffle()
+        .prop_map(|idx| idx.into_iter().map(Card::from_index).collect())
+}
+
+/// Two disjoint hands of `n` cards each.
+fn two_hands(n: usize) -> impl Strategy<Value = (Vec<Card>, Vec<Card>)> {
+    cards(2 * n).prop_map(move |mut all| {
+        let b = all.split_off(n);
+        (all, b)
+    })
+}
+
+/// Any 5, 6 or 7 distinct cards.
+fn hand() -> impl Strategy<Value = Vec<Card>> {
+    (5usize..=7).prop_flat_map(cards)
+}
+
+/// River board plus two ranges. Hands are drawn from the whole deck, so
+/// board-blocked and overlapping combos (the NaN cells) show up too.
+fn spot() -> impl Strategy<Value = (Vec<Card>, Vec<Vec<Card>>, Vec<Vec<Card>>)> {
+    (
+        cards(5),
+        prop::collection::vec(cards(2), 1..16),
+        prop::collection::vec(cards(2), 1..16),
+    )
+}
+
+proptest! {
+    #[test]
+    fn ordering_matches_reference((a, b) in (5usize..=7).prop_flat_map(two_hands)) {
+        let ours = evaluate_7_cards(&a).cmp(&evaluate_7_cards(&b));
+        let reference = eval_best(&b).cmp(&eval_best(&a));
+        prop_assert_eq!(ours, reference, "{:?} vs {:?}", a, b);
+    }
+
+    #[test]
+    fn category_matches_reference(cards in hand()) {
+        let score = evaluate_7_cards(&cards);
+        prop_assert!((1..=7462).contains(&score));
+        prop_assert_eq!(category_of_score(score), eval_best(&cards).0);
+    }
+
+    #[test]
+    fn card_order_does_not_matter(cards in hand()) {
+        let mut reversed = cards.clone();
+        reversed.reverse();
+        prop_assert_eq!(evaluate_7_cards(&cards), evaluate_7_cards(&reversed));
+    }
+
+    #[test]
+    fn adding_a_card_never_worsens((cards, extra) in (5usize..=6).prop_flat_map(|n| cards(n + 1))
+        .prop_map(|mut all| { let extra = all.pop().unwrap(); (all, extra) }))
+    {
+        let mut more = cards.clone();
+        more.push(extra);
+        prop_assert!(evaluate_7_cards(&more) <= evaluate_7_cards(&cards));
+    }
+
+    #[test]
+    fn best_of_seven_is_min_over_five_card_subsets(cards in cards(7)) {
+        let mut best = u16::MAX;
+        for skip_a in 0..7 {
+            for skip_b in skip_a + 1..7 {
+                let five: Vec<Card> = (0..7).filter(|&i| i != skip_a && i != skip_b).map(|i| cards[i]).collect();
+                best = best.min(evaluate_5_cards(&five.try_into().unwrap()));
+            }
+        }
+        prop_assert_eq!(evaluate_7_cards(&cards), best);
+    }
+
+    #[test]
+    fn equity_matrix_is_antisymmetric((board, range0, range1) in spot()) {
+        let forward = compute_equity_matrix(&board, &range0, &range1);
+        let swapped = compute_equity_matrix(&board, &range1, &range0);
+        let (n0, n1) = (range0.len(), range1.len());
+
+        for i in 0..n0 {
+            for j in 0..n1 {
+                let eq = forward[i * n1 + j];
+                let eq_t = swapped[j * n0 + i];
+                prop_assert_eq!(eq.is_nan(), eq_t.is_nan(), "blocked cells differ at ({}, {})", i, j);
+                if !eq.is_nan() {
+                    prop_assert!([0.0, 0.5, 1.0].contains(&eq));
+                    prop_assert_eq!(eq + eq_t, 1.0, "({}, {}): {} + {}", i, j, eq, eq_t);
+                }
+            }
+        }
+    }
+}
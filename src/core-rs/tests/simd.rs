@@ -0,0 +1,135 @@
+//! Differential tests: the dispatching kernels in `simd` against the scalar
+//! reference. Natively both sides are scalar; the interesting run is the
+//! simd128 WASM build under Node:
+//!
+//! `RUSTFLAGS='-C target-feature=+simd128' cargo test --target wasm32-unknown-unknown --test simd`
+
+use poker_solver_core::simd::{self, scalar};
+use poker_solver_core::{compute_equity_matrix, compute_single_equity, init_lookup_tables, Card};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+/// Equity-matrix-like rows: 0, 0.5, 1 or NaN. Lengths up to 13 cover every
+/// remainder after the four-lane body.
+fn random_row(rng: &mut StdRng, len: usize) -> Vec<f32> {
+    (0..len).map(|_| [0.0, 0.5, 1.0, f32::NAN][rng.gen_range(0..4)]).collect()
+}
+
+fn same_bits(a: &[f32], b: &[f32]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.to_bits() == y.to_bits())
+}
+
+#[test]
+fn equity_row_matches_scalar() {
+    let mut rng = StdRng::seed_from_u64(7);
+    for len in (0..=13).chain([64, 301]) {
+        let scores: Vec<u16> = (0..len).map(|_| rng.gen_range(1..=7462)).collect();
+        let masks: Vec<u64> = (0..len)
+            .map(|_| if rng.gen_bool(0.1) { u64::MAX } else { (1u64 << rng.gen_range(0..52)) | (1u64 << rng.gen_range(0..52)) })
+            .collect();
+        for _ in 0..20 {
+            // Reuse one of the row's scores now and then to produce ties.
+            let score = if len > 0 && rng.gen_bool(0.3) { scores[rng.gen_range(0..len)] } else { rng.gen_range(1..=7462) };
+            let mask = (1u64 << rng.gen_range(0..52)) | (1u64 << rng.gen_range(0..52));
+            let mut fast = vec![-1.0; len];
+            let mut reference = vec![-1.0; len];
+            simd::equity_row(score, mask, &scores, &masks, &mut fast);
+            scalar::equity_row(score, mask, &scores, &masks, &mut reference);
+            assert!(same_bits(&fast, &reference), "len {}: {:?} vs {:?}", len, fast, reference);
+        }
+    }
+}
+
+#[test]
+fn masked_sums_match_scalar() {
+    let mut rng = StdRng::seed_from_u64(11);
+    for len in (0..=13).chain([64, 301, 1326]) {
+        let row = random_row(&mut rng, len);
+        let weights: Vec<f32> = (0..len).map(|_| rng.gen_range(0.0..1.0)).collect();
+        let (fast_eq, fast_total) = simd::masked_sums(&row, &weights);
+        let (ref_eq, ref_total) = scalar::masked_sums(&row, &weights);
+        let tolerance = 1e-5 * (1.0 + ref_total);
+        assert!((fast_eq - ref_eq).abs() <= tolerance, "len {}: {} vs {}", len, fast_eq, ref_eq);
+        assert!((fast_total - ref_total).abs() <= tolerance, "len {}: {} vs {}", len, fast_total, ref_total);
+    }
+}
+
+#[test]
+fn accumulate_opponent_matches_scalar() {
+    let mut rng = StdRng::seed_from_u64(13);
+    for len in (0..=13).chain([64, 301]) {
+        let (mut fast_eq, mut fast_total) = (vec![0.0; len], vec![0.0; len]);
+        let (mut ref_eq, mut ref_total) = (vec![0.0; len], vec![0.0; len]);
+        for _ in 0..50 {
+            let row = random_row(&mut rng, len);
+            let weight = rng.gen_range(0.0..1.0);
+            simd::accumulate_opponent(&row, weight, &mut fast_eq, &mut fast_total);
+            scalar::accumulate_opponent(&row, weight, &mut ref_eq, &mut ref_total);
+        }
+        assert!(same_bits(&fast_eq, &ref_eq), "len {}", len);
+        assert!(same_bits(&fast_total, &ref_total), "len {}", len);
+    }
+}
+
+/// Signed values with some exact zeros, like regrets and utilities.
+fn random_values(rng: &mut StdRng, len: usize) -> Vec<f32> {
+    (0..len).map(|_| if rng.gen_bool(0.1) { 0.0 } else { rng.gen_range(-50.0..50.0) }).collect()
+}
+
+#[test]
+fn cfr_kernels_match_scalar() {
+    let mut rng = StdRng::seed_from_u64(19);
+    for len in (0..=13).chain([64, 301]) {
+        let values = random_values(&mut rng, len);
+        let weights: Vec<f32> = (0..len).map(|_| rng.gen_range(0.0..1.0)).collect();
+        let start = random_values(&mut rng, len);
+
+        let (mut fast, mut reference) = (start.clone(), start.clone());
+        simd::scale(&mut fast, &weights);
+        scalar::scale(&mut reference, &weights);
+        assert!(same_bits(&fast, &reference), "scale, len {}", len);
+
+        let (mut fast, mut reference) = (start.clone(), start.clone());
+        simd::add(&mut fast, &values);
+        scalar::add(&mut reference, &values);
+        assert!(same_bits(&fast, &reference), "add, len {}", len);
+
+        let (mut fast, mut reference) = (start.clone(), start.clone());
+        simd::mul_add(&mut fast, &weights, &values);
+        scalar::mul_add(&mut reference, &weights, &values);
+        assert!(same_bits(&fast, &reference), "mul_add, len {}", len);
+
+        let (mut fast, mut reference) = (values.clone(), values.clone());
+        simd::discount(&mut fast, 0.73, 0.5);
+        scalar::discount(&mut reference, 0.73, 0.5);
+        assert!(same_bits(&fast, &reference), "discount, len {}", len);
+
+        let (mut fast, mut reference) = (start.clone(), start);
+        simd::decay_add(&mut fast, 0.9, &weights, 0.25);
+        scalar::decay_add(&mut reference, 0.9, &weights, 0.25);
+        assert!(same_bits(&fast, &reference), "decay_add, len {}", len);
+    }
+}
+
+#[test]
+fn equity_matrix_matches_single_matchups() {
+    init_lookup_tables();
+    let mut rng = StdRng::seed_from_u64(17);
+    let board: Vec<Card> = ["Ks", "Th", "7s", "4d", "2c"].iter().filter_map(|c| Card::from_str(c)).collect();
+    let mut hand = || vec![Card::from_index(rng.gen_range(0..52)), Card::from_index(rng.gen_range(0..52))];
+    let range0: Vec<Vec<Card>> = (0..23).map(|_| hand()).filter(|h| h[0] != h[1]).collect();
+    let range1: Vec<Vec<Card>> = (0..37).map(|_| hand()).filter(|h| h[0] != h[1]).collect();
+
+    let matrix = compute_equity_matrix(&board, &range0, &range1);
+    for (i, h0) in range0.iter().enumerate() {
+        for (j, h1) in range1.iter().enumerate() {
+            let cell = matrix[i * range1.len() + j];
+            match compute_single_equity(&board, h0, h1) {
+                Some(eq) => assert_eq!(cell, eq, "{:?} vs {:?}", h0, h1),
+                None => assert!(cell.is_nan(), "{:?} vs {:?} should be blocked", h0, h1),
+            }
+        }
+    }
+}
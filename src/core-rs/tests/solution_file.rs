@@ -0,0 +1,90 @@
+//! Cross-target check of the solution file format: a blob exported by the
+//! WASM build (tests/fixtures/wasm_export.psol, the tiny_spec spot after 100
+//! iterations under Node) must load natively and match a native solve. An
+//! older export of the same spot (tests/fixtures/solution_v1.psol) predates
+//! sectioned files and covers reading version 1.
+
+use poker_solver_core::solver::format::{self, FORMAT_VERSION};
+use poker_solver_core::solver::io::MAGIC;
+use poker_solver_core::solver::{BetSize, GameConfig, RaiseSize, RiverCards, Solution};
+use poker_solver_core::{GameVariant, SolverSession};
+
+const WASM_EXPORT: &[u8] = include_bytes!("fixtures/wasm_export.psol");
+const VERSION_1: &[u8] = include_bytes!("fixtures/solution_v1.psol");
+
+fn tiny_spot() -> SolverSession {
+    let config = GameConfig {
+        initial_pot: 100.0,
+        stacks: [100.0, 100.0],
+        bet_sizes: vec![BetSize::PotFraction(0.75)],
+        raise_sizes: vec![RaiseSize::PotFraction(1.0)].into(),
+        river_bet_sizes: None,
+        river_raise_sizes: None,
+        river_cards: RiverCards::All,
+        raise_limit: 1,
+        chip_scale: 100,
+        icm: None,
+        bounty: None,
+        abstraction: None,
+        suit_isomorphism: false,
+        profiles: Vec::new(),
+        size_overrides: Vec::new(),
+        sizing_rules: Vec::new(),
+        donk_sizes: None,
+        player_sizes: Default::default(),
+        add_allin: true,
+        variant: GameVariant::Holdem,
+    };
+    SolverSession::from_config(config, "Ks Th 7s 4d 2c", "As Ad, Kc Kd, Qh Jh, 6h 5h", "Ac Kh, Tc Td, 9s 8s, Qc Qd")
+        .unwrap()
+}
+
+#[test]
+fn wasm_export_loads_natively() {
+    let solution = Solution::from_bytes(WASM_EXPORT).expect("fixture decodes");
+    assert_eq!(solution.board, "Ks Th 7s 4d 2c");
+    assert_eq!(solution.iterations, 100);
+
+    let loaded = SolverSession::from_solution(&solution).expect("fixture matches the native tree");
+
+    let mut native = tiny_spot();
+    native.step(100);
+
+    for node in 0..native.num_nodes() {
+        let (Ok(a), Ok(b)) = (loaded.node_strategy_table(node), native.node_strategy_table(node)) else {
+            continue;
+        };
+        for (hand, probs) in a["strategies"].as_object().unwrap() {
+            let other = b["strategies"][hand].as_array().unwrap();
+            for (p, q) in probs.as_array().unwrap().iter().zip(other) {
+                let (p, q) = (p.as_f64().unwrap(), q.as_f64().unwrap());
+                assert!((p - q).abs() < 1e-4, "node {} {}: {} vs {}", node, hand, p, q);
+            }
+        }
+    }
+}
+
+#[test]
+fn file_round_trip_preserves_state() {
+    let mut session = tiny_spot();
+    session.step(30);
+
+    let path = std::env::temp_dir().join(format!("solution-file-{}.psol", std::process::id()));
+    session.save_solution(&path).unwrap();
+    let restored = SolverSession::load_solution(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(restored.to_solution(), session.to_solution());
+}
+
+#[test]
+fn version_1_fixture_migrates_to_the_current_format() {
+    assert_eq!(format::read(MAGIC, "solution file", VERSION_1).unwrap().version(), 1);
+    let solution = Solution::from_bytes(VERSION_1).unwrap();
+    assert!(solution.locks.is_empty());
+
+    // Re-exported, the same solution comes back from the current format
+    let bytes = SolverSession::from_solution(&solution).unwrap().export_solution();
+    assert_eq!(format::read(MAGIC, "solution file", &bytes).unwrap().version(), FORMAT_VERSION);
+    assert_eq!(Solution::from_bytes(&bytes).unwrap(), solution);
+}
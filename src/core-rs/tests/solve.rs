@@ -0,0 +1,60 @@
+//! The one-shot `solve` entry point against an equivalent session-based run
+//! of the shared fixture spec (tests/fixtures/tiny_spec.json).
+
+use base64::Engine;
+use poker_solver_core::solve::{solve, SolveSpec};
+use poker_solver_core::solver::Solution;
+use poker_solver_core::SolverSession;
+use serde_json::Value;
+
+const TINY_SPEC: &str = include_str!("fixtures/tiny_spec.json");
+
+fn session_run(spec: &SolveSpec) -> SolverSession {
+    let mut session = SolverSession::from_config(spec.config.clone(), &spec.board, &spec.range0, &spec.range1).unwrap();
+    session.step(spec.iterations.unwrap());
+    session
+}
+
+#[test]
+fn root_strategy_matches_session_run() {
+    let spec: SolveSpec = serde_json::from_str(TINY_SPEC).unwrap();
+    let result: Value = serde_json::from_str(&solve(TINY_SPEC)).unwrap();
+    assert_eq!(result["ok"], true, "{}", result);
+
+    let session = session_run(&spec);
+    assert_eq!(result["iterations"], session.iterations());
+    // Same pipeline, same iterations: identical up to the JSON float round trip.
+    let expected = session.node_strategy_table(0).unwrap();
+    let root = &result["root_strategy"];
+    assert_eq!(root["actions"], expected["actions"]);
+    for (hand, probs) in expected["strategies"].as_object().unwrap() {
+        let got: Vec<f32> = serde_json::from_value(root["strategies"][hand].clone()).unwrap();
+        let want: Vec<f32> = serde_json::from_value(probs.clone()).unwrap();
+        assert_eq!(got, want, "{}", hand);
+    }
+    let exploitability = result["exploitability"].as_f64().unwrap();
+    assert!((exploitability - session.exploitability() as f64).abs() < 1e-3);
+}
+
+#[test]
+fn export_output_is_the_solution_file() {
+    let mut spec: Value = serde_json::from_str(TINY_SPEC).unwrap();
+    spec["outputs"] = serde_json::json!(["export"]);
+    let result: Value = serde_json::from_str(&solve(&spec.to_string())).unwrap();
+    assert!(result.get("root_strategy").is_none() && result.get("exploitability").is_none());
+
+    let bytes = base64::engine::general_purpose::STANDARD.decode(result["export"].as_str().unwrap()).unwrap();
+    let solution = Solution::from_bytes(&bytes).unwrap();
+    let session = session_run(&serde_json::from_value(spec).unwrap());
+    assert_eq!(solution, session.to_solution());
+}
+
+#[test]
+fn target_exploitability_stops_early() {
+    let mut spec: Value = serde_json::from_str(TINY_SPEC).unwrap();
+    spec["iterations"] = serde_json::json!(5_000);
+    spec["target_exploitability"] = serde_json::json!(5.0);
+    let result: Value = serde_json::from_str(&solve(&spec.to_string())).unwrap();
+    assert!(result["exploitability_pct"].as_f64().unwrap() <= 5.0);
+    assert!(result["iterations"].as_u64().unwrap() < 5_000);
+}